@@ -0,0 +1,270 @@
+use crate::models::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ── Activity feed ───────────────────────────────────────────────────────────
+// One chronological stream across agents: messages, run starts/ends, and the
+// watcher's file-change rollups, newest first.
+
+pub const DEFAULT_FEED_LIMIT: usize = 50;
+pub const MAX_FEED_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Message,
+    RunStarted,
+    RunEnded,
+    FileChanges,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityFilters {
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub kinds: Vec<ActivityKind>, // empty = every kind
+}
+
+impl ActivityFilters {
+    pub fn includes(&self, kind: ActivityKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    pub fn includes_messages(&self) -> bool {
+        self.includes(ActivityKind::Message) || self.includes(ActivityKind::FileChanges)
+    }
+
+    pub fn includes_runs(&self) -> bool {
+        self.includes(ActivityKind::RunStarted) || self.includes(ActivityKind::RunEnded)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub id: String, // stable across pages: "<kind>:<entity id>"
+    pub kind: ActivityKind,
+    pub timestamp: DateTime<Utc>,
+    pub agent_id: String,
+    pub agent_name: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub summary: String,
+    pub run_id: Option<String>,
+    pub message_id: Option<String>,
+    pub run_status: Option<RunStatus>,
+    pub message_kind: Option<MessageKind>,
+    pub file_count: Option<usize>,
+}
+
+/// Number of files a watcher status message rolls up, if it is one.
+fn rolled_up_files(message: &Message) -> Option<usize> {
+    let files = message.metadata.as_ref()?.get("files")?.as_array()?;
+    Some(files.len())
+}
+
+fn message_entry(message: Message) -> (ActivityKind, ActivityEntryDraft) {
+    let file_count = rolled_up_files(&message);
+    let kind = if file_count.is_some() {
+        ActivityKind::FileChanges
+    } else {
+        ActivityKind::Message
+    };
+    let draft = ActivityEntryDraft {
+        id: format!("message:{}", message.id),
+        timestamp: message.created_at,
+        agent_id: message.agent_id,
+        summary: message.content,
+        run_id: None,
+        message_id: Some(message.id),
+        run_status: None,
+        message_kind: Some(message.kind),
+        file_count,
+    };
+    (kind, draft)
+}
+
+fn run_entries(run: &Run) -> Vec<(ActivityKind, ActivityEntryDraft)> {
+    let draft = |id: String, timestamp: DateTime<Utc>, summary: String| ActivityEntryDraft {
+        id,
+        timestamp,
+        agent_id: run.agent_id.clone(),
+        summary,
+        run_id: Some(run.id.clone()),
+        message_id: None,
+        run_status: Some(run.status.clone()),
+        message_kind: None,
+        file_count: (!run.file_changes.is_empty()).then_some(run.file_changes.len()),
+    };
+    let mut entries = vec![(
+        ActivityKind::RunStarted,
+        draft(
+            format!("run_started:{}", run.id),
+            run.started_at,
+            run.summary
+                .clone()
+                .unwrap_or_else(|| "Run started".to_string()),
+        ),
+    )];
+    if let Some(ended_at) = run.ended_at {
+        entries.push((
+            ActivityKind::RunEnded,
+            draft(
+                format!("run_ended:{}", run.id),
+                ended_at,
+                format!(
+                    "Run {}{}",
+                    run.status.as_str(),
+                    run.summary
+                        .as_deref()
+                        .map(|summary| format!(": {}", summary))
+                        .unwrap_or_default()
+                ),
+            ),
+        ));
+    }
+    entries
+}
+
+/// Entry fields known before agent/project names are joined in.
+struct ActivityEntryDraft {
+    id: String,
+    timestamp: DateTime<Utc>,
+    agent_id: String,
+    summary: String,
+    run_id: Option<String>,
+    message_id: Option<String>,
+    run_status: Option<RunStatus>,
+    message_kind: Option<MessageKind>,
+    file_count: Option<usize>,
+}
+
+/// Merge messages and run transitions into one feed, newest first, keeping
+/// entries strictly older than `before` and at most `limit` of them.
+pub fn build_activity_feed(
+    agents: &[Agent],
+    projects: &[Project],
+    messages: Vec<Message>,
+    runs: &[Run],
+    filters: &ActivityFilters,
+    before: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Vec<ActivityEntry> {
+    let agents: HashMap<&str, &Agent> = agents.iter().map(|a| (a.id.as_str(), a)).collect();
+    let projects: HashMap<&str, &Project> = projects.iter().map(|p| (p.id.as_str(), p)).collect();
+
+    let mut drafts: Vec<(ActivityKind, ActivityEntryDraft)> =
+        messages.into_iter().map(message_entry).collect();
+    drafts.extend(runs.iter().flat_map(run_entries));
+
+    let mut entries: Vec<ActivityEntry> = drafts
+        .into_iter()
+        .filter(|(kind, draft)| {
+            filters.includes(*kind) && before.is_none_or(|before| draft.timestamp < before)
+        })
+        .filter_map(|(kind, draft)| {
+            let agent = agents.get(draft.agent_id.as_str())?;
+            if filters
+                .agent_id
+                .as_deref()
+                .is_some_and(|agent_id| agent_id != agent.id)
+                || filters
+                    .project_id
+                    .as_deref()
+                    .is_some_and(|project_id| project_id != agent.project_id)
+            {
+                return None;
+            }
+            let project_name = projects
+                .get(agent.project_id.as_str())
+                .map(|project| project.name.clone())
+                .unwrap_or_default();
+            Some(ActivityEntry {
+                id: draft.id,
+                kind,
+                timestamp: draft.timestamp,
+                agent_id: draft.agent_id,
+                agent_name: agent.name.clone(),
+                project_id: agent.project_id.clone(),
+                project_name,
+                summary: draft.summary,
+                run_id: draft.run_id,
+                message_id: draft.message_id,
+                run_status: draft.run_status,
+                message_kind: draft.message_kind,
+                file_count: draft.file_count,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.id.cmp(&b.id)));
+    entries.truncate(limit);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_messages_runs_and_rollups_newest_first() {
+        let project = Project::new("Site", "#123456");
+        let agent = Agent::new("Writer", &project.id, AgentKind::Terminal, "content");
+        let other = Agent::new("Other", "elsewhere", AgentKind::Terminal, "ops");
+        let base = Utc::now() - chrono::Duration::minutes(10);
+
+        let mut run = Run::new(
+            &agent.id,
+            RunStatus::Completed,
+            Some("Update copy".to_string()),
+        );
+        run.started_at = base;
+        run.ended_at = Some(base + chrono::Duration::minutes(5));
+
+        let mut rollup =
+            Message::from_agent(&agent.id, MessageKind::StatusUpdate, "2 files modified");
+        rollup.created_at = base + chrono::Duration::minutes(2);
+        rollup.metadata = Some(serde_json::json!({ "file_count": 2, "files": [{}, {}] }));
+        let mut note = Message::from_agent(&other.id, MessageKind::Output, "elsewhere");
+        note.created_at = base + chrono::Duration::minutes(3);
+
+        let projects = vec![project.clone()];
+        let feed = build_activity_feed(
+            &[agent.clone(), other],
+            &projects,
+            vec![rollup, note],
+            &[run],
+            &ActivityFilters {
+                project_id: Some(project.id.clone()),
+                ..Default::default()
+            },
+            None,
+            10,
+        );
+        let kinds: Vec<ActivityKind> = feed.iter().map(|entry| entry.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ActivityKind::RunEnded,
+                ActivityKind::FileChanges,
+                ActivityKind::RunStarted
+            ]
+        );
+        assert_eq!(feed[0].summary, "Run completed: Update copy");
+        assert_eq!(feed[1].file_count, Some(2));
+        assert_eq!(feed[1].project_name, "Site");
+
+        let older = build_activity_feed(
+            &[agent],
+            &projects,
+            vec![],
+            &[],
+            &ActivityFilters::default(),
+            Some(base),
+            10,
+        );
+        assert!(older.is_empty());
+    }
+}
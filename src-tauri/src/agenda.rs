@@ -0,0 +1,506 @@
+use crate::connectors::ConnectorItem;
+use crate::db::Database;
+use crate::deep_link;
+use crate::export;
+use crate::models::*;
+use crate::timezone::UserTimezone;
+use chrono::{DateTime, TimeZone, Utc};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ── Agenda ──────────────────────────────────────────────────────────────────
+// Upcoming scheduled agent runs and due connector items in one ordered list,
+// also published as an iCalendar feed for calendar apps to subscribe to.
+
+/// Runs listed per agent, so an every-minute schedule can't flood the agenda.
+const MAX_OCCURRENCES_PER_AGENT: usize = 100;
+
+pub const CALENDAR_FEED_SETTINGS_KEY: &str = "calendar_feed";
+const FEED_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const RUN_EVENT_MINUTES: i64 = 15;
+const ITEM_EVENT_MINUTES: i64 = 30;
+
+/// Keep an .ics file on disk up to date with the next `days_ahead` days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarFeedSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default = "default_days_ahead")]
+    pub days_ahead: u32,
+}
+
+fn default_days_ahead() -> u32 {
+    14
+}
+
+impl Default for CalendarFeedSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            days_ahead: default_days_ahead(),
+        }
+    }
+}
+
+impl CalendarFeedSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=90).contains(&self.days_ahead) {
+            return Err("days ahead must be between 1 and 90".to_string());
+        }
+        if self.enabled && self.path.as_deref().is_none_or(|p| p.trim().is_empty()) {
+            return Err("a file path is required to publish the calendar feed".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarExportResult {
+    pub path: String,
+    pub event_count: usize,
+    pub size_bytes: u64,
+    pub completed_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgendaRange {
+    pub since: Option<DateTime<Utc>>, // defaults to now
+    pub until: Option<DateTime<Utc>>, // defaults to a day after `since`
+}
+
+impl AgendaRange {
+    pub fn resolve(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let since = self.since.unwrap_or(now);
+        let until = self.until.unwrap_or(since + chrono::Duration::days(1));
+        (since, until)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgendaKind {
+    ScheduledRun,
+    DueItem,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgendaEntry {
+    pub kind: AgendaKind,
+    pub at: DateTime<Utc>,
+    pub title: String,
+    pub overdue: bool, // due before the range started and still open
+    pub agent_id: Option<String>,
+    pub project_id: Option<String>,
+    pub connector_id: Option<String>,
+    pub item_id: Option<String>,
+    pub url: Option<String>,
+}
+
+fn parse_schedule(schedule: &str) -> Result<Cron, String> {
+    Cron::new(schedule.trim())
+        .parse()
+        .map_err(|error| format!("Invalid schedule '{}': {}", schedule.trim(), error))
+}
+
+/// Reject cron expressions the agenda couldn't expand.
+pub fn validate_schedule(schedule: Option<&str>) -> Result<(), String> {
+    match schedule.map(str::trim) {
+        Some(schedule) if !schedule.is_empty() => parse_schedule(schedule).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+/// Occurrences of an agent's schedule within [since, until), evaluated in
+/// the user's timezone so "0 9 * * *" means 9am where the user is.
+pub fn schedule_occurrences(
+    zone: UserTimezone,
+    schedule: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<DateTime<Utc>>, String> {
+    let cron = parse_schedule(schedule)?;
+    Ok(match zone {
+        UserTimezone::System => occurrences(&cron, &chrono::Local, since, until),
+        UserTimezone::Named(tz) => occurrences(&cron, &tz, since, until),
+    })
+}
+
+fn occurrences<Z: TimeZone>(
+    cron: &Cron,
+    zone: &Z,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    let start = since.with_timezone(zone) - chrono::Duration::seconds(1);
+    cron.iter_after(start)
+        .map(|at| at.with_timezone(&Utc))
+        .take_while(|at| *at < until)
+        .filter(|at| *at >= since)
+        .take(MAX_OCCURRENCES_PER_AGENT)
+        .collect()
+}
+
+pub fn build_agenda(
+    zone: UserTimezone,
+    agents: &[Agent],
+    due_items: Vec<(String, ConnectorItem)>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<AgendaEntry> {
+    let mut entries = Vec::new();
+    for agent in agents {
+        let Some(schedule) = agent
+            .config
+            .schedule
+            .as_deref()
+            .filter(|schedule| !schedule.trim().is_empty())
+        else {
+            continue;
+        };
+        match schedule_occurrences(zone, schedule, since, until) {
+            Ok(occurrences) => entries.extend(occurrences.into_iter().map(|at| AgendaEntry {
+                kind: AgendaKind::ScheduledRun,
+                at,
+                title: format!("{} scheduled run", agent.name),
+                overdue: false,
+                agent_id: Some(agent.id.clone()),
+                project_id: Some(agent.project_id.clone()),
+                connector_id: None,
+                item_id: None,
+                url: None,
+            })),
+            Err(error) => tracing::warn!("Skipping schedule for agent {}: {}", agent.id, error),
+        }
+    }
+
+    for (connector_id, item) in due_items {
+        // All-day items start their day and are due until it ends.
+        let (at, overdue) = match item.due_date() {
+            Some(day) => {
+                let (start, end) = zone.day_bounds(day);
+                (start, end <= since)
+            }
+            None => {
+                let Some(due_at) = item.due_at else {
+                    continue;
+                };
+                (due_at, due_at < since)
+            }
+        };
+        if at >= until {
+            continue;
+        }
+        entries.push(AgendaEntry {
+            kind: AgendaKind::DueItem,
+            at,
+            title: item.title,
+            overdue,
+            agent_id: None,
+            project_id: None,
+            connector_id: Some(connector_id),
+            item_id: Some(item.id),
+            url: item.url,
+        });
+    }
+
+    entries.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.title.cmp(&b.title)));
+    entries
+}
+
+/// The agenda for [since, until) straight from the database.
+pub fn load_agenda(
+    db: &Database,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<AgendaEntry>, String> {
+    let agents = db.list_agents().map_err(|e| e.to_string())?;
+    // All-day items are stored at UTC midnight, up to a day after their
+    // local start; build_agenda trims the extra.
+    let due_items = db
+        .get_open_items_due_before(&(until + chrono::Duration::days(1)).to_rfc3339())
+        .map_err(|e| e.to_string())?;
+    Ok(build_agenda(
+        UserTimezone::load(db),
+        &agents,
+        due_items,
+        since,
+        until,
+    ))
+}
+
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn ics_time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Fold content lines at 75 octets as RFC 5545 requires, without splitting
+/// a UTF-8 character.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(ch);
+        width += len;
+    }
+    out.push_str("\r\n");
+}
+
+fn event_uid(entry: &AgendaEntry) -> String {
+    match entry.kind {
+        AgendaKind::ScheduledRun => format!(
+            "run-{}-{}@kanbun",
+            entry.agent_id.as_deref().unwrap_or_default(),
+            entry.at.timestamp()
+        ),
+        AgendaKind::DueItem => format!(
+            "item-{}-{}@kanbun",
+            entry.connector_id.as_deref().unwrap_or_default(),
+            entry.item_id.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+/// Render agenda entries as an iCalendar (RFC 5545) document.
+pub fn render_ics(entries: &[AgendaEntry], generated_at: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//Kanbun//Agenda//EN",
+        "CALSCALE:GREGORIAN",
+        "METHOD:PUBLISH",
+        "X-WR-CALNAME:Kanbun",
+    ] {
+        push_line(&mut out, line);
+    }
+    for entry in entries {
+        let (minutes, category, description) = match entry.kind {
+            AgendaKind::ScheduledRun => (RUN_EVENT_MINUTES, "Scheduled run", None),
+            AgendaKind::DueItem => (
+                ITEM_EVENT_MINUTES,
+                "Due item",
+                entry
+                    .connector_id
+                    .as_deref()
+                    .map(|connector| format!("Due in {}", connector)),
+            ),
+        };
+        let url = entry
+            .url
+            .clone()
+            .or_else(|| entry.agent_id.as_deref().map(deep_link::agent_link));
+
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}", ics_text(&event_uid(entry))));
+        push_line(&mut out, &format!("DTSTAMP:{}", ics_time(generated_at)));
+        push_line(&mut out, &format!("DTSTART:{}", ics_time(entry.at)));
+        push_line(
+            &mut out,
+            &format!(
+                "DTEND:{}",
+                ics_time(entry.at + chrono::Duration::minutes(minutes))
+            ),
+        );
+        push_line(&mut out, &format!("SUMMARY:{}", ics_text(&entry.title)));
+        push_line(&mut out, &format!("CATEGORIES:{}", category));
+        if let Some(description) = description {
+            push_line(&mut out, &format!("DESCRIPTION:{}", ics_text(&description)));
+        }
+        if let Some(url) = url {
+            push_line(&mut out, &format!("URL:{}", url));
+        }
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// Write the agenda for [since, until) to an .ics file.
+pub fn write_ics(
+    db: &Database,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    destination_path: &str,
+) -> Result<CalendarExportResult, String> {
+    let entries = load_agenda(db, since, until)?;
+    let now = Utc::now();
+    let size_bytes = export::write_export_file(destination_path, &render_ics(&entries, now))?;
+    Ok(CalendarExportResult {
+        path: destination_path.to_string(),
+        event_count: entries.len(),
+        size_bytes,
+        completed_at: now.to_rfc3339(),
+    })
+}
+
+/// Rewrite the configured feed file now, if publishing is on.
+pub fn refresh_feed(db: &Database) -> Result<Option<CalendarExportResult>, String> {
+    let settings: CalendarFeedSettings = db
+        .get_setting(CALENDAR_FEED_SETTINGS_KEY)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let Some(path) = settings
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| settings.enabled && !path.is_empty())
+    else {
+        return Ok(None);
+    };
+    let since = Utc::now();
+    let until = since + chrono::Duration::days(settings.days_ahead as i64);
+    write_ics(db, since, until, path).map(Some)
+}
+
+/// Keep the published feed file current.
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        if let Err(error) = refresh_feed(&db) {
+            tracing::warn!("Calendar feed refresh failed: {}", error);
+        }
+        std::thread::sleep(FEED_REFRESH_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::{ItemStatus, DUE_DATE_KEY};
+    use std::collections::HashMap;
+
+    #[test]
+    fn merges_schedule_occurrences_and_due_items_in_order() {
+        let since = Utc::now();
+        let until = since + chrono::Duration::hours(3);
+        let mut agent = Agent::new("Reporter", "project", AgentKind::Terminal, "ops");
+        agent.config.schedule = Some("0 * * * *".to_string());
+
+        let item = |id: &str, due_at| ConnectorItem {
+            id: id.to_string(),
+            source: "todoist".to_string(),
+            title: format!("Task {}", id),
+            content: None,
+            status: ItemStatus::Active,
+            priority: None,
+            tags: vec![],
+            url: None,
+            parent_id: None,
+            metadata: HashMap::new(),
+            created_at: None,
+            updated_at: None,
+            due_at: Some(due_at),
+            raw: None,
+        };
+        let due = vec![
+            (
+                "todoist".to_string(),
+                item("late", since - chrono::Duration::hours(1)),
+            ),
+            (
+                "todoist".to_string(),
+                item("soon", since + chrono::Duration::minutes(90)),
+            ),
+            (
+                "todoist".to_string(),
+                item("later", until + chrono::Duration::hours(1)),
+            ),
+        ];
+
+        let agent_id = agent.id.clone();
+        let agenda = build_agenda(UserTimezone::System, &[agent], due, since, until);
+        let runs = agenda
+            .iter()
+            .filter(|entry| entry.kind == AgendaKind::ScheduledRun)
+            .count();
+        assert!((2..=3).contains(&runs));
+        assert_eq!(agenda[0].item_id.as_deref(), Some("late"));
+        assert!(agenda[0].overdue);
+        assert!(agenda
+            .iter()
+            .all(|entry| entry.item_id.as_deref() != Some("later")));
+        assert!(agenda.windows(2).all(|pair| pair[0].at <= pair[1].at));
+
+        let ics = render_ics(&agenda, since);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), agenda.len());
+        assert!(ics.contains("UID:item-todoist-soon@kanbun\r\n"));
+        assert!(ics.contains("SUMMARY:Reporter scheduled run\r\n"));
+        assert!(ics.contains(&format!("URL:kanbun://agent/{}", agent_id)));
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(ics_text("a, b; c\nd"), "a\\, b\\; c\\nd");
+
+        assert!(CalendarFeedSettings::default().validate().is_ok());
+        let no_path = CalendarFeedSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(no_path.validate().is_err());
+
+        assert!(validate_schedule(Some("not a cron")).is_err());
+        assert!(validate_schedule(Some("*/15 9-17 * * 1-5")).is_ok());
+    }
+
+    #[test]
+    fn all_day_items_are_due_until_the_users_day_ends() {
+        let zone = UserTimezone::Named(chrono_tz::America::Los_Angeles);
+        // 7pm Pacific on the 15th, already the 16th in UTC.
+        let since = DateTime::parse_from_rfc3339("2026-10-16T02:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let until = since + chrono::Duration::hours(24);
+        let item = |id: &str, date: &str| ConnectorItem {
+            id: id.to_string(),
+            source: "todoist".to_string(),
+            title: format!("Task {}", id),
+            content: None,
+            status: ItemStatus::Active,
+            priority: None,
+            tags: vec![],
+            url: None,
+            parent_id: None,
+            metadata: HashMap::from([(DUE_DATE_KEY.to_string(), date.to_string())]),
+            created_at: None,
+            updated_at: None,
+            due_at: chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .map(|day| day.and_time(chrono::NaiveTime::MIN).and_utc()),
+            raw: None,
+        };
+        let due = ["2026-10-14", "2026-10-15", "2026-10-16", "2026-10-17"]
+            .into_iter()
+            .map(|date| ("todoist".to_string(), item(date, date)))
+            .collect();
+
+        let agenda = build_agenda(zone, &[], due, since, until);
+        let listed: Vec<(&str, bool)> = agenda
+            .iter()
+            .map(|entry| (entry.item_id.as_deref().unwrap(), entry.overdue))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("2026-10-14", true),
+                ("2026-10-15", false),
+                ("2026-10-16", false)
+            ]
+        );
+        assert_eq!(agenda[1].at.to_rfc3339(), "2026-10-15T07:00:00+00:00");
+    }
+}
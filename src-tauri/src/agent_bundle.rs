@@ -0,0 +1,372 @@
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use crate::watchers;
+use crate::workdir;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ── Portable agent bundles ──────────────────────────────────────────────────
+// Agent setups (config, watch paths, adapter config) and templates written as
+// YAML so they can move between Kanbun instances. History never leaves.
+
+pub const BUNDLE_VERSION: u32 = 1;
+const IMPORTED_PROJECT_COLOR: &str = "#6366f1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentBundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub agents: Vec<PortableAgent>,
+    #[serde(default)]
+    pub templates: Vec<PortableTemplate>,
+}
+
+/// An agent without its identity or history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableAgent {
+    pub name: String,
+    pub project_name: String, // matched (or created) on import
+    pub kind: AgentKind,
+    pub function_tag: String,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    pub config: AgentConfig,
+    #[serde(default)]
+    pub adapter: Option<AdapterConfig>,
+    #[serde(default)]
+    pub template: Option<String>, // name of the template it was created from
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableTemplate {
+    pub name: String, // a local template with the same name is replaced
+    pub kind: AgentKind,
+    pub function_tag: String,
+    pub autonomy_level: AutonomyLevel,
+    #[serde(default)]
+    pub adapter: Option<AdapterConfig>,
+    #[serde(default)]
+    pub instructions: Vec<String>,
+    #[serde(default)]
+    pub ignore_globs: Vec<String>,
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleExportResult {
+    pub path: String,
+    pub agent_count: usize,
+    pub template_count: usize,
+    pub size_bytes: u64,
+    pub completed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleImport {
+    pub agents: Vec<Agent>,
+    pub templates: Vec<AgentTemplate>,
+    pub created_projects: Vec<Project>,
+}
+
+/// Session names are derived per agent and env usually holds secrets, so
+/// both are dropped unless env is asked for explicitly.
+fn portable_adapter(mut adapter: AdapterConfig, include_env: bool) -> AdapterConfig {
+    adapter.session_name = None;
+    if !include_env {
+        adapter.env = None;
+    }
+    adapter
+}
+
+pub fn build_bundle(
+    db: &Database,
+    agent_ids: &[String],
+    template_ids: &[String],
+    include_env: bool,
+) -> Result<AgentBundle, CommandError> {
+    let project_names: HashMap<String, String> = db
+        .list_projects()?
+        .into_iter()
+        .map(|project| (project.id, project.name))
+        .collect();
+
+    let mut portable_agents = Vec::with_capacity(agent_ids.len());
+    for agent_id in agent_ids {
+        let agent = db
+            .get_agent(agent_id)?
+            .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+        let adapter = db
+            .get_adapter_config(&agent.id)?
+            .map(|adapter| portable_adapter(adapter, include_env));
+        let mut config = agent.config.clone();
+        config.watching_paused = false;
+        let template = match &agent.template_id {
+            Some(template_id) => db
+                .get_agent_template(template_id)?
+                .map(|template| template.name),
+            None => None,
+        };
+        portable_agents.push(PortableAgent {
+            name: agent.name.clone(),
+            project_name: project_names
+                .get(&agent.project_id)
+                .cloned()
+                .unwrap_or_default(),
+            kind: agent.kind.clone(),
+            function_tag: agent.function_tag.clone(),
+            working_directory: agent.working_directory.clone(),
+            config,
+            adapter,
+            template,
+        });
+    }
+
+    let mut templates = Vec::with_capacity(template_ids.len());
+    for template_id in template_ids {
+        let template = db.get_agent_template(template_id)?.ok_or_else(|| {
+            CommandError::not_found(format!("Agent template not found: {}", template_id))
+        })?;
+        templates.push(PortableTemplate {
+            name: template.name,
+            kind: template.kind,
+            function_tag: template.function_tag,
+            autonomy_level: template.autonomy_level,
+            adapter: template
+                .adapter
+                .map(|adapter| portable_adapter(adapter, include_env)),
+            instructions: template.instructions,
+            ignore_globs: template.ignore_globs,
+            include_globs: template.include_globs,
+        });
+    }
+
+    Ok(AgentBundle {
+        version: BUNDLE_VERSION,
+        exported_at: Utc::now(),
+        agents: portable_agents,
+        templates,
+    })
+}
+
+pub fn to_yaml(bundle: &AgentBundle) -> Result<String, CommandError> {
+    serde_yaml::to_string(bundle)
+        .map_err(|error| CommandError::from(format!("failed to write bundle: {}", error)))
+}
+
+pub fn from_yaml(content: &str) -> Result<AgentBundle, CommandError> {
+    let bundle: AgentBundle = serde_yaml::from_str(content)
+        .map_err(|error| CommandError::invalid_input(format!("invalid agent bundle: {}", error)))?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(CommandError::invalid_input(format!(
+            "agent bundle version {} is newer than this Kanbun supports ({})",
+            bundle.version, BUNDLE_VERSION
+        )));
+    }
+    Ok(bundle)
+}
+
+/// Create every agent and template in the bundle. Agents land in
+/// `project_id` when given, otherwise in the project with their exported
+/// project name, which is created if this instance doesn't have it.
+pub fn import_bundle(
+    db: &Database,
+    bundle: &AgentBundle,
+    project_id: Option<&str>,
+) -> Result<BundleImport, CommandError> {
+    let mut projects = db.list_projects()?;
+    if let Some(project_id) = project_id {
+        if !projects.iter().any(|project| project.id == project_id) {
+            return Err(CommandError::not_found(format!(
+                "Project not found: {}",
+                project_id
+            )));
+        }
+    }
+    for portable in &bundle.agents {
+        if portable.name.trim().is_empty() {
+            return Err(CommandError::invalid_input("Agent name cannot be empty"));
+        }
+        workdir::validate(portable.working_directory.as_deref())?;
+        watchers::validate_paths(portable.working_directory.as_deref(), &portable.config).map_err(
+            |error| CommandError::invalid_input(format!("{}: {}", portable.name, error)),
+        )?;
+        if project_id.is_none() && portable.project_name.trim().is_empty() {
+            return Err(CommandError::invalid_input(format!(
+                "Agent {} has no project; choose one to import into",
+                portable.name
+            )));
+        }
+    }
+
+    let existing_templates = db.list_agent_templates()?;
+    let mut imported = BundleImport {
+        agents: Vec::with_capacity(bundle.agents.len()),
+        templates: resolve_templates(&bundle.templates, &existing_templates),
+        created_projects: Vec::new(),
+    };
+
+    for portable in &bundle.agents {
+        let target_project_id = match project_id {
+            Some(project_id) => project_id.to_string(),
+            None => {
+                let name = portable.project_name.trim();
+                match projects.iter().find(|project| project.name == name) {
+                    Some(project) => project.id.clone(),
+                    None => {
+                        let project = Project::new(name, IMPORTED_PROJECT_COLOR);
+                        db.create_project(&project)?;
+                        projects.push(project.clone());
+                        let id = project.id.clone();
+                        imported.created_projects.push(project);
+                        id
+                    }
+                }
+            }
+        };
+
+        let mut agent = Agent::new(
+            portable.name.trim(),
+            &target_project_id,
+            portable.kind.clone(),
+            &portable.function_tag,
+        );
+        agent.working_directory = portable.working_directory.clone();
+        agent.config = portable.config.clone();
+        agent.template_id = portable.template.as_deref().and_then(|name| {
+            imported
+                .templates
+                .iter()
+                .chain(&existing_templates)
+                .find(|template| template.name == name)
+                .map(|template| template.id.clone())
+        });
+        db.create_agent(&agent)?;
+        if let Some(adapter) = &portable.adapter {
+            db.set_adapter_config(&agent.id, adapter)?;
+        }
+        imported.agents.push(agent);
+    }
+
+    for template in &imported.templates {
+        db.save_agent_template(template)?;
+    }
+
+    Ok(imported)
+}
+
+/// The bundle's templates as they'll be saved: taking over the id of a
+/// local template with the same name, so agents can be linked to them
+/// before they're written.
+fn resolve_templates(
+    portable: &[PortableTemplate],
+    existing: &[AgentTemplate],
+) -> Vec<AgentTemplate> {
+    portable
+        .iter()
+        .map(|portable| {
+            let mut template = AgentTemplate::new(
+                &portable.name,
+                portable.kind.clone(),
+                &portable.function_tag,
+            );
+            template.autonomy_level = portable.autonomy_level.clone();
+            template.adapter = portable.adapter.clone();
+            template.instructions = portable.instructions.clone();
+            template.ignore_globs = portable.ignore_globs.clone();
+            template.include_globs = portable.include_globs.clone();
+            if let Some(existing) = existing
+                .iter()
+                .find(|existing| existing.name == template.name)
+            {
+                template.id = existing.id.clone();
+                template.created_at = existing.created_at;
+            }
+            template
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_round_trips_without_env_or_history() {
+        let source = Database::new(":memory:").unwrap();
+        let project = Project::new("Test Project", "#123456");
+        source.create_project(&project).unwrap();
+        let mut agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        agent.config.watch_paths = vec!["/tmp/docs".to_string()];
+        agent.config.ignore_globs = vec!["*.log".to_string()];
+        source.create_agent(&agent).unwrap();
+        source
+            .set_adapter_config(
+                &agent.id,
+                &AdapterConfig {
+                    adapter_type: AdapterType::ClaudeCode,
+                    session_name: Some("kanbun-docs".to_string()),
+                    endpoint: None,
+                    command: Some("/tmp/docs".to_string()),
+                    env: Some(serde_json::json!({ "API_KEY": "secret" })),
+                },
+            )
+            .unwrap();
+        source
+            .insert_message(&Message::to_agent(
+                &agent.id,
+                MessageKind::Instruction,
+                "not exported",
+            ))
+            .unwrap();
+
+        let bundle = build_bundle(&source, std::slice::from_ref(&agent.id), &[], false).unwrap();
+        let yaml = to_yaml(&bundle).unwrap();
+        assert!(!yaml.contains("secret"));
+        assert!(!yaml.contains("not exported"));
+
+        let target = Database::new(":memory:").unwrap();
+        let imported = import_bundle(&target, &from_yaml(&yaml).unwrap(), None).unwrap();
+        assert_eq!(imported.created_projects.len(), 1);
+        assert_eq!(imported.created_projects[0].name, "Test Project");
+
+        let copy = &imported.agents[0];
+        assert_ne!(copy.id, agent.id);
+        assert_eq!(copy.name, "Docs");
+        assert_eq!(copy.config.watch_paths, agent.config.watch_paths);
+        assert_eq!(copy.config.ignore_globs, agent.config.ignore_globs);
+        let adapter = target.get_adapter_config(&copy.id).unwrap().unwrap();
+        assert_eq!(adapter.command.as_deref(), Some("/tmp/docs"));
+        assert!(adapter.session_name.is_none());
+        assert!(adapter.env.is_none());
+        assert!(target
+            .get_messages_for_agent(&copy.id, 10)
+            .unwrap()
+            .is_empty());
+
+        // A second import reuses the project it created the first time.
+        let again = import_bundle(&target, &bundle, None).unwrap();
+        assert!(again.created_projects.is_empty());
+        assert_eq!(again.agents[0].project_id, copy.project_id);
+    }
+
+    #[test]
+    fn import_refuses_watch_paths_outside_allowed_paths() {
+        let source = Database::new(":memory:").unwrap();
+        let project = Project::new("Scoped", "#123456");
+        source.create_project(&project).unwrap();
+        let mut agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        agent.config.allowed_paths = vec!["/tmp/kanbun-scope/docs".to_string()];
+        agent.config.watch_paths = vec!["/tmp/kanbun-scope/secrets".to_string()];
+        source.create_agent(&agent).unwrap();
+        let bundle = build_bundle(&source, std::slice::from_ref(&agent.id), &[], false).unwrap();
+
+        let target = Database::new(":memory:").unwrap();
+        let error = import_bundle(&target, &bundle, None).unwrap_err();
+        assert_eq!(error.code, crate::errors::ErrorCode::InvalidInput);
+        assert!(target.list_projects().unwrap().is_empty());
+    }
+}
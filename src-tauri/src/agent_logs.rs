@@ -0,0 +1,221 @@
+use crate::traces::TraceEvent;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+// ── Per-agent logs ──────────────────────────────────────────────────────────
+// Every trace event that carries an `agent_id` — adapter threads run inside
+// an `adapter` span, deliveries inside a message span, and the bus logs each
+// message it stores — is appended to logs/agents/<agent_id>.log under app
+// data. A file rolls over to .1, .2, … once it passes MAX_LOG_BYTES, so a
+// misbehaving adapter can be read back with `get_agent_logs` instead of
+// relaunching the app from a terminal with RUST_LOG set.
+
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const ROTATED_FILES: usize = 2;
+pub const MAX_TAIL_LINES: usize = 5000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentLogTail {
+    pub path: String,
+    pub lines: Vec<String>, // oldest first
+}
+
+pub struct AgentLogs {
+    dir: PathBuf,
+    writing: Mutex<()>, // appends and rotation happen one at a time
+}
+
+static INSTALLED: OnceLock<AgentLogs> = OnceLock::new();
+
+/// Start writing agent logs under `dir`. Call once, at startup.
+pub fn install(dir: PathBuf) {
+    if let Err(error) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create agent log directory: {}", error);
+        return;
+    }
+    let _ = INSTALLED.set(AgentLogs::new(dir));
+}
+
+pub fn installed() -> Option<&'static AgentLogs> {
+    INSTALLED.get()
+}
+
+/// Called by the trace subscriber for events that name an agent.
+pub(crate) fn record(agent_id: &str, event: &TraceEvent) {
+    if let Some(logs) = installed() {
+        // Never trace from here: the subscriber is the caller.
+        let _ = logs.append(agent_id, &format_event(event));
+    }
+}
+
+/// One line per event: time, level, target, message, then fields other
+/// than the agent id the file already names.
+pub fn format_event(event: &TraceEvent) -> String {
+    let mut line = format!(
+        "{} {:<5} {}: {}",
+        event.timestamp.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        event.level.to_uppercase(),
+        event.target,
+        event.message
+    );
+    for (name, value) in &event.fields {
+        if name != "agent_id" {
+            line.push_str(&format!(" {}={}", name, value));
+        }
+    }
+    line.replace(['\n', '\r'], " ")
+}
+
+/// Agent ids are UUIDs; anything else could name a path outside the log
+/// directory.
+fn valid_agent_id(agent_id: &str) -> bool {
+    !agent_id.is_empty()
+        && agent_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl AgentLogs {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            writing: Mutex::new(()),
+        }
+    }
+
+    /// The live file for generation 0, older rotations after it.
+    fn path(&self, agent_id: &str, generation: usize) -> PathBuf {
+        if generation == 0 {
+            self.dir.join(format!("{}.log", agent_id))
+        } else {
+            self.dir.join(format!("{}.log.{}", agent_id, generation))
+        }
+    }
+
+    pub fn append(&self, agent_id: &str, line: &str) -> io::Result<()> {
+        if !valid_agent_id(agent_id) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid agent id",
+            ));
+        }
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.path(agent_id, 0);
+        if fs::metadata(&path).is_ok_and(|meta| meta.len() >= MAX_LOG_BYTES) {
+            self.rotate(agent_id)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}", line)
+    }
+
+    fn rotate(&self, agent_id: &str) -> io::Result<()> {
+        let _ = fs::remove_file(self.path(agent_id, ROTATED_FILES));
+        for generation in (0..ROTATED_FILES).rev() {
+            let from = self.path(agent_id, generation);
+            if from.exists() {
+                fs::rename(&from, self.path(agent_id, generation + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The last `lines` lines, reaching into rotated files when the live
+    /// one is shorter.
+    pub fn tail(&self, agent_id: &str, lines: usize) -> io::Result<AgentLogTail> {
+        if !valid_agent_id(agent_id) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid agent id",
+            ));
+        }
+        let lines = lines.min(MAX_TAIL_LINES);
+        let mut tail = VecDeque::new();
+        for generation in 0..=ROTATED_FILES {
+            if tail.len() >= lines {
+                break;
+            }
+            let older = last_lines(&self.path(agent_id, generation), lines - tail.len())?;
+            for line in older.into_iter().rev() {
+                tail.push_front(line);
+            }
+        }
+        Ok(AgentLogTail {
+            path: self.path(agent_id, 0).to_string_lossy().to_string(),
+            lines: tail.into(),
+        })
+    }
+
+    /// Drop an agent's files, e.g. when the agent is deleted.
+    pub fn remove(&self, agent_id: &str) {
+        if !valid_agent_id(agent_id) {
+            return;
+        }
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        for generation in 0..=ROTATED_FILES {
+            let _ = fs::remove_file(self.path(agent_id, generation));
+        }
+    }
+}
+
+fn last_lines(path: &Path, count: usize) -> io::Result<Vec<String>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+    let mut lines = VecDeque::with_capacity(count);
+    for line in BufReader::new(file).lines() {
+        if lines.len() == count {
+            lines.pop_front();
+        }
+        lines.push_back(line?);
+    }
+    Ok(lines.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kanbun-agent-logs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn files_rotate_and_tail_spans_rotations() {
+        let dir = temp_dir();
+        let logs = AgentLogs::new(dir.clone());
+        let line = "x".repeat(1023);
+        let per_file = (MAX_LOG_BYTES / 1024) as usize;
+
+        for index in 0..per_file * 3 + 5 {
+            logs.append("agent-1", &format!("{:06} {}", index, &line[7..]))
+                .expect("append should succeed");
+        }
+        assert!(logs.path("agent-1", 1).exists());
+        assert!(logs.path("agent-1", ROTATED_FILES).exists());
+        assert!(!logs.path("agent-1", ROTATED_FILES + 1).exists());
+
+        let tail = logs.tail("agent-1", 10).expect("tail should read");
+        assert_eq!(tail.lines.len(), 10);
+        assert!(tail.lines[9].starts_with(&format!("{:06}", per_file * 3 + 4)));
+
+        // Five lines in the live file, the rest from the one before it.
+        let tail = logs.tail("agent-1", 8).expect("tail should read");
+        assert!(tail.lines[0].starts_with(&format!("{:06}", per_file * 3 - 3)));
+        assert!(tail.lines[7].starts_with(&format!("{:06}", per_file * 3 + 4)));
+
+        assert!(logs.tail("../agent-1", 10).is_err());
+        assert!(logs.tail("agent-2", 10).unwrap().lines.is_empty());
+
+        logs.remove("agent-1");
+        assert!(logs.tail("agent-1", 10).unwrap().lines.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -1,10 +1,21 @@
 use super::{Adapter, AdapterError, AdapterHealth};
 use crate::db::Database;
 use crate::models::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
 use std::io::ErrorKind;
 use std::process::Command;
 use std::sync::Arc;
 
+/// Reported lines remembered, so a status line redrawn in place ("✻
+/// Thinking… (4s)") isn't reported again on every tick.
+const RECENT_LINES: usize = 32;
+
+/// How often the monitor captures the pane and checks the queue when no
+/// queued message wakes it sooner.
+pub const MONITOR_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+
 /// Adapter for Claude Code terminal sessions.
 ///
 /// Strategy: Claude Code runs inside tmux sessions. This adapter:
@@ -25,6 +36,111 @@ pub struct ClaudeCodeAdapter {
     claude_command: String, // "claude" by default, overridable
 }
 
+/// Claude Code prints a cumulative `Total cost: $0.1234` line for `/cost` and
+/// on exit. Returns the last one found in `content`.
+fn parse_session_cost(content: &str) -> Option<f64> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once("Total cost:"))
+        .filter_map(|(_, rest)| rest.trim().trim_start_matches('$').parse::<f64>().ok())
+        .next_back()
+}
+
+/// Claude Code asks "Do you want to …?" before running a tool or applying an
+/// edit. Returns the prompt with the lines above it that describe the action.
+fn approval_prompt(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let question = lines.iter().rposition(|line| {
+        line.trim_matches(|c: char| c == '│' || c.is_whitespace())
+            .starts_with("Do you want to")
+    })?;
+    let context = lines[question.saturating_sub(8)..=question]
+        .iter()
+        .map(|line| {
+            line.trim_matches(|c: char| c == '│' || c == '╭' || c == '╰' || c.is_whitespace())
+        })
+        .filter(|line| !line.is_empty() && !line.chars().all(|c| c == '─'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(context)
+}
+
+fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Finds the lines of a pane capture that weren't in the previous one.
+/// Lines are compared by hash, and the new capture is lined up with the old
+/// either where the old window's tail reappears at its top (the pane
+/// scrolled) or by the lines they start with (the bottom was redrawn in
+/// place), whichever accounts for more. Unlike a substring check, an
+/// appended line that repeats an earlier one still counts. Redrawn lines
+/// are dropped when they only differ from a recent report in digits and
+/// symbols, which is how spinners and timers change.
+#[derive(Default)]
+struct PaneDiff {
+    window: Vec<u64>, // line hashes of the last capture
+    window_hash: u64,
+    recent: VecDeque<u64>, // line_shape hashes of the last RECENT_LINES reported
+}
+
+/// A line's letters, for comparing redraws.
+fn line_shape(line: &str) -> u64 {
+    let letters: String = line.chars().filter(|c| c.is_alphabetic()).collect();
+    hash_of(&letters)
+}
+
+impl PaneDiff {
+    fn new_output(&mut self, capture: &str) -> Option<String> {
+        // tmux pads the pane with blank lines and trailing spaces.
+        let lines: Vec<&str> = capture.lines().map(str::trim_end).collect();
+        let end = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |last| last + 1);
+        let lines = &lines[..end];
+        if lines.is_empty() {
+            return None;
+        }
+        let hashes: Vec<u64> = lines.iter().map(|line| hash_of(*line)).collect();
+        let window_hash = hash_of(&hashes);
+        if !self.window.is_empty() && window_hash == self.window_hash {
+            return None;
+        }
+
+        let old = &self.window;
+        let scrolled = (1..=old.len().min(hashes.len()))
+            .rev()
+            .find(|&overlap| old[old.len() - overlap..] == hashes[..overlap])
+            .unwrap_or(0);
+        let redrawn = old
+            .iter()
+            .zip(&hashes)
+            .take_while(|(old, new)| old == new)
+            .count();
+        let in_place = redrawn > scrolled;
+        self.window = hashes;
+        self.window_hash = window_hash;
+
+        let mut fresh = Vec::new();
+        for line in &lines[scrolled.max(redrawn)..] {
+            let shape = line_shape(line);
+            if in_place && self.recent.contains(&shape) {
+                continue;
+            }
+            if self.recent.len() == RECENT_LINES {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(shape);
+            fresh.push(*line);
+        }
+        let fresh = fresh.join("\n");
+        (!fresh.trim().is_empty()).then_some(fresh)
+    }
+}
+
 impl ClaudeCodeAdapter {
     pub fn new(config: &AdapterConfig) -> Self {
         Self {
@@ -69,9 +185,7 @@ impl ClaudeCodeAdapter {
 
         if !output.status.success() {
             return Err(AdapterError::SpawnFailed(
-                String::from_utf8_lossy(&output.stderr)
-                    .trim()
-                    .to_string(),
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
             ));
         }
 
@@ -189,7 +303,7 @@ impl Adapter for ClaudeCodeAdapter {
                 // for direct delivery since we read the pane async
             }
             _ => {
-                log::warn!("Unexpected message kind for delivery: {:?}", message.kind);
+                tracing::warn!("Unexpected message kind for delivery: {:?}", message.kind);
             }
         }
 
@@ -208,12 +322,14 @@ impl Adapter for ClaudeCodeAdapter {
         // Spawn a background thread that monitors the tmux pane
         let session_clone = session.clone();
         std::thread::spawn(move || {
-            let mut last_output = String::new();
+            let _span = crate::traces::adapter_span(&agent_id, "claude_code").entered();
+            let mut pane = PaneDiff::default();
+            let mut last_session_cost = 0.0_f64;
 
             loop {
                 if let Ok(Some(config)) = db.get_adapter_config(&agent_id) {
                     if config.adapter_type != AdapterType::ClaudeCode {
-                        log::info!(
+                        tracing::info!(
                             "Adapter config switched for {}; stopping Claude monitor",
                             agent_id
                         );
@@ -221,7 +337,8 @@ impl Adapter for ClaudeCodeAdapter {
                     }
                 }
 
-                std::thread::sleep(std::time::Duration::from_secs(3));
+                // A queued message cuts the wait short.
+                db.wait_for_messages(&agent_id, MONITOR_INTERVAL);
 
                 // Check if session still exists
                 let exists = Command::new("tmux")
@@ -231,7 +348,7 @@ impl Adapter for ClaudeCodeAdapter {
                     .unwrap_or(false);
 
                 if !exists {
-                    log::info!("Session {} ended, stopping monitor", session_clone);
+                    tracing::info!("Session {} ended, stopping monitor", session_clone);
                     let msg =
                         Message::from_agent(&agent_id, MessageKind::Completed, "Session ended");
                     let _ = db.insert_message(&msg);
@@ -251,43 +368,54 @@ impl Adapter for ClaudeCodeAdapter {
                     .output();
 
                 if let Ok(output) = output {
-                    let current = String::from_utf8_lossy(&output.stdout).to_string();
-
-                    // Only report if output changed (new activity)
-                    if current != last_output && !current.trim().is_empty() {
-                        // Extract just the new lines
-                        let new_content = if !last_output.is_empty() {
-                            current
-                                .lines()
-                                .filter(|line| !last_output.contains(line))
-                                .collect::<Vec<_>>()
-                                .join("\n")
-                        } else {
-                            current.clone()
-                        };
-
-                        if !new_content.trim().is_empty() {
-                            // Send heartbeat with recent output
-                            let msg = Message::from_agent(
-                                &agent_id,
-                                MessageKind::Heartbeat,
-                                &new_content,
-                            );
-                            let _ = db.insert_message(&msg);
-                            let _ = db.append_run_output(&agent_id, "heartbeat", &msg.content);
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
+                    let current = String::from_utf8_lossy(&output.stdout);
+
+                    // Only genuinely new output becomes a heartbeat.
+                    if let Some(new_content) = pane.new_output(&current) {
+                        // Send heartbeat with recent output
+                        let mut msg =
+                            Message::from_agent(&agent_id, MessageKind::Heartbeat, &new_content);
+                        // The pane only reports a running session total,
+                        // so attribute the increase to the current run.
+                        if let Some(session_cost) = parse_session_cost(&new_content) {
+                            if session_cost > last_session_cost {
+                                msg.metadata = Some(serde_json::json!({
+                                    "usage": TokenUsage {
+                                        cost: session_cost - last_session_cost,
+                                        ..TokenUsage::default()
+                                    },
+                                }));
+                            }
+                            last_session_cost = session_cost;
+                        }
+                        let _ = db.insert_message(&msg);
+                        let _ = db.append_run_output(&agent_id, "heartbeat", &msg.content);
+                        let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
+
+                        // DraftOnly agents never confirm their own actions:
+                        // decline the prompt and hand the run to review.
+                        if let Some(prompt) = approval_prompt(&new_content) {
+                            if super::hold_proposed_action(&db, &agent_id, &prompt) {
+                                let _ = Command::new("tmux")
+                                    .args(["send-keys", "-t", &session_clone, "Escape"])
+                                    .output();
+                            }
                         }
-
-                        last_output = current;
                     }
                 }
 
                 // Check for pending messages to deliver
+                super::expire_stale_messages(&db, &agent_id);
                 if let Ok(pending) = db.get_pending_messages(&agent_id) {
                     for msg in pending {
+                        let _span = crate::traces::message_span(&msg, "deliver").entered();
                         match msg.kind {
                             MessageKind::Instruction => {
-                                let _ = db.start_instruction_run(&agent_id, &msg.content);
+                                let _ = db.start_instruction_run(
+                                    &agent_id,
+                                    Some(&msg.id),
+                                    &msg.content,
+                                );
                                 let _ = Command::new("tmux")
                                     .args([
                                         "send-keys",
@@ -307,7 +435,11 @@ impl Adapter for ClaudeCodeAdapter {
                                 let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
                             }
                             MessageKind::Resume => {
-                                let _ = db.start_instruction_run(&agent_id, &msg.content);
+                                let _ = db.start_instruction_run(
+                                    &agent_id,
+                                    Some(&msg.id),
+                                    &msg.content,
+                                );
                                 let _ = Command::new("tmux")
                                     .args([
                                         "send-keys",
@@ -357,6 +489,7 @@ impl Adapter for ClaudeCodeAdapter {
                             _ => {}
                         }
                         let _ = db.mark_delivered(&msg.id);
+                        tracing::debug!("Message delivered");
                     }
                 }
             }
@@ -398,3 +531,67 @@ impl Adapter for ClaudeCodeAdapter {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approval_prompt_keeps_the_proposed_action() {
+        let pane = "\
+⏺ I'll run the migration now.
+╭──────────────────────────────────────────╮
+│ Bash command                             │
+│   psql -f migrations/004_drop_users.sql  │
+│ Do you want to proceed?                  │
+│ ❯ 1. Yes                                 │
+│   2. No, and tell Claude what to do      │
+╰──────────────────────────────────────────╯";
+        let prompt = approval_prompt(pane).expect("prompt should be detected");
+        assert!(prompt.contains("psql -f migrations/004_drop_users.sql"));
+        assert!(prompt.ends_with("Do you want to proceed?"));
+        assert!(approval_prompt("Compiling kanbun v0.1.0\nFinished").is_none());
+    }
+
+    #[test]
+    fn pane_diff_reports_only_new_lines() {
+        let mut pane = PaneDiff::default();
+        assert_eq!(
+            pane.new_output("$ cargo test\nok\n\n\n").as_deref(),
+            Some("$ cargo test\nok")
+        );
+        // Same window, different padding.
+        assert_eq!(pane.new_output("$ cargo test   \nok\n"), None);
+
+        // Appended lines count even when they repeat earlier ones, which a
+        // substring check against the old pane would drop.
+        assert_eq!(
+            pane.new_output("$ cargo test\nok\n$ cargo test\nok")
+                .as_deref(),
+            Some("$ cargo test\nok")
+        );
+
+        // Scrolled: the old tail is now the top of the window.
+        assert_eq!(
+            pane.new_output("$ cargo test\nok\nDone").as_deref(),
+            Some("Done")
+        );
+
+        // A spinner is reported when it appears, not as it ticks.
+        assert_eq!(
+            pane.new_output("$ cargo test\nok\nDone\n✻ Thinking… (1s)")
+                .as_deref(),
+            Some("✻ Thinking… (1s)")
+        );
+        assert_eq!(
+            pane.new_output("$ cargo test\nok\nDone\n✢ Thinking… (2s)"),
+            None
+        );
+        assert_eq!(
+            pane.new_output("$ cargo test\nok\nDone\n⏺ Tests pass.")
+                .as_deref(),
+            Some("⏺ Tests pass.")
+        );
+        assert_eq!(pane.new_output("\n\n"), None);
+    }
+}
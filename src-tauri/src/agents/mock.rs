@@ -15,7 +15,7 @@ impl MockAdapter {
 
 impl Adapter for MockAdapter {
     fn deliver(&self, message: &Message) -> Result<(), AdapterError> {
-        log::info!(
+        tracing::info!(
             "[MockAdapter] Delivering to {}: {:?} - {}",
             message.agent_id,
             message.kind,
@@ -28,10 +28,11 @@ impl Adapter for MockAdapter {
         let agent_id = agent_id.to_string();
 
         std::thread::spawn(move || {
+            let _span = crate::traces::adapter_span(&agent_id, "mock").entered();
             loop {
                 if let Ok(Some(config)) = db.get_adapter_config(&agent_id) {
                     if config.adapter_type != AdapterType::Mock {
-                        log::info!(
+                        tracing::info!(
                             "[MockAdapter] Config switched for {}, stopping mock loop",
                             agent_id
                         );
@@ -39,42 +40,61 @@ impl Adapter for MockAdapter {
                     }
                 }
 
-                std::thread::sleep(std::time::Duration::from_secs(2));
+                db.wait_for_messages(&agent_id, super::PENDING_POLL_FALLBACK);
 
                 // Check for pending messages and echo them back
+                super::expire_stale_messages(&db, &agent_id);
                 if let Ok(pending) = db.get_pending_messages(&agent_id) {
                     for msg in pending {
+                        let _span = crate::traces::message_span(&msg, "deliver").entered();
                         let _ = db.mark_delivered(&msg.id);
+                        tracing::debug!("Message delivered");
 
                         match msg.kind {
                             MessageKind::Instruction | MessageKind::Resume => {
-                                let _ = db.start_instruction_run(&agent_id, &msg.content);
+                                let _ = db.start_instruction_run(
+                                    &agent_id,
+                                    Some(&msg.id),
+                                    &msg.content,
+                                );
                                 let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
 
                                 // Simulate processing delay
                                 std::thread::sleep(std::time::Duration::from_secs(1));
 
                                 // Echo back as output
-                                let response = Message::from_agent(
+                                let mut response = Message::from_agent(
                                     &agent_id,
                                     MessageKind::Output,
                                     &format!("[mock] Processed: {}", msg.content),
                                 );
+                                response.reply_to = Some(msg.id.clone());
                                 let _ = db.insert_message(&response);
-                                let _ =
-                                    db.append_run_output(&agent_id, "output", &response.content);
+                                let _ = db.append_run_output_to(
+                                    &agent_id,
+                                    Some(&msg.id),
+                                    "output",
+                                    &response.content,
+                                );
                                 let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
 
                                 // Mark completed
-                                let done = Message::from_agent(
+                                let mut done = Message::from_agent(
                                     &agent_id,
                                     MessageKind::Completed,
                                     "Task completed (mock)",
                                 );
+                                done.reply_to = Some(msg.id.clone());
                                 let _ = db.insert_message(&done);
-                                let _ = db.append_run_output(&agent_id, "completed", &done.content);
-                                let _ = db.finalize_latest_run(
+                                let _ = db.append_run_output_to(
                                     &agent_id,
+                                    Some(&msg.id),
+                                    "completed",
+                                    &done.content,
+                                );
+                                let _ = db.finalize_run_for(
+                                    &agent_id,
+                                    Some(&msg.id),
                                     RunStatus::Completed,
                                     Some(done.content.clone()),
                                 );
@@ -104,9 +124,15 @@ impl Adapter for MockAdapter {
                                 let _ = db.insert_message(&blocked);
                             }
                             MessageKind::Cancel => {
-                                let _ = db.append_run_output(&agent_id, "cancel", &msg.content);
-                                let _ = db.finalize_latest_run(
+                                let _ = db.append_run_output_to(
+                                    &agent_id,
+                                    msg.run_key(),
+                                    "cancel",
+                                    &msg.content,
+                                );
+                                let _ = db.finalize_run_for(
                                     &agent_id,
+                                    msg.run_key(),
                                     RunStatus::Failed,
                                     Some("Cancelled by operator".to_string()),
                                 );
@@ -3,10 +3,13 @@ use crate::models::*;
 use std::io::ErrorKind;
 use std::process::Command;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub mod claude_code;
 pub mod mock;
 pub mod process;
+pub mod simulation;
+pub mod validate;
 pub mod webhook;
 
 /// The adapter trait. Each agent kind gets an implementation that translates
@@ -36,6 +39,10 @@ pub trait Adapter: Send + Sync {
     fn health_check(&self, agent_id: &str) -> Result<AdapterHealth, AdapterError>;
 }
 
+/// How long a delivery loop waits for a queued-message signal before checking
+/// the queue anyway.
+pub const PENDING_POLL_FALLBACK: Duration = Duration::from_secs(2);
+
 #[derive(Debug)]
 pub enum AdapterError {
     NotConnected(String),
@@ -57,6 +64,40 @@ impl std::fmt::Display for AdapterError {
     }
 }
 
+/// Longest a message can sit in the queue before a running adapter loop
+/// looks at it, when the queued-message signal is missed.
+pub fn delivery_interval(adapter_type: &AdapterType) -> Duration {
+    match adapter_type {
+        AdapterType::ClaudeCode => claude_code::MONITOR_INTERVAL,
+        _ => PENDING_POLL_FALLBACK,
+    }
+}
+
+/// Emitted when the supervisor sees an adapter's health change.
+pub const ADAPTER_HEALTH_EVENT: &str = "adapter:health";
+
+/// Payload of `ADAPTER_HEALTH_EVENT`, built from the supervisor's cached state
+/// rather than a fresh probe.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AdapterHealthEvent {
+    pub agent_id: String,
+    pub health: AdapterHealth,
+}
+
+/// Compact per-agent health row built from cached supervisor state.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AdapterHealthSummary {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub adapter_type: AdapterType,
+    pub connected: bool,
+    pub last_heartbeat: Option<String>,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub consecutive_failures: u32,
+    pub retry_after_seconds: Option<u64>,
+    pub last_error: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct AdapterHealth {
     pub connected: bool,
@@ -77,6 +118,97 @@ fn can_use_tmux() -> bool {
     }
 }
 
+/// Whether the agent's session outlived the app. Only Claude Code's tmux
+/// sessions can; process and webhook adapters start over with the app.
+pub fn has_live_session(db: &Database, agent_id: &str) -> bool {
+    let Ok(Some(config)) = db.get_adapter_config(agent_id) else {
+        return false;
+    };
+    config.adapter_type == AdapterType::ClaudeCode
+        && can_use_tmux()
+        && claude_code::ClaudeCodeAdapter::new(&config)
+            .health_check(agent_id)
+            .is_ok_and(|health| health.session_active)
+}
+
+/// Drop queued instructions whose TTL has passed and report each one back to
+/// the operator as a StatusUpdate. Delivery loops call this before polling so
+/// an adapter that was down for hours doesn't replay stale instructions.
+pub fn expire_stale_messages(db: &Database, agent_id: &str) {
+    let expired = match db.expire_pending_messages(agent_id) {
+        Ok(expired) => expired,
+        Err(error) => {
+            tracing::warn!(
+                "Failed to expire stale messages for {}: {}",
+                agent_id,
+                error
+            );
+            return;
+        }
+    };
+
+    for message in expired {
+        let expires_at = message
+            .expires_at
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        let mut status = Message::from_agent(
+            agent_id,
+            MessageKind::StatusUpdate,
+            &format!(
+                "Instruction expired before delivery (expired at {}): {}",
+                expires_at, message.content
+            ),
+        );
+        status.reply_to = Some(message.id.clone());
+        status.metadata = Some(serde_json::json!({
+            "source": "message_ttl",
+            "expired_message_id": message.id,
+            "expires_at": expires_at,
+        }));
+        if let Err(error) = db.insert_message(&status) {
+            tracing::warn!(
+                "Failed to report expired message for {}: {}",
+                agent_id,
+                error
+            );
+        }
+    }
+}
+
+/// DraftOnly agents produce output but never act on it unreviewed. Adapters
+/// call this when the agent proposes an action (a tool call or edit awaiting
+/// confirmation): the proposal is recorded as a Blocked message and the run
+/// moves to NeedsReview. Returns false for other autonomy levels, in which
+/// case the adapter leaves the prompt to the operator as before.
+pub fn hold_proposed_action(db: &Database, agent_id: &str, proposal: &str) -> bool {
+    let draft_only = db
+        .get_agent_config(agent_id)
+        .ok()
+        .flatten()
+        .is_some_and(|config| matches!(config.autonomy_level, AutonomyLevel::DraftOnly));
+    if !draft_only {
+        return false;
+    }
+
+    let mut blocked = Message::from_agent(agent_id, MessageKind::Blocked, proposal);
+    blocked.metadata = Some(serde_json::json!({
+        "source": "autonomy",
+        "autonomy_level": "draft_only",
+    }));
+    if let Err(error) = db.insert_message(&blocked) {
+        tracing::warn!("Failed to record held action for {}: {}", agent_id, error);
+    }
+    let _ = db.append_run_output(agent_id, "blocked", proposal);
+    let _ = db.finalize_latest_run(
+        agent_id,
+        RunStatus::NeedsReview,
+        Some("Proposed action held for review (draft only)".to_string()),
+    );
+    let _ = db.update_agent_status(agent_id, &AgentStatus::Blocked);
+    true
+}
+
 /// Create the appropriate adapter for a given config
 pub fn create_adapter(config: &AdapterConfig) -> Box<dyn Adapter> {
     match config.adapter_type {
@@ -91,10 +223,13 @@ pub fn create_adapter(config: &AdapterConfig) -> Box<dyn Adapter> {
             if can_use_tmux() {
                 Box::new(claude_code::ClaudeCodeAdapter::new(config))
             } else {
-                log::warn!(
+                tracing::warn!(
                     "tmux unavailable; running claude_code workstream as process-backed session"
                 );
-                let fallback_command = config.endpoint.clone().unwrap_or_else(|| "claude".to_string());
+                let fallback_command = config
+                    .endpoint
+                    .clone()
+                    .unwrap_or_else(|| "claude".to_string());
                 let mut process_config = config.clone();
                 process_config.adapter_type = AdapterType::Process;
                 process_config.endpoint = None;
@@ -106,8 +241,9 @@ pub fn create_adapter(config: &AdapterConfig) -> Box<dyn Adapter> {
         AdapterType::Process => Box::new(process::ProcessAdapter::new(config)),
         AdapterType::Mock => Box::new(mock::MockAdapter::new()),
         AdapterType::HttpWebhook => Box::new(webhook::WebhookAdapter::new(config)),
+        AdapterType::Simulation => Box::new(simulation::SimulationAdapter::new(config)),
         _ => {
-            log::warn!(
+            tracing::warn!(
                 "No adapter implemented for {:?}, falling back to mock",
                 config.adapter_type
             );
@@ -1,13 +1,14 @@
 use super::{Adapter, AdapterError, AdapterHealth};
 use crate::db::Database;
 use crate::models::*;
+use crate::watchers::PathScope;
 use chrono::Utc;
 use std::collections::{HashMap, VecDeque};
 use std::io::{BufRead, BufReader, Write};
 use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const OUTPUT_RING_MAX_LINES: usize = 240;
 const STATUS_TAIL_LINES: usize = 8;
@@ -75,6 +76,14 @@ fn process_sessions() -> &'static Mutex<HashMap<String, Arc<ProcessSession>>> {
     PROCESS_SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
+/// Live child processes, each read by its own stdout and stderr threads.
+pub fn session_count() -> usize {
+    process_sessions()
+        .lock()
+        .map(|sessions| sessions.len())
+        .unwrap_or(0)
+}
+
 fn get_session(agent_id: &str) -> Option<Arc<ProcessSession>> {
     process_sessions()
         .lock()
@@ -208,13 +217,31 @@ fn should_suppress_auto_restart(policy: RestartPolicy, code: Option<i32>) -> boo
     }
 }
 
-fn resolve_agent_working_directory(db: &Arc<Database>, agent_id: &str) -> Option<String> {
-    db.list_agents()
-        .ok()
-        .and_then(|agents| agents.into_iter().find(|agent| agent.id == agent_id))
-        .and_then(|agent| agent.working_directory)
+/// Where the process runs: the agent's working directory, which must sit
+/// inside its allowed paths, or the first allowed path when it has none.
+fn resolve_agent_working_directory(
+    db: &Arc<Database>,
+    agent_id: &str,
+) -> Result<Option<String>, AdapterError> {
+    let Some(agent) = db.get_agent(agent_id).ok().flatten() else {
+        return Ok(None);
+    };
+    let scope = PathScope::for_agent(&agent);
+    let working_directory = agent
+        .working_directory
         .map(|path| shellexpand::tilde(path.trim()).to_string())
-        .filter(|path| !path.trim().is_empty())
+        .filter(|path| !path.trim().is_empty());
+    match working_directory {
+        Some(path) => {
+            scope
+                .check([path.as_str()])
+                .map_err(AdapterError::SpawnFailed)?;
+            Ok(Some(path))
+        }
+        None => Ok(scope
+            .default_root()
+            .map(|root| root.to_string_lossy().to_string())),
+    }
 }
 
 fn truncate_output(input: &str) -> String {
@@ -230,6 +257,16 @@ fn truncate_output(input: &str) -> String {
     )
 }
 
+/// Agent CLIs in JSON streaming mode (`claude -p --output-format stream-json`,
+/// `codex exec --json`) report token usage on result/turn lines.
+fn parse_usage_line(line: &str) -> Option<TokenUsage> {
+    if !line.starts_with('{') || !line.contains("usage") && !line.contains("cost") {
+        return None;
+    }
+    let value = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    TokenUsage::from_json(&value)
+}
+
 fn stream_output(
     db: Arc<Database>,
     agent_id: String,
@@ -260,7 +297,10 @@ fn stream_output(
             *last_heartbeat = Some(Utc::now().to_rfc3339());
         }
 
-        let message = Message::from_agent(&agent_id, MessageKind::Output, &rendered);
+        let mut message = Message::from_agent(&agent_id, MessageKind::Output, &rendered);
+        if let Some(usage) = parse_usage_line(text) {
+            message.metadata = Some(serde_json::json!({ "usage": usage }));
+        }
         let _ = db.insert_message(&message);
         let _ = db.append_run_output(&agent_id, stream_kind, &rendered);
         let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
@@ -357,7 +397,7 @@ impl ProcessAdapter {
             command
         };
 
-        if let Some(cwd) = resolve_agent_working_directory(&db, agent_id) {
+        if let Some(cwd) = resolve_agent_working_directory(&db, agent_id)? {
             command.current_dir(cwd);
         }
 
@@ -448,128 +488,152 @@ impl Adapter for ProcessAdapter {
         let session = self.ensure_session(&agent_id, db.clone())?;
         let _ = db.update_agent_status(&agent_id, &AgentStatus::Idle);
 
-        thread::spawn(move || loop {
-            match db.get_adapter_config(&agent_id) {
-                Ok(Some(config)) => {
-                    if is_process_like_adapter_type(config.adapter_type) {
-                        ()
-                    } else {
-                        break;
+        thread::spawn(move || {
+            let _span = crate::traces::adapter_span(&agent_id, "process").entered();
+            // The process is watched every tick; the queue only when a message
+            // signal arrives or the fallback interval has passed.
+            let mut woken = false;
+            let mut last_poll: Option<Instant> = None;
+            loop {
+                match db.get_adapter_config(&agent_id) {
+                    Ok(Some(config)) => {
+                        if is_process_like_adapter_type(config.adapter_type) {
+                            ()
+                        } else {
+                            break;
+                        }
                     }
+                    Ok(_) => break,
+                    Err(_) => break,
                 }
-                Ok(_) => break,
-                Err(_) => break,
-            }
 
-            let state = process_state(&session).unwrap_or(ProcessState::Exited(None));
-            if let ProcessState::Exited(code) = state {
-                let suppress_auto_restart =
-                    should_suppress_auto_restart(session.restart_policy, code);
-                if !suppress_auto_restart {
-                    remove_session(&agent_id);
+                let state = process_state(&session).unwrap_or(ProcessState::Exited(None));
+                if let ProcessState::Exited(code) = state {
+                    let suppress_auto_restart =
+                        should_suppress_auto_restart(session.restart_policy, code);
+                    if !suppress_auto_restart {
+                        remove_session(&agent_id);
+                    }
+                    let (kind, status, note, agent_status) = if code.unwrap_or(0) == 0 {
+                        (
+                            MessageKind::Completed,
+                            RunStatus::Completed,
+                            format!(
+                                "Process exited normally{}.",
+                                code.map(|c| format!(" (code {})", c)).unwrap_or_default()
+                            ),
+                            AgentStatus::Completed,
+                        )
+                    } else {
+                        (
+                            MessageKind::Error,
+                            RunStatus::Failed,
+                            format!(
+                                "Process exited with failure{}.",
+                                code.map(|c| format!(" (code {})", c)).unwrap_or_default()
+                            ),
+                            AgentStatus::Errored,
+                        )
+                    };
+                    let note = if suppress_auto_restart {
+                        format!("{} Auto-restart paused by policy.", note)
+                    } else {
+                        note
+                    };
+                    let message = Message::from_agent(&agent_id, kind, &note);
+                    let _ = db.insert_message(&message);
+                    let _ = db.append_run_output(&agent_id, "process_exit", &note);
+                    let _ = db.finalize_latest_run(&agent_id, status, Some(note.clone()));
+                    let _ = db.update_agent_status(&agent_id, &agent_status);
+                    break;
                 }
-                let (kind, status, note, agent_status) = if code.unwrap_or(0) == 0 {
-                    (
-                        MessageKind::Completed,
-                        RunStatus::Completed,
-                        format!(
-                            "Process exited normally{}.",
-                            code.map(|c| format!(" (code {})", c)).unwrap_or_default()
-                        ),
-                        AgentStatus::Completed,
-                    )
-                } else {
-                    (
-                        MessageKind::Error,
-                        RunStatus::Failed,
-                        format!(
-                            "Process exited with failure{}.",
-                            code.map(|c| format!(" (code {})", c)).unwrap_or_default()
-                        ),
-                        AgentStatus::Errored,
-                    )
-                };
-                let note = if suppress_auto_restart {
-                    format!("{} Auto-restart paused by policy.", note)
+
+                let mut cancel_requested = false;
+                let poll_due = woken
+                    || last_poll.is_none_or(|at| at.elapsed() >= super::PENDING_POLL_FALLBACK);
+                let pending = if poll_due {
+                    last_poll = Some(Instant::now());
+                    super::expire_stale_messages(&db, &agent_id);
+                    db.get_pending_messages(&agent_id)
                 } else {
-                    note
+                    Ok(Vec::new())
                 };
-                let message = Message::from_agent(&agent_id, kind, &note);
-                let _ = db.insert_message(&message);
-                let _ = db.append_run_output(&agent_id, "process_exit", &note);
-                let _ = db.finalize_latest_run(&agent_id, status, Some(note.clone()));
-                let _ = db.update_agent_status(&agent_id, &agent_status);
-                break;
-            }
-
-            let mut cancel_requested = false;
-            if let Ok(pending) = db.get_pending_messages(&agent_id) {
-                for message in pending {
-                    match message.kind {
-                        MessageKind::Instruction | MessageKind::Resume => {
-                            let _ = db.start_instruction_run(&agent_id, &message.content);
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
-                            if let Err(error) = write_instruction(&session, &message.content, true)
-                            {
-                                let text = format!("failed to send instruction: {}", error);
-                                let error_message =
-                                    Message::from_agent(&agent_id, MessageKind::Error, &text);
-                                let _ = db.insert_message(&error_message);
-                                let _ = db.append_run_output(&agent_id, "error", &text);
+                if let Ok(pending) = pending {
+                    for message in pending {
+                        let _span = crate::traces::message_span(&message, "deliver").entered();
+                        match message.kind {
+                            MessageKind::Instruction | MessageKind::Resume => {
+                                let _ = db.start_instruction_run(
+                                    &agent_id,
+                                    Some(&message.id),
+                                    &message.content,
+                                );
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
+                                if let Err(error) =
+                                    write_instruction(&session, &message.content, true)
+                                {
+                                    let text = format!("failed to send instruction: {}", error);
+                                    let error_message =
+                                        Message::from_agent(&agent_id, MessageKind::Error, &text);
+                                    let _ = db.insert_message(&error_message);
+                                    let _ = db.append_run_output(&agent_id, "error", &text);
+                                    let _ = db.finalize_latest_run(
+                                        &agent_id,
+                                        RunStatus::Failed,
+                                        Some("Process instruction delivery failed".to_string()),
+                                    );
+                                    let _ =
+                                        db.update_agent_status(&agent_id, &AgentStatus::Errored);
+                                }
+                            }
+                            MessageKind::Pause => {
+                                let _ = write_instruction(&session, "\u{3}", false);
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
+                                emit_status_message(
+                                    &db,
+                                    &agent_id,
+                                    "Pause signal sent to process stdin.",
+                                );
+                            }
+                            MessageKind::Cancel => {
+                                let _ = terminate_session(&session);
+                                remove_session(&agent_id);
+                                let _ = db.append_run_output(&agent_id, "cancel", &message.content);
                                 let _ = db.finalize_latest_run(
                                     &agent_id,
                                     RunStatus::Failed,
-                                    Some("Process instruction delivery failed".to_string()),
+                                    Some("Cancelled by operator".to_string()),
                                 );
-                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Errored);
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Idle);
+                                emit_status_message(&db, &agent_id, "Process terminated.");
+                                cancel_requested = true;
                             }
+                            MessageKind::StatusRequest => {
+                                let last_output = session
+                                    .output_ring
+                                    .lock()
+                                    .ok()
+                                    .and_then(|ring| ring.snapshot_tail(STATUS_TAIL_LINES))
+                                    .unwrap_or_else(|| "No output captured yet.".to_string());
+                                let details = format!(
+                                    "Process command `{}` is running.\nLast output: {}",
+                                    session.command, last_output
+                                );
+                                emit_status_message(&db, &agent_id, &details);
+                            }
+                            _ => {}
                         }
-                        MessageKind::Pause => {
-                            let _ = write_instruction(&session, "\u{3}", false);
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
-                            emit_status_message(
-                                &db,
-                                &agent_id,
-                                "Pause signal sent to process stdin.",
-                            );
-                        }
-                        MessageKind::Cancel => {
-                            let _ = terminate_session(&session);
-                            remove_session(&agent_id);
-                            let _ = db.append_run_output(&agent_id, "cancel", &message.content);
-                            let _ = db.finalize_latest_run(
-                                &agent_id,
-                                RunStatus::Failed,
-                                Some("Cancelled by operator".to_string()),
-                            );
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Idle);
-                            emit_status_message(&db, &agent_id, "Process terminated.");
-                            cancel_requested = true;
-                        }
-                        MessageKind::StatusRequest => {
-                            let last_output = session
-                                .output_ring
-                                .lock()
-                                .ok()
-                                .and_then(|ring| ring.snapshot_tail(STATUS_TAIL_LINES))
-                                .unwrap_or_else(|| "No output captured yet.".to_string());
-                            let details = format!(
-                                "Process command `{}` is running.\nLast output: {}",
-                                session.command, last_output
-                            );
-                            emit_status_message(&db, &agent_id, &details);
-                        }
-                        _ => {}
+                        let _ = db.mark_delivered(&message.id);
+                        tracing::debug!("Message delivered");
                     }
-                    let _ = db.mark_delivered(&message.id);
                 }
-            }
 
-            if cancel_requested {
-                break;
-            }
+                if cancel_requested {
+                    break;
+                }
 
-            thread::sleep(Duration::from_millis(400));
+                woken = db.wait_for_messages(&agent_id, Duration::from_millis(400));
+            }
         });
 
         Ok(())
@@ -732,6 +796,18 @@ mod tests {
         assert!(!should_suppress_auto_restart(RestartPolicy::Always, None));
     }
 
+    #[test]
+    fn parse_usage_line_reads_json_stream_results() {
+        let claude = r#"{"type":"result","total_cost_usd":0.0421,"usage":{"input_tokens":10,"cache_read_input_tokens":5,"output_tokens":20}}"#;
+        let usage = parse_usage_line(claude).expect("claude result usage should parse");
+        assert_eq!(usage.token_in, 15);
+        assert_eq!(usage.token_out, 20);
+        assert!((usage.cost - 0.0421).abs() < f64::EPSILON);
+
+        assert!(parse_usage_line("plain text mentioning usage").is_none());
+        assert!(parse_usage_line(r#"{"type":"message","text":"hi"}"#).is_none());
+    }
+
     #[test]
     fn parse_env_strips_internal_control_keys() {
         let config = process_config_with_env(Some(json!({
@@ -0,0 +1,521 @@
+use super::{Adapter, AdapterError, AdapterHealth};
+use crate::db::Database;
+use crate::models::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Simulation adapter. Answers each instruction by replaying the next turn of
+/// a recorded transcript: the same messages and file changes, at the same
+/// offsets, every time. Lets the UI and the bus be exercised
+/// deterministically, and demos run without a live agent.
+///
+/// The transcript path lives in the config's `endpoint`. Turns are used in
+/// order and wrap around once the transcript runs out.
+pub struct SimulationAdapter {
+    transcript_path: Option<String>,
+}
+
+/// Current transcript format. Bump when a change isn't backward compatible.
+pub const TRANSCRIPT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub version: u32,
+    #[serde(default)]
+    pub recorded_from: Option<String>, // agent name
+    #[serde(default)]
+    pub recorded_at: Option<DateTime<Utc>>,
+    pub turns: Vec<TranscriptTurn>,
+}
+
+/// What the agent did in response to one instruction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptTurn {
+    #[serde(default)]
+    pub instruction: Option<String>, // as recorded; replay answers any instruction
+    pub steps: Vec<TranscriptStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptStep {
+    pub at_ms: u64, // offset from the start of the turn
+    #[serde(flatten)]
+    pub event: StepEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepEvent {
+    Message {
+        kind: MessageKind,
+        content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<serde_json::Value>,
+    },
+    FileChange {
+        path: String,
+        change_type: FileChangeType,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        old_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lines_added: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lines_removed: Option<u32>,
+    },
+}
+
+/// Result of `record_simulation_transcript`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedTranscript {
+    pub path: String,
+    pub turns: usize,
+    pub steps: usize,
+}
+
+impl SimulationAdapter {
+    pub fn new(config: &AdapterConfig) -> Self {
+        Self {
+            transcript_path: config
+                .endpoint
+                .as_deref()
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string),
+        }
+    }
+
+    fn load(&self) -> Result<Transcript, AdapterError> {
+        let path = self.transcript_path.as_deref().ok_or_else(|| {
+            AdapterError::SpawnFailed("simulation adapter needs a transcript path".to_string())
+        })?;
+        load_transcript(path).map_err(AdapterError::SpawnFailed)
+    }
+}
+
+/// Read and check a transcript file. Steps may only use kinds an agent
+/// sends, and there has to be at least one turn to replay.
+pub fn load_transcript(path: &str) -> Result<Transcript, String> {
+    let expanded = shellexpand::tilde(path);
+    let raw = std::fs::read_to_string(expanded.as_ref())
+        .map_err(|e| format!("failed to read transcript {}: {}", path, e))?;
+    let transcript: Transcript =
+        serde_json::from_str(&raw).map_err(|e| format!("invalid transcript {}: {}", path, e))?;
+    if transcript.version > TRANSCRIPT_VERSION {
+        return Err(format!(
+            "transcript {} is version {}; this build reads up to {}",
+            path, transcript.version, TRANSCRIPT_VERSION
+        ));
+    }
+    if transcript.turns.is_empty() {
+        return Err(format!("transcript {} has no turns", path));
+    }
+    for step in transcript.turns.iter().flat_map(|turn| &turn.steps) {
+        if let StepEvent::Message { kind, .. } = &step.event {
+            if matches!(
+                kind,
+                MessageKind::Instruction
+                    | MessageKind::Pause
+                    | MessageKind::Resume
+                    | MessageKind::Cancel
+                    | MessageKind::StatusRequest
+            ) {
+                return Err(format!(
+                    "transcript {} replays a {} message, which only Kanbun sends",
+                    path,
+                    kind.as_str()
+                ));
+            }
+        }
+    }
+    Ok(transcript)
+}
+
+impl Adapter for SimulationAdapter {
+    fn deliver(&self, message: &Message) -> Result<(), AdapterError> {
+        tracing::info!(
+            "[SimulationAdapter] Delivering to {}: {:?} - {}",
+            message.agent_id,
+            message.kind,
+            message.content
+        );
+        Ok(())
+    }
+
+    fn start(&self, agent_id: &str, db: Arc<Database>) -> Result<(), AdapterError> {
+        let transcript = self.load()?;
+        let agent_id = agent_id.to_string();
+
+        std::thread::spawn(move || {
+            let _span = crate::traces::adapter_span(&agent_id, "simulation").entered();
+            let mut next_turn = 0;
+            loop {
+                match db.get_adapter_config(&agent_id) {
+                    Ok(Some(config)) if config.adapter_type == AdapterType::Simulation => {}
+                    _ => break,
+                }
+
+                db.wait_for_messages(&agent_id, super::PENDING_POLL_FALLBACK);
+
+                super::expire_stale_messages(&db, &agent_id);
+                let Ok(pending) = db.get_pending_messages(&agent_id) else {
+                    continue;
+                };
+                for msg in pending {
+                    let _span = crate::traces::message_span(&msg, "deliver").entered();
+                    let _ = db.mark_delivered(&msg.id);
+                    tracing::debug!("Message delivered");
+
+                    match msg.kind {
+                        MessageKind::Instruction | MessageKind::Resume => {
+                            let turn = &transcript.turns[next_turn % transcript.turns.len()];
+                            next_turn += 1;
+                            let _ =
+                                db.start_instruction_run(&agent_id, Some(&msg.id), &msg.content);
+                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
+                            replay_turn(&db, &agent_id, &msg, turn);
+                        }
+                        MessageKind::StatusRequest => {
+                            let status = Message::from_agent(
+                                &agent_id,
+                                MessageKind::StatusUpdate,
+                                &format!(
+                                    "Simulation adapter replaying a {}-turn transcript; next turn {}.",
+                                    transcript.turns.len(),
+                                    next_turn % transcript.turns.len() + 1
+                                ),
+                            );
+                            let _ = db.insert_message(&status);
+                            let _ =
+                                db.append_run_output(&agent_id, "status_update", &status.content);
+                        }
+                        MessageKind::Pause => {
+                            let _ = db.append_run_output(&agent_id, "pause", &msg.content);
+                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
+                        }
+                        MessageKind::Cancel => {
+                            let _ = db.append_run_output_to(
+                                &agent_id,
+                                msg.run_key(),
+                                "cancel",
+                                &msg.content,
+                            );
+                            let _ = db.finalize_run_for(
+                                &agent_id,
+                                msg.run_key(),
+                                RunStatus::Failed,
+                                Some("Cancelled by operator".to_string()),
+                            );
+                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Idle);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn stop(&self, _agent_id: &str) -> Result<(), AdapterError> {
+        Ok(())
+    }
+
+    fn health_check(&self, _agent_id: &str) -> Result<AdapterHealth, AdapterError> {
+        let (connected, details, last_error) = match self.load() {
+            Ok(transcript) => (
+                true,
+                format!(
+                    "Simulation replaying {} turns from {}",
+                    transcript.turns.len(),
+                    self.transcript_path.as_deref().unwrap_or_default()
+                ),
+                None,
+            ),
+            Err(error) => (
+                false,
+                "Simulation transcript unavailable".to_string(),
+                Some(error.to_string()),
+            ),
+        };
+        Ok(AdapterHealth {
+            connected,
+            session_active: connected,
+            last_heartbeat: connected.then(|| Utc::now().to_rfc3339()),
+            details: Some(details),
+            retry_after_seconds: None,
+            consecutive_failures: None,
+            last_error,
+            suppress_auto_restart: None,
+        })
+    }
+}
+
+/// Play one turn against the run `instruction` started. A Cancel queued
+/// while waiting for the next step ends the replay early and is left for the
+/// delivery loop to handle.
+fn replay_turn(db: &Database, agent_id: &str, instruction: &Message, turn: &TranscriptTurn) {
+    let started = Instant::now();
+    let run_key = Some(instruction.id.as_str());
+    let mut steps: Vec<&TranscriptStep> = turn.steps.iter().collect();
+    steps.sort_by_key(|step| step.at_ms);
+
+    for step in steps {
+        if !wait_until(db, agent_id, started, Duration::from_millis(step.at_ms)) {
+            return;
+        }
+        match &step.event {
+            StepEvent::Message {
+                kind,
+                content,
+                metadata,
+            } => {
+                let mut message = Message::from_agent(agent_id, kind.clone(), content);
+                message.reply_to = Some(instruction.id.clone());
+                message.metadata = metadata.clone();
+                let _ = db.insert_message(&message);
+                if *kind == MessageKind::Heartbeat {
+                    continue;
+                }
+                let _ = db.append_run_output_to(agent_id, run_key, kind.as_str(), content);
+                let outcome = match kind {
+                    MessageKind::Completed => Some((RunStatus::Completed, AgentStatus::Completed)),
+                    MessageKind::Error => Some((RunStatus::Failed, AgentStatus::Errored)),
+                    MessageKind::Blocked => Some((RunStatus::NeedsReview, AgentStatus::Blocked)),
+                    _ => None,
+                };
+                if let Some((run_status, agent_status)) = outcome {
+                    let _ =
+                        db.finalize_run_for(agent_id, run_key, run_status, Some(content.clone()));
+                    let _ = db.update_agent_status(agent_id, &agent_status);
+                    return;
+                }
+            }
+            StepEvent::FileChange {
+                path,
+                change_type,
+                old_path,
+                lines_added,
+                lines_removed,
+            } => {
+                let _ = db.record_file_change(
+                    agent_id,
+                    FileChange {
+                        path: path.clone(),
+                        change_type: change_type.clone(),
+                        timestamp: Utc::now(),
+                        count: 1,
+                        old_path: old_path.clone(),
+                        lines_added: *lines_added,
+                        lines_removed: *lines_removed,
+                    },
+                );
+            }
+        }
+    }
+
+    // The recording stopped before the agent said it was done.
+    let _ = db.finalize_run_for(
+        agent_id,
+        run_key,
+        RunStatus::Completed,
+        Some("Simulation replay finished".to_string()),
+    );
+    let _ = db.update_agent_status(agent_id, &AgentStatus::Completed);
+}
+
+/// Sleep until `offset` past `started`. Returns false if a Cancel is queued
+/// in the meantime.
+fn wait_until(db: &Database, agent_id: &str, started: Instant, offset: Duration) -> bool {
+    loop {
+        let elapsed = started.elapsed();
+        if elapsed >= offset {
+            return true;
+        }
+        if db.wait_for_messages(agent_id, offset - elapsed) {
+            let cancelled = db.get_pending_messages(agent_id).is_ok_and(|pending| {
+                pending
+                    .iter()
+                    .any(|message| message.kind == MessageKind::Cancel)
+            });
+            if cancelled {
+                return false;
+            }
+        }
+    }
+}
+
+/// Capture an agent's session as a transcript. Every instruction (or
+/// resume) since `since` starts a turn; what the agent sent back until the
+/// next one, and the file changes on that instruction's run, become its
+/// steps, timed from when the instruction was sent.
+pub fn record_transcript(
+    db: &Database,
+    agent_id: &str,
+    since: Option<&str>,
+) -> rusqlite::Result<Transcript> {
+    let mut turns: Vec<(DateTime<Utc>, TranscriptTurn)> = vec![];
+    for message in db.get_messages_for_agent_between(agent_id, since, None)? {
+        let is_turn_start = message.direction == MessageDirection::ToAgent
+            && matches!(message.kind, MessageKind::Instruction | MessageKind::Resume);
+        if is_turn_start {
+            let mut turn = TranscriptTurn {
+                instruction: Some(message.content.clone()),
+                steps: vec![],
+            };
+            if let Some(run) = db.get_run_for_instruction(&message.id)? {
+                turn.steps
+                    .extend(run.file_changes.into_iter().map(|change| TranscriptStep {
+                        at_ms: offset_ms(message.created_at, change.timestamp),
+                        event: StepEvent::FileChange {
+                            path: change.path,
+                            change_type: change.change_type,
+                            old_path: change.old_path,
+                            lines_added: change.lines_added,
+                            lines_removed: change.lines_removed,
+                        },
+                    }));
+            }
+            turns.push((message.created_at, turn));
+            continue;
+        }
+        if message.direction != MessageDirection::FromAgent
+            || message.kind == MessageKind::Heartbeat
+        {
+            continue;
+        }
+        let Some((started_at, turn)) = turns.last_mut() else {
+            continue; // before the first instruction
+        };
+        turn.steps.push(TranscriptStep {
+            at_ms: offset_ms(*started_at, message.created_at),
+            event: StepEvent::Message {
+                kind: message.kind,
+                content: message.content,
+                metadata: message.metadata,
+            },
+        });
+    }
+
+    let mut turns: Vec<TranscriptTurn> = turns.into_iter().map(|(_, turn)| turn).collect();
+    for turn in &mut turns {
+        turn.steps.sort_by_key(|step| step.at_ms);
+    }
+    Ok(Transcript {
+        version: TRANSCRIPT_VERSION,
+        recorded_from: db.get_agent(agent_id)?.map(|agent| agent.name),
+        recorded_at: Some(Utc::now()),
+        turns,
+    })
+}
+
+fn offset_ms(start: DateTime<Utc>, at: DateTime<Utc>) -> u64 {
+    (at - start).num_milliseconds().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_agent(db: &Database, name: &str, project: &Project) -> String {
+        let agent = Agent::new(name, &project.id, AgentKind::Terminal, "engineering");
+        db.create_agent(&agent).unwrap();
+        agent.id
+    }
+
+    #[test]
+    fn recorded_sessions_replay_into_the_same_messages() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).unwrap();
+        let live = setup_agent(&db, "Live", &project);
+
+        // A live session: instruction, output, a file change, completion.
+        let instruction = Message::to_agent(&live, MessageKind::Instruction, "fix the header");
+        db.insert_message(&instruction).unwrap();
+        db.start_instruction_run(&live, Some(&instruction.id), &instruction.content)
+            .unwrap();
+        let mut output = Message::from_agent(&live, MessageKind::Output, "editing header.tsx");
+        output.created_at = instruction.created_at + chrono::Duration::milliseconds(40);
+        db.insert_message(&output).unwrap();
+        let mut heartbeat = Message::from_agent(&live, MessageKind::Heartbeat, "alive");
+        heartbeat.created_at = instruction.created_at + chrono::Duration::milliseconds(50);
+        db.insert_message(&heartbeat).unwrap();
+        db.record_file_change(
+            &live,
+            FileChange {
+                path: "src/header.tsx".to_string(),
+                change_type: FileChangeType::Modified,
+                timestamp: instruction.created_at + chrono::Duration::milliseconds(60),
+                count: 1,
+                old_path: None,
+                lines_added: Some(3),
+                lines_removed: Some(1),
+            },
+        )
+        .unwrap();
+        let mut done = Message::from_agent(&live, MessageKind::Completed, "header fixed");
+        done.created_at = instruction.created_at + chrono::Duration::milliseconds(80);
+        db.insert_message(&done).unwrap();
+
+        let transcript = record_transcript(&db, &live, None).unwrap();
+        assert_eq!(transcript.recorded_from.as_deref(), Some("Live"));
+        assert_eq!(transcript.turns.len(), 1);
+        let offsets: Vec<u64> = transcript.turns[0]
+            .steps
+            .iter()
+            .map(|step| step.at_ms)
+            .collect();
+        assert_eq!(offsets, [40, 60, 80]);
+
+        // The JSON round-trips and replays against a fresh agent.
+        let path = std::env::temp_dir().join(format!("kanbun-transcript-{}.json", live));
+        std::fs::write(&path, serde_json::to_string_pretty(&transcript).unwrap()).unwrap();
+        let loaded = load_transcript(path.to_str().unwrap()).expect("transcript should load");
+        let _ = std::fs::remove_file(&path);
+
+        let replayed = setup_agent(&db, "Replayed", &project);
+        let prompt = Message::to_agent(&replayed, MessageKind::Instruction, "anything");
+        db.insert_message(&prompt).unwrap();
+        db.start_instruction_run(&replayed, Some(&prompt.id), &prompt.content)
+            .unwrap();
+        replay_turn(&db, &replayed, &prompt, &loaded.turns[0]);
+
+        let replies: Vec<(MessageKind, String)> = db
+            .get_messages_for_agent_between(&replayed, None, None)
+            .unwrap()
+            .into_iter()
+            .filter(|message| message.direction == MessageDirection::FromAgent)
+            .map(|message| (message.kind, message.content))
+            .collect();
+        assert_eq!(
+            replies,
+            [
+                (MessageKind::Output, "editing header.tsx".to_string()),
+                (MessageKind::Completed, "header fixed".to_string()),
+            ]
+        );
+        let run = db
+            .get_run_for_instruction(&prompt.id)
+            .unwrap()
+            .expect("run should exist");
+        assert_eq!(run.status, RunStatus::Completed);
+        assert_eq!(run.file_changes.len(), 1);
+        assert_eq!(run.file_changes[0].lines_added, Some(3));
+    }
+
+    #[test]
+    fn transcripts_cannot_send_operator_messages() {
+        let path =
+            std::env::temp_dir().join(format!("kanbun-transcript-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{"version":1,"turns":[{"steps":[{"at_ms":0,"type":"message","kind":"cancel","content":"stop"}]}]}"#,
+        )
+        .unwrap();
+        let error = load_transcript(path.to_str().unwrap()).unwrap_err();
+        let _ = std::fs::remove_file(&path);
+        assert!(error.contains("only Kanbun sends"));
+    }
+}
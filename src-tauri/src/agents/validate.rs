@@ -0,0 +1,346 @@
+use crate::models::{AdapterConfig, AdapterType};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+// ── Adapter config validation ───────────────────────────────────────────────
+// Catches configs that would only fail once the adapter starts: a command
+// that isn't installed, an endpoint that isn't a URL, a session name tmux
+// would rewrite. Errors block saving; warnings are returned alongside.
+//
+// Field meanings follow `create_adapter`: claude_code keeps its session
+// prefix in `session_name`, its working directory in `command` and the
+// claude binary in `endpoint`; simulation keeps its transcript path in
+// `endpoint`.
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueLevel {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+    pub level: IssueLevel,
+    pub field: String, // "command", "endpoint", "session_name", "env", "adapter_type"
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigValidation {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigValidation {
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.level == IssueLevel::Error)
+    }
+
+    /// The errors as one message, for callers that can only return a string.
+    pub fn error_summary(&self) -> String {
+        self.issues
+            .iter()
+            .filter(|issue| issue.level == IssueLevel::Error)
+            .map(|issue| format!("{}: {}", issue.field, issue.message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn push(&mut self, level: IssueLevel, field: &str, message: String) {
+        self.issues.push(ConfigIssue {
+            level,
+            field: field.to_string(),
+            message,
+        });
+    }
+
+    fn error(&mut self, field: &str, message: String) {
+        self.push(IssueLevel::Error, field, message);
+    }
+
+    fn warning(&mut self, field: &str, message: String) {
+        self.push(IssueLevel::Warning, field, message);
+    }
+}
+
+/// Check a config without contacting anything but the local shell.
+pub fn validate_config(config: &AdapterConfig) -> ConfigValidation {
+    let mut validation = ConfigValidation::default();
+    check_env(config, &mut validation);
+
+    match config.adapter_type {
+        AdapterType::Process => match non_empty(&config.command) {
+            Some(command) => check_command("command", command, &mut validation),
+            None => validation.error(
+                "command",
+                "Process adapters need a command to run.".to_string(),
+            ),
+        },
+        AdapterType::Codex => {
+            let command = non_empty(&config.command).unwrap_or("codex");
+            check_command("command", command, &mut validation);
+        }
+        AdapterType::ClaudeCode => {
+            if let Some(prefix) = non_empty(&config.session_name) {
+                check_session_name(prefix, &mut validation);
+            }
+            let claude = non_empty(&config.endpoint).unwrap_or("claude");
+            check_command("endpoint", claude, &mut validation);
+            if let Some(directory) = non_empty(&config.command) {
+                let expanded = shellexpand::tilde(directory);
+                if !Path::new(expanded.as_ref()).is_dir() {
+                    validation.warning(
+                        "command",
+                        format!("Working directory {} does not exist.", directory),
+                    );
+                }
+            }
+            if !super::can_use_tmux() {
+                validation.warning(
+                    "adapter_type",
+                    "tmux is not installed; the session will run as a plain process.".to_string(),
+                );
+            }
+        }
+        AdapterType::HttpWebhook => {
+            if let Some(endpoint) = non_empty(&config.endpoint) {
+                if let Err(message) = parse_endpoint(endpoint) {
+                    validation.error("endpoint", message);
+                }
+            }
+        }
+        AdapterType::Tmux => {
+            if let Some(name) = non_empty(&config.session_name) {
+                check_session_name(name, &mut validation);
+            }
+            validation.warning(
+                "adapter_type",
+                "tmux adapters are not implemented yet; the agent will run as a mock.".to_string(),
+            );
+        }
+        AdapterType::Simulation => match non_empty(&config.endpoint) {
+            Some(path) => {
+                if let Err(message) = super::simulation::load_transcript(path) {
+                    validation.error("endpoint", message);
+                }
+            }
+            None => validation.error(
+                "endpoint",
+                "Simulation adapters need a transcript path.".to_string(),
+            ),
+        },
+        AdapterType::Mock => {}
+    }
+
+    validation
+}
+
+/// Ask a webhook endpoint whether it's up. Failing to answer is a warning:
+/// the receiving service may simply not be running yet.
+pub async fn probe_endpoint(config: &AdapterConfig, validation: &mut ConfigValidation) {
+    if config.adapter_type != AdapterType::HttpWebhook {
+        return;
+    }
+    let Some(endpoint) = non_empty(&config.endpoint) else {
+        return;
+    };
+    let Ok(url) = parse_endpoint(endpoint) else {
+        return; // already reported
+    };
+    let client = Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| Client::new());
+    match client.get(url).send().await {
+        Ok(response) if response.status().is_server_error() => validation.warning(
+            "endpoint",
+            format!("Endpoint answered HTTP {}.", response.status()),
+        ),
+        Ok(_) => {}
+        Err(error) => validation.warning("endpoint", format!("Endpoint did not answer: {}", error)),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_endpoint(endpoint: &str) -> Result<Url, String> {
+    let url = Url::parse(endpoint).map_err(|error| format!("Not a valid URL: {}", error))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "Endpoint must use http or https, not {}.",
+            url.scheme()
+        ));
+    }
+    Ok(url)
+}
+
+/// tmux rewrites `.` and `:` in session names, after which Kanbun can no
+/// longer find the session it created.
+fn check_session_name(name: &str, validation: &mut ConfigValidation) {
+    if name.contains(['.', ':']) {
+        validation.error(
+            "session_name",
+            format!(
+                "\"{}\" contains '.' or ':', which tmux does not allow.",
+                name
+            ),
+        );
+    } else if name.chars().any(char::is_control) {
+        validation.error(
+            "session_name",
+            "Session name contains control characters.".to_string(),
+        );
+    } else if name.chars().any(char::is_whitespace) {
+        validation.warning(
+            "session_name",
+            format!(
+                "\"{}\" contains spaces; attach with quotes (tmux attach -t \"…\").",
+                name
+            ),
+        );
+    }
+}
+
+fn check_env(config: &AdapterConfig, validation: &mut ConfigValidation) {
+    let Some(env) = &config.env else {
+        return;
+    };
+    if env.is_null() {
+        return;
+    }
+    let Some(map) = env.as_object() else {
+        validation.error(
+            "env",
+            "Environment must be an object of NAME: value pairs.".to_string(),
+        );
+        return;
+    };
+    for (key, value) in map {
+        if key.trim().is_empty() {
+            validation.warning("env", "An entry with an empty name is ignored.".to_string());
+        } else if value.is_object() || value.is_array() || value.is_null() {
+            validation.warning(
+                "env",
+                format!("{} is not a string; it will be passed as {}.", key, value),
+            );
+        }
+    }
+}
+
+/// The adapter runs commands through a login shell, so a program missing
+/// from the app's own PATH may still be found by the shell's.
+fn check_command(field: &str, command: &str, validation: &mut ConfigValidation) {
+    let Some(program) = command.split_whitespace().find(|word| !word.contains('=')) else {
+        return; // only variable assignments; leave it to the shell
+    };
+    if resolves(program) {
+        return;
+    }
+    validation.error(
+        field,
+        format!("{} was not found on PATH or by the login shell.", program),
+    );
+}
+
+fn resolves(program: &str) -> bool {
+    let expanded = shellexpand::tilde(program);
+    let path = Path::new(expanded.as_ref());
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    let on_path = std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| {
+            let candidate = dir.join(program);
+            candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+        })
+    });
+    on_path || resolves_in_shell(program)
+}
+
+fn resolves_in_shell(program: &str) -> bool {
+    let output = if cfg!(target_os = "windows") {
+        Command::new("where").arg(program).output()
+    } else {
+        Command::new("sh")
+            .args(["-lc", "command -v -- \"$1\"", "sh", program])
+            .output()
+    };
+    output.is_ok_and(|output| output.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(adapter_type: AdapterType) -> AdapterConfig {
+        AdapterConfig {
+            adapter_type,
+            session_name: None,
+            endpoint: None,
+            command: None,
+            env: None,
+        }
+    }
+
+    fn fields(validation: &ConfigValidation, level: IssueLevel) -> Vec<&str> {
+        validation
+            .issues
+            .iter()
+            .filter(|issue| issue.level == level)
+            .map(|issue| issue.field.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn process_commands_must_resolve() {
+        let mut process = config(AdapterType::Process);
+        assert_eq!(
+            fields(&validate_config(&process), IssueLevel::Error),
+            ["command"]
+        );
+
+        process.command = Some("sh -c 'echo ready'".to_string());
+        assert!(!validate_config(&process).has_errors());
+
+        process.command = Some("FOO=1 kanbun-no-such-binary --serve".to_string());
+        let validation = validate_config(&process);
+        assert!(validation.has_errors());
+        assert!(validation.error_summary().contains("kanbun-no-such-binary"));
+    }
+
+    #[test]
+    fn endpoints_session_names_and_env_are_checked() {
+        let mut webhook = config(AdapterType::HttpWebhook);
+        webhook.endpoint = Some("localhost:8765/hook".to_string());
+        webhook.env = Some(serde_json::json!({ "AUTH_HEADER": "Bearer x", "RETRIES": [1, 2] }));
+        let validation = validate_config(&webhook);
+        assert_eq!(fields(&validation, IssueLevel::Error), ["endpoint"]);
+        assert_eq!(fields(&validation, IssueLevel::Warning), ["env"]);
+
+        webhook.endpoint = Some("https://agents.example.com/hook".to_string());
+        webhook.env = Some(serde_json::json!(["AUTH_HEADER=x"]));
+        assert_eq!(
+            fields(&validate_config(&webhook), IssueLevel::Error),
+            ["env"]
+        );
+
+        let mut tmux = config(AdapterType::Tmux);
+        tmux.session_name = Some("site.v2".to_string());
+        assert_eq!(
+            fields(&validate_config(&tmux), IssueLevel::Error),
+            ["session_name"]
+        );
+    }
+}
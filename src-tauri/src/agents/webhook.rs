@@ -12,7 +12,6 @@ use std::time::Duration;
 const AUTH_HEADER_KEY: &str = "AUTH_HEADER";
 const DEFAULT_WEBHOOK_ENDPOINT: &str = "http://localhost:8765/kanbun/webhook";
 const REQUEST_TIMEOUT_SECONDS: u64 = 8;
-const POLL_INTERVAL_MS: u64 = 700;
 
 #[derive(Debug, Serialize)]
 struct WebhookRequest<'a> {
@@ -32,6 +31,8 @@ struct WebhookResponse {
     status: Option<String>,
     #[serde(default)]
     content: Option<String>,
+    #[serde(default)]
+    usage: Option<Value>,
 }
 
 #[derive(Debug)]
@@ -191,101 +192,132 @@ impl Adapter for WebhookAdapter {
     fn start(&self, agent_id: &str, db: Arc<Database>) -> Result<(), AdapterError> {
         let agent_id = agent_id.to_string();
 
-        thread::spawn(move || loop {
-            let config = match db.get_adapter_config(&agent_id) {
-                Ok(Some(config)) if config.adapter_type == AdapterType::HttpWebhook => config,
-                Ok(_) => break,
-                Err(_) => break,
-            };
-            let adapter = WebhookAdapter::new(&config);
-
-            if let Ok(pending) = db.get_pending_messages(&agent_id) {
-                for message in pending {
-                    let maybe_response = adapter.post_payload(&agent_id, &message);
-
-                    match message.kind {
-                        MessageKind::Instruction | MessageKind::Resume => {
-                            let _ = db.start_instruction_run(&agent_id, &message.content);
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
-                        }
-                        MessageKind::Pause => {
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
-                        }
-                        MessageKind::Cancel => {
-                            let _ = db.finalize_latest_run(
-                                &agent_id,
-                                RunStatus::Failed,
-                                Some("Cancelled by operator".to_string()),
-                            );
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Idle);
+        thread::spawn(move || {
+            let _span = crate::traces::adapter_span(&agent_id, "http_webhook").entered();
+            loop {
+                let config = match db.get_adapter_config(&agent_id) {
+                    Ok(Some(config)) if config.adapter_type == AdapterType::HttpWebhook => config,
+                    Ok(_) => break,
+                    Err(_) => break,
+                };
+                let adapter = WebhookAdapter::new(&config);
+
+                super::expire_stale_messages(&db, &agent_id);
+                if let Ok(pending) = db.get_pending_messages(&agent_id) {
+                    for message in pending {
+                        let _span = crate::traces::message_span(&message, "deliver").entered();
+                        let maybe_response = adapter.post_payload(&agent_id, &message);
+
+                        match message.kind {
+                            MessageKind::Instruction | MessageKind::Resume => {
+                                let _ = db.start_instruction_run(
+                                    &agent_id,
+                                    Some(&message.id),
+                                    &message.content,
+                                );
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
+                            }
+                            MessageKind::Pause => {
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
+                            }
+                            MessageKind::Cancel => {
+                                let _ = db.finalize_run_for(
+                                    &agent_id,
+                                    message.run_key(),
+                                    RunStatus::Failed,
+                                    Some("Cancelled by operator".to_string()),
+                                );
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Idle);
+                            }
+                            _ => {}
                         }
-                        _ => {}
-                    }
 
-                    match maybe_response {
-                        Ok(Some(reply)) => {
-                            let mapped = reply
-                                .kind
-                                .as_deref()
-                                .or(reply.status.as_deref())
-                                .map(Self::message_type_from_reply)
-                                .unwrap_or(MessageKind::Output);
-                            let mapped_kind = mapped.clone();
-                            let content = reply.content.unwrap_or_else(|| {
-                                "Webhook adapter completed with no response.".to_string()
-                            });
-                            let inbound = Message::from_agent(&agent_id, mapped_kind, &content);
-                            let _ = db.insert_message(&inbound);
-                            let _ = db.append_run_output(
-                                &agent_id,
-                                Self::message_kind_as_output(mapped.clone()),
-                                &content,
-                            );
-
-                            if let Some(run_status) = reply
-                                .status
-                                .and_then(|status| Self::normalize_status(&status))
-                            {
-                                let _ = db.finalize_latest_run(
+                        match maybe_response {
+                            Ok(Some(reply)) => {
+                                let mapped = reply
+                                    .kind
+                                    .as_deref()
+                                    .or(reply.status.as_deref())
+                                    .map(Self::message_type_from_reply)
+                                    .unwrap_or(MessageKind::Output);
+                                let mapped_kind = mapped.clone();
+                                let content = reply.content.unwrap_or_else(|| {
+                                    "Webhook adapter completed with no response.".to_string()
+                                });
+                                let mut inbound =
+                                    Message::from_agent(&agent_id, mapped_kind, &content);
+                                if let Some(usage) =
+                                    reply.usage.as_ref().and_then(TokenUsage::from_json)
+                                {
+                                    inbound.metadata = Some(serde_json::json!({ "usage": usage }));
+                                }
+                                let _ = db.insert_message(&inbound);
+                                let _ = db.append_run_output_to(
                                     &agent_id,
-                                    run_status.clone(),
-                                    Some(content.clone()),
+                                    Some(&message.id),
+                                    Self::message_kind_as_output(mapped.clone()),
+                                    &content,
                                 );
-                                let _ = match run_status {
-                                    RunStatus::Completed => {
-                                        db.update_agent_status(&agent_id, &AgentStatus::Completed)
-                                    }
-                                    RunStatus::Failed => {
-                                        db.update_agent_status(&agent_id, &AgentStatus::Errored)
-                                    }
-                                    RunStatus::NeedsReview => {
-                                        db.update_agent_status(&agent_id, &AgentStatus::Blocked)
-                                    }
-                                    RunStatus::InProgress => Ok(()),
-                                };
-                            } else {
+
+                                if let Some(run_status) = reply
+                                    .status
+                                    .and_then(|status| Self::normalize_status(&status))
+                                {
+                                    let _ = db.finalize_run_for(
+                                        &agent_id,
+                                        Some(&message.id),
+                                        run_status.clone(),
+                                        Some(content.clone()),
+                                    );
+                                    let _ = match run_status {
+                                        RunStatus::Completed => db.update_agent_status(
+                                            &agent_id,
+                                            &AgentStatus::Completed,
+                                        ),
+                                        RunStatus::Failed => {
+                                            db.update_agent_status(&agent_id, &AgentStatus::Errored)
+                                        }
+                                        RunStatus::NeedsReview => {
+                                            db.update_agent_status(&agent_id, &AgentStatus::Blocked)
+                                        }
+                                        RunStatus::InProgress => Ok(()),
+                                    };
+                                } else {
+                                    let _ =
+                                        db.update_agent_status(&agent_id, &AgentStatus::Running);
+                                }
+                            }
+                            Ok(None) => {
                                 let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
                             }
+                            Err(error) => {
+                                let text = format!("Webhook delivery failed: {error}");
+                                let failure =
+                                    Message::from_agent(&agent_id, MessageKind::Error, &text);
+                                let _ = db.insert_message(&failure);
+                                let _ = db.append_run_output_to(
+                                    &agent_id,
+                                    Some(&message.id),
+                                    "error",
+                                    &text,
+                                );
+                                let _ = db.finalize_run_for(
+                                    &agent_id,
+                                    Some(&message.id),
+                                    RunStatus::Failed,
+                                    Some(text),
+                                );
+                                let _ = db.update_agent_status(&agent_id, &AgentStatus::Errored);
+                            }
                         }
-                        Ok(None) => {
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
-                        }
-                        Err(error) => {
-                            let text = format!("Webhook delivery failed: {error}");
-                            let failure = Message::from_agent(&agent_id, MessageKind::Error, &text);
-                            let _ = db.insert_message(&failure);
-                            let _ = db.append_run_output(&agent_id, "error", &text);
-                            let _ =
-                                db.finalize_latest_run(&agent_id, RunStatus::Failed, Some(text));
-                            let _ = db.update_agent_status(&agent_id, &AgentStatus::Errored);
-                        }
-                    }
 
-                    let _ = db.mark_delivered(&message.id);
+                        let _ = db.mark_delivered(&message.id);
+                        tracing::debug!("Message delivered");
+                    }
                 }
-            }
 
-            thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+                db.wait_for_messages(&agent_id, super::PENDING_POLL_FALLBACK);
+            }
         });
 
         Ok(())
@@ -349,4 +381,26 @@ mod tests {
         let unknown = WebhookAdapter::message_type_from_reply("not-a-kind");
         assert_eq!(unknown, MessageKind::Output);
     }
+
+    #[test]
+    fn reply_usage_accepts_openai_and_native_shapes() {
+        let reply: WebhookResponse = serde_json::from_value(serde_json::json!({
+            "status": "completed",
+            "content": "ok",
+            "usage": { "prompt_tokens": 90, "completion_tokens": 10, "cost": 0.001 }
+        }))
+        .expect("reply should parse");
+        let usage = reply
+            .usage
+            .as_ref()
+            .and_then(TokenUsage::from_json)
+            .expect("usage should parse");
+        assert_eq!(usage.token_in, 90);
+        assert_eq!(usage.token_out, 10);
+
+        let native = TokenUsage::from_json(&serde_json::json!({ "token_in": 5, "token_out": 7 }))
+            .expect("native usage should parse");
+        assert_eq!(native.token_out, 7);
+        assert!(TokenUsage::from_json(&serde_json::json!({ "content": "no usage" })).is_none());
+    }
 }
@@ -0,0 +1,225 @@
+use crate::models::*;
+use chrono::{DateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ── Run analytics ───────────────────────────────────────────────────────────
+// Outcome and timing rollups over run history, computed from the light
+// `RunFact` projection so outputs blobs never get loaded.
+
+const BUSIEST_HOURS: usize = 3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalyticsRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// The columns analytics needs from a run.
+#[derive(Debug, Clone)]
+pub struct RunFact {
+    pub agent_id: String,
+    pub status: RunStatus,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub summary: Option<String>,
+    pub review: Option<ReviewDecision>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryCount {
+    pub category: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HourCount {
+    pub hour: u32, // 0-23, UTC
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct OutcomeStats {
+    pub total_runs: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub needs_review: usize,
+    pub in_progress: usize,
+    pub success_rate: Option<f64>, // completed / finished runs
+    pub median_duration_seconds: Option<i64>,
+    pub failure_categories: Vec<CategoryCount>,
+    pub busiest_hours: Vec<HourCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentRunAnalytics {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub stats: OutcomeStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunAnalytics {
+    pub project_id: Option<String>,
+    pub range: AnalyticsRange,
+    pub overall: OutcomeStats,
+    pub agents: Vec<AgentRunAnalytics>,
+}
+
+/// Bucket a failed run by what its summary and review say went wrong.
+pub fn failure_category(fact: &RunFact) -> &'static str {
+    if fact.review == Some(ReviewDecision::Reject) {
+        return "rejected";
+    }
+    if fact.review == Some(ReviewDecision::RequestChanges) {
+        return "changes_requested";
+    }
+    let summary = fact.summary.as_deref().unwrap_or("").to_lowercase();
+    if summary.contains("cancelled") {
+        "cancelled"
+    } else if summary.contains("exited") || summary.contains("exit code") {
+        "process_exit"
+    } else if summary.contains("adapter") || summary.contains("delivery failed") {
+        "adapter_error"
+    } else if summary.contains("timed out") || summary.contains("timeout") {
+        "timeout"
+    } else {
+        "error"
+    }
+}
+
+fn median(values: &mut [i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    })
+}
+
+fn sorted_counts<K: Clone + Ord>(counts: HashMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+pub fn outcome_stats<'a>(facts: impl IntoIterator<Item = &'a RunFact>) -> OutcomeStats {
+    let mut stats = OutcomeStats::default();
+    let mut durations = Vec::new();
+    let mut failures: HashMap<&'static str, usize> = HashMap::new();
+    let mut hours: HashMap<u32, usize> = HashMap::new();
+
+    for fact in facts {
+        stats.total_runs += 1;
+        match fact.status {
+            RunStatus::Completed => stats.completed += 1,
+            RunStatus::Failed => {
+                stats.failed += 1;
+                *failures.entry(failure_category(fact)).or_default() += 1;
+            }
+            RunStatus::NeedsReview => stats.needs_review += 1,
+            RunStatus::InProgress => stats.in_progress += 1,
+        }
+        if let Some(ended_at) = fact.ended_at {
+            durations.push((ended_at - fact.started_at).num_seconds().max(0));
+        }
+        *hours.entry(fact.started_at.hour()).or_default() += 1;
+    }
+
+    let finished = stats.completed + stats.failed + stats.needs_review;
+    stats.success_rate = (finished > 0).then(|| stats.completed as f64 / finished as f64);
+    stats.median_duration_seconds = median(&mut durations);
+    stats.failure_categories = sorted_counts(failures)
+        .into_iter()
+        .map(|(category, count)| CategoryCount {
+            category: category.to_string(),
+            count,
+        })
+        .collect();
+    stats.busiest_hours = sorted_counts(hours)
+        .into_iter()
+        .take(BUSIEST_HOURS)
+        .map(|(hour, count)| HourCount { hour, count })
+        .collect();
+    stats
+}
+
+pub fn compute_run_analytics(
+    agents: &[Agent],
+    facts: &[RunFact],
+    project_id: Option<String>,
+    range: AnalyticsRange,
+) -> RunAnalytics {
+    let mut by_agent: HashMap<&str, Vec<&RunFact>> = HashMap::new();
+    for fact in facts {
+        by_agent
+            .entry(fact.agent_id.as_str())
+            .or_default()
+            .push(fact);
+    }
+
+    let mut agent_stats: Vec<AgentRunAnalytics> = agents
+        .iter()
+        .filter_map(|agent| {
+            let facts = by_agent.get(agent.id.as_str())?;
+            Some(AgentRunAnalytics {
+                agent_id: agent.id.clone(),
+                agent_name: agent.name.clone(),
+                stats: outcome_stats(facts.iter().copied()),
+            })
+        })
+        .collect();
+    agent_stats.sort_by(|a, b| {
+        b.stats
+            .total_runs
+            .cmp(&a.stats.total_runs)
+            .then_with(|| a.agent_name.cmp(&b.agent_name))
+    });
+
+    RunAnalytics {
+        project_id,
+        range,
+        overall: outcome_stats(facts),
+        agents: agent_stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fact(status: RunStatus, minutes: i64, summary: &str) -> RunFact {
+        let started_at = DateTime::parse_from_rfc3339("2026-03-02T09:15:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        RunFact {
+            agent_id: "agent-1".to_string(),
+            status,
+            started_at,
+            ended_at: Some(started_at + chrono::Duration::minutes(minutes)),
+            summary: Some(summary.to_string()),
+            review: None,
+        }
+    }
+
+    #[test]
+    fn outcome_stats_rates_durations_and_categories() {
+        let facts = vec![
+            fact(RunStatus::Completed, 10, "done"),
+            fact(RunStatus::Completed, 20, "done"),
+            fact(RunStatus::Failed, 2, "Cancelled by operator"),
+            fact(RunStatus::NeedsReview, 40, "blocked on credentials"),
+        ];
+        let stats = outcome_stats(&facts);
+        assert_eq!(stats.total_runs, 4);
+        assert_eq!(stats.success_rate, Some(0.5));
+        assert_eq!(stats.median_duration_seconds, Some(15 * 60));
+        assert_eq!(stats.failure_categories[0].category, "cancelled");
+        assert_eq!(stats.busiest_hours[0].hour, 9);
+        assert_eq!(stats.busiest_hours[0].count, 4);
+    }
+}
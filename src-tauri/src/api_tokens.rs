@@ -0,0 +1,173 @@
+use crate::db::Database;
+use crate::models::{ApiToken, ApiTokenScope};
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+// ── API tokens ──────────────────────────────────────────────────────────────
+// Per-integration bearer tokens for the local HTTP endpoints. Each token is
+// read-only or read-write and can be revoked on its own; the database keeps
+// a SHA-256 of the token, never the token.
+
+const TOKEN_PREFIX: &str = "kbn_";
+const DISPLAY_PREFIX_CHARS: usize = 12;
+
+/// A freshly created token. `secret` is only ever returned here.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiAuthError {
+    Unauthorized, // no token, or not one we know
+    Forbidden,    // a valid token without the scope the request needs
+}
+
+impl ApiAuthError {
+    pub fn status(self) -> &'static str {
+        match self {
+            ApiAuthError::Unauthorized => "401 Unauthorized",
+            ApiAuthError::Forbidden => "403 Forbidden",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            ApiAuthError::Unauthorized => "missing or unknown bearer token",
+            ApiAuthError::Forbidden => "token is read-only",
+        }
+    }
+}
+
+pub fn hash_token(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+pub fn create(db: &Database, name: &str, scope: ApiTokenScope) -> Result<CreatedApiToken, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("token name is required".to_string());
+    }
+    let secret = format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let token = ApiToken {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        scope,
+        prefix: secret.chars().take(DISPLAY_PREFIX_CHARS).collect(),
+        created_at: Utc::now(),
+        last_used_at: None,
+        revoked_at: None,
+    };
+    db.create_api_token(&token, &hash_token(&secret))
+        .map_err(|e| e.to_string())?;
+    Ok(CreatedApiToken { token, secret })
+}
+
+pub fn revoke(db: &Database, token_id: &str) -> Result<(), String> {
+    if db
+        .revoke_api_token(token_id, Utc::now())
+        .map_err(|e| e.to_string())?
+    {
+        Ok(())
+    } else {
+        Err(format!("No active API token: {}", token_id))
+    }
+}
+
+/// The bearer token from raw request headers.
+pub fn bearer_token(headers: &str) -> Option<&str> {
+    headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            return None;
+        }
+        value
+            .trim()
+            .strip_prefix("Bearer ")
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+    })
+}
+
+/// Check the request's bearer token against the stored tokens and record
+/// its use.
+pub fn authenticate(
+    db: &Database,
+    headers: &str,
+    required: ApiTokenScope,
+) -> Result<ApiToken, ApiAuthError> {
+    let secret = bearer_token(headers).ok_or(ApiAuthError::Unauthorized)?;
+    let token = db
+        .find_active_api_token(&hash_token(secret))
+        .ok()
+        .flatten()
+        .ok_or(ApiAuthError::Unauthorized)?;
+    if !token.scope.permits(required) {
+        return Err(ApiAuthError::Forbidden);
+    }
+    if let Err(error) = db.touch_api_token(&token.id, Utc::now()) {
+        tracing::debug!("Failed to record use of API token {}: {}", token.id, error);
+    }
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_hashed_scoped_and_revocable() {
+        let db = Database::new(":memory:").expect("db should open");
+        let reader = create(&db, "Grafana", ApiTokenScope::ReadOnly).expect("token should create");
+        let writer = create(&db, "Zapier", ApiTokenScope::ReadWrite).expect("token should create");
+        assert!(reader.secret.starts_with(TOKEN_PREFIX));
+        assert!(reader.secret.starts_with(&reader.token.prefix));
+        assert!(create(&db, "  ", ApiTokenScope::ReadOnly).is_err());
+
+        let headers = |secret: &str| {
+            format!(
+                "POST /intake HTTP/1.1\r\nAuthorization: Bearer {}\r\n",
+                secret
+            )
+        };
+        let read = authenticate(&db, &headers(&reader.secret), ApiTokenScope::ReadOnly)
+            .expect("reader should read");
+        assert_eq!(read.id, reader.token.id);
+        assert_eq!(
+            authenticate(&db, &headers(&reader.secret), ApiTokenScope::ReadWrite).unwrap_err(),
+            ApiAuthError::Forbidden
+        );
+        assert!(authenticate(&db, &headers(&writer.secret), ApiTokenScope::ReadWrite).is_ok());
+        assert_eq!(
+            authenticate(&db, &headers("kbn_guess"), ApiTokenScope::ReadOnly).unwrap_err(),
+            ApiAuthError::Unauthorized
+        );
+        assert_eq!(
+            authenticate(&db, "GET /metrics HTTP/1.1\r\n", ApiTokenScope::ReadOnly).unwrap_err(),
+            ApiAuthError::Unauthorized
+        );
+
+        let listed = db.list_api_tokens().unwrap();
+        assert_eq!(listed.len(), 2);
+        let used = listed
+            .iter()
+            .find(|token| token.id == reader.token.id)
+            .unwrap();
+        assert!(used.last_used_at.is_some());
+
+        revoke(&db, &writer.token.id).expect("token should revoke");
+        assert!(revoke(&db, &writer.token.id).is_err());
+        assert_eq!(
+            authenticate(&db, &headers(&writer.secret), ApiTokenScope::ReadWrite).unwrap_err(),
+            ApiAuthError::Unauthorized
+        );
+        assert!(db.has_active_api_tokens().unwrap());
+    }
+}
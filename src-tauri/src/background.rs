@@ -0,0 +1,135 @@
+use crate::db::{TableWrite, ALL_TABLES};
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+
+// ── Background tasks ────────────────────────────────────────────────────────
+// The listeners that react to what happens in the app (webhooks,
+// notifications, the tray, trace export, the metrics and intake endpoints)
+// share one runtime and sleep until the database reports a write they care
+// about, instead of each polling on its own thread.
+
+/// How long a task waits after the first write before acting, so a burst
+/// of writes (a message and the status change it causes) is handled once.
+pub const SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+static RUNTIME: OnceLock<Option<Runtime>> = OnceLock::new();
+
+fn runtime() -> Option<&'static Runtime> {
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .thread_name("kanbun-background")
+                .enable_all()
+                .build()
+                .map_err(|error| tracing::warn!("Background tasks disabled: {}", error))
+                .ok()
+        })
+        .as_ref()
+}
+
+/// Run `task` on the shared runtime. `name` labels it in the log if the
+/// runtime couldn't be started.
+pub fn spawn<F>(name: &str, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    match runtime() {
+        Some(runtime) => {
+            runtime.spawn(task);
+        }
+        None => tracing::warn!("{} not started: no background runtime", name),
+    }
+}
+
+/// Wait for a write to one of `tables`. Returns false once the database is
+/// gone. Missed events (the receiver lagged) count as a write, since any of
+/// them may have been relevant.
+pub async fn wait_for_write(writes: &mut broadcast::Receiver<TableWrite>, tables: &[&str]) -> bool {
+    loop {
+        match writes.recv().await {
+            Ok(write) if touches(&write, tables) => return true,
+            Ok(_) => {}
+            Err(RecvError::Lagged(_)) => return true,
+            Err(RecvError::Closed) => return false,
+        }
+    }
+}
+
+/// Take whatever else is already queued, after letting a burst settle.
+pub async fn settle(writes: &mut broadcast::Receiver<TableWrite>) {
+    tokio::time::sleep(SETTLE_DELAY).await;
+    while !matches!(
+        writes.try_recv(),
+        Err(TryRecvError::Empty) | Err(TryRecvError::Closed)
+    ) {}
+}
+
+/// Everything queued on `receiver`, oldest first, without waiting. The bool
+/// is true when events were dropped because the receiver fell behind.
+pub fn drain<T: Clone>(receiver: &mut broadcast::Receiver<T>) -> (Vec<T>, bool) {
+    let mut events = Vec::new();
+    let mut lagged = false;
+    loop {
+        match receiver.try_recv() {
+            Ok(event) => events.push(event),
+            Err(TryRecvError::Lagged(_)) => lagged = true,
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return (events, lagged),
+        }
+    }
+}
+
+fn touches(write: &TableWrite, tables: &[&str]) -> bool {
+    write.table == ALL_TABLES || tables.contains(&write.table.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::*;
+
+    #[test]
+    fn waits_for_writes_to_the_tables_asked_for() {
+        let db = Database::new(":memory:").expect("db should open");
+        let mut writes = db.subscribe_writes();
+        let mut changes = db.subscribe_status_changes();
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).unwrap();
+        let agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Idle)
+            .unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Idle)
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        assert!(runtime.block_on(wait_for_write(&mut writes, &["agents"])));
+        let (rest, lagged) = drain(&mut writes);
+        assert!(!lagged);
+        assert!(rest.iter().all(|write| write.table == "agents"));
+
+        // A change that ends where it started is still two changes; writing
+        // the same status again is none.
+        let (changes, _) = drain(&mut changes);
+        let steps: Vec<(AgentStatus, AgentStatus)> = changes
+            .into_iter()
+            .map(|change| (change.from, change.to))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (AgentStatus::Idle, AgentStatus::Blocked),
+                (AgentStatus::Blocked, AgentStatus::Idle)
+            ]
+        );
+    }
+}
@@ -0,0 +1,822 @@
+use crate::commands;
+use crate::connectors::{self, ConnectorItem};
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// ── Board ───────────────────────────────────────────────────────────────────
+// Per-project kanban cards. Assigning a card to an agent sends it as an
+// instruction rendered from the board's template; the run that picks the
+// instruction up is linked back to the card, and the card follows the run
+// from In Progress to Review or Done.
+//
+// Cards linked to a connector item keep its status in step with their
+// column: moves are pushed upstream, and upstream changes move the card on
+// the next sync. `source_status` records the status both sides last agreed
+// on, which tells a local move from an upstream one; a card changed on both
+// sides is flagged as a conflict until someone picks a side.
+//
+// Columns can carry WIP limits, which hold for moves and assignments but
+// not for cards that follow a run or an upstream item. With auto-dispatch
+// on, an agent that has finished its card gets the next Ready card: one
+// assigned to it, or else the next unassigned one.
+
+pub const BOARD_SETTINGS_KEY: &str = "board";
+const MAX_TASK_TITLE_CHARS: usize = 200;
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardSettings {
+    /// Placeholders: {title}, {description}, {project}
+    #[serde(default = "default_instruction_template")]
+    pub instruction_template: String,
+    #[serde(default)]
+    pub wip_limits: HashMap<TaskColumn, u32>, // max cards per column, per project
+    #[serde(default)]
+    pub auto_dispatch: bool,
+}
+
+fn default_instruction_template() -> String {
+    "Work on this card from the {project} board.\n\nTask: {title}\n\n{description}".to_string()
+}
+
+impl Default for BoardSettings {
+    fn default() -> Self {
+        Self {
+            instruction_template: default_instruction_template(),
+            wip_limits: HashMap::new(),
+            auto_dispatch: false,
+        }
+    }
+}
+
+impl BoardSettings {
+    pub fn validate(&self) -> Result<(), CommandError> {
+        if !self.instruction_template.contains("{title}") {
+            return Err(CommandError::invalid_input(
+                "instruction template must include {title}",
+            ));
+        }
+        if let Some((column, _)) = self.wip_limits.iter().find(|(_, limit)| **limit == 0) {
+            return Err(CommandError::invalid_input(format!(
+                "WIP limit for {} must be at least 1",
+                column.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub fn load_settings(db: &Database) -> Result<BoardSettings, CommandError> {
+    db.get_setting::<BoardSettings>(BOARD_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// The instruction an agent receives for `task`. Blank runs left by an
+/// empty description are collapsed.
+pub fn render_instruction(template: &str, task: &Task, project_name: &str) -> String {
+    let rendered = template
+        .replace("{title}", task.title.trim())
+        .replace(
+            "{description}",
+            task.description.as_deref().unwrap_or_default().trim(),
+        )
+        .replace("{project}", project_name);
+    let mut out = String::new();
+    let mut blank_lines = 0;
+    for line in rendered.trim().lines() {
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+fn load_task(db: &Database, task_id: &str) -> Result<Task, CommandError> {
+    db.get_task(task_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Task not found: {}", task_id)))
+}
+
+fn load_project(db: &Database, project_id: &str) -> Result<Project, CommandError> {
+    db.list_projects()?
+        .into_iter()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| CommandError::not_found(format!("Project not found: {}", project_id)))
+}
+
+/// Status of the cached item a card links to; None for unlinked cards.
+fn linked_item_status(
+    db: &Database,
+    source_item: Option<&SourceItemRef>,
+) -> Result<Option<connectors::ItemStatus>, CommandError> {
+    let Some(source) = source_item else {
+        return Ok(None);
+    };
+    let item = db
+        .get_connector_item(&source.connector_id, &source.item_id)?
+        .ok_or_else(|| {
+            CommandError::not_found(format!(
+                "Item '{}' not found in {}",
+                source.item_id, source.connector_id
+            ))
+        })?;
+    Ok(Some(item.status))
+}
+
+/// Create a card at the bottom of its column, or edit an existing one's
+/// text and linked item.
+pub fn save_task(db: &Database, input: TaskInput) -> Result<Task, CommandError> {
+    let title = input.title.trim();
+    if title.is_empty() {
+        return Err(CommandError::invalid_input("Task title cannot be empty"));
+    }
+    if title.chars().count() > MAX_TASK_TITLE_CHARS {
+        return Err(CommandError::invalid_input(format!(
+            "Task title is longer than {} characters",
+            MAX_TASK_TITLE_CHARS
+        )));
+    }
+    load_project(db, &input.project_id)?;
+    let description = input
+        .description
+        .map(|description| description.trim().to_string())
+        .filter(|description| !description.is_empty());
+
+    let existing = match &input.id {
+        Some(id) => db.get_task(id)?,
+        None => None,
+    };
+    let task = match existing {
+        Some(mut task) => {
+            if task.project_id != input.project_id {
+                return Err(CommandError::invalid_input(
+                    "Task does not belong to this project",
+                ));
+            }
+            task.title = title.to_string();
+            task.description = description;
+            if task.source_item != input.source_item {
+                task.source_status = linked_item_status(db, input.source_item.as_ref())?;
+                task.sync_conflict = None;
+                task.source_item = input.source_item;
+            }
+            task.updated_at = Utc::now();
+            task
+        }
+        None => {
+            // A linked card starts in the column matching its item.
+            let source_status = linked_item_status(db, input.source_item.as_ref())?;
+            let column = input.column.unwrap_or_else(|| match &source_status {
+                Some(status) => TaskColumn::for_item_status(status, TaskColumn::Backlog),
+                None => TaskColumn::Backlog,
+            });
+            let mut task = Task::new(&input.project_id, title, column);
+            if let Some(id) = input.id {
+                task.id = id;
+            }
+            task.description = description;
+            task.source_item = input.source_item;
+            task.source_status = source_status;
+            task.position = db
+                .list_tasks(&input.project_id)?
+                .iter()
+                .filter(|other| other.column == column)
+                .count() as u32;
+            task
+        }
+    };
+    db.save_task(&task)?;
+    Ok(task)
+}
+
+/// Refuse to bring `task` into a column that's already at its WIP limit.
+fn check_wip_limit(
+    db: &Database,
+    settings: &BoardSettings,
+    task: &Task,
+    column: TaskColumn,
+) -> Result<(), CommandError> {
+    let Some(limit) = settings.wip_limits.get(&column).copied() else {
+        return Ok(());
+    };
+    if task.column == column {
+        return Ok(());
+    }
+    let count = db
+        .list_tasks(&task.project_id)?
+        .iter()
+        .filter(|other| other.column == column)
+        .count();
+    if count as u32 >= limit {
+        return Err(CommandError::invalid_input(format!(
+            "{} is at its WIP limit of {}",
+            column.as_str(),
+            limit
+        )));
+    }
+    Ok(())
+}
+
+/// Move a card to `position` in `column` (the bottom when omitted),
+/// within the column's WIP limit.
+pub fn move_task(
+    db: &Database,
+    task_id: &str,
+    column: TaskColumn,
+    position: Option<u32>,
+) -> Result<Task, CommandError> {
+    let task = load_task(db, task_id)?;
+    check_wip_limit(db, &load_settings(db)?, &task, column)?;
+    place_task(db, task_id, column, position)
+}
+
+/// `move_task` without the WIP limit, for cards following their item
+/// upstream. Renumbers the columns the card left and entered.
+fn place_task(
+    db: &Database,
+    task_id: &str,
+    column: TaskColumn,
+    position: Option<u32>,
+) -> Result<Task, CommandError> {
+    let task = load_task(db, task_id)?;
+    let tasks = db.list_tasks(&task.project_id)?;
+    let from = task.column;
+    let mut target: Vec<Task> = tasks
+        .iter()
+        .filter(|other| other.column == column && other.id != task.id)
+        .cloned()
+        .collect();
+    let index = position
+        .map(|position| position as usize)
+        .unwrap_or(target.len())
+        .min(target.len());
+    let mut moved = task;
+    moved.column = column;
+    target.insert(index, moved);
+
+    let now = Utc::now();
+    let renumber = |cards: Vec<Task>| -> Result<(), CommandError> {
+        for (position, mut card) in cards.into_iter().enumerate() {
+            let position = position as u32;
+            let original = tasks.iter().find(|other| other.id == card.id);
+            let unchanged = original
+                .is_some_and(|other| other.position == position && other.column == card.column);
+            if !unchanged {
+                card.position = position;
+                card.updated_at = now;
+                db.save_task(&card)?;
+            }
+        }
+        Ok(())
+    };
+    renumber(target)?;
+    if from != column {
+        renumber(
+            tasks
+                .iter()
+                .filter(|other| other.column == from && other.id != task_id)
+                .cloned()
+                .collect(),
+        )?;
+    }
+    load_task(db, task_id)
+}
+
+/// Hand a card to an agent: send the rendered instruction (or `instruction`
+/// in its place) and move the card to In Progress.
+pub fn assign_task(
+    db: &Arc<Database>,
+    task_id: &str,
+    agent_id: &str,
+    instruction: Option<String>,
+) -> Result<Task, CommandError> {
+    let task = load_task(db, task_id)?;
+    let agent = db
+        .get_agent(agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+    if agent.project_id != task.project_id {
+        return Err(CommandError::invalid_input(format!(
+            "{} works on a different project",
+            agent.name
+        )));
+    }
+    let project = load_project(db, &task.project_id)?;
+    let settings = load_settings(db)?;
+    check_wip_limit(db, &settings, &task, TaskColumn::InProgress)?;
+
+    let content = match instruction.filter(|text| !text.trim().is_empty()) {
+        Some(instruction) => format!("{}\n\nTask: {}", instruction.trim(), task.title),
+        None => render_instruction(&settings.instruction_template, &task, &project.name),
+    };
+    let mut msg = Message::to_agent(&agent.id, MessageKind::Instruction, &content);
+    let mut metadata = serde_json::json!({ "task_id": task.id });
+    if let Some(source_item) = &task.source_item {
+        metadata["source_item"] = serde_json::json!(source_item);
+    }
+    msg.metadata = Some(metadata);
+    let msg = commands::enqueue_message(db, msg)?;
+
+    let mut task = place_task(db, task_id, TaskColumn::InProgress, None)?;
+    task.assigned_agent_id = Some(agent.id);
+    task.instruction_message_id = Some(msg.id);
+    task.run_id = None;
+    task.updated_at = Utc::now();
+    db.save_task(&task)?;
+    Ok(task)
+}
+
+/// Push a linked card's column to its item upstream when they disagree.
+/// Conflicted cards wait for `resolve_conflict`.
+pub async fn push_item_status(db: &Database, task: Task) -> Result<Task, CommandError> {
+    let Some(source) = task.source_item.clone() else {
+        return Ok(task);
+    };
+    let wanted = task.column.item_status();
+    if task.sync_conflict.is_some() || task.source_status.as_ref() == Some(&wanted) {
+        return Ok(task);
+    }
+    let config = db
+        .get_connector_config(&source.connector_id)?
+        .ok_or_else(|| format!("Connector '{}' not configured", source.connector_id))?;
+    let mut item = db
+        .get_connector_item(&source.connector_id, &source.item_id)?
+        .ok_or_else(|| {
+            CommandError::not_found(format!(
+                "Item '{}' not found in {}",
+                source.item_id, source.connector_id
+            ))
+        })?;
+    item.status = wanted.clone();
+    let connector = connectors::create_connector(&config)?;
+    let updated = connector.update(&item).await?;
+    db.upsert_connector_items(&source.connector_id, &[updated])?;
+
+    let mut task = load_task(db, &task.id)?;
+    task.source_status = Some(wanted);
+    task.updated_at = Utc::now();
+    db.save_task(&task)?;
+    Ok(task)
+}
+
+/// Bring linked cards in line with freshly pulled items. Cards follow items
+/// that changed upstream; cards moved locally since the last agreement are
+/// returned for the caller to push; cards changed on both sides are flagged.
+/// Cards whose item wasn't in the pull are left alone.
+pub fn reconcile_items(
+    db: &Database,
+    connector_id: &str,
+    items: &[ConnectorItem],
+) -> Result<Vec<Task>, CommandError> {
+    let mut to_push = Vec::new();
+    for mut task in db.list_tasks_for_connector(connector_id)? {
+        let Some(item) = task
+            .source_item
+            .as_ref()
+            .and_then(|source| items.iter().find(|item| item.id == source.item_id))
+        else {
+            continue;
+        };
+        let remote = item.status.clone();
+        let local = task.column.item_status();
+        let base = task.source_status.clone();
+
+        if remote == local {
+            if base.as_ref() != Some(&remote) || task.sync_conflict.is_some() {
+                task.source_status = Some(remote);
+                task.sync_conflict = None;
+                task.updated_at = Utc::now();
+                db.save_task(&task)?;
+            }
+        } else if base.as_ref() == Some(&remote) {
+            if task.sync_conflict.is_none() {
+                to_push.push(task);
+            }
+        } else if base.is_none() || base.as_ref() == Some(&local) {
+            let column = TaskColumn::for_item_status(&remote, task.column);
+            let mut moved = place_task(db, &task.id, column, None)?;
+            moved.source_status = Some(remote);
+            moved.sync_conflict = None;
+            db.save_task(&moved)?;
+        } else if task.sync_conflict.as_ref() != Some(&remote) {
+            task.sync_conflict = Some(remote);
+            task.updated_at = Utc::now();
+            db.save_task(&task)?;
+        }
+    }
+    Ok(to_push)
+}
+
+/// Settle a conflicted card. Keeping upstream moves the card to match its
+/// item; keeping the board leaves the card and returns it ready to push.
+pub fn resolve_conflict(
+    db: &Database,
+    task_id: &str,
+    keep_local: bool,
+) -> Result<Task, CommandError> {
+    let task = load_task(db, task_id)?;
+    let Some(remote) = task.sync_conflict.clone() else {
+        return Ok(task);
+    };
+    let mut task = if keep_local {
+        task
+    } else {
+        let column = TaskColumn::for_item_status(&remote, task.column);
+        place_task(db, task_id, column, None)?
+    };
+    task.source_status = Some(remote);
+    task.sync_conflict = None;
+    task.updated_at = Utc::now();
+    db.save_task(&task)?;
+    Ok(task)
+}
+
+/// Link assigned cards to the runs their instructions started and move
+/// cards still in the agent's hands (In Progress, Review) along with them.
+fn follow_runs(db: &Database, tasks: &mut [Task]) -> Result<(), CommandError> {
+    for index in 0..tasks.len() {
+        let Some(message_id) = tasks[index].instruction_message_id.clone() else {
+            continue;
+        };
+        let Some(run) = db.get_run_for_instruction(&message_id)? else {
+            continue;
+        };
+        let mut changed = tasks[index].run_id.as_deref() != Some(run.id.as_str());
+        let target = TaskColumn::for_run_status(&run.status);
+        let agent_owned = matches!(
+            tasks[index].column,
+            TaskColumn::InProgress | TaskColumn::Review
+        );
+        if let Some(target) = target.filter(|target| agent_owned && *target != tasks[index].column)
+        {
+            let bottom = tasks
+                .iter()
+                .filter(|other| other.column == target)
+                .map(|other| other.position + 1)
+                .max()
+                .unwrap_or(0);
+            tasks[index].column = target;
+            tasks[index].position = bottom;
+            changed = true;
+        }
+        let task = &mut tasks[index];
+        task.run_id = Some(run.id);
+        task.run_status = Some(run.status);
+        if changed {
+            task.updated_at = Utc::now();
+            db.save_task(task)?;
+        }
+    }
+    Ok(())
+}
+
+/// A project's cards in board order, with run links brought up to date.
+pub fn load_board(db: &Database, project_id: &str) -> Result<Vec<Task>, CommandError> {
+    let mut tasks = db.list_tasks(project_id)?;
+    follow_runs(db, &mut tasks)?;
+    tasks.sort_by_key(|task| {
+        (
+            TaskColumn::ALL
+                .iter()
+                .position(|column| *column == task.column),
+            task.position,
+        )
+    });
+    Ok(tasks)
+}
+
+/// Auto-dispatch: give each agent that has worked the board and has no
+/// card in progress the next Ready card it may take. Cards assigned to an
+/// agent wait for that agent; unassigned ones go to whichever is idle.
+pub fn dispatch_ready(db: &Arc<Database>) -> Result<usize, CommandError> {
+    let settings = load_settings(db)?;
+    if !settings.auto_dispatch {
+        return Ok(0);
+    }
+    let in_progress_limit = settings.wip_limits.get(&TaskColumn::InProgress).copied();
+    let agents = db.list_agents()?;
+
+    let mut sent = 0;
+    for project in db.list_projects()? {
+        let board = load_board(db, &project.id)?;
+        let workers: HashSet<&str> = board
+            .iter()
+            .filter_map(|task| task.assigned_agent_id.as_deref())
+            .collect();
+        let busy: HashSet<&str> = board
+            .iter()
+            .filter(|task| task.column == TaskColumn::InProgress)
+            .filter_map(|task| task.assigned_agent_id.as_deref())
+            .collect();
+        let mut in_progress = board
+            .iter()
+            .filter(|task| task.column == TaskColumn::InProgress)
+            .count() as u32;
+        let mut idle: Vec<&str> = agents
+            .iter()
+            .filter(|agent| agent.project_id == project.id)
+            .map(|agent| agent.id.as_str())
+            .filter(|agent_id| workers.contains(agent_id) && !busy.contains(agent_id))
+            .collect();
+
+        for task in board.iter().filter(|task| task.column == TaskColumn::Ready) {
+            if idle.is_empty() || in_progress_limit.is_some_and(|limit| in_progress >= limit) {
+                break;
+            }
+            let agent_id = match task.assigned_agent_id.as_deref() {
+                Some(agent_id) if idle.contains(&agent_id) => agent_id,
+                Some(_) => continue,
+                None => idle[0],
+            };
+            assign_task(db, &task.id, agent_id, None)?;
+            idle.retain(|idle_id| *idle_id != agent_id);
+            in_progress += 1;
+            sent += 1;
+        }
+    }
+    Ok(sent)
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        match dispatch_ready(&db) {
+            Ok(0) => {}
+            Ok(sent) => tracing::info!("Board dispatched {} card(s)", sent),
+            Err(error) => tracing::warn!("Board dispatch failed: {}", error),
+        }
+        std::thread::sleep(TICK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(project_id: &str, title: &str) -> TaskInput {
+        TaskInput {
+            id: None,
+            project_id: project_id.to_string(),
+            title: title.to_string(),
+            description: None,
+            column: None,
+            source_item: None,
+        }
+    }
+
+    #[test]
+    fn cards_move_and_follow_the_run_of_their_agent() {
+        let db = Arc::new(Database::new(":memory:").expect("db should open"));
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Api, "docs");
+        db.create_agent(&agent).expect("agent should save");
+
+        let first = save_task(&db, input(&project.id, "Fix typo")).unwrap();
+        let second = save_task(&db, input(&project.id, "Write changelog")).unwrap();
+        let third = save_task(
+            &db,
+            TaskInput {
+                description: Some("Cover the new API".to_string()),
+                ..input(&project.id, "Update docs")
+            },
+        )
+        .unwrap();
+        assert_eq!((first.position, second.position, third.position), (0, 1, 2));
+        assert!(save_task(&db, input(&project.id, "  ")).is_err());
+        assert!(save_task(&db, input("missing", "Orphan")).is_err());
+
+        let moved = move_task(&db, &third.id, TaskColumn::Backlog, Some(0)).unwrap();
+        assert_eq!(moved.position, 0);
+        let order: Vec<String> = load_board(&db, &project.id)
+            .unwrap()
+            .into_iter()
+            .map(|task| task.title)
+            .collect();
+        assert_eq!(order, ["Update docs", "Fix typo", "Write changelog"]);
+
+        move_task(&db, &first.id, TaskColumn::Ready, None).unwrap();
+        let backlog: Vec<u32> = load_board(&db, &project.id)
+            .unwrap()
+            .into_iter()
+            .filter(|task| task.column == TaskColumn::Backlog)
+            .map(|task| task.position)
+            .collect();
+        assert_eq!(backlog, [0, 1]);
+
+        let assigned = assign_task(&db, &third.id, &agent.id, None).unwrap();
+        assert_eq!(assigned.column, TaskColumn::InProgress);
+        assert_eq!(
+            assigned.assigned_agent_id.as_deref(),
+            Some(agent.id.as_str())
+        );
+        let pending = db.get_pending_messages(&agent.id).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            pending[0].content,
+            "Work on this card from the Site board.\n\nTask: Update docs\n\nCover the new API"
+        );
+        assert_eq!(
+            pending[0].metadata.as_ref().unwrap()["task_id"],
+            assigned.id.as_str()
+        );
+
+        // The run picking up the instruction is linked, and the card follows it.
+        let run = db
+            .start_instruction_run(&agent.id, Some(&pending[0].id), &pending[0].content)
+            .unwrap();
+        let board = load_board(&db, &project.id).unwrap();
+        let card = board.iter().find(|task| task.id == third.id).unwrap();
+        assert_eq!(card.run_id.as_deref(), Some(run.id.as_str()));
+        assert_eq!(card.run_status, Some(RunStatus::InProgress));
+
+        let mut finished = db.get_run(&run.id).unwrap().unwrap();
+        finished.status = RunStatus::Completed;
+        finished.ended_at = Some(Utc::now());
+        db.update_run(&finished).unwrap();
+        let board = load_board(&db, &project.id).unwrap();
+        let card = board.iter().find(|task| task.id == third.id).unwrap();
+        assert_eq!(card.column, TaskColumn::Done);
+        assert_eq!(
+            db.get_task(&third.id).unwrap().unwrap().column,
+            TaskColumn::Done
+        );
+
+        let elsewhere = Project::new("Other", "#111111");
+        db.create_project(&elsewhere).unwrap();
+        let stranger = Agent::new("Ops", &elsewhere.id, AgentKind::Api, "ops");
+        db.create_agent(&stranger).unwrap();
+        assert!(assign_task(&db, &first.id, &stranger.id, None).is_err());
+
+        let no_description = Task::new(&project.id, "Ship it", TaskColumn::Ready);
+        assert_eq!(
+            render_instruction(
+                "Do {title}\n\n{description}\n\nThanks",
+                &no_description,
+                "Site"
+            ),
+            "Do Ship it\n\nThanks"
+        );
+        assert!(BoardSettings {
+            instruction_template: "Just do it".to_string(),
+            ..BoardSettings::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    fn todoist_item(id: &str, status: connectors::ItemStatus) -> ConnectorItem {
+        ConnectorItem {
+            id: id.to_string(),
+            source: "todoist".to_string(),
+            title: "Renew domain".to_string(),
+            content: None,
+            status,
+            priority: None,
+            tags: vec![],
+            url: None,
+            parent_id: None,
+            metadata: Default::default(),
+            created_at: None,
+            updated_at: None,
+            due_at: None,
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn linked_cards_follow_upstream_and_flag_conflicts() {
+        use connectors::ItemStatus;
+
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        db.save_connector_config(&connectors::ConnectorConfig {
+            connector_type: "todoist".to_string(),
+            auth_token: Some("token".to_string()),
+            settings: Default::default(),
+            enabled: true,
+        })
+        .unwrap();
+        db.upsert_connector_items("todoist", &[todoist_item("t1", ItemStatus::InProgress)])
+            .unwrap();
+        let card = save_task(
+            &db,
+            TaskInput {
+                source_item: Some(SourceItemRef {
+                    connector_id: "todoist".to_string(),
+                    item_id: "t1".to_string(),
+                }),
+                ..input(&project.id, "Renew domain")
+            },
+        )
+        .unwrap();
+        assert_eq!(card.column, TaskColumn::InProgress);
+        assert_eq!(card.source_status, Some(ItemStatus::InProgress));
+
+        // Completed upstream: the card follows.
+        let pulled = [todoist_item("t1", ItemStatus::Completed)];
+        assert!(reconcile_items(&db, "todoist", &pulled).unwrap().is_empty());
+        let card = db.get_task(&card.id).unwrap().unwrap();
+        assert_eq!(card.column, TaskColumn::Done);
+        assert_eq!(card.source_status, Some(ItemStatus::Completed));
+
+        // Moved on the board only: returned for pushing.
+        move_task(&db, &card.id, TaskColumn::Review, None).unwrap();
+        let to_push = reconcile_items(&db, "todoist", &pulled).unwrap();
+        assert_eq!(to_push.len(), 1);
+
+        // Changed on both sides: flagged, and left where it is.
+        let reopened = [todoist_item("t1", ItemStatus::Active)];
+        assert!(reconcile_items(&db, "todoist", &reopened)
+            .unwrap()
+            .is_empty());
+        let card = db.get_task(&card.id).unwrap().unwrap();
+        assert_eq!(card.column, TaskColumn::Review);
+        assert_eq!(card.sync_conflict, Some(ItemStatus::Active));
+
+        let resolved = resolve_conflict(&db, &card.id, false).unwrap();
+        assert_eq!(resolved.column, TaskColumn::Backlog);
+        assert_eq!(resolved.source_status, Some(ItemStatus::Active));
+        assert_eq!(resolved.sync_conflict, None);
+    }
+
+    #[test]
+    fn wip_limits_hold_and_idle_agents_take_the_next_ready_card() {
+        let db = Arc::new(Database::new(":memory:").expect("db should open"));
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Api, "docs");
+        db.create_agent(&agent).expect("agent should save");
+        db.set_setting(
+            BOARD_SETTINGS_KEY,
+            &BoardSettings {
+                wip_limits: HashMap::from([(TaskColumn::Ready, 2)]),
+                ..BoardSettings::default()
+            },
+        )
+        .unwrap();
+
+        let ready = |title: &str| {
+            save_task(
+                &db,
+                TaskInput {
+                    column: Some(TaskColumn::Ready),
+                    ..input(&project.id, title)
+                },
+            )
+            .unwrap()
+        };
+        let first = ready("First");
+        let second = ready("Second");
+        let third = save_task(&db, input(&project.id, "Third")).unwrap();
+        assert!(move_task(&db, &third.id, TaskColumn::Ready, None)
+            .unwrap_err()
+            .message
+            .contains("WIP limit"));
+        // Reordering within a full column is fine.
+        move_task(&db, &second.id, TaskColumn::Ready, Some(0)).unwrap();
+
+        // Off until enabled.
+        assert_eq!(dispatch_ready(&db).unwrap(), 0);
+        db.set_setting(
+            BOARD_SETTINGS_KEY,
+            &BoardSettings {
+                auto_dispatch: true,
+                ..BoardSettings::default()
+            },
+        )
+        .unwrap();
+        // Only agents that have worked the board are fed from it.
+        assert_eq!(dispatch_ready(&db).unwrap(), 0);
+
+        assign_task(&db, &third.id, &agent.id, None).unwrap();
+        assert_eq!(dispatch_ready(&db).unwrap(), 0); // still busy
+        let message = db.get_pending_messages(&agent.id).unwrap().remove(0);
+        let run = db
+            .start_instruction_run(&agent.id, Some(&message.id), &message.content)
+            .unwrap();
+        let mut finished = db.get_run(&run.id).unwrap().unwrap();
+        finished.status = RunStatus::NeedsReview;
+        db.update_run(&finished).unwrap();
+
+        assert_eq!(dispatch_ready(&db).unwrap(), 1);
+        let board = load_board(&db, &project.id).unwrap();
+        let next = board.iter().find(|task| task.id == second.id).unwrap();
+        assert_eq!(next.column, TaskColumn::InProgress);
+        assert_eq!(next.assigned_agent_id.as_deref(), Some(agent.id.as_str()));
+        let waiting = board.iter().find(|task| task.id == first.id).unwrap();
+        assert_eq!(waiting.column, TaskColumn::Ready);
+        assert_eq!(dispatch_ready(&db).unwrap(), 0);
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// ── Clock ───────────────────────────────────────────────────────────────────
+// Where core modules get the time. The database carries one and hands it to
+// whatever times things against it (run and message timestamps, adapter
+// retry backoff, TTL and idle expiry), so tests can swap in a ManualClock
+// and step through hours of retries without sleeping.
+
+pub trait Clock: Send + Sync {
+    /// Wall-clock time, for anything stored or shown.
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Monotonic time, for deadlines held in memory.
+    fn instant(&self) -> Instant;
+}
+
+/// The real time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to. Both readings advance together.
+pub struct ManualClock {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl ManualClock {
+    /// Frozen at `at` until advanced.
+    pub fn new(at: DateTime<Utc>) -> Self {
+        Self {
+            started_at: at,
+            started: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock().unwrap() += by;
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.started_at
+            + chrono::Duration::from_std(self.elapsed()).unwrap_or(chrono::Duration::MAX)
+    }
+
+    fn instant(&self) -> Instant {
+        self.started + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_moves_only_when_advanced() {
+        let at = DateTime::parse_from_rfc3339("2026-03-01T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let clock = ManualClock::new(at);
+        let instant = clock.instant();
+        assert_eq!(clock.now(), at);
+        assert_eq!(clock.instant(), instant);
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(clock.now(), at + chrono::Duration::seconds(90));
+        assert_eq!(clock.instant() - instant, Duration::from_secs(90));
+    }
+}
@@ -1,13 +1,62 @@
-use chrono::Utc;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+use tracing::Instrument;
 
-use crate::agents;
-use crate::connectors::{self, ConnectorConfig, ConnectorInfo, ConnectorItem};
+use crate::activity::{self, ActivityEntry, ActivityFilters};
+use crate::agenda::{
+    self, AgendaEntry, AgendaRange, CalendarExportResult, CalendarFeedSettings,
+    CALENDAR_FEED_SETTINGS_KEY,
+};
+use crate::agent_bundle::{self, BundleExportResult, BundleImport};
+use crate::agent_logs::{self, AgentLogTail};
+use crate::agents::{
+    self,
+    simulation::{self, RecordedTranscript},
+    validate::{self, ConfigValidation},
+};
+use crate::analytics::{self, AnalyticsRange, RunAnalytics};
+use crate::api_tokens::{self, CreatedApiToken};
+use crate::backup::{
+    self, CloudBackup, CloudBackupResult, CloudBackupSettings, CLOUD_BACKUP_SETTINGS_KEY,
+};
+use crate::board::{self, BoardSettings, BOARD_SETTINGS_KEY};
+use crate::connectors::{self, ConnectorConfig, ConnectorInfo, ConnectorItem, ConnectorItemDetail};
+use crate::context_docs::{self, ContextDocImport, ContextDocTemplate, DocDiff};
+use crate::daily_stats::{self, DailyStats, StatsRange};
+use crate::dashboard_cache::DashboardCache;
 use crate::db::Database;
+use crate::deep_link::{DeepLinkSettings, DEEP_LINK_SETTINGS_KEY};
+use crate::diagnostics;
+use crate::digest::{self, DigestSettings, ProjectDigest, DIGEST_SETTINGS_KEY};
+use crate::errors::CommandError;
+use crate::export::{self, RunReport, TranscriptExportResult, TranscriptFormat, TranscriptRange};
+use crate::github::{self, GithubSettings, GITHUB_SETTINGS_KEY};
+use crate::intake::{self, CaptureResult, IntakeSettings, INTAKE_SETTINGS_KEY};
+use crate::metrics::{self, MetricsSettings, METRICS_SETTINGS_KEY};
 use crate::models::*;
+use crate::notifications::{
+    self,
+    chat::{self, ChatSettings, CHAT_SETTINGS_KEY},
+    push::{self, PushSettings, PUSH_SETTINGS_KEY},
+};
+use crate::pipelines::{self, Pipeline, PipelineInput, PipelineLinkStatus, PIPELINES_KEY};
+use crate::project_bundle::{self, ProjectExportResult, ProjectImport};
+use crate::redaction::{self, RedactionSettings};
+use crate::routing::{self, RoutedInstruction};
+use crate::run_archive::{self, ArchivedRun, RunArchiveSettings, RUN_ARCHIVE_SETTINGS_KEY};
+use crate::seed::{self, DemoWorkspace};
+use crate::snapshot_crypto;
+use crate::summarize::{self, SummarizerSettings, SUMMARIZER_SETTINGS_KEY};
+use crate::timezone::{TimezoneSettings, UserTimezone, TIMEZONE_SETTINGS_KEY};
+use crate::traces::{self, TraceEvent, TracingSettings, TRACING_SETTINGS_KEY};
+use crate::watchdog::{self, WatchdogSettings, WATCHDOG_SETTINGS_KEY};
+use crate::watchers;
+use crate::webhooks;
+use crate::workdir::{self, WorkingDirectory};
 
 #[derive(Debug, Clone, Default)]
 struct AdapterRuntimeState {
@@ -16,13 +65,19 @@ struct AdapterRuntimeState {
     next_retry_at: Option<Instant>,
     last_error: Option<String>,
     last_failure_at: Option<chrono::DateTime<chrono::Utc>>,
+    failing_since: Option<chrono::DateTime<chrono::Utc>>, // first failure of the current streak
     last_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Result of the supervisor's last live check, for the fleet overview.
+    connected: bool,
+    last_heartbeat: Option<String>,
+    last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    idle_stopped: bool, // stopped for inactivity; only a new message restarts it
 }
 
 impl AdapterRuntimeState {
-    fn retry_after_seconds(&self) -> Option<u64> {
+    fn retry_after_seconds(&self, now: Instant) -> Option<u64> {
         self.next_retry_at.map(|retry_at| {
-            let remaining = retry_at.saturating_duration_since(Instant::now());
+            let remaining = retry_at.saturating_duration_since(now);
             remaining.as_secs()
         })
     }
@@ -47,12 +102,29 @@ fn clear_adapter_runtime(agent_id: &str) {
     }
 }
 
+/// Adapters whose most recent start attempt failed.
+pub(crate) fn failing_adapter_count() -> usize {
+    adapter_runtime()
+        .lock()
+        .map(|runtime| {
+            runtime
+                .values()
+                .filter(|state| state.consecutive_failures > 0)
+                .count()
+        })
+        .unwrap_or(0)
+}
+
 fn clear_all_adapter_runtime() {
     if let Ok(mut runtime) = adapter_runtime().lock() {
         runtime.clear();
     }
 }
 
+/// Failed starts in a row after which the adapter counts as down: its run
+/// is failed and it is raised as an `adapter_down` attention item.
+const SUSTAINED_ADAPTER_FAILURES: u32 = 3;
+
 fn adapter_retry_backoff(failure_count: u32) -> Duration {
     let exponent = failure_count.saturating_sub(1).min(5);
     Duration::from_secs((1_u64 << exponent) * 2)
@@ -63,19 +135,25 @@ fn record_adapter_start_failure(
     agent_id: &str,
     reason: &str,
 ) -> Result<String, String> {
+    let clock = db.clock();
     let (consecutive_failures, retry_after) = {
         let mut runtime = adapter_runtime()
             .lock()
             .map_err(|_| "adapter runtime lock poisoned".to_string())?;
         let state = runtime.entry(agent_id.to_string()).or_default();
         state.started = false;
+        state.connected = false;
+        if state.consecutive_failures == 0 {
+            state.failing_since = Some(clock.now());
+        }
         state.consecutive_failures = state.consecutive_failures.saturating_add(1);
         let backoff = adapter_retry_backoff(state.consecutive_failures);
-        state.next_retry_at = Some(Instant::now() + backoff);
+        state.next_retry_at = Some(clock.instant() + backoff);
         state.last_error = Some(reason.to_string());
-        state.last_failure_at = Some(Utc::now());
+        state.last_failure_at = Some(clock.now());
         (state.consecutive_failures, backoff.as_secs())
     };
+    metrics::increment("kanbun_adapter_start_failures_total", &[]);
 
     let summary = format!(
         "Adapter unavailable: {}. Auto-retry in {}s (attempt {}).",
@@ -94,7 +172,7 @@ fn record_adapter_start_failure(
     if let Ok(Some(run)) = db.get_latest_run_for_agent(agent_id) {
         if run.status == RunStatus::InProgress && run.ended_at.is_none() {
             let _ = db.append_run_output(agent_id, "adapter_error", &summary);
-            if consecutive_failures >= 3 {
+            if consecutive_failures >= SUSTAINED_ADAPTER_FAILURES {
                 let _ = db.finalize_latest_run(
                     agent_id,
                     RunStatus::Failed,
@@ -122,15 +200,31 @@ fn ensure_adapter_started(db: &Arc<Database>, agent_id: &str, force: bool) -> Re
             .map_err(|_| "adapter runtime lock poisoned".to_string())?;
         let state = runtime.entry(agent_id.to_string()).or_default();
 
+        if state.idle_stopped && !force {
+            return Ok(());
+        }
+
         if state.started {
-            match adapter.health_check(agent_id) {
+            let checked = adapter.health_check(agent_id);
+            state.last_checked_at = Some(db.now());
+            state.connected = checked
+                .as_ref()
+                .is_ok_and(|health| health.connected || health.session_active);
+            if let Some(heartbeat) = checked
+                .as_ref()
+                .ok()
+                .and_then(|health| health.last_heartbeat.clone())
+            {
+                state.last_heartbeat = Some(heartbeat);
+            }
+            match checked {
                 Ok(health) if health.connected || health.session_active => return Ok(()),
                 Ok(health) if !force && health.suppress_auto_restart.unwrap_or(false) => {
                     state.last_error = health.last_error.clone();
                     return Ok(());
                 }
                 Ok(_) => {
-                    log::warn!(
+                    tracing::warn!(
                         "Adapter for {} was marked started but is unhealthy; restarting",
                         agent_id
                     );
@@ -139,7 +233,7 @@ fn ensure_adapter_started(db: &Arc<Database>, agent_id: &str, force: bool) -> Re
                         Some("health check reported disconnected adapter".to_string());
                 }
                 Err(error) => {
-                    log::warn!("Adapter health check failed for {}: {}", agent_id, error);
+                    tracing::warn!("Adapter health check failed for {}: {}", agent_id, error);
                     state.started = false;
                     state.last_error = Some(format!("health check failed: {}", error));
                 }
@@ -148,7 +242,7 @@ fn ensure_adapter_started(db: &Arc<Database>, agent_id: &str, force: bool) -> Re
 
         if !force {
             if let Some(retry_at) = state.next_retry_at {
-                if Instant::now() < retry_at {
+                if db.clock().instant() < retry_at {
                     return Ok(());
                 }
             }
@@ -163,27 +257,27 @@ fn ensure_adapter_started(db: &Arc<Database>, agent_id: &str, force: bool) -> Re
                     .map_err(|_| "adapter runtime lock poisoned".to_string())?;
                 let state = runtime.entry(agent_id.to_string()).or_default();
                 state.started = true;
+                state.connected = true;
+                state.idle_stopped = false;
                 state.consecutive_failures = 0;
                 state.next_retry_at = None;
                 state.last_error = None;
                 state.last_failure_at = None;
-                state.last_started_at = Some(Utc::now());
+                state.failing_since = None;
+                state.last_started_at = Some(db.now());
             }
 
-            if let Ok(agents) = db.list_agents() {
-                if let Some(agent) = agents.into_iter().find(|agent| agent.id == agent_id) {
-                    if agent.status == AgentStatus::Errored {
-                        let next_status = match db.get_latest_run_for_agent(agent_id) {
-                            Ok(Some(run))
-                                if run.status == RunStatus::InProgress
-                                    && run.ended_at.is_none() =>
-                            {
-                                AgentStatus::Running
-                            }
-                            _ => AgentStatus::Idle,
-                        };
-                        let _ = db.update_agent_status(agent_id, &next_status);
-                    }
+            if let Ok(Some(agent)) = db.get_agent(agent_id) {
+                if agent.status == AgentStatus::Errored {
+                    let next_status = match db.get_latest_run_for_agent(agent_id) {
+                        Ok(Some(run))
+                            if run.status == RunStatus::InProgress && run.ended_at.is_none() =>
+                        {
+                            AgentStatus::Running
+                        }
+                        _ => AgentStatus::Idle,
+                    };
+                    let _ = db.update_agent_status(agent_id, &next_status);
                 }
             }
 
@@ -197,25 +291,423 @@ fn ensure_adapter_started(db: &Arc<Database>, agent_id: &str, force: bool) -> Re
     }
 }
 
-// ── Dashboard ───────────────────────────────────────────────────────────────
+const ADAPTER_SUPERVISOR_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Stop a running adapter whose agent has been quiet longer than its idle
+/// timeout, mark the agent Idle, and record why. The supervisor leaves it
+/// stopped until a new message is sent. Returns true if it was stopped.
+fn stop_if_idle(db: &Arc<Database>, agent: &Agent, now: DateTime<Utc>) -> bool {
+    let Some(timeout_minutes) = agent.config.idle_timeout_minutes else {
+        return false;
+    };
+    let Some(state) = adapter_runtime_snapshot(&agent.id) else {
+        return false;
+    };
+    if !state.started || state.idle_stopped {
+        return false;
+    }
+    let last_activity = agent.last_active_at.max(state.last_started_at);
+    if last_activity.is_some_and(|at| now - at < chrono::Duration::minutes(timeout_minutes as i64))
+    {
+        return false;
+    }
+    if db
+        .get_pending_messages(&agent.id)
+        .map(|pending| !pending.is_empty())
+        .unwrap_or(true)
+    {
+        return false;
+    }
+    let Ok(Some(config)) = db.get_adapter_config(&agent.id) else {
+        return false;
+    };
+
+    if let Err(error) = agents::create_adapter(&config).stop(&agent.id) {
+        tracing::warn!("Failed to stop idle adapter for {}: {}", agent.id, error);
+        return false;
+    }
+    if let Ok(mut runtime) = adapter_runtime().lock() {
+        let state = runtime.entry(agent.id.clone()).or_default();
+        state.started = false;
+        state.connected = false;
+        state.idle_stopped = true;
+    }
+    let _ = db.update_agent_status(&agent.id, &AgentStatus::Idle);
+
+    let mut status = Message::from_agent(
+        &agent.id,
+        MessageKind::StatusUpdate,
+        &format!(
+            "Adapter stopped after {} minute(s) without activity",
+            timeout_minutes
+        ),
+    );
+    status.metadata = Some(serde_json::json!({
+        "source": "idle_timeout",
+        "idle_timeout_minutes": timeout_minutes,
+    }));
+    if let Err(error) = db.insert_message(&status) {
+        tracing::warn!("Failed to record idle stop for {}: {}", agent.id, error);
+    }
+    true
+}
+
+/// Start every configured adapter once, respecting retry cooldowns. Agents
+/// silenced by `pause_all_agents` are skipped until resumed.
+fn supervise_adapters(db: &Arc<Database>) {
+    let agents = match db.list_agents() {
+        Ok(agents) => agents,
+        Err(error) => {
+            tracing::warn!("Failed to list agents for adapter supervisor: {}", error);
+            return;
+        }
+    };
+    let paused: HashSet<String> = load_paused_agents(db)
+        .unwrap_or_else(|error| {
+            tracing::warn!("Failed to load paused agents: {}", error);
+            vec![]
+        })
+        .into_iter()
+        .map(|paused| paused.agent_id)
+        .collect();
+    let now = db.now();
+    for agent in agents.iter().filter(|agent| !paused.contains(&agent.id)) {
+        if stop_if_idle(db, agent, now) {
+            continue;
+        }
+        if let Err(error) = ensure_adapter_started(db, &agent.id, false) {
+            tracing::warn!("Failed to start adapter for {}: {}", agent.id, error);
+        }
+    }
+}
+
+/// The parts of an adapter's cached health whose change is worth an event.
+type HealthKey = (bool, u32, Option<String>);
+
+/// Events for adapters whose connection, failure count or last error changed
+/// since `last_seen`, which is updated to match.
+fn health_changes(
+    runtime: &HashMap<String, AdapterRuntimeState>,
+    last_seen: &mut HashMap<String, HealthKey>,
+    now: Instant,
+) -> Vec<agents::AdapterHealthEvent> {
+    last_seen.retain(|agent_id, _| runtime.contains_key(agent_id));
+    let mut events = vec![];
+    for (agent_id, state) in runtime {
+        let connected = state.started && state.connected;
+        let key = (
+            connected,
+            state.consecutive_failures,
+            state.last_error.clone(),
+        );
+        if last_seen.get(agent_id) == Some(&key) {
+            continue;
+        }
+        last_seen.insert(agent_id.clone(), key);
+        events.push(agents::AdapterHealthEvent {
+            agent_id: agent_id.clone(),
+            health: agents::AdapterHealth {
+                connected,
+                session_active: connected,
+                last_heartbeat: state.last_heartbeat.clone(),
+                details: None,
+                retry_after_seconds: state.retry_after_seconds(now),
+                consecutive_failures: (state.consecutive_failures > 0)
+                    .then_some(state.consecutive_failures),
+                last_error: state.last_error.clone(),
+                suppress_auto_restart: None,
+            },
+        });
+    }
+    events
+}
+
+/// Keep adapter loops running in the background: bootstraps them after an
+/// app restart, before any message is sent, and restarts ones that drop.
+/// Health changes seen along the way go to the frontend as events.
+pub fn spawn_adapter_supervisor(db: Arc<Database>, app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_seen = HashMap::new();
+        loop {
+            supervise_adapters(&db);
+            let events = adapter_runtime()
+                .lock()
+                .map(|runtime| health_changes(&runtime, &mut last_seen, db.clock().instant()))
+                .unwrap_or_default();
+            for event in events {
+                if let Err(error) = app.emit(agents::ADAPTER_HEALTH_EVENT, event) {
+                    tracing::warn!("Failed to emit adapter health event: {}", error);
+                }
+            }
+            std::thread::sleep(ADAPTER_SUPERVISOR_INTERVAL);
+        }
+    });
+}
+
+// ── Pause all ───────────────────────────────────────────────────────────────
+
+const PAUSED_AGENTS_KEY: &str = "paused_agents";
+
+/// An agent silenced by `pause_all_agents`. Only agents that were running
+/// get a Pause message, and only those get a Resume back.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PausedAgent {
+    pub agent_id: String,
+    pub was_running: bool,
+    pub paused_at: DateTime<Utc>,
+}
+
+pub(crate) fn load_paused_agents(db: &Database) -> Result<Vec<PausedAgent>, String> {
+    db.get_setting::<Vec<PausedAgent>>(PAUSED_AGENTS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_paused_agents(db: State<'_, Arc<Database>>) -> Result<Vec<PausedAgent>, CommandError> {
+    load_paused_agents(db.inner()).map_err(CommandError::from)
+}
+
+/// Pause every agent (or every agent in a project): running ones get a
+/// Pause message, and no adapter is auto-restarted until resumed.
+#[tauri::command]
+pub fn pause_all_agents(
+    db: State<'_, Arc<Database>>,
+    project_id: Option<String>,
+) -> Result<Vec<PausedAgent>, CommandError> {
+    pause_agents(db.inner(), project_id.as_deref()).map_err(CommandError::from)
+}
 
+/// Undo `pause_all_agents` for everything (or one project's agents).
 #[tauri::command]
-pub fn get_dashboard(db: State<'_, Arc<Database>>) -> Result<DashboardView, String> {
-    let projects = db.list_projects().map_err(|e| e.to_string())?;
-    let agents = db.list_agents().map_err(|e| e.to_string())?;
+pub fn resume_all_agents(
+    db: State<'_, Arc<Database>>,
+    project_id: Option<String>,
+) -> Result<Vec<PausedAgent>, CommandError> {
+    resume_agents(db.inner(), project_id.as_deref()).map_err(CommandError::from)
+}
+
+pub(crate) fn pause_agents(
+    db: &Arc<Database>,
+    project_id: Option<&str>,
+) -> Result<Vec<PausedAgent>, String> {
+    let mut paused = load_paused_agents(db)?;
+    let now = db.now();
+    let newly_paused: Vec<PausedAgent> = db
+        .list_agents()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|agent| project_id.is_none_or(|id| agent.project_id == id))
+        .filter(|agent| !paused.iter().any(|entry| entry.agent_id == agent.id))
+        .map(|agent| PausedAgent {
+            was_running: agent.status == AgentStatus::Running,
+            agent_id: agent.id,
+            paused_at: now,
+        })
+        .collect();
+
+    paused.extend(newly_paused.iter().cloned());
+    db.set_setting(PAUSED_AGENTS_KEY, &paused)
+        .map_err(|e| e.to_string())?;
+
+    for entry in newly_paused.iter().filter(|entry| entry.was_running) {
+        let mut msg = Message::to_agent(&entry.agent_id, MessageKind::Pause, "Paused (pause all)");
+        msg.metadata = Some(serde_json::json!({ "source": "pause_all" }));
+        enqueue_message(db, msg)?;
+    }
+    Ok(newly_paused)
+}
+
+pub(crate) fn resume_agents(
+    db: &Arc<Database>,
+    project_id: Option<&str>,
+) -> Result<Vec<PausedAgent>, String> {
+    let in_scope: HashSet<String> = db
+        .list_agents()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|agent| project_id.is_none_or(|id| agent.project_id == id))
+        .map(|agent| agent.id)
+        .collect();
+    let (resumed, still_paused): (Vec<PausedAgent>, Vec<PausedAgent>) = load_paused_agents(db)?
+        .into_iter()
+        .partition(|entry| project_id.is_none() || in_scope.contains(&entry.agent_id));
+    db.set_setting(PAUSED_AGENTS_KEY, &still_paused)
+        .map_err(|e| e.to_string())?;
+
+    for entry in &resumed {
+        if entry.was_running && in_scope.contains(&entry.agent_id) {
+            let mut msg = resume_message(db, &entry.agent_id);
+            msg.metadata = Some(serde_json::json!({ "source": "pause_all" }));
+            enqueue_message(db, msg)?;
+        } else if let Err(error) = ensure_adapter_started(db, &entry.agent_id, false) {
+            tracing::warn!("Failed to start adapter for {}: {}", entry.agent_id, error);
+        }
+    }
+    Ok(resumed)
+}
+
+// ── Dashboard ───────────────────────────────────────────────────────────────
+
+/// Distinct files each agent changed at or after `since`.
+fn files_changed_since(runs: &[Run], since: DateTime<Utc>) -> HashMap<String, usize> {
+    let mut paths: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for run in runs {
+        for change in run
+            .file_changes
+            .iter()
+            .filter(|change| change.timestamp >= since)
+        {
+            paths
+                .entry(run.agent_id.as_str())
+                .or_default()
+                .insert(change.path.as_str());
+        }
+    }
+    paths
+        .into_iter()
+        .map(|(agent_id, paths)| (agent_id.to_string(), paths.len()))
+        .collect()
+}
 
-    // Ensure adapter loops are active after app restarts, even before sending a new message.
-    for agent in &agents {
-        if let Err(error) = ensure_adapter_started(db.inner(), &agent.id, false) {
-            log::warn!("Failed to start adapter for {}: {}", agent.id, error);
+/// Attention items an agent raises, before dismissals and snoozes apply.
+pub(crate) fn attention_items(
+    agent: &Agent,
+    project_name: &str,
+    recent_run: Option<&Run>,
+    stuck_runs: &[watchdog::StuckRun],
+) -> Vec<AttentionItem> {
+    let item = |reason: &str, timestamp: DateTime<Utc>| AttentionItem {
+        agent_id: agent.id.clone(),
+        agent_name: agent.name.clone(),
+        project_name: project_name.to_string(),
+        reason: reason.to_string(),
+        timestamp,
+    };
+    let mut items = vec![];
+    match agent.status {
+        AgentStatus::Errored => items.push(item(
+            "errored",
+            agent.last_active_at.unwrap_or(agent.created_at),
+        )),
+        AgentStatus::Blocked => items.push(item(
+            "blocked",
+            agent.last_active_at.unwrap_or(agent.created_at),
+        )),
+        _ => {}
+    }
+    if let Some(stuck) = stuck_runs.iter().find(|stuck| stuck.agent_id == agent.id) {
+        items.push(item("stuck", stuck.last_activity_at));
+    }
+    // The supervisor keeps retrying in the background; this surfaces an
+    // adapter that has stayed down through several of those retries.
+    if let Some(since) = adapter_runtime_snapshot(&agent.id)
+        .filter(|state| state.consecutive_failures >= SUSTAINED_ADAPTER_FAILURES)
+        .and_then(|state| state.failing_since)
+    {
+        items.push(item("adapter_down", since));
+    }
+    // Check for runs needing review
+    if let Some(run) = recent_run {
+        if run.status == RunStatus::NeedsReview {
+            items.push(item("needs_review", run.started_at));
         }
     }
+    items
+}
+
+pub(crate) fn attention_visible(
+    states: &HashMap<(String, String), AttentionState>,
+    item: &AttentionItem,
+    now: DateTime<Utc>,
+) -> bool {
+    !states
+        .get(&(item.agent_id.clone(), item.reason.clone()))
+        .is_some_and(|state| state.suppresses(item, now))
+}
+
+/// Dashboard overview. `filter` narrows projects and agents; `view` loads a
+/// saved filter by name instead. Served from the cache until a relevant
+/// write.
+#[tauri::command]
+pub fn get_dashboard(
+    db: State<'_, Arc<Database>>,
+    cache: State<'_, DashboardCache>,
+    filter: Option<DashboardFilter>,
+    view: Option<String>,
+) -> Result<DashboardView, CommandError> {
+    let filter = match view {
+        Some(name) => load_dashboard_views(db.inner())?
+            .into_iter()
+            .find(|saved| saved.name == name)
+            .map(|saved| saved.filter)
+            .ok_or_else(|| CommandError::not_found(format!("Saved view not found: {}", name)))?,
+        None => filter.unwrap_or_default(),
+    };
+    cache
+        .get_or_build(&filter, || build_dashboard(db.inner(), &filter))
+        .map_err(CommandError::from)
+}
+
+fn build_dashboard(db: &Arc<Database>, filter: &DashboardFilter) -> Result<DashboardView, String> {
+    let mut projects = db
+        .list_projects_filtered(filter)
+        .map_err(|e| e.to_string())?;
+    let mut agents = db.list_agents_filtered(filter).map_err(|e| e.to_string())?;
 
     let mut needs_attention: Vec<AttentionItem> = vec![];
     let mut running = 0;
     let mut idle = 0;
     let mut errored = 0;
     let mut total_files_changed = 0;
+    let mut total_lines_added = 0;
+    let mut total_lines_removed = 0;
+    let latest_runs = db.get_latest_runs_by_agent().unwrap_or_else(|error| {
+        tracing::warn!("Failed to load latest runs: {}", error);
+        Default::default()
+    });
+    let zone = UserTimezone::load(db);
+    let today_start = zone.day_start(zone.date_of(db.now()));
+    let files_changed_today = db
+        .get_runs_active_since(&today_start.to_rfc3339())
+        .map(|runs| files_changed_since(&runs, today_start))
+        .unwrap_or_else(|error| {
+            tracing::warn!("Failed to load today's file changes: {}", error);
+            Default::default()
+        });
+    let mut files_changed_by_project: HashMap<String, usize> = HashMap::new();
+    let usage_by_agent = db.get_usage_totals_by_agent().unwrap_or_else(|error| {
+        tracing::warn!("Failed to load usage totals: {}", error);
+        Default::default()
+    });
+    let mut total_usage = TokenUsage::default();
+
+    let watchdog_settings = db
+        .get_setting::<WatchdogSettings>(WATCHDOG_SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let stuck_runs =
+        watchdog::find_stuck_runs(db, &watchdog_settings, db.now()).unwrap_or_else(|error| {
+            tracing::warn!("Failed to check for stuck runs: {}", error);
+            vec![]
+        });
+
+    let attention_states = db.list_attention_states().unwrap_or_else(|error| {
+        tracing::warn!("Failed to load attention state: {}", error);
+        Default::default()
+    });
+    let now = db.now();
+    if filter.only_needs_attention {
+        agents.retain(|agent| {
+            attention_items(agent, "", latest_runs.get(&agent.id), &stuck_runs)
+                .iter()
+                .any(|item| attention_visible(&attention_states, item, now))
+        });
+    }
+    if filter.narrows_agents() {
+        projects.retain(|project| agents.iter().any(|agent| agent.project_id == project.id));
+    }
 
     let mut project_agents: Vec<ProjectWithAgents> = vec![];
 
@@ -224,71 +716,88 @@ pub fn get_dashboard(db: State<'_, Arc<Database>>) -> Result<DashboardView, Stri
             .iter()
             .filter(|a| a.project_id == project.id)
             .map(|agent| {
-                let recent_run = db.get_latest_run_for_agent(&agent.id).unwrap_or(None);
-                let files_changed = recent_run
-                    .as_ref()
-                    .map(|r| r.file_changes.len())
-                    .unwrap_or(0);
+                let recent_run = latest_runs.get(&agent.id).cloned();
+                let files_changed = files_changed_today.get(&agent.id).copied().unwrap_or(0);
                 total_files_changed += files_changed;
+                if files_changed > 0 {
+                    *files_changed_by_project
+                        .entry(project.id.clone())
+                        .or_default() += files_changed;
+                }
+                let (lines_added, lines_removed) =
+                    recent_run.as_ref().map(Run::line_churn).unwrap_or_default();
+                total_lines_added += lines_added;
+                total_lines_removed += lines_removed;
 
                 match agent.status {
                     AgentStatus::Running => running += 1,
                     AgentStatus::Idle => idle += 1,
-                    AgentStatus::Errored => {
-                        errored += 1;
-                        needs_attention.push(AttentionItem {
-                            agent_id: agent.id.clone(),
-                            agent_name: agent.name.clone(),
-                            project_name: project.name.clone(),
-                            reason: "errored".to_string(),
-                            timestamp: agent.last_active_at.unwrap_or(agent.created_at),
-                        });
-                    }
-                    AgentStatus::Blocked => {
-                        needs_attention.push(AttentionItem {
-                            agent_id: agent.id.clone(),
-                            agent_name: agent.name.clone(),
-                            project_name: project.name.clone(),
-                            reason: "blocked".to_string(),
-                            timestamp: agent.last_active_at.unwrap_or(agent.created_at),
-                        });
-                    }
+                    AgentStatus::Errored => errored += 1,
                     _ => {}
                 }
-
-                // Check for runs needing review
-                if let Some(ref run) = recent_run {
-                    if run.status == RunStatus::NeedsReview {
-                        needs_attention.push(AttentionItem {
-                            agent_id: agent.id.clone(),
-                            agent_name: agent.name.clone(),
-                            project_name: project.name.clone(),
-                            reason: "needs_review".to_string(),
-                            timestamp: run.started_at,
-                        });
-                    }
-                }
+                needs_attention.extend(attention_items(
+                    agent,
+                    &project.name,
+                    recent_run.as_ref(),
+                    &stuck_runs,
+                ));
 
                 AgentSummary {
                     agent: agent.clone(),
                     recent_run,
                     files_changed_today: files_changed,
+                    lines_added,
+                    lines_removed,
+                    usage: usage_by_agent.get(&agent.id).copied().unwrap_or_default(),
                 }
             })
             .collect();
 
+        let mut project_usage = TokenUsage::default();
+        for summary in &project_agent_list {
+            project_usage.add(&summary.usage);
+        }
+        total_usage.add(&project_usage);
+
         project_agents.push(ProjectWithAgents {
             project: project.clone(),
             agents: project_agent_list,
+            usage: project_usage,
         });
     }
 
+    needs_attention.retain(|item| attention_visible(&attention_states, item, now));
+
+    let mut pinned: Vec<AgentSummary> = project_agents
+        .iter()
+        .flat_map(|project| project.agents.iter())
+        .filter(|summary| summary.agent.pinned_position.is_some())
+        .cloned()
+        .collect();
+    pinned.sort_by_key(|summary| summary.agent.pinned_position);
+
+    let groups = db
+        .list_agent_groups()
+        .map(|groups| group_rollups(&groups, &project_agents, &needs_attention))
+        .unwrap_or_else(|error| {
+            tracing::warn!("Failed to load agent groups: {}", error);
+            vec![]
+        });
+
     let total_agents = agents.len();
     let needs_attention_count = needs_attention.len();
 
+    let labels = db.get_label_rollups().unwrap_or_else(|error| {
+        tracing::warn!("Failed to load label rollups: {}", error);
+        vec![]
+    });
+
     Ok(DashboardView {
         projects: project_agents,
+        pinned,
+        groups,
         needs_attention,
+        labels,
         stats: DashboardStats {
             total_agents,
             running,
@@ -296,35 +805,128 @@ pub fn get_dashboard(db: State<'_, Arc<Database>>) -> Result<DashboardView, Stri
             errored,
             needs_attention: needs_attention_count,
             files_changed_today: total_files_changed,
+            files_changed_by_project,
+            lines_added: total_lines_added,
+            lines_removed: total_lines_removed,
+            usage: total_usage,
         },
     })
 }
 
+const DASHBOARD_VIEWS_KEY: &str = "dashboard_views";
+
+fn load_dashboard_views(db: &Database) -> Result<Vec<SavedDashboardView>, String> {
+    db.get_setting::<Vec<SavedDashboardView>>(DASHBOARD_VIEWS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_dashboard_views(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<SavedDashboardView>, CommandError> {
+    load_dashboard_views(db.inner()).map_err(CommandError::from)
+}
+
+/// Save a named filter preset, replacing any view with the same name.
+#[tauri::command]
+pub fn save_dashboard_view(
+    db: State<'_, Arc<Database>>,
+    name: String,
+    filter: DashboardFilter,
+) -> Result<Vec<SavedDashboardView>, CommandError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input("View name cannot be empty"));
+    }
+    let mut views = load_dashboard_views(db.inner())?;
+    views.retain(|view| view.name != name);
+    views.push(SavedDashboardView {
+        name: name.to_string(),
+        filter,
+    });
+    views.sort_by(|a, b| a.name.cmp(&b.name));
+    db.set_setting(DASHBOARD_VIEWS_KEY, &views)?;
+    Ok(views)
+}
+
+#[tauri::command]
+pub fn delete_dashboard_view(
+    db: State<'_, Arc<Database>>,
+    name: String,
+) -> Result<Vec<SavedDashboardView>, CommandError> {
+    let mut views = load_dashboard_views(db.inner())?;
+    views.retain(|view| view.name != name);
+    db.set_setting(DASHBOARD_VIEWS_KEY, &views)?;
+    Ok(views)
+}
+
+const ATTENTION_REASONS: [&str; 5] = [
+    "errored",
+    "needs_review",
+    "blocked",
+    "stuck",
+    "adapter_down",
+];
+
+fn validate_attention_reason(reason: &str) -> Result<(), String> {
+    if ATTENTION_REASONS.contains(&reason) {
+        Ok(())
+    } else {
+        Err(format!("Unknown attention reason: {}", reason))
+    }
+}
+
+/// Acknowledge an attention item. It stays hidden until the same reason
+/// comes up again for the agent.
+#[tauri::command]
+pub fn dismiss_attention(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    reason: String,
+) -> Result<(), CommandError> {
+    validate_attention_reason(&reason)?;
+    db.dismiss_attention(&agent_id, &reason, db.now())
+        .map_err(CommandError::from)
+}
+
+/// Hide an attention item until `until`, however often it recurs meanwhile.
+#[tauri::command]
+pub fn snooze_attention(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    reason: String,
+    until: DateTime<Utc>,
+) -> Result<(), CommandError> {
+    validate_attention_reason(&reason)?;
+    if until <= db.now() {
+        return Err(CommandError::invalid_input(
+            "snooze time must be in the future",
+        ));
+    }
+    db.snooze_attention(&agent_id, &reason, until)
+        .map_err(CommandError::from)
+}
+
 // ── Agent detail ────────────────────────────────────────────────────────────
 
 #[tauri::command]
 pub fn get_agent_detail(
     db: State<'_, Arc<Database>>,
     agent_id: String,
-) -> Result<AgentDetail, String> {
-    let agents = db.list_agents().map_err(|e| e.to_string())?;
-    let agent = agents
-        .into_iter()
-        .find(|a| a.id == agent_id)
-        .ok_or("Agent not found")?;
+) -> Result<AgentDetail, CommandError> {
+    let agent = db
+        .get_agent(&agent_id)?
+        .ok_or_else(|| CommandError::not_found("Agent not found"))?;
 
-    let runs = db
-        .get_runs_for_agent(&agent_id, 20)
-        .map_err(|e| e.to_string())?;
+    let runs = db.get_run_headers_for_agent(&agent_id, 20)?;
 
-    let mut messages = db
-        .get_messages_for_agent(&agent_id, 50)
-        .map_err(|e| e.to_string())?;
+    let mut messages = db.get_messages_for_agent(&agent_id, 50)?;
     messages.reverse(); // oldest first for display
 
     let adapter_config = db
-        .get_adapter_config(&agent_id)
-        .map_err(|e| e.to_string())?;
+        .get_adapter_config(&agent_id)?
+        .map(|config| redaction::mask_adapter_env(&config));
 
     Ok(AgentDetail {
         agent,
@@ -337,11 +939,49 @@ pub fn get_agent_detail(
 #[derive(serde::Serialize)]
 pub struct AgentDetail {
     pub agent: Agent,
-    pub runs: Vec<Run>,
+    pub runs: Vec<RunHeader>,
     pub messages: Vec<Message>,
     pub adapter_config: Option<AdapterConfig>,
 }
 
+const RUN_OUTPUT_PAGE_DEFAULT: usize = 100;
+const RUN_OUTPUT_PAGE_MAX: usize = 500;
+
+/// A run with its newest page of outputs; `get_run_outputs` pages back.
+#[tauri::command]
+pub fn get_run_detail(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+    output_limit: Option<usize>,
+) -> Result<RunDetail, CommandError> {
+    let run = db
+        .get_run_header(&run_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Run not found: {}", run_id)))?;
+    let limit = output_limit
+        .unwrap_or(RUN_OUTPUT_PAGE_DEFAULT)
+        .clamp(1, RUN_OUTPUT_PAGE_MAX);
+    let offset = run.output_count.saturating_sub(limit);
+    let outputs = db
+        .get_run_outputs(&run_id, offset, limit)?
+        .ok_or_else(|| CommandError::not_found(format!("Run not found: {}", run_id)))?;
+    Ok(RunDetail { run, outputs })
+}
+
+/// A page of a run's outputs starting at index `offset`.
+#[tauri::command]
+pub fn get_run_outputs(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<RunOutputPage, CommandError> {
+    let limit = limit
+        .unwrap_or(RUN_OUTPUT_PAGE_DEFAULT)
+        .clamp(1, RUN_OUTPUT_PAGE_MAX);
+    db.get_run_outputs(&run_id, offset, limit)?
+        .ok_or_else(|| CommandError::not_found(format!("Run not found: {}", run_id)))
+}
+
 // ── Agent management ────────────────────────────────────────────────────────
 
 #[tauri::command]
@@ -349,9 +989,9 @@ pub fn create_project(
     db: State<'_, Arc<Database>>,
     name: String,
     color: String,
-) -> Result<Project, String> {
+) -> Result<Project, CommandError> {
     let project = Project::new(&name, &color);
-    db.create_project(&project).map_err(|e| e.to_string())?;
+    db.create_project(&project)?;
     Ok(project)
 }
 
@@ -359,9 +999,9 @@ pub fn create_project(
 pub fn list_project_context_docs(
     db: State<'_, Arc<Database>>,
     project_id: String,
-) -> Result<Vec<ProjectContextDocument>, String> {
+) -> Result<Vec<ProjectContextDocument>, CommandError> {
     db.list_project_context_docs(&project_id)
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
@@ -371,7 +1011,7 @@ pub fn save_project_context_doc(
     doc_id: Option<String>,
     title: String,
     content: String,
-) -> Result<ProjectContextDocument, String> {
+) -> Result<ProjectContextDocument, CommandError> {
     let normalized_title = {
         let trimmed = title.trim();
         if trimmed.is_empty() {
@@ -383,12 +1023,11 @@ pub fn save_project_context_doc(
 
     let now = chrono::Utc::now();
     let mut doc = if let Some(doc_id) = doc_id {
-        if let Some(mut existing) = db
-            .get_project_context_doc(&doc_id)
-            .map_err(|e| e.to_string())?
-        {
+        if let Some(mut existing) = db.get_project_context_doc(&doc_id)? {
             if existing.project_id != project_id {
-                return Err("Context document does not belong to this project".to_string());
+                return Err(CommandError::invalid_input(
+                    "Context document does not belong to this project",
+                ));
             }
             existing.title = normalized_title;
             existing.content = content;
@@ -402,6 +1041,8 @@ pub fn save_project_context_doc(
                 content,
                 created_at: now,
                 updated_at: now,
+                source_path: None,
+                watch_source: false,
             }
         }
     } else {
@@ -413,8 +1054,7 @@ pub fn save_project_context_doc(
         doc.updated_at = doc.created_at;
     }
 
-    db.save_project_context_doc(&doc)
-        .map_err(|e| e.to_string())?;
+    db.save_project_context_doc(&doc)?;
     Ok(doc)
 }
 
@@ -422,40 +1062,1373 @@ pub fn save_project_context_doc(
 pub fn delete_project_context_doc(
     db: State<'_, Arc<Database>>,
     doc_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     db.delete_project_context_doc(&doc_id)
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
+}
+
+/// Import a Markdown/text file, or a folder's files matching `pattern`, as
+/// context docs. With `watch`, docs follow later edits to their files.
+#[tauri::command]
+pub fn import_context_docs(
+    db: State<'_, Arc<Database>>,
+    project_id: String,
+    path: String,
+    pattern: Option<String>,
+    watch: Option<bool>,
+) -> Result<ContextDocImport, CommandError> {
+    context_docs::import_docs(
+        &db,
+        &project_id,
+        &path,
+        pattern.as_deref(),
+        watch.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+pub fn list_context_doc_templates(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<ContextDocTemplate>, CommandError> {
+    context_docs::load_templates(&db)
+}
+
+#[tauri::command]
+pub fn save_context_doc_template(
+    db: State<'_, Arc<Database>>,
+    template: ContextDocTemplate,
+) -> Result<ContextDocTemplate, CommandError> {
+    context_docs::save_template(&db, template)
+}
+
+#[tauri::command]
+pub fn delete_context_doc_template(
+    db: State<'_, Arc<Database>>,
+    template_id: String,
+) -> Result<(), CommandError> {
+    context_docs::delete_template(&db, &template_id)
+}
+
+/// Create a context doc from a template, filling its `{{placeholders}}`
+/// from `values`.
+#[tauri::command]
+pub fn create_doc_from_template(
+    db: State<'_, Arc<Database>>,
+    project_id: String,
+    template_id: String,
+    values: HashMap<String, String>,
+) -> Result<ProjectContextDocument, CommandError> {
+    context_docs::create_from_template(&db, &project_id, &template_id, values)
+}
+
+/// The docs an agent works with: its own stack, or all of the project's
+/// docs when it has none.
+#[tauri::command]
+pub fn get_agent_context_docs(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+) -> Result<Vec<ProjectContextDocument>, CommandError> {
+    let agent = db
+        .get_agent(&agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+    context_docs::agent_context(&db, &agent)
+}
+
+/// Give an agent its own ordered context stack; an empty list clears it.
+#[tauri::command]
+pub fn set_agent_context_docs(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    doc_ids: Vec<String>,
+) -> Result<Vec<ProjectContextDocument>, CommandError> {
+    context_docs::set_agent_context(&db, &agent_id, doc_ids)
+}
+
+/// Earlier versions of a context doc, newest first.
+#[tauri::command]
+pub fn list_doc_revisions(
+    db: State<'_, Arc<Database>>,
+    doc_id: String,
+) -> Result<Vec<ContextDocRevision>, CommandError> {
+    db.list_doc_revisions(&doc_id).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn restore_doc_revision(
+    db: State<'_, Arc<Database>>,
+    revision_id: String,
+) -> Result<ProjectContextDocument, CommandError> {
+    context_docs::restore_revision(&db, &revision_id)
+}
+
+/// Line diff between two versions of a doc; a missing revision id stands
+/// for the current version.
+#[tauri::command]
+pub fn diff_doc_revisions(
+    db: State<'_, Arc<Database>>,
+    doc_id: String,
+    from_revision_id: Option<String>,
+    to_revision_id: Option<String>,
+) -> Result<DocDiff, CommandError> {
+    context_docs::diff_versions(
+        &db,
+        &doc_id,
+        from_revision_id.as_deref(),
+        to_revision_id.as_deref(),
+    )
 }
 
 #[tauri::command]
 pub fn create_agent(
     db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
     name: String,
     project_id: String,
     kind: AgentKind,
     function_tag: String,
     working_directory: Option<String>,
-) -> Result<Agent, String> {
+) -> Result<Agent, CommandError> {
+    workdir::validate(working_directory.as_deref())?;
     let mut agent = Agent::new(&name, &project_id, kind, &function_tag);
     agent.working_directory = working_directory;
-    db.create_agent(&agent).map_err(|e| e.to_string())?;
+    watchers::validate_scope(&agent)?;
+    db.create_agent(&agent)?;
+    watcher.agent_changed(&agent);
     Ok(agent)
 }
 
 #[tauri::command]
-pub fn update_agent_status(
+pub fn update_agent(
     db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
     agent_id: String,
-    status: AgentStatus,
-) -> Result<(), String> {
-    db.update_agent_status(&agent_id, &status)
-        .map_err(|e| e.to_string())
-}
+    update: AgentUpdate,
+) -> Result<Agent, CommandError> {
+    let mut agent = db
+        .get_agent(&agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
 
-// ── Message Bus ─────────────────────────────────────────────────────────────
+    if let Some(name) = update.name {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(CommandError::invalid_input("Agent name cannot be empty"));
+        }
+        agent.name = name.to_string();
+    }
+    if let Some(function_tag) = update.function_tag {
+        agent.function_tag = function_tag.trim().to_string();
+    }
+    if let Some(working_directory) = update.working_directory {
+        let working_directory = working_directory.trim();
+        workdir::validate(Some(working_directory))?;
+        agent.working_directory =
+            (!working_directory.is_empty()).then(|| working_directory.to_string());
+    }
+    if let Some(config) = update.config {
+        agenda::validate_schedule(config.schedule.as_deref())?;
+        if config.idle_timeout_minutes == Some(0) {
+            return Err(CommandError::invalid_input(
+                "Idle timeout must be at least one minute",
+            ));
+        }
+        watchers::validate_globs(&config.ignore_globs)?;
+        watchers::validate_globs(&config.include_globs)?;
+        let capabilities = routing::normalize_capabilities(config.capabilities.clone())?;
+        agent.config = AgentConfig {
+            capabilities,
+            ..config
+        };
+    }
+    watchers::validate_scope(&agent)?;
 
-/// Send an instruction to an agent. The message goes into the bus and the
-/// adapter picks it up on its next poll cycle.
+    db.update_agent(&agent)?;
+    watcher.agent_changed(&agent);
+    Ok(agent)
+}
+
+/// Expand `~` in a candidate working directory and check it: it must be a
+/// readable directory (created first when `create` is set). Reports the git
+/// branch and remote when it's inside a repository.
+#[tauri::command]
+pub fn validate_working_directory(
+    path: String,
+    create: Option<bool>,
+) -> Result<WorkingDirectory, CommandError> {
+    workdir::inspect(&path, create.unwrap_or(false))
+}
+
+// ── Routing ─────────────────────────────────────────────────────────────────
+
+/// Send an instruction to whichever idle agent in the project suits it best
+/// (see `routing`). Paused agents are left out.
+#[tauri::command]
+pub fn route_instruction(
+    db: State<'_, Arc<Database>>,
+    project_id: String,
+    text: String,
+) -> Result<RoutedInstruction, CommandError> {
+    route_to_project(db.inner(), &project_id, &text)
+}
+
+pub(crate) fn route_to_project(
+    db: &Arc<Database>,
+    project_id: &str,
+    text: &str,
+) -> Result<RoutedInstruction, CommandError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(CommandError::invalid_input("Instruction cannot be empty"));
+    }
+    if !db
+        .list_projects()?
+        .iter()
+        .any(|project| project.id == project_id)
+    {
+        return Err(CommandError::not_found(format!(
+            "Project not found: {}",
+            project_id
+        )));
+    }
+
+    let paused: HashSet<String> = load_paused_agents(db)?
+        .into_iter()
+        .map(|entry| entry.agent_id)
+        .collect();
+    let since = (db.now() - chrono::Duration::hours(routing::LOAD_WINDOW_HOURS)).to_rfc3339();
+    let mut idle = Vec::new();
+    for agent in db.list_agents()? {
+        if agent.project_id == project_id
+            && agent.status == AgentStatus::Idle
+            && !paused.contains(&agent.id)
+        {
+            let recent_runs = db.count_runs_started_since(&agent.id, &since)?;
+            idle.push((agent, recent_runs));
+        }
+    }
+
+    let candidates = routing::rank(&idle, text);
+    let best = candidates
+        .first()
+        .ok_or_else(|| CommandError::invalid_input("No idle agent in this project to route to"))?;
+    let mut msg = Message::to_agent(&best.agent_id, MessageKind::Instruction, text);
+    msg.metadata = Some(serde_json::json!({
+        "routed": { "matched": best.matched, "score": best.score }
+    }));
+    let message = enqueue_message(db, msg)?;
+    Ok(RoutedInstruction {
+        message,
+        candidates,
+    })
+}
+
+// ── Agent Groups ────────────────────────────────────────────────────────────
+
+fn load_agent_group(db: &Database, group_id: &str) -> Result<AgentGroup, CommandError> {
+    db.get_agent_group(group_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent group not found: {}", group_id)))
+}
+
+#[tauri::command]
+pub fn list_agent_groups(db: State<'_, Arc<Database>>) -> Result<Vec<AgentGroup>, CommandError> {
+    db.list_agent_groups().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn create_agent_group(
+    db: State<'_, Arc<Database>>,
+    name: String,
+    agent_ids: Option<Vec<String>>,
+) -> Result<AgentGroup, CommandError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input("Group name cannot be empty"));
+    }
+    let group = AgentGroup::new(name);
+    db.create_agent_group(&group)?;
+    for agent_id in agent_ids.unwrap_or_default() {
+        db.add_agent_group_member(&group.id, &agent_id)?;
+    }
+    load_agent_group(db.inner(), &group.id)
+}
+
+#[tauri::command]
+pub fn rename_agent_group(
+    db: State<'_, Arc<Database>>,
+    group_id: String,
+    name: String,
+) -> Result<AgentGroup, CommandError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input("Group name cannot be empty"));
+    }
+    db.rename_agent_group(&group_id, name)?;
+    load_agent_group(db.inner(), &group_id)
+}
+
+#[tauri::command]
+pub fn delete_agent_group(
+    db: State<'_, Arc<Database>>,
+    group_id: String,
+) -> Result<(), CommandError> {
+    db.delete_agent_group(&group_id).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn add_agent_to_group(
+    db: State<'_, Arc<Database>>,
+    group_id: String,
+    agent_id: String,
+) -> Result<AgentGroup, CommandError> {
+    load_agent_group(db.inner(), &group_id)?;
+    if db.get_agent_config(&agent_id)?.is_none() {
+        return Err(CommandError::not_found(format!(
+            "Agent not found: {}",
+            agent_id
+        )));
+    }
+    db.add_agent_group_member(&group_id, &agent_id)?;
+    load_agent_group(db.inner(), &group_id)
+}
+
+#[tauri::command]
+pub fn remove_agent_from_group(
+    db: State<'_, Arc<Database>>,
+    group_id: String,
+    agent_id: String,
+) -> Result<AgentGroup, CommandError> {
+    db.remove_agent_group_member(&group_id, &agent_id)?;
+    load_agent_group(db.inner(), &group_id)
+}
+
+/// Send the same instruction to every member of a group. Each message is
+/// tagged with `metadata.group_id`.
+#[tauri::command]
+pub fn send_group_instruction(
+    db: State<'_, Arc<Database>>,
+    group_id: String,
+    content: String,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<Vec<Message>, CommandError> {
+    if content.trim().is_empty() {
+        return Err(CommandError::invalid_input("Instruction cannot be empty"));
+    }
+    let group = load_agent_group(db.inner(), &group_id)?;
+    group
+        .agent_ids
+        .iter()
+        .map(|agent_id| {
+            let mut msg = Message::to_agent(agent_id, MessageKind::Instruction, &content);
+            msg.expires_at = expires_at;
+            msg.metadata = Some(serde_json::json!({ "group_id": group.id }));
+            enqueue_message(db.inner(), msg).map_err(CommandError::from)
+        })
+        .collect()
+}
+
+/// Pause running members, or resume blocked ones. Members in any other
+/// state are left alone.
+#[tauri::command]
+pub fn set_group_paused(
+    db: State<'_, Arc<Database>>,
+    group_id: String,
+    paused: bool,
+) -> Result<Vec<Message>, CommandError> {
+    let group = load_agent_group(db.inner(), &group_id)?;
+    let from_status = if paused {
+        AgentStatus::Running
+    } else {
+        AgentStatus::Blocked
+    };
+    db.list_agents()?
+        .into_iter()
+        .filter(|agent| group.agent_ids.contains(&agent.id) && agent.status == from_status)
+        .map(|agent| {
+            let mut msg = if paused {
+                Message::to_agent(&agent.id, MessageKind::Pause, "Paused with group")
+            } else {
+                resume_message(db.inner(), &agent.id)
+            };
+            msg.metadata = Some(serde_json::json!({ "group_id": group.id }));
+            enqueue_message(db.inner(), msg).map_err(CommandError::from)
+        })
+        .collect()
+}
+
+/// Roll up dashboard agent summaries per group.
+fn group_rollups(
+    groups: &[AgentGroup],
+    projects: &[ProjectWithAgents],
+    needs_attention: &[AttentionItem],
+) -> Vec<GroupRollup> {
+    let summaries: HashMap<&str, &AgentSummary> = projects
+        .iter()
+        .flat_map(|project| project.agents.iter())
+        .map(|summary| (summary.agent.id.as_str(), summary))
+        .collect();
+    groups
+        .iter()
+        .map(|group| {
+            let mut rollup = GroupRollup {
+                group_id: group.id.clone(),
+                name: group.name.clone(),
+                ..Default::default()
+            };
+            for summary in group
+                .agent_ids
+                .iter()
+                .filter_map(|agent_id| summaries.get(agent_id.as_str()))
+            {
+                rollup.agent_count += 1;
+                match summary.agent.status {
+                    AgentStatus::Running => rollup.running += 1,
+                    AgentStatus::Idle => rollup.idle += 1,
+                    AgentStatus::Blocked => rollup.blocked += 1,
+                    AgentStatus::Errored => rollup.errored += 1,
+                    AgentStatus::Completed => {}
+                }
+                if needs_attention
+                    .iter()
+                    .any(|item| item.agent_id == summary.agent.id)
+                {
+                    rollup.needs_attention += 1;
+                }
+                rollup.usage.add(&summary.usage);
+            }
+            rollup
+        })
+        .collect()
+}
+
+// ── Board ───────────────────────────────────────────────────────────────────
+
+/// A project's cards in board order. Cards whose instruction has started a
+/// run are linked to it and moved along with it.
+#[tauri::command]
+pub fn get_board(
+    db: State<'_, Arc<Database>>,
+    project_id: String,
+) -> Result<Vec<Task>, CommandError> {
+    board::load_board(&db, &project_id)
+}
+
+#[tauri::command]
+pub fn save_task(db: State<'_, Arc<Database>>, task: TaskInput) -> Result<Task, CommandError> {
+    board::save_task(&db, task)
+}
+
+/// Move a card within or across columns; `position` defaults to the bottom.
+/// A linked item follows the card upstream; if that fails the move stands
+/// and the next sync retries it.
+#[tauri::command]
+pub async fn move_task(
+    db: State<'_, Arc<Database>>,
+    task_id: String,
+    column: TaskColumn,
+    position: Option<u32>,
+) -> Result<Task, CommandError> {
+    let task = board::move_task(&db, &task_id, column, position)?;
+    Ok(push_task_item_status(&db, task).await)
+}
+
+#[tauri::command]
+pub fn delete_task(db: State<'_, Arc<Database>>, task_id: String) -> Result<(), CommandError> {
+    db.delete_task(&task_id).map_err(CommandError::from)
+}
+
+/// Send the card to an agent as an instruction, rendered from the board
+/// template unless `instruction` is given.
+#[tauri::command]
+pub async fn assign_task(
+    db: State<'_, Arc<Database>>,
+    task_id: String,
+    agent_id: String,
+    instruction: Option<String>,
+) -> Result<Task, CommandError> {
+    let task = board::assign_task(&db, &task_id, &agent_id, instruction)?;
+    Ok(push_task_item_status(&db, task).await)
+}
+
+/// Settle a card whose column and linked item both changed since the last
+/// sync: keep the board's column (pushed upstream) or the item's status.
+#[tauri::command]
+pub async fn resolve_task_conflict(
+    db: State<'_, Arc<Database>>,
+    task_id: String,
+    keep_local: bool,
+) -> Result<Task, CommandError> {
+    let task = board::resolve_conflict(&db, &task_id, keep_local)?;
+    board::push_item_status(&db, task).await
+}
+
+async fn push_task_item_status(db: &Database, task: Task) -> Task {
+    let fallback = task.clone();
+    board::push_item_status(db, task)
+        .await
+        .unwrap_or_else(|error| {
+            tracing::warn!("Failed to push card {} upstream: {}", fallback.id, error);
+            fallback
+        })
+}
+
+#[tauri::command]
+pub fn get_board_settings(db: State<'_, Arc<Database>>) -> Result<BoardSettings, CommandError> {
+    board::load_settings(&db)
+}
+
+#[tauri::command]
+pub fn set_board_settings(
+    db: State<'_, Arc<Database>>,
+    settings: BoardSettings,
+) -> Result<BoardSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(BOARD_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+// ── Pipelines ───────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn list_pipelines(db: State<'_, Arc<Database>>) -> Result<Vec<Pipeline>, CommandError> {
+    pipelines::load(db.inner()).map_err(CommandError::from)
+}
+
+/// Create or replace a pipeline. Fails if its links would close a cycle
+/// with any enabled pipeline.
+#[tauri::command]
+pub fn save_pipeline(
+    db: State<'_, Arc<Database>>,
+    pipeline: PipelineInput,
+) -> Result<Pipeline, CommandError> {
+    let mut stored = pipelines::load(db.inner())?;
+    let saved = pipelines::apply_input(&mut stored, pipeline)?;
+    db.set_setting(PIPELINES_KEY, &stored)?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub fn delete_pipeline(
+    db: State<'_, Arc<Database>>,
+    pipeline_id: String,
+) -> Result<(), CommandError> {
+    let mut stored = pipelines::load(db.inner())?;
+    stored.retain(|pipeline| pipeline.id != pipeline_id);
+    db.set_setting(PIPELINES_KEY, &stored)
+        .map_err(CommandError::from)
+}
+
+/// Per-link trigger history: how often it fired and the state of the last
+/// downstream run.
+#[tauri::command]
+pub fn get_pipeline_status(
+    db: State<'_, Arc<Database>>,
+    pipeline_id: String,
+) -> Result<Vec<PipelineLinkStatus>, CommandError> {
+    let pipeline = pipelines::load(db.inner())?
+        .into_iter()
+        .find(|pipeline| pipeline.id == pipeline_id)
+        .ok_or_else(|| CommandError::not_found(format!("Pipeline not found: {}", pipeline_id)))?;
+    pipelines::status(db.inner(), &pipeline).map_err(CommandError::from)
+}
+
+// ── Notifications ───────────────────────────────────────────────────────────
+
+const DEFAULT_NOTIFICATION_LIMIT: usize = 100;
+
+#[derive(serde::Serialize)]
+pub struct NotificationCenter {
+    pub unread_count: u32,
+    pub notifications: Vec<NotificationRecord>,
+}
+
+#[tauri::command]
+pub fn list_notification_rules(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<NotificationRule>, CommandError> {
+    db.list_notification_rules().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn save_notification_rule(
+    db: State<'_, Arc<Database>>,
+    input: NotificationRuleInput,
+) -> Result<NotificationRule, CommandError> {
+    let existing = db
+        .list_notification_rules()?
+        .into_iter()
+        .find(|rule| input.id.as_deref() == Some(rule.id.as_str()));
+    if let Some(id) = input.id.as_deref().filter(|_| existing.is_none()) {
+        return Err(CommandError::not_found(format!(
+            "Notification rule not found: {}",
+            id
+        )));
+    }
+    let rule = notifications::apply_rule_input(input, existing.as_ref())?;
+    db.save_notification_rule(&rule)?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub fn delete_notification_rule(
+    db: State<'_, Arc<Database>>,
+    rule_id: String,
+) -> Result<(), CommandError> {
+    db.delete_notification_rule(&rule_id)
+        .map_err(CommandError::from)
+}
+
+/// Recent alerts, newest first, with the unread count for the badge.
+#[tauri::command]
+pub fn get_notification_center(
+    db: State<'_, Arc<Database>>,
+    unread_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<NotificationCenter, CommandError> {
+    Ok(NotificationCenter {
+        unread_count: db.count_unread_notifications()?,
+        notifications: db.list_notifications(
+            unread_only.unwrap_or(false),
+            limit.unwrap_or(DEFAULT_NOTIFICATION_LIMIT),
+        )?,
+    })
+}
+
+/// Mark the given notifications read, or every unread one when
+/// `notification_ids` is omitted. Returns the remaining unread count.
+#[tauri::command]
+pub fn mark_notifications_read(
+    db: State<'_, Arc<Database>>,
+    notification_ids: Option<Vec<String>>,
+) -> Result<u32, CommandError> {
+    db.mark_notifications_read(notification_ids.as_deref())?;
+    db.count_unread_notifications().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn get_chat_notification_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<ChatSettings, CommandError> {
+    db.get_setting::<ChatSettings>(CHAT_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_chat_notification_settings(
+    db: State<'_, Arc<Database>>,
+    settings: ChatSettings,
+) -> Result<ChatSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(CHAT_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Post a test message to the Slack or Discord webhook a project routes to.
+#[tauri::command]
+pub async fn send_test_chat_notification(
+    db: State<'_, Arc<Database>>,
+    channel: NotificationChannel,
+    project_id: Option<String>,
+) -> Result<(), CommandError> {
+    let settings = db
+        .get_setting::<ChatSettings>(CHAT_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)?;
+    let url = settings
+        .webhook_url(channel, project_id.as_deref().unwrap_or(""))
+        .ok_or_else(|| format!("No {:?} webhook configured", channel))?;
+    chat::post(
+        &chat::build_client(),
+        channel,
+        url,
+        "Kanbun test notification",
+    )
+    .await
+    .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn get_push_settings(db: State<'_, Arc<Database>>) -> Result<PushSettings, CommandError> {
+    db.get_setting::<PushSettings>(PUSH_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_push_settings(
+    db: State<'_, Arc<Database>>,
+    settings: PushSettings,
+) -> Result<PushSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(PUSH_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Send a test push with the saved settings, bypassing the rate limit.
+#[tauri::command]
+pub async fn send_test_push(db: State<'_, Arc<Database>>) -> Result<(), CommandError> {
+    let settings = db
+        .get_setting::<PushSettings>(PUSH_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)?;
+    push::send(
+        &chat::build_client(),
+        &settings,
+        "Kanbun",
+        "Kanbun test notification",
+        false,
+    )
+    .await
+    .map_err(CommandError::from)
+}
+
+// ── Metrics ─────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_metrics_settings(db: State<'_, Arc<Database>>) -> Result<MetricsSettings, CommandError> {
+    db.get_setting::<MetricsSettings>(METRICS_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// Takes effect within a few seconds; the endpoint follows the setting.
+#[tauri::command]
+pub fn set_metrics_settings(
+    db: State<'_, Arc<Database>>,
+    settings: MetricsSettings,
+) -> Result<MetricsSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(METRICS_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+// ── Tracing ─────────────────────────────────────────────────────────────────
+
+const DEFAULT_TRACE_LIMIT: usize = 200;
+
+/// Recent trace events, newest first. `correlation_id` follows one run or
+/// sync; `level` is the least severe level to include (default debug).
+#[tauri::command]
+pub fn get_recent_traces(
+    correlation_id: Option<String>,
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<TraceEvent>, CommandError> {
+    let level = traces::parse_level(level.as_deref().unwrap_or("debug"))?;
+    Ok(traces::installed()
+        .map(|collector| {
+            collector.recent(
+                correlation_id.as_deref(),
+                level,
+                limit.unwrap_or(DEFAULT_TRACE_LIMIT),
+            )
+        })
+        .unwrap_or_default())
+}
+
+const DEFAULT_AGENT_LOG_LINES: usize = 500;
+
+/// The end of an agent's log file, oldest line first. `tail_lines` is capped
+/// at MAX_TAIL_LINES.
+#[tauri::command]
+pub fn get_agent_logs(
+    agent_id: String,
+    tail_lines: Option<usize>,
+) -> Result<AgentLogTail, CommandError> {
+    let logs = agent_logs::installed().ok_or("Agent logs are not available")?;
+    logs.tail(&agent_id, tail_lines.unwrap_or(DEFAULT_AGENT_LOG_LINES))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn get_tracing_settings(db: State<'_, Arc<Database>>) -> Result<TracingSettings, CommandError> {
+    db.get_setting::<TracingSettings>(TRACING_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_tracing_settings(
+    db: State<'_, Arc<Database>>,
+    settings: TracingSettings,
+) -> Result<TracingSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(TRACING_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+// ── Timezone ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_timezone_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<TimezoneSettings, CommandError> {
+    db.get_setting::<TimezoneSettings>(TIMEZONE_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// Set the IANA timezone days are counted in; an empty name follows the
+/// system again.
+#[tauri::command]
+pub fn set_timezone_settings(
+    db: State<'_, Arc<Database>>,
+    settings: TimezoneSettings,
+) -> Result<TimezoneSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(TIMEZONE_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+// ── GitHub reporting ────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_github_settings(db: State<'_, Arc<Database>>) -> Result<GithubSettings, CommandError> {
+    db.get_setting::<GithubSettings>(GITHUB_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_github_settings(
+    db: State<'_, Arc<Database>>,
+    settings: GithubSettings,
+) -> Result<GithubSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(GITHUB_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Report one finalized run to GitHub now, even with automatic reporting
+/// off. Returns false when the run has no GitHub remote or commit.
+#[tauri::command]
+pub async fn report_run_to_github(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+) -> Result<bool, CommandError> {
+    github::report_run_by_id(db.inner(), &run_id).await
+}
+
+// ── Inbox intake ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_intake_settings(db: State<'_, Arc<Database>>) -> Result<IntakeSettings, CommandError> {
+    db.get_setting::<IntakeSettings>(INTAKE_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// Takes effect within a few seconds; the endpoint follows the setting.
+#[tauri::command]
+pub fn set_intake_settings(
+    db: State<'_, Arc<Database>>,
+    settings: IntakeSettings,
+) -> Result<IntakeSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(INTAKE_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Capture text from Shortcuts, Raycast or a global hotkey: an instruction
+/// to `agent` when one is named, otherwise an Inbox item. Agents and
+/// projects may be given by id or name.
+#[tauri::command]
+pub fn quick_capture(
+    db: State<'_, Arc<Database>>,
+    text: String,
+    project: Option<String>,
+    agent: Option<String>,
+) -> Result<CaptureResult, CommandError> {
+    intake::quick_capture(
+        db.inner(),
+        &text,
+        project.as_deref(),
+        agent.as_deref(),
+        false,
+    )
+}
+
+#[tauri::command]
+pub fn get_deep_link_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<DeepLinkSettings, CommandError> {
+    db.get_setting::<DeepLinkSettings>(DEEP_LINK_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// Extra URL schemes capture links may call back with x-success, on top of
+/// Shortcuts and Raycast.
+#[tauri::command]
+pub fn set_deep_link_settings(
+    db: State<'_, Arc<Database>>,
+    settings: DeepLinkSettings,
+) -> Result<DeepLinkSettings, CommandError> {
+    settings.validate().map_err(CommandError::invalid_input)?;
+    db.set_setting(DEEP_LINK_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+// ── API Tokens ──────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn list_api_tokens(db: State<'_, Arc<Database>>) -> Result<Vec<ApiToken>, CommandError> {
+    db.list_api_tokens().map_err(CommandError::from)
+}
+
+/// The returned secret is shown once; only its hash is kept.
+#[tauri::command]
+pub fn create_api_token(
+    db: State<'_, Arc<Database>>,
+    name: String,
+    scope: ApiTokenScope,
+) -> Result<CreatedApiToken, CommandError> {
+    api_tokens::create(db.inner(), &name, scope).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn revoke_api_token(
+    db: State<'_, Arc<Database>>,
+    token_id: String,
+) -> Result<(), CommandError> {
+    api_tokens::revoke(db.inner(), &token_id).map_err(CommandError::from)
+}
+
+// ── Outbound Webhooks ───────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn list_webhook_subscriptions(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<WebhookSubscription>, CommandError> {
+    db.list_webhook_subscriptions().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn save_webhook_subscription(
+    db: State<'_, Arc<Database>>,
+    input: WebhookSubscriptionInput,
+) -> Result<WebhookSubscription, CommandError> {
+    let existing = db
+        .list_webhook_subscriptions()?
+        .into_iter()
+        .find(|subscription| input.id.as_deref() == Some(subscription.id.as_str()));
+    if let Some(id) = input.id.as_deref().filter(|_| existing.is_none()) {
+        return Err(CommandError::not_found(format!(
+            "Webhook subscription not found: {}",
+            id
+        )));
+    }
+    let subscription = webhooks::apply_subscription_input(input, existing.as_ref())?;
+    db.save_webhook_subscription(&subscription)?;
+    Ok(subscription)
+}
+
+#[tauri::command]
+pub fn delete_webhook_subscription(
+    db: State<'_, Arc<Database>>,
+    subscription_id: String,
+) -> Result<(), CommandError> {
+    db.delete_webhook_subscription(&subscription_id)
+        .map_err(CommandError::from)
+}
+
+/// POST a sample agent_status_changed payload to one subscription,
+/// regardless of its event filters.
+#[tauri::command]
+pub async fn send_test_webhook(
+    db: State<'_, Arc<Database>>,
+    subscription_id: String,
+) -> Result<(), CommandError> {
+    let subscription = db
+        .list_webhook_subscriptions()?
+        .into_iter()
+        .find(|subscription| subscription.id == subscription_id)
+        .ok_or_else(|| {
+            CommandError::not_found(format!(
+                "Webhook subscription not found: {}",
+                subscription_id
+            ))
+        })?;
+    let payload = WebhookPayload {
+        id: uuid::Uuid::new_v4().to_string(),
+        event: WebhookEvent::AgentStatusChanged,
+        occurred_at: Utc::now(),
+        agent_id: String::new(),
+        project_id: String::new(),
+        data: serde_json::json!({ "test": true, "from": "idle", "to": "running" }),
+    };
+    let result = webhooks::send(&chat::build_client(), &subscription, &payload).await;
+    db.record_webhook_delivery(
+        &subscription.id,
+        Utc::now(),
+        result.as_ref().err().map(String::as_str),
+    )?;
+    result.map_err(CommandError::from)
+}
+
+// ── Agent Templates ─────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn list_agent_templates(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<AgentTemplate>, CommandError> {
+    let mut templates = db.list_agent_templates()?;
+    for template in &mut templates {
+        template.adapter = template.adapter.as_ref().map(redaction::mask_adapter_env);
+    }
+    Ok(templates)
+}
+
+#[tauri::command]
+pub fn save_agent_template(
+    db: State<'_, Arc<Database>>,
+    template: AgentTemplateInput,
+) -> Result<AgentTemplate, CommandError> {
+    let name = template.name.trim();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input("Template name cannot be empty"));
+    }
+    watchers::validate_globs(&template.ignore_globs)?;
+    watchers::validate_globs(&template.include_globs)?;
+
+    let existing = match &template.id {
+        Some(id) => db.get_agent_template(id)?,
+        None => None,
+    };
+    let mut adapter = template.adapter;
+    if let Some(adapter) = &mut adapter {
+        let previous = existing
+            .as_ref()
+            .and_then(|existing| existing.adapter.as_ref());
+        redaction::restore_masked_env(adapter, previous);
+    }
+    let mut saved = existing
+        .unwrap_or_else(|| AgentTemplate::new(name, template.kind.clone(), &template.function_tag));
+    if let Some(id) = template.id {
+        saved.id = id;
+    }
+    saved.name = name.to_string();
+    saved.kind = template.kind;
+    saved.function_tag = template.function_tag.trim().to_string();
+    if let Some(autonomy_level) = template.autonomy_level {
+        saved.autonomy_level = autonomy_level;
+    }
+    saved.adapter = adapter;
+    saved.instructions = template
+        .instructions
+        .into_iter()
+        .map(|instruction| instruction.trim().to_string())
+        .filter(|instruction| !instruction.is_empty())
+        .collect();
+    saved.ignore_globs = template.ignore_globs;
+    saved.include_globs = template.include_globs;
+    saved.updated_at = Utc::now();
+
+    db.save_agent_template(&saved)?;
+    saved.adapter = saved.adapter.as_ref().map(redaction::mask_adapter_env);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub fn delete_agent_template(
+    db: State<'_, Arc<Database>>,
+    template_id: String,
+) -> Result<(), CommandError> {
+    db.delete_agent_template(&template_id)
+        .map_err(CommandError::from)
+}
+
+/// Create an agent from a template: the agent, its adapter config, and its
+/// starting instructions in one call.
+#[tauri::command]
+pub fn create_agent_from_template(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    project_id: String,
+    template_id: String,
+    name: Option<String>,
+    working_directory: Option<String>,
+) -> Result<Agent, CommandError> {
+    let template = db.get_agent_template(&template_id)?.ok_or_else(|| {
+        CommandError::not_found(format!("Agent template not found: {}", template_id))
+    })?;
+    let agent = instantiate_template(
+        db.inner(),
+        &template,
+        &project_id,
+        name.as_deref(),
+        working_directory,
+    )?;
+    watcher.agent_changed(&agent);
+    Ok(agent)
+}
+
+fn instantiate_template(
+    db: &Arc<Database>,
+    template: &AgentTemplate,
+    project_id: &str,
+    name: Option<&str>,
+    working_directory: Option<String>,
+) -> Result<Agent, CommandError> {
+    workdir::validate(working_directory.as_deref())?;
+    let name = name.map(str::trim).filter(|name| !name.is_empty());
+    let mut agent = template.instantiate(project_id, name);
+    agent.working_directory = working_directory
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty());
+    watchers::validate_scope(&agent)?;
+    db.create_agent(&agent)?;
+
+    if let Some(mut adapter) = template.adapter.clone() {
+        // Claude Code sessions start in `command` (see ClaudeCodeAdapter::new).
+        if adapter.adapter_type == AdapterType::ClaudeCode && adapter.command.is_none() {
+            adapter.command = agent.working_directory.clone();
+        }
+        db.set_adapter_config(&agent.id, &adapter)?;
+    }
+    for instruction in &template.instructions {
+        enqueue_message(
+            db,
+            Message::to_agent(&agent.id, MessageKind::Instruction, instruction),
+        )?;
+    }
+    Ok(agent)
+}
+
+/// Copy an agent's config, watch paths and adapter config into a new agent,
+/// optionally in another project. The copy gets its own adapter session.
+#[tauri::command]
+pub fn clone_agent(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    agent_id: String,
+    new_name: String,
+    new_project_id: Option<String>,
+) -> Result<Agent, CommandError> {
+    let clone = duplicate_agent(db.inner(), &agent_id, &new_name, new_project_id)?;
+    watcher.agent_changed(&clone);
+    Ok(clone)
+}
+
+fn duplicate_agent(
+    db: &Database,
+    agent_id: &str,
+    new_name: &str,
+    new_project_id: Option<String>,
+) -> Result<Agent, CommandError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(CommandError::invalid_input("Agent name cannot be empty"));
+    }
+    let source = db
+        .get_agent(agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+    let project_id = new_project_id.unwrap_or_else(|| source.project_id.clone());
+    if project_id != source.project_id
+        && !db
+            .list_projects()?
+            .iter()
+            .any(|project| project.id == project_id)
+    {
+        return Err(CommandError::not_found(format!(
+            "Project not found: {}",
+            project_id
+        )));
+    }
+
+    let mut clone = Agent::new(
+        new_name,
+        &project_id,
+        source.kind.clone(),
+        &source.function_tag,
+    );
+    clone.working_directory = source.working_directory.clone();
+    clone.config = source.config.clone();
+    clone.template_id = source.template_id.clone();
+    watchers::validate_scope(&clone)?;
+    db.create_agent(&clone)?;
+
+    if let Some(mut adapter) = db.get_adapter_config(agent_id)? {
+        adapter.session_name = None; // sessions are derived from the new agent's id
+        db.set_adapter_config(&clone.id, &adapter)?;
+    }
+    Ok(clone)
+}
+
+/// Write agents (config, watch paths, adapter config) and templates to a
+/// portable YAML bundle. Adapter env is left out unless `include_env`.
+#[tauri::command]
+pub fn export_agents_yaml(
+    db: State<'_, Arc<Database>>,
+    agent_ids: Vec<String>,
+    template_ids: Option<Vec<String>>,
+    include_env: Option<bool>,
+    destination_path: String,
+) -> Result<BundleExportResult, CommandError> {
+    let destination_path = destination_path.trim();
+    if destination_path.is_empty() {
+        return Err(CommandError::invalid_input("destination path is required"));
+    }
+    let template_ids = template_ids.unwrap_or_default();
+    if agent_ids.is_empty() && template_ids.is_empty() {
+        return Err(CommandError::invalid_input(
+            "select at least one agent or template to export",
+        ));
+    }
+
+    let bundle =
+        agent_bundle::build_bundle(&db, &agent_ids, &template_ids, include_env.unwrap_or(false))?;
+    let size_bytes = export::write_export_file(destination_path, &agent_bundle::to_yaml(&bundle)?)?;
+
+    Ok(BundleExportResult {
+        path: destination_path.to_string(),
+        agent_count: bundle.agents.len(),
+        template_count: bundle.templates.len(),
+        size_bytes,
+        completed_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// Create the agents and templates from a YAML bundle. Agents go into
+/// `project_id` when given, otherwise into projects matched by name.
+#[tauri::command]
+pub fn import_agents_yaml(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    source_path: String,
+    project_id: Option<String>,
+) -> Result<BundleImport, CommandError> {
+    let source_path = source_path.trim();
+    if source_path.is_empty() {
+        return Err(CommandError::invalid_input("source path is required"));
+    }
+    let content = std::fs::read_to_string(source_path)
+        .map_err(|error| format!("failed to read {}: {}", source_path, error))?;
+    let bundle = agent_bundle::from_yaml(&content)?;
+
+    let imported = agent_bundle::import_bundle(&db, &bundle, project_id.as_deref())?;
+    for agent in &imported.agents {
+        watcher.agent_changed(agent);
+    }
+    Ok(imported)
+}
+
+/// Write a whole project (agents, adapter configs without env, context
+/// docs, templates and, with `include_runs`, finished runs) to one JSON file.
+#[tauri::command]
+pub fn export_project(
+    db: State<'_, Arc<Database>>,
+    project_id: String,
+    destination_path: String,
+    include_runs: Option<bool>,
+) -> Result<ProjectExportResult, CommandError> {
+    let destination_path = destination_path.trim();
+    if destination_path.is_empty() {
+        return Err(CommandError::invalid_input("destination path is required"));
+    }
+    let bundle = project_bundle::build_bundle(&db, &project_id, include_runs.unwrap_or(false))?;
+    let size_bytes =
+        export::write_export_file(destination_path, &project_bundle::to_json(&bundle)?)?;
+
+    Ok(ProjectExportResult {
+        path: destination_path.to_string(),
+        agent_count: bundle.agents.len(),
+        template_count: bundle.templates.len(),
+        context_doc_count: bundle.context_docs.len(),
+        run_count: bundle.runs.len(),
+        size_bytes,
+        completed_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// Recreate an exported project as a new project, optionally renamed.
+#[tauri::command]
+pub fn import_project(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    source_path: String,
+    name: Option<String>,
+) -> Result<ProjectImport, CommandError> {
+    let source_path = source_path.trim();
+    if source_path.is_empty() {
+        return Err(CommandError::invalid_input("source path is required"));
+    }
+    let content = std::fs::read_to_string(source_path)
+        .map_err(|error| format!("failed to read {}: {}", source_path, error))?;
+    let bundle = project_bundle::from_json(&content)?;
+
+    let imported = project_bundle::import_bundle(&db, &bundle, name.as_deref())?;
+    for agent in &imported.agents {
+        watcher.agent_changed(agent);
+    }
+    Ok(imported)
+}
+
+/// Pause or resume file watching for an agent without touching its watch
+/// paths.
+#[tauri::command]
+pub fn set_watching(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    agent_id: String,
+    enabled: bool,
+) -> Result<Agent, CommandError> {
+    let mut agent = db
+        .get_agent(&agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+    agent.config.watching_paused = !enabled;
+    db.update_agent(&agent)?;
+    watcher.agent_changed(&agent);
+    Ok(agent)
+}
+
+/// Pin or unpin an agent. Newly pinned agents go to the end of the list.
+#[tauri::command]
+pub fn pin_agent(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    pinned: bool,
+) -> Result<Agent, CommandError> {
+    if !db.set_agent_pinned(&agent_id, pinned)? {
+        return Err(CommandError::not_found(format!(
+            "Agent not found: {}",
+            agent_id
+        )));
+    }
+    db.get_agent(&agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))
+}
+
+/// Reorder pinned agents; returns them in their new order.
+#[tauri::command]
+pub fn reorder_pinned_agents(
+    db: State<'_, Arc<Database>>,
+    agent_ids: Vec<String>,
+) -> Result<Vec<Agent>, CommandError> {
+    db.reorder_pinned_agents(&agent_ids)?;
+    let mut pinned: Vec<Agent> = db
+        .list_agents()?
+        .into_iter()
+        .filter(|agent| agent.pinned_position.is_some())
+        .collect();
+    pinned.sort_by_key(|agent| agent.pinned_position);
+    Ok(pinned)
+}
+
+/// Returns false when the transition was illegal and ignored; `force`
+/// writes it anyway.
+#[tauri::command]
+pub fn update_agent_status(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    status: AgentStatus,
+    force: Option<bool>,
+) -> Result<bool, CommandError> {
+    db.set_agent_status(&agent_id, &status, force.unwrap_or(false))
+        .map_err(CommandError::from)
+}
+
+// ── Message Bus ─────────────────────────────────────────────────────────────
+
+/// Send an instruction to an agent. The message goes into the bus and the
+/// adapter picks it up on its next poll cycle.
 #[tauri::command]
 pub fn send_message(
     db: State<'_, Arc<Database>>,
@@ -463,34 +2436,239 @@ pub fn send_message(
     kind: MessageKind,
     content: String,
     reply_to: Option<String>,
-) -> Result<Message, String> {
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<Message, CommandError> {
     let mut msg = Message::to_agent(&agent_id, kind, &content);
     msg.reply_to = reply_to;
+    msg.expires_at = expires_at;
+    enqueue_message(db.inner(), msg).map_err(CommandError::from)
+}
+
+/// An answer to a Blocked message and the run it put back in progress.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlockedReply {
+    pub message: Message,
+    pub run: Run,
+}
+
+/// Answer an agent that posted Blocked. The reply goes out as an instruction
+/// threaded to the Blocked message and continues the run it stopped, instead
+/// of starting a fresh run without the context.
+#[tauri::command]
+pub fn reply_to_blocked(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    message_id: String,
+    content: String,
+) -> Result<BlockedReply, CommandError> {
+    let (message, run) = answer_blocked(db.inner(), &agent_id, &message_id, &content)?;
+    if !message.is_awaiting_approval() {
+        start_adapter_for_delivery(db.inner(), &agent_id);
+    }
+    Ok(BlockedReply { message, run })
+}
+
+fn answer_blocked(
+    db: &Database,
+    agent_id: &str,
+    message_id: &str,
+    content: &str,
+) -> Result<(Message, Run), CommandError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(CommandError::invalid_input("Reply cannot be empty"));
+    }
+    let blocked = db
+        .get_message(message_id)?
+        .filter(|message| message.agent_id == agent_id)
+        .ok_or_else(|| CommandError::not_found(format!("Message not found: {}", message_id)))?;
+    if blocked.kind != MessageKind::Blocked {
+        return Err(CommandError::invalid_input(format!(
+            "Message {} is not a Blocked message",
+            message_id
+        )));
+    }
+    let run = db.get_run_blocked_by(&blocked)?.ok_or_else(|| {
+        CommandError::invalid_input(format!(
+            "Can't tell which run {} stopped; send a new instruction instead",
+            message_id
+        ))
+    })?;
+
+    let mut msg = Message::to_agent(agent_id, MessageKind::Instruction, content);
+    msg.reply_to = Some(blocked.id.clone());
+    msg.metadata = Some(serde_json::json!({ "run_id": run.id }));
+    let held = requires_approval(db, &msg)?;
+    if held {
+        mark_awaiting_approval(&mut msg);
+    }
+    let run = db.reopen_run_with_reply(&run.id, &msg)?.ok_or_else(|| {
+        CommandError::invalid_input(format!("Run {} is no longer waiting for input", run.id))
+    })?;
+    // The run already carries the reply, so dispatching only applies the
+    // agent's status; approval does that for a held reply.
+    let msg = if held {
+        msg
+    } else {
+        dispatch_message(db, msg)?
+    };
+    Ok((msg, run))
+}
+
+/// Queue an outbound message, apply its run/status side effects, and make
+/// sure the adapter loop is up to deliver it. Instructions to Manual agents
+/// are held until approved instead.
+pub(crate) fn enqueue_message(db: &Arc<Database>, msg: Message) -> Result<Message, String> {
+    let msg = queue_message(db, msg)?;
+    if !msg.is_awaiting_approval() {
+        start_adapter_for_delivery(db, &msg.agent_id);
+    }
+    Ok(msg)
+}
+
+/// Ensure the adapter loop is running so queued messages are picked up.
+fn start_adapter_for_delivery(db: &Arc<Database>, agent_id: &str) {
+    if let Err(error) = ensure_adapter_started(db, agent_id, true) {
+        tracing::warn!("Failed to start adapter for {}: {}", agent_id, error);
+    }
+}
+
+/// `enqueue_message` without starting the adapter, for callers outside the
+/// app process (the MCP server); the app's adapter supervisor delivers it.
+pub(crate) fn queue_message(db: &Database, mut msg: Message) -> Result<Message, String> {
+    if msg.kind == MessageKind::Resume && msg.content.trim().is_empty() {
+        fill_resume(db, &mut msg)?;
+    }
+    if requires_approval(db, &msg)? {
+        return hold_message(db, msg);
+    }
+    db.insert_message(&msg).map_err(|e| e.to_string())?;
+    dispatch_message(db, msg)
+}
+
+/// An empty Resume replays the agent's last delivered instruction, or its
+/// latest run's original one, so resuming means the same thing whichever
+/// adapter receives it. The replayed message is named in
+/// `metadata.replays` when it is still on the bus.
+fn fill_resume(db: &Database, msg: &mut Message) -> Result<(), String> {
+    let (content, replays) = match db
+        .get_last_delivered_instruction(&msg.agent_id)
+        .map_err(|e| e.to_string())?
+    {
+        Some(instruction) => (instruction.content, Some(instruction.id)),
+        None => {
+            let run = db
+                .get_latest_run_for_agent(&msg.agent_id)
+                .map_err(|e| e.to_string())?;
+            let instruction = run.as_ref().and_then(|run| {
+                run.outputs
+                    .iter()
+                    .find(|output| output.kind == "instruction")
+                    .map(|output| output.content.clone())
+            });
+            match instruction {
+                Some(content) => (content, run.and_then(|run| run.instruction_message_id)),
+                None => return Err("Nothing to resume: no instruction has been sent".to_string()),
+            }
+        }
+    };
+    msg.content = content;
+    if let Some(replays) = replays {
+        let metadata = msg.metadata.get_or_insert_with(|| serde_json::json!({}));
+        if !metadata.is_object() {
+            *metadata = serde_json::json!({});
+        }
+        metadata["replays"] = serde_json::json!(replays);
+    }
+    Ok(())
+}
+
+/// A Resume replaying the agent's last instruction, or a plain "Resume" for
+/// agents that were never given one.
+pub(crate) fn resume_message(db: &Database, agent_id: &str) -> Message {
+    let mut msg = Message::to_agent(agent_id, MessageKind::Resume, "");
+    if fill_resume(db, &mut msg).is_err() {
+        msg.content = "Resume".to_string();
+    }
+    msg
+}
+
+/// Store a message held for approval whatever the agent's autonomy, for
+/// instructions arriving from links anything on the machine can open.
+pub(crate) fn hold_message(db: &Database, mut msg: Message) -> Result<Message, String> {
+    mark_awaiting_approval(&mut msg);
     db.insert_message(&msg).map_err(|e| e.to_string())?;
+    Ok(msg)
+}
+
+fn mark_awaiting_approval(msg: &mut Message) {
+    let metadata = msg.metadata.get_or_insert_with(|| serde_json::json!({}));
+    if !metadata.is_object() {
+        *metadata = serde_json::json!({});
+    }
+    metadata["awaiting_approval"] = serde_json::json!(true);
+}
+
+/// Manual agents do nothing without approval: anything that starts work
+/// waits in the queue. Control messages (pause, cancel, status) go through.
+fn requires_approval(db: &Database, msg: &Message) -> Result<bool, String> {
+    if !matches!(msg.kind, MessageKind::Instruction | MessageKind::Resume) {
+        return Ok(false);
+    }
+    let config = db
+        .get_agent_config(&msg.agent_id)
+        .map_err(|e| e.to_string())?;
+    Ok(config.is_some_and(|config| matches!(config.autonomy_level, AutonomyLevel::Manual)))
+}
+
+/// Side effects of a message entering the delivery queue.
+fn dispatch_message(db: &Database, msg: Message) -> Result<Message, String> {
+    let _span = traces::message_span(&msg, "queue").entered();
+    tracing::debug!("Message queued");
+    let agent_id = msg.agent_id.clone();
+    let run_key = msg.run_key().map(str::to_string);
 
     match msg.kind {
         MessageKind::Instruction | MessageKind::Resume => {
-            if let Err(error) = db.start_instruction_run(&agent_id, &msg.content) {
-                log::warn!("Failed to start run for {}: {}", agent_id, error);
+            match db.start_instruction_run(&agent_id, Some(&msg.id), &msg.content) {
+                Ok(run) => {
+                    if let Some(source) = SourceItemRef::from_metadata(msg.metadata.as_ref()) {
+                        if run.source_item.is_none() {
+                            if let Err(error) = db.set_run_source_item(&run.id, &source) {
+                                tracing::warn!(
+                                    "Failed to link run {} to source item: {}",
+                                    run.id,
+                                    error
+                                );
+                            }
+                        }
+                    }
+                }
+                Err(error) => tracing::warn!("Failed to start run for {}: {}", agent_id, error),
             }
             let _ = db.update_agent_status(&agent_id, &AgentStatus::Running);
         }
         MessageKind::Pause => {
-            if let Err(error) = db.append_run_output(&agent_id, "pause", &msg.content) {
-                log::warn!("Failed to append pause output for {}: {}", agent_id, error);
+            if let Err(error) =
+                db.append_run_output_to(&agent_id, run_key.as_deref(), "pause", &msg.content)
+            {
+                tracing::warn!("Failed to append pause output for {}: {}", agent_id, error);
             }
             let _ = db.update_agent_status(&agent_id, &AgentStatus::Blocked);
         }
         MessageKind::Cancel => {
-            if let Err(error) = db.append_run_output(&agent_id, "cancel", &msg.content) {
-                log::warn!("Failed to append cancel output for {}: {}", agent_id, error);
+            if let Err(error) =
+                db.append_run_output_to(&agent_id, run_key.as_deref(), "cancel", &msg.content)
+            {
+                tracing::warn!("Failed to append cancel output for {}: {}", agent_id, error);
             }
-            if let Err(error) = db.finalize_latest_run(
+            if let Err(error) = db.finalize_run_for(
                 &agent_id,
+                run_key.as_deref(),
                 RunStatus::Failed,
                 Some("Cancelled by operator".to_string()),
             ) {
-                log::warn!(
+                tracing::warn!(
                     "Failed to finalize cancelled run for {}: {}",
                     agent_id,
                     error
@@ -501,14 +2679,44 @@ pub fn send_message(
         _ => {}
     }
 
-    // Ensure the adapter loop is running so queued messages are picked up.
-    if let Err(error) = ensure_adapter_started(db.inner(), &agent_id, true) {
-        log::warn!("Failed to start adapter for {}: {}", agent_id, error);
-    }
+    Ok(msg)
+}
+
+/// Outbound messages held for approval, for one agent or all of them.
+#[tauri::command]
+pub fn list_pending_approvals(
+    db: State<'_, Arc<Database>>,
+    agent_id: Option<String>,
+) -> Result<Vec<Message>, CommandError> {
+    db.get_messages_awaiting_approval(agent_id.as_deref())
+        .map_err(CommandError::from)
+}
 
+/// Release a held instruction to the agent.
+#[tauri::command]
+pub fn approve_message(
+    db: State<'_, Arc<Database>>,
+    message_id: String,
+) -> Result<Message, CommandError> {
+    let msg = db
+        .approve_held_message(&message_id)?
+        .ok_or_else(|| format!("Message is not awaiting approval: {}", message_id))?;
+    let msg = dispatch_message(db.inner(), msg)?;
+    start_adapter_for_delivery(db.inner(), &msg.agent_id);
     Ok(msg)
 }
 
+/// Discard a held instruction; it is kept in the thread marked rejected.
+#[tauri::command]
+pub fn reject_message(
+    db: State<'_, Arc<Database>>,
+    message_id: String,
+) -> Result<Message, CommandError> {
+    db.reject_held_message(&message_id)?
+        .ok_or_else(|| format!("Message is not awaiting approval: {}", message_id))
+        .map_err(CommandError::from)
+}
+
 /// Get conversation thread for an agent
 #[tauri::command]
 pub fn get_conversation(
@@ -516,25 +2724,98 @@ pub fn get_conversation(
     agent_id: String,
     limit: Option<usize>,
     before_created_at: Option<String>,
-) -> Result<ConversationThread, String> {
+) -> Result<ConversationThread, CommandError> {
     let limit = limit.unwrap_or(50).clamp(1, 500);
     let query_limit = limit.saturating_add(1);
-    let mut messages = db
-        .get_messages_for_agent_before(&agent_id, query_limit, before_created_at.as_deref())
-        .map_err(|e| e.to_string())?;
+    let mut messages =
+        db.get_messages_for_agent_before(&agent_id, query_limit, before_created_at.as_deref())?;
+
+    let has_more = messages.len() > limit;
+    if has_more {
+        messages.truncate(limit);
+    }
+
+    // Reverse so oldest first for display
+    messages.reverse();
+
+    Ok(ConversationThread {
+        agent_id,
+        messages,
+        has_more,
+    })
+}
+
+/// Render an agent's message history, with run boundaries and file changes
+/// interleaved, into a Markdown or JSON transcript file.
+#[tauri::command]
+pub fn export_conversation(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    format: TranscriptFormat,
+    range: Option<TranscriptRange>,
+    destination_path: String,
+) -> Result<TranscriptExportResult, CommandError> {
+    let destination_path = destination_path.trim();
+    if destination_path.is_empty() {
+        return Err(CommandError::invalid_input("destination path is required"));
+    }
+
+    let agent = db
+        .get_agent(&agent_id)?
+        .ok_or_else(|| CommandError::not_found("Agent not found"))?;
+
+    let range = range.unwrap_or_default();
+    let since = range.since.map(|t| t.to_rfc3339());
+    let until = range.until.map(|t| t.to_rfc3339());
+    let messages =
+        db.get_messages_for_agent_between(&agent_id, since.as_deref(), until.as_deref())?;
+    let runs = db.get_runs_for_agent_between(&agent_id, since.as_deref(), until.as_deref())?;
+
+    let transcript = export::build_transcript(&agent, messages, &runs, &range);
+    let rendered = match format {
+        TranscriptFormat::Markdown => export::render_markdown(&transcript),
+        TranscriptFormat::Json => export::render_json(&transcript)?,
+    };
+    let size_bytes = export::write_export_file(destination_path, &rendered)?;
+
+    Ok(TranscriptExportResult {
+        path: destination_path.to_string(),
+        format,
+        entry_count: transcript.entries.len(),
+        size_bytes,
+        completed_at: Utc::now().to_rfc3339(),
+    })
+}
 
-    let has_more = messages.len() > limit;
-    if has_more {
-        messages.truncate(limit);
-    }
+/// Render a single run as a Markdown report. The report is always returned;
+/// when `destination_path` is given it is also written to disk.
+#[tauri::command]
+pub fn export_run_report(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+    destination_path: Option<String>,
+) -> Result<RunReport, CommandError> {
+    let run = db
+        .get_run(&run_id)?
+        .ok_or_else(|| CommandError::not_found("Run not found"))?;
+    let agent = db
+        .get_agent(&run.agent_id)?
+        .ok_or_else(|| CommandError::not_found("Agent not found"))?;
 
-    // Reverse so oldest first for display
-    messages.reverse();
+    let markdown = export::render_run_report(&agent, &run);
+    let destination_path = destination_path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    let size_bytes = match &destination_path {
+        Some(path) => export::write_export_file(path, &markdown)?,
+        None => markdown.len() as u64,
+    };
 
-    Ok(ConversationThread {
-        agent_id,
-        messages,
-        has_more,
+    Ok(RunReport {
+        run_id,
+        markdown,
+        path: destination_path,
+        size_bytes,
     })
 }
 
@@ -547,66 +2828,103 @@ pub fn receive_message(
     content: String,
     metadata: Option<serde_json::Value>,
     reply_to: Option<String>,
-) -> Result<Message, String> {
+) -> Result<Message, CommandError> {
     let mut msg = Message::from_agent(&agent_id, kind, &content);
     msg.metadata = metadata;
     msg.reply_to = reply_to;
+    record_agent_message(db.inner(), msg).map_err(CommandError::from)
+}
+
+/// Store a message from an agent and apply its run output and status side
+/// effects.
+pub(crate) fn record_agent_message(db: &Database, msg: Message) -> Result<Message, String> {
+    let _span = traces::message_span(&msg, "receive").entered();
+    tracing::debug!("Message received");
+    let agent_id = msg.agent_id.clone();
     db.insert_message(&msg).map_err(|e| e.to_string())?;
+    let run_key = msg.run_key().map(str::to_string);
 
     match msg.kind {
         MessageKind::Output => {
-            if let Err(error) = db.append_run_output(&agent_id, "output", &msg.content) {
-                log::warn!("Failed to append output for {}: {}", agent_id, error);
+            if let Err(error) =
+                db.append_run_output_to(&agent_id, run_key.as_deref(), "output", &msg.content)
+            {
+                tracing::warn!("Failed to append output for {}: {}", agent_id, error);
             }
         }
         MessageKind::StatusUpdate => {
-            if let Err(error) = db.append_run_output(&agent_id, "status_update", &msg.content) {
-                log::warn!("Failed to append status update for {}: {}", agent_id, error);
+            if let Err(error) = db.append_run_output_to(
+                &agent_id,
+                run_key.as_deref(),
+                "status_update",
+                &msg.content,
+            ) {
+                tracing::warn!("Failed to append status update for {}: {}", agent_id, error);
             }
         }
         MessageKind::Heartbeat => {
             if !msg.content.trim().is_empty() {
-                if let Err(error) = db.append_run_output(&agent_id, "heartbeat", &msg.content) {
-                    log::warn!("Failed to append heartbeat for {}: {}", agent_id, error);
+                if let Err(error) = db.append_run_output_to(
+                    &agent_id,
+                    run_key.as_deref(),
+                    "heartbeat",
+                    &msg.content,
+                ) {
+                    tracing::warn!("Failed to append heartbeat for {}: {}", agent_id, error);
                 }
             }
         }
         MessageKind::Error => {
-            if let Err(error) = db.append_run_output(&agent_id, "error", &msg.content) {
-                log::warn!("Failed to append error output for {}: {}", agent_id, error);
-            }
             if let Err(error) =
-                db.finalize_latest_run(&agent_id, RunStatus::Failed, Some(msg.content.clone()))
+                db.append_run_output_to(&agent_id, run_key.as_deref(), "error", &msg.content)
             {
-                log::warn!("Failed to finalize failed run for {}: {}", agent_id, error);
+                tracing::warn!("Failed to append error output for {}: {}", agent_id, error);
+            }
+            if let Err(error) = db.finalize_run_for(
+                &agent_id,
+                run_key.as_deref(),
+                RunStatus::Failed,
+                Some(msg.content.clone()),
+            ) {
+                tracing::warn!("Failed to finalize failed run for {}: {}", agent_id, error);
             }
         }
         MessageKind::Blocked => {
-            if let Err(error) = db.append_run_output(&agent_id, "blocked", &msg.content) {
-                log::warn!(
+            if let Err(error) =
+                db.append_run_output_to(&agent_id, run_key.as_deref(), "blocked", &msg.content)
+            {
+                tracing::warn!(
                     "Failed to append blocked output for {}: {}",
                     agent_id,
                     error
                 );
             }
-            if let Err(error) =
-                db.finalize_latest_run(&agent_id, RunStatus::NeedsReview, Some(msg.content.clone()))
-            {
-                log::warn!("Failed to finalize blocked run for {}: {}", agent_id, error);
+            if let Err(error) = db.finalize_run_for(
+                &agent_id,
+                run_key.as_deref(),
+                RunStatus::NeedsReview,
+                Some(msg.content.clone()),
+            ) {
+                tracing::warn!("Failed to finalize blocked run for {}: {}", agent_id, error);
             }
         }
         MessageKind::Completed => {
-            if let Err(error) = db.append_run_output(&agent_id, "completed", &msg.content) {
-                log::warn!(
+            if let Err(error) =
+                db.append_run_output_to(&agent_id, run_key.as_deref(), "completed", &msg.content)
+            {
+                tracing::warn!(
                     "Failed to append completion output for {}: {}",
                     agent_id,
                     error
                 );
             }
-            if let Err(error) =
-                db.finalize_latest_run(&agent_id, RunStatus::Completed, Some(msg.content.clone()))
-            {
-                log::warn!(
+            if let Err(error) = db.finalize_run_for(
+                &agent_id,
+                run_key.as_deref(),
+                RunStatus::Completed,
+                Some(msg.content.clone()),
+            ) {
+                tracing::warn!(
                     "Failed to finalize completed run for {}: {}",
                     agent_id,
                     error
@@ -625,6 +2943,15 @@ pub fn receive_message(
         MessageKind::Completed => Some(AgentStatus::Completed),
         _ => None,
     };
+    // With concurrent runs, finishing one run leaves the agent busy with the rest.
+    let new_status = match new_status {
+        Some(AgentStatus::Completed)
+            if !db.get_active_runs(&agent_id).unwrap_or_default().is_empty() =>
+        {
+            Some(AgentStatus::Running)
+        }
+        other => other,
+    };
     if let Some(status) = new_status {
         let _ = db.update_agent_status(&agent_id, &status);
     }
@@ -637,10 +2964,9 @@ pub fn receive_message(
 pub fn poll_pending_messages(
     db: State<'_, Arc<Database>>,
     agent_id: String,
-) -> Result<Vec<Message>, String> {
-    let messages = db
-        .get_pending_messages(&agent_id)
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<Message>, CommandError> {
+    agents::expire_stale_messages(&db, &agent_id);
+    let messages = db.get_pending_messages(&agent_id)?;
     // Mark them as delivered
     for msg in &messages {
         let _ = db.mark_delivered(&msg.id);
@@ -648,21 +2974,411 @@ pub fn poll_pending_messages(
     Ok(messages)
 }
 
+/// A message waiting in an agent's queue.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QueuedMessage {
+    pub position: usize, // 1 is delivered first
+    pub message: Message,
+    pub age_seconds: i64,
+    pub estimated_delivery_at: Option<DateTime<Utc>>, // None until the adapter is up
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AgentQueue {
+    pub agent_id: String,
+    pub adapter_running: bool,
+    pub delivery_interval_ms: u64,
+    pub messages: Vec<QueuedMessage>,
+    pub held_for_approval: usize, // waiting on `approve_message`, not in line yet
+}
+
+/// What is about to reach an agent, in delivery order. A running adapter
+/// takes the whole queue on its next look, so every message shares one
+/// estimate: the next poll, after any pending restart backoff.
+#[tauri::command]
+pub fn get_agent_queue(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+) -> Result<AgentQueue, CommandError> {
+    agent_queue(db.inner(), &agent_id)
+}
+
+fn agent_queue(db: &Database, agent_id: &str) -> Result<AgentQueue, CommandError> {
+    db.get_agent(agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+    let interval = db
+        .get_adapter_config(agent_id)?
+        .map(|config| agents::delivery_interval(&config.adapter_type))
+        .unwrap_or(agents::PENDING_POLL_FALLBACK);
+    let runtime = adapter_runtime_snapshot(agent_id);
+    let adapter_running = runtime
+        .as_ref()
+        .is_some_and(|state| state.started && !state.idle_stopped);
+
+    let now = db.now();
+    let estimate = adapter_running.then(|| {
+        let backoff = runtime
+            .as_ref()
+            .and_then(|state| state.retry_after_seconds(db.clock().instant()))
+            .unwrap_or(0);
+        now + chrono::Duration::seconds(backoff as i64)
+            + chrono::Duration::milliseconds(interval.as_millis() as i64)
+    });
+    let messages = db
+        .get_pending_messages(agent_id)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, message)| QueuedMessage {
+            position: index + 1,
+            age_seconds: (now - message.created_at).num_seconds().max(0),
+            estimated_delivery_at: estimate,
+            message,
+        })
+        .collect();
+    Ok(AgentQueue {
+        agent_id: agent_id.to_string(),
+        adapter_running,
+        delivery_interval_ms: interval.as_millis() as u64,
+        messages,
+        held_for_approval: db.get_messages_awaiting_approval(Some(agent_id))?.len(),
+    })
+}
+
+// ── Runs ────────────────────────────────────────────────────────────────────
+
+const MAX_RUN_LABEL_CHARS: usize = 48;
+
+/// Trim, lowercase, and dedupe labels so "Release" and "release " filter together.
+fn normalize_labels(labels: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for label in labels {
+        let label = label.trim().to_lowercase();
+        if label.is_empty() {
+            continue;
+        }
+        if label.chars().count() > MAX_RUN_LABEL_CHARS {
+            return Err(format!(
+                "Label '{}' is longer than {} characters",
+                label, MAX_RUN_LABEL_CHARS
+            ));
+        }
+        if !normalized.contains(&label) {
+            normalized.push(label);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Replace a run's labels.
+#[tauri::command]
+pub fn set_run_labels(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+    labels: Vec<String>,
+) -> Result<Run, CommandError> {
+    let labels = normalize_labels(labels)?;
+    db.set_run_labels(&run_id, &labels)?;
+    db.get_run(&run_id)?
+        .ok_or_else(|| CommandError::not_found("Run not found"))
+}
+
+/// Run history filtered to one label, optionally scoped to an agent.
+#[tauri::command]
+pub fn list_runs_by_label(
+    db: State<'_, Arc<Database>>,
+    label: String,
+    agent_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<Run>, CommandError> {
+    let label = label.trim().to_lowercase();
+    if label.is_empty() {
+        return Err(CommandError::invalid_input("Label is required"));
+    }
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    db.get_runs_by_label(&label, agent_id.as_deref(), limit)
+        .map_err(CommandError::from)
+}
+
+const MAX_RUN_COMMENT_CHARS: usize = 10_000;
+
+fn normalize_comment_body(body: &str) -> Result<String, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("Comment cannot be empty".to_string());
+    }
+    if body.chars().count() > MAX_RUN_COMMENT_CHARS {
+        return Err(format!(
+            "Comment is longer than {} characters",
+            MAX_RUN_COMMENT_CHARS
+        ));
+    }
+    Ok(body.to_string())
+}
+
+#[tauri::command]
+pub fn list_run_comments(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+) -> Result<Vec<RunComment>, CommandError> {
+    db.list_run_comments(&run_id).map_err(CommandError::from)
+}
+
+/// Leave a review note on a run; `output_index` anchors it to one output.
+#[tauri::command]
+pub fn add_run_comment(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+    body: String,
+    output_index: Option<usize>,
+) -> Result<RunComment, CommandError> {
+    add_comment(db.inner(), &run_id, &body, output_index)
+}
+
+fn add_comment(
+    db: &Database,
+    run_id: &str,
+    body: &str,
+    output_index: Option<usize>,
+) -> Result<RunComment, CommandError> {
+    let run = db
+        .get_run(run_id)?
+        .ok_or_else(|| CommandError::not_found("Run not found"))?;
+    if let Some(index) = output_index {
+        if index >= run.outputs.len() {
+            return Err(CommandError::invalid_input(format!(
+                "Run has {} outputs; there is no output {}",
+                run.outputs.len(),
+                index
+            )));
+        }
+    }
+    let comment = RunComment::new(run_id, output_index, &normalize_comment_body(body)?);
+    db.save_run_comment(&comment)?;
+    Ok(comment)
+}
+
+#[tauri::command]
+pub fn edit_run_comment(
+    db: State<'_, Arc<Database>>,
+    comment_id: String,
+    body: String,
+) -> Result<RunComment, CommandError> {
+    let mut comment = db
+        .get_run_comment(&comment_id)?
+        .ok_or_else(|| CommandError::not_found("Comment not found"))?;
+    comment.body = normalize_comment_body(&body)?;
+    comment.updated_at = Utc::now();
+    db.save_run_comment(&comment)?;
+    Ok(comment)
+}
+
+#[tauri::command]
+pub fn delete_run_comment(
+    db: State<'_, Arc<Database>>,
+    comment_id: String,
+) -> Result<(), CommandError> {
+    db.delete_run_comment(&comment_id)
+        .map_err(CommandError::from)
+}
+
+/// Resolve a NeedsReview run. Approve completes it, reject fails it, and
+/// request-changes closes it and sends the notes back as a new instruction.
+#[tauri::command]
+pub fn review_run(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+    decision: ReviewDecision,
+    notes: Option<String>,
+) -> Result<Run, CommandError> {
+    apply_run_review(db.inner(), &run_id, decision, notes)
+}
+
+fn apply_run_review(
+    db: &Arc<Database>,
+    run_id: &str,
+    decision: ReviewDecision,
+    notes: Option<String>,
+) -> Result<Run, CommandError> {
+    let mut run = db
+        .get_run(run_id)?
+        .ok_or_else(|| CommandError::not_found("Run not found"))?;
+    if run.status != RunStatus::NeedsReview {
+        return Err(CommandError::invalid_input(format!(
+            "Run is {}, not awaiting review",
+            run.status.as_str().replace('_', " ")
+        )));
+    }
+
+    let notes = notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+    if decision == ReviewDecision::RequestChanges && notes.is_none() {
+        return Err(CommandError::invalid_input(
+            "Requesting changes needs notes describing what to change",
+        ));
+    }
+
+    let now = Utc::now();
+    run.status = match decision {
+        ReviewDecision::Approve => RunStatus::Completed,
+        ReviewDecision::RequestChanges | ReviewDecision::Reject => RunStatus::Failed,
+    };
+    run.ended_at.get_or_insert(now);
+    run.outputs.push(RunOutput {
+        kind: "review".to_string(),
+        content: match &notes {
+            Some(notes) => format!("{}: {}", decision.as_str(), notes),
+            None => decision.as_str().to_string(),
+        },
+        timestamp: now,
+    });
+    run.review = Some(RunReview {
+        decision,
+        notes: notes.clone(),
+        reviewed_at: now,
+        follow_up_message_id: None,
+    });
+    // Persist before queueing a follow-up so the new instruction opens a fresh run.
+    db.update_run(&run)?;
+
+    match decision {
+        ReviewDecision::RequestChanges => {
+            let mut follow_up = Message::to_agent(
+                &run.agent_id,
+                MessageKind::Instruction,
+                &format!(
+                    "Changes requested on your previous run:\n\n{}",
+                    notes.unwrap_or_default()
+                ),
+            );
+            follow_up.metadata = Some(serde_json::json!({ "review_of_run": run.id }));
+            let follow_up = enqueue_message(db, follow_up)?;
+            if let Some(review) = run.review.as_mut() {
+                review.follow_up_message_id = Some(follow_up.id);
+            }
+            db.update_run(&run)?;
+        }
+        ReviewDecision::Approve | ReviewDecision::Reject => {
+            db.update_agent_status(&run.agent_id, &AgentStatus::Idle)?;
+        }
+    }
+
+    Ok(run)
+}
+
+// ── Demo Workspace ──────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_demo_workspace(
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<DemoWorkspace>, CommandError> {
+    seed::get_demo_workspace(db.inner()).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn create_demo_workspace(db: State<'_, Arc<Database>>) -> Result<DemoWorkspace, CommandError> {
+    let workspace = seed::create_demo_workspace(db.inner())?;
+    for agent_id in &workspace.agent_ids {
+        if let Err(error) = ensure_adapter_started(db.inner(), agent_id, false) {
+            tracing::warn!("Failed to start demo adapter for {}: {}", agent_id, error);
+        }
+    }
+    Ok(workspace)
+}
+
+/// Remove the demo projects and agents. Returns false when there was no
+/// demo workspace to remove.
+#[tauri::command]
+pub fn delete_demo_workspace(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+) -> Result<bool, CommandError> {
+    let Some(workspace) = seed::get_demo_workspace(db.inner())? else {
+        return Ok(false);
+    };
+    // Every agent in the demo projects goes, including ones added since.
+    let agent_ids: Vec<String> = db
+        .list_agents()?
+        .into_iter()
+        .filter(|agent| workspace.project_ids.contains(&agent.project_id))
+        .map(|agent| agent.id)
+        .collect();
+    // Configs are read first and adapters stopped after the rows are gone,
+    // so the supervisor has nothing left to restart.
+    let configs: Vec<(String, AdapterConfig)> = agent_ids
+        .iter()
+        .filter_map(|agent_id| {
+            db.get_adapter_config(agent_id)
+                .ok()
+                .flatten()
+                .map(|config| (agent_id.clone(), config))
+        })
+        .collect();
+    seed::delete_demo_workspace(db.inner())?;
+    for (agent_id, config) in configs {
+        if let Err(error) = agents::create_adapter(&config).stop(&agent_id) {
+            tracing::warn!("Failed stopping demo adapter for {}: {}", agent_id, error);
+        }
+        clear_adapter_runtime(&agent_id);
+    }
+    for agent_id in &agent_ids {
+        watcher.agent_removed(agent_id);
+    }
+    if let Some(logs) = agent_logs::installed() {
+        for agent_id in &agent_ids {
+            logs.remove(agent_id);
+        }
+    }
+    Ok(true)
+}
+
 // ── Adapter Config ──────────────────────────────────────────────────────────
 
+/// The stored config with env values in the clear, for the adapter edit
+/// form's reveal action. Every other read path masks secrets.
 #[tauri::command]
-pub fn set_adapter_config(
+pub fn get_adapter_config_for_edit(
     db: State<'_, Arc<Database>>,
     agent_id: String,
+) -> Result<Option<AdapterConfig>, CommandError> {
+    db.get_adapter_config(&agent_id).map_err(CommandError::from)
+}
+
+/// Check a config before saving it. With `probe`, webhook endpoints are
+/// also asked whether they answer.
+#[tauri::command]
+pub async fn validate_adapter_config(
     config: AdapterConfig,
-) -> Result<(), String> {
-    if let Some(existing_config) = db
-        .get_adapter_config(&agent_id)
-        .map_err(|e| e.to_string())?
-    {
+    probe: Option<bool>,
+) -> Result<ConfigValidation, CommandError> {
+    let mut validation = validate::validate_config(&config);
+    if probe.unwrap_or(false) {
+        validate::probe_endpoint(&config, &mut validation).await;
+    }
+    Ok(validation)
+}
+
+/// Save and restart the adapter. Configs with validation errors are
+/// refused; warnings are returned so the form can show them.
+#[tauri::command]
+pub fn set_adapter_config(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    mut config: AdapterConfig,
+) -> Result<ConfigValidation, CommandError> {
+    let existing_config = db.get_adapter_config(&agent_id)?;
+    redaction::restore_masked_env(&mut config, existing_config.as_ref());
+    let validation = validate::validate_config(&config);
+    if validation.has_errors() {
+        return Err(CommandError::invalid_input(format!(
+            "Adapter config not saved: {}",
+            validation.error_summary()
+        )));
+    }
+    if let Some(existing_config) = existing_config {
         let existing = agents::create_adapter(&existing_config);
         if let Err(error) = existing.stop(&agent_id) {
-            log::warn!(
+            tracing::warn!(
                 "Failed stopping existing adapter for {} before reconfigure: {}",
                 agent_id,
                 error
@@ -670,32 +3386,54 @@ pub fn set_adapter_config(
         }
     }
 
-    db.set_adapter_config(&agent_id, &config)
-        .map_err(|e| e.to_string())?;
+    db.set_adapter_config(&agent_id, &config)?;
 
     clear_adapter_runtime(&agent_id);
 
     if let Err(error) = ensure_adapter_started(db.inner(), &agent_id, true) {
-        log::warn!("Failed to start adapter for {}: {}", agent_id, error);
+        tracing::warn!("Failed to start adapter for {}: {}", agent_id, error);
     }
 
-    Ok(())
+    Ok(validation)
+}
+
+/// Save an agent's session since `since` (RFC 3339; default: all of it) as a
+/// transcript a simulation adapter can replay.
+#[tauri::command]
+pub fn record_simulation_transcript(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    path: String,
+    since: Option<String>,
+) -> Result<RecordedTranscript, CommandError> {
+    let transcript = simulation::record_transcript(db.inner(), &agent_id, since.as_deref())?;
+    if transcript.turns.is_empty() {
+        return Err(CommandError::invalid_input(
+            "No instructions to record for this agent",
+        ));
+    }
+    let json = serde_json::to_string_pretty(&transcript).map_err(|e| e.to_string())?;
+    let expanded = shellexpand::tilde(&path);
+    std::fs::write(expanded.as_ref(), json)
+        .map_err(|e| format!("Failed to write transcript {}: {}", path, e))?;
+    Ok(RecordedTranscript {
+        path: expanded.into_owned(),
+        turns: transcript.turns.len(),
+        steps: transcript.turns.iter().map(|turn| turn.steps.len()).sum(),
+    })
 }
 
 #[tauri::command]
 pub fn get_adapter_health(
     db: State<'_, Arc<Database>>,
     agent_id: String,
-) -> Result<Option<agents::AdapterHealth>, String> {
-    let Some(config) = db
-        .get_adapter_config(&agent_id)
-        .map_err(|e| e.to_string())?
-    else {
+) -> Result<Option<agents::AdapterHealth>, CommandError> {
+    let Some(config) = db.get_adapter_config(&agent_id)? else {
         return Ok(None);
     };
 
     if let Err(error) = ensure_adapter_started(db.inner(), &agent_id, false) {
-        log::warn!(
+        tracing::warn!(
             "Failed to ensure adapter running during health check for {}: {}",
             agent_id,
             error
@@ -722,50 +3460,273 @@ pub fn get_adapter_health(
             health.consecutive_failures = Some(state.consecutive_failures);
         }
 
-        if let Some(retry_after) = state.retry_after_seconds() {
-            health.retry_after_seconds = Some(retry_after);
-        }
+        let retry_after = state.retry_after_seconds(db.clock().instant());
+        if let Some(retry_after) = retry_after {
+            health.retry_after_seconds = Some(retry_after);
+        }
+
+        if let Some(last_error) = state.last_error.clone() {
+            let supervisor_summary = match retry_after {
+                Some(retry_after) => format!(
+                    "Supervisor: {} start failures. Next retry in {}s.",
+                    state.consecutive_failures, retry_after
+                ),
+                None => format!(
+                    "Supervisor: {} start failures recorded.",
+                    state.consecutive_failures
+                ),
+            };
+            health.last_error = Some(last_error);
+            health.details = Some(match health.details {
+                Some(details) if !details.trim().is_empty() => {
+                    format!("{}\n\n{}", supervisor_summary, details)
+                }
+                _ => supervisor_summary,
+            });
+        }
+    }
+
+    Ok(Some(health))
+}
+
+/// One row per configured adapter from the supervisor's cached state. Never
+/// runs live tmux/HTTP checks, so it is cheap enough to poll.
+#[tauri::command]
+pub fn get_all_adapter_health(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<agents::AdapterHealthSummary>, CommandError> {
+    let agents = db.list_agents()?;
+    let configs = db.list_adapter_configs()?;
+    let runtime = adapter_runtime()
+        .lock()
+        .map_err(|_| "adapter runtime lock poisoned".to_string())?;
+    let now = db.clock().instant();
+
+    Ok(agents
+        .into_iter()
+        .filter_map(|agent| {
+            let config = configs.get(&agent.id)?;
+            let state = runtime.get(&agent.id).cloned().unwrap_or_default();
+            Some(agents::AdapterHealthSummary {
+                adapter_type: config.adapter_type.clone(),
+                connected: state.started && state.connected,
+                last_heartbeat: state
+                    .last_heartbeat
+                    .clone()
+                    .or_else(|| agent.last_active_at.map(|at| at.to_rfc3339())),
+                last_checked_at: state.last_checked_at,
+                consecutive_failures: state.consecutive_failures,
+                retry_after_seconds: state.retry_after_seconds(now),
+                last_error: state.last_error,
+                agent_name: agent.name,
+                agent_id: agent.id,
+            })
+        })
+        .collect())
+}
+
+/// DB size, row counts, slow queries, watcher backlog and adapter threads,
+/// for attaching real numbers to a performance report.
+#[tauri::command]
+pub fn get_diagnostics(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherStats>,
+) -> Result<diagnostics::Diagnostics, CommandError> {
+    let configs = db.list_adapter_configs()?;
+    let running: Vec<AdapterType> = adapter_runtime()
+        .lock()
+        .map_err(|_| "adapter runtime lock poisoned".to_string())?
+        .iter()
+        .filter(|(_, state)| state.started)
+        .filter_map(|(agent_id, _)| configs.get(agent_id))
+        .map(|config| config.adapter_type.clone())
+        .collect();
+    diagnostics::collect(&db, &watcher, &running).map_err(CommandError::from)
+}
+
+/// What the new adapter is told about the agent's earlier work on handoff.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HandoffContextStrategy {
+    None,           // start fresh
+    LastRunSummary, // only the most recent run's summary
+    #[default]
+    Condensed, // the last run's summary plus recent instructions
+}
+
+const HANDOFF_INSTRUCTION_COUNT: usize = 5;
+const HANDOFF_INSTRUCTION_CHARS: usize = 500;
+
+#[derive(serde::Serialize)]
+pub struct HandoffResult {
+    pub agent_id: String,
+    pub from: Option<AdapterType>,
+    pub to: AdapterType,
+    pub context_message: Option<Message>, // first message the new adapter receives
+}
+
+/// Move an agent to a different adapter (e.g. Claude Code to Codex): stop the
+/// old one, start the new one, and send it a condensed context so the switch
+/// doesn't lose the thread.
+#[tauri::command]
+pub fn handoff_agent(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    new_adapter_config: AdapterConfig,
+    context_strategy: Option<HandoffContextStrategy>,
+) -> Result<HandoffResult, CommandError> {
+    handoff(
+        db.inner(),
+        &agent_id,
+        new_adapter_config,
+        context_strategy.unwrap_or_default(),
+    )
+}
+
+fn handoff(
+    db: &Arc<Database>,
+    agent_id: &str,
+    mut config: AdapterConfig,
+    strategy: HandoffContextStrategy,
+) -> Result<HandoffResult, CommandError> {
+    let agent = db
+        .get_agent(agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))?;
+
+    // Gather the context before the new adapter starts adding to the history.
+    let instructions: Vec<Message> = db
+        .get_messages_for_agent(agent_id, 100)?
+        .into_iter()
+        .filter(|msg| {
+            msg.direction == MessageDirection::ToAgent && msg.kind == MessageKind::Instruction
+        })
+        .take(HANDOFF_INSTRUCTION_COUNT)
+        .collect();
+    let last_run = db
+        .get_runs_for_agent(agent_id, 10)?
+        .into_iter()
+        .find(|run| run.summary.is_some());
+
+    let previous = db.get_adapter_config(agent_id)?;
+    redaction::restore_masked_env(&mut config, previous.as_ref());
+    if let Some(previous) = &previous {
+        if let Err(error) = agents::create_adapter(previous).stop(agent_id) {
+            tracing::warn!(
+                "Failed stopping adapter for {} before handoff: {}",
+                agent_id,
+                error
+            );
+        }
+    }
+
+    // Claude Code sessions start in `command` (see ClaudeCodeAdapter::new).
+    if config.adapter_type == AdapterType::ClaudeCode && config.command.is_none() {
+        config.command = agent.working_directory.clone();
+    }
+    db.set_adapter_config(agent_id, &config)?;
+    clear_adapter_runtime(agent_id);
+    if let Err(error) = ensure_adapter_started(db, agent_id, true) {
+        tracing::warn!(
+            "Failed to start adapter for {} after handoff: {}",
+            agent_id,
+            error
+        );
+    }
+
+    let from = previous.map(|previous| previous.adapter_type);
+    let transition = serde_json::json!({ "from": from, "to": config.adapter_type });
+    let mut note = Message::from_agent(
+        agent_id,
+        MessageKind::StatusUpdate,
+        &format!(
+            "Handed off from {} to {}",
+            from.as_ref()
+                .map_or("no adapter".to_string(), adapter_label),
+            adapter_label(&config.adapter_type)
+        ),
+    );
+    note.metadata = Some(serde_json::json!({ "source": "handoff", "handoff": transition }));
+    db.insert_message(&note)?;
+
+    let context_message = match handoff_context(strategy, &instructions, last_run.as_ref()) {
+        Some(content) => {
+            let mut msg = Message::to_agent(agent_id, MessageKind::Instruction, &content);
+            msg.metadata = Some(serde_json::json!({ "handoff": transition }));
+            Some(enqueue_message(db, msg)?)
+        }
+        None => None,
+    };
+
+    Ok(HandoffResult {
+        agent_id: agent_id.to_string(),
+        from,
+        to: config.adapter_type,
+        context_message,
+    })
+}
+
+fn adapter_label(adapter_type: &AdapterType) -> String {
+    serde_json::to_value(adapter_type)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
 
-        let retry_after = state.retry_after_seconds();
-        if let Some(last_error) = state.last_error.clone() {
-            let supervisor_summary = match retry_after {
-                Some(retry_after) => format!(
-                    "Supervisor: {} start failures. Next retry in {}s.",
-                    state.consecutive_failures, retry_after
-                ),
-                None => format!(
-                    "Supervisor: {} start failures recorded.",
-                    state.consecutive_failures
-                ),
+/// `instructions` are newest first, as stored; the message lists them in
+/// the order they were given.
+fn handoff_context(
+    strategy: HandoffContextStrategy,
+    instructions: &[Message],
+    last_run: Option<&Run>,
+) -> Option<String> {
+    let summary = last_run.and_then(|run| {
+        run.summary
+            .as_deref()
+            .filter(|summary| !summary.trim().is_empty())
+            .map(|summary| (run.status.as_str(), summary.trim()))
+    });
+    let instructions: &[Message] = match strategy {
+        HandoffContextStrategy::None => return None,
+        HandoffContextStrategy::LastRunSummary => &[],
+        HandoffContextStrategy::Condensed => instructions,
+    };
+    if summary.is_none() && instructions.is_empty() {
+        return None;
+    }
+
+    let mut content = String::from(
+        "You are taking over this agent's work from a previous session. Context so far:",
+    );
+    if let Some((status, summary)) = summary {
+        content.push_str(&format!("\n\nLast run ({}): {}", status, summary));
+    }
+    if !instructions.is_empty() {
+        content.push_str("\n\nRecent instructions, oldest first:");
+        for (index, msg) in instructions.iter().rev().enumerate() {
+            let text = msg.content.trim();
+            let text = match text.char_indices().nth(HANDOFF_INSTRUCTION_CHARS) {
+                Some((cut, _)) => format!("{}…", &text[..cut]),
+                None => text.to_string(),
             };
-            health.last_error = Some(last_error);
-            health.details = Some(match health.details {
-                Some(details) if !details.trim().is_empty() => {
-                    format!("{}\n\n{}", supervisor_summary, details)
-                }
-                _ => supervisor_summary,
-            });
+            content.push_str(&format!("\n{}. {}", index + 1, text));
         }
     }
-
-    Ok(Some(health))
+    content.push_str("\n\nWait for the next instruction before making changes.");
+    Some(content)
 }
 
 #[tauri::command]
 pub fn restart_adapter(
     db: State<'_, Arc<Database>>,
     agent_id: String,
-) -> Result<Option<agents::AdapterHealth>, String> {
-    let Some(config) = db
-        .get_adapter_config(&agent_id)
-        .map_err(|e| e.to_string())?
-    else {
+) -> Result<Option<agents::AdapterHealth>, CommandError> {
+    let Some(config) = db.get_adapter_config(&agent_id)? else {
         return Ok(None);
     };
 
     let adapter = agents::create_adapter(&config);
     if let Err(error) = adapter.stop(&agent_id) {
-        log::warn!(
+        tracing::warn!(
             "Failed stopping adapter for {} during restart: {}",
             agent_id,
             error
@@ -780,7 +3741,7 @@ pub fn restart_adapter(
     healthy_adapter
         .health_check(&agent_id)
         .map(Some)
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -788,42 +3749,104 @@ pub struct DatabaseSnapshotResult {
     pub path: String,
     pub size_bytes: u64,
     pub completed_at: String,
+    pub cloud_backup: Option<CloudBackupResult>,
 }
 
+/// Write a snapshot to `destination_path`, and with `upload_to_cloud` also
+/// send that file to the configured bucket. With a `passphrase` the file is
+/// encrypted; import asks for the same passphrase.
 #[tauri::command]
-pub fn export_database_snapshot(
+pub async fn export_database_snapshot(
     db: State<'_, Arc<Database>>,
     destination_path: String,
-) -> Result<DatabaseSnapshotResult, String> {
+    upload_to_cloud: Option<bool>,
+    passphrase: Option<String>,
+) -> Result<DatabaseSnapshotResult, CommandError> {
     let destination_path = destination_path.trim();
     if destination_path.is_empty() {
-        return Err("destination path is required".to_string());
+        return Err(CommandError::invalid_input("destination path is required"));
     }
 
-    db.export_snapshot_to_path(destination_path)?;
+    match passphrase
+        .as_deref()
+        .filter(|passphrase| !passphrase.is_empty())
+    {
+        Some(passphrase) => {
+            snapshot_crypto::validate_passphrase(passphrase)?;
+            let plaintext = db.scratch_file("snapshot")?;
+            db.export_snapshot_to_path(&plaintext.path_str())?;
+            snapshot_crypto::encrypt_file(
+                plaintext.path(),
+                std::path::Path::new(destination_path),
+                passphrase,
+            )?;
+        }
+        None => db.export_snapshot_to_path(destination_path)?,
+    }
     let size_bytes = std::fs::metadata(destination_path)
         .map(|metadata| metadata.len())
         .unwrap_or(0);
+    let cloud_backup = if upload_to_cloud.unwrap_or(false) {
+        let settings = backup::load_settings(db.inner())?;
+        Some(backup::upload_snapshot_file(&settings, destination_path).await?)
+    } else {
+        None
+    };
 
     Ok(DatabaseSnapshotResult {
         path: destination_path.to_string(),
         size_bytes,
         completed_at: Utc::now().to_rfc3339(),
+        cloud_backup,
     })
 }
 
+/// Replace the live database with a snapshot file and reset everything that
+/// caches its contents. Encrypted snapshots need their passphrase.
+fn restore_snapshot(
+    db: &Database,
+    watcher: &watchers::WatcherHandle,
+    source_path: &str,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
+    if snapshot_crypto::is_encrypted_file(std::path::Path::new(source_path)) {
+        let passphrase = passphrase
+            .filter(|passphrase| !passphrase.is_empty())
+            .ok_or("snapshot is encrypted; a passphrase is required")?;
+        let plaintext = db.scratch_file("snapshot")?;
+        snapshot_crypto::decrypt_file(
+            std::path::Path::new(source_path),
+            plaintext.path(),
+            passphrase,
+        )?;
+        db.import_snapshot_from_path(&plaintext.path_str())?;
+    } else {
+        db.import_snapshot_from_path(source_path)?;
+    }
+    db.reload_redactor().map_err(|e| e.to_string())?;
+    clear_all_adapter_runtime();
+    watcher.resync();
+    Ok(())
+}
+
 #[tauri::command]
 pub fn import_database_snapshot(
     db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
     source_path: String,
-) -> Result<DatabaseSnapshotResult, String> {
+    passphrase: Option<String>,
+) -> Result<DatabaseSnapshotResult, CommandError> {
     let source_path = source_path.trim();
     if source_path.is_empty() {
-        return Err("source path is required".to_string());
+        return Err(CommandError::invalid_input("source path is required"));
     }
 
-    db.import_snapshot_from_path(source_path)?;
-    clear_all_adapter_runtime();
+    restore_snapshot(
+        db.inner(),
+        watcher.inner(),
+        source_path,
+        passphrase.as_deref(),
+    )?;
     let size_bytes = std::fs::metadata(source_path)
         .map(|metadata| metadata.len())
         .unwrap_or(0);
@@ -832,15 +3855,444 @@ pub fn import_database_snapshot(
         path: source_path.to_string(),
         size_bytes,
         completed_at: Utc::now().to_rfc3339(),
+        cloud_backup: None,
+    })
+}
+
+// ── Cloud backup ────────────────────────────────────────────────────────────
+
+/// The secret key and passphrase come back masked.
+#[tauri::command]
+pub fn get_cloud_backup_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<CloudBackupSettings>, CommandError> {
+    Ok(db
+        .get_setting::<CloudBackupSettings>(CLOUD_BACKUP_SETTINGS_KEY)?
+        .map(|settings| settings.masked()))
+}
+
+/// Masked placeholders left untouched keep the stored secrets.
+#[tauri::command]
+pub fn set_cloud_backup_settings(
+    db: State<'_, Arc<Database>>,
+    mut settings: CloudBackupSettings,
+) -> Result<CloudBackupSettings, CommandError> {
+    let previous = db.get_setting::<CloudBackupSettings>(CLOUD_BACKUP_SETTINGS_KEY)?;
+    settings.restore_masked(previous.as_ref());
+    settings.validate().map_err(CommandError::invalid_input)?;
+    db.set_setting(CLOUD_BACKUP_SETTINGS_KEY, &settings)?;
+    Ok(settings.masked())
+}
+
+/// Upload a fresh snapshot now and prune beyond the retention count.
+#[tauri::command]
+pub async fn run_cloud_backup(
+    db: State<'_, Arc<Database>>,
+) -> Result<CloudBackupResult, CommandError> {
+    let settings = backup::load_settings(db.inner())?;
+    backup::upload_database(db.inner(), &settings)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// Backups in the bucket, newest first.
+#[tauri::command]
+pub async fn list_cloud_backups(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<CloudBackup>, CommandError> {
+    let settings = backup::load_settings(db.inner())?;
+    backup::list_backups(&settings)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// Download one backup and restore it over the live database. Backups open
+/// with the backup passphrase unless another `passphrase` is given, for
+/// ones uploaded from an export encrypted under its own.
+#[tauri::command]
+pub async fn restore_cloud_backup(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    key: String,
+    passphrase: Option<String>,
+) -> Result<DatabaseSnapshotResult, CommandError> {
+    let settings = backup::load_settings(db.inner())?;
+    let passphrase = passphrase
+        .filter(|passphrase| !passphrase.is_empty())
+        .unwrap_or_else(|| settings.passphrase.clone());
+    let download = db.scratch_file("restore")?;
+    let path = download.path_str();
+    let result = match backup::download_backup(&settings, &key, &path).await {
+        Ok(size_bytes) => restore_snapshot(db.inner(), watcher.inner(), &path, Some(&passphrase))
+            .map(|()| size_bytes),
+        Err(error) => Err(error),
+    };
+    drop(download);
+
+    Ok(DatabaseSnapshotResult {
+        path: key,
+        size_bytes: result?,
+        completed_at: Utc::now().to_rfc3339(),
+        cloud_backup: None,
     })
 }
 
+// ── Redaction ───────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_redaction_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<RedactionSettings, CommandError> {
+    db.get_redaction_settings().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_redaction_settings(
+    db: State<'_, Arc<Database>>,
+    settings: RedactionSettings,
+) -> Result<RedactionSettings, CommandError> {
+    settings.validate()?;
+    db.set_redaction_settings(&settings)?;
+    Ok(settings)
+}
+
+// ── Run analytics ───────────────────────────────────────────────────────────
+
+/// Success rate, median duration, failure categories, and busiest hours,
+/// overall and per agent, for runs started inside `range`.
+#[tauri::command]
+pub fn get_run_analytics(
+    db: State<'_, Arc<Database>>,
+    project_id: Option<String>,
+    range: Option<AnalyticsRange>,
+) -> Result<RunAnalytics, CommandError> {
+    let range = range.unwrap_or_default();
+    let since = range.since.map(|t| t.to_rfc3339());
+    let until = range.until.map(|t| t.to_rfc3339());
+    let facts = db.get_run_facts(project_id.as_deref(), since.as_deref(), until.as_deref())?;
+    let agents = db.list_agents()?;
+    Ok(analytics::compute_run_analytics(
+        &agents, &facts, project_id, range,
+    ))
+}
+
+/// Daily rollups for charting, oldest first; today is computed live.
+#[tauri::command]
+pub fn get_stats_timeseries(
+    db: State<'_, Arc<Database>>,
+    range: Option<StatsRange>,
+) -> Result<Vec<DailyStats>, CommandError> {
+    let zone = UserTimezone::load(db.inner());
+    daily_stats::timeseries(
+        db.inner(),
+        zone,
+        &range.unwrap_or_default(),
+        zone.date_of(db.now()),
+    )
+    .map_err(CommandError::from)
+}
+
+// ── Run archive ─────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_run_archive_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<RunArchiveSettings, CommandError> {
+    db.get_setting::<RunArchiveSettings>(RUN_ARCHIVE_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_run_archive_settings(
+    db: State<'_, Arc<Database>>,
+    settings: RunArchiveSettings,
+) -> Result<RunArchiveSettings, CommandError> {
+    settings.validate().map_err(CommandError::invalid_input)?;
+    db.set_setting(RUN_ARCHIVE_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Archive finished runs now. Defaults to the saved `after_days`.
+#[tauri::command]
+pub fn archive_runs(
+    db: State<'_, Arc<Database>>,
+    older_than_days: Option<u32>,
+) -> Result<usize, CommandError> {
+    let settings = RunArchiveSettings {
+        after_days: match older_than_days {
+            Some(days) => days,
+            None => get_run_archive_settings(db.clone())?.after_days,
+        },
+        ..Default::default()
+    };
+    settings.validate().map_err(CommandError::invalid_input)?;
+    run_archive::archive_older_than(db.inner(), settings.after_days).map_err(CommandError::from)
+}
+
+/// Archived runs, newest first; `before` is a `started_at` cursor.
+#[tauri::command]
+pub fn list_archived_runs(
+    db: State<'_, Arc<Database>>,
+    agent_id: Option<String>,
+    before: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+) -> Result<Vec<ArchivedRun>, CommandError> {
+    let before = before.map(|t| t.to_rfc3339());
+    db.list_archived_runs(
+        agent_id.as_deref(),
+        before.as_deref(),
+        limit.unwrap_or(50).clamp(1, run_archive::ARCHIVE_BATCH),
+    )
+    .map_err(CommandError::from)
+}
+
+/// Move an archived run, outputs and comments included, back into runs.
+#[tauri::command]
+pub fn restore_run(db: State<'_, Arc<Database>>, run_id: String) -> Result<Run, CommandError> {
+    db.restore_archived_run(&run_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Archived run not found: {}", run_id)))
+}
+
+// ── Agenda ──────────────────────────────────────────────────────────────────
+
+/// Scheduled agent runs and due connector items in [since, until), plus
+/// open items already overdue, ordered by time. Defaults to the next 24h.
+#[tauri::command]
+pub fn get_agenda(
+    db: State<'_, Arc<Database>>,
+    range: Option<AgendaRange>,
+) -> Result<Vec<AgendaEntry>, CommandError> {
+    let (since, until) = range.unwrap_or_default().resolve(Utc::now());
+    if until <= since {
+        return Err(CommandError::invalid_input(
+            "range end must be after its start",
+        ));
+    }
+    agenda::load_agenda(db.inner(), since, until).map_err(CommandError::from)
+}
+
+/// Write the agenda for [since, until) to an .ics file. Defaults to the
+/// next 24h, like `get_agenda`.
+#[tauri::command]
+pub fn export_agenda_ics(
+    db: State<'_, Arc<Database>>,
+    range: Option<AgendaRange>,
+    destination_path: String,
+) -> Result<CalendarExportResult, CommandError> {
+    let destination_path = destination_path.trim();
+    if destination_path.is_empty() {
+        return Err(CommandError::invalid_input("destination path is required"));
+    }
+    let (since, until) = range.unwrap_or_default().resolve(Utc::now());
+    if until <= since {
+        return Err(CommandError::invalid_input(
+            "range end must be after its start",
+        ));
+    }
+    agenda::write_ics(db.inner(), since, until, destination_path).map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn get_calendar_feed_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<CalendarFeedSettings, CommandError> {
+    db.get_setting::<CalendarFeedSettings>(CALENDAR_FEED_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// Save the feed settings and rewrite the feed file straight away.
+#[tauri::command]
+pub fn set_calendar_feed_settings(
+    db: State<'_, Arc<Database>>,
+    settings: CalendarFeedSettings,
+) -> Result<CalendarFeedSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(CALENDAR_FEED_SETTINGS_KEY, &settings)?;
+    agenda::refresh_feed(db.inner())?;
+    Ok(settings)
+}
+
+// ── Weekly digest ───────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_digest_settings(db: State<'_, Arc<Database>>) -> Result<DigestSettings, CommandError> {
+    db.get_setting::<DigestSettings>(DIGEST_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_digest_settings(
+    db: State<'_, Arc<Database>>,
+    settings: DigestSettings,
+) -> Result<DigestSettings, CommandError> {
+    db.set_setting(DIGEST_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Compile the Markdown digest for the week containing `week_of` (default:
+/// this week so far), for one project or every project with activity.
+/// Saving and pushing default to the digest settings.
+#[tauri::command]
+pub async fn generate_weekly_digest(
+    db: State<'_, Arc<Database>>,
+    project_id: Option<String>,
+    week_of: Option<chrono::NaiveDate>,
+    save_as_doc: Option<bool>,
+    push_connector: Option<String>,
+) -> Result<Vec<ProjectDigest>, CommandError> {
+    let mut settings = db
+        .get_setting::<DigestSettings>(DIGEST_SETTINGS_KEY)?
+        .unwrap_or_default();
+    if let Some(save_as_doc) = save_as_doc {
+        settings.save_as_doc = save_as_doc;
+    }
+    if push_connector.is_some() {
+        settings.push_connector = push_connector;
+    }
+    let today = UserTimezone::load(db.inner()).date_of(db.now());
+    let week_start = digest::week_start(week_of.unwrap_or(today));
+    digest::generate_weekly_digests(db.inner(), project_id.as_deref(), week_start, &settings).await
+}
+
+// ── Activity feed ───────────────────────────────────────────────────────────
+
+/// Messages, run transitions, and file-change rollups across all agents,
+/// newest first. Page with `before` set to the last entry's timestamp.
+#[tauri::command]
+pub fn get_activity_feed(
+    db: State<'_, Arc<Database>>,
+    limit: Option<usize>,
+    before: Option<DateTime<Utc>>,
+    filters: Option<ActivityFilters>,
+) -> Result<Vec<ActivityEntry>, CommandError> {
+    let filters = filters.unwrap_or_default();
+    let limit = limit
+        .unwrap_or(activity::DEFAULT_FEED_LIMIT)
+        .clamp(1, activity::MAX_FEED_LIMIT);
+    let before_str = before.map(|t| t.to_rfc3339());
+    let project_id = filters.project_id.as_deref();
+    let agent_id = filters.agent_id.as_deref();
+
+    let messages = if filters.includes_messages() {
+        db.get_feed_messages(project_id, agent_id, before_str.as_deref(), limit)?
+    } else {
+        vec![]
+    };
+    let runs = if filters.includes_runs() {
+        db.get_feed_runs(project_id, agent_id, before_str.as_deref(), limit)?
+    } else {
+        vec![]
+    };
+    let agents = db.list_agents()?;
+    let projects = db.list_projects()?;
+
+    Ok(activity::build_activity_feed(
+        &agents, &projects, messages, &runs, &filters, before, limit,
+    ))
+}
+
+// ── Run watchdog ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_watchdog_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<WatchdogSettings, CommandError> {
+    db.get_setting::<WatchdogSettings>(WATCHDOG_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_watchdog_settings(
+    db: State<'_, Arc<Database>>,
+    settings: WatchdogSettings,
+) -> Result<WatchdogSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(WATCHDOG_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+// ── File watcher ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_watcher_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<watchers::WatcherSettings, CommandError> {
+    db.get_setting::<watchers::WatcherSettings>(watchers::WATCHER_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+/// Watched roots with their agents and event counters, for debugging why
+/// changes in a directory aren't reaching an agent.
+#[tauri::command]
+pub fn get_watcher_status(
+    stats: State<'_, watchers::WatcherStats>,
+) -> Result<watchers::WatcherStatus, CommandError> {
+    Ok(stats.snapshot())
+}
+
+#[tauri::command]
+pub fn set_watcher_settings(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    settings: watchers::WatcherSettings,
+) -> Result<watchers::WatcherSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(watchers::WATCHER_SETTINGS_KEY, &settings)?;
+    watcher.settings_changed(&settings);
+    Ok(settings)
+}
+
+// ── Run summaries ───────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_summarizer_settings(
+    db: State<'_, Arc<Database>>,
+) -> Result<SummarizerSettings, CommandError> {
+    db.get_setting::<SummarizerSettings>(SUMMARIZER_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn set_summarizer_settings(
+    db: State<'_, Arc<Database>>,
+    settings: SummarizerSettings,
+) -> Result<SummarizerSettings, CommandError> {
+    settings.validate()?;
+    db.set_setting(SUMMARIZER_SETTINGS_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Regenerate a run's summary on demand, even if it already has a real one.
+/// Uses the configured endpoint whether or not automatic summaries are on.
+#[tauri::command]
+pub async fn summarize_run(
+    db: State<'_, Arc<Database>>,
+    run_id: String,
+) -> Result<Run, CommandError> {
+    let settings = db
+        .get_setting::<SummarizerSettings>(SUMMARIZER_SETTINGS_KEY)?
+        .unwrap_or_default();
+    settings.validate()?;
+    summarize::summarize_run(db.inner(), &settings, &run_id)
+        .await?
+        .ok_or_else(|| "Run has no outputs to summarize".to_string())
+        .map_err(CommandError::from)
+}
+
 // ── Connectors ──────────────────────────────────────────────────────────────
 
 /// List all configured connectors with their current status
 #[tauri::command]
-pub async fn list_connectors(db: State<'_, Arc<Database>>) -> Result<Vec<ConnectorInfo>, String> {
-    let configs = db.list_connector_configs().map_err(|e| e.to_string())?;
+pub async fn list_connectors(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<ConnectorInfo>, CommandError> {
+    let configs = db.list_connector_configs()?;
     let mut infos = Vec::new();
 
     for config in &configs {
@@ -855,7 +4307,7 @@ pub async fn list_connectors(db: State<'_, Arc<Database>>) -> Result<Vec<Connect
                 infos.push(info);
             }
             Err(e) => {
-                log::warn!(
+                tracing::warn!(
                     "Failed to create connector {}: {}",
                     config.connector_type,
                     e
@@ -868,14 +4320,20 @@ pub async fn list_connectors(db: State<'_, Arc<Database>>) -> Result<Vec<Connect
 
 /// Add or update a connector configuration
 #[tauri::command]
-pub fn save_connector(db: State<'_, Arc<Database>>, config: ConnectorConfig) -> Result<(), String> {
-    db.save_connector_config(&config).map_err(|e| e.to_string())
+pub fn save_connector(
+    db: State<'_, Arc<Database>>,
+    config: ConnectorConfig,
+) -> Result<(), CommandError> {
+    db.save_connector_config(&config)
+        .map_err(CommandError::from)
 }
 
 /// List saved connector configs (including settings/auth placeholders)
 #[tauri::command]
-pub fn get_connector_configs(db: State<'_, Arc<Database>>) -> Result<Vec<ConnectorConfig>, String> {
-    db.list_connector_configs().map_err(|e| e.to_string())
+pub fn get_connector_configs(
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<ConnectorConfig>, CommandError> {
+    db.list_connector_configs().map_err(CommandError::from)
 }
 
 /// Pull items from a connector and cache them locally
@@ -883,25 +4341,53 @@ pub fn get_connector_configs(db: State<'_, Arc<Database>>) -> Result<Vec<Connect
 pub async fn sync_connector(
     db: State<'_, Arc<Database>>,
     connector_type: String,
-) -> Result<connectors::SyncResult, String> {
+) -> Result<connectors::SyncResult, CommandError> {
     let config = db
-        .get_connector_config(&connector_type)
-        .map_err(|e| e.to_string())?
+        .get_connector_config(&connector_type)?
         .ok_or_else(|| format!("Connector '{}' not configured", connector_type))?;
 
-    let connector = connectors::create_connector(&config).map_err(|e| e.to_string())?;
+    let connector = connectors::create_connector(&config)?;
 
-    let items = connector.pull(None).await.map_err(|e| e.to_string())?;
+    let span = tracing::info_span!(
+        "connector_sync",
+        correlation_id = uuid::Uuid::new_v4().to_string().as_str(),
+        connector = connector_type.as_str(),
+    );
+    let started = Instant::now();
+    let pulled = connector.pull(None).instrument(span.clone()).await;
+    metrics::record_connector_sync(&connector_type, started.elapsed(), pulled.is_ok());
+    let items = span.in_scope(|| {
+        let items = pulled.map_err(|e| {
+            tracing::warn!("Connector sync failed: {}", e);
+            CommandError::from(e)
+        })?;
+        db.upsert_connector_items(&connector_type, &items)?;
+        tracing::info!(
+            pulled = items.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Connector synced"
+        );
+        Ok::<_, CommandError>(items)
+    })?;
     let count = items.len();
 
-    db.upsert_connector_items(&connector_type, &items)
-        .map_err(|e| e.to_string())?;
+    // Board cards follow their items, and cards moved since the last sync
+    // push their status back.
+    let mut pushed = 0;
+    let mut errors = vec![];
+    for task in board::reconcile_items(&db, &connector_type, &items)? {
+        let card = task.title.clone();
+        match board::push_item_status(&db, task).await {
+            Ok(_) => pushed += 1,
+            Err(error) => errors.push(format!("{}: {}", card, error)),
+        }
+    }
 
     Ok(connectors::SyncResult {
         connector_id: connector_type,
         pulled: count,
-        pushed: 0,
-        errors: vec![],
+        pushed,
+        errors,
         synced_at: chrono::Utc::now(),
     })
 }
@@ -911,9 +4397,25 @@ pub async fn sync_connector(
 pub fn get_connector_items(
     db: State<'_, Arc<Database>>,
     connector_type: String,
-) -> Result<Vec<ConnectorItem>, String> {
+) -> Result<Vec<ConnectorItem>, CommandError> {
     db.get_connector_items(&connector_type)
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
+}
+
+/// A cached item plus the raw upstream JSON it was mapped from.
+#[tauri::command]
+pub fn get_connector_item_detail(
+    db: State<'_, Arc<Database>>,
+    connector_id: String,
+    item_id: String,
+) -> Result<ConnectorItemDetail, CommandError> {
+    db.get_connector_item_detail(&connector_id, &item_id)?
+        .ok_or_else(|| {
+            CommandError::not_found(format!(
+                "Connector item not found: {}/{}",
+                connector_id, item_id
+            ))
+        })
 }
 
 /// Push a new item to a connector
@@ -921,22 +4423,219 @@ pub fn get_connector_items(
 pub async fn push_connector_item(
     db: State<'_, Arc<Database>>,
     connector_type: String,
-    item: ConnectorItem,
-) -> Result<ConnectorItem, String> {
-    let config = db
-        .get_connector_config(&connector_type)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connector '{}' not configured", connector_type))?;
+    item: ConnectorItem,
+) -> Result<ConnectorItem, CommandError> {
+    let config = db
+        .get_connector_config(&connector_type)?
+        .ok_or_else(|| format!("Connector '{}' not configured", connector_type))?;
+
+    let connector = connectors::create_connector(&config)?;
+
+    let created = connector.push(&item).await?;
+
+    // Cache the new item locally
+    db.upsert_connector_items(&connector_type, &[created.clone()])?;
+
+    Ok(created)
+}
+
+/// Send a cached connector item to an agent as an instruction. The resulting
+/// run records the item as its source.
+#[tauri::command]
+pub fn send_connector_item_to_agent(
+    db: State<'_, Arc<Database>>,
+    agent_id: String,
+    connector_type: String,
+    item_id: String,
+    instruction: Option<String>,
+) -> Result<Message, CommandError> {
+    let item = db
+        .get_connector_item(&connector_type, &item_id)?
+        .ok_or_else(|| {
+            CommandError::not_found(format!(
+                "Item '{}' not found in {}",
+                item_id, connector_type
+            ))
+        })?;
+
+    let msg = connector_item_instruction(&agent_id, &connector_type, &item, instruction);
+    enqueue_message(db.inner(), msg).map_err(CommandError::from)
+}
+
+pub(crate) fn connector_item_instruction(
+    agent_id: &str,
+    connector_type: &str,
+    item: &ConnectorItem,
+    instruction: Option<String>,
+) -> Message {
+    let mut content = match instruction.filter(|text| !text.trim().is_empty()) {
+        Some(instruction) => format!("{}\n\nTask: {}", instruction.trim(), item.title),
+        None => item.title.clone(),
+    };
+    if let Some(body) = item
+        .content
+        .as_deref()
+        .filter(|body| !body.trim().is_empty())
+    {
+        content.push_str(&format!("\n\n{}", body.trim()));
+    }
+    if let Some(url) = &item.url {
+        content.push_str(&format!("\n\nSource: {}", url));
+    }
+
+    let mut msg = Message::to_agent(agent_id, MessageKind::Instruction, &content);
+    msg.metadata = Some(serde_json::json!({
+        "source_item": SourceItemRef {
+            connector_id: connector_type.to_string(),
+            item_id: item.id.clone(),
+        },
+    }));
+    msg
+}
+
+/// All runs, across agents, that worked on a connector item.
+#[tauri::command]
+pub fn list_runs_for_connector_item(
+    db: State<'_, Arc<Database>>,
+    connector_type: String,
+    item_id: String,
+) -> Result<Vec<Run>, CommandError> {
+    db.get_runs_for_source_item(&connector_type, &item_id)
+        .map_err(CommandError::from)
+}
+
+/// Agents created from connector items, plus the items skipped because an
+/// agent already exists for them.
+#[derive(serde::Serialize)]
+pub struct ItemAgentsResult {
+    pub created: Vec<Agent>,
+    pub skipped_item_ids: Vec<String>,
+}
+
+/// Fallbacks for agents created from connector items. Item metadata
+/// (`agent_name`, `function_tag`, `working_directory`) wins over these.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ItemAgentDefaults {
+    #[serde(default)]
+    pub kind: Option<AgentKind>,
+    #[serde(default)]
+    pub function_tag: Option<String>,
+    #[serde(default)]
+    pub base_directory: Option<String>, // each agent gets a folder under it named after the item
+}
+
+/// Create one agent per cached connector item (e.g. one per epic), named
+/// after the item and linked back to it as the agent's source.
+#[tauri::command]
+pub fn create_agents_from_connector_items(
+    db: State<'_, Arc<Database>>,
+    watcher: State<'_, watchers::WatcherHandle>,
+    project_id: String,
+    connector_type: String,
+    item_ids: Vec<String>,
+    defaults: Option<ItemAgentDefaults>,
+) -> Result<ItemAgentsResult, CommandError> {
+    if !db
+        .list_projects()?
+        .iter()
+        .any(|project| project.id == project_id)
+    {
+        return Err(CommandError::not_found(format!(
+            "Project not found: {}",
+            project_id
+        )));
+    }
+    let mut items = Vec::with_capacity(item_ids.len());
+    for item_id in &item_ids {
+        let item = db
+            .get_connector_item(&connector_type, item_id)?
+            .ok_or_else(|| {
+                CommandError::not_found(format!(
+                    "Item '{}' not found in {}",
+                    item_id, connector_type
+                ))
+            })?;
+        items.push(item);
+    }
 
-    let connector = connectors::create_connector(&config).map_err(|e| e.to_string())?;
+    let mut linked: HashSet<SourceItemRef> = db
+        .list_agents()?
+        .into_iter()
+        .filter_map(|agent| agent.source_item)
+        .collect();
+    let defaults = defaults.unwrap_or_default();
+    let mut result = ItemAgentsResult {
+        created: vec![],
+        skipped_item_ids: vec![],
+    };
+    for item in &items {
+        let source = SourceItemRef {
+            connector_id: connector_type.clone(),
+            item_id: item.id.clone(),
+        };
+        if !linked.insert(source.clone()) {
+            result.skipped_item_ids.push(item.id.clone());
+            continue;
+        }
+        let agent = agent_for_connector_item(&project_id, source, item, &defaults);
+        db.create_agent(&agent)?;
+        watcher.agent_changed(&agent);
+        result.created.push(agent);
+    }
+    Ok(result)
+}
 
-    let created = connector.push(&item).await.map_err(|e| e.to_string())?;
+fn agent_for_connector_item(
+    project_id: &str,
+    source: SourceItemRef,
+    item: &ConnectorItem,
+    defaults: &ItemAgentDefaults,
+) -> Agent {
+    fn trimmed(value: &Option<String>) -> Option<&str> {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+    let meta = |key: &str| {
+        item.metadata
+            .get(key)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let name = meta("agent_name").unwrap_or_else(|| item.title.trim());
+    let function_tag = meta("function_tag")
+        .or(trimmed(&defaults.function_tag))
+        .or(item.tags.first().map(String::as_str))
+        .unwrap_or("general");
 
-    // Cache the new item locally
-    db.upsert_connector_items(&connector_type, &[created.clone()])
-        .map_err(|e| e.to_string())?;
+    let kind = defaults.kind.clone().unwrap_or(AgentKind::Terminal);
+    let mut agent = Agent::new(name, project_id, kind, function_tag);
+    agent.working_directory = meta("working_directory").map(str::to_string).or_else(|| {
+        trimmed(&defaults.base_directory).map(|base| {
+            Path::new(base)
+                .join(directory_name(&item.title))
+                .to_string_lossy()
+                .into_owned()
+        })
+    });
+    agent.source_item = Some(source);
+    agent
+}
 
-    Ok(created)
+/// Lowercase, dash-separated folder name for an item title.
+fn directory_name(title: &str) -> String {
+    let name = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "agent".to_string()
+    } else {
+        name
+    }
 }
 
 /// Delete an item from a connector
@@ -945,21 +4644,16 @@ pub async fn delete_connector_item(
     db: State<'_, Arc<Database>>,
     connector_type: String,
     item_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let config = db
-        .get_connector_config(&connector_type)
-        .map_err(|e| e.to_string())?
+        .get_connector_config(&connector_type)?
         .ok_or_else(|| format!("Connector '{}' not configured", connector_type))?;
 
-    let connector = connectors::create_connector(&config).map_err(|e| e.to_string())?;
+    let connector = connectors::create_connector(&config)?;
 
-    connector
-        .delete(&item_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    connector.delete(&item_id).await?;
 
-    db.delete_connector_item(&connector_type, &item_id)
-        .map_err(|e| e.to_string())?;
+    db.delete_connector_item(&connector_type, &item_id)?;
 
     Ok(())
 }
@@ -995,6 +4689,709 @@ mod tests {
         (db, agent.id)
     }
 
+    #[test]
+    fn files_changed_today_counts_distinct_paths_since_midnight() {
+        let since = Utc::now() - chrono::Duration::hours(2);
+        let change = |path: &str, hours_ago: i64| FileChange {
+            path: path.to_string(),
+            change_type: FileChangeType::Modified,
+            timestamp: Utc::now() - chrono::Duration::hours(hours_ago),
+            count: 1,
+            old_path: None,
+            lines_added: None,
+            lines_removed: None,
+        };
+        let mut run = Run::new("agent-1", RunStatus::InProgress, None);
+        run.file_changes = vec![
+            change("/repo/a.rs", 5),
+            change("/repo/b.rs", 1),
+            change("/repo/b.rs", 0),
+            change("/repo/c.rs", 0),
+        ];
+
+        let counts = files_changed_since(&[run], since);
+        assert_eq!(counts.get("agent-1"), Some(&2));
+
+        let zone = UserTimezone::System;
+        let start = zone.day_start(zone.date_of(Utc::now()));
+        assert!(start <= Utc::now());
+        assert!(Utc::now() - start <= chrono::Duration::hours(25));
+    }
+
+    #[test]
+    fn group_operations_reach_every_member() {
+        let (db, agent_id) = setup_mock_agent();
+        let project_id = db.list_projects().unwrap()[0].id.clone();
+        let second = Agent::new("Second", &project_id, AgentKind::Terminal, "engineering");
+        db.create_agent(&second).expect("agent should insert");
+        let group = AgentGroup::new("Launch crew");
+        db.create_agent_group(&group).expect("group should insert");
+        for id in [&agent_id, &second.id, &agent_id] {
+            db.add_agent_group_member(&group.id, id)
+                .expect("member should insert");
+        }
+        let group = db.get_agent_group(&group.id).unwrap().unwrap();
+        assert_eq!(group.agent_ids, [agent_id.clone(), second.id.clone()]);
+
+        for id in &group.agent_ids {
+            enqueue_message(
+                &db,
+                Message::to_agent(id, MessageKind::Instruction, "Ship the launch post"),
+            )
+            .expect("instruction should queue");
+        }
+        db.update_agent_status(&second.id, &AgentStatus::Errored)
+            .unwrap();
+
+        let dashboard_agents: Vec<AgentSummary> = db
+            .list_agents()
+            .unwrap()
+            .into_iter()
+            .map(|agent| AgentSummary {
+                agent,
+                recent_run: None,
+                files_changed_today: 0,
+                lines_added: 0,
+                lines_removed: 0,
+                usage: TokenUsage::default(),
+            })
+            .collect();
+        let projects = vec![ProjectWithAgents {
+            project: db.list_projects().unwrap().remove(0),
+            agents: dashboard_agents,
+            usage: TokenUsage::default(),
+        }];
+        let attention = vec![AttentionItem {
+            agent_id: second.id.clone(),
+            agent_name: second.name.clone(),
+            project_name: String::new(),
+            reason: "errored".to_string(),
+            timestamp: Utc::now(),
+        }];
+        let rollups = group_rollups(std::slice::from_ref(&group), &projects, &attention);
+        assert_eq!(rollups[0].agent_count, 2);
+        assert_eq!(rollups[0].errored, 1);
+        assert_eq!(rollups[0].needs_attention, 1);
+
+        db.remove_agent_group_member(&group.id, &second.id).unwrap();
+        db.delete_agent_group(&group.id).unwrap();
+        assert!(db.list_agent_groups().unwrap().is_empty());
+
+        clear_adapter_runtime(&agent_id);
+        clear_adapter_runtime(&second.id);
+    }
+
+    #[test]
+    fn clone_agent_copies_config_but_not_session() {
+        let (db, agent_id) = setup_mock_agent();
+        let mut source = db.get_agent(&agent_id).unwrap().unwrap();
+        source.config.watch_paths = vec!["/repo/a".to_string()];
+        source.config.ignore_globs = vec!["dist/**".to_string()];
+        db.update_agent(&source).expect("agent should update");
+        let other = Project::new("Other", "#445566");
+        db.create_project(&other).expect("project should insert");
+
+        let clone = duplicate_agent(&db, &agent_id, "Copy", Some(other.id.clone()))
+            .expect("agent should clone");
+        assert_ne!(clone.id, agent_id);
+        assert_eq!(clone.project_id, other.id);
+        assert_eq!(clone.config.watch_paths, ["/repo/a"]);
+        assert_eq!(clone.config.ignore_globs, ["dist/**"]);
+        let adapter = db
+            .get_adapter_config(&clone.id)
+            .unwrap()
+            .expect("adapter config should be copied");
+        assert_eq!(adapter.adapter_type, AdapterType::Mock);
+        assert!(adapter.session_name.is_none());
+
+        assert!(duplicate_agent(&db, &agent_id, "Copy", Some("missing".to_string())).is_err());
+    }
+
+    #[test]
+    fn pause_all_pauses_running_agents_and_resumes_only_them() {
+        let (db, agent_id) = setup_mock_agent();
+        let project_id = db.list_projects().unwrap()[0].id.clone();
+        let idle = Agent::new("Idle", &project_id, AgentKind::Terminal, "engineering");
+        db.create_agent(&idle).expect("agent should insert");
+        db.update_agent_status(&agent_id, &AgentStatus::Running)
+            .unwrap();
+
+        let paused = pause_agents(&db, Some(&project_id)).expect("pause should apply");
+        assert_eq!(paused.len(), 2);
+        assert!(pause_agents(&db, None).unwrap().is_empty());
+        let status_of = |id: &str| db.get_agent(id).unwrap().unwrap().status;
+        assert_eq!(status_of(&agent_id), AgentStatus::Blocked);
+        assert_eq!(status_of(&idle.id), AgentStatus::Idle);
+
+        let resumed = resume_agents(&db, None).expect("resume should apply");
+        assert_eq!(resumed.len(), 2);
+        assert!(load_paused_agents(&db).unwrap().is_empty());
+        assert_eq!(status_of(&agent_id), AgentStatus::Running);
+        let kinds: Vec<MessageKind> = db
+            .get_messages_for_agent(&idle.id, 10)
+            .unwrap()
+            .into_iter()
+            .map(|message| message.kind)
+            .collect();
+        assert!(kinds.is_empty());
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn empty_resume_replays_last_delivered_instruction() {
+        let (db, agent_id) = setup_mock_agent();
+        let resume = || Message::to_agent(&agent_id, MessageKind::Resume, "");
+        assert!(queue_message(&db, resume()).is_err());
+
+        let delivered = Message::to_agent(&agent_id, MessageKind::Instruction, "fix the build");
+        db.insert_message(&delivered).unwrap();
+        db.mark_delivered(&delivered.id).unwrap();
+        let queued = Message::to_agent(&agent_id, MessageKind::Instruction, "not yet");
+        db.insert_message(&queued).unwrap();
+
+        let replayed = queue_message(&db, resume()).expect("resume should queue");
+        assert_eq!(replayed.content, "fix the build");
+        assert_eq!(
+            replayed.metadata.unwrap()["replays"],
+            serde_json::json!(delivered.id)
+        );
+        let explicit = Message::to_agent(&agent_id, MessageKind::Resume, "carry on");
+        assert_eq!(queue_message(&db, explicit).unwrap().content, "carry on");
+
+        // Without a delivered instruction, the latest run's is used.
+        let project_id = db.list_projects().unwrap()[0].id.clone();
+        let other = Agent::new("Other", &project_id, AgentKind::Terminal, "engineering");
+        db.create_agent(&other).expect("agent should insert");
+        db.start_instruction_run(&other.id, None, "ship it")
+            .expect("run should start");
+        assert_eq!(resume_message(&db, &other.id).content, "ship it");
+    }
+
+    #[test]
+    fn reply_to_blocked_continues_the_stopped_run() {
+        let (db, agent_id) = setup_mock_agent();
+        let instruction = queue_message(
+            &db,
+            Message::to_agent(&agent_id, MessageKind::Instruction, "deploy the site"),
+        )
+        .unwrap();
+        let mut blocked = Message::from_agent(&agent_id, MessageKind::Blocked, "Which region?");
+        blocked.reply_to = Some(instruction.id.clone());
+        let blocked = record_agent_message(&db, blocked).unwrap();
+        let stopped = db.get_run_blocked_by(&blocked).unwrap().unwrap();
+        assert_eq!(stopped.status, RunStatus::NeedsReview);
+
+        let output = Message::from_agent(&agent_id, MessageKind::Output, "checking");
+        let output = record_agent_message(&db, output).unwrap();
+        assert!(answer_blocked(&db, &agent_id, &output.id, "eu-west").is_err());
+        assert!(answer_blocked(&db, &agent_id, &blocked.id, "  ").is_err());
+        // Without a run key there's no telling which run it stopped.
+        let unkeyed = Message::from_agent(&agent_id, MessageKind::Blocked, "Which one?");
+        let unkeyed = record_agent_message(&db, unkeyed).unwrap();
+        let error = answer_blocked(&db, &agent_id, &unkeyed.id, "eu-west").unwrap_err();
+        assert_eq!(error.code, crate::errors::ErrorCode::InvalidInput);
+
+        let (reply, run) = answer_blocked(&db, &agent_id, &blocked.id, "eu-west").unwrap();
+        assert_eq!(run.id, stopped.id);
+        assert_eq!(run.status, RunStatus::InProgress);
+        assert_eq!(reply.reply_to.as_deref(), Some(blocked.id.as_str()));
+        // The adapter reporting the delivered reply joins the same run.
+        let joined = db
+            .start_instruction_run(&agent_id, Some(&reply.id), &reply.content)
+            .unwrap();
+        assert_eq!(joined.id, stopped.id);
+        let run = db.get_run(&stopped.id).unwrap().unwrap();
+        let instructions: Vec<&str> = run
+            .outputs
+            .iter()
+            .filter(|output| output.kind == "instruction")
+            .map(|output| output.content.as_str())
+            .collect();
+        assert_eq!(instructions, ["deploy the site", "eu-west"]);
+        assert_eq!(
+            db.get_agent(&agent_id).unwrap().unwrap().status,
+            AgentStatus::Running
+        );
+        assert!(answer_blocked(&db, &agent_id, &blocked.id, "again").is_err());
+    }
+
+    #[test]
+    fn route_instruction_picks_the_matching_idle_agent() {
+        let (db, agent_id) = setup_mock_agent();
+        let project_id = db.list_projects().unwrap()[0].id.clone();
+        let mut rust = Agent::new("Rust", &project_id, AgentKind::Terminal, "engineering");
+        rust.config.capabilities = vec!["rust".to_string()];
+        db.create_agent(&rust).expect("agent should insert");
+        db.set_adapter_config(
+            &rust.id,
+            &db.get_adapter_config(&agent_id).unwrap().unwrap(),
+        )
+        .expect("adapter config should insert");
+
+        let routed = route_to_project(&db, &project_id, "Fix the Rust build")
+            .expect("instruction should route");
+        assert_eq!(routed.message.agent_id, rust.id);
+        assert_eq!(routed.candidates.len(), 2);
+        assert_eq!(
+            db.get_agent(&rust.id).unwrap().unwrap().status,
+            AgentStatus::Running
+        );
+
+        // The matched agent is busy now, so the other one gets the next.
+        let routed = route_to_project(&db, &project_id, "Fix the Rust tests").unwrap();
+        assert_eq!(routed.message.agent_id, agent_id);
+        assert!(route_to_project(&db, &project_id, "Anything").is_err());
+
+        clear_adapter_runtime(&agent_id);
+        clear_adapter_runtime(&rust.id);
+    }
+
+    #[test]
+    fn agent_queue_lists_pending_messages_in_delivery_order() {
+        let (db, agent_id) = setup_mock_agent();
+        let first = Message::to_agent(&agent_id, MessageKind::Instruction, "first");
+        db.insert_message(&first).unwrap();
+        let second = Message::to_agent(&agent_id, MessageKind::StatusRequest, "");
+        db.insert_message(&second).unwrap();
+        hold_message(
+            &db,
+            Message::to_agent(&agent_id, MessageKind::Instruction, "held"),
+        )
+        .unwrap();
+
+        let queue = agent_queue(&db, &agent_id).expect("queue should load");
+        assert!(!queue.adapter_running);
+        assert_eq!(queue.held_for_approval, 1);
+        let ids: Vec<(usize, &str)> = queue
+            .messages
+            .iter()
+            .map(|queued| (queued.position, queued.message.id.as_str()))
+            .collect();
+        assert_eq!(ids, [(1, first.id.as_str()), (2, second.id.as_str())]);
+        assert!(queue.messages[0].estimated_delivery_at.is_none());
+
+        adapter_runtime()
+            .lock()
+            .unwrap()
+            .entry(agent_id.clone())
+            .or_default()
+            .started = true;
+        let queue = agent_queue(&db, &agent_id).unwrap();
+        assert!(queue.adapter_running);
+        assert_eq!(queue.delivery_interval_ms, 2000);
+        assert!(queue.messages[1]
+            .estimated_delivery_at
+            .is_some_and(|at| at > db.now()));
+
+        assert!(agent_queue(&db, "missing").is_err());
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn agent_queue_estimates_on_the_database_clock() {
+        use crate::clock::{Clock, ManualClock};
+
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let db = Database::new(":memory:")
+            .expect("in-memory db should initialize")
+            .with_clock(clock.clone());
+        let project = Project::new("Test Project", "#112233");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Queue", &project.id, AgentKind::Terminal, "engineering");
+        db.create_agent(&agent).expect("agent should insert");
+        db.insert_message(&Message::to_agent(
+            &agent.id,
+            MessageKind::Instruction,
+            "first",
+        ))
+        .unwrap();
+        {
+            let mut runtime = adapter_runtime().lock().unwrap();
+            let state = runtime.entry(agent.id.clone()).or_default();
+            state.started = true;
+            state.next_retry_at = Some(clock.instant() + Duration::from_secs(30));
+        }
+        let interval = chrono::Duration::from_std(agents::PENDING_POLL_FALLBACK).unwrap();
+
+        let queue = agent_queue(&db, &agent.id).expect("queue should load");
+        assert_eq!(
+            queue.messages[0].estimated_delivery_at,
+            Some(clock.now() + chrono::Duration::seconds(30) + interval)
+        );
+
+        clock.advance(Duration::from_secs(20));
+        let queue = agent_queue(&db, &agent.id).expect("queue should load");
+        assert_eq!(
+            queue.messages[0].estimated_delivery_at,
+            Some(clock.now() + chrono::Duration::seconds(10) + interval)
+        );
+        clear_adapter_runtime(&agent.id);
+    }
+
+    #[test]
+    fn template_creates_agent_with_adapter_and_instructions() {
+        let db = Arc::new(Database::new(":memory:").expect("in-memory db should initialize"));
+        let project = Project::new("Test Project", "#112233");
+        db.create_project(&project).expect("project should insert");
+
+        let mut template = AgentTemplate::new("Engineer", AgentKind::Terminal, "engineering");
+        template.autonomy_level = AutonomyLevel::Autonomous;
+        template.ignore_globs = vec!["target/**".to_string()];
+        template.adapter = Some(AdapterConfig {
+            adapter_type: AdapterType::Mock,
+            session_name: None,
+            endpoint: None,
+            command: None,
+            env: None,
+        });
+        template.instructions = vec!["Read the README".to_string(), "Run the tests".to_string()];
+        db.save_agent_template(&template)
+            .expect("template should save");
+        let template = db
+            .get_agent_template(&template.id)
+            .expect("template should load")
+            .expect("template should exist");
+
+        let missing = instantiate_template(
+            &db,
+            &template,
+            &project.id,
+            None,
+            Some("/nonexistent/repo/x".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(missing.code, crate::errors::ErrorCode::NotFound);
+
+        let repo = std::env::temp_dir().to_string_lossy().to_string();
+        let agent = instantiate_template(
+            &db,
+            &template,
+            &project.id,
+            Some("Repo X engineer"),
+            Some(repo.clone()),
+        )
+        .expect("agent should be created");
+        assert_eq!(agent.name, "Repo X engineer");
+        assert_eq!(agent.working_directory, Some(repo));
+        assert_eq!(agent.function_tag, "engineering");
+        assert!(matches!(
+            agent.config.autonomy_level,
+            AutonomyLevel::Autonomous
+        ));
+        assert_eq!(agent.config.ignore_globs, ["target/**"]);
+        assert_eq!(
+            db.get_adapter_config(&agent.id)
+                .unwrap()
+                .map(|config| config.adapter_type),
+            Some(AdapterType::Mock)
+        );
+        let instructions: Vec<String> = db
+            .get_messages_for_agent(&agent.id, 10)
+            .unwrap()
+            .into_iter()
+            .filter(|message| message.kind == MessageKind::Instruction)
+            .map(|message| message.content)
+            .collect();
+        assert_eq!(instructions.len(), 2);
+
+        clear_adapter_runtime(&agent.id);
+    }
+
+    #[test]
+    fn manual_agents_hold_instructions_until_approved() {
+        let (db, agent_id) = setup_mock_agent();
+        let mut config = db.get_agent_config(&agent_id).unwrap().unwrap();
+        config.autonomy_level = AutonomyLevel::Manual;
+        let mut agent = db.get_agent(&agent_id).unwrap().unwrap();
+        agent.config = config;
+        db.update_agent(&agent).expect("agent should update");
+
+        let held = enqueue_message(
+            &db,
+            Message::to_agent(&agent_id, MessageKind::Instruction, "Deploy to prod"),
+        )
+        .expect("instruction should queue");
+        // Queued without starting the adapter, which would deliver it at once.
+        let status = queue_message(
+            &db,
+            Message::to_agent(&agent_id, MessageKind::StatusRequest, "Status?"),
+        )
+        .expect("status request should queue");
+        let pending = db.get_pending_messages(&agent_id).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, status.id);
+        assert!(db.get_latest_run_for_agent(&agent_id).unwrap().is_none());
+        assert_eq!(
+            db.get_messages_awaiting_approval(Some(&agent_id))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let approved = db
+            .approve_held_message(&held.id)
+            .unwrap()
+            .expect("message should be held");
+        assert!(approved
+            .metadata
+            .as_ref()
+            .unwrap()
+            .get("approved_at")
+            .is_some());
+        dispatch_message(&db, approved).expect("approved message should dispatch");
+        assert!(db.approve_held_message(&held.id).unwrap().is_none());
+        assert!(db.get_latest_run_for_agent(&agent_id).unwrap().is_some());
+
+        let rejected = enqueue_message(
+            &db,
+            Message::to_agent(&agent_id, MessageKind::Instruction, "Drop the table"),
+        )
+        .expect("instruction should queue");
+        let rejected = db
+            .reject_held_message(&rejected.id)
+            .unwrap()
+            .expect("message should be held");
+        assert!(rejected.delivered_at.is_some());
+        assert!(db.get_messages_awaiting_approval(None).unwrap().is_empty());
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn review_run_request_changes_queues_follow_up_instruction() {
+        let (db, agent_id) = setup_mock_agent();
+        db.start_instruction_run(&agent_id, None, "Draft release notes")
+            .expect("run should start");
+        let run = db
+            .finalize_latest_run(&agent_id, RunStatus::NeedsReview, None)
+            .expect("run should finalize")
+            .expect("run should exist");
+
+        assert!(apply_run_review(&db, &run.id, ReviewDecision::RequestChanges, None).is_err());
+
+        let reviewed = apply_run_review(
+            &db,
+            &run.id,
+            ReviewDecision::RequestChanges,
+            Some("Mention the migration".to_string()),
+        )
+        .expect("review should apply");
+        assert_eq!(reviewed.status, RunStatus::Failed);
+        let follow_up_id = reviewed
+            .review
+            .and_then(|review| review.follow_up_message_id)
+            .expect("follow-up should be linked");
+
+        let latest = db
+            .get_latest_run_for_agent(&agent_id)
+            .expect("latest run should load")
+            .expect("follow-up run should exist");
+        assert_ne!(latest.id, run.id);
+        assert_eq!(latest.status, RunStatus::InProgress);
+
+        let messages = db
+            .get_messages_for_agent(&agent_id, 10)
+            .expect("messages should load");
+        assert!(messages.iter().any(|message| message.id == follow_up_id
+            && message.content.contains("Mention the migration")));
+
+        let again = apply_run_review(&db, &run.id, ReviewDecision::Approve, None);
+        assert!(again.is_err());
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn normalize_labels_trims_lowercases_and_dedupes() {
+        let labels = normalize_labels(vec![
+            " Release ".to_string(),
+            "release".to_string(),
+            "".to_string(),
+            "Client-X".to_string(),
+        ])
+        .expect("labels should normalize");
+        assert_eq!(labels, vec!["release", "client-x"]);
+        assert!(normalize_labels(vec!["x".repeat(MAX_RUN_LABEL_CHARS + 1)]).is_err());
+    }
+
+    #[test]
+    fn connector_item_instructions_link_runs_to_their_source() {
+        let (db, agent_id) = setup_mock_agent();
+        let item = ConnectorItem {
+            id: "task-42".to_string(),
+            source: "todoist".to_string(),
+            title: "Renew SSL certificate".to_string(),
+            content: Some("Expires Friday".to_string()),
+            status: connectors::ItemStatus::Active,
+            priority: Some(1),
+            tags: vec![],
+            url: Some("https://todoist.com/showTask?id=task-42".to_string()),
+            parent_id: None,
+            metadata: HashMap::new(),
+            created_at: None,
+            updated_at: None,
+            due_at: None,
+            raw: None,
+        };
+
+        let msg = connector_item_instruction(&agent_id, "todoist", &item, None);
+        assert!(msg.content.starts_with("Renew SSL certificate"));
+        assert!(msg.content.contains("Expires Friday"));
+        enqueue_message(&db, msg).expect("instruction should queue");
+
+        let runs = db
+            .get_runs_for_source_item("todoist", "task-42")
+            .expect("runs should load");
+        assert_eq!(runs.len(), 1);
+        assert_eq!(
+            runs[0].source_item,
+            Some(SourceItemRef {
+                connector_id: "todoist".to_string(),
+                item_id: "task-42".to_string(),
+            })
+        );
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn connector_item_agents_take_name_and_directory_from_the_item() {
+        let (db, agent_id) = setup_mock_agent();
+        let project_id = db.list_agents().unwrap()[0].project_id.clone();
+        let mut item = ConnectorItem {
+            id: "epic-7".to_string(),
+            source: "obsidian".to_string(),
+            title: "Billing: Usage-based plans".to_string(),
+            content: None,
+            status: connectors::ItemStatus::Active,
+            priority: None,
+            tags: vec!["payments".to_string()],
+            url: None,
+            parent_id: None,
+            metadata: HashMap::new(),
+            created_at: None,
+            updated_at: None,
+            due_at: None,
+            raw: None,
+        };
+        let source = SourceItemRef {
+            connector_id: "obsidian".to_string(),
+            item_id: item.id.clone(),
+        };
+
+        let mut defaults = ItemAgentDefaults {
+            base_directory: Some("/work".to_string()),
+            ..Default::default()
+        };
+        let agent = agent_for_connector_item(&project_id, source.clone(), &item, &defaults);
+        assert_eq!(agent.name, "Billing: Usage-based plans");
+        assert_eq!(agent.function_tag, "payments");
+        assert_eq!(
+            agent.working_directory.as_deref(),
+            Some("/work/billing-usage-based-plans")
+        );
+
+        item.metadata.insert(
+            "working_directory".to_string(),
+            "/repos/billing".to_string(),
+        );
+        item.metadata
+            .insert("agent_name".to_string(), "Billing".to_string());
+        defaults.function_tag = Some("sdk".to_string());
+        let agent = agent_for_connector_item(&project_id, source.clone(), &item, &defaults);
+        assert_eq!(agent.name, "Billing");
+        assert_eq!(agent.function_tag, "sdk");
+        assert_eq!(agent.working_directory.as_deref(), Some("/repos/billing"));
+
+        db.create_agent(&agent).unwrap();
+        let stored = db.get_agent(&agent.id).unwrap().unwrap();
+        assert_eq!(stored.source_item, Some(source));
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn run_comments_anchor_to_existing_outputs() {
+        let (db, agent_id) = setup_mock_agent();
+        let mut run = Run::new(&agent_id, RunStatus::Completed, None);
+        run.outputs = vec![RunOutput {
+            kind: "message".to_string(),
+            content: "Pushed the fix".to_string(),
+            timestamp: Utc::now(),
+        }];
+        db.create_run(&run).expect("run should insert");
+
+        let general = add_comment(&db, &run.id, "  Broke CI, see line 40 ", None)
+            .expect("comment should save");
+        assert_eq!(general.body, "Broke CI, see line 40");
+        let anchored =
+            add_comment(&db, &run.id, "This claim is wrong", Some(0)).expect("comment should save");
+        assert!(add_comment(&db, &run.id, "Out of range", Some(1)).is_err());
+        assert!(add_comment(&db, &run.id, "   ", None).is_err());
+        assert!(add_comment(&db, "missing", "Hi", None).is_err());
+
+        let comments = db.list_run_comments(&run.id).unwrap();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].id, general.id);
+        assert_eq!(comments[1].output_index, Some(0));
+
+        db.delete_run_comment(&anchored.id).unwrap();
+        assert_eq!(db.list_run_comments(&run.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn handoff_switches_adapter_and_replays_condensed_context() {
+        let (db, agent_id) = setup_mock_agent();
+        for (minutes_ago, text) in [(30, "Draft the launch post"), (20, "Tighten the intro")] {
+            let mut msg = Message::to_agent(&agent_id, MessageKind::Instruction, text);
+            msg.created_at = Utc::now() - chrono::Duration::minutes(minutes_ago);
+            db.insert_message(&msg).expect("instruction should insert");
+        }
+        let run = Run::new(
+            &agent_id,
+            RunStatus::Completed,
+            Some("Rewrote the intro paragraph".to_string()),
+        );
+        db.create_run(&run).expect("run should insert");
+
+        let result = handoff(
+            &db,
+            &agent_id,
+            AdapterConfig {
+                adapter_type: AdapterType::Mock,
+                session_name: None,
+                endpoint: None,
+                command: None,
+                env: None,
+            },
+            HandoffContextStrategy::Condensed,
+        )
+        .expect("handoff should succeed");
+        assert_eq!(result.from, Some(AdapterType::Mock));
+        let context = result
+            .context_message
+            .expect("context should be sent")
+            .content;
+        assert!(context.contains("Last run (completed): Rewrote the intro paragraph"));
+        let draft = context
+            .find("1. Draft the launch post")
+            .expect("oldest first");
+        let tighten = context.find("2. Tighten the intro").expect("newest last");
+        assert!(draft < tighten);
+        assert!(db
+            .get_adapter_config(&agent_id)
+            .unwrap()
+            .is_some_and(|config| config.session_name.is_none()));
+
+        let summary_only = handoff_context(HandoffContextStrategy::LastRunSummary, &[], Some(&run))
+            .expect("summary should be replayed");
+        assert!(!summary_only.contains("Recent instructions"));
+        assert!(handoff_context(HandoffContextStrategy::None, &[], Some(&run)).is_none());
+
+        clear_adapter_runtime(&agent_id);
+    }
+
     #[test]
     fn adapter_retry_backoff_grows_and_caps() {
         assert_eq!(adapter_retry_backoff(1).as_secs(), 2);
@@ -1006,6 +5403,88 @@ mod tests {
         assert_eq!(adapter_retry_backoff(20).as_secs(), 64);
     }
 
+    #[test]
+    fn failed_starts_wait_out_the_backoff_on_the_database_clock() {
+        use crate::clock::{Clock, ManualClock};
+
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let db = Arc::new(
+            Database::new(":memory:")
+                .expect("in-memory db should initialize")
+                .with_clock(clock.clone()),
+        );
+        let project = Project::new("Test Project", "#112233");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Replay", &project.id, AgentKind::Api, "engineering");
+        db.create_agent(&agent).expect("agent should insert");
+        let config = AdapterConfig {
+            adapter_type: AdapterType::Simulation,
+            session_name: None,
+            endpoint: Some("/nonexistent/kanbun-transcript.json".to_string()),
+            command: None,
+            env: None,
+        };
+        db.set_adapter_config(&agent.id, &config)
+            .expect("adapter config should insert");
+        let failures = || {
+            adapter_runtime_snapshot(&agent.id)
+                .map(|state| state.consecutive_failures)
+                .unwrap_or_default()
+        };
+
+        assert!(ensure_adapter_started(&db, &agent.id, false).is_err());
+        assert_eq!(failures(), 1);
+
+        // Still inside the first 2s backoff: the supervisor skips the agent.
+        clock.advance(Duration::from_secs(1));
+        ensure_adapter_started(&db, &agent.id, false).expect("retry should wait");
+        assert_eq!(failures(), 1);
+
+        clock.advance(Duration::from_secs(2));
+        assert!(ensure_adapter_started(&db, &agent.id, false).is_err());
+        assert_eq!(failures(), 2);
+        let state = adapter_runtime_snapshot(&agent.id).expect("runtime state should exist");
+        assert_eq!(state.retry_after_seconds(clock.instant()), Some(4));
+        assert_eq!(state.last_failure_at, Some(clock.now()));
+
+        clear_adapter_runtime(&agent.id);
+    }
+
+    #[test]
+    fn idle_adapters_stop_and_stay_stopped_until_messaged() {
+        let (db, agent_id) = setup_mock_agent();
+        clear_adapter_runtime(&agent_id);
+        ensure_adapter_started(&db, &agent_id, false).expect("mock adapter should start");
+        let mut agent = db.get_agent(&agent_id).unwrap().unwrap();
+        assert!(!stop_if_idle(
+            &db,
+            &agent,
+            Utc::now() + chrono::Duration::hours(2)
+        ));
+
+        agent.config.idle_timeout_minutes = Some(30);
+        assert!(!stop_if_idle(&db, &agent, Utc::now()));
+        assert!(stop_if_idle(
+            &db,
+            &agent,
+            Utc::now() + chrono::Duration::hours(2)
+        ));
+        let state = adapter_runtime_snapshot(&agent_id).expect("runtime state should exist");
+        assert!(state.idle_stopped && !state.started);
+        let messages = db.get_messages_for_agent(&agent_id, 10).unwrap();
+        assert!(messages
+            .iter()
+            .any(|message| message.kind == MessageKind::StatusUpdate
+                && message.content.contains("30 minute(s)")));
+
+        ensure_adapter_started(&db, &agent_id, false).expect("supervisor pass should be a no-op");
+        assert!(!adapter_runtime_snapshot(&agent_id).unwrap().started);
+        ensure_adapter_started(&db, &agent_id, true).expect("message should restart adapter");
+        assert!(adapter_runtime_snapshot(&agent_id).unwrap().started);
+
+        clear_adapter_runtime(&agent_id);
+    }
+
     #[test]
     fn ensure_adapter_started_bootstraps_runtime_state() {
         let (db, agent_id) = setup_mock_agent();
@@ -1022,6 +5501,83 @@ mod tests {
         clear_adapter_runtime(&agent_id);
     }
 
+    #[test]
+    fn supervisor_pass_caches_the_health_check() {
+        let (db, agent_id) = setup_mock_agent();
+        clear_adapter_runtime(&agent_id);
+        ensure_adapter_started(&db, &agent_id, false).expect("mock adapter should start");
+        assert!(adapter_runtime_snapshot(&agent_id).unwrap().connected);
+
+        // A later supervisor pass caches the live check for the overview.
+        ensure_adapter_started(&db, &agent_id, false).expect("health pass should succeed");
+        let state = adapter_runtime_snapshot(&agent_id).expect("runtime state should exist");
+        assert!(state.connected);
+        assert!(state.last_checked_at.is_some());
+        assert!(state.last_heartbeat.is_some());
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn sustained_start_failures_raise_adapter_down() {
+        let (db, agent_id) = setup_mock_agent();
+        let agent = db
+            .get_agent(&agent_id)
+            .unwrap()
+            .expect("agent should exist");
+        let raised = || {
+            attention_items(&agent, "", None, &[])
+                .into_iter()
+                .any(|item| item.reason == "adapter_down")
+        };
+
+        for attempt in 1..=SUSTAINED_ADAPTER_FAILURES {
+            assert!(!raised(), "raised after {} failure(s)", attempt - 1);
+            record_adapter_start_failure(&db, &agent_id, "tmux not found")
+                .expect("failure should record");
+        }
+        assert!(raised());
+        let state = adapter_runtime_snapshot(&agent_id).expect("runtime state should exist");
+        assert!(state.failing_since <= state.last_failure_at);
+
+        ensure_adapter_started(&db, &agent_id, true).expect("forced start should succeed");
+        assert!(!raised());
+
+        clear_adapter_runtime(&agent_id);
+    }
+
+    #[test]
+    fn health_events_only_on_change() {
+        let mut runtime = HashMap::new();
+        runtime.insert(
+            "agent-1".to_string(),
+            AdapterRuntimeState {
+                started: true,
+                connected: true,
+                ..Default::default()
+            },
+        );
+        let mut last_seen = HashMap::new();
+
+        let first = health_changes(&runtime, &mut last_seen, Instant::now());
+        assert_eq!(first.len(), 1);
+        assert!(first[0].health.connected);
+        assert!(health_changes(&runtime, &mut last_seen, Instant::now()).is_empty());
+
+        let state = runtime.get_mut("agent-1").unwrap();
+        state.connected = false;
+        state.consecutive_failures = 1;
+        state.last_error = Some("session ended".to_string());
+        let dropped = health_changes(&runtime, &mut last_seen, Instant::now());
+        assert_eq!(dropped.len(), 1);
+        assert!(!dropped[0].health.connected);
+        assert_eq!(dropped[0].health.consecutive_failures, Some(1));
+
+        runtime.clear();
+        assert!(health_changes(&runtime, &mut last_seen, Instant::now()).is_empty());
+        assert!(last_seen.is_empty());
+    }
+
     #[test]
     fn forced_start_bypasses_retry_cooldown() {
         let (db, agent_id) = setup_mock_agent();
@@ -1038,6 +5594,7 @@ mod tests {
                     last_error: Some("simulated failure".to_string()),
                     last_failure_at: Some(Utc::now()),
                     last_started_at: None,
+                    ..Default::default()
                 },
             );
         }
@@ -1046,7 +5603,7 @@ mod tests {
         let cooled = adapter_runtime_snapshot(&agent_id).expect("runtime state should exist");
         assert!(!cooled.started);
         assert_eq!(cooled.consecutive_failures, 3);
-        assert!(cooled.retry_after_seconds().is_some());
+        assert!(cooled.retry_after_seconds(Instant::now()).is_some());
 
         ensure_adapter_started(&db, &agent_id, true).expect("forced start should bypass cooldown");
         let recovered = adapter_runtime_snapshot(&agent_id).expect("runtime state should exist");
@@ -0,0 +1,265 @@
+use super::*;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize as DeserializeDerive;
+
+pub const INBOX_CONNECTOR_ID: &str = "inbox";
+
+/// Built-in Inbox connector. Items arrive from outside — the local intake
+/// endpoint, or a webhook relay (Zapier, Make, a form backend) polled for
+/// pending tasks — rather than from a service Kanbun syncs with.
+///
+/// Setup: nothing required. Optionally set relay_url in ConnectorConfig
+/// settings to a URL returning pending tasks as JSON, with auth_token sent
+/// as a bearer token.
+///
+/// Mapping:
+///   title / subject / name → title
+///   content / body / description / text / notes → content
+///   tags (list or comma-separated) → tags
+///   due / due_at → due_at
+///   project / project_id → metadata["project_id"]
+///   agent / agent_id → metadata["agent_id"], the agent to brief
+pub struct InboxConnector {
+    client: Client,
+    relay_url: Option<String>,
+    token: Option<String>,
+}
+
+#[derive(Debug, DeserializeDerive)]
+#[serde(untagged)]
+enum TagList {
+    List(Vec<String>),
+    Text(String),
+}
+
+/// One task as posted by a form or automation.
+#[derive(Debug, DeserializeDerive)]
+pub struct IntakeTask {
+    #[serde(default)]
+    pub id: Option<String>, // sender's id, so a resend doesn't duplicate
+    #[serde(alias = "subject", alias = "name")]
+    pub title: String,
+    #[serde(
+        default,
+        alias = "body",
+        alias = "description",
+        alias = "text",
+        alias = "notes"
+    )]
+    pub content: Option<String>,
+    #[serde(default)]
+    tags: Option<TagList>,
+    #[serde(default)]
+    pub priority: Option<u8>,
+    #[serde(default, alias = "due")]
+    pub due_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default, alias = "project")]
+    pub project_id: Option<String>,
+    #[serde(default, alias = "agent")]
+    pub agent_id: Option<String>,
+}
+
+const MAX_CAPTURE_TITLE_CHARS: usize = 120;
+
+impl IntakeTask {
+    /// A task from free text: the first line becomes the title and the full
+    /// text the content when there's more than fits in it.
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim();
+        let first_line = text.lines().next().unwrap_or_default().trim();
+        let title: String = first_line.chars().take(MAX_CAPTURE_TITLE_CHARS).collect();
+        Self {
+            id: None,
+            content: (title != text).then(|| text.to_string()),
+            title,
+            tags: None,
+            priority: None,
+            due_at: None,
+            url: None,
+            project_id: None,
+            agent_id: None,
+        }
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        let tags = match &self.tags {
+            Some(TagList::List(tags)) => tags.clone(),
+            Some(TagList::Text(text)) => text.split(',').map(str::to_string).collect(),
+            None => vec![],
+        };
+        tags.into_iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// Normalize into a cached Inbox item. `received_via` is recorded in
+    /// metadata ("intake" or "relay").
+    pub fn into_item(
+        self,
+        received_via: &str,
+        now: DateTime<Utc>,
+    ) -> Result<ConnectorItem, String> {
+        let title = self.title.trim().to_string();
+        if title.is_empty() {
+            return Err("task title is required".to_string());
+        }
+        if self
+            .priority
+            .is_some_and(|priority| !(1..=4).contains(&priority))
+        {
+            return Err("priority must be between 1 and 4".to_string());
+        }
+        let tags = self.tags();
+        let mut metadata = HashMap::from([("received_via".to_string(), received_via.to_string())]);
+        for (key, value) in [("project_id", self.project_id), ("agent_id", self.agent_id)] {
+            if let Some(value) = value.filter(|value| !value.trim().is_empty()) {
+                metadata.insert(key.to_string(), value.trim().to_string());
+            }
+        }
+        Ok(ConnectorItem {
+            id: self
+                .id
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            source: INBOX_CONNECTOR_ID.to_string(),
+            title,
+            content: self.content.filter(|content| !content.trim().is_empty()),
+            status: ItemStatus::Active,
+            priority: self.priority,
+            tags,
+            url: self.url.filter(|url| !url.trim().is_empty()),
+            parent_id: None,
+            metadata,
+            created_at: Some(now),
+            updated_at: Some(now),
+            due_at: self.due_at,
+            raw: None,
+        })
+    }
+}
+
+/// Tasks from a request body: one task, a list, or `{"tasks": [...]}`.
+pub fn parse_tasks(body: serde_json::Value) -> Result<Vec<IntakeTask>, String> {
+    let body = match body {
+        serde_json::Value::Object(mut object) if object.contains_key("tasks") => {
+            object.remove("tasks").unwrap_or_default()
+        }
+        body => body,
+    };
+    let tasks = match body {
+        serde_json::Value::Array(tasks) => tasks,
+        task => vec![task],
+    };
+    tasks
+        .into_iter()
+        .map(|task| serde_json::from_value(task).map_err(|e| format!("invalid task: {}", e)))
+        .collect()
+}
+
+impl InboxConnector {
+    pub fn new(relay_url: Option<String>, token: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            relay_url: relay_url.filter(|url| !url.trim().is_empty()),
+            token: token.filter(|token| !token.trim().is_empty()),
+        }
+    }
+
+    /// Pending tasks from the relay; empty when none is configured.
+    pub async fn fetch_relay(&self) -> Result<Vec<IntakeTask>, ConnectorError> {
+        let Some(url) = &self.relay_url else {
+            return Ok(vec![]);
+        };
+        let mut request = self.client.get(url);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| ConnectorError::NetworkError(e.to_string()))?;
+        if resp.status() == 401 || resp.status() == 403 {
+            return Err(ConnectorError::AuthFailed(
+                "Relay rejected the token".into(),
+            ));
+        }
+        if !resp.status().is_success() {
+            return Err(ConnectorError::NetworkError(format!(
+                "Relay returned {}",
+                resp.status()
+            )));
+        }
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| ConnectorError::ParseError(e.to_string()))?;
+        if body.is_null() {
+            return Ok(vec![]);
+        }
+        parse_tasks(body).map_err(ConnectorError::ParseError)
+    }
+}
+
+#[async_trait]
+impl Connector for InboxConnector {
+    fn info(&self) -> ConnectorInfo {
+        ConnectorInfo {
+            id: INBOX_CONNECTOR_ID.into(),
+            name: "Inbox".into(),
+            icon: "📥".into(),
+            capabilities: ConnectorCapabilities {
+                can_read: true,
+                can_write: true,
+                can_delete: true,
+                can_search: false,
+                supports_hierarchy: false,
+                supports_due_dates: true,
+                supports_priorities: true,
+                supports_tags: true,
+            },
+            auth_type: AuthType::Local,
+            status: ConnectorStatus::Connected,
+        }
+    }
+
+    async fn pull(
+        &self,
+        _filter: Option<PullFilter>,
+    ) -> Result<Vec<ConnectorItem>, ConnectorError> {
+        let now = Utc::now();
+        self.fetch_relay()
+            .await?
+            .into_iter()
+            .map(|task| task.into_item("relay", now))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ConnectorError::ParseError)
+    }
+
+    /// Inbox items only live in the local cache, so pushing just assigns an
+    /// id; the caller caches the result.
+    async fn push(&self, item: &ConnectorItem) -> Result<ConnectorItem, ConnectorError> {
+        let mut item = item.clone();
+        if item.id.trim().is_empty() {
+            item.id = uuid::Uuid::new_v4().to_string();
+        }
+        item.source = INBOX_CONNECTOR_ID.to_string();
+        Ok(item)
+    }
+
+    async fn update(&self, item: &ConnectorItem) -> Result<ConnectorItem, ConnectorError> {
+        Ok(item.clone())
+    }
+
+    async fn delete(&self, _external_id: &str) -> Result<(), ConnectorError> {
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<ConnectorStatus, ConnectorError> {
+        Ok(ConnectorStatus::Connected)
+    }
+}
@@ -1,8 +1,9 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod inbox;
 pub mod obsidian;
 pub mod todoist;
 
@@ -25,6 +26,36 @@ pub struct ConnectorItem {
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
     pub due_at: Option<DateTime<Utc>>,
+    /// The service's own JSON for the item as last fetched, for debugging
+    /// the mapping above. Kept gzipped in the cache and only sent by
+    /// `get_connector_item_detail`.
+    #[serde(default, skip_serializing)]
+    pub raw: Option<serde_json::Value>,
+}
+
+/// Metadata key set when a service gives a due date without a time
+/// ("2026-03-01"). `due_at` is then midnight UTC of that date, for ordering;
+/// the agenda places the item on that day in the user's timezone instead.
+pub const DUE_DATE_KEY: &str = "due_date";
+
+/// A cached item next to the upstream JSON it was mapped from, for working
+/// out why a field came through wrong. `raw` is empty for items the
+/// connector builds itself (inbox captures, Obsidian notes).
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectorItemDetail {
+    pub connector_id: String,
+    pub item: ConnectorItem,
+    pub raw: Option<serde_json::Value>,
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+impl ConnectorItem {
+    /// The all-day due date, if the item has one.
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        self.metadata
+            .get(DUE_DATE_KEY)
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -190,6 +221,10 @@ pub fn create_connector(config: &ConnectorConfig) -> Result<Box<dyn Connector>,
                 .ok_or_else(|| ConnectorError::Other("Obsidian vault path required".into()))?;
             Ok(Box::new(obsidian::ObsidianConnector::new(vault_path)))
         }
+        "inbox" => Ok(Box::new(inbox::InboxConnector::new(
+            config.settings.get("relay_url").cloned(),
+            config.auth_token.clone(),
+        ))),
         // Future:
         // "notion" => { ... }
         // "linear" => { ... }
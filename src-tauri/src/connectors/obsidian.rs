@@ -150,9 +150,13 @@ impl ObsidianConnector {
             .map(|t| DateTime::<Utc>::from(t));
 
         // Parse due date from frontmatter
-        let due_at = frontmatter
+        let due_date = frontmatter
             .get("due")
-            .and_then(|d| chrono::NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok());
+        if let Some(date) = due_date {
+            item_metadata.insert(super::DUE_DATE_KEY.into(), date.to_string());
+        }
+        let due_at = due_date
             .and_then(|nd| nd.and_hms_opt(0, 0, 0))
             .map(|ndt| ndt.and_utc());
 
@@ -176,6 +180,7 @@ impl ObsidianConnector {
             created_at,
             updated_at,
             due_at,
+            raw: None,
         })
     }
 
@@ -281,7 +286,7 @@ impl Connector for ObsidianConnector {
                     items.push(item);
                 }
                 Err(e) => {
-                    log::warn!("Failed to parse {}: {}", path.display(), e);
+                    tracing::warn!("Failed to parse {}: {}", path.display(), e);
                 }
             }
         }
@@ -15,6 +15,7 @@ const BASE_URL: &str = "https://api.todoist.com/rest/v2";
 ///   Labels → tags
 ///   Priority (1-4, where 4=urgent in Todoist) → priority (normalized: 4→1, 3→2, 2→3, 1→4)
 ///   Section/Project → we flatten for now, project name goes in metadata
+///   Completed → closes the task; any other status reopens it
 pub struct TodoistConnector {
     client: Client,
     token: String,
@@ -129,6 +130,9 @@ impl TodoistConnector {
             if due.is_recurring {
                 metadata.insert("recurring".into(), "true".into());
             }
+            if due.datetime.is_none() {
+                metadata.insert(super::DUE_DATE_KEY.into(), due.date.clone());
+            }
         }
 
         ConnectorItem {
@@ -155,8 +159,18 @@ impl TodoistConnector {
                 .map(|t| t.with_timezone(&Utc)),
             updated_at: None, // Todoist REST v2 doesn't return this on tasks
             due_at: Self::parse_due(&task.due),
+            raw: None,
         }
     }
+
+    /// Map one task from the API, keeping its JSON as the item's `raw`.
+    fn parse_task(&self, raw: serde_json::Value) -> Result<ConnectorItem, ConnectorError> {
+        let task = TodoistTask::deserialize(&raw)
+            .map_err(|e| ConnectorError::ParseError(e.to_string()))?;
+        let mut item = self.task_to_item(task);
+        item.raw = Some(raw);
+        Ok(item)
+    }
 }
 
 #[async_trait]
@@ -234,13 +248,15 @@ impl Connector for TodoistConnector {
             )));
         }
 
-        let tasks: Vec<TodoistTask> = response
+        let tasks: Vec<serde_json::Value> = response
             .json()
             .await
             .map_err(|e| ConnectorError::ParseError(e.to_string()))?;
 
-        let mut items: Vec<ConnectorItem> =
-            tasks.into_iter().map(|t| self.task_to_item(t)).collect();
+        let mut items: Vec<ConnectorItem> = tasks
+            .into_iter()
+            .map(|t| self.parse_task(t))
+            .collect::<Result<_, _>>()?;
 
         // Apply client-side filters that Todoist API doesn't support natively
         if let Some(ref f) = filter {
@@ -284,12 +300,12 @@ impl Connector for TodoistConnector {
             )));
         }
 
-        let task: TodoistTask = response
+        let task: serde_json::Value = response
             .json()
             .await
             .map_err(|e| ConnectorError::ParseError(e.to_string()))?;
 
-        Ok(self.task_to_item(task))
+        self.parse_task(task)
     }
 
     async fn update(&self, item: &ConnectorItem) -> Result<ConnectorItem, ConnectorError> {
@@ -327,12 +343,38 @@ impl Connector for TodoistConnector {
             )));
         }
 
-        let task: TodoistTask = response
+        let task: serde_json::Value = response
             .json()
             .await
             .map_err(|e| ConnectorError::ParseError(e.to_string()))?;
+        let mut updated = self.parse_task(task)?;
+
+        // Completion isn't part of the task body; it has its own endpoints.
+        let complete = item.status == ItemStatus::Completed;
+        if complete != (updated.status == ItemStatus::Completed) {
+            let action = if complete { "close" } else { "reopen" };
+            let response = self
+                .client
+                .post(format!("{}/tasks/{}/{}", BASE_URL, item.id, action))
+                .header("Authorization", self.auth_header())
+                .send()
+                .await
+                .map_err(|e| ConnectorError::NetworkError(e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(ConnectorError::Other(format!(
+                    "Failed to {} task: {}",
+                    action,
+                    response.status()
+                )));
+            }
+        }
 
-        Ok(self.task_to_item(task))
+        updated.status = if complete {
+            ItemStatus::Completed
+        } else {
+            ItemStatus::Active
+        };
+        Ok(updated)
     }
 
     async fn delete(&self, external_id: &str) -> Result<(), ConnectorError> {
@@ -0,0 +1,756 @@
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// ── Context docs ────────────────────────────────────────────────────────────
+// Project context documents beyond plain CRUD. Saving a doc keeps the version
+// it replaces as a revision (see `Database::save_project_context_doc`); this
+// restores and compares those versions.
+//
+// Docs can also be imported from Markdown or text files. An imported doc
+// remembers its file; with `watch_source` on, a background pass re-reads the
+// file and updates the doc when it changes.
+//
+// New docs can start from a template: a few built in (product brief,
+// runbook, client onboarding) plus any saved under `DOC_TEMPLATES_KEY`.
+// `{{placeholders}}` are filled when the doc is created; `{{project}}` and
+// `{{date}}` are always available.
+//
+// An agent can have its own ordered stack of the project's docs; agents
+// without one see all of them.
+
+pub const DOC_TEMPLATES_KEY: &str = "context_doc_templates";
+
+const IMPORT_EXTENSIONS: [&str; 4] = ["md", "markdown", "mdx", "txt"];
+const MAX_IMPORT_BYTES: u64 = 1_000_000;
+const MAX_IMPORT_FILES: usize = 200;
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String, // without the trailing newline
+}
+
+/// Line diff between two versions of a doc.
+#[derive(Debug, Clone, Serialize)]
+pub struct DocDiff {
+    pub from_title: String,
+    pub to_title: String,
+    pub added: usize,
+    pub removed: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+pub fn diff_text(from: &str, to: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(from, to)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            op: match change.tag() {
+                ChangeTag::Equal => DiffOp::Equal,
+                ChangeTag::Insert => DiffOp::Insert,
+                ChangeTag::Delete => DiffOp::Delete,
+            },
+            text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+        })
+        .collect()
+}
+
+fn load_doc(db: &Database, doc_id: &str) -> Result<ProjectContextDocument, CommandError> {
+    db.get_project_context_doc(doc_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Context document not found: {}", doc_id)))
+}
+
+fn load_revision(db: &Database, revision_id: &str) -> Result<ContextDocRevision, CommandError> {
+    db.get_doc_revision(revision_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Revision not found: {}", revision_id)))
+}
+
+/// Title and content of one version: a revision, or the current doc when
+/// `revision_id` is None.
+fn version(
+    db: &Database,
+    doc: &ProjectContextDocument,
+    revision_id: Option<&str>,
+) -> Result<(String, String), CommandError> {
+    let Some(revision_id) = revision_id else {
+        return Ok((doc.title.clone(), doc.content.clone()));
+    };
+    let revision = load_revision(db, revision_id)?;
+    if revision.doc_id != doc.id {
+        return Err(CommandError::invalid_input(
+            "Revision belongs to a different document",
+        ));
+    }
+    Ok((revision.title, revision.content))
+}
+
+/// Compare two versions of a doc; either side may be the current version.
+pub fn diff_versions(
+    db: &Database,
+    doc_id: &str,
+    from_revision_id: Option<&str>,
+    to_revision_id: Option<&str>,
+) -> Result<DocDiff, CommandError> {
+    let doc = load_doc(db, doc_id)?;
+    let (from_title, from_content) = version(db, &doc, from_revision_id)?;
+    let (to_title, to_content) = version(db, &doc, to_revision_id)?;
+    let lines = diff_text(&from_content, &to_content);
+    Ok(DocDiff {
+        from_title,
+        to_title,
+        added: lines
+            .iter()
+            .filter(|line| line.op == DiffOp::Insert)
+            .count(),
+        removed: lines
+            .iter()
+            .filter(|line| line.op == DiffOp::Delete)
+            .count(),
+        lines,
+    })
+}
+
+/// Bring back an earlier version. The version being replaced becomes a
+/// revision itself, so a restore can be undone the same way.
+pub fn restore_revision(
+    db: &Database,
+    revision_id: &str,
+) -> Result<ProjectContextDocument, CommandError> {
+    let revision = load_revision(db, revision_id)?;
+    let mut doc = load_doc(db, &revision.doc_id)?;
+    doc.title = revision.title;
+    doc.content = revision.content;
+    doc.updated_at = chrono::Utc::now();
+    db.save_project_context_doc(&doc)?;
+    Ok(doc)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextDocTemplate {
+    pub id: String,
+    pub name: String,
+    pub title: String, // title of created docs; may use placeholders too
+    pub content: String,
+    #[serde(default)]
+    pub built_in: bool,
+    #[serde(default)]
+    pub placeholders: Vec<String>, // filled in when listed, for the form
+}
+
+const BUILT_IN_TEMPLATES: [(&str, &str, &str, &str); 3] = [
+    (
+        "product_brief",
+        "Product brief",
+        "{{product}} brief",
+        "# {{product}}\n\n\
+         ## What it is\n{{summary}}\n\n\
+         ## Who it's for\n{{audience}}\n\n\
+         ## Voice and tone\n{{voice}}\n\n\
+         ## Out of scope\n- \n\n\
+         _{{project}}, written {{date}}_\n",
+    ),
+    (
+        "runbook",
+        "Runbook",
+        "{{service}} runbook",
+        "# {{service}} runbook\n\n\
+         ## Where it runs\n{{environment}}\n\n\
+         ## Deploying\n1. \n\n\
+         ## Checking health\n- \n\n\
+         ## When it breaks\nEscalate to {{owner}}.\n\n\
+         _{{project}}, written {{date}}_\n",
+    ),
+    (
+        "client_onboarding",
+        "Client onboarding",
+        "{{client}} onboarding",
+        "# Working with {{client}}\n\n\
+         Primary contact: {{contact}}\n\n\
+         ## Goals\n{{goals}}\n\n\
+         ## How they like to work\n- \n\n\
+         ## Access and accounts\n- \n\n\
+         _{{project}}, written {{date}}_\n",
+    ),
+];
+
+/// Names in `{{…}}` in order of first use.
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("}}") else {
+            break;
+        };
+        let name = rest[..end].trim();
+        if !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !names.iter().any(|known| known == name)
+        {
+            names.push(name.to_string());
+        }
+        rest = &rest[end + 2..];
+    }
+    names
+}
+
+fn fill(text: &str, values: &HashMap<String, String>) -> String {
+    values.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{{{}}}}}", name), value)
+    })
+}
+
+/// Built-in templates followed by saved ones, with their placeholders.
+pub fn load_templates(db: &Database) -> Result<Vec<ContextDocTemplate>, CommandError> {
+    let saved = db
+        .get_setting::<Vec<ContextDocTemplate>>(DOC_TEMPLATES_KEY)
+        .map(Option::unwrap_or_default)?;
+    let built_in = BUILT_IN_TEMPLATES
+        .iter()
+        .map(|(id, name, title, content)| ContextDocTemplate {
+            id: id.to_string(),
+            name: name.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            built_in: true,
+            placeholders: vec![],
+        });
+    Ok(built_in
+        .chain(saved)
+        .map(|mut template| {
+            template.placeholders =
+                placeholders(&format!("{}\n{}", template.title, template.content));
+            template
+        })
+        .collect())
+}
+
+/// Add or replace a saved template. Built-in ones can't be overwritten.
+pub fn save_template(
+    db: &Database,
+    mut template: ContextDocTemplate,
+) -> Result<ContextDocTemplate, CommandError> {
+    template.name = template.name.trim().to_string();
+    if template.name.is_empty() {
+        return Err(CommandError::invalid_input("Template name cannot be empty"));
+    }
+    if template.title.trim().is_empty() {
+        template.title = template.name.clone();
+    }
+    if template.id.trim().is_empty() {
+        template.id = uuid::Uuid::new_v4().to_string();
+    }
+    if BUILT_IN_TEMPLATES.iter().any(|(id, ..)| *id == template.id) {
+        return Err(CommandError::invalid_input(
+            "Built-in templates can't be changed",
+        ));
+    }
+    template.built_in = false;
+    template.placeholders = vec![];
+    let mut saved: Vec<ContextDocTemplate> = load_templates(db)?
+        .into_iter()
+        .filter(|stored| !stored.built_in && stored.id != template.id)
+        .map(|mut stored| {
+            stored.placeholders = vec![];
+            stored
+        })
+        .collect();
+    saved.push(template.clone());
+    db.set_setting(DOC_TEMPLATES_KEY, &saved)?;
+    template.placeholders = placeholders(&format!("{}\n{}", template.title, template.content));
+    Ok(template)
+}
+
+pub fn delete_template(db: &Database, template_id: &str) -> Result<(), CommandError> {
+    let saved: Vec<ContextDocTemplate> = load_templates(db)?
+        .into_iter()
+        .filter(|stored| !stored.built_in && stored.id != template_id)
+        .map(|mut stored| {
+            stored.placeholders = vec![];
+            stored
+        })
+        .collect();
+    db.set_setting(DOC_TEMPLATES_KEY, &saved)
+        .map_err(CommandError::from)
+}
+
+/// Create a doc in `project_id` from a template. Every placeholder needs a
+/// value, apart from `project` and `date`, which are filled in.
+pub fn create_from_template(
+    db: &Database,
+    project_id: &str,
+    template_id: &str,
+    values: HashMap<String, String>,
+) -> Result<ProjectContextDocument, CommandError> {
+    let project = db
+        .list_projects()?
+        .into_iter()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| CommandError::not_found(format!("Project not found: {}", project_id)))?;
+    let template = load_templates(db)?
+        .into_iter()
+        .find(|template| template.id == template_id)
+        .ok_or_else(|| CommandError::not_found(format!("Template not found: {}", template_id)))?;
+
+    let mut values: HashMap<String, String> = values
+        .into_iter()
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+    values.entry("project".to_string()).or_insert(project.name);
+    values
+        .entry("date".to_string())
+        .or_insert_with(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let missing: Vec<&str> = template
+        .placeholders
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !values.contains_key(*name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(CommandError::invalid_input(format!(
+            "Missing values for: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let doc = ProjectContextDocument::new(
+        project_id,
+        fill(&template.title, &values).trim(),
+        &fill(&template.content, &values),
+    );
+    db.save_project_context_doc(&doc)?;
+    Ok(doc)
+}
+
+fn load_agent(db: &Database, agent_id: &str) -> Result<Agent, CommandError> {
+    db.get_agent(agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))
+}
+
+/// The docs an agent works with: its own stack in order, or all of its
+/// project's docs when it has none.
+pub fn agent_context(
+    db: &Database,
+    agent: &Agent,
+) -> Result<Vec<ProjectContextDocument>, CommandError> {
+    let stack = db.list_agent_context_docs(&agent.id)?;
+    if !stack.is_empty() {
+        return Ok(stack);
+    }
+    db.list_project_context_docs(&agent.project_id)
+        .map_err(CommandError::from)
+}
+
+/// Set an agent's context stack. Docs must belong to the agent's project;
+/// an empty list goes back to all of them.
+pub fn set_agent_context(
+    db: &Database,
+    agent_id: &str,
+    doc_ids: Vec<String>,
+) -> Result<Vec<ProjectContextDocument>, CommandError> {
+    let agent = load_agent(db, agent_id)?;
+    let mut seen = std::collections::HashSet::new();
+    let doc_ids: Vec<String> = doc_ids
+        .into_iter()
+        .filter(|doc_id| seen.insert(doc_id.clone()))
+        .collect();
+    for doc_id in &doc_ids {
+        if load_doc(db, doc_id)?.project_id != agent.project_id {
+            return Err(CommandError::invalid_input(format!(
+                "Context document {} belongs to a different project",
+                doc_id
+            )));
+        }
+    }
+    db.set_agent_context_docs(&agent.id, &doc_ids)?;
+    agent_context(db, &agent)
+}
+
+/// Docs created or refreshed by an import, and the files left out.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextDocImport {
+    pub docs: Vec<ProjectContextDocument>,
+    pub skipped: Vec<String>, // "path: reason"
+}
+
+fn title_from_path(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().trim().to_string())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| "Untitled context".to_string())
+}
+
+fn read_source(path: &Path) -> Result<String, CommandError> {
+    let metadata = std::fs::metadata(path)?;
+    if metadata.len() > MAX_IMPORT_BYTES {
+        return Err(CommandError::invalid_input(format!(
+            "larger than {} bytes",
+            MAX_IMPORT_BYTES
+        )));
+    }
+    let bytes = std::fs::read(path)?;
+    String::from_utf8(bytes).map_err(|_| CommandError::invalid_input("not UTF-8 text"))
+}
+
+/// Files to import from `root`: the file itself, or the files under a
+/// folder matching `pattern` (relative to the folder), Markdown and text
+/// files by default. Hidden and gitignored files are left out.
+fn import_paths(root: &Path, pattern: Option<&str>) -> Result<Vec<PathBuf>, CommandError> {
+    if root.is_file() {
+        return Ok(vec![root.to_path_buf()]);
+    }
+    if !root.is_dir() {
+        return Err(CommandError::not_found(format!(
+            "Not found: {}",
+            root.display()
+        )));
+    }
+    let matcher = match pattern.map(str::trim).filter(|pattern| !pattern.is_empty()) {
+        Some(pattern) => Some(
+            Glob::new(pattern)
+                .map_err(|error| format!("invalid glob '{}': {}", pattern, error))?
+                .compile_matcher(),
+        ),
+        None => None,
+    };
+    let mut paths: Vec<PathBuf> = ignore::WalkBuilder::new(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| match &matcher {
+            Some(matcher) => matcher.is_match(path.strip_prefix(root).unwrap_or(path)),
+            None => path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    IMPORT_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+                }),
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Import a file, or a folder's matching files, as context docs titled
+/// after their file names. Files imported before update their existing doc
+/// instead of adding another.
+pub fn import_docs(
+    db: &Database,
+    project_id: &str,
+    path: &str,
+    pattern: Option<&str>,
+    watch: bool,
+) -> Result<ContextDocImport, CommandError> {
+    if !db
+        .list_projects()?
+        .iter()
+        .any(|project| project.id == project_id)
+    {
+        return Err(CommandError::not_found(format!(
+            "Project not found: {}",
+            project_id
+        )));
+    }
+    let root = PathBuf::from(shellexpand::tilde(path.trim()).to_string());
+    let root = root.canonicalize().unwrap_or(root);
+    let mut paths = import_paths(&root, pattern)?;
+    let mut skipped: Vec<String> = paths
+        .iter()
+        .skip(MAX_IMPORT_FILES)
+        .map(|path| {
+            format!(
+                "{}: over the {} file limit",
+                path.display(),
+                MAX_IMPORT_FILES
+            )
+        })
+        .collect();
+    paths.truncate(MAX_IMPORT_FILES);
+
+    let mut existing: HashMap<String, ProjectContextDocument> = db
+        .list_project_context_docs(project_id)?
+        .into_iter()
+        .filter_map(|doc| Some((doc.source_path.clone()?, doc)))
+        .collect();
+    let mut docs = Vec::with_capacity(paths.len());
+    for path in paths {
+        let content = match read_source(&path) {
+            Ok(content) => content,
+            Err(reason) => {
+                skipped.push(format!("{}: {}", path.display(), reason));
+                continue;
+            }
+        };
+        let source_path = path.to_string_lossy().to_string();
+        let mut doc = match existing.remove(&source_path) {
+            Some(mut doc) => {
+                doc.content = content;
+                doc.updated_at = chrono::Utc::now();
+                doc
+            }
+            None => {
+                let mut doc =
+                    ProjectContextDocument::new(project_id, &title_from_path(&path), &content);
+                doc.source_path = Some(source_path);
+                doc
+            }
+        };
+        doc.watch_source = watch;
+        db.save_project_context_doc(&doc)?;
+        docs.push(doc);
+    }
+    Ok(ContextDocImport { docs, skipped })
+}
+
+/// Re-read watched source files and update docs whose file changed. Files
+/// that can't be read leave their doc as it is.
+pub fn sync_watched(db: &Database) -> Result<usize, CommandError> {
+    let mut updated = 0;
+    for mut doc in db.list_watched_context_docs()? {
+        let Some(source_path) = doc.source_path.clone() else {
+            continue;
+        };
+        let content = match read_source(Path::new(&source_path)) {
+            Ok(content) => content,
+            Err(reason) => {
+                tracing::debug!("Skipping context doc source {}: {}", source_path, reason);
+                continue;
+            }
+        };
+        if content == doc.content {
+            continue;
+        }
+        doc.content = content;
+        doc.updated_at = chrono::Utc::now();
+        db.save_project_context_doc(&doc)?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        match sync_watched(&db) {
+            Ok(0) => {}
+            Ok(updated) => tracing::info!("Updated {} context doc(s) from their files", updated),
+            Err(error) => tracing::warn!("Context doc sync failed: {}", error),
+        }
+        std::thread::sleep(SYNC_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_keep_revisions_that_restore_and_diff() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+
+        let mut doc =
+            ProjectContextDocument::new(&project.id, "Brief", "Tone: plain\nAudience: devs\n");
+        db.save_project_context_doc(&doc).unwrap();
+        // Unchanged saves don't pile up revisions.
+        db.save_project_context_doc(&doc).unwrap();
+        assert!(db.list_doc_revisions(&doc.id).unwrap().is_empty());
+
+        doc.content = "Tone: plain\nAudience: founders\n".to_string();
+        doc.updated_at = chrono::Utc::now();
+        db.save_project_context_doc(&doc).unwrap();
+        let revisions = db.list_doc_revisions(&doc.id).unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content, "Tone: plain\nAudience: devs\n");
+
+        let diff = diff_versions(&db, &doc.id, Some(&revisions[0].id), None).unwrap();
+        assert_eq!((diff.added, diff.removed), (1, 1));
+        assert_eq!(diff.lines[0].op, DiffOp::Equal);
+        assert!(diff
+            .lines
+            .iter()
+            .any(|line| line.op == DiffOp::Delete && line.text == "Audience: devs"));
+
+        let restored = restore_revision(&db, &revisions[0].id).unwrap();
+        assert_eq!(restored.content, "Tone: plain\nAudience: devs\n");
+        let revisions = db.list_doc_revisions(&doc.id).unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].content, "Tone: plain\nAudience: founders\n");
+
+        db.delete_project_context_doc(&doc.id).unwrap();
+        assert!(db.list_doc_revisions(&doc.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn imports_folders_by_glob_and_follows_watched_files() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let dir = std::env::temp_dir().join(format!("kanbun-docs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("guides")).unwrap();
+        std::fs::write(dir.join("API conventions.md"), "Use snake_case.").unwrap();
+        std::fs::write(dir.join("guides/voice.txt"), "Plain and direct.").unwrap();
+        std::fs::write(dir.join("logo.png"), [0u8, 159, 146, 150]).unwrap();
+
+        let dir_path = dir.to_string_lossy().to_string();
+        let imported = import_docs(&db, &project.id, &dir_path, None, true).unwrap();
+        let mut titles: Vec<&str> = imported.docs.iter().map(|doc| doc.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, ["API conventions", "voice"]);
+        assert!(imported.skipped.is_empty());
+
+        let only_markdown = import_docs(&db, &project.id, &dir_path, Some("*.md"), true).unwrap();
+        assert_eq!(only_markdown.docs.len(), 1);
+        // Re-importing refreshes the existing doc.
+        assert_eq!(db.list_project_context_docs(&project.id).unwrap().len(), 2);
+
+        std::fs::write(dir.join("API conventions.md"), "Use camelCase.").unwrap();
+        assert_eq!(sync_watched(&db).unwrap(), 1);
+        let doc = db
+            .get_project_context_doc(&only_markdown.docs[0].id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(doc.content, "Use camelCase.");
+        assert_eq!(db.list_doc_revisions(&doc.id).unwrap().len(), 1);
+        assert_eq!(sync_watched(&db).unwrap(), 0);
+
+        assert!(import_docs(&db, &project.id, &dir_path, Some("["), false).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn templates_fill_placeholders_and_require_values() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+
+        let templates = load_templates(&db).unwrap();
+        let runbook = templates
+            .iter()
+            .find(|template| template.id == "runbook")
+            .unwrap();
+        assert_eq!(
+            runbook.placeholders,
+            ["service", "environment", "owner", "project", "date"]
+        );
+
+        let values = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+        let error = create_from_template(
+            &db,
+            &project.id,
+            "runbook",
+            values(&[("service", "API"), ("owner", " ")]),
+        )
+        .unwrap_err();
+        assert_eq!(error.message, "Missing values for: environment, owner");
+
+        let doc = create_from_template(
+            &db,
+            &project.id,
+            "runbook",
+            values(&[
+                ("service", "API"),
+                ("environment", "Fly.io"),
+                ("owner", "Sam"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(doc.title, "API runbook");
+        assert!(doc.content.contains("Escalate to Sam."));
+        assert!(doc.content.contains("_Site, written "));
+        assert!(!doc.content.contains("{{"));
+
+        let saved = save_template(
+            &db,
+            ContextDocTemplate {
+                id: String::new(),
+                name: "Style guide".to_string(),
+                title: String::new(),
+                content: "Prefer {{tone}}.".to_string(),
+                built_in: false,
+                placeholders: vec![],
+            },
+        )
+        .unwrap();
+        assert_eq!(saved.placeholders, ["tone"]);
+        assert_eq!(load_templates(&db).unwrap().len(), 4);
+        let doc = create_from_template(
+            &db,
+            &project.id,
+            &saved.id,
+            values(&[("tone", "short words")]),
+        )
+        .unwrap();
+        assert_eq!(
+            (doc.title.as_str(), doc.content.as_str()),
+            ("Style guide", "Prefer short words.")
+        );
+        assert!(save_template(
+            &db,
+            ContextDocTemplate {
+                id: "runbook".to_string(),
+                ..saved.clone()
+            }
+        )
+        .is_err());
+        delete_template(&db, &saved.id).unwrap();
+        assert_eq!(load_templates(&db).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn agents_get_their_own_context_stack() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("SDK", &project.id, AgentKind::Api, "sdk");
+        db.create_agent(&agent).expect("agent should save");
+        let api = ProjectContextDocument::new(&project.id, "API conventions", "snake_case");
+        let voice = ProjectContextDocument::new(&project.id, "Voice", "plain");
+        let errors = ProjectContextDocument::new(&project.id, "Errors", "typed");
+        for doc in [&api, &voice, &errors] {
+            db.save_project_context_doc(doc).unwrap();
+        }
+        assert_eq!(agent_context(&db, &agent).unwrap().len(), 3);
+
+        let stack = set_agent_context(
+            &db,
+            &agent.id,
+            vec![errors.id.clone(), api.id.clone(), errors.id.clone()],
+        )
+        .unwrap();
+        let titles: Vec<&str> = stack.iter().map(|doc| doc.title.as_str()).collect();
+        assert_eq!(titles, ["Errors", "API conventions"]);
+
+        let elsewhere = Project::new("Other", "#111111");
+        db.create_project(&elsewhere).unwrap();
+        let foreign = ProjectContextDocument::new(&elsewhere.id, "Foreign", "");
+        db.save_project_context_doc(&foreign).unwrap();
+        assert!(set_agent_context(&db, &agent.id, vec![foreign.id.clone()]).is_err());
+
+        db.delete_project_context_doc(&errors.id).unwrap();
+        assert_eq!(agent_context(&db, &agent).unwrap().len(), 1);
+        assert_eq!(set_agent_context(&db, &agent.id, vec![]).unwrap().len(), 2);
+    }
+}
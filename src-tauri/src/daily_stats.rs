@@ -0,0 +1,211 @@
+use crate::db::Database;
+use crate::models::*;
+use crate::timezone::UserTimezone;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ── Daily stats ─────────────────────────────────────────────────────────────
+// One row per calendar day in the user's timezone, written once the day is over so charts
+// over weeks don't rescan run history. Today is always computed live.
+
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MAX_BACKFILL_DAYS: u64 = 90;
+const DEFAULT_RANGE_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyStats {
+    pub day: NaiveDate, // calendar day in the user's timezone
+    pub runs_completed: u64,
+    pub runs_failed: u64,
+    pub files_changed: u64, // distinct files per agent
+    pub messages: u64,      // excluding heartbeats
+    pub cost: f64,          // runs started that day
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatsRange {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>, // inclusive
+}
+
+/// Roll up runs overlapping a day and that day's message count.
+pub fn summarize_day(
+    zone: UserTimezone,
+    day: NaiveDate,
+    runs: &[Run],
+    messages: u64,
+) -> DailyStats {
+    let (start, end) = zone.day_bounds(day);
+    let within = |at: DateTime<Utc>| at >= start && at < end;
+    let mut stats = DailyStats {
+        day,
+        runs_completed: 0,
+        runs_failed: 0,
+        files_changed: 0,
+        messages,
+        cost: 0.0,
+    };
+    let mut files = HashSet::new();
+    for run in runs {
+        if run.ended_at.is_some_and(within) {
+            match run.status {
+                RunStatus::Completed => stats.runs_completed += 1,
+                RunStatus::Failed => stats.runs_failed += 1,
+                _ => {}
+            }
+        }
+        if within(run.started_at) {
+            stats.cost += run.cost;
+        }
+        for change in run
+            .file_changes
+            .iter()
+            .filter(|change| within(change.timestamp))
+        {
+            files.insert((run.agent_id.as_str(), change.path.as_str()));
+        }
+    }
+    stats.files_changed = files.len() as u64;
+    stats
+}
+
+pub fn compute_day(
+    db: &Database,
+    zone: UserTimezone,
+    day: NaiveDate,
+) -> rusqlite::Result<DailyStats> {
+    let (start, end) = zone.day_bounds(day);
+    let (start, end) = (start.to_rfc3339(), end.to_rfc3339());
+    let runs = db.get_runs_overlapping(&start, &end)?;
+    let messages = db.count_messages_between(&start, &end)?;
+    Ok(summarize_day(zone, day, &runs, messages))
+}
+
+/// Write rollups for every finished day not recorded yet, starting after the
+/// last recorded day (or the first run, at most MAX_BACKFILL_DAYS back).
+pub fn record_completed_days(
+    db: &Database,
+    zone: UserTimezone,
+    today: NaiveDate,
+) -> rusqlite::Result<usize> {
+    let earliest_allowed = today
+        .checked_sub_days(Days::new(MAX_BACKFILL_DAYS))
+        .unwrap_or(today);
+    let first_unrecorded = match db.latest_daily_stats_day()? {
+        Some(day) => day.succ_opt(),
+        None => db
+            .earliest_run_started_at()?
+            .map(|started| zone.date_of(started)),
+    };
+    let Some(mut day) = first_unrecorded.map(|day| day.max(earliest_allowed)) else {
+        return Ok(0);
+    };
+
+    let mut recorded = 0;
+    while day < today {
+        db.upsert_daily_stats(&compute_day(db, zone, day)?)?;
+        recorded += 1;
+        let Some(next) = day.succ_opt() else { break };
+        day = next;
+    }
+    Ok(recorded)
+}
+
+/// Stored rollups in range, plus today's live numbers when the range
+/// reaches today. Defaults to the last 30 days.
+pub fn timeseries(
+    db: &Database,
+    zone: UserTimezone,
+    range: &StatsRange,
+    today: NaiveDate,
+) -> rusqlite::Result<Vec<DailyStats>> {
+    let until = range.until.unwrap_or(today).min(today);
+    let since = range.since.unwrap_or_else(|| {
+        until
+            .checked_sub_days(Days::new(DEFAULT_RANGE_DAYS - 1))
+            .unwrap_or(until)
+    });
+    let mut series = db.get_daily_stats(since, until)?;
+    if until == today && since <= today {
+        series.retain(|stats| stats.day != today);
+        series.push(compute_day(db, zone, today)?);
+    }
+    Ok(series)
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        let zone = UserTimezone::load(&db);
+        match record_completed_days(&db, zone, zone.date_of(db.now())) {
+            Ok(0) => {}
+            Ok(recorded) => tracing::info!("Recorded daily stats for {} day(s)", recorded),
+            Err(error) => tracing::warn!("Failed to record daily stats: {}", error),
+        }
+        std::thread::sleep(ROLLUP_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_finished_days_and_serves_today_live() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Worker", &project.id, AgentKind::Terminal, "ops");
+        db.create_agent(&agent).expect("agent should insert");
+
+        let zone = UserTimezone::System;
+        let today = zone.date_of(Utc::now());
+        let yesterday = today.pred_opt().expect("yesterday exists");
+        let (start, _) = zone.day_bounds(yesterday);
+        let mut run = Run::new(&agent.id, RunStatus::Completed, None);
+        run.started_at = start + chrono::Duration::hours(9);
+        run.ended_at = Some(start + chrono::Duration::hours(10));
+        run.cost = 0.25;
+        for path in ["/repo/a.rs", "/repo/a.rs", "/repo/b.rs"] {
+            run.file_changes.push(FileChange {
+                path: path.to_string(),
+                change_type: FileChangeType::Modified,
+                timestamp: start + chrono::Duration::hours(9),
+                count: 1,
+                old_path: None,
+                lines_added: None,
+                lines_removed: None,
+            });
+        }
+        db.create_run(&run).expect("run should insert");
+
+        assert_eq!(
+            record_completed_days(&db, zone, today).expect("rollup should run"),
+            1
+        );
+        assert_eq!(
+            record_completed_days(&db, zone, today).expect("rollup should run"),
+            0
+        );
+
+        let series = timeseries(
+            &db,
+            zone,
+            &StatsRange {
+                since: Some(yesterday),
+                until: None,
+            },
+            today,
+        )
+        .expect("series should load");
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].day, yesterday);
+        assert_eq!(series[0].runs_completed, 1);
+        assert_eq!(series[0].files_changed, 2);
+        assert!((series[0].cost - 0.25).abs() < f64::EPSILON);
+        assert_eq!(series[1].day, today);
+        assert_eq!(series[1].runs_completed, 0);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::db::{Database, TableWrite, ALL_TABLES};
+use crate::models::{DashboardFilter, DashboardView};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+// ── Dashboard cache ─────────────────────────────────────────────────────────
+// The dashboard reruns its whole query set on every refresh. Views are kept
+// per filter until a write lands in a table they're built from; the write
+// events come from the database's update hook. Parts of the view also age
+// on their own (stuck runs, today's file counts, snoozes running out), so
+// entries expire after MAX_AGE regardless.
+
+const MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Tables the dashboard reads. Writes anywhere else leave it alone.
+const DASHBOARD_TABLES: [&str; 7] = [
+    "projects",
+    "agents",
+    "runs",
+    "attention_state",
+    "agent_groups",
+    "agent_group_members",
+    "settings",
+];
+
+struct Entry {
+    view: DashboardView,
+    built_at: Instant,
+}
+
+struct State {
+    writes: broadcast::Receiver<TableWrite>,
+    entries: HashMap<String, Entry>,
+    generation: u64, // bumped on every invalidation
+}
+
+pub struct DashboardCache {
+    state: Mutex<State>,
+}
+
+impl DashboardCache {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            state: Mutex::new(State {
+                writes: db.subscribe_writes(),
+                entries: HashMap::new(),
+                generation: 0,
+            }),
+        }
+    }
+
+    /// The cached view for `filter`, or a fresh one from `build`.
+    pub fn get_or_build(
+        &self,
+        filter: &DashboardFilter,
+        build: impl FnOnce() -> Result<DashboardView, String>,
+    ) -> Result<DashboardView, String> {
+        let key = serde_json::to_string(filter).map_err(|e| e.to_string())?;
+        let generation = {
+            let mut state = self.state.lock().unwrap();
+            state.apply_writes();
+            if let Some(entry) = state.entries.get(&key) {
+                if entry.built_at.elapsed() < MAX_AGE {
+                    return Ok(entry.view.clone());
+                }
+            }
+            state.generation
+        };
+        // Built outside the lock. If a relevant write lands meanwhile, the
+        // view may predate it, so it's returned but not kept.
+        let built_at = Instant::now();
+        let view = build()?;
+        let mut state = self.state.lock().unwrap();
+        state.apply_writes();
+        if state.generation == generation {
+            state.entries.insert(
+                key,
+                Entry {
+                    view: view.clone(),
+                    built_at,
+                },
+            );
+        }
+        Ok(view)
+    }
+}
+
+impl State {
+    /// Drop every entry if a relevant write came in since the last look.
+    fn apply_writes(&mut self) {
+        let mut invalidate = false;
+        loop {
+            match self.writes.try_recv() {
+                Ok(write) => {
+                    invalidate |= write.table == ALL_TABLES
+                        || DASHBOARD_TABLES.contains(&write.table.as_str());
+                }
+                Err(TryRecvError::Lagged(_)) => invalidate = true,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+            }
+        }
+        if invalidate {
+            self.entries.clear();
+            self.generation += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::*;
+
+    fn view(total_agents: usize) -> DashboardView {
+        DashboardView {
+            projects: vec![],
+            pinned: vec![],
+            groups: vec![],
+            needs_attention: vec![],
+            labels: vec![],
+            stats: DashboardStats {
+                total_agents,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn views_are_reused_until_a_relevant_write() {
+        let db = Database::new(":memory:").expect("db should open");
+        let cache = DashboardCache::new(&db);
+        let filter = DashboardFilter::default();
+        let mut builds = 0;
+        let get = |builds: &mut usize| {
+            cache
+                .get_or_build(&filter, || {
+                    *builds += 1;
+                    Ok(view(*builds))
+                })
+                .unwrap()
+        };
+
+        assert_eq!(get(&mut builds).stats.total_agents, 1);
+        assert_eq!(get(&mut builds).stats.total_agents, 1);
+
+        // Tables the dashboard doesn't read leave it cached.
+        let project = Project::new("Site", "#6366f1");
+        let doc = ProjectContextDocument::new(&project.id, "Brief", "");
+        db.create_project(&project).unwrap();
+        assert_eq!(get(&mut builds).stats.total_agents, 2);
+        db.save_project_context_doc(&doc).unwrap();
+        assert_eq!(get(&mut builds).stats.total_agents, 2);
+
+        let agent = Agent::new("Docs", &project.id, AgentKind::Api, "docs");
+        db.create_agent(&agent).unwrap();
+        assert_eq!(get(&mut builds).stats.total_agents, 3);
+        assert_eq!(builds, 3);
+    }
+}
@@ -1,17 +1,136 @@
+use crate::clock::{Clock, SystemClock};
 use crate::models::*;
-use rusqlite::{params, Connection, Result};
+use crate::redaction::{RedactionSettings, Redactor, REDACTION_SETTINGS_KEY};
+use crate::traces;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::sync::Mutex;
-use std::time::Duration;
-use uuid::Uuid;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+mod query_log;
+mod scratch;
+
+pub use query_log::SlowQuery;
+pub use scratch::ScratchFile;
+
+const WRITE_EVENT_CAPACITY: usize = 1024;
+const BUS_LOG_PREVIEW_CHARS: usize = 200;
+
+const AGENT_COLUMNS: &str =
+    "id, name, project_id, kind, function_tag, status, working_directory, last_active_at, \
+     created_at, config, pinned_position, source_connector_id, source_item_id, template_id";
+
+const RUN_COLUMNS: &str =
+    "id, agent_id, status, started_at, ended_at, summary, outputs, file_changes, \
+     token_in, token_out, cost, git_snapshot, review, labels, instruction_message_id, \
+     source_connector_id, source_item_id";
+
+/// RUN_COLUMNS without the outputs blob, followed by the output count, for
+/// `row_to_run_header`.
+const RUN_HEADER_COLUMNS: &str =
+    "id, agent_id, status, started_at, ended_at, summary, '[]' AS outputs, file_changes, \
+     token_in, token_out, cost, git_snapshot, review, labels, instruction_message_id, \
+     source_connector_id, source_item_id, json_array_length(outputs) AS output_count";
+
+/// RUN_COLUMNS without the outputs and git snapshot blobs, for list views
+/// that never render them.
+const RUN_SUMMARY_COLUMNS: &str =
+    "id, agent_id, status, started_at, ended_at, summary, '[]' AS outputs, file_changes, \
+     token_in, token_out, cost, NULL AS git_snapshot, review, labels, instruction_message_id, \
+     source_connector_id, source_item_id";
 
 pub struct Database {
     conn: Mutex<Connection>,
+    redactor: RwLock<Arc<Redactor>>,
+    writes: broadcast::Sender<TableWrite>,
+    status_changes: broadcast::Sender<StatusChange>,
+    queued: MessageSignals,
+    clock: Arc<dyn Clock>,
+}
+
+/// Wakes adapter loops when a message is queued for their agent, instead of
+/// leaving them to find it on their next poll. Messages queued by another
+/// process (the MCP server) raise no signal, so loops still poll on a timeout.
+#[derive(Default)]
+struct MessageSignals {
+    agents: Mutex<HashSet<String>>,
+    arrived: Condvar,
+}
+
+/// A row inserted, updated or deleted in `table`, as reported by SQLite.
+/// Subscribers use these to drop whatever they derived from the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableWrite {
+    pub table: String,
+}
+
+/// Stands in for every table when a write bypasses the update hook, as a
+/// snapshot restore does.
+pub const ALL_TABLES: &str = "*";
+
+/// An agent's status changed, with the time it was written. Every change is
+/// sent, so a subscriber sees Running -> Blocked -> Running even when the
+/// agent ends where it started. Only changes made in this process are sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub agent_id: String,
+    pub from: AgentStatus,
+    pub to: AgentStatus,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// On-disk size of the database and how many rows each table holds.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStats {
+    pub size_bytes: u64,
+    pub free_bytes: u64, // pages on the freelist, reclaimable with VACUUM
+    pub tables: Vec<TableRowCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRowCount {
+    pub table: String,
+    pub rows: u64,
+}
+
+/// What an archived run keeps compressed: its heavy columns as stored, plus
+/// its review comments.
+#[derive(Serialize, Deserialize)]
+struct ArchivePayload {
+    outputs: String,
+    file_changes: String,
+    git_snapshot: Option<String>,
+    review: Option<String>,
+    comments: Vec<RunComment>,
+}
+
+/// JSON, gzipped, for blobs that are kept but rarely read: archived runs
+/// and raw connector payloads.
+fn gzip_json<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, value)
+        .map_err(std::io::Error::from)
+        .and_then(|_| encoder.finish())
+        .map_err(|error| rusqlite::Error::ToSqlConversionFailure(Box::new(error)))
+}
+
+fn gunzip_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    serde_json::from_reader(GzDecoder::new(bytes)).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Blob, Box::new(error))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
 
     fn setup_db_with_agent() -> (Database, String) {
         let db = Database::new(":memory:").expect("in-memory db should initialize");
@@ -32,10 +151,10 @@ mod tests {
         let (db, agent_id) = setup_db_with_agent();
 
         let first = db
-            .start_instruction_run(&agent_id, "Implement feature A")
+            .start_instruction_run(&agent_id, None, "Implement feature A")
             .expect("first instruction should create run");
         let second = db
-            .start_instruction_run(&agent_id, "Apply follow-up fix")
+            .start_instruction_run(&agent_id, None, "Apply follow-up fix")
             .expect("second instruction should reuse active run");
 
         assert_eq!(first.id, second.id);
@@ -52,7 +171,7 @@ mod tests {
     fn finalize_latest_run_marks_completed() {
         let (db, agent_id) = setup_db_with_agent();
 
-        db.start_instruction_run(&agent_id, "Ship update")
+        db.start_instruction_run(&agent_id, None, "Ship update")
             .expect("instruction should create run");
         db.append_run_output(&agent_id, "output", "Generated patch")
             .expect("output should append");
@@ -72,6 +191,41 @@ mod tests {
         assert_eq!(latest.summary.as_deref(), Some("Ship complete"));
     }
 
+    #[test]
+    fn finalize_compacts_repeated_heartbeats() {
+        let (db, agent_id) = setup_db_with_agent();
+        db.start_instruction_run(&agent_id, None, "Watch the build")
+            .expect("run should start");
+        for _ in 0..5 {
+            db.append_run_output(&agent_id, "heartbeat", "Compiling kanbun\n> waiting")
+                .expect("heartbeat should append");
+        }
+        db.append_run_output(&agent_id, "output", "Build finished")
+            .expect("output should append");
+        db.append_run_output(&agent_id, "heartbeat", "$ ")
+            .expect("heartbeat should append");
+
+        let run = db
+            .finalize_latest_run(&agent_id, RunStatus::Completed, None)
+            .expect("finalize should succeed")
+            .expect("run should exist");
+        let kinds: Vec<&str> = run.outputs.iter().map(|o| o.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "instruction",
+                "heartbeat",
+                "heartbeats_compacted",
+                "heartbeat",
+                "output",
+                "heartbeat"
+            ]
+        );
+        assert!(run.outputs[2]
+            .content
+            .starts_with("3 similar heartbeats omitted"));
+    }
+
     #[test]
     fn append_run_output_creates_run_when_missing() {
         let (db, agent_id) = setup_db_with_agent();
@@ -152,6 +306,373 @@ mod tests {
         let _ = std::fs::remove_file(backup_path.with_extension("db-shm"));
     }
 
+    #[test]
+    fn inbound_messages_and_run_outputs_are_redacted() {
+        let (db, agent_id) = setup_db_with_agent();
+
+        let leaked = "export API_TOKEN=abcd1234efgh5678";
+        db.insert_message(&Message::from_agent(&agent_id, MessageKind::Output, leaked))
+            .expect("message should insert");
+        db.append_run_output(&agent_id, "stdout", leaked)
+            .expect("output should append");
+
+        let messages = db
+            .get_messages_for_agent(&agent_id, 10)
+            .expect("messages should load");
+        assert_eq!(messages[0].content, "export API_TOKEN=[REDACTED]");
+        let run = db
+            .get_latest_run_for_agent(&agent_id)
+            .expect("query should succeed")
+            .expect("run should exist");
+        assert_eq!(run.outputs[0].content, "export API_TOKEN=[REDACTED]");
+
+        db.set_redaction_settings(&RedactionSettings {
+            enabled: false,
+            custom_patterns: vec![],
+        })
+        .expect("settings should save");
+        db.insert_message(&Message::from_agent(&agent_id, MessageKind::Output, leaked))
+            .expect("message should insert");
+        let messages = db
+            .get_messages_for_agent(&agent_id, 10)
+            .expect("messages should load");
+        assert!(messages.iter().any(|message| message.content == leaked));
+    }
+
+    #[test]
+    fn inbound_usage_metadata_accumulates_on_the_active_run() {
+        let (db, agent_id) = setup_db_with_agent();
+        let run = db
+            .start_instruction_run(&agent_id, None, "Summarize the changelog")
+            .expect("run should start");
+
+        let mut first = Message::from_agent(&agent_id, MessageKind::Output, "step 1");
+        first.metadata = Some(serde_json::json!({
+            "usage": { "input_tokens": 1200, "output_tokens": 300, "total_cost_usd": 0.02 }
+        }));
+        db.insert_message(&first)
+            .expect("first message should insert");
+        let mut second = Message::from_agent(&agent_id, MessageKind::Completed, "done");
+        second.metadata = Some(serde_json::json!({
+            "usage": { "prompt_tokens": 800, "completion_tokens": 200 }
+        }));
+        db.insert_message(&second)
+            .expect("second message should insert");
+
+        let latest = db
+            .get_latest_run_for_agent(&agent_id)
+            .expect("latest run should load")
+            .expect("run should exist");
+        assert_eq!(latest.id, run.id);
+        assert_eq!(latest.token_in, 2000);
+        assert_eq!(latest.token_out, 500);
+        assert!((latest.cost - 0.02).abs() < f64::EPSILON);
+
+        let totals = db.get_usage_totals_by_agent().expect("totals should load");
+        assert_eq!(
+            totals.get(&agent_id).map(|usage| usage.token_in),
+            Some(2000)
+        );
+    }
+
+    #[test]
+    fn concurrent_runs_are_keyed_by_instruction_up_to_the_cap() {
+        let (db, agent_id) = setup_db_with_agent();
+        let mut agent = db
+            .list_agents()
+            .expect("agents should load")
+            .into_iter()
+            .find(|agent| agent.id == agent_id)
+            .expect("agent should exist");
+        agent.config.max_concurrent_runs = 2;
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE agents SET config = ?1 WHERE id = ?2",
+                params![serde_json::to_string(&agent.config).unwrap(), agent_id],
+            )
+            .expect("config should update");
+        }
+
+        let first = db
+            .start_instruction_run(&agent_id, Some("msg-a"), "Write the tests")
+            .expect("first run should start");
+        let second = db
+            .start_instruction_run(&agent_id, Some("msg-b"), "Update the docs")
+            .expect("second run should start");
+        assert_ne!(first.id, second.id);
+        let repeat = db
+            .start_instruction_run(&agent_id, Some("msg-a"), "Write the tests")
+            .expect("repeat should resolve");
+        assert_eq!(repeat.id, first.id);
+        assert_eq!(repeat.outputs.len(), 1);
+
+        // At the cap, a third instruction joins the newest active run.
+        let third = db
+            .start_instruction_run(&agent_id, Some("msg-c"), "Fix lint")
+            .expect("third instruction should attach");
+        assert_eq!(third.id, second.id);
+
+        db.append_run_output_to(&agent_id, Some("msg-a"), "output", "tests written")
+            .expect("output should route");
+        db.finalize_run_for(&agent_id, Some("msg-a"), RunStatus::Completed, None)
+            .expect("first run should finalize");
+
+        let first = db
+            .get_run(&first.id)
+            .expect("run should load")
+            .expect("run should exist");
+        assert_eq!(first.status, RunStatus::Completed);
+        assert!(first
+            .outputs
+            .iter()
+            .any(|output| output.content == "tests written"));
+        let active = db
+            .get_active_runs(&agent_id)
+            .expect("active runs should load");
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, second.id);
+
+        // A key for a finished run must not close a different active run.
+        db.finalize_run_for(&agent_id, Some("msg-a"), RunStatus::Failed, None)
+            .expect("finalize should be a no-op");
+        assert_eq!(
+            db.get_active_runs(&agent_id)
+                .expect("active runs should load")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn run_labels_filter_history_and_roll_up() {
+        let (db, agent_id) = setup_db_with_agent();
+        let first = db
+            .start_instruction_run(&agent_id, None, "Cut the release")
+            .expect("run should start");
+        db.set_run_labels(&first.id, &["release".to_string(), "client-x".to_string()])
+            .expect("labels should save");
+        db.finalize_latest_run(&agent_id, RunStatus::Completed, None)
+            .expect("run should finalize");
+        let second = db
+            .start_instruction_run(&agent_id, None, "Try a new prompt")
+            .expect("run should start");
+        db.set_run_labels(&second.id, &["experiment".to_string()])
+            .expect("labels should save");
+
+        let release = db
+            .get_runs_by_label("release", Some(&agent_id), 10)
+            .expect("label filter should load");
+        assert_eq!(release.len(), 1);
+        assert_eq!(release[0].id, first.id);
+        assert_eq!(release[0].labels, vec!["release", "client-x"]);
+        assert!(db
+            .get_runs_by_label("release", Some("other-agent"), 10)
+            .expect("label filter should load")
+            .is_empty());
+
+        let rollups = db.get_label_rollups().expect("rollups should load");
+        assert_eq!(rollups.len(), 3);
+        let experiment = rollups
+            .iter()
+            .find(|rollup| rollup.label == "experiment")
+            .expect("experiment rollup should exist");
+        assert_eq!(experiment.run_count, 1);
+        assert_eq!(experiment.in_progress, 1);
+    }
+
+    #[test]
+    fn agents_load_by_id() {
+        let (db, agent_id) = setup_db_with_agent();
+        let agent = db
+            .get_agent(&agent_id)
+            .expect("agent should load")
+            .expect("agent should exist");
+        assert_eq!(agent.id, agent_id);
+        assert_eq!(
+            agent.name,
+            db.list_agents().expect("agents should load")[0].name
+        );
+        assert!(db
+            .get_agent("missing")
+            .expect("lookup should run")
+            .is_none());
+    }
+
+    #[test]
+    fn run_outputs_page_without_loading_the_run() {
+        let (db, agent_id) = setup_db_with_agent();
+        let run = db
+            .start_instruction_run(&agent_id, None, "Summarize logs")
+            .expect("run should start");
+        for line in 0..5 {
+            db.append_run_output(&agent_id, "message", &format!("line {line}"))
+                .expect("output should append");
+        }
+
+        let headers = db
+            .get_run_headers_for_agent(&agent_id, 10)
+            .expect("headers should load");
+        assert_eq!(headers.len(), 1);
+        // The instruction itself is the run's first output.
+        assert_eq!(headers[0].output_count, 6);
+        assert!(headers[0].run.outputs.is_empty());
+
+        let page = db
+            .get_run_outputs(&run.id, 4, 10)
+            .expect("outputs should load")
+            .expect("run should exist");
+        assert_eq!(page.total, 6);
+        let contents: Vec<_> = page.outputs.iter().map(|o| o.content.as_str()).collect();
+        assert_eq!(contents, vec!["line 3", "line 4"]);
+        assert!(db.get_run_outputs("missing", 0, 10).unwrap().is_none());
+    }
+
+    #[test]
+    fn queued_messages_wake_waiting_adapters() {
+        let (db, agent_id) = setup_db_with_agent();
+        let db = Arc::new(db);
+        let short = Duration::from_millis(20);
+
+        // A signal raised before anyone waits is kept for the next wait.
+        db.insert_message(&Message::to_agent(
+            &agent_id,
+            MessageKind::Instruction,
+            "Ship it",
+        ))
+        .expect("message should insert");
+        assert!(db.wait_for_messages(&agent_id, short));
+        assert!(!db.wait_for_messages(&agent_id, short));
+
+        // Agent output and held instructions don't wake the loop.
+        db.insert_message(&Message::from_agent(&agent_id, MessageKind::Output, "done"))
+            .expect("output should insert");
+        let mut held = Message::to_agent(&agent_id, MessageKind::Instruction, "Deploy");
+        held.metadata = Some(serde_json::json!({ "awaiting_approval": true }));
+        db.insert_message(&held)
+            .expect("held message should insert");
+        assert!(!db.wait_for_messages(&agent_id, short));
+
+        let waiter = {
+            let db = db.clone();
+            let agent_id = agent_id.clone();
+            std::thread::spawn(move || db.wait_for_messages(&agent_id, Duration::from_secs(10)))
+        };
+        db.approve_held_message(&held.id)
+            .expect("approval should succeed")
+            .expect("message should be held");
+        assert!(waiter.join().unwrap());
+    }
+
+    #[test]
+    fn expired_messages_leave_the_pending_queue() {
+        let (db, agent_id) = setup_db_with_agent();
+
+        let mut stale = Message::to_agent(&agent_id, MessageKind::Instruction, "Deploy now");
+        stale.expires_at = Some(chrono::Utc::now() - chrono::Duration::minutes(5));
+        db.insert_message(&stale)
+            .expect("stale message should insert");
+        let mut fresh = Message::to_agent(&agent_id, MessageKind::Instruction, "Run tests");
+        fresh.expires_at = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+        db.insert_message(&fresh)
+            .expect("fresh message should insert");
+
+        let pending = db
+            .get_pending_messages(&agent_id)
+            .expect("pending should load");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, fresh.id);
+
+        let expired = db
+            .expire_pending_messages(&agent_id)
+            .expect("expiry should succeed");
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, stale.id);
+        assert!(db
+            .expire_pending_messages(&agent_id)
+            .expect("second expiry should succeed")
+            .is_empty());
+
+        let stored = db
+            .get_messages_for_agent(&agent_id, 10)
+            .expect("messages should load")
+            .into_iter()
+            .find(|message| message.id == stale.id)
+            .expect("stale message should still exist");
+        assert!(stored.delivered_at.is_some());
+        assert_eq!(
+            stored.metadata.and_then(|m| m.get("expired").cloned()),
+            Some(serde_json::json!(true))
+        );
+    }
+
+    #[test]
+    fn late_updates_do_not_revive_finished_agents() {
+        let (db, agent_id) = setup_db_with_agent();
+        let status = |db: &Database| db.get_agent(&agent_id).unwrap().unwrap().status;
+
+        db.update_agent_status(&agent_id, &AgentStatus::Completed)
+            .unwrap();
+        assert!(!db
+            .set_agent_status(&agent_id, &AgentStatus::Running, false)
+            .unwrap());
+        assert_eq!(status(&db), AgentStatus::Completed);
+
+        // A new run makes Running legal again.
+        db.start_instruction_run(&agent_id, None, "Ship the docs")
+            .unwrap();
+        assert!(db
+            .set_agent_status(&agent_id, &AgentStatus::Running, false)
+            .unwrap());
+
+        db.update_agent_status(&agent_id, &AgentStatus::Errored)
+            .unwrap();
+        db.update_agent_status(&agent_id, &AgentStatus::Completed)
+            .unwrap();
+        assert_eq!(status(&db), AgentStatus::Errored);
+        assert!(db
+            .set_agent_status(&agent_id, &AgentStatus::Completed, true)
+            .unwrap());
+        assert_eq!(status(&db), AgentStatus::Completed);
+        db.update_agent_status(&agent_id, &AgentStatus::Idle)
+            .unwrap();
+        assert_eq!(status(&db), AgentStatus::Idle);
+    }
+
+    #[test]
+    fn message_ttl_follows_the_database_clock() {
+        let clock = Arc::new(crate::clock::ManualClock::new(
+            chrono::DateTime::parse_from_rfc3339("2026-03-01T09:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        ));
+        let (db, agent_id) = setup_db_with_agent();
+        let db = db.with_clock(clock.clone());
+
+        let mut message = Message::to_agent(&agent_id, MessageKind::Instruction, "Deploy now");
+        message.expires_at = Some(clock.now() + chrono::Duration::minutes(10));
+        db.insert_message(&message).expect("message should insert");
+
+        clock.advance(Duration::from_secs(9 * 60));
+        assert!(db
+            .expire_pending_messages(&agent_id)
+            .expect("expiry should succeed")
+            .is_empty());
+
+        clock.advance(Duration::from_secs(2 * 60));
+        let expired = db
+            .expire_pending_messages(&agent_id)
+            .expect("expiry should succeed");
+        assert_eq!(expired.len(), 1);
+        let stored = db
+            .get_messages_for_agent(&agent_id, 10)
+            .expect("messages should load")
+            .into_iter()
+            .find(|stored| stored.id == message.id)
+            .expect("message should still exist");
+        assert_eq!(stored.delivered_at, Some(clock.now()));
+    }
+
     #[test]
     fn get_messages_for_agent_before_paginates_history() {
         let (db, agent_id) = setup_db_with_agent();
@@ -170,6 +691,7 @@ mod tests {
                 created_at,
                 delivered_at: Some(created_at),
                 acknowledged_at: None,
+                expires_at: None,
             };
             db.insert_message(&message).expect("message should insert");
         }
@@ -194,18 +716,388 @@ mod tests {
         assert_eq!(page_two[0].content, "msg-1");
         assert_eq!(page_two[1].content, "msg-0");
     }
+
+    #[test]
+    fn agent_filters_apply_in_sql() {
+        let db = Database::new(":memory:").expect("db should initialize");
+        let web = Project::new("Web", "#111111");
+        let ops = Project::new("Ops", "#222222");
+        db.create_project(&web).expect("project should insert");
+        db.create_project(&ops).expect("project should insert");
+        let writer = Agent::new("Writer", &web.id, AgentKind::Terminal, "content");
+        let deployer = Agent::new("Deployer", &ops.id, AgentKind::Terminal, "devops");
+        db.create_agent(&writer).expect("agent should insert");
+        db.create_agent(&deployer).expect("agent should insert");
+        db.update_agent_status(&deployer.id, &AgentStatus::Errored)
+            .expect("status should update");
+
+        let names = |filter: &DashboardFilter| -> Vec<String> {
+            db.list_agents_filtered(filter)
+                .expect("agents should load")
+                .into_iter()
+                .map(|agent| agent.name)
+                .collect()
+        };
+        assert_eq!(names(&DashboardFilter::default()).len(), 2);
+        let by_project = DashboardFilter {
+            project_ids: vec![web.id.clone()],
+            ..Default::default()
+        };
+        assert_eq!(names(&by_project), vec!["Writer"]);
+        let by_status = DashboardFilter {
+            statuses: vec![AgentStatus::Errored],
+            ..Default::default()
+        };
+        assert_eq!(names(&by_status), vec!["Deployer"]);
+        let by_tag = DashboardFilter {
+            function_tags: vec!["content".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(names(&by_tag), vec!["Writer"]);
+        assert_eq!(
+            db.list_projects_filtered(&by_project)
+                .expect("projects should load")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn dismissed_attention_hides_only_the_acknowledged_occurrence() {
+        let (db, agent_id) = setup_db_with_agent();
+        let now = chrono::Utc::now();
+        let item = |timestamp| AttentionItem {
+            agent_id: agent_id.clone(),
+            agent_name: "Agent".to_string(),
+            project_name: "Project".to_string(),
+            reason: "errored".to_string(),
+            timestamp,
+        };
+        let key = (agent_id.clone(), "errored".to_string());
+
+        db.dismiss_attention(&agent_id, "errored", now)
+            .expect("dismiss should save");
+        let states = db.list_attention_states().expect("states should load");
+        assert!(states[&key].suppresses(&item(now - chrono::Duration::minutes(1)), now));
+        assert!(!states[&key].suppresses(&item(now + chrono::Duration::minutes(1)), now));
+
+        db.snooze_attention(&agent_id, "errored", now + chrono::Duration::hours(1))
+            .expect("snooze should save");
+        let states = db.list_attention_states().expect("states should load");
+        let later = item(now + chrono::Duration::minutes(1));
+        assert!(states[&key].suppresses(&later, now));
+        assert!(!states[&key].suppresses(&later, now + chrono::Duration::hours(2)));
+    }
+
+    #[test]
+    fn latest_runs_by_agent_skip_heavy_columns() {
+        let db = Database::new(":memory:").expect("db should initialize");
+        let project = Project::new("Project", "#111111");
+        db.create_project(&project).expect("project should insert");
+        let first = Agent::new("First", &project.id, AgentKind::Terminal, "dev");
+        let second = Agent::new("Second", &project.id, AgentKind::Terminal, "dev");
+        db.create_agent(&first).expect("agent should insert");
+        db.create_agent(&second).expect("agent should insert");
+
+        let mut older = Run::new(&first.id, RunStatus::Completed, Some("older".to_string()));
+        older.started_at = chrono::Utc::now() - chrono::Duration::hours(1);
+        db.create_run(&older).expect("run should insert");
+        let mut newer = Run::new(&first.id, RunStatus::InProgress, Some("newer".to_string()));
+        newer.outputs.push(RunOutput {
+            kind: "output".to_string(),
+            content: "large pane capture".to_string(),
+            timestamp: chrono::Utc::now(),
+        });
+        db.create_run(&newer).expect("run should insert");
+
+        let latest = db.get_latest_runs_by_agent().expect("query should run");
+        assert_eq!(latest.len(), 1);
+        let run = &latest[&first.id];
+        assert_eq!(run.id, newer.id);
+        assert!(run.outputs.is_empty());
+        assert!(!latest.contains_key(&second.id));
+    }
+
+    #[test]
+    fn file_changes_count_only_during_runs_unless_autonomous() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Writer", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).expect("agent should insert");
+
+        assert!(!db.file_change_is_agent_activity(&agent.id).unwrap());
+        db.start_instruction_run(&agent.id, None, "Draft the post")
+            .expect("run should start");
+        assert!(db.file_change_is_agent_activity(&agent.id).unwrap());
+        db.finalize_latest_run(&agent.id, RunStatus::Completed, None)
+            .expect("run should finalize");
+        assert!(!db.file_change_is_agent_activity(&agent.id).unwrap());
+
+        let mut autonomous = agent.clone();
+        autonomous.config.autonomy_level = AutonomyLevel::Autonomous;
+        db.update_agent(&autonomous).expect("agent should update");
+        assert!(db.file_change_is_agent_activity(&agent.id).unwrap());
+    }
+
+    #[test]
+    fn pinned_agents_append_and_reorder() {
+        let db = Database::new(":memory:").expect("db should initialize");
+        let project = Project::new("Project", "#111111");
+        db.create_project(&project).expect("project should insert");
+        let agents: Vec<Agent> = ["A", "B", "C"]
+            .iter()
+            .map(|name| Agent::new(name, &project.id, AgentKind::Terminal, "dev"))
+            .collect();
+        for agent in &agents {
+            db.create_agent(agent).expect("agent should insert");
+        }
+        let pinned_order = |db: &Database| {
+            let mut pinned: Vec<Agent> = db
+                .list_agents()
+                .unwrap()
+                .into_iter()
+                .filter(|agent| agent.pinned_position.is_some())
+                .collect();
+            pinned.sort_by_key(|agent| agent.pinned_position);
+            pinned
+                .into_iter()
+                .map(|agent| agent.name)
+                .collect::<Vec<_>>()
+        };
+
+        for name in ["C", "A", "B"] {
+            let agent = agents.iter().find(|agent| agent.name == name).unwrap();
+            assert!(db.set_agent_pinned(&agent.id, true).unwrap());
+        }
+        assert!(db.set_agent_pinned(&agents[2].id, true).unwrap());
+        assert_eq!(pinned_order(&db), ["C", "A", "B"]);
+        assert!(!db.set_agent_pinned("missing", true).unwrap());
+
+        db.reorder_pinned_agents(&[agents[1].id.clone()])
+            .expect("reorder should succeed");
+        assert_eq!(pinned_order(&db), ["B", "C", "A"]);
+
+        db.set_agent_pinned(&agents[2].id, false).unwrap();
+        assert_eq!(pinned_order(&db), ["B", "A"]);
+    }
+
+    #[test]
+    fn connector_item_detail_keeps_raw_payload() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        db.save_connector_config(&crate::connectors::ConnectorConfig {
+            connector_type: "todoist".to_string(),
+            auth_token: Some("token".to_string()),
+            settings: Default::default(),
+            enabled: true,
+        })
+        .unwrap();
+        let raw = serde_json::json!({
+            "id": "t1",
+            "content": "Renew domain",
+            "due": { "date": "2026-03-01", "is_recurring": false }
+        });
+        let item = crate::connectors::ConnectorItem {
+            id: "t1".to_string(),
+            source: "todoist".to_string(),
+            title: "Renew domain".to_string(),
+            content: None,
+            status: crate::connectors::ItemStatus::Active,
+            priority: Some(2),
+            tags: vec![],
+            url: None,
+            parent_id: None,
+            metadata: HashMap::new(),
+            created_at: None,
+            updated_at: None,
+            due_at: None,
+            raw: Some(raw.clone()),
+        };
+        db.upsert_connector_items("todoist", std::slice::from_ref(&item))
+            .unwrap();
+
+        let detail = db
+            .get_connector_item_detail("todoist", "t1")
+            .unwrap()
+            .expect("item should be cached");
+        assert_eq!(detail.item.title, "Renew domain");
+        assert_eq!(detail.raw, Some(raw));
+        assert!(detail.synced_at.is_some());
+        assert!(serde_json::to_value(&detail.item)
+            .unwrap()
+            .get("raw")
+            .is_none());
+
+        db.upsert_connector_items(
+            "todoist",
+            &[crate::connectors::ConnectorItem { raw: None, ..item }],
+        )
+        .unwrap();
+        let detail = db
+            .get_connector_item_detail("todoist", "t1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(detail.raw, None);
+        assert!(db
+            .get_connector_item_detail("todoist", "t2")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn feed_runs_page_starts_and_ends_by_their_own_timestamps() {
+        let (db, agent_id) = setup_db_with_agent();
+        let base = chrono::Utc::now() - chrono::Duration::hours(2);
+        let minutes = |m: i64| base + chrono::Duration::minutes(m);
+        let run_between = |start: i64, end: i64| {
+            let mut run = Run::new(&agent_id, RunStatus::Completed, None);
+            run.started_at = minutes(start);
+            run.ended_at = Some(minutes(end));
+            db.create_run(&run).unwrap();
+            run.id
+        };
+        // Two long runs spanning `before` end after it but started early.
+        run_between(0, 60);
+        run_between(1, 55);
+        let earlier = run_between(20, 21);
+        let latest = run_between(22, 23);
+        run_between(40, 41);
+
+        let before = minutes(30);
+        let runs = db
+            .get_feed_runs(None, None, Some(&before.to_rfc3339()), 2)
+            .unwrap();
+        let ids: Vec<&str> = runs.iter().map(|run| run.id.as_str()).collect();
+        assert!(ids.contains(&latest.as_str()));
+        assert!(ids.contains(&earlier.as_str()));
+
+        let agents = db.list_agents().unwrap();
+        let projects = db.list_projects().unwrap();
+        let page = crate::activity::build_activity_feed(
+            &agents,
+            &projects,
+            vec![],
+            &runs,
+            &Default::default(),
+            Some(before),
+            2,
+        );
+        let entries: Vec<&str> = page.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(
+            entries,
+            [
+                format!("run_ended:{}", latest),
+                format!("run_started:{}", latest)
+            ]
+        );
+    }
+}
+
+/// The status transition matrix. Idle, Running and Blocked agents may move
+/// anywhere. An Errored or Completed agent goes back to Running only once a
+/// new run has started, so a late heartbeat, output line or file event from
+/// the finished run can't revive it. Errored agents don't become Blocked or
+/// Completed until they have been reset.
+fn status_transition_allowed(
+    from: &AgentStatus,
+    to: &AgentStatus,
+    has_open_run: impl FnOnce() -> Result<bool>,
+) -> Result<bool> {
+    use AgentStatus::*;
+    Ok(match (from, to) {
+        (Idle | Running | Blocked, _) => true,
+        (_, Idle) => true,
+        (Errored, Errored) | (Completed, Completed | Errored | Blocked) => true,
+        (Errored | Completed, Running) => has_open_run()?,
+        (Errored, Blocked | Completed) => false,
+    })
 }
 
 impl Database {
     pub fn new(path: &str) -> Result<Self> {
-        let conn = Connection::open(path)?;
+        let mut conn = Connection::open(path)?;
+        conn.profile(Some(query_log::record));
+        let (writes, _) = broadcast::channel(WRITE_EVENT_CAPACITY);
+        let hook_writes = writes.clone();
+        conn.update_hook(Some(move |_action, _db: &str, table: &str, _rowid| {
+            // Nobody listening is fine; the send just has no one to reach.
+            let _ = hook_writes.send(TableWrite {
+                table: table.to_string(),
+            });
+        }));
         let db = Self {
             conn: Mutex::new(conn),
+            redactor: RwLock::new(Arc::new(Redactor::default())),
+            writes,
+            status_changes: broadcast::channel(WRITE_EVENT_CAPACITY).0,
+            queued: MessageSignals::default(),
+            clock: Arc::new(SystemClock),
         };
         db.initialize()?;
+        db.reload_redactor()?;
         Ok(db)
     }
 
+    /// Replace the system clock, for tests that need to freeze or advance
+    /// time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// The current time by this database's clock. Timestamps the database
+    /// writes itself come from here.
+    pub fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.clock.now()
+    }
+
+    /// Writes from here on. Sent synchronously by the write itself, so a
+    /// subscriber that drains the receiver sees every write that returned.
+    pub fn subscribe_writes(&self) -> broadcast::Receiver<TableWrite> {
+        self.writes.subscribe()
+    }
+
+    /// Agent status changes from here on, sent as they're written.
+    pub fn subscribe_status_changes(&self) -> broadcast::Receiver<StatusChange> {
+        self.status_changes.subscribe()
+    }
+
+    /// Block until a message is queued for `agent_id` or `timeout` passes.
+    /// Returns true when woken by a message. A signal raised while nobody was
+    /// waiting is kept, so a message queued between polls isn't missed.
+    pub fn wait_for_messages(&self, agent_id: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut agents = self.queued.agents.lock().unwrap();
+        loop {
+            if agents.remove(agent_id) {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            agents = self
+                .queued
+                .arrived
+                .wait_timeout(agents, remaining)
+                .unwrap()
+                .0;
+        }
+    }
+
+    fn signal_message_queued(&self, agent_id: &str) {
+        self.queued
+            .agents
+            .lock()
+            .unwrap()
+            .insert(agent_id.to_string());
+        self.queued.arrived.notify_all();
+    }
+
     fn initialize(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute_batch(
@@ -230,6 +1122,24 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_project_context_docs_project
                 ON project_context_docs(project_id, updated_at DESC);
 
+            CREATE TABLE IF NOT EXISTS context_doc_revisions (
+                id TEXT PRIMARY KEY,
+                doc_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                saved_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_context_doc_revisions_doc
+                ON context_doc_revisions(doc_id, saved_at DESC);
+
+            CREATE TABLE IF NOT EXISTS agent_context_docs (
+                agent_id TEXT NOT NULL REFERENCES agents(id),
+                doc_id TEXT NOT NULL REFERENCES project_context_docs(id),
+                position INTEGER NOT NULL,
+                PRIMARY KEY (agent_id, doc_id)
+            );
+
             CREATE TABLE IF NOT EXISTS agents (
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
@@ -254,6 +1164,37 @@ impl Database {
                 file_changes TEXT NOT NULL DEFAULT '[]'
             );
 
+            CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL REFERENCES projects(id),
+                title TEXT NOT NULL,
+                description TEXT,
+                board_column TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                assigned_agent_id TEXT,
+                source_connector_id TEXT,
+                source_item_id TEXT,
+                instruction_message_id TEXT,
+                run_id TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_tasks_project
+                ON tasks(project_id, board_column, position);
+
+            CREATE TABLE IF NOT EXISTS run_comments (
+                id TEXT PRIMARY KEY,
+                run_id TEXT NOT NULL REFERENCES runs(id),
+                output_index INTEGER,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_run_comments_run
+                ON run_comments(run_id, created_at);
+
             CREATE INDEX IF NOT EXISTS idx_agents_project ON agents(project_id);
             CREATE INDEX IF NOT EXISTS idx_runs_agent ON runs(agent_id);
             CREATE INDEX IF NOT EXISTS idx_runs_started ON runs(started_at);
@@ -319,21 +1260,194 @@ impl Database {
                 ON connector_items(status);
             CREATE INDEX IF NOT EXISTS idx_connector_items_due
                 ON connector_items(due_at) WHERE due_at IS NOT NULL;
-        ",
-        )?;
-        Ok(())
-    }
 
-    pub fn export_snapshot_to_path(
-        &self,
-        destination_path: &str,
-    ) -> std::result::Result<(), String> {
-        if destination_path.trim().is_empty() {
-            return Err("destination path is empty".to_string());
-        }
+            CREATE TABLE IF NOT EXISTS attention_state (
+                agent_id TEXT NOT NULL REFERENCES agents(id),
+                reason TEXT NOT NULL,
+                dismissed_at TEXT,
+                snoozed_until TEXT,
+                PRIMARY KEY (agent_id, reason)
+            );
 
-        let destination = Path::new(destination_path);
-        if let Some(parent) = destination.parent() {
+            CREATE TABLE IF NOT EXISTS daily_stats (
+                day TEXT PRIMARY KEY,
+                runs_completed INTEGER NOT NULL DEFAULT 0,
+                runs_failed INTEGER NOT NULL DEFAULT 0,
+                files_changed INTEGER NOT NULL DEFAULT 0,
+                messages INTEGER NOT NULL DEFAULT 0,
+                cost REAL NOT NULL DEFAULT 0,
+                recorded_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS agent_groups (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS agent_group_members (
+                group_id TEXT NOT NULL REFERENCES agent_groups(id) ON DELETE CASCADE,
+                agent_id TEXT NOT NULL REFERENCES agents(id),
+                added_at TEXT NOT NULL,
+                PRIMARY KEY (group_id, agent_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS notification_rules (
+                id TEXT PRIMARY KEY,
+                event TEXT NOT NULL,
+                project_ids TEXT NOT NULL DEFAULT '[]',
+                channel TEXT NOT NULL,
+                quiet_hours TEXT,
+                repeat_every_minutes INTEGER,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS notifications (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL,
+                link TEXT NOT NULL,
+                channels TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                read_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_notifications_created
+                ON notifications(created_at);
+
+            CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                secret TEXT,
+                events TEXT NOT NULL DEFAULT '[]',
+                project_ids TEXT NOT NULL DEFAULT '[]',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                last_delivery_at TEXT,
+                last_error TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS api_tokens (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                token_hash TEXT NOT NULL UNIQUE,
+                prefix TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                last_used_at TEXT,
+                revoked_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS agent_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                function_tag TEXT NOT NULL,
+                autonomy_level TEXT NOT NULL,
+                adapter_config TEXT,
+                instructions TEXT NOT NULL DEFAULT '[]',
+                ignore_globs TEXT NOT NULL DEFAULT '[]',
+                include_globs TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS runs_archive (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                ended_at TEXT,
+                summary TEXT,
+                token_in INTEGER NOT NULL DEFAULT 0,
+                token_out INTEGER NOT NULL DEFAULT 0,
+                cost REAL NOT NULL DEFAULT 0,
+                labels TEXT NOT NULL DEFAULT '[]',
+                instruction_message_id TEXT,
+                source_connector_id TEXT,
+                source_item_id TEXT,
+                output_count INTEGER NOT NULL DEFAULT 0,
+                archived_at TEXT NOT NULL,
+                payload BLOB NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_runs_archive_agent
+                ON runs_archive(agent_id, started_at);
+        ",
+        )?;
+
+        // Columns added after the initial schema; older databases get them here.
+        Self::ensure_column(&conn, "messages", "expires_at", "TEXT")?;
+        Self::ensure_column(&conn, "runs", "token_in", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "runs", "token_out", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "runs", "cost", "REAL NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "runs", "git_snapshot", "TEXT")?;
+        Self::ensure_column(&conn, "runs", "review", "TEXT")?;
+        Self::ensure_column(&conn, "runs", "labels", "TEXT NOT NULL DEFAULT '[]'")?;
+        Self::ensure_column(&conn, "runs", "instruction_message_id", "TEXT")?;
+        Self::ensure_column(&conn, "runs", "source_connector_id", "TEXT")?;
+        Self::ensure_column(&conn, "runs", "source_item_id", "TEXT")?;
+        Self::ensure_column(&conn, "project_context_docs", "source_path", "TEXT")?;
+        Self::ensure_column(
+            &conn,
+            "project_context_docs",
+            "watch_source",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        Self::ensure_column(&conn, "tasks", "source_status", "TEXT")?;
+        Self::ensure_column(&conn, "tasks", "sync_conflict", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "pinned_position", "INTEGER")?;
+        Self::ensure_column(&conn, "agents", "source_connector_id", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "source_item_id", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "template_id", "TEXT")?;
+        Self::ensure_column(&conn, "connector_items", "raw_payload", "BLOB")?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_runs_status ON runs(status);
+             CREATE INDEX IF NOT EXISTS idx_runs_agent_started ON runs(agent_id, started_at);",
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_runs_source_item
+                ON runs(source_connector_id, source_item_id) WHERE source_item_id IS NOT NULL",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .any(|name| name == column);
+        if !exists {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn export_snapshot_to_path(
+        &self,
+        destination_path: &str,
+    ) -> std::result::Result<(), String> {
+        if destination_path.trim().is_empty() {
+            return Err("destination path is empty".to_string());
+        }
+
+        let destination = Path::new(destination_path);
+        if let Some(parent) = destination.parent() {
             if !parent.as_os_str().is_empty() {
                 std::fs::create_dir_all(parent).map_err(|error| {
                     format!(
@@ -377,6 +1491,55 @@ impl Database {
         Ok(())
     }
 
+    /// A private scratch file beside the database, for snapshot plaintext.
+    /// In-memory databases (tests) use the temp dir.
+    pub fn scratch_file(&self, prefix: &str) -> std::result::Result<ScratchFile, String> {
+        let dir = {
+            let conn = self.conn.lock().unwrap();
+            conn.path()
+                .filter(|path| !path.is_empty())
+                .and_then(|path| Path::new(path).parent().map(Path::to_path_buf))
+        };
+        ScratchFile::create(&dir.unwrap_or_else(std::env::temp_dir), prefix)
+    }
+
+    pub fn storage_stats(&self) -> Result<StorageStats> {
+        let conn = self.conn.lock().unwrap();
+        let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let free_pages: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        let names = conn
+            .prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                 ORDER BY name",
+            )?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        let tables = names
+            .into_iter()
+            .map(|table| {
+                let rows = conn.query_row(
+                    &format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")),
+                    [],
+                    |row| row.get(0),
+                )?;
+                Ok(TableRowCount { table, rows })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(StorageStats {
+            size_bytes: page_size * page_count,
+            free_bytes: page_size * free_pages,
+            tables,
+        })
+    }
+
+    /// The slowest statements among those recently over the slow-query
+    /// threshold, slowest first.
+    pub fn slow_queries(&self, limit: usize) -> Vec<SlowQuery> {
+        query_log::slowest(limit)
+    }
+
     pub fn import_snapshot_from_path(&self, source_path: &str) -> std::result::Result<(), String> {
         if source_path.trim().is_empty() {
             return Err("source path is empty".to_string());
@@ -412,10 +1575,86 @@ impl Database {
         target_conn
             .execute_batch("PRAGMA wal_checkpoint(TRUNCATE); PRAGMA optimize;")
             .map_err(|error| format!("failed to finalize restored database: {}", error))?;
+        let _ = self.writes.send(TableWrite {
+            table: ALL_TABLES.to_string(),
+        });
+
+        Ok(())
+    }
+
+    /// Allocated size of the main database file (excludes the WAL).
+    pub fn database_size_bytes(&self) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    // ── Settings ────────────────────────────────────────────────────────
+
+    pub fn get_setting<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let conn = self.conn.lock().unwrap();
+        let raw: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(raw.and_then(|value| serde_json::from_str(&value).ok()))
+    }
+
+    pub fn set_setting<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![
+                key,
+                serde_json::to_string(value).unwrap(),
+                self.now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    // ── Redaction ───────────────────────────────────────────────────────
+
+    pub fn get_redaction_settings(&self) -> Result<RedactionSettings> {
+        Ok(self
+            .get_setting::<RedactionSettings>(REDACTION_SETTINGS_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub fn set_redaction_settings(&self, settings: &RedactionSettings) -> Result<()> {
+        self.set_setting(REDACTION_SETTINGS_KEY, settings)?;
+        self.reload_redactor()
+    }
 
+    /// Rebuild the cached redactor from persisted settings.
+    pub fn reload_redactor(&self) -> Result<()> {
+        let settings = self.get_redaction_settings()?;
+        let redactor = Arc::new(Redactor::from_settings(&settings));
+        if let Ok(mut current) = self.redactor.write() {
+            *current = redactor;
+        }
         Ok(())
     }
 
+    fn redact(&self, input: &str) -> String {
+        match self.redactor.read() {
+            Ok(redactor) => redactor.redact(input).into_owned(),
+            Err(_) => input.to_string(),
+        }
+    }
+
     // ── Projects ────────────────────────────────────────────────────────
 
     pub fn create_project(&self, project: &Project) -> Result<()> {
@@ -434,32 +1673,100 @@ impl Database {
     }
 
     pub fn list_projects(&self) -> Result<Vec<Project>> {
+        self.list_projects_filtered(&DashboardFilter::default())
+    }
+
+    pub fn list_projects_filtered(&self, filter: &DashboardFilter) -> Result<Vec<Project>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, color, repo_paths, created_at FROM projects ORDER BY name",
+            "SELECT id, name, color, repo_paths, created_at FROM projects
+             WHERE ?1 = '[]' OR id IN (SELECT value FROM json_each(?1))
+             ORDER BY name",
         )?;
         let projects = stmt
-            .query_map([], |row| {
-                let repo_paths_str: String = row.get(3)?;
-                Ok(Project {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    color: row.get(2)?,
-                    repo_paths: serde_json::from_str(&repo_paths_str).unwrap_or_default(),
-                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                })
-            })?
+            .query_map(
+                params![serde_json::to_string(&filter.project_ids).unwrap()],
+                |row| {
+                    let repo_paths_str: String = row.get(3)?;
+                    Ok(Project {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        color: row.get(2)?,
+                        repo_paths: serde_json::from_str(&repo_paths_str).unwrap_or_default(),
+                        created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                            .unwrap()
+                            .with_timezone(&chrono::Utc),
+                    })
+                },
+            )?
             .collect::<Result<Vec<_>>>()?;
         Ok(projects)
     }
 
+    /// Delete a project with its agents, tasks, context docs and everything
+    /// recorded against those agents.
+    pub fn delete_project(&self, project_id: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let agents = "SELECT id FROM agents WHERE project_id = ?1";
+        let docs = "SELECT id FROM project_context_docs WHERE project_id = ?1";
+        for statement in [
+            format!("DELETE FROM run_comments WHERE run_id IN (SELECT id FROM runs WHERE agent_id IN ({agents}))"),
+            format!("DELETE FROM runs WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM runs_archive WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM messages WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM adapter_configs WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM attention_state WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM agent_group_members WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM agent_context_docs WHERE agent_id IN ({agents}) OR doc_id IN ({docs})"),
+            format!("DELETE FROM notifications WHERE agent_id IN ({agents})"),
+            format!("DELETE FROM context_doc_revisions WHERE doc_id IN ({docs})"),
+            "DELETE FROM project_context_docs WHERE project_id = ?1".to_string(),
+            "DELETE FROM tasks WHERE project_id = ?1".to_string(),
+            "DELETE FROM agents WHERE project_id = ?1".to_string(),
+            "DELETE FROM projects WHERE id = ?1".to_string(),
+        ] {
+            tx.execute(&statement, params![project_id])?;
+        }
+        tx.commit()
+    }
+
+    /// Saves the doc, first keeping the version it replaces as a revision
+    /// when the title or content changed.
     pub fn save_project_context_doc(&self, doc: &ProjectContextDocument) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO project_context_docs (id, project_id, title, content, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let previous = tx
+            .query_row(
+                "SELECT title, content, updated_at FROM project_context_docs WHERE id = ?1",
+                params![doc.id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        if let Some((title, content, saved_at)) = previous {
+            if title != doc.title || content != doc.content {
+                tx.execute(
+                    "INSERT INTO context_doc_revisions (id, doc_id, title, content, saved_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        uuid::Uuid::new_v4().to_string(),
+                        doc.id,
+                        title,
+                        content,
+                        saved_at
+                    ],
+                )?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO project_context_docs (id, project_id, title, content, created_at, updated_at, source_path, watch_source)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 doc.id,
                 doc.project_id,
@@ -467,34 +1774,39 @@ impl Database {
                 doc.content,
                 doc.created_at.to_rfc3339(),
                 doc.updated_at.to_rfc3339(),
+                doc.source_path,
+                doc.watch_source,
             ],
         )?;
-        Ok(())
+        tx.commit()
+    }
+
+    fn row_to_context_doc(row: &rusqlite::Row) -> Result<ProjectContextDocument> {
+        Ok(ProjectContextDocument {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            source_path: row.get(6)?,
+            watch_source: row.get(7)?,
+        })
     }
 
     pub fn get_project_context_doc(&self, doc_id: &str) -> Result<Option<ProjectContextDocument>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, title, content, created_at, updated_at
+        conn.query_row(
+            "SELECT id, project_id, title, content, created_at, updated_at, source_path, watch_source
              FROM project_context_docs WHERE id = ?1 LIMIT 1",
-        )?;
-
-        let mut rows = stmt.query_map(params![doc_id], |row| {
-            Ok(ProjectContextDocument {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            })
-        })?;
-
-        Ok(rows.next().transpose()?)
+            params![doc_id],
+            Self::row_to_context_doc,
+        )
+        .optional()
     }
 
     pub fn list_project_context_docs(
@@ -503,33 +1815,42 @@ impl Database {
     ) -> Result<Vec<ProjectContextDocument>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, project_id, title, content, created_at, updated_at
+            "SELECT id, project_id, title, content, created_at, updated_at, source_path, watch_source
              FROM project_context_docs
              WHERE project_id = ?1
              ORDER BY updated_at DESC",
         )?;
 
         let docs = stmt
-            .query_map(params![project_id], |row| {
-                Ok(ProjectContextDocument {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    title: row.get(2)?,
-                    content: row.get(3)?,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                })
-            })?
+            .query_map(params![project_id], Self::row_to_context_doc)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(docs)
+    }
+
+    /// Docs imported from files that follow their source, across projects.
+    pub fn list_watched_context_docs(&self) -> Result<Vec<ProjectContextDocument>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, title, content, created_at, updated_at, source_path, watch_source
+             FROM project_context_docs
+             WHERE watch_source = 1 AND source_path IS NOT NULL",
+        )?;
+        let docs = stmt
+            .query_map([], Self::row_to_context_doc)?
             .collect::<Result<Vec<_>>>()?;
         Ok(docs)
     }
 
     pub fn delete_project_context_doc(&self, doc_id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM context_doc_revisions WHERE doc_id = ?1",
+            params![doc_id],
+        )?;
+        conn.execute(
+            "DELETE FROM agent_context_docs WHERE doc_id = ?1",
+            params![doc_id],
+        )?;
         conn.execute(
             "DELETE FROM project_context_docs WHERE id = ?1",
             params![doc_id],
@@ -537,283 +1858,2182 @@ impl Database {
         Ok(())
     }
 
-    // ── Agents ──────────────────────────────────────────────────────────
+    /// Replace an agent's context stack with `doc_ids`, in that order.
+    pub fn set_agent_context_docs(&self, agent_id: &str, doc_ids: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM agent_context_docs WHERE agent_id = ?1",
+            params![agent_id],
+        )?;
+        for (position, doc_id) in doc_ids.iter().enumerate() {
+            tx.execute(
+                "INSERT OR IGNORE INTO agent_context_docs (agent_id, doc_id, position)
+                 VALUES (?1, ?2, ?3)",
+                params![agent_id, doc_id, position as i64],
+            )?;
+        }
+        tx.commit()
+    }
 
-    pub fn create_agent(&self, agent: &Agent) -> Result<()> {
+    /// The docs linked to an agent, in stack order.
+    pub fn list_agent_context_docs(&self, agent_id: &str) -> Result<Vec<ProjectContextDocument>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT d.id, d.project_id, d.title, d.content, d.created_at, d.updated_at, d.source_path, d.watch_source
+             FROM agent_context_docs a
+             JOIN project_context_docs d ON d.id = a.doc_id
+             WHERE a.agent_id = ?1
+             ORDER BY a.position ASC",
+        )?;
+        let docs = stmt
+            .query_map(params![agent_id], Self::row_to_context_doc)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(docs)
+    }
+
+    fn row_to_doc_revision(row: &rusqlite::Row) -> Result<ContextDocRevision> {
+        Ok(ContextDocRevision {
+            id: row.get(0)?,
+            doc_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            saved_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        })
+    }
+
+    /// A doc's earlier versions, newest first.
+    pub fn list_doc_revisions(&self, doc_id: &str) -> Result<Vec<ContextDocRevision>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, doc_id, title, content, saved_at
+             FROM context_doc_revisions
+             WHERE doc_id = ?1
+             ORDER BY saved_at DESC",
+        )?;
+        let revisions = stmt
+            .query_map(params![doc_id], Self::row_to_doc_revision)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(revisions)
+    }
+
+    pub fn get_doc_revision(&self, revision_id: &str) -> Result<Option<ContextDocRevision>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, doc_id, title, content, saved_at
+             FROM context_doc_revisions WHERE id = ?1",
+            params![revision_id],
+            Self::row_to_doc_revision,
+        )
+        .optional()
+    }
+
+    // ── Notifications ───────────────────────────────────────────────────
+
+    pub fn save_notification_rule(&self, rule: &NotificationRule) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO agents (id, name, project_id, kind, function_tag, status, working_directory, last_active_at, created_at, config)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT OR REPLACE INTO notification_rules (id, event, project_ids, channel, quiet_hours, repeat_every_minutes, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
-                agent.id,
-                agent.name,
-                agent.project_id,
-                serde_json::to_string(&agent.kind).unwrap(),
-                agent.function_tag,
-                serde_json::to_string(&agent.status).unwrap(),
-                agent.working_directory,
-                agent.last_active_at.map(|t| t.to_rfc3339()),
-                agent.created_at.to_rfc3339(),
-                serde_json::to_string(&agent.config).unwrap(),
+                rule.id,
+                serde_json::to_string(&rule.event).unwrap(),
+                serde_json::to_string(&rule.project_ids).unwrap(),
+                serde_json::to_string(&rule.channel).unwrap(),
+                rule.quiet_hours
+                    .as_ref()
+                    .map(|quiet| serde_json::to_string(quiet).unwrap()),
+                rule.repeat_every_minutes,
+                rule.enabled,
+                rule.created_at.to_rfc3339(),
             ],
         )?;
         Ok(())
     }
 
-    pub fn list_agents(&self) -> Result<Vec<Agent>> {
+    pub fn list_notification_rules(&self) -> Result<Vec<NotificationRule>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, project_id, kind, function_tag, status, working_directory, last_active_at, created_at, config
-             FROM agents ORDER BY name"
+            "SELECT id, event, project_ids, channel, quiet_hours, repeat_every_minutes, enabled, created_at
+             FROM notification_rules ORDER BY created_at",
         )?;
-        let agents = stmt
+        let rules = stmt
             .query_map([], |row| {
-                Ok(Agent {
+                Ok(NotificationRule {
                     id: row.get(0)?,
-                    name: row.get(1)?,
-                    project_id: row.get(2)?,
-                    kind: serde_json::from_str(&row.get::<_, String>(3)?).unwrap(),
-                    function_tag: row.get(4)?,
-                    status: serde_json::from_str(&row.get::<_, String>(5)?).unwrap(),
-                    working_directory: row.get(6)?,
-                    last_active_at: row
-                        .get::<_, Option<String>>(7)?
-                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|t| t.with_timezone(&chrono::Utc)),
-                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                    event: serde_json::from_str(&row.get::<_, String>(1)?).unwrap(),
+                    project_ids: serde_json::from_str(&row.get::<_, String>(2)?)
+                        .unwrap_or_default(),
+                    channel: serde_json::from_str(&row.get::<_, String>(3)?).unwrap(),
+                    quiet_hours: row
+                        .get::<_, Option<String>>(4)?
+                        .and_then(|raw| serde_json::from_str(&raw).ok()),
+                    repeat_every_minutes: row.get(5)?,
+                    enabled: row.get(6)?,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
                         .unwrap()
                         .with_timezone(&chrono::Utc),
-                    config: serde_json::from_str(&row.get::<_, String>(9)?).unwrap(),
                 })
             })?
             .collect::<Result<Vec<_>>>()?;
-        Ok(agents)
+        Ok(rules)
     }
 
-    pub fn update_agent_status(&self, agent_id: &str, status: &AgentStatus) -> Result<()> {
+    pub fn delete_notification_rule(&self, rule_id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "UPDATE agents SET status = ?1, last_active_at = ?2 WHERE id = ?3",
-            params![
-                serde_json::to_string(status).unwrap(),
-                chrono::Utc::now().to_rfc3339(),
-                agent_id,
-            ],
+            "DELETE FROM notification_rules WHERE id = ?1",
+            params![rule_id],
         )?;
         Ok(())
     }
 
-    // ── Runs ────────────────────────────────────────────────────────────
-
-    pub fn create_run(&self, run: &Run) -> Result<()> {
+    pub fn insert_notification(&self, record: &NotificationRecord) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO runs (id, agent_id, status, started_at, ended_at, summary, outputs, file_changes)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO notifications (id, agent_id, event, title, body, link, channels, created_at, read_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
-                run.id,
-                run.agent_id,
-                serde_json::to_string(&run.status).unwrap(),
-                run.started_at.to_rfc3339(),
-                run.ended_at.map(|t| t.to_rfc3339()),
-                run.summary,
-                serde_json::to_string(&run.outputs).unwrap(),
-                serde_json::to_string(&run.file_changes).unwrap(),
+                record.id,
+                record.agent_id,
+                serde_json::to_string(&record.event).unwrap(),
+                record.title,
+                record.body,
+                record.link,
+                serde_json::to_string(&record.channels).unwrap(),
+                record.created_at.to_rfc3339(),
+                record.read_at.map(|t| t.to_rfc3339()),
             ],
         )?;
         Ok(())
     }
 
-    pub fn update_run(&self, run: &Run) -> Result<()> {
+    /// Newest first.
+    pub fn list_notifications(
+        &self,
+        unread_only: bool,
+        limit: usize,
+    ) -> Result<Vec<NotificationRecord>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE runs
-             SET status = ?1, started_at = ?2, ended_at = ?3, summary = ?4, outputs = ?5, file_changes = ?6
-             WHERE id = ?7",
-            params![
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, event, title, body, link, channels, created_at, read_at
+             FROM notifications
+             WHERE ?1 = 0 OR read_at IS NULL
+             ORDER BY created_at DESC
+             LIMIT ?2",
+        )?;
+        let records = stmt
+            .query_map(params![unread_only, limit], |row| {
+                Ok(NotificationRecord {
+                    id: row.get(0)?,
+                    agent_id: row.get(1)?,
+                    event: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
+                    title: row.get(3)?,
+                    body: row.get(4)?,
+                    link: row.get(5)?,
+                    channels: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    read_at: row
+                        .get::<_, Option<String>>(8)?
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|t| t.with_timezone(&chrono::Utc)),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    pub fn count_unread_notifications(&self) -> Result<u32> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM notifications WHERE read_at IS NULL",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Mark the given notifications read, or all of them when `ids` is None.
+    /// Returns how many changed.
+    pub fn mark_notifications_read(&self, ids: Option<&[String]>) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let now = self.now().to_rfc3339();
+        match ids {
+            Some(ids) => conn.execute(
+                "UPDATE notifications SET read_at = ?1
+                 WHERE read_at IS NULL AND id IN (SELECT value FROM json_each(?2))",
+                params![now, serde_json::to_string(ids).unwrap()],
+            ),
+            None => conn.execute(
+                "UPDATE notifications SET read_at = ?1 WHERE read_at IS NULL",
+                params![now],
+            ),
+        }
+    }
+
+    // ── Webhook Subscriptions ───────────────────────────────────────────
+
+    pub fn save_webhook_subscription(&self, subscription: &WebhookSubscription) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO webhook_subscriptions (id, url, secret, events, project_ids, enabled, created_at, last_delivery_at, last_error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                subscription.id,
+                subscription.url,
+                subscription.secret,
+                serde_json::to_string(&subscription.events).unwrap(),
+                serde_json::to_string(&subscription.project_ids).unwrap(),
+                subscription.enabled,
+                subscription.created_at.to_rfc3339(),
+                subscription.last_delivery_at.map(|t| t.to_rfc3339()),
+                subscription.last_error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_webhook_subscriptions(&self) -> Result<Vec<WebhookSubscription>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, url, secret, events, project_ids, enabled, created_at, last_delivery_at, last_error
+             FROM webhook_subscriptions ORDER BY created_at",
+        )?;
+        let subscriptions = stmt
+            .query_map([], |row| {
+                Ok(WebhookSubscription {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    secret: row.get(2)?,
+                    events: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+                    project_ids: serde_json::from_str(&row.get::<_, String>(4)?)
+                        .unwrap_or_default(),
+                    enabled: row.get(5)?,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    last_delivery_at: row
+                        .get::<_, Option<String>>(7)?
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|t| t.with_timezone(&chrono::Utc)),
+                    last_error: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(subscriptions)
+    }
+
+    pub fn delete_webhook_subscription(&self, subscription_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM webhook_subscriptions WHERE id = ?1",
+            params![subscription_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_webhook_delivery(
+        &self,
+        subscription_id: &str,
+        delivered_at: chrono::DateTime<chrono::Utc>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE webhook_subscriptions SET last_delivery_at = ?2, last_error = ?3 WHERE id = ?1",
+            params![subscription_id, delivered_at.to_rfc3339(), error],
+        )?;
+        Ok(())
+    }
+
+    // ── API Tokens ──────────────────────────────────────────────────────
+
+    fn row_to_api_token(row: &rusqlite::Row) -> Result<ApiToken> {
+        let parse_time = |value: Option<String>| {
+            value
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+        Ok(ApiToken {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            scope: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
+            prefix: row.get(3)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            last_used_at: parse_time(row.get(5)?),
+            revoked_at: parse_time(row.get(6)?),
+        })
+    }
+
+    pub fn create_api_token(&self, token: &ApiToken, token_hash: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO api_tokens (id, name, scope, token_hash, prefix, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                token.id,
+                token.name,
+                serde_json::to_string(&token.scope).unwrap(),
+                token_hash,
+                token.prefix,
+                token.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every token, revoked ones included, newest first.
+    pub fn list_api_tokens(&self) -> Result<Vec<ApiToken>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, scope, prefix, created_at, last_used_at, revoked_at
+             FROM api_tokens ORDER BY created_at DESC",
+        )?;
+        let tokens = stmt
+            .query_map([], Self::row_to_api_token)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(tokens)
+    }
+
+    /// The unrevoked token with this hash, if any.
+    pub fn find_active_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, name, scope, prefix, created_at, last_used_at, revoked_at
+             FROM api_tokens WHERE token_hash = ?1 AND revoked_at IS NULL",
+            params![token_hash],
+            Self::row_to_api_token,
+        )
+        .optional()
+    }
+
+    pub fn has_active_api_tokens(&self) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM api_tokens WHERE revoked_at IS NULL)",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Returns false when the token doesn't exist or was already revoked.
+    pub fn revoke_api_token(
+        &self,
+        token_id: &str,
+        revoked_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE api_tokens SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL",
+            params![token_id, revoked_at.to_rfc3339()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn touch_api_token(
+        &self,
+        token_id: &str,
+        used_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE api_tokens SET last_used_at = ?2 WHERE id = ?1",
+            params![token_id, used_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // ── Agent Groups ────────────────────────────────────────────────────
+
+    pub fn create_agent_group(&self, group: &AgentGroup) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO agent_groups (id, name, created_at) VALUES (?1, ?2, ?3)",
+            params![group.id, group.name, group.created_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn rename_agent_group(&self, group_id: &str, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE agent_groups SET name = ?1 WHERE id = ?2",
+            params![name, group_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_agent_group(&self, group_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM agent_group_members WHERE group_id = ?1",
+            params![group_id],
+        )?;
+        conn.execute("DELETE FROM agent_groups WHERE id = ?1", params![group_id])?;
+        Ok(())
+    }
+
+    pub fn add_agent_group_member(&self, group_id: &str, agent_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO agent_group_members (group_id, agent_id, added_at)
+             VALUES (?1, ?2, ?3)",
+            params![group_id, agent_id, self.now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_agent_group_member(&self, group_id: &str, agent_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM agent_group_members WHERE group_id = ?1 AND agent_id = ?2",
+            params![group_id, agent_id],
+        )?;
+        Ok(())
+    }
+
+    /// All groups with their members in the order they were added.
+    pub fn list_agent_groups(&self) -> Result<Vec<AgentGroup>> {
+        let conn = self.conn.lock().unwrap();
+        let mut groups = conn
+            .prepare("SELECT id, name, created_at FROM agent_groups ORDER BY name")?
+            .query_map([], |row| {
+                Ok(AgentGroup {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    agent_ids: vec![],
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let members = conn
+            .prepare(
+                "SELECT group_id, agent_id FROM agent_group_members
+                 ORDER BY added_at, agent_id",
+            )?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        for (group_id, agent_id) in members {
+            if let Some(group) = groups.iter_mut().find(|group| group.id == group_id) {
+                group.agent_ids.push(agent_id);
+            }
+        }
+        Ok(groups)
+    }
+
+    pub fn get_agent_group(&self, group_id: &str) -> Result<Option<AgentGroup>> {
+        Ok(self
+            .list_agent_groups()?
+            .into_iter()
+            .find(|group| group.id == group_id))
+    }
+
+    // ── Agent Templates ─────────────────────────────────────────────────
+
+    pub fn save_agent_template(&self, template: &AgentTemplate) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO agent_templates (id, name, kind, function_tag, autonomy_level, adapter_config, instructions, ignore_globs, include_globs, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                template.id,
+                template.name,
+                serde_json::to_string(&template.kind).unwrap(),
+                template.function_tag,
+                serde_json::to_string(&template.autonomy_level).unwrap(),
+                template
+                    .adapter
+                    .as_ref()
+                    .map(|adapter| serde_json::to_string(adapter).unwrap()),
+                serde_json::to_string(&template.instructions).unwrap(),
+                serde_json::to_string(&template.ignore_globs).unwrap(),
+                serde_json::to_string(&template.include_globs).unwrap(),
+                template.created_at.to_rfc3339(),
+                template.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn row_to_agent_template(row: &rusqlite::Row) -> rusqlite::Result<AgentTemplate> {
+        Ok(AgentTemplate {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            kind: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
+            function_tag: row.get(3)?,
+            autonomy_level: serde_json::from_str(&row.get::<_, String>(4)?).unwrap(),
+            adapter: row
+                .get::<_, Option<String>>(5)?
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            instructions: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+            ignore_globs: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+            include_globs: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        })
+    }
+
+    pub fn get_agent_template(&self, template_id: &str) -> Result<Option<AgentTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, name, kind, function_tag, autonomy_level, adapter_config, instructions, ignore_globs, include_globs, created_at, updated_at
+             FROM agent_templates WHERE id = ?1",
+            params![template_id],
+            Self::row_to_agent_template,
+        )
+        .optional()
+    }
+
+    pub fn list_agent_templates(&self) -> Result<Vec<AgentTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, kind, function_tag, autonomy_level, adapter_config, instructions, ignore_globs, include_globs, created_at, updated_at
+             FROM agent_templates ORDER BY name",
+        )?;
+        let templates = stmt
+            .query_map([], Self::row_to_agent_template)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(templates)
+    }
+
+    pub fn delete_agent_template(&self, template_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM agent_templates WHERE id = ?1",
+            params![template_id],
+        )?;
+        Ok(())
+    }
+
+    // ── Agents ──────────────────────────────────────────────────────────
+
+    pub fn create_agent(&self, agent: &Agent) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO agents (id, name, project_id, kind, function_tag, status, working_directory, last_active_at, created_at, config, pinned_position, source_connector_id, source_item_id, template_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                agent.id,
+                agent.name,
+                agent.project_id,
+                serde_json::to_string(&agent.kind).unwrap(),
+                agent.function_tag,
+                serde_json::to_string(&agent.status).unwrap(),
+                agent.working_directory,
+                agent.last_active_at.map(|t| t.to_rfc3339()),
+                agent.created_at.to_rfc3339(),
+                serde_json::to_string(&agent.config).unwrap(),
+                agent.pinned_position,
+                agent.source_item.as_ref().map(|source| source.connector_id.as_str()),
+                agent.source_item.as_ref().map(|source| source.item_id.as_str()),
+                agent.template_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_agent(&self, agent: &Agent) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE agents SET name = ?1, function_tag = ?2, working_directory = ?3, config = ?4
+             WHERE id = ?5",
+            params![
+                agent.name,
+                agent.function_tag,
+                agent.working_directory,
+                serde_json::to_string(&agent.config).unwrap(),
+                agent.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_agents(&self) -> Result<Vec<Agent>> {
+        self.list_agents_filtered(&DashboardFilter::default())
+    }
+
+    pub fn get_agent(&self, agent_id: &str) -> Result<Option<Agent>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!("SELECT {AGENT_COLUMNS} FROM agents WHERE id = ?1"),
+            params![agent_id],
+            Self::row_to_agent,
+        )
+        .optional()
+    }
+
+    /// Agents matching the filter's projects, statuses and function tags.
+    /// `only_needs_attention` is left to the caller.
+    pub fn list_agents_filtered(&self, filter: &DashboardFilter) -> Result<Vec<Agent>> {
+        let statuses: Vec<String> = filter
+            .statuses
+            .iter()
+            .map(|status| serde_json::to_string(status).unwrap())
+            .collect();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {AGENT_COLUMNS}
+             FROM agents
+             WHERE (?1 = '[]' OR project_id IN (SELECT value FROM json_each(?1)))
+               AND (?2 = '[]' OR status IN (SELECT value FROM json_each(?2)))
+               AND (?3 = '[]' OR function_tag IN (SELECT value FROM json_each(?3)))
+             ORDER BY name"
+        ))?;
+        let agents = stmt
+            .query_map(
+                params![
+                    serde_json::to_string(&filter.project_ids).unwrap(),
+                    serde_json::to_string(&statuses).unwrap(),
+                    serde_json::to_string(&filter.function_tags).unwrap(),
+                ],
+                Self::row_to_agent,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(agents)
+    }
+
+    fn row_to_agent(row: &rusqlite::Row) -> Result<Agent> {
+        Ok(Agent {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            project_id: row.get(2)?,
+            kind: serde_json::from_str(&row.get::<_, String>(3)?).unwrap(),
+            function_tag: row.get(4)?,
+            status: serde_json::from_str(&row.get::<_, String>(5)?).unwrap(),
+            working_directory: row.get(6)?,
+            last_active_at: row
+                .get::<_, Option<String>>(7)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            config: serde_json::from_str(&row.get::<_, String>(9)?).unwrap(),
+            pinned_position: row.get(10)?,
+            source_item: match (
+                row.get::<_, Option<String>>(11)?,
+                row.get::<_, Option<String>>(12)?,
+            ) {
+                (Some(connector_id), Some(item_id)) => Some(SourceItemRef {
+                    connector_id,
+                    item_id,
+                }),
+                _ => None,
+            },
+            template_id: row.get(13)?,
+        })
+    }
+
+    /// Pin an agent after the currently pinned ones, or unpin it. Returns
+    /// false if the agent doesn't exist.
+    pub fn set_agent_pinned(&self, agent_id: &str, pinned: bool) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = if pinned {
+            conn.execute(
+                "UPDATE agents
+                 SET pinned_position = (SELECT COALESCE(MAX(pinned_position) + 1, 0) FROM agents)
+                 WHERE id = ?1 AND pinned_position IS NULL",
+                params![agent_id],
+            )?
+        } else {
+            conn.execute(
+                "UPDATE agents SET pinned_position = NULL WHERE id = ?1",
+                params![agent_id],
+            )?
+        };
+        if changed > 0 {
+            return Ok(true);
+        }
+        conn.query_row(
+            "SELECT 1 FROM agents WHERE id = ?1",
+            params![agent_id],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|found| found.is_some())
+    }
+
+    /// Renumber pinned agents: those in `agent_ids` first, in that order,
+    /// then any other pinned agents in their existing order. Unpinned ids
+    /// are ignored.
+    pub fn reorder_pinned_agents(&self, agent_ids: &[String]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let mut pinned: Vec<String> = conn
+            .prepare(
+                "SELECT id FROM agents WHERE pinned_position IS NOT NULL
+                 ORDER BY pinned_position, name",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>()?;
+        let rank = |id: &String| {
+            agent_ids
+                .iter()
+                .position(|wanted| wanted == id)
+                .unwrap_or(agent_ids.len())
+        };
+        pinned.sort_by_key(rank); // stable, so unlisted agents keep their order
+        for (position, id) in pinned.iter().enumerate() {
+            conn.execute(
+                "UPDATE agents SET pinned_position = ?1 WHERE id = ?2",
+                params![position as u32, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Set an agent's status unless the transition is illegal (see
+    /// `status_transition_allowed`), in which case it is logged and ignored.
+    pub fn update_agent_status(&self, agent_id: &str, status: &AgentStatus) -> Result<()> {
+        self.set_agent_status(agent_id, status, false).map(|_| ())
+    }
+
+    /// Returns whether the status was written. `force` skips the transition
+    /// check, for operator overrides.
+    pub fn set_agent_status(
+        &self,
+        agent_id: &str,
+        status: &AgentStatus,
+        force: bool,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let current: Option<String> = conn
+            .query_row(
+                "SELECT status FROM agents WHERE id = ?1",
+                params![agent_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(current) = current else {
+            return Ok(false);
+        };
+        let current: AgentStatus = serde_json::from_str(&current).unwrap_or(AgentStatus::Idle);
+
+        if !force {
+            let has_open_run = || -> Result<bool> {
+                conn.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM runs
+                     WHERE agent_id = ?1 AND status = '\"in_progress\"' AND ended_at IS NULL)",
+                    params![agent_id],
+                    |row| row.get(0),
+                )
+            };
+            if !status_transition_allowed(&current, status, has_open_run)? {
+                tracing::warn!(
+                    agent_id,
+                    from = ?current,
+                    to = ?status,
+                    "Ignored illegal agent status transition"
+                );
+                return Ok(false);
+            }
+        }
+
+        let now = self.now();
+        conn.execute(
+            "UPDATE agents SET status = ?1, last_active_at = ?2 WHERE id = ?3",
+            params![
+                serde_json::to_string(status).unwrap(),
+                now.to_rfc3339(),
+                agent_id
+            ],
+        )?;
+        drop(conn);
+        if current != *status {
+            let _ = self.status_changes.send(StatusChange {
+                agent_id: agent_id.to_string(),
+                from: current,
+                to: status.clone(),
+                at: now,
+            });
+        }
+        Ok(true)
+    }
+
+    // ── Runs ────────────────────────────────────────────────────────────
+
+    pub fn create_run(&self, run: &Run) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO runs (id, agent_id, status, started_at, ended_at, summary, outputs, file_changes, token_in, token_out, cost, git_snapshot, review, labels, instruction_message_id, source_connector_id, source_item_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                run.id,
+                run.agent_id,
+                serde_json::to_string(&run.status).unwrap(),
+                run.started_at.to_rfc3339(),
+                run.ended_at.map(|t| t.to_rfc3339()),
+                run.summary,
+                serde_json::to_string(&run.outputs).unwrap(),
+                serde_json::to_string(&run.file_changes).unwrap(),
+                run.token_in as i64,
+                run.token_out as i64,
+                run.cost,
+                run.git_snapshot
+                    .as_ref()
+                    .map(|snapshot| serde_json::to_string(snapshot).unwrap()),
+                run.review
+                    .as_ref()
+                    .map(|review| serde_json::to_string(review).unwrap()),
+                serde_json::to_string(&run.labels).unwrap(),
+                run.instruction_message_id,
+                run.source_item.as_ref().map(|source| source.connector_id.as_str()),
+                run.source_item.as_ref().map(|source| source.item_id.as_str()),
+            ],
+        )?;
+        traces::run_span(run).in_scope(|| tracing::debug!(status = ?run.status, "Run started"));
+        Ok(())
+    }
+
+    pub fn update_run(&self, run: &Run) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE runs
+             SET status = ?1, started_at = ?2, ended_at = ?3, summary = ?4, outputs = ?5, file_changes = ?6,
+                 token_in = ?7, token_out = ?8, cost = ?9, git_snapshot = ?10, review = ?11,
+                 labels = ?12
+             WHERE id = ?13",
+            params![
                 serde_json::to_string(&run.status).unwrap(),
                 run.started_at.to_rfc3339(),
                 run.ended_at.map(|t| t.to_rfc3339()),
                 run.summary,
                 serde_json::to_string(&run.outputs).unwrap(),
                 serde_json::to_string(&run.file_changes).unwrap(),
+                run.token_in as i64,
+                run.token_out as i64,
+                run.cost,
+                run.git_snapshot
+                    .as_ref()
+                    .map(|snapshot| serde_json::to_string(snapshot).unwrap()),
+                run.review
+                    .as_ref()
+                    .map(|review| serde_json::to_string(review).unwrap()),
+                serde_json::to_string(&run.labels).unwrap(),
+                run.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn summarize_instruction(instruction: &str) -> String {
+        let trimmed = instruction.trim();
+        if trimmed.is_empty() {
+            return "Running instruction".to_string();
+        }
+        let mut preview = trimmed.chars().take(96).collect::<String>();
+        if trimmed.chars().count() > 96 {
+            preview.push_str("...");
+        }
+        format!("Running: {}", preview)
+    }
+
+    /// In-progress runs for an agent, newest first.
+    pub fn get_active_runs(&self, agent_id: &str) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs
+             WHERE agent_id = ?1 AND status = '\"in_progress\"' AND ended_at IS NULL
+             ORDER BY started_at DESC"
+        ))?;
+        let runs = stmt
+            .query_map(params![agent_id], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    /// In-progress runs across all agents, oldest first.
+    pub fn get_all_active_runs(&self) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs
+             WHERE status = '\"in_progress\"' AND ended_at IS NULL
+             ORDER BY started_at ASC"
+        ))?;
+        let runs = stmt
+            .query_map([], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    /// Find the run a message refers to. `run_key` may be a run id or the id
+    /// of the instruction message that started the run.
+    fn find_run_by_key(&self, agent_id: &str, run_key: &str) -> Result<Option<Run>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "SELECT {RUN_COLUMNS}
+                 FROM runs
+                 WHERE agent_id = ?1 AND (id = ?2 OR instruction_message_id = ?2)
+                 ORDER BY started_at DESC LIMIT 1"
+            ),
+            params![agent_id, run_key],
+            Self::row_to_run,
+        )
+        .optional()
+    }
+
+    fn ensure_in_progress_run(
+        &self,
+        agent_id: &str,
+        run_key: Option<&str>,
+        summary: Option<String>,
+    ) -> Result<Run> {
+        if let Some(run_key) = run_key {
+            if let Some(run) = self.find_run_by_key(agent_id, run_key)? {
+                return Ok(run);
+            }
+        }
+        if let Some(run) = self.get_active_runs(agent_id)?.into_iter().next() {
+            return Ok(run);
+        }
+
+        let run = self.new_run(agent_id, RunStatus::InProgress, summary);
+        self.create_run(&run)?;
+        Ok(run)
+    }
+
+    /// The run a Blocked message stopped: the one its run key names. None
+    /// when the message carries no key or the run is gone.
+    pub fn get_run_blocked_by(&self, blocked: &Message) -> Result<Option<Run>> {
+        match blocked.run_key() {
+            Some(run_key) => self.find_run_by_key(&blocked.agent_id, run_key),
+            None => Ok(None),
+        }
+    }
+
+    /// Put a blocked run back in progress with the operator's answer as its
+    /// next instruction, and queue the answer, in one transaction. The reply
+    /// becomes the run's key, so the adapter reporting it on delivery joins
+    /// this run rather than opening another. None when the run is gone or no
+    /// longer waiting for review; nothing is written then.
+    pub fn reopen_run_with_reply(&self, run_id: &str, reply: &Message) -> Result<Option<Run>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let run = tx
+            .query_row(
+                &format!("SELECT {RUN_COLUMNS} FROM runs WHERE id = ?1"),
+                params![run_id],
+                Self::row_to_run,
+            )
+            .optional()?;
+        let Some(mut run) = run.filter(|run| run.status == RunStatus::NeedsReview) else {
+            return Ok(None);
+        };
+        run.status = RunStatus::InProgress;
+        run.ended_at = None;
+        run.instruction_message_id = Some(reply.id.clone());
+        run.outputs.push(RunOutput {
+            kind: "instruction".to_string(),
+            content: self.redact(&reply.content),
+            timestamp: self.now(),
+        });
+        tx.execute(
+            "UPDATE runs SET status = ?1, ended_at = NULL, outputs = ?2, instruction_message_id = ?3
+             WHERE id = ?4",
+            params![
+                serde_json::to_string(&run.status).unwrap(),
+                serde_json::to_string(&run.outputs).unwrap(),
+                reply.id,
                 run.id,
             ],
         )?;
+        let content = self.stored_message_content(reply);
+        Self::insert_message_row(&tx, reply, &content)?;
+        tx.commit()?;
+        drop(conn);
+        traces::run_span(&run).in_scope(|| tracing::debug!("Run reopened"));
+        self.message_inserted(reply, &content)?;
+        Ok(Some(run))
+    }
+
+    /// `Run::new`, started by this database's clock.
+    fn new_run(&self, agent_id: &str, status: RunStatus, summary: Option<String>) -> Run {
+        let mut run = Run::new(agent_id, status, summary);
+        run.started_at = self.now();
+        run
+    }
+
+    /// Open a run for an instruction. Each instruction gets its own run up to
+    /// the agent's `max_concurrent_runs`; past the cap it joins the newest
+    /// active run. Calling again for the same instruction id is a no-op, since
+    /// both the bus and the adapter loop report the same instruction.
+    pub fn start_instruction_run(
+        &self,
+        agent_id: &str,
+        instruction_id: Option<&str>,
+        instruction: &str,
+    ) -> Result<Run> {
+        if let Some(instruction_id) = instruction_id {
+            if let Some(run) = self.find_run_by_key(agent_id, instruction_id)? {
+                return Ok(run);
+            }
+        }
+
+        let instruction = self.redact(instruction);
+        let summary = Self::summarize_instruction(&instruction);
+        let max_concurrent = self
+            .get_agent_config(agent_id)?
+            .map(|config| config.max_concurrent_runs.max(1) as usize)
+            .unwrap_or(1);
+        let active = self.get_active_runs(agent_id)?;
+
+        let mut run = if active.len() < max_concurrent {
+            let mut run = self.new_run(agent_id, RunStatus::InProgress, Some(summary.clone()));
+            run.instruction_message_id = instruction_id.map(str::to_string);
+            self.create_run(&run)?;
+            run
+        } else {
+            active
+                .into_iter()
+                .next()
+                .expect("active runs at cap is non-empty")
+        };
+        run.outputs.push(RunOutput {
+            kind: "instruction".to_string(),
+            content: instruction,
+            timestamp: self.now(),
+        });
+        run.summary = Some(summary);
+        self.update_run(&run)?;
+        Ok(run)
+    }
+
+    pub fn append_run_output(
+        &self,
+        agent_id: &str,
+        kind: &str,
+        content: &str,
+    ) -> Result<Option<Run>> {
+        self.append_run_output_to(agent_id, None, kind, content)
+    }
+
+    /// Append output to the run identified by `run_key` (see `find_run_by_key`),
+    /// falling back to the newest active run. Returns None when the output was
+    /// skipped because the agent keeps heartbeats out of its runs.
+    pub fn append_run_output_to(
+        &self,
+        agent_id: &str,
+        run_key: Option<&str>,
+        kind: &str,
+        content: &str,
+    ) -> Result<Option<Run>> {
+        if kind == "heartbeat"
+            && self
+                .get_agent_config(agent_id)?
+                .is_some_and(|config| config.exclude_heartbeats_from_runs)
+        {
+            return Ok(None);
+        }
+
+        let mut run =
+            self.ensure_in_progress_run(agent_id, run_key, Some("Agent activity".to_string()))?;
+        run.outputs.push(RunOutput {
+            kind: kind.to_string(),
+            content: self.redact(content),
+            timestamp: self.now(),
+        });
+        if run.summary.is_none() {
+            run.summary = Some("Agent activity".to_string());
+        }
+        self.update_run(&run)?;
+        Ok(Some(run))
+    }
+
+    pub fn finalize_latest_run(
+        &self,
+        agent_id: &str,
+        status: RunStatus,
+        summary: Option<String>,
+    ) -> Result<Option<Run>> {
+        self.finalize_run_for(agent_id, None, status, summary)
+    }
+
+    /// Finalize the run identified by `run_key`, or the newest active run.
+    /// A key that points at an already-finished run leaves it untouched rather
+    /// than closing some other run in its place.
+    pub fn finalize_run_for(
+        &self,
+        agent_id: &str,
+        run_key: Option<&str>,
+        status: RunStatus,
+        summary: Option<String>,
+    ) -> Result<Option<Run>> {
+        let summary = summary.map(|summary| self.redact(&summary));
+        let target = match run_key {
+            Some(run_key) => self.find_run_by_key(agent_id, run_key)?,
+            None => None,
+        };
+        let target = match target {
+            Some(run) => Some(run),
+            None => match self.get_active_runs(agent_id)?.into_iter().next() {
+                Some(run) => Some(run),
+                None => self.get_latest_run_for_agent(agent_id)?,
+            },
+        };
+
+        if let Some(mut run) = target {
+            if run.status == RunStatus::InProgress && run.ended_at.is_none() {
+                run.status = status;
+                run.ended_at = Some(self.now());
+                if let Some(summary) = summary {
+                    if !summary.trim().is_empty() {
+                        run.summary = Some(summary);
+                    }
+                }
+                run.git_snapshot = self.capture_git_snapshot(agent_id);
+                run.compact_heartbeats();
+                self.update_run(&run)?;
+                traces::run_span(&run)
+                    .in_scope(|| tracing::debug!(status = ?run.status, "Run finalized"));
+                return Ok(Some(run));
+            }
+            return Ok(Some(run));
+        }
+
+        // If no run exists yet, create a terminal run entry to preserve traceability.
+        let mut run = self.new_run(agent_id, status, summary);
+        run.ended_at = Some(run.started_at);
+        run.git_snapshot = self.capture_git_snapshot(agent_id);
+        self.create_run(&run)?;
+        Ok(Some(run))
+    }
+
+    pub fn get_agent_config(&self, agent_id: &str) -> Result<Option<AgentConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let config = conn
+            .query_row(
+                "SELECT config FROM agents WHERE id = ?1",
+                params![agent_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(config.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    /// Snapshot the agent's working directory if it is a git checkout, so a
+    /// finalized run carries a reviewable diff rather than just a change count.
+    fn capture_git_snapshot(&self, agent_id: &str) -> Option<GitSnapshot> {
+        let (working_directory, config) = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT working_directory, config FROM agents WHERE id = ?1",
+                params![agent_id],
+                |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .ok()
+            .flatten()?
+        };
+        let include_patch = serde_json::from_str::<AgentConfig>(&config)
+            .map(|config| config.capture_git_patch)
+            .unwrap_or(false);
+        crate::git::capture_snapshot(&working_directory?, include_patch)
+    }
+
+    fn row_to_run(row: &rusqlite::Row) -> rusqlite::Result<Run> {
+        Ok(Run {
+            id: row.get(0)?,
+            agent_id: row.get(1)?,
+            status: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
+            started_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            ended_at: row
+                .get::<_, Option<String>>(4)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            summary: row.get(5)?,
+            outputs: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+            file_changes: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+            token_in: row.get::<_, i64>(8)?.max(0) as u64,
+            token_out: row.get::<_, i64>(9)?.max(0) as u64,
+            cost: row.get(10)?,
+            git_snapshot: row
+                .get::<_, Option<String>>(11)?
+                .and_then(|raw| serde_json::from_str(&raw).ok()),
+            review: row
+                .get::<_, Option<String>>(12)?
+                .and_then(|raw| serde_json::from_str(&raw).ok()),
+            labels: serde_json::from_str(&row.get::<_, String>(13)?).unwrap_or_default(),
+            instruction_message_id: row.get(14)?,
+            source_item: match (
+                row.get::<_, Option<String>>(15)?,
+                row.get::<_, Option<String>>(16)?,
+            ) {
+                (Some(connector_id), Some(item_id)) => Some(SourceItemRef {
+                    connector_id,
+                    item_id,
+                }),
+                _ => None,
+            },
+        })
+    }
+
+    pub fn get_run(&self, run_id: &str) -> Result<Option<Run>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "SELECT {RUN_COLUMNS}
+             FROM runs WHERE id = ?1"
+            ),
+            params![run_id],
+            Self::row_to_run,
+        )
+        .optional()
+    }
+
+    /// A run without its outputs; page through those with `get_run_outputs`.
+    pub fn get_run_header(&self, run_id: &str) -> Result<Option<RunHeader>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!("SELECT {RUN_HEADER_COLUMNS} FROM runs WHERE id = ?1"),
+            params![run_id],
+            Self::row_to_run_header,
+        )
+        .optional()
+    }
+
+    /// An agent's latest runs, newest first, without outputs.
+    pub fn get_run_headers_for_agent(
+        &self,
+        agent_id: &str,
+        limit: usize,
+    ) -> Result<Vec<RunHeader>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_HEADER_COLUMNS}
+             FROM runs WHERE agent_id = ?1 ORDER BY started_at DESC LIMIT ?2"
+        ))?;
+        let runs = stmt
+            .query_map(params![agent_id, limit], Self::row_to_run_header)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    fn row_to_run_header(row: &rusqlite::Row) -> Result<RunHeader> {
+        Ok(RunHeader {
+            run: Self::row_to_run(row)?,
+            output_count: row.get::<_, Option<i64>>(17)?.unwrap_or(0).max(0) as usize,
+        })
+    }
+
+    /// Up to `limit` of a run's outputs starting at index `offset`, sliced in
+    /// SQL so the rest of the blob is never deserialized. None if the run
+    /// doesn't exist.
+    pub fn get_run_outputs(
+        &self,
+        run_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Option<RunOutputPage>> {
+        let conn = self.conn.lock().unwrap();
+        let total = conn
+            .query_row(
+                "SELECT json_array_length(outputs) FROM runs WHERE id = ?1",
+                params![run_id],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .optional()?;
+        let Some(total) = total else {
+            return Ok(None);
+        };
+        let mut stmt = conn.prepare(
+            "SELECT output.value
+             FROM runs, json_each(runs.outputs) AS output
+             WHERE runs.id = ?1
+             ORDER BY output.key
+             LIMIT ?2 OFFSET ?3",
+        )?;
+        let outputs = stmt
+            .query_map(params![run_id, limit, offset], |row| {
+                row.get::<_, String>(0)
+            })?
+            .filter_map(|raw| match raw {
+                Ok(raw) => serde_json::from_str(&raw).ok().map(Ok),
+                Err(error) => Some(Err(error)),
+            })
+            .collect::<Result<Vec<RunOutput>>>()?;
+        Ok(Some(RunOutputPage {
+            run_id: run_id.to_string(),
+            offset,
+            total: total.unwrap_or(0).max(0) as usize,
+            outputs,
+        }))
+    }
+
+    /// The run an instruction started, if it didn't join an existing one.
+    pub fn get_run_for_instruction(&self, message_id: &str) -> Result<Option<Run>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "SELECT {RUN_SUMMARY_COLUMNS}
+             FROM runs WHERE instruction_message_id = ?1"
+            ),
+            params![message_id],
+            Self::row_to_run,
+        )
+        .optional()
+    }
+
+    pub fn get_latest_run_for_agent(&self, agent_id: &str) -> Result<Option<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs WHERE agent_id = ?1 ORDER BY started_at DESC LIMIT 1"
+        ))?;
+        let mut runs = stmt.query_map(params![agent_id], Self::row_to_run)?;
+        Ok(runs.next().transpose()?)
+    }
+
+    /// Latest run for every agent in one query, keyed by agent id. Outputs
+    /// and git snapshots are left empty.
+    pub fn get_latest_runs_by_agent(&self) -> Result<HashMap<String, Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_SUMMARY_COLUMNS}
+             FROM (
+                 SELECT *, ROW_NUMBER() OVER (
+                     PARTITION BY agent_id ORDER BY started_at DESC
+                 ) AS latest_rank
+                 FROM runs
+             )
+             WHERE latest_rank = 1"
+        ))?;
+        let runs = stmt
+            .query_map([], Self::row_to_run)?
+            .map(|run| run.map(|run| (run.agent_id.clone(), run)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(runs)
+    }
+
+    /// Runs finalized after `since`, oldest first, without outputs or git
+    /// snapshots.
+    pub fn get_runs_ended_since(&self, since: &str) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_SUMMARY_COLUMNS}
+             FROM runs WHERE ended_at > ?1 ORDER BY ended_at ASC"
+        ))?;
+        let runs = stmt
+            .query_map(params![since], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    pub fn get_runs_for_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs WHERE agent_id = ?1 ORDER BY started_at DESC LIMIT ?2"
+        ))?;
+        let runs = stmt
+            .query_map(params![agent_id, limit], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    /// Runs overlapping an optional [since, until] window, oldest first.
+    pub fn get_runs_for_agent_between(
+        &self,
+        agent_id: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs
+             WHERE agent_id = ?1
+               AND (?2 IS NULL OR ended_at IS NULL OR ended_at >= ?2)
+               AND (?3 IS NULL OR started_at <= ?3)
+             ORDER BY started_at ASC"
+        ))?;
+        let runs = stmt
+            .query_map(params![agent_id, since, until], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    /// How many runs an agent started at or after `since`.
+    pub fn count_runs_started_since(&self, agent_id: &str, since: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM runs WHERE agent_id = ?1 AND started_at >= ?2",
+            params![agent_id, since],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count as usize)
+    }
+
+    /// Runs still open or ended at/after `since`, across all agents, without
+    /// outputs or git snapshots.
+    pub fn get_runs_active_since(&self, since: &str) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_SUMMARY_COLUMNS}
+             FROM runs
+             WHERE ended_at IS NULL OR ended_at >= ?1
+             ORDER BY started_at ASC"
+        ))?;
+        let runs = stmt
+            .query_map(params![since], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    pub fn set_run_source_item(&self, run_id: &str, source: &SourceItemRef) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE runs SET source_connector_id = ?1, source_item_id = ?2 WHERE id = ?3",
+            params![source.connector_id, source.item_id, run_id],
+        )?;
         Ok(())
     }
 
-    fn summarize_instruction(instruction: &str) -> String {
-        let trimmed = instruction.trim();
-        if trimmed.is_empty() {
-            return "Running instruction".to_string();
-        }
-        let mut preview = trimmed.chars().take(96).collect::<String>();
-        if trimmed.chars().count() > 96 {
-            preview.push_str("...");
+    /// Finished runs that ended at or after `since`, oldest first.
+    pub fn get_finished_runs_since(&self, since: &str) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs
+             WHERE ended_at IS NOT NULL AND ended_at >= ?1
+             ORDER BY ended_at ASC"
+        ))?;
+        let runs = stmt
+            .query_map(params![since], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    pub fn set_run_summary(&self, run_id: &str, summary: &str) -> Result<()> {
+        let summary = self.redact(summary);
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE runs SET summary = ?1 WHERE id = ?2",
+            params![summary, run_id],
+        )?;
+        Ok(())
+    }
+
+    /// Every run that worked on a connector item, newest first, across agents.
+    pub fn get_runs_for_source_item(&self, connector_id: &str, item_id: &str) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs
+             WHERE source_connector_id = ?1 AND source_item_id = ?2
+             ORDER BY started_at DESC"
+        ))?;
+        let runs = stmt
+            .query_map(params![connector_id, item_id], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    pub fn set_run_labels(&self, run_id: &str, labels: &[String]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE runs SET labels = ?1 WHERE id = ?2",
+            params![serde_json::to_string(labels).unwrap(), run_id],
+        )?;
+        Ok(())
+    }
+
+    /// Runs carrying `label`, newest first, optionally limited to one agent.
+    pub fn get_runs_by_label(
+        &self,
+        label: &str,
+        agent_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS}
+             FROM runs
+             WHERE EXISTS (SELECT 1 FROM json_each(runs.labels) WHERE json_each.value = ?1)
+               AND (?2 IS NULL OR agent_id = ?2)
+             ORDER BY started_at DESC LIMIT ?3"
+        ))?;
+        let runs = stmt
+            .query_map(params![label, agent_id, limit], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    // ── Run archive ──
+
+    /// Move up to `limit` finished runs that ended before `cutoff` into
+    /// runs_archive (see `run_archive`). Runs waiting for review stay.
+    pub fn archive_runs_ended_before(&self, cutoff: &str, limit: usize) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let ids: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT id FROM runs
+                 WHERE ended_at IS NOT NULL AND ended_at < ?1 AND status NOT IN (?2, ?3)
+                 ORDER BY ended_at ASC
+                 LIMIT ?4",
+            )?;
+            let ids = stmt
+                .query_map(
+                    params![
+                        cutoff,
+                        serde_json::to_string(&RunStatus::InProgress).unwrap(),
+                        serde_json::to_string(&RunStatus::NeedsReview).unwrap(),
+                        limit
+                    ],
+                    |row| row.get(0),
+                )?
+                .collect::<Result<Vec<_>>>()?;
+            ids
+        };
+        let archived_at = self.now().to_rfc3339();
+        for id in &ids {
+            let mut payload: ArchivePayload = tx.query_row(
+                "SELECT outputs, file_changes, git_snapshot, review FROM runs WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(ArchivePayload {
+                        outputs: row.get(0)?,
+                        file_changes: row.get(1)?,
+                        git_snapshot: row.get(2)?,
+                        review: row.get(3)?,
+                        comments: vec![],
+                    })
+                },
+            )?;
+            payload.comments = tx
+                .prepare(
+                    "SELECT id, run_id, output_index, body, created_at, updated_at
+                     FROM run_comments WHERE run_id = ?1
+                     ORDER BY created_at ASC",
+                )?
+                .query_map(params![id], Self::row_to_run_comment)?
+                .collect::<Result<Vec<_>>>()?;
+            let compressed = gzip_json(&payload)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO runs_archive
+                    (id, agent_id, status, started_at, ended_at, summary, token_in, token_out,
+                     cost, labels, instruction_message_id, source_connector_id, source_item_id,
+                     output_count, archived_at, payload)
+                 SELECT id, agent_id, status, started_at, ended_at, summary, token_in, token_out,
+                        cost, labels, instruction_message_id, source_connector_id, source_item_id,
+                        json_array_length(outputs), ?2, ?3
+                 FROM runs WHERE id = ?1",
+                params![id, archived_at, compressed],
+            )?;
+            tx.execute("DELETE FROM run_comments WHERE run_id = ?1", params![id])?;
+            tx.execute("DELETE FROM runs WHERE id = ?1", params![id])?;
         }
-        format!("Running: {}", preview)
+        tx.commit()?;
+        Ok(ids.len())
     }
 
-    fn ensure_in_progress_run(&self, agent_id: &str, summary: Option<String>) -> Result<Run> {
-        if let Some(run) = self.get_latest_run_for_agent(agent_id)? {
-            if run.status == RunStatus::InProgress && run.ended_at.is_none() {
-                return Ok(run);
+    /// Archived runs, most recently started first, optionally for one agent
+    /// and before a `started_at` cursor.
+    pub fn list_archived_runs(
+        &self,
+        agent_id: Option<&str>,
+        before: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<crate::run_archive::ArchivedRun>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, status, started_at, ended_at, summary, token_in, token_out,
+                    cost, labels, output_count, archived_at, length(payload)
+             FROM runs_archive
+             WHERE (?1 IS NULL OR agent_id = ?1) AND (?2 IS NULL OR started_at < ?2)
+             ORDER BY started_at DESC
+             LIMIT ?3",
+        )?;
+        let parse_time = |raw: String| {
+            chrono::DateTime::parse_from_rfc3339(&raw)
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        let runs = stmt
+            .query_map(params![agent_id, before, limit], |row| {
+                Ok(crate::run_archive::ArchivedRun {
+                    id: row.get(0)?,
+                    agent_id: row.get(1)?,
+                    status: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
+                    started_at: parse_time(row.get(3)?),
+                    ended_at: row.get::<_, Option<String>>(4)?.map(parse_time),
+                    summary: row.get(5)?,
+                    token_in: row.get::<_, i64>(6)?.max(0) as u64,
+                    token_out: row.get::<_, i64>(7)?.max(0) as u64,
+                    cost: row.get(8)?,
+                    labels: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+                    output_count: row.get::<_, i64>(10)?.max(0) as usize,
+                    archived_at: parse_time(row.get(11)?),
+                    payload_bytes: row.get::<_, i64>(12)?.max(0) as usize,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    /// Put an archived run and its comments back into runs. None if it
+    /// isn't archived.
+    pub fn restore_archived_run(&self, run_id: &str) -> Result<Option<Run>> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let Some(compressed) = tx
+                .query_row(
+                    "SELECT payload FROM runs_archive WHERE id = ?1",
+                    params![run_id],
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            let payload: ArchivePayload = gunzip_json(&compressed)?;
+            tx.execute(
+                &format!(
+                    "INSERT INTO runs ({RUN_COLUMNS})
+                     SELECT id, agent_id, status, started_at, ended_at, summary, ?2, ?3,
+                            token_in, token_out, cost, ?4, ?5, labels, instruction_message_id,
+                            source_connector_id, source_item_id
+                     FROM runs_archive WHERE id = ?1"
+                ),
+                params![
+                    run_id,
+                    payload.outputs,
+                    payload.file_changes,
+                    payload.git_snapshot,
+                    payload.review
+                ],
+            )?;
+            for comment in &payload.comments {
+                tx.execute(
+                    "INSERT OR REPLACE INTO run_comments (id, run_id, output_index, body, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        comment.id,
+                        comment.run_id,
+                        comment.output_index.map(|index| index as i64),
+                        comment.body,
+                        comment.created_at.to_rfc3339(),
+                        comment.updated_at.to_rfc3339(),
+                    ],
+                )?;
             }
+            tx.execute("DELETE FROM runs_archive WHERE id = ?1", params![run_id])?;
+            tx.commit()?;
         }
+        self.get_run(run_id)
+    }
 
-        let run = Run {
-            id: Uuid::new_v4().to_string(),
-            agent_id: agent_id.to_string(),
-            status: RunStatus::InProgress,
-            started_at: chrono::Utc::now(),
-            ended_at: None,
-            summary,
-            outputs: vec![],
-            file_changes: vec![],
-        };
-        self.create_run(&run)?;
-        Ok(run)
+    fn row_to_run_comment(row: &rusqlite::Row) -> Result<RunComment> {
+        Ok(RunComment {
+            id: row.get(0)?,
+            run_id: row.get(1)?,
+            output_index: row
+                .get::<_, Option<i64>>(2)?
+                .map(|index| index.max(0) as usize),
+            body: row.get(3)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        })
     }
 
-    pub fn start_instruction_run(&self, agent_id: &str, instruction: &str) -> Result<Run> {
-        let mut run =
-            self.ensure_in_progress_run(agent_id, Some(Self::summarize_instruction(instruction)))?;
-        run.outputs.push(RunOutput {
-            kind: "instruction".to_string(),
-            content: instruction.to_string(),
-            timestamp: chrono::Utc::now(),
-        });
-        run.summary = Some(Self::summarize_instruction(instruction));
-        self.update_run(&run)?;
-        Ok(run)
+    pub fn save_run_comment(&self, comment: &RunComment) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO run_comments (id, run_id, output_index, body, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                comment.id,
+                comment.run_id,
+                comment.output_index.map(|index| index as i64),
+                comment.body,
+                comment.created_at.to_rfc3339(),
+                comment.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
     }
 
-    pub fn append_run_output(&self, agent_id: &str, kind: &str, content: &str) -> Result<Run> {
-        let mut run = self.ensure_in_progress_run(agent_id, Some("Agent activity".to_string()))?;
-        run.outputs.push(RunOutput {
-            kind: kind.to_string(),
-            content: content.to_string(),
-            timestamp: chrono::Utc::now(),
-        });
-        if run.summary.is_none() {
-            run.summary = Some("Agent activity".to_string());
+    pub fn get_run_comment(&self, comment_id: &str) -> Result<Option<RunComment>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, run_id, output_index, body, created_at, updated_at
+             FROM run_comments WHERE id = ?1",
+            params![comment_id],
+            Self::row_to_run_comment,
+        )
+        .optional()
+    }
+
+    /// A run's comments, oldest first.
+    pub fn list_run_comments(&self, run_id: &str) -> Result<Vec<RunComment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, run_id, output_index, body, created_at, updated_at
+             FROM run_comments WHERE run_id = ?1
+             ORDER BY created_at ASC",
+        )?;
+        let comments = stmt
+            .query_map(params![run_id], Self::row_to_run_comment)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(comments)
+    }
+
+    pub fn delete_run_comment(&self, comment_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM run_comments WHERE id = ?1",
+            params![comment_id],
+        )?;
+        Ok(())
+    }
+
+    /// Per-label run counts and spend across all runs, most used first.
+    pub fn get_label_rollups(&self) -> Result<Vec<LabelRollup>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT json_each.value,
+                    COUNT(*),
+                    SUM(CASE WHEN runs.status = '\"in_progress\"' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN runs.status = '\"needs_review\"' THEN 1 ELSE 0 END),
+                    SUM(runs.cost),
+                    MAX(runs.started_at)
+             FROM runs, json_each(runs.labels)
+             GROUP BY json_each.value
+             ORDER BY COUNT(*) DESC, json_each.value ASC",
+        )?;
+        let rollups = stmt
+            .query_map([], |row| {
+                Ok(LabelRollup {
+                    label: row.get(0)?,
+                    run_count: row.get::<_, i64>(1)?.max(0) as usize,
+                    in_progress: row.get::<_, i64>(2)?.max(0) as usize,
+                    needs_review: row.get::<_, i64>(3)?.max(0) as usize,
+                    cost: row.get(4)?,
+                    last_run_at: row
+                        .get::<_, Option<String>>(5)?
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|t| t.with_timezone(&chrono::Utc)),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(rollups)
+    }
+
+    /// Status/timing projection of runs for analytics, optionally scoped to a
+    /// project and a started_at window. Skips the outputs blobs entirely.
+    pub fn get_run_facts(
+        &self,
+        project_id: Option<&str>,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Vec<crate::analytics::RunFact>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT runs.agent_id, runs.status, runs.started_at, runs.ended_at, runs.summary, runs.review
+             FROM runs
+             JOIN agents ON agents.id = runs.agent_id
+             WHERE (?1 IS NULL OR agents.project_id = ?1)
+               AND (?2 IS NULL OR runs.started_at >= ?2)
+               AND (?3 IS NULL OR runs.started_at <= ?3)",
+        )?;
+        let facts = stmt
+            .query_map(params![project_id, since, until], |row| {
+                Ok(crate::analytics::RunFact {
+                    agent_id: row.get(0)?,
+                    status: serde_json::from_str(&row.get::<_, String>(1)?).unwrap(),
+                    started_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    ended_at: row
+                        .get::<_, Option<String>>(3)?
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|t| t.with_timezone(&chrono::Utc)),
+                    summary: row.get(4)?,
+                    review: row
+                        .get::<_, Option<String>>(5)?
+                        .and_then(|raw| serde_json::from_str::<RunReview>(&raw).ok())
+                        .map(|review| review.decision),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(facts)
+    }
+
+    /// Add reported token usage to the run named by `run_key`, else the agent's
+    /// most recent run. Usage that arrives after a run finalizes still belongs
+    /// to it, so this does not open a new run; returns None when the agent has
+    /// no runs yet.
+    pub fn record_run_usage(
+        &self,
+        agent_id: &str,
+        run_key: Option<&str>,
+        usage: &TokenUsage,
+    ) -> Result<Option<Run>> {
+        if usage.is_empty() {
+            return Ok(None);
         }
+        let run = match run_key {
+            Some(run_key) => self.find_run_by_key(agent_id, run_key)?,
+            None => None,
+        };
+        let run = match run {
+            Some(run) => Some(run),
+            None => self.get_latest_run_for_agent(agent_id)?,
+        };
+        let Some(mut run) = run else {
+            return Ok(None);
+        };
+        run.add_usage(usage);
         self.update_run(&run)?;
-        Ok(run)
+        Ok(Some(run))
+    }
+
+    /// Lifetime token usage and cost per agent, summed across runs.
+    pub fn get_usage_totals_by_agent(&self) -> Result<HashMap<String, TokenUsage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT agent_id, SUM(token_in), SUM(token_out), SUM(cost)
+             FROM runs GROUP BY agent_id",
+        )?;
+        let totals = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    TokenUsage {
+                        token_in: row.get::<_, i64>(1)?.max(0) as u64,
+                        token_out: row.get::<_, i64>(2)?.max(0) as u64,
+                        cost: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(totals)
+    }
+
+    // ── Tasks ───────────────────────────────────────────────────────────
+
+    fn row_to_task(row: &rusqlite::Row) -> Result<Task> {
+        let source_connector_id: Option<String> = row.get(7)?;
+        let source_item_id: Option<String> = row.get(8)?;
+        Ok(Task {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            title: row.get(2)?,
+            description: row.get(3)?,
+            column: TaskColumn::parse(&row.get::<_, String>(4)?).unwrap_or(TaskColumn::Backlog),
+            position: row.get::<_, i64>(5)?.max(0) as u32,
+            assigned_agent_id: row.get(6)?,
+            source_item: source_connector_id
+                .zip(source_item_id)
+                .map(|(connector_id, item_id)| SourceItemRef {
+                    connector_id,
+                    item_id,
+                }),
+            source_status: row
+                .get::<_, Option<String>>(13)?
+                .and_then(|status| serde_json::from_str(&status).ok()),
+            sync_conflict: row
+                .get::<_, Option<String>>(14)?
+                .and_then(|status| serde_json::from_str(&status).ok()),
+            instruction_message_id: row.get(9)?,
+            run_id: row.get(10)?,
+            run_status: None,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(11)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        })
+    }
+
+    pub fn save_task(&self, task: &Task) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO tasks (id, project_id, title, description, board_column, position, assigned_agent_id, source_connector_id, source_item_id, instruction_message_id, run_id, created_at, updated_at, source_status, sync_conflict)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                task.id,
+                task.project_id,
+                task.title,
+                task.description,
+                task.column.as_str(),
+                task.position as i64,
+                task.assigned_agent_id,
+                task.source_item.as_ref().map(|item| &item.connector_id),
+                task.source_item.as_ref().map(|item| &item.item_id),
+                task.instruction_message_id,
+                task.run_id,
+                task.created_at.to_rfc3339(),
+                task.updated_at.to_rfc3339(),
+                task.source_status
+                    .as_ref()
+                    .map(|status| serde_json::to_string(status).unwrap()),
+                task.sync_conflict
+                    .as_ref()
+                    .map(|status| serde_json::to_string(status).unwrap()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_task(&self, task_id: &str) -> Result<Option<Task>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, project_id, title, description, board_column, position, assigned_agent_id, source_connector_id, source_item_id, instruction_message_id, run_id, created_at, updated_at, source_status, sync_conflict
+             FROM tasks WHERE id = ?1",
+            params![task_id],
+            Self::row_to_task,
+        )
+        .optional()
+    }
+
+    /// A project's cards in board order: by column, then position.
+    pub fn list_tasks(&self, project_id: &str) -> Result<Vec<Task>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, title, description, board_column, position, assigned_agent_id, source_connector_id, source_item_id, instruction_message_id, run_id, created_at, updated_at, source_status, sync_conflict
+             FROM tasks WHERE project_id = ?1
+             ORDER BY position ASC, created_at ASC",
+        )?;
+        let mut tasks = stmt
+            .query_map(params![project_id], Self::row_to_task)?
+            .collect::<Result<Vec<_>>>()?;
+        tasks.sort_by_key(|task| {
+            TaskColumn::ALL
+                .iter()
+                .position(|column| *column == task.column)
+        });
+        Ok(tasks)
+    }
+
+    /// Cards linked to items of one connector, across projects.
+    pub fn list_tasks_for_connector(&self, connector_id: &str) -> Result<Vec<Task>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, title, description, board_column, position, assigned_agent_id, source_connector_id, source_item_id, instruction_message_id, run_id, created_at, updated_at, source_status, sync_conflict
+             FROM tasks WHERE source_connector_id = ?1
+             ORDER BY created_at ASC",
+        )?;
+        let tasks = stmt
+            .query_map(params![connector_id], Self::row_to_task)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(tasks)
+    }
+
+    pub fn delete_task(&self, task_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
+        Ok(())
+    }
+
+    // ── Attention state ─────────────────────────────────────────────────
+
+    pub fn dismiss_attention(
+        &self,
+        agent_id: &str,
+        reason: &str,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO attention_state (agent_id, reason, dismissed_at, snoozed_until)
+             VALUES (?1, ?2, ?3, NULL)
+             ON CONFLICT(agent_id, reason)
+             DO UPDATE SET dismissed_at = excluded.dismissed_at, snoozed_until = NULL",
+            params![agent_id, reason, at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn snooze_attention(
+        &self,
+        agent_id: &str,
+        reason: &str,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO attention_state (agent_id, reason, dismissed_at, snoozed_until)
+             VALUES (?1, ?2, NULL, ?3)
+             ON CONFLICT(agent_id, reason) DO UPDATE SET snoozed_until = excluded.snoozed_until",
+            params![agent_id, reason, until.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_attention_states(&self) -> Result<HashMap<(String, String), AttentionState>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT agent_id, reason, dismissed_at, snoozed_until FROM attention_state")?;
+        let parse = |raw: Option<String>| {
+            raw.and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+        let states = stmt
+            .query_map([], |row| {
+                let state = AttentionState {
+                    agent_id: row.get(0)?,
+                    reason: row.get(1)?,
+                    dismissed_at: parse(row.get(2)?),
+                    snoozed_until: parse(row.get(3)?),
+                };
+                Ok(((state.agent_id.clone(), state.reason.clone()), state))
+            })?
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(states)
+    }
+
+    // ── Daily stats ─────────────────────────────────────────────────────
+
+    /// Runs overlapping [since, until), without outputs or git snapshots.
+    pub fn get_runs_overlapping(&self, since: &str, until: &str) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_SUMMARY_COLUMNS}
+             FROM runs
+             WHERE started_at < ?2 AND (ended_at IS NULL OR ended_at >= ?1)"
+        ))?;
+        let runs = stmt
+            .query_map(params![since, until], Self::row_to_run)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
     }
 
-    pub fn finalize_latest_run(
-        &self,
-        agent_id: &str,
-        status: RunStatus,
-        summary: Option<String>,
-    ) -> Result<Option<Run>> {
-        if let Some(mut run) = self.get_latest_run_for_agent(agent_id)? {
-            if run.status == RunStatus::InProgress && run.ended_at.is_none() {
-                run.status = status;
-                run.ended_at = Some(chrono::Utc::now());
-                if let Some(summary) = summary {
-                    if !summary.trim().is_empty() {
-                        run.summary = Some(summary);
-                    }
-                }
-                self.update_run(&run)?;
-                return Ok(Some(run));
-            }
-            return Ok(Some(run));
-        }
+    /// Messages created in [since, until), not counting heartbeats.
+    pub fn count_messages_between(&self, since: &str, until: &str) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM messages
+             WHERE created_at >= ?1 AND created_at < ?2 AND kind != ?3",
+            params![
+                since,
+                until,
+                serde_json::to_string(&MessageKind::Heartbeat).unwrap()
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(count.max(0) as u64)
+    }
 
-        // If no run exists yet, create a terminal run entry to preserve traceability.
-        let run = Run {
-            id: Uuid::new_v4().to_string(),
-            agent_id: agent_id.to_string(),
-            status,
-            started_at: chrono::Utc::now(),
-            ended_at: Some(chrono::Utc::now()),
-            summary,
-            outputs: vec![],
-            file_changes: vec![],
-        };
-        self.create_run(&run)?;
-        Ok(Some(run))
+    pub fn earliest_run_started_at(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let conn = self.conn.lock().unwrap();
+        let raw: Option<String> =
+            conn.query_row("SELECT MIN(started_at) FROM runs", [], |row| row.get(0))?;
+        Ok(raw
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|t| t.with_timezone(&chrono::Utc)))
     }
 
-    pub fn get_latest_run_for_agent(&self, agent_id: &str) -> Result<Option<Run>> {
+    pub fn latest_daily_stats_day(&self) -> Result<Option<chrono::NaiveDate>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, agent_id, status, started_at, ended_at, summary, outputs, file_changes
-             FROM runs WHERE agent_id = ?1 ORDER BY started_at DESC LIMIT 1",
-        )?;
-        let mut runs = stmt.query_map(params![agent_id], |row| {
-            Ok(Run {
-                id: row.get(0)?,
-                agent_id: row.get(1)?,
-                status: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
-                started_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                ended_at: row
-                    .get::<_, Option<String>>(4)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|t| t.with_timezone(&chrono::Utc)),
-                summary: row.get(5)?,
-                outputs: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
-                file_changes: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
-            })
-        })?;
-        Ok(runs.next().transpose()?)
+        let raw: Option<String> =
+            conn.query_row("SELECT MAX(day) FROM daily_stats", [], |row| row.get(0))?;
+        Ok(raw.and_then(|day| day.parse().ok()))
     }
 
-    pub fn get_runs_for_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<Run>> {
+    pub fn upsert_daily_stats(&self, stats: &crate::daily_stats::DailyStats) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO daily_stats
+                (day, runs_completed, runs_failed, files_changed, messages, cost, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                stats.day.to_string(),
+                stats.runs_completed as i64,
+                stats.runs_failed as i64,
+                stats.files_changed as i64,
+                stats.messages as i64,
+                stats.cost,
+                self.now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Recorded days in [since, until], oldest first.
+    pub fn get_daily_stats(
+        &self,
+        since: chrono::NaiveDate,
+        until: chrono::NaiveDate,
+    ) -> Result<Vec<crate::daily_stats::DailyStats>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, agent_id, status, started_at, ended_at, summary, outputs, file_changes
-             FROM runs WHERE agent_id = ?1 ORDER BY started_at DESC LIMIT ?2",
+            "SELECT day, runs_completed, runs_failed, files_changed, messages, cost
+             FROM daily_stats
+             WHERE day >= ?1 AND day <= ?2
+             ORDER BY day ASC",
         )?;
-        let runs = stmt
-            .query_map(params![agent_id, limit], |row| {
-                Ok(Run {
-                    id: row.get(0)?,
-                    agent_id: row.get(1)?,
-                    status: serde_json::from_str(&row.get::<_, String>(2)?).unwrap(),
-                    started_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    ended_at: row
-                        .get::<_, Option<String>>(4)?
-                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|t| t.with_timezone(&chrono::Utc)),
-                    summary: row.get(5)?,
-                    outputs: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
-                    file_changes: serde_json::from_str(&row.get::<_, String>(7)?)
-                        .unwrap_or_default(),
+        let rows = stmt
+            .query_map(params![since.to_string(), until.to_string()], |row| {
+                let day = row.get::<_, String>(0)?;
+                Ok(crate::daily_stats::DailyStats {
+                    day: day.parse().map_err(|error| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            0,
+                            rusqlite::types::Type::Text,
+                            Box::new(error),
+                        )
+                    })?,
+                    runs_completed: row.get::<_, i64>(1)?.max(0) as u64,
+                    runs_failed: row.get::<_, i64>(2)?.max(0) as u64,
+                    files_changed: row.get::<_, i64>(3)?.max(0) as u64,
+                    messages: row.get::<_, i64>(4)?.max(0) as u64,
+                    cost: row.get(5)?,
                 })
             })?
             .collect::<Result<Vec<_>>>()?;
-        Ok(runs)
+        Ok(rows)
+    }
+
+    /// Whether a watched file change should be treated as the agent working.
+    /// Edits only count while the agent has an active run, unless it runs
+    /// autonomously, in which case a change may be the first sign of a run.
+    pub fn file_change_is_agent_activity(&self, agent_id: &str) -> Result<bool> {
+        if !self.get_active_runs(agent_id)?.is_empty() {
+            return Ok(true);
+        }
+        Ok(self
+            .get_agent_config(agent_id)?
+            .is_some_and(|config| matches!(config.autonomy_level, AutonomyLevel::Autonomous)))
     }
 
     pub fn record_file_change(&self, agent_id: &str, change: FileChange) -> Result<Run> {
-        if let Some(mut run) = self.get_latest_run_for_agent(agent_id)? {
-            if run.status == RunStatus::InProgress && run.ended_at.is_none() {
-                run.file_changes.push(change);
-                run.summary = Some(format!("{} file changes detected", run.file_changes.len()));
-                self.update_run(&run)?;
-                return Ok(run);
-            }
+        if let Some(mut run) = self.get_active_runs(agent_id)?.into_iter().next() {
+            run.file_changes.push(change);
+            run.summary = Some(format!("{} file changes detected", run.file_changes.len()));
+            self.update_run(&run)?;
+            return Ok(run);
         }
 
-        let run = Run {
-            id: Uuid::new_v4().to_string(),
-            agent_id: agent_id.to_string(),
-            status: RunStatus::InProgress,
-            started_at: chrono::Utc::now(),
-            ended_at: None,
-            summary: Some("File changes detected".to_string()),
-            outputs: vec![],
-            file_changes: vec![change],
-        };
+        let mut run = self.new_run(
+            agent_id,
+            RunStatus::InProgress,
+            Some("File changes detected".to_string()),
+        );
+        run.file_changes.push(change);
         self.create_run(&run)?;
         Ok(run)
     }
@@ -842,30 +4062,105 @@ impl Database {
                 .get::<_, Option<String>>(9)?
                 .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
                 .map(|t| t.with_timezone(&chrono::Utc)),
+            expires_at: row
+                .get::<_, Option<String>>(10)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
         })
     }
 
+    /// Persist a message. Inbound (agent → kanbun) content passes through the
+    /// redactor; outbound messages are stored verbatim because adapters deliver
+    /// them from this table.
     pub fn insert_message(&self, msg: &Message) -> Result<()> {
+        let content = self.stored_message_content(msg);
         let conn = self.conn.lock().unwrap();
+        Self::insert_message_row(&conn, msg, &content)?;
+        drop(conn);
+        self.message_inserted(msg, &content)
+    }
+
+    /// Inbound content is redacted before it's stored; instructions are kept
+    /// as written.
+    fn stored_message_content(&self, msg: &Message) -> String {
+        match msg.direction {
+            MessageDirection::FromAgent => self.redact(&msg.content),
+            MessageDirection::ToAgent => msg.content.clone(),
+        }
+    }
+
+    fn insert_message_row(conn: &Connection, msg: &Message, content: &str) -> Result<()> {
         conn.execute(
-            "INSERT INTO messages (id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT INTO messages (id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 msg.id,
                 msg.agent_id,
                 serde_json::to_string(&msg.direction).unwrap(),
                 serde_json::to_string(&msg.kind).unwrap(),
-                msg.content,
+                content,
                 msg.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap()),
                 msg.reply_to,
                 msg.created_at.to_rfc3339(),
                 msg.delivered_at.map(|t| t.to_rfc3339()),
                 msg.acknowledged_at.map(|t| t.to_rfc3339()),
+                msg.expires_at.map(|t| t.to_rfc3339()),
             ],
         )?;
         Ok(())
     }
 
+    /// Follow-up of a stored message: the bus log line, waking the adapter
+    /// loop, and usage rollup.
+    fn message_inserted(&self, msg: &Message, content: &str) -> Result<()> {
+        // Lands in the agent's log file.
+        let mut preview: String = content.chars().take(BUS_LOG_PREVIEW_CHARS).collect();
+        if preview.len() < content.len() {
+            preview.push('…');
+        }
+        tracing::debug!(
+            target: "kanbun::bus",
+            agent_id = msg.agent_id.as_str(),
+            message_id = msg.id.as_str(),
+            direction = match msg.direction {
+                MessageDirection::ToAgent => "to_agent",
+                MessageDirection::FromAgent => "from_agent",
+            },
+            kind = msg.kind.as_str(),
+            "{}",
+            preview
+        );
+
+        if msg.direction == MessageDirection::ToAgent && !msg.is_awaiting_approval() {
+            self.signal_message_queued(&msg.agent_id);
+        }
+
+        // Usage convention: inbound messages may report `metadata.usage`,
+        // which is rolled up onto the agent's current run.
+        if msg.direction == MessageDirection::FromAgent {
+            if let Some(usage) = msg
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("usage"))
+                .and_then(TokenUsage::from_json)
+            {
+                self.record_run_usage(&msg.agent_id, msg.run_key(), &usage)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_message(&self, message_id: &str) -> Result<Option<Message>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages WHERE id = ?1",
+            params![message_id],
+            Self::row_to_message,
+        )
+        .optional()
+    }
+
     /// Get conversation thread for an agent (most recent messages first)
     pub fn get_messages_for_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<Message>> {
         self.get_messages_for_agent_before(agent_id, limit, None)
@@ -885,7 +4180,7 @@ impl Database {
             before_created_at.filter(|value| !value.trim().is_empty())
         {
             let mut stmt = conn.prepare(
-                "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at
+                "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
                  FROM messages
                  WHERE agent_id = ?1 AND created_at < ?2
                  ORDER BY created_at DESC
@@ -895,7 +4190,7 @@ impl Database {
             rows.collect::<Result<Vec<_>>>()?
         } else {
             let mut stmt = conn.prepare(
-                "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at
+                "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
                  FROM messages
                  WHERE agent_id = ?1
                  ORDER BY created_at DESC
@@ -908,14 +4203,196 @@ impl Database {
         Ok(messages)
     }
 
+    /// Messages across agents for the activity feed, newest first, skipping
+    /// heartbeats. Optionally narrowed to a project or agent.
+    pub fn get_feed_messages(
+        &self,
+        project_id: Option<&str>,
+        agent_id: Option<&str>,
+        before: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.agent_id, m.direction, m.kind, m.content, m.metadata, m.reply_to, m.created_at, m.delivered_at, m.acknowledged_at, m.expires_at
+             FROM messages m
+             JOIN agents a ON a.id = m.agent_id
+             WHERE m.kind != ?1
+               AND (?2 IS NULL OR a.project_id = ?2)
+               AND (?3 IS NULL OR m.agent_id = ?3)
+               AND (?4 IS NULL OR m.created_at < ?4)
+             ORDER BY m.created_at DESC
+             LIMIT ?5",
+        )?;
+        let heartbeat = serde_json::to_string(&MessageKind::Heartbeat).unwrap();
+        let messages = stmt
+            .query_map(
+                params![heartbeat, project_id, agent_id, before, limit],
+                Self::row_to_message,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Runs that started or ended before `before`, most recent transition
+    /// first, without outputs or git snapshots. Starts and ends are paged
+    /// separately, each by its own timestamp, so the newest `limit` of either
+    /// are always here, including a run that started before `before` but
+    /// ended after it.
+    pub fn get_feed_runs(
+        &self,
+        project_id: Option<&str>,
+        agent_id: Option<&str>,
+        before: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "WITH scoped AS (
+                 SELECT id, started_at, ended_at FROM runs
+                 WHERE agent_id IN (
+                     SELECT id FROM agents WHERE ?1 IS NULL OR project_id = ?1
+                 )
+                   AND (?2 IS NULL OR agent_id = ?2)
+             ),
+             starts AS (
+                 SELECT id FROM scoped
+                 WHERE ?3 IS NULL OR started_at < ?3
+                 ORDER BY started_at DESC
+                 LIMIT ?4
+             ),
+             ends AS (
+                 SELECT id FROM scoped
+                 WHERE ended_at IS NOT NULL AND (?3 IS NULL OR ended_at < ?3)
+                 ORDER BY ended_at DESC
+                 LIMIT ?4
+             )
+             SELECT {RUN_SUMMARY_COLUMNS}
+             FROM runs
+             WHERE id IN (SELECT id FROM starts UNION SELECT id FROM ends)
+             ORDER BY COALESCE(ended_at, started_at) DESC"
+        ))?;
+        let runs = stmt
+            .query_map(
+                params![project_id, agent_id, before, limit],
+                Self::row_to_run,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    /// Get an agent's messages within an optional [since, until] window, oldest first.
+    pub fn get_messages_for_agent_between(
+        &self,
+        agent_id: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Vec<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages
+             WHERE agent_id = ?1
+               AND (?2 IS NULL OR created_at >= ?2)
+               AND (?3 IS NULL OR created_at <= ?3)
+             ORDER BY created_at ASC"
+        )?;
+        let messages = stmt
+            .query_map(params![agent_id, since, until], Self::row_to_message)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
     /// Get pending outbound messages that haven't been delivered to the agent yet.
-    /// Adapters poll this to pick up new instructions.
+    /// Adapters poll this to pick up new instructions. Messages held for
+    /// approval stay out until approved.
+    /// Messages from any agent created after `since`, oldest first, for
+    /// notification dispatch. Heartbeats are skipped.
+    pub fn get_agent_messages_since(&self, since: &str) -> Result<Vec<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages
+             WHERE direction = '\"from_agent\"' AND kind != ?1 AND created_at > ?2
+             ORDER BY created_at ASC",
+        )?;
+        let heartbeat = serde_json::to_string(&MessageKind::Heartbeat).unwrap();
+        let messages = stmt
+            .query_map(params![heartbeat, since], Self::row_to_message)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
     pub fn get_pending_messages(&self, agent_id: &str) -> Result<Vec<Message>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
              FROM messages
              WHERE agent_id = ?1 AND direction = '\"to_agent\"' AND delivered_at IS NULL
+               AND (expires_at IS NULL OR expires_at > ?2)
+               AND COALESCE(json_extract(metadata, '$.awaiting_approval'), 0) = 0
+             ORDER BY created_at ASC"
+        )?;
+        let messages = stmt
+            .query_map(
+                params![agent_id, self.now().to_rfc3339()],
+                Self::row_to_message,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Take undelivered outbound messages whose TTL has passed out of the queue.
+    /// They are marked delivered with `metadata.expired = true` so they never
+    /// reach the adapter, and returned so callers can report the expiry.
+    pub fn expire_pending_messages(&self, agent_id: &str) -> Result<Vec<Message>> {
+        let now = self.now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages
+             WHERE agent_id = ?1 AND direction = '\"to_agent\"' AND delivered_at IS NULL
+               AND expires_at IS NOT NULL AND expires_at <= ?2
+             ORDER BY created_at ASC"
+        )?;
+        let expired = stmt
+            .query_map(params![agent_id, now], Self::row_to_message)?
+            .collect::<Result<Vec<_>>>()?;
+
+        for message in &expired {
+            conn.execute(
+                "UPDATE messages
+                 SET delivered_at = ?1,
+                     metadata = json_set(COALESCE(metadata, '{}'), '$.expired', json('true'))
+                 WHERE id = ?2",
+                params![now, message.id],
+            )?;
+        }
+        Ok(expired)
+    }
+
+    /// Outbound messages held for operator approval, oldest first.
+    /// Undelivered, unexpired instructions across all agents, excluding
+    /// those held for approval.
+    pub fn count_pending_messages(&self) -> Result<u32> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM messages
+             WHERE direction = '\"to_agent\"' AND delivered_at IS NULL
+               AND (expires_at IS NULL OR expires_at > ?1)
+               AND COALESCE(json_extract(metadata, '$.awaiting_approval'), 0) = 0",
+            params![self.now().to_rfc3339()],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_messages_awaiting_approval(&self, agent_id: Option<&str>) -> Result<Vec<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages
+             WHERE (?1 IS NULL OR agent_id = ?1) AND direction = '\"to_agent\"' AND delivered_at IS NULL
+               AND json_extract(metadata, '$.awaiting_approval') = 1
              ORDER BY created_at ASC"
         )?;
         let messages = stmt
@@ -924,12 +4401,99 @@ impl Database {
         Ok(messages)
     }
 
+    /// Release a held message to the queue. Returns None if it isn't held.
+    pub fn approve_held_message(&self, message_id: &str) -> Result<Option<Message>> {
+        let approved = self.resolve_held_message(
+            message_id,
+            "UPDATE messages
+             SET metadata = json_set(json_remove(metadata, '$.awaiting_approval'), '$.approved_at', ?1)
+             WHERE id = ?2",
+        )?;
+        if let Some(message) = &approved {
+            self.signal_message_queued(&message.agent_id);
+        }
+        Ok(approved)
+    }
+
+    /// Drop a held message without delivering it, recorded as
+    /// `metadata.rejected = true`. Returns None if it isn't held.
+    pub fn reject_held_message(&self, message_id: &str) -> Result<Option<Message>> {
+        self.resolve_held_message(
+            message_id,
+            "UPDATE messages
+             SET delivered_at = ?1,
+                 metadata = json_set(json_remove(metadata, '$.awaiting_approval'), '$.rejected', json('true'))
+             WHERE id = ?2",
+        )
+    }
+
+    fn resolve_held_message(&self, message_id: &str, update: &str) -> Result<Option<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let select = "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages WHERE id = ?1";
+        let held = conn
+            .query_row(select, params![message_id], Self::row_to_message)
+            .optional()?
+            .filter(|message| {
+                message.delivered_at.is_none()
+                    && message
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.get("awaiting_approval"))
+                        .and_then(serde_json::Value::as_bool)
+                        .unwrap_or(false)
+            });
+        if held.is_none() {
+            return Ok(None);
+        }
+        conn.execute(update, params![self.now().to_rfc3339(), message_id])?;
+        conn.query_row(select, params![message_id], Self::row_to_message)
+            .optional()
+    }
+
+    /// Instructions sent by a pipeline, oldest first.
+    pub fn get_pipeline_messages(&self, pipeline_id: &str) -> Result<Vec<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages
+             WHERE direction = '\"to_agent\"' AND json_extract(metadata, '$.pipeline_id') = ?1
+             ORDER BY created_at ASC"
+        )?;
+        let messages = stmt
+            .query_map(params![pipeline_id], Self::row_to_message)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// The instruction an agent was most recently given, skipping ones that
+    /// expired or were rejected before reaching it.
+    pub fn get_last_delivered_instruction(&self, agent_id: &str) -> Result<Option<Message>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, agent_id, direction, kind, content, metadata, reply_to, created_at, delivered_at, acknowledged_at, expires_at
+             FROM messages
+             WHERE agent_id = ?1 AND direction = '\"to_agent\"' AND kind = ?2
+               AND delivered_at IS NOT NULL
+               AND COALESCE(json_extract(metadata, '$.expired'), 0) = 0
+               AND COALESCE(json_extract(metadata, '$.rejected'), 0) = 0
+             ORDER BY delivered_at DESC, created_at DESC
+             LIMIT 1",
+            params![
+                agent_id,
+                serde_json::to_string(&MessageKind::Instruction).unwrap()
+            ],
+            Self::row_to_message,
+        )
+        .optional()
+    }
+
     /// Mark a message as delivered (adapter picked it up)
     pub fn mark_delivered(&self, message_id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "UPDATE messages SET delivered_at = ?1 WHERE id = ?2",
-            params![chrono::Utc::now().to_rfc3339(), message_id],
+            params![self.now().to_rfc3339(), message_id],
         )?;
         Ok(())
     }
@@ -939,7 +4503,7 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "UPDATE messages SET acknowledged_at = ?1 WHERE id = ?2",
-            params![chrono::Utc::now().to_rfc3339(), message_id],
+            params![self.now().to_rfc3339(), message_id],
         )?;
         Ok(())
     }
@@ -963,26 +4527,43 @@ impl Database {
         Ok(())
     }
 
+    fn row_to_adapter_config(row: &rusqlite::Row) -> rusqlite::Result<AdapterConfig> {
+        Ok(AdapterConfig {
+            adapter_type: serde_json::from_str(&row.get::<_, String>(0)?).unwrap(),
+            session_name: row.get(1)?,
+            endpoint: row.get(2)?,
+            command: row.get(3)?,
+            env: row
+                .get::<_, Option<String>>(4)?
+                .and_then(|s| serde_json::from_str(&s).ok()),
+        })
+    }
+
     pub fn get_adapter_config(&self, agent_id: &str) -> Result<Option<AdapterConfig>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT adapter_type, session_name, endpoint, command, env
              FROM adapter_configs WHERE agent_id = ?1",
         )?;
-        let mut configs = stmt.query_map(params![agent_id], |row| {
-            Ok(AdapterConfig {
-                adapter_type: serde_json::from_str(&row.get::<_, String>(0)?).unwrap(),
-                session_name: row.get(1)?,
-                endpoint: row.get(2)?,
-                command: row.get(3)?,
-                env: row
-                    .get::<_, Option<String>>(4)?
-                    .and_then(|s| serde_json::from_str(&s).ok()),
-            })
-        })?;
+        let mut configs = stmt.query_map(params![agent_id], Self::row_to_adapter_config)?;
         Ok(configs.next().transpose()?)
     }
 
+    /// Every adapter config, keyed by agent id.
+    pub fn list_adapter_configs(&self) -> Result<HashMap<String, AdapterConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT adapter_type, session_name, endpoint, command, env, agent_id
+             FROM adapter_configs",
+        )?;
+        let configs = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(5)?, Self::row_to_adapter_config(row)?))
+            })?
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(configs)
+    }
+
     // ── Connector Configs ───────────────────────────────────────────────
 
     pub fn save_connector_config(&self, config: &crate::connectors::ConnectorConfig) -> Result<()> {
@@ -1047,13 +4628,14 @@ impl Database {
         items: &[crate::connectors::ConnectorItem],
     ) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let now = chrono::Utc::now().to_rfc3339();
+        let now = self.now().to_rfc3339();
 
         for item in items {
+            let raw_payload = item.raw.as_ref().map(gzip_json).transpose()?;
             conn.execute(
                 "INSERT OR REPLACE INTO connector_items
-                 (id, connector_id, source, title, content, status, priority, tags, url, parent_id, metadata, created_at, updated_at, due_at, synced_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                 (id, connector_id, source, title, content, status, priority, tags, url, parent_id, metadata, created_at, updated_at, due_at, synced_at, raw_payload)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
                 params![
                     item.id,
                     connector_id,
@@ -1070,6 +4652,7 @@ impl Database {
                     item.updated_at.map(|t| t.to_rfc3339()),
                     item.due_at.map(|t| t.to_rfc3339()),
                     now,
+                    raw_payload,
                 ],
             )?;
         }
@@ -1083,6 +4666,37 @@ impl Database {
         Ok(())
     }
 
+    fn row_to_connector_item(
+        row: &rusqlite::Row,
+    ) -> rusqlite::Result<crate::connectors::ConnectorItem> {
+        Ok(crate::connectors::ConnectorItem {
+            id: row.get(0)?,
+            source: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            status: serde_json::from_str(&row.get::<_, String>(4)?)
+                .unwrap_or(crate::connectors::ItemStatus::Active),
+            priority: row.get(5)?,
+            tags: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+            url: row.get(7)?,
+            parent_id: row.get(8)?,
+            metadata: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+            created_at: row
+                .get::<_, Option<String>>(10)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            updated_at: row
+                .get::<_, Option<String>>(11)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            due_at: row
+                .get::<_, Option<String>>(12)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            raw: None,
+        })
+    }
+
     pub fn get_connector_items(
         &self,
         connector_id: &str,
@@ -1093,37 +4707,119 @@ impl Database {
              FROM connector_items WHERE connector_id = ?1 ORDER BY due_at ASC NULLS LAST, updated_at DESC"
         )?;
         let items = stmt
-            .query_map(params![connector_id], |row| {
-                Ok(crate::connectors::ConnectorItem {
-                    id: row.get(0)?,
-                    source: row.get(1)?,
-                    title: row.get(2)?,
-                    content: row.get(3)?,
-                    status: serde_json::from_str(&row.get::<_, String>(4)?)
-                        .unwrap_or(crate::connectors::ItemStatus::Active),
-                    priority: row.get(5)?,
-                    tags: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
-                    url: row.get(7)?,
-                    parent_id: row.get(8)?,
-                    metadata: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
-                    created_at: row
-                        .get::<_, Option<String>>(10)?
-                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|t| t.with_timezone(&chrono::Utc)),
-                    updated_at: row
-                        .get::<_, Option<String>>(11)?
-                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|t| t.with_timezone(&chrono::Utc)),
-                    due_at: row
-                        .get::<_, Option<String>>(12)?
-                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|t| t.with_timezone(&chrono::Utc)),
-                })
-            })?
+            .query_map(params![connector_id], Self::row_to_connector_item)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(items)
+    }
+
+    /// Open connector items due before `until`, with their connector id.
+    pub fn get_open_items_due_before(
+        &self,
+        until: &str,
+    ) -> Result<Vec<(String, crate::connectors::ConnectorItem)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, source, title, content, status, priority, tags, url, parent_id, metadata, created_at, updated_at, due_at, connector_id
+             FROM connector_items
+             WHERE due_at IS NOT NULL AND due_at < ?1 AND status NOT IN (?2, ?3)
+             ORDER BY due_at ASC",
+        )?;
+        let items = stmt
+            .query_map(
+                params![
+                    until,
+                    serde_json::to_string(&crate::connectors::ItemStatus::Completed).unwrap(),
+                    serde_json::to_string(&crate::connectors::ItemStatus::Archived).unwrap(),
+                ],
+                |row| Ok((row.get::<_, String>(13)?, Self::row_to_connector_item(row)?)),
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(items)
+    }
+
+    /// Connector items marked completed in [since, until) that were the
+    /// source of a run by one of the project's agents.
+    pub fn get_items_closed_for_project(
+        &self,
+        project_id: &str,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<(String, crate::connectors::ConnectorItem)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT ci.id, ci.source, ci.title, ci.content, ci.status, ci.priority, ci.tags, ci.url, ci.parent_id, ci.metadata, ci.created_at, ci.updated_at, ci.due_at, ci.connector_id
+             FROM connector_items ci
+             JOIN runs r ON r.source_connector_id = ci.connector_id AND r.source_item_id = ci.id
+             JOIN agents a ON a.id = r.agent_id
+             WHERE a.project_id = ?1 AND ci.status = ?2
+               AND COALESCE(ci.updated_at, ci.synced_at) >= ?3
+               AND COALESCE(ci.updated_at, ci.synced_at) < ?4
+             ORDER BY ci.title ASC",
+        )?;
+        let items = stmt
+            .query_map(
+                params![
+                    project_id,
+                    serde_json::to_string(&crate::connectors::ItemStatus::Completed).unwrap(),
+                    since,
+                    until,
+                ],
+                |row| Ok((row.get::<_, String>(13)?, Self::row_to_connector_item(row)?)),
+            )?
             .collect::<Result<Vec<_>>>()?;
         Ok(items)
     }
 
+    pub fn get_connector_item(
+        &self,
+        connector_id: &str,
+        item_id: &str,
+    ) -> Result<Option<crate::connectors::ConnectorItem>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, source, title, content, status, priority, tags, url, parent_id, metadata, created_at, updated_at, due_at
+             FROM connector_items WHERE connector_id = ?1 AND id = ?2",
+            params![connector_id, item_id],
+            Self::row_to_connector_item,
+        )
+        .optional()
+    }
+
+    /// A cached item with the upstream JSON it was mapped from, if the
+    /// connector kept it, and when it was last synced.
+    pub fn get_connector_item_detail(
+        &self,
+        connector_id: &str,
+        item_id: &str,
+    ) -> Result<Option<crate::connectors::ConnectorItemDetail>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT id, source, title, content, status, priority, tags, url, parent_id, metadata, created_at, updated_at, due_at, synced_at, raw_payload
+                 FROM connector_items WHERE connector_id = ?1 AND id = ?2",
+                params![connector_id, item_id],
+                |row| {
+                    Ok((
+                        Self::row_to_connector_item(row)?,
+                        row.get::<_, String>(13)?,
+                        row.get::<_, Option<Vec<u8>>>(14)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((item, synced_at, raw_payload)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(crate::connectors::ConnectorItemDetail {
+            connector_id: connector_id.to_string(),
+            item,
+            raw: raw_payload.as_deref().map(gunzip_json).transpose()?,
+            synced_at: chrono::DateTime::parse_from_rfc3339(&synced_at)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .ok(),
+        }))
+    }
+
     pub fn delete_connector_item(&self, connector_id: &str, item_id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// ── Slow query log ──────────────────────────────────────────────────────────
+// SQLite's profile hook reports every statement with its run time. Anything
+// over SLOW_QUERY_THRESHOLD goes into a ring of the most recent slow
+// statements for `get_diagnostics`; everything else returns straight away.
+// The hook takes a plain fn, so the ring is process-wide rather than per
+// connection.
+
+const SLOW_QUERY_THRESHOLD: Duration = Duration::from_millis(5);
+const SLOW_QUERY_CAPACITY: usize = 200;
+const SQL_PREVIEW_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    pub sql: String, // whitespace collapsed, truncated
+    pub duration_ms: f64,
+    pub at: DateTime<Utc>,
+}
+
+fn slow_queries() -> &'static Mutex<VecDeque<SlowQuery>> {
+    static SLOW_QUERIES: OnceLock<Mutex<VecDeque<SlowQuery>>> = OnceLock::new();
+    SLOW_QUERIES.get_or_init(|| Mutex::new(VecDeque::with_capacity(SLOW_QUERY_CAPACITY)))
+}
+
+/// Profile callback installed on each connection.
+pub(super) fn record(sql: &str, duration: Duration) {
+    if duration < SLOW_QUERY_THRESHOLD {
+        return;
+    }
+    let sql: String = sql
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SQL_PREVIEW_CHARS)
+        .collect();
+    let Ok(mut queries) = slow_queries().lock() else {
+        return;
+    };
+    if queries.len() == SLOW_QUERY_CAPACITY {
+        queries.pop_front();
+    }
+    queries.push_back(SlowQuery {
+        sql,
+        duration_ms: duration.as_secs_f64() * 1000.0,
+        at: Utc::now(),
+    });
+}
+
+/// The slowest of the recently logged statements, slowest first.
+pub fn slowest(limit: usize) -> Vec<SlowQuery> {
+    let mut queries: Vec<SlowQuery> = slow_queries()
+        .lock()
+        .map(|queries| queries.iter().cloned().collect())
+        .unwrap_or_default();
+    queries.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+    queries.truncate(limit);
+    queries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_slow_statements_are_kept() {
+        record("SELECT 'fast-statement'", Duration::from_micros(50));
+        record(
+            "SELECT *\n   FROM   runs WHERE 'slow-statement' = ?1",
+            Duration::from_millis(40),
+        );
+
+        let logged = slowest(SLOW_QUERY_CAPACITY);
+        assert!(!logged
+            .iter()
+            .any(|query| query.sql.contains("fast-statement")));
+        let slow = logged
+            .iter()
+            .find(|query| query.sql.contains("slow-statement"))
+            .expect("slow statement should be logged");
+        assert_eq!(slow.sql, "SELECT * FROM runs WHERE 'slow-statement' = ?1");
+        assert!(slow.duration_ms >= 40.0);
+    }
+}
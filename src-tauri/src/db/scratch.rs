@@ -0,0 +1,74 @@
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+// ── Scratch files ───────────────────────────────────────────────────────────
+// A snapshot on its way into or out of encryption (or a cloud backup being
+// restored) holds every token and adapter env value in the database. It is
+// staged next to the database in the app data directory rather than in the
+// shared temp dir, created readable by this user only, and removed however
+// the operation ends (including SQLite's journal files beside it).
+
+const SIDECAR_SUFFIXES: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+pub struct ScratchFile {
+    path: PathBuf,
+}
+
+impl ScratchFile {
+    /// A new empty file in `dir`, mode 0600 on Unix.
+    pub fn create(dir: &Path, prefix: &str) -> Result<Self, String> {
+        let path = dir.join(format!(".{}-{}.tmp", prefix, uuid::Uuid::new_v4()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&path)
+            .map_err(|error| format!("failed to create {}: {}", path.display(), error))?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path as the snapshot functions take it.
+    pub fn path_str(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+        for suffix in SIDECAR_SUFFIXES {
+            let mut sidecar = self.path.clone().into_os_string();
+            sidecar.push(suffix);
+            let _ = std::fs::remove_file(sidecar);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_while_held_and_gone_once_dropped() {
+        let dir = std::env::temp_dir();
+        let scratch = ScratchFile::create(&dir, "kanbun-scratch").unwrap();
+        let path = scratch.path().to_path_buf();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::write(&path, b"secret").unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        drop(scratch);
+        assert!(!path.exists());
+    }
+}
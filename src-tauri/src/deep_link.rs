@@ -0,0 +1,380 @@
+use crate::commands;
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::intake::{self, CaptureKind};
+use crate::models::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+// ── Deep links ──────────────────────────────────────────────────────────────
+// kanbun:// URLs from notifications, chat posts and exported reports. Agent
+// and run links focus the window on that entity; send links queue an
+// instruction and capture links do what `quick_capture` does. Any web page
+// can open these, so instructions are always held for approval rather than
+// delivered straight away. Capture links may carry an x-success callback
+// (x-callback-url style) that gets the created id back to Shortcuts. Only
+// Shortcuts, Raycast and schemes named in the settings are called back.
+
+pub const SCHEME: &str = "kanbun";
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+pub const DEEP_LINK_SETTINGS_KEY: &str = "deep_links";
+const CALLBACK_SCHEMES: &[&str] = &["shortcuts", "raycast"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeepLinkSettings {
+    #[serde(default)]
+    pub callback_schemes: Vec<String>, // followed for x-success besides the built-in ones
+}
+
+impl DeepLinkSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for scheme in &self.callback_schemes {
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            if !valid {
+                return Err(format!("not a URL scheme: {}", scheme));
+            }
+            let scheme = scheme.to_ascii_lowercase();
+            if matches!(
+                scheme.as_str(),
+                "http" | "https" | "file" | "javascript" | "data" | SCHEME
+            ) {
+                return Err(format!("{} can't be used for callbacks", scheme));
+            }
+        }
+        Ok(())
+    }
+
+    fn allows(&self, scheme: &str) -> bool {
+        CALLBACK_SCHEMES.contains(&scheme)
+            || self
+                .callback_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+}
+
+pub fn agent_link(agent_id: &str) -> String {
+    format!("{}://agent/{}", SCHEME, agent_id)
+}
+
+pub fn run_link(run_id: &str) -> String {
+    format!("{}://run/{}", SCHEME, run_id)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeepLink {
+    Agent {
+        agent_id: String,
+    },
+    Run {
+        run_id: String,
+    },
+    Send {
+        agent_id: String,
+        text: String,
+    },
+    Capture {
+        text: String,
+        project: Option<String>,
+        agent: Option<String>,
+        x_success: Option<String>,
+    },
+}
+
+/// What the frontend receives on `deep-link`: the parsed link, the agent to
+/// focus, the held message for send links, and the Inbox item for captures.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkTarget {
+    pub link: DeepLink,
+    pub agent_id: Option<String>,
+    pub message_id: Option<String>,
+    pub item_id: Option<String>,
+}
+
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("invalid percent escape in {}", value))?;
+                out.push(hex);
+                i += 2;
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8(out).map_err(|_| "link is not valid UTF-8".to_string())
+}
+
+pub fn parse(url: &str) -> Result<DeepLink, String> {
+    let rest = url
+        .strip_prefix(SCHEME)
+        .and_then(|rest| rest.strip_prefix("://"))
+        .ok_or_else(|| format!("not a {}:// link: {}", SCHEME, url))?;
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((key, percent_decode(value)?))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.clone())
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    match segments.as_slice() {
+        ["agent" | "agents", id] => Ok(DeepLink::Agent {
+            agent_id: percent_decode(id)?,
+        }),
+        ["run" | "runs", id] => Ok(DeepLink::Run {
+            run_id: percent_decode(id)?,
+        }),
+        ["send"] => Ok(DeepLink::Send {
+            agent_id: param("agent").ok_or("send link needs an agent")?,
+            text: param("text").ok_or("send link needs text")?,
+        }),
+        ["capture"] => Ok(DeepLink::Capture {
+            text: param("text").ok_or("capture link needs text")?,
+            project: param("project"),
+            agent: param("agent"),
+            x_success: param("x-success"),
+        }),
+        _ => Err(format!("unsupported link: {}", url)),
+    }
+}
+
+/// Resolve a link against the database; send and capture links queue their
+/// instruction held for approval.
+pub fn resolve(db: &Arc<Database>, link: DeepLink) -> Result<DeepLinkTarget, CommandError> {
+    let agent_exists = |agent_id: &str| -> Result<(), CommandError> {
+        db.get_agent(agent_id)?
+            .map(|_| ())
+            .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))
+    };
+    let (agent_id, message_id, item_id) = match &link {
+        DeepLink::Agent { agent_id } => {
+            agent_exists(agent_id)?;
+            (Some(agent_id.clone()), None, None)
+        }
+        DeepLink::Run { run_id } => {
+            let run = db
+                .get_run(run_id)?
+                .ok_or_else(|| CommandError::not_found(format!("Run not found: {}", run_id)))?;
+            (Some(run.agent_id), None, None)
+        }
+        DeepLink::Send { agent_id, text } => {
+            agent_exists(agent_id)?;
+            let mut msg = Message::to_agent(agent_id, MessageKind::Instruction, text);
+            msg.metadata = Some(serde_json::json!({ "source": "deep_link" }));
+            let msg = commands::hold_message(db, msg)?;
+            (Some(agent_id.clone()), Some(msg.id), None)
+        }
+        DeepLink::Capture {
+            text,
+            project,
+            agent,
+            ..
+        } => {
+            let capture =
+                intake::quick_capture(db, text, project.as_deref(), agent.as_deref(), true)?;
+            match capture.kind {
+                CaptureKind::Instruction => (capture.agent_id, Some(capture.id), None),
+                CaptureKind::InboxItem => (None, None, Some(capture.id)),
+            }
+        }
+    };
+    Ok(DeepLinkTarget {
+        link,
+        agent_id,
+        message_id,
+        item_id,
+    })
+}
+
+/// The x-success callback with the created id appended, when its scheme is
+/// one `settings` allows, so a web page can't bounce through Kanbun to
+/// somewhere else.
+fn success_callback(target: &DeepLinkTarget, settings: &DeepLinkSettings) -> Option<String> {
+    let DeepLink::Capture {
+        x_success: Some(callback),
+        ..
+    } = &target.link
+    else {
+        return None;
+    };
+    let scheme = callback.split_once(':')?.0.to_ascii_lowercase();
+    if !settings.allows(&scheme) {
+        tracing::warn!("Ignoring x-success callback {}", callback);
+        return None;
+    }
+    let id = target.item_id.as_ref().or(target.message_id.as_ref())?;
+    let separator = if callback.contains('?') { '&' } else { '?' };
+    Some(format!("{}{}id={}", callback, separator, id))
+}
+
+/// Handle a URL the OS handed us: bring the window forward and tell the
+/// frontend where to go.
+pub fn handle(app: &AppHandle, db: &Arc<Database>, url: &str) {
+    let target = match parse(url)
+        .map_err(CommandError::from)
+        .and_then(|link| resolve(db, link))
+    {
+        Ok(target) => target,
+        Err(error) => {
+            tracing::warn!("Ignoring deep link {}: {}", url, error);
+            return;
+        }
+    };
+    let settings: DeepLinkSettings = db
+        .get_setting(DEEP_LINK_SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if let Some(callback) = success_callback(&target, &settings) {
+        // Shortcuts is waiting on the callback; stay in the background. The
+        // URL goes to the OS launcher (ShellExecute on Windows), not a shell.
+        if let Err(error) = open::that_detached(&callback) {
+            tracing::warn!("Failed to open x-success callback: {}", error);
+        }
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    if let Err(error) = app.emit(DEEP_LINK_EVENT, target) {
+        tracing::warn!("Failed to emit deep link event: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_links_and_holds_sent_instructions() {
+        assert_eq!(
+            parse("kanbun://agent/a1").unwrap(),
+            DeepLink::Agent {
+                agent_id: "a1".to_string()
+            }
+        );
+        assert_eq!(
+            parse("kanbun://agents/a1/").unwrap(),
+            DeepLink::Agent {
+                agent_id: "a1".to_string()
+            }
+        );
+        assert_eq!(
+            parse("kanbun://run/r1").unwrap(),
+            DeepLink::Run {
+                run_id: "r1".to_string()
+            }
+        );
+        assert_eq!(
+            parse("kanbun://send?agent=a1&text=Fix+the%20build%21").unwrap(),
+            DeepLink::Send {
+                agent_id: "a1".to_string(),
+                text: "Fix the build!".to_string()
+            }
+        );
+        assert!(parse("kanbun://send?agent=a1").is_err());
+        assert!(parse("kanbun://send?agent=a1&text=%zz").is_err());
+        assert!(parse("https://agent/a1").is_err());
+        assert!(parse("kanbun://settings").is_err());
+        assert_eq!(
+            parse(&agent_link("a1")).unwrap(),
+            parse("kanbun://agent/a1").unwrap()
+        );
+
+        let db = Arc::new(Database::new(":memory:").expect("db should open"));
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).expect("agent should save");
+
+        let link = parse(&format!("kanbun://send?agent={}&text=Ship+it", agent.id)).unwrap();
+        let target = resolve(&db, link).expect("send link should resolve");
+        let held = db
+            .get_messages_awaiting_approval(Some(&agent.id))
+            .expect("held messages should load");
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].content, "Ship it");
+        assert_eq!(target.message_id.as_deref(), Some(held[0].id.as_str()));
+        assert!(db.get_pending_messages(&agent.id).unwrap().is_empty());
+
+        let missing = parse("kanbun://agent/nope").unwrap();
+        assert_eq!(
+            resolve(&db, missing).unwrap_err().code,
+            crate::errors::ErrorCode::NotFound
+        );
+
+        let link = parse(
+            "kanbun://capture?text=Call+the+vendor&project=site&x-success=shortcuts%3A%2F%2Fx-callback-url%2Fdone",
+        )
+        .unwrap();
+        let target = resolve(&db, link).expect("capture link should resolve");
+        let item_id = target
+            .item_id
+            .clone()
+            .expect("capture should create an item");
+        let settings = DeepLinkSettings::default();
+        assert_eq!(
+            success_callback(&target, &settings).as_deref(),
+            Some(format!("shortcuts://x-callback-url/done?id={}", item_id).as_str())
+        );
+        let link = parse(&format!(
+            "kanbun://capture?text=Tidy+up&agent={}&x-success=https://evil.example",
+            agent.id
+        ))
+        .unwrap();
+        let target = resolve(&db, link).expect("capture link should resolve");
+        assert!(target.message_id.is_some());
+        assert_eq!(
+            db.get_messages_awaiting_approval(Some(&agent.id))
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(success_callback(&target, &settings).is_none());
+        assert!(parse("kanbun://capture?project=site").is_err());
+
+        let link = parse("kanbun://capture?text=Note&x-success=obsidian%3A%2F%2Fopen").unwrap();
+        let target = resolve(&db, link).expect("capture link should resolve");
+        assert!(success_callback(&target, &settings).is_none());
+        let settings = DeepLinkSettings {
+            callback_schemes: vec!["Obsidian".to_string()],
+        };
+        assert!(settings.validate().is_ok());
+        assert!(success_callback(&target, &settings)
+            .unwrap()
+            .starts_with("obsidian://open?id="));
+        for scheme in ["https", "kanbun", "cmd /c", ""] {
+            let settings = DeepLinkSettings {
+                callback_schemes: vec![scheme.to_string()],
+            };
+            assert!(settings.validate().is_err(), "{} should be refused", scheme);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::db::{Database, SlowQuery, StorageStats};
+use crate::models::AdapterType;
+use crate::watchers::WatcherStats;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// ── Diagnostics ─────────────────────────────────────────────────────────────
+// A point-in-time report of where the app's time and memory go, so a slow
+// dashboard or a stuck watcher can be reported with numbers attached.
+
+const SLOW_QUERY_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub collected_at: DateTime<Utc>,
+    pub storage: StorageStats,
+    pub slow_queries: Vec<SlowQuery>,
+    pub watcher: WatcherQueue,
+    pub adapters: AdapterThreads,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatcherQueue {
+    pub watched_roots: usize,
+    pub queued_events: u64,
+    pub debouncing_events: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdapterThreads {
+    pub running_loops: usize, // one delivery thread per started adapter
+    pub loops_by_type: BTreeMap<String, usize>,
+    pub process_sessions: usize,   // plus two reader threads each
+    pub os_threads: Option<usize>, // the whole app, where the platform reports it
+}
+
+/// Gather the report. `running` holds the adapter type of every adapter
+/// the supervisor has started.
+pub fn collect(
+    db: &Database,
+    watcher: &WatcherStats,
+    running: &[AdapterType],
+) -> Result<Diagnostics, String> {
+    let storage = db.storage_stats().map_err(|e| e.to_string())?;
+    let watcher = watcher.snapshot();
+
+    let mut loops_by_type = BTreeMap::new();
+    for adapter_type in running {
+        let name = serde_json::to_value(adapter_type)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+        *loops_by_type.entry(name).or_insert(0) += 1;
+    }
+
+    Ok(Diagnostics {
+        collected_at: Utc::now(),
+        storage,
+        slow_queries: db.slow_queries(SLOW_QUERY_LIMIT),
+        watcher: WatcherQueue {
+            watched_roots: watcher.roots.len(),
+            queued_events: watcher.queued_events,
+            debouncing_events: watcher.debouncing_events,
+        },
+        adapters: AdapterThreads {
+            running_loops: running.len(),
+            loops_by_type,
+            process_sessions: crate::agents::process::session_count(),
+            os_threads: os_thread_count(),
+        },
+    })
+}
+
+fn os_thread_count() -> Option<usize> {
+    if cfg!(target_os = "linux") {
+        std::fs::read_dir("/proc/self/task")
+            .ok()
+            .map(|tasks| tasks.count())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::*;
+
+    #[test]
+    fn report_counts_rows_and_running_adapters() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).unwrap();
+        db.create_agent(&Agent::new("Docs", &project.id, AgentKind::Api, "docs"))
+            .unwrap();
+
+        let report = collect(
+            &db,
+            &WatcherStats::default(),
+            &[
+                AdapterType::Process,
+                AdapterType::Process,
+                AdapterType::Mock,
+            ],
+        )
+        .expect("diagnostics should collect");
+
+        let rows = |table: &str| {
+            report
+                .storage
+                .tables
+                .iter()
+                .find(|count| count.table == table)
+                .map(|count| count.rows)
+        };
+        assert_eq!(rows("projects"), Some(1));
+        assert_eq!(rows("agents"), Some(1));
+        assert!(report.storage.size_bytes > 0);
+        assert_eq!(report.adapters.running_loops, 3);
+        assert_eq!(report.adapters.loops_by_type.get("process"), Some(&2));
+        assert_eq!(report.watcher.queued_events, 0);
+    }
+}
@@ -0,0 +1,418 @@
+use crate::connectors::{self, ConnectorItem, ItemStatus};
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use crate::timezone::UserTimezone;
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ── Weekly digest ───────────────────────────────────────────────────────────
+// A Markdown recap per project for one Monday–Sunday week in the user's
+// timezone, saved as a
+// project context doc and optionally pushed to a connector.
+
+pub const DIGEST_SETTINGS_KEY: &str = "weekly_digest";
+const LAST_DIGEST_WEEK_KEY: &str = "weekly_digest_last_week";
+const TICK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MAX_FAILURES_LISTED: usize = 10;
+const MAX_FILES_LISTED: usize = 25;
+
+/// Scheduled digest job. Off by default; the command works either way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSettings {
+    pub enabled: bool, // generate last week's digests every Monday
+    #[serde(default = "default_save_as_doc")]
+    pub save_as_doc: bool,
+    #[serde(default)]
+    pub push_connector: Option<String>, // connector id, e.g. "obsidian"
+}
+
+fn default_save_as_doc() -> bool {
+    true
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            save_as_doc: true,
+            push_connector: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectDigest {
+    pub project_id: String,
+    pub week_start: NaiveDate,
+    pub title: String,
+    pub markdown: String,
+    pub runs_completed: usize,
+    pub runs_failed: usize,
+    pub files_changed: usize,
+    pub items_closed: usize,
+    pub doc_id: Option<String>,
+    pub pushed_item_id: Option<String>,
+}
+
+impl ProjectDigest {
+    pub fn is_empty(&self) -> bool {
+        self.runs_completed == 0
+            && self.runs_failed == 0
+            && self.files_changed == 0
+            && self.items_closed == 0
+    }
+}
+
+/// Monday of the week containing `day`.
+pub fn week_start(day: NaiveDate) -> NaiveDate {
+    day.checked_sub_days(Days::new(day.weekday().num_days_from_monday() as u64))
+        .unwrap_or(day)
+}
+
+pub fn digest_title(week_start: NaiveDate) -> String {
+    format!("Weekly digest {}", week_start.format("%Y-%m-%d"))
+}
+
+/// Build the digest for a project from runs overlapping the week and the
+/// connector items closed during it.
+pub fn build_project_digest(
+    project: &Project,
+    agents: &[Agent],
+    runs: &[Run],
+    closed_items: &[(String, ConnectorItem)],
+    week_start: NaiveDate,
+    zone: UserTimezone,
+) -> ProjectDigest {
+    let week_end = week_start
+        .checked_add_days(Days::new(6))
+        .unwrap_or(week_start);
+    let (start, _) = zone.day_bounds(week_start);
+    let (_, end) = zone.day_bounds(week_end);
+    let within = |at: chrono::DateTime<Utc>| at >= start && at < end;
+
+    let agent_names: HashMap<&str, &str> = agents
+        .iter()
+        .filter(|agent| agent.project_id == project.id)
+        .map(|agent| (agent.id.as_str(), agent.name.as_str()))
+        .collect();
+    let runs: Vec<&Run> = runs
+        .iter()
+        .filter(|run| agent_names.contains_key(run.agent_id.as_str()))
+        .collect();
+    let ended_with = |status: RunStatus| {
+        runs.iter()
+            .filter(|run| run.status == status && run.ended_at.is_some_and(within))
+            .collect::<Vec<_>>()
+    };
+    let completed = ended_with(RunStatus::Completed);
+    let failed = ended_with(RunStatus::Failed);
+    let files: BTreeSet<&str> = runs
+        .iter()
+        .flat_map(|run| run.file_changes.iter())
+        .filter(|change| within(change.timestamp))
+        .map(|change| change.path.as_str())
+        .collect();
+
+    let title = digest_title(week_start);
+    let mut md = format!(
+        "# {} — {}\n\n_{} to {}_\n\n",
+        title,
+        project.name,
+        week_start.format("%Y-%m-%d"),
+        week_end.format("%Y-%m-%d")
+    );
+    md.push_str("## Summary\n\n");
+    md.push_str(&format!("- Runs completed: {}\n", completed.len()));
+    md.push_str(&format!("- Runs failed: {}\n", failed.len()));
+    md.push_str(&format!("- Files changed: {}\n", files.len()));
+    md.push_str(&format!(
+        "- Connector items closed: {}\n",
+        closed_items.len()
+    ));
+
+    if !failed.is_empty() {
+        md.push_str("\n## Notable failures\n\n");
+        for run in failed.iter().take(MAX_FAILURES_LISTED) {
+            md.push_str(&format!(
+                "- **{}**: {}\n",
+                agent_names
+                    .get(run.agent_id.as_str())
+                    .unwrap_or(&"Unknown agent"),
+                run.summary.as_deref().unwrap_or("No summary")
+            ));
+        }
+        if failed.len() > MAX_FAILURES_LISTED {
+            md.push_str(&format!(
+                "- …and {} more\n",
+                failed.len() - MAX_FAILURES_LISTED
+            ));
+        }
+    }
+
+    if !files.is_empty() {
+        md.push_str("\n## Files changed\n\n");
+        for path in files.iter().take(MAX_FILES_LISTED) {
+            md.push_str(&format!("- `{}`\n", path));
+        }
+        if files.len() > MAX_FILES_LISTED {
+            md.push_str(&format!("- …and {} more\n", files.len() - MAX_FILES_LISTED));
+        }
+    }
+
+    if !closed_items.is_empty() {
+        md.push_str("\n## Connector items closed\n\n");
+        for (connector_id, item) in closed_items {
+            match &item.url {
+                Some(url) => {
+                    md.push_str(&format!("- [{}]({}) ({})\n", item.title, url, connector_id))
+                }
+                None => md.push_str(&format!("- {} ({})\n", item.title, connector_id)),
+            }
+        }
+    }
+
+    ProjectDigest {
+        project_id: project.id.clone(),
+        week_start,
+        title,
+        markdown: md,
+        runs_completed: completed.len(),
+        runs_failed: failed.len(),
+        files_changed: files.len(),
+        items_closed: closed_items.len(),
+        doc_id: None,
+        pushed_item_id: None,
+    }
+}
+
+pub fn compile_project_digest(
+    db: &Database,
+    project: &Project,
+    week_start: NaiveDate,
+) -> rusqlite::Result<ProjectDigest> {
+    let week_end = week_start
+        .checked_add_days(Days::new(6))
+        .unwrap_or(week_start);
+    let zone = UserTimezone::load(db);
+    let (start, end) = (zone.day_bounds(week_start).0, zone.day_bounds(week_end).1);
+    let (start, end) = (start.to_rfc3339(), end.to_rfc3339());
+    let agents = db.list_agents()?;
+    let runs = db.get_runs_overlapping(&start, &end)?;
+    let closed = db.get_items_closed_for_project(&project.id, &start, &end)?;
+    Ok(build_project_digest(
+        project, &agents, &runs, &closed, week_start, zone,
+    ))
+}
+
+/// Store the digest as a context doc, replacing the same week's earlier one.
+pub fn save_digest_doc(db: &Database, digest: &mut ProjectDigest) -> rusqlite::Result<()> {
+    let existing = db
+        .list_project_context_docs(&digest.project_id)?
+        .into_iter()
+        .find(|doc| doc.title == digest.title);
+    let doc = match existing {
+        Some(mut doc) => {
+            doc.content = digest.markdown.clone();
+            doc.updated_at = Utc::now();
+            doc
+        }
+        None => ProjectContextDocument::new(&digest.project_id, &digest.title, &digest.markdown),
+    };
+    db.save_project_context_doc(&doc)?;
+    digest.doc_id = Some(doc.id);
+    Ok(())
+}
+
+pub async fn push_digest(
+    db: &Database,
+    connector_id: &str,
+    project: &Project,
+    digest: &mut ProjectDigest,
+) -> Result<(), String> {
+    let config = db
+        .get_connector_config(connector_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Connector '{}' not configured", connector_id))?;
+    let connector = connectors::create_connector(&config).map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let item = ConnectorItem {
+        id: String::new(),
+        source: config.connector_type.clone(),
+        title: format!("{} - {}", digest.title, project.name),
+        content: Some(digest.markdown.clone()),
+        status: ItemStatus::Active,
+        priority: None,
+        tags: vec!["kanbun".to_string(), "digest".to_string()],
+        url: None,
+        parent_id: None,
+        metadata: HashMap::new(),
+        created_at: Some(now),
+        updated_at: Some(now),
+        due_at: None,
+        raw: None,
+    };
+    let created = connector.push(&item).await.map_err(|e| e.to_string())?;
+    db.upsert_connector_items(connector_id, std::slice::from_ref(&created))
+        .map_err(|e| e.to_string())?;
+    digest.pushed_item_id = Some(created.id);
+    Ok(())
+}
+
+/// Compile digests for one project, or every project with activity that
+/// week, then save and push them as `settings` asks.
+pub async fn generate_weekly_digests(
+    db: &Database,
+    project_id: Option<&str>,
+    week_start: NaiveDate,
+    settings: &DigestSettings,
+) -> Result<Vec<ProjectDigest>, CommandError> {
+    let projects: Vec<Project> = db
+        .list_projects()?
+        .into_iter()
+        .filter(|project| project_id.is_none_or(|id| project.id == id))
+        .collect();
+    if let Some(id) = project_id {
+        if projects.is_empty() {
+            return Err(CommandError::not_found(format!(
+                "Project '{}' not found",
+                id
+            )));
+        }
+    }
+
+    let mut digests = Vec::new();
+    for project in &projects {
+        let mut digest = compile_project_digest(db, project, week_start)?;
+        if project_id.is_none() && digest.is_empty() {
+            continue;
+        }
+        if settings.save_as_doc {
+            save_digest_doc(db, &mut digest)?;
+        }
+        if let Some(connector_id) = &settings.push_connector {
+            push_digest(db, connector_id, project, &mut digest).await?;
+        }
+        digests.push(digest);
+    }
+    Ok(digests)
+}
+
+/// Generate last week's digests once, on the first tick after it ends.
+async fn run_scheduled(db: &Database) -> Result<usize, String> {
+    let settings = db
+        .get_setting::<DigestSettings>(DIGEST_SETTINGS_KEY)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    if !settings.enabled {
+        return Ok(0);
+    }
+    let this_week = week_start(UserTimezone::load(db).date_of(db.now()));
+    let Some(last_week) = this_week.checked_sub_days(Days::new(7)) else {
+        return Ok(0);
+    };
+    let last_done = db
+        .get_setting::<NaiveDate>(LAST_DIGEST_WEEK_KEY)
+        .map_err(|e| e.to_string())?;
+    if last_done.is_some_and(|done| done >= last_week) {
+        return Ok(0);
+    }
+
+    let digests = generate_weekly_digests(db, None, last_week, &settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    db.set_setting(LAST_DIGEST_WEEK_KEY, &last_week)
+        .map_err(|e| e.to_string())?;
+    Ok(digests.len())
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(error) => {
+                tracing::warn!("Weekly digest job disabled: {}", error);
+                return;
+            }
+        };
+        loop {
+            match runtime.block_on(run_scheduled(&db)) {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Generated {} weekly digest(s)", count),
+                Err(error) => tracing::warn!("Weekly digest generation failed: {}", error),
+            }
+            std::thread::sleep(TICK_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_covers_the_projects_week_and_replaces_its_doc() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let other = Project::new("Other Project", "#654321");
+        db.create_project(&other).expect("project should insert");
+        let agent = Agent::new("Worker", &project.id, AgentKind::Terminal, "ops");
+        db.create_agent(&agent).expect("agent should insert");
+        let outsider = Agent::new("Outsider", &other.id, AgentKind::Terminal, "ops");
+        db.create_agent(&outsider).expect("agent should insert");
+
+        let week = week_start(UserTimezone::System.date_of(Utc::now()));
+        assert_eq!(week.weekday(), chrono::Weekday::Mon);
+        let (start, _) = UserTimezone::System.day_bounds(week);
+        let at = start + chrono::Duration::hours(1);
+
+        let mut done = Run::new(&agent.id, RunStatus::Completed, Some("Shipped".into()));
+        done.started_at = at;
+        done.ended_at = Some(at);
+        done.file_changes.push(FileChange {
+            path: "/repo/src/main.rs".to_string(),
+            change_type: FileChangeType::Modified,
+            timestamp: at,
+            count: 1,
+            old_path: None,
+            lines_added: None,
+            lines_removed: None,
+        });
+        db.create_run(&done).expect("run should insert");
+        let mut failed = Run::new(&agent.id, RunStatus::Failed, Some("Tests broke".into()));
+        failed.started_at = at;
+        failed.ended_at = Some(at);
+        db.create_run(&failed).expect("run should insert");
+        let mut elsewhere = Run::new(&outsider.id, RunStatus::Completed, None);
+        elsewhere.started_at = at;
+        elsewhere.ended_at = Some(at);
+        db.create_run(&elsewhere).expect("run should insert");
+
+        let mut digest =
+            compile_project_digest(&db, &project, week).expect("digest should compile");
+        assert_eq!(digest.runs_completed, 1);
+        assert_eq!(digest.runs_failed, 1);
+        assert_eq!(digest.files_changed, 1);
+        assert_eq!(digest.items_closed, 0);
+        assert!(digest.markdown.contains("**Worker**: Tests broke"));
+        assert!(digest.markdown.contains("`/repo/src/main.rs`"));
+
+        save_digest_doc(&db, &mut digest).expect("doc should save");
+        let first_id = digest.doc_id.clone();
+        save_digest_doc(&db, &mut digest).expect("doc should save");
+        assert_eq!(digest.doc_id, first_id);
+        let docs = db
+            .list_project_context_docs(&project.id)
+            .expect("docs should load");
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].title, digest_title(week));
+
+        let quiet = compile_project_digest(&db, &other, week - Days::new(7))
+            .expect("digest should compile");
+        assert!(quiet.is_empty());
+    }
+}
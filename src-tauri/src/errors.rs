@@ -0,0 +1,185 @@
+use crate::agents::AdapterError;
+use crate::connectors::ConnectorError;
+use serde::Serialize;
+
+// ── Command errors ──────────────────────────────────────────────────────────
+// What every Tauri command rejects with: a stable `code` the frontend can
+// branch on, the human-readable `message` it used to get as a bare string,
+// and whether trying again unchanged might succeed. Lookups that can miss
+// return `CommandError::not_found` themselves; a plain `String` from an older
+// helper converts on `?` as `Failed`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    InvalidInput,
+    DatabaseBusy,
+    Database,
+    AdapterNotConnected,
+    AdapterDeliveryFailed,
+    AdapterSpawnFailed,
+    Adapter,
+    ConnectorAuthFailed,
+    ConnectorRateLimited,
+    ConnectorNetwork,
+    Connector,
+    Failed,
+}
+
+impl ErrorCode {
+    /// Transient conditions: a lock held by another writer, an adapter
+    /// still starting, a rate limit or a flaky network.
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::DatabaseBusy
+                | Self::AdapterNotConnected
+                | Self::AdapterDeliveryFailed
+                | Self::ConnectorRateLimited
+                | Self::ConnectorNetwork
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub retryable: bool,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retryable: code.retryable(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A bare message carries no kind, so it's always `Failed`; code that knows
+/// better builds the error with `not_found`/`invalid_input` where it fails.
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Failed, message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        let code = match error.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            std::io::ErrorKind::InvalidInput => ErrorCode::InvalidInput,
+            _ => ErrorCode::Failed,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for CommandError {
+    fn from(error: rusqlite::Error) -> Self {
+        let code = match &error {
+            rusqlite::Error::QueryReturnedNoRows => ErrorCode::NotFound,
+            rusqlite::Error::SqliteFailure(failure, _)
+                if matches!(
+                    failure.code,
+                    rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+                ) =>
+            {
+                ErrorCode::DatabaseBusy
+            }
+            _ => ErrorCode::Database,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<AdapterError> for CommandError {
+    fn from(error: AdapterError) -> Self {
+        let code = match &error {
+            AdapterError::NotConnected(_) | AdapterError::SessionNotFound(_) => {
+                ErrorCode::AdapterNotConnected
+            }
+            AdapterError::DeliveryFailed(_) => ErrorCode::AdapterDeliveryFailed,
+            AdapterError::SpawnFailed(_) => ErrorCode::AdapterSpawnFailed,
+            AdapterError::Other(_) => ErrorCode::Adapter,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<ConnectorError> for CommandError {
+    fn from(error: ConnectorError) -> Self {
+        let code = match &error {
+            ConnectorError::AuthFailed(_) => ErrorCode::ConnectorAuthFailed,
+            ConnectorError::NotFound(_) => ErrorCode::NotFound,
+            ConnectorError::RateLimited(_) => ErrorCode::ConnectorRateLimited,
+            ConnectorError::NetworkError(_) => ErrorCode::ConnectorNetwork,
+            ConnectorError::NotSupported(_) => ErrorCode::InvalidInput,
+            ConnectorError::ParseError(_)
+            | ConnectorError::FileSystemError(_)
+            | ConnectorError::Other(_) => ErrorCode::Connector,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_keep_their_kind_across_conversions() {
+        let busy: CommandError = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        )
+        .into();
+        assert_eq!(busy.code, ErrorCode::DatabaseBusy);
+        assert!(busy.retryable);
+
+        let missing = CommandError::not_found(format!("Agent not found: {}", "a1"));
+        assert_eq!(missing.code, ErrorCode::NotFound);
+        assert!(!missing.retryable);
+        let plain: CommandError = "Agent not found: a1".to_string().into();
+        assert_eq!(plain.code, ErrorCode::Failed);
+
+        let spawn: CommandError = AdapterError::SpawnFailed("tmux".to_string()).into();
+        assert_eq!(spawn.code, ErrorCode::AdapterSpawnFailed);
+        assert_eq!(spawn.message, "Spawn failed: tmux");
+
+        let limited: CommandError = ConnectorError::RateLimited("todoist".to_string()).into();
+        assert!(limited.retryable);
+
+        assert_eq!(
+            serde_json::to_value(CommandError::invalid_input("Name is required")).unwrap(),
+            serde_json::json!({
+                "code": "invalid_input",
+                "message": "Name is required",
+                "retryable": false
+            })
+        );
+    }
+}
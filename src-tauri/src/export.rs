@@ -0,0 +1,540 @@
+use crate::deep_link;
+use crate::models::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// ── Conversation transcripts ────────────────────────────────────────────────
+// A transcript interleaves an agent's messages with run boundaries and file
+// changes so the history reads as one timeline outside the app.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranscriptRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TranscriptRange {
+    fn contains(&self, timestamp: &DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| *timestamp >= since)
+            && self.until.is_none_or(|until| *timestamp <= until)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEntry {
+    RunStarted {
+        timestamp: DateTime<Utc>,
+        run_id: String,
+        summary: Option<String>,
+    },
+    Message {
+        timestamp: DateTime<Utc>,
+        message: Message,
+    },
+    FileChange {
+        timestamp: DateTime<Utc>,
+        run_id: String,
+        path: String,
+        change_type: FileChangeType,
+    },
+    RunEnded {
+        timestamp: DateTime<Utc>,
+        run_id: String,
+        status: RunStatus,
+        summary: Option<String>,
+    },
+}
+
+impl TranscriptEntry {
+    fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            Self::RunStarted { timestamp, .. }
+            | Self::Message { timestamp, .. }
+            | Self::FileChange { timestamp, .. }
+            | Self::RunEnded { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Tie-break for identical timestamps: open runs before their contents,
+    /// close them after.
+    fn order_rank(&self) -> u8 {
+        match self {
+            Self::RunStarted { .. } => 0,
+            Self::Message { .. } => 1,
+            Self::FileChange { .. } => 2,
+            Self::RunEnded { .. } => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub exported_at: DateTime<Utc>,
+    pub range: TranscriptRange,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptExportResult {
+    pub path: String,
+    pub format: TranscriptFormat,
+    pub entry_count: usize,
+    pub size_bytes: u64,
+    pub completed_at: String,
+}
+
+pub fn build_transcript(
+    agent: &Agent,
+    messages: Vec<Message>,
+    runs: &[Run],
+    range: &TranscriptRange,
+) -> Transcript {
+    let mut entries: Vec<TranscriptEntry> = messages
+        .into_iter()
+        .map(|message| TranscriptEntry::Message {
+            timestamp: message.created_at,
+            message,
+        })
+        .collect();
+
+    for run in runs {
+        if range.contains(&run.started_at) {
+            entries.push(TranscriptEntry::RunStarted {
+                timestamp: run.started_at,
+                run_id: run.id.clone(),
+                summary: run.summary.clone(),
+            });
+        }
+        for change in &run.file_changes {
+            if range.contains(&change.timestamp) {
+                entries.push(TranscriptEntry::FileChange {
+                    timestamp: change.timestamp,
+                    run_id: run.id.clone(),
+                    path: change.path.clone(),
+                    change_type: change.change_type.clone(),
+                });
+            }
+        }
+        if let Some(ended_at) = run.ended_at {
+            if range.contains(&ended_at) {
+                entries.push(TranscriptEntry::RunEnded {
+                    timestamp: ended_at,
+                    run_id: run.id.clone(),
+                    status: run.status.clone(),
+                    summary: run.summary.clone(),
+                });
+            }
+        }
+    }
+
+    entries.sort_by_key(|entry| (entry.timestamp(), entry.order_rank()));
+
+    Transcript {
+        agent_id: agent.id.clone(),
+        agent_name: agent.name.clone(),
+        exported_at: Utc::now(),
+        range: range.clone(),
+        entries,
+    }
+}
+
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+fn quote_block(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render_markdown(transcript: &Transcript) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "# {} — conversation transcript\n\n",
+        transcript.agent_name
+    ));
+    out.push_str(&format!(
+        "- Exported: {}\n",
+        format_timestamp(&transcript.exported_at)
+    ));
+    let range = match (&transcript.range.since, &transcript.range.until) {
+        (None, None) => "full history".to_string(),
+        (Some(since), None) => format!("since {}", format_timestamp(since)),
+        (None, Some(until)) => format!("until {}", format_timestamp(until)),
+        (Some(since), Some(until)) => {
+            format!("{} – {}", format_timestamp(since), format_timestamp(until))
+        }
+    };
+    out.push_str(&format!("- Range: {}\n", range));
+    out.push_str(&format!("- Entries: {}\n", transcript.entries.len()));
+
+    for entry in &transcript.entries {
+        match entry {
+            TranscriptEntry::RunStarted {
+                timestamp, summary, ..
+            } => {
+                out.push_str(&format!(
+                    "\n---\n\n## Run started · {}\n",
+                    format_timestamp(timestamp)
+                ));
+                if let Some(summary) = summary.as_deref().filter(|s| !s.trim().is_empty()) {
+                    out.push_str(&format!("\n_{}_\n", summary.trim()));
+                }
+            }
+            TranscriptEntry::Message { timestamp, message } => {
+                let author = match message.direction {
+                    MessageDirection::ToAgent => "Operator",
+                    MessageDirection::FromAgent => transcript.agent_name.as_str(),
+                };
+                out.push_str(&format!(
+                    "\n**{}** · {} · {}\n\n{}\n",
+                    author,
+                    message.kind.as_str(),
+                    format_timestamp(timestamp),
+                    quote_block(message.content.trim_end())
+                ));
+            }
+            TranscriptEntry::FileChange {
+                timestamp,
+                path,
+                change_type,
+                ..
+            } => {
+                out.push_str(&format!(
+                    "\n- File {}: `{}` · {}\n",
+                    change_type.as_str(),
+                    path,
+                    format_timestamp(timestamp)
+                ));
+            }
+            TranscriptEntry::RunEnded {
+                timestamp,
+                status,
+                summary,
+                ..
+            } => {
+                out.push_str(&format!(
+                    "\n## Run {} · {}\n",
+                    status.as_str().replace('_', " "),
+                    format_timestamp(timestamp)
+                ));
+                if let Some(summary) = summary.as_deref().filter(|s| !s.trim().is_empty()) {
+                    out.push_str(&format!("\n_{}_\n", summary.trim()));
+                }
+                out.push_str("\n---\n");
+            }
+        }
+    }
+
+    out
+}
+
+// ── Run reports ─────────────────────────────────────────────────────────────
+// A single run written up for people outside the app: what was asked, what
+// happened, what changed, and what it cost.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub run_id: String,
+    pub markdown: String,
+    pub path: Option<String>,
+    pub size_bytes: u64,
+}
+
+pub(crate) fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match (seconds / 3600, (seconds % 3600) / 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+pub fn render_run_report(agent: &Agent, run: &Run) -> String {
+    let mut out = String::new();
+    let title = run
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|summary| !summary.is_empty())
+        .unwrap_or("Agent run");
+    out.push_str(&format!("# {}\n\n", title));
+
+    out.push_str(&format!("- Agent: {}\n", agent.name));
+    out.push_str(&format!(
+        "- Status: {}\n",
+        run.status.as_str().replace('_', " ")
+    ));
+    out.push_str(&format!(
+        "- Started: {}\n",
+        format_timestamp(&run.started_at)
+    ));
+    match run.ended_at {
+        Some(ended_at) => {
+            out.push_str(&format!("- Ended: {}\n", format_timestamp(&ended_at)));
+            out.push_str(&format!(
+                "- Duration: {}\n",
+                format_duration(ended_at - run.started_at)
+            ));
+        }
+        None => out.push_str(&format!(
+            "- Duration: {} (still running)\n",
+            format_duration(Utc::now() - run.started_at)
+        )),
+    }
+    if run.token_in > 0 || run.token_out > 0 || run.cost > 0.0 {
+        out.push_str(&format!(
+            "- Cost: ${:.4} ({} tokens in, {} tokens out)\n",
+            run.cost, run.token_in, run.token_out
+        ));
+    }
+    if !run.labels.is_empty() {
+        out.push_str(&format!("- Labels: {}\n", run.labels.join(", ")));
+    }
+    if let Some(source) = &run.source_item {
+        out.push_str(&format!(
+            "- Source: {} item `{}`\n",
+            source.connector_id, source.item_id
+        ));
+    }
+    out.push_str(&format!("- Link: {}\n", deep_link::run_link(&run.id)));
+
+    let instructions: Vec<&RunOutput> = run
+        .outputs
+        .iter()
+        .filter(|output| output.kind == "instruction")
+        .collect();
+    if !instructions.is_empty() {
+        out.push_str("\n## Instruction\n\n");
+        for instruction in instructions {
+            out.push_str(&format!(
+                "{}\n\n",
+                quote_block(instruction.content.trim_end())
+            ));
+        }
+    }
+
+    let timeline: Vec<&RunOutput> = run
+        .outputs
+        .iter()
+        .filter(|output| output.kind != "instruction")
+        .collect();
+    if !timeline.is_empty() {
+        out.push_str("\n## Timeline\n\n");
+        for output in timeline {
+            let content = output.content.trim();
+            if content.contains('\n') {
+                out.push_str(&format!(
+                    "- {} · {}\n\n  ```\n{}\n  ```\n",
+                    output.timestamp.format("%H:%M:%S"),
+                    output.kind,
+                    content
+                        .lines()
+                        .map(|line| format!("  {}", line))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ));
+            } else {
+                out.push_str(&format!(
+                    "- {} · {} · {}\n",
+                    output.timestamp.format("%H:%M:%S"),
+                    output.kind,
+                    content
+                ));
+            }
+        }
+    }
+
+    if !run.file_changes.is_empty() {
+        out.push_str("\n## File changes\n\n");
+        for change in &run.file_changes {
+            let lines = match (change.lines_added, change.lines_removed) {
+                (Some(added), Some(removed)) => format!(" (+{} -{})", added, removed),
+                _ => String::new(),
+            };
+            out.push_str(&format!(
+                "- {} `{}`{}\n",
+                change.change_type.as_str(),
+                change.path,
+                lines
+            ));
+        }
+    }
+
+    if let Some(snapshot) = &run.git_snapshot {
+        if !snapshot.diff_stat.trim().is_empty() {
+            out.push_str("\n## Diff\n\n");
+            if let Some(branch) = &snapshot.branch {
+                out.push_str(&format!("Branch `{}`\n\n", branch));
+            }
+            out.push_str(&format!("```\n{}\n```\n", snapshot.diff_stat.trim_end()));
+        }
+    }
+
+    if let Some(review) = &run.review {
+        out.push_str(&format!(
+            "\n## Review\n\n{} · {}\n",
+            review.decision.as_str().replace('_', " "),
+            format_timestamp(&review.reviewed_at)
+        ));
+        if let Some(notes) = &review.notes {
+            out.push_str(&format!("\n{}\n", quote_block(notes)));
+        }
+    }
+
+    out
+}
+
+pub fn render_json(transcript: &Transcript) -> Result<String, String> {
+    serde_json::to_string_pretty(transcript)
+        .map_err(|error| format!("failed to serialize transcript: {}", error))
+}
+
+/// Write rendered export content, creating parent directories as needed.
+pub fn write_export_file(destination_path: &str, content: &str) -> Result<u64, String> {
+    let destination = Path::new(destination_path);
+    if let Some(parent) = destination.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).map_err(|error| {
+                format!(
+                    "failed to create export directory {}: {}",
+                    parent.display(),
+                    error
+                )
+            })?;
+        }
+    }
+
+    std::fs::write(destination, content).map_err(|error| {
+        format!(
+            "failed to write export file {}: {}",
+            destination.display(),
+            error
+        )
+    })?;
+    Ok(content.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_at(agent_id: &str, kind: MessageKind, content: &str, at: DateTime<Utc>) -> Message {
+        let mut message = match kind {
+            MessageKind::Instruction => Message::to_agent(agent_id, kind, content),
+            _ => Message::from_agent(agent_id, kind, content),
+        };
+        message.created_at = at;
+        message
+    }
+
+    #[test]
+    fn transcript_interleaves_runs_messages_and_file_changes() {
+        let agent = Agent::new("Docs Agent", "project", AgentKind::Terminal, "docs");
+        let base = Utc::now();
+        let at = |seconds: i64| base + chrono::Duration::seconds(seconds);
+
+        let mut run = Run::new(
+            &agent.id,
+            RunStatus::Completed,
+            Some("Update README".to_string()),
+        );
+        run.started_at = at(0);
+        run.ended_at = Some(at(30));
+        run.file_changes.push(FileChange {
+            path: "README.md".to_string(),
+            change_type: FileChangeType::Modified,
+            timestamp: at(20),
+            count: 1,
+            old_path: None,
+            lines_added: None,
+            lines_removed: None,
+        });
+        let messages = vec![
+            message_at(&agent.id, MessageKind::Instruction, "Update README", at(0)),
+            message_at(&agent.id, MessageKind::Completed, "Done", at(30)),
+        ];
+
+        let transcript = build_transcript(&agent, messages, &[run], &TranscriptRange::default());
+        let kinds: Vec<u8> = transcript
+            .entries
+            .iter()
+            .map(TranscriptEntry::order_rank)
+            .collect();
+        assert_eq!(kinds, vec![0, 1, 2, 1, 3]);
+
+        let markdown = render_markdown(&transcript);
+        assert!(markdown.starts_with("# Docs Agent — conversation transcript"));
+        assert!(markdown.contains("- File modified: `README.md`"));
+        assert!(markdown.contains("## Run completed"));
+
+        let json = render_json(&transcript).expect("json should render");
+        assert!(json.contains("\"type\": \"run_started\""));
+    }
+
+    #[test]
+    fn run_report_covers_instruction_timeline_changes_and_cost() {
+        let agent = Agent::new("Docs Agent", "project", AgentKind::Terminal, "docs");
+        let base = Utc::now();
+        let mut run = Run::new(
+            &agent.id,
+            RunStatus::Completed,
+            Some("Running: Update README".to_string()),
+        );
+        run.started_at = base;
+        run.ended_at = Some(base + chrono::Duration::seconds(95));
+        run.cost = 0.125;
+        run.token_in = 1000;
+        run.token_out = 200;
+        run.outputs = vec![
+            RunOutput {
+                kind: "instruction".to_string(),
+                content: "Update README".to_string(),
+                timestamp: base,
+            },
+            RunOutput {
+                kind: "output".to_string(),
+                content: "Rewrote install section".to_string(),
+                timestamp: base + chrono::Duration::seconds(60),
+            },
+        ];
+        run.file_changes.push(FileChange {
+            path: "README.md".to_string(),
+            change_type: FileChangeType::Modified,
+            timestamp: base + chrono::Duration::seconds(50),
+            count: 1,
+            old_path: None,
+            lines_added: None,
+            lines_removed: None,
+        });
+
+        let report = render_run_report(&agent, &run);
+        assert!(report.starts_with("# Running: Update README"));
+        assert!(report.contains("- Duration: 1m 35s"));
+        assert!(report.contains("- Cost: $0.1250 (1000 tokens in, 200 tokens out)"));
+        assert!(report.contains(&format!("- Link: kanbun://run/{}\n", run.id)));
+        assert!(report.contains("## Instruction\n\n> Update README"));
+        assert!(report.contains("output · Rewrote install section"));
+        assert!(report.contains("- modified `README.md`"));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::models::GitSnapshot;
+use chrono::Utc;
+use std::path::Path;
+use std::process::Command;
+
+const MAX_PATCH_BYTES: usize = 512 * 1024;
+
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Snapshot the working tree of the git repository containing `working_directory`.
+/// Returns None when the directory is missing, not inside a repository, or git
+/// is not installed. Diffs are taken against HEAD so staged and unstaged edits
+/// both show up.
+pub fn capture_snapshot(working_directory: &str, include_patch: bool) -> Option<GitSnapshot> {
+    let expanded = shellexpand::tilde(working_directory.trim()).to_string();
+    let dir = Path::new(&expanded);
+    if expanded.is_empty() || !dir.is_dir() {
+        return None;
+    }
+
+    let inside = git(dir, &["rev-parse", "--is-inside-work-tree"])?;
+    if inside.trim() != "true" {
+        return None;
+    }
+
+    let head = git(dir, &["rev-parse", "HEAD"]).map(|sha| sha.trim().to_string());
+    // Before the first commit there is no HEAD to diff against.
+    let base: &[&str] = if head.is_some() { &["HEAD"] } else { &[] };
+
+    let branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && name != "HEAD");
+    let status = git(dir, &["status", "--porcelain"]).unwrap_or_default();
+    let diff_stat = git(dir, &[&["diff", "--stat"], base].concat()).unwrap_or_default();
+
+    let (patch, patch_truncated) = if include_patch {
+        match git(dir, &[&["diff"], base].concat()) {
+            Some(patch) if patch.len() > MAX_PATCH_BYTES => {
+                let mut cut = MAX_PATCH_BYTES;
+                while !patch.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                (Some(patch[..cut].to_string()), true)
+            }
+            Some(patch) => (Some(patch), false),
+            None => (None, false),
+        }
+    } else {
+        (None, false)
+    };
+
+    Some(GitSnapshot {
+        branch,
+        head,
+        status: status.trim_end().to_string(),
+        diff_stat: diff_stat.trim_end().to_string(),
+        patch,
+        patch_truncated,
+        captured_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_status_stat_and_patch_for_dirty_repo() {
+        let dir = std::env::temp_dir().join(format!("kanbun-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("temp dir should create");
+        let run = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args(args)
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        };
+        if !run(&["init", "-q"]) {
+            // git unavailable in this environment
+            return;
+        }
+        std::fs::write(dir.join("notes.md"), "one\n").expect("file should write");
+        assert!(run(&["add", "."]));
+        assert!(run(&[
+            "-c",
+            "user.name=kanbun",
+            "-c",
+            "user.email=kanbun@example.com",
+            "commit",
+            "-qm",
+            "init"
+        ]));
+        std::fs::write(dir.join("notes.md"), "one\ntwo\n").expect("file should write");
+
+        let path = dir.to_string_lossy().to_string();
+        let snapshot = capture_snapshot(&path, true).expect("snapshot should capture");
+        assert!(snapshot.head.is_some());
+        assert!(snapshot.status.contains("notes.md"));
+        assert!(snapshot.diff_stat.contains("1 file changed"));
+        assert!(snapshot.patch.as_deref().unwrap_or("").contains("+two"));
+
+        let without_patch = capture_snapshot(&path, false).expect("snapshot should capture");
+        assert!(without_patch.patch.is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(capture_snapshot(&path, false).is_none());
+    }
+}
@@ -0,0 +1,253 @@
+use crate::db::Database;
+use crate::git::git;
+use crate::models::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_COMMITS_PER_POLL: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+struct RepoState {
+    head: Option<String>,
+    branch: Option<String>, // None when HEAD is detached
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitActivity {
+    Commit {
+        sha: String,
+        subject: String,
+    },
+    BranchSwitch {
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl GitActivity {
+    pub fn output_kind(&self) -> &'static str {
+        match self {
+            GitActivity::Commit { .. } => "commit",
+            GitActivity::BranchSwitch { .. } => "branch",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let name = |branch: &Option<String>| {
+            branch
+                .clone()
+                .unwrap_or_else(|| "detached HEAD".to_string())
+        };
+        match self {
+            GitActivity::Commit { sha, subject } => format!("committed {}: {}", sha, subject),
+            GitActivity::BranchSwitch { from, to } => {
+                format!("switched branch {} -> {}", name(from), name(to))
+            }
+        }
+    }
+}
+
+fn repo_root(path: &str) -> Option<PathBuf> {
+    let expanded = shellexpand::tilde(path.trim()).to_string();
+    let dir = Path::new(&expanded);
+    if expanded.is_empty() || !dir.is_dir() {
+        return None;
+    }
+    git(dir, &["rev-parse", "--show-toplevel"]).map(|root| PathBuf::from(root.trim()))
+}
+
+fn read_state(repo: &Path) -> RepoState {
+    RepoState {
+        head: git(repo, &["rev-parse", "HEAD"]).map(|sha| sha.trim().to_string()),
+        branch: git(repo, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty() && name != "HEAD"),
+    }
+}
+
+/// Commits reachable from `new` but not `old`, oldest first. A first commit
+/// (no previous HEAD) lists just that commit.
+fn new_commits(repo: &Path, old: Option<&str>, new: &str) -> Vec<GitActivity> {
+    let (range, limit) = match old {
+        Some(old) => (format!("{}..{}", old, new), MAX_COMMITS_PER_POLL),
+        None => (new.to_string(), 1),
+    };
+    let limit = format!("-{}", limit);
+    let Some(log) = git(repo, &["log", "--format=%h%x09%s", &limit, &range]) else {
+        return vec![];
+    };
+    let mut commits: Vec<GitActivity> = log
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(sha, subject)| GitActivity::Commit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+        })
+        .collect();
+    commits.reverse();
+    commits
+}
+
+fn diff_states(repo: &Path, previous: &RepoState, current: &RepoState) -> Vec<GitActivity> {
+    let mut activity = Vec::new();
+    if previous.branch != current.branch {
+        activity.push(GitActivity::BranchSwitch {
+            from: previous.branch.clone(),
+            to: current.branch.clone(),
+        });
+    }
+    if let Some(head) = current.head.as_deref() {
+        if previous.head.as_deref() != Some(head) {
+            // A checkout moves HEAD without anyone committing; only report
+            // commits that landed on the branch we were already on.
+            let same_branch = previous.branch == current.branch;
+            if same_branch {
+                activity.extend(new_commits(repo, previous.head.as_deref(), head));
+            }
+        }
+    }
+    activity
+}
+
+/// Tracks HEAD and branch for every repository an agent watches and reports
+/// commits and branch switches as run outputs. `.git` internals never reach
+/// the file watcher, so this is the only place git activity shows up.
+#[derive(Default)]
+pub struct GitMonitor {
+    repos: HashMap<(String, PathBuf), RepoState>,
+}
+
+impl GitMonitor {
+    /// Compare each watched repository against the last poll. The first time
+    /// a repository is seen only its state is recorded.
+    pub fn poll(&mut self, agents: &[Agent]) -> Vec<(String, GitActivity)> {
+        let mut seen = Vec::new();
+        let mut activity = Vec::new();
+        for agent in agents {
+            let mut roots: Vec<PathBuf> = crate::collect_watch_paths(agent)
+                .iter()
+                .filter_map(|path| repo_root(path))
+                .collect();
+            roots.sort();
+            roots.dedup();
+
+            for root in roots {
+                let key = (agent.id.clone(), root.clone());
+                let current = read_state(&root);
+                if let Some(previous) = self.repos.get(&key) {
+                    for event in diff_states(&root, previous, &current) {
+                        activity.push((agent.id.clone(), event));
+                    }
+                }
+                self.repos.insert(key.clone(), current);
+                seen.push(key);
+            }
+        }
+        self.repos.retain(|key, _| seen.contains(key));
+        activity
+    }
+}
+
+fn record_activity(db: &Database, agent_id: &str, activity: &GitActivity) -> rusqlite::Result<()> {
+    let content = activity.describe();
+    db.append_run_output(agent_id, activity.output_kind(), &content)?;
+
+    let mut message = Message::from_agent(agent_id, MessageKind::StatusUpdate, &content);
+    message.metadata = Some(match activity {
+        GitActivity::Commit { sha, subject } => serde_json::json!({
+            "git_event": "commit",
+            "sha": sha,
+            "subject": subject,
+        }),
+        GitActivity::BranchSwitch { from, to } => serde_json::json!({
+            "git_event": "branch_switch",
+            "from": from,
+            "to": to,
+        }),
+    });
+    db.insert_message(&message)?;
+    Ok(())
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || {
+        let mut monitor = GitMonitor::default();
+        loop {
+            match db.list_agents() {
+                Ok(agents) => {
+                    for (agent_id, activity) in monitor.poll(&agents) {
+                        if let Err(error) = record_activity(&db, &agent_id, &activity) {
+                            tracing::warn!(
+                                "Failed to record git activity for agent {}: {}",
+                                agent_id,
+                                error
+                            );
+                        }
+                    }
+                }
+                Err(error) => tracing::warn!("Failed to list agents for git monitor: {}", error),
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn reports_new_commits_and_branch_switches() {
+        let dir = std::env::temp_dir().join(format!("kanbun-gitmon-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("temp dir should create");
+        let run = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args([
+                    "-c",
+                    "user.name=kanbun",
+                    "-c",
+                    "user.email=kanbun@example.com",
+                ])
+                .args(args)
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        };
+        if !run(&["init", "-q", "-b", "main"]) {
+            // git unavailable in this environment
+            return;
+        }
+        std::fs::write(dir.join("a.md"), "one\n").expect("file should write");
+        assert!(run(&["add", "."]));
+        assert!(run(&["commit", "-qm", "first"]));
+
+        let mut agent = Agent::new("Worker", "project", AgentKind::Terminal, "ops");
+        agent.working_directory = Some(dir.to_string_lossy().to_string());
+        let agents = vec![agent];
+        let mut monitor = GitMonitor::default();
+        assert!(monitor.poll(&agents).is_empty());
+
+        std::fs::write(dir.join("a.md"), "two\n").expect("file should write");
+        assert!(run(&["commit", "-qam", "second"]));
+        std::fs::write(dir.join("a.md"), "three\n").expect("file should write");
+        assert!(run(&["commit", "-qam", "third"]));
+        let activity = monitor.poll(&agents);
+        let described: Vec<String> = activity.iter().map(|(_, event)| event.describe()).collect();
+        assert_eq!(described.len(), 2);
+        assert!(described[0].starts_with("committed ") && described[0].ends_with(": second"));
+        assert!(described[1].ends_with(": third"));
+
+        assert!(run(&["checkout", "-qb", "feature"]));
+        let activity = monitor.poll(&agents);
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].1.describe(), "switched branch main -> feature");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
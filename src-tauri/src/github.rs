@@ -0,0 +1,437 @@
+use crate::db::Database;
+use crate::deep_link;
+use crate::errors::CommandError;
+use crate::export;
+use crate::git::git;
+use crate::metrics;
+use crate::models::*;
+use chrono::Utc;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ── GitHub reporting ────────────────────────────────────────────────────────
+// When a run in a GitHub checkout finalizes, post a commit status on the HEAD
+// it ended at and/or a comment on the open pull request for that commit, so
+// reviewers can tell which changes came from which agent.
+
+pub const GITHUB_SETTINGS_KEY: &str = "github_reporting";
+const TICK_INTERVAL: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT_SECONDS: u64 = 15;
+const MAX_DESCRIPTION_CHARS: usize = 140; // GitHub rejects longer status descriptions
+const MAX_FILES_LISTED: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub token: Option<String>, // needs repo:status, plus pull request write for comments
+    #[serde(default = "default_true")]
+    pub commit_status: bool,
+    #[serde(default)]
+    pub pr_comment: bool,
+    #[serde(default)]
+    pub project_ids: Vec<String>, // empty = every project
+    #[serde(default = "default_api_base")]
+    pub api_base: String, // GitHub Enterprise: https://<host>/api/v3
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_api_base() -> String {
+    "https://api.github.com".to_string()
+}
+
+impl Default for GithubSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: None,
+            commit_status: true,
+            pr_comment: false,
+            project_ids: vec![],
+            api_base: default_api_base(),
+        }
+    }
+}
+
+impl GithubSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.api_base.starts_with("https://") && !self.api_base.starts_with("http://") {
+            return Err(format!("API base must be http(s): {}", self.api_base));
+        }
+        if self.enabled && self.token().is_none() {
+            return Err("a GitHub token is required to report runs".to_string());
+        }
+        Ok(())
+    }
+
+    fn token(&self) -> Option<&str> {
+        self.token
+            .as_deref()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+    }
+
+    pub fn covers(&self, project_id: &str) -> bool {
+        self.project_ids.is_empty() || self.project_ids.iter().any(|id| id == project_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoRef {
+    pub owner: String,
+    pub name: String,
+}
+
+/// owner/name from an `origin` URL in any of the forms GitHub hands out.
+pub fn parse_remote(url: &str, host: &str) -> Option<RepoRef> {
+    let url = url.trim();
+    let path = [
+        format!("git@{}:", host),
+        format!("ssh://git@{}/", host),
+        format!("https://{}/", host),
+        format!("http://{}/", host),
+    ]
+    .iter()
+    .find_map(|prefix| url.strip_prefix(prefix.as_str()))?;
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, name) = path.split_once('/')?;
+    if owner.is_empty() || name.is_empty() || name.contains('/') {
+        return None;
+    }
+    Some(RepoRef {
+        owner: owner.to_string(),
+        name: name.to_string(),
+    })
+}
+
+/// The host remotes point at, derived from the API base.
+fn remote_host(api_base: &str) -> String {
+    let host = api_base
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    match host {
+        "api.github.com" => "github.com".to_string(),
+        host => host.to_string(),
+    }
+}
+
+fn agent_repo(agent: &Agent, host: &str) -> Option<RepoRef> {
+    let working_directory = agent.working_directory.as_deref()?;
+    let expanded = shellexpand::tilde(working_directory.trim()).to_string();
+    let url = git(Path::new(&expanded), &["remote", "get-url", "origin"])?;
+    parse_remote(&url, host)
+}
+
+/// Commit status state for a finalized run; None while it's still going.
+fn status_state(status: &RunStatus) -> Option<&'static str> {
+    match status {
+        RunStatus::Completed => Some("success"),
+        RunStatus::Failed => Some("failure"),
+        RunStatus::NeedsReview => Some("pending"),
+        RunStatus::InProgress => None,
+    }
+}
+
+fn changed_paths(run: &Run) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    run.file_changes
+        .iter()
+        .map(|change| change.path.as_str())
+        .filter(|path| seen.insert(*path))
+        .collect()
+}
+
+fn run_duration(run: &Run) -> String {
+    run.ended_at
+        .map(|ended_at| export::format_duration(ended_at - run.started_at))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+pub fn status_description(agent: &Agent, run: &Run) -> String {
+    let description = format!(
+        "{}: {} in {}, {} file(s) changed",
+        agent.name,
+        run.status.as_str().replace('_', " "),
+        run_duration(run),
+        changed_paths(run).len()
+    );
+    description.chars().take(MAX_DESCRIPTION_CHARS).collect()
+}
+
+pub fn comment_body(agent: &Agent, run: &Run, sha: &str) -> String {
+    let mut body = format!(
+        "**Kanbun run** by `{}` · {}\n\n",
+        agent.name,
+        run.status.as_str().replace('_', " ")
+    );
+    if let Some(summary) = run.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+        body.push_str(&format!("> {}\n\n", summary.trim().replace('\n', "\n> ")));
+    }
+    body.push_str(&format!("- Commit: {}\n", sha));
+    body.push_str(&format!("- Duration: {}\n", run_duration(run)));
+    let paths = changed_paths(run);
+    body.push_str(&format!("- Files changed: {}\n", paths.len()));
+    for path in paths.iter().take(MAX_FILES_LISTED) {
+        body.push_str(&format!("  - `{}`\n", path));
+    }
+    if paths.len() > MAX_FILES_LISTED {
+        body.push_str(&format!(
+            "  - …and {} more\n",
+            paths.len() - MAX_FILES_LISTED
+        ));
+    }
+    body.push_str(&format!("\n<sub>{}</sub>\n", deep_link::run_link(&run.id)));
+    body
+}
+
+fn build_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+        .user_agent("kanbun")
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+async fn github_request(
+    client: &Client,
+    settings: &GithubSettings,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let url = format!("{}{}", settings.api_base.trim_end_matches('/'), path);
+    let mut request = client
+        .request(method, &url)
+        .bearer_auth(settings.token().unwrap_or_default())
+        .header("Accept", "application/vnd.github+json")
+        .header("X-GitHub-Api-Version", "2022-11-28");
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("GitHub returned HTTP {} for {}", status, path));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+/// Post the configured status and/or PR comment for one finalized run.
+/// Returns Ok(false) when the run has nothing to report on (no GitHub
+/// remote, no commit, or still in progress).
+pub async fn report_run(
+    client: &Client,
+    settings: &GithubSettings,
+    agent: &Agent,
+    run: &Run,
+) -> Result<bool, String> {
+    let Some(state) = status_state(&run.status) else {
+        return Ok(false);
+    };
+    let Some(sha) = run
+        .git_snapshot
+        .as_ref()
+        .and_then(|snapshot| snapshot.head.as_deref())
+    else {
+        return Ok(false);
+    };
+    let Some(repo) = agent_repo(agent, &remote_host(&settings.api_base)) else {
+        return Ok(false);
+    };
+    let repo_path = format!("/repos/{}/{}", repo.owner, repo.name);
+
+    if settings.commit_status {
+        github_request(
+            client,
+            settings,
+            reqwest::Method::POST,
+            &format!("{}/statuses/{}", repo_path, sha),
+            Some(json!({
+                "state": state,
+                "description": status_description(agent, run),
+                "context": format!("kanbun/{}", agent.name),
+            })),
+        )
+        .await?;
+    }
+    if settings.pr_comment {
+        let pulls = github_request(
+            client,
+            settings,
+            reqwest::Method::GET,
+            &format!("{}/commits/{}/pulls", repo_path, sha),
+            None,
+        )
+        .await?;
+        let open_pull = pulls.as_array().and_then(|pulls| {
+            pulls
+                .iter()
+                .find(|pull| pull["state"] == "open")
+                .and_then(|pull| pull["number"].as_u64())
+        });
+        if let Some(number) = open_pull {
+            github_request(
+                client,
+                settings,
+                reqwest::Method::POST,
+                &format!("{}/issues/{}/comments", repo_path, number),
+                Some(json!({ "body": comment_body(agent, run, sha) })),
+            )
+            .await?;
+        }
+    }
+    Ok(true)
+}
+
+/// Load a run with its git snapshot and report it under the saved settings.
+pub async fn report_run_by_id(db: &Database, run_id: &str) -> Result<bool, CommandError> {
+    let settings: GithubSettings = db.get_setting(GITHUB_SETTINGS_KEY)?.unwrap_or_default();
+    if settings.token().is_none() {
+        return Err(CommandError::invalid_input("no GitHub token configured"));
+    }
+    let run = db
+        .get_run(run_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Run not found: {}", run_id)))?;
+    let agent = db
+        .get_agent(&run.agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", run.agent_id)))?;
+    report_run(&build_client(), &settings, &agent, &run)
+        .await
+        .map_err(CommandError::from)
+}
+
+async fn report_finalized(db: &Database, run_cursor: &mut String) -> Result<(), String> {
+    let runs = db
+        .get_runs_ended_since(run_cursor)
+        .map_err(|e| e.to_string())?;
+    if let Some(ended_at) = runs.last().and_then(|run| run.ended_at) {
+        *run_cursor = ended_at.to_rfc3339();
+    }
+    let settings: GithubSettings = db
+        .get_setting(GITHUB_SETTINGS_KEY)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    if !settings.enabled || settings.token().is_none() || runs.is_empty() {
+        return Ok(());
+    }
+    let agents = db.list_agents().map_err(|e| e.to_string())?;
+    let client = build_client();
+    for summary in runs {
+        let Some(agent) = agents.iter().find(|agent| agent.id == summary.agent_id) else {
+            continue;
+        };
+        if !settings.covers(&agent.project_id) {
+            continue;
+        }
+        // Summaries leave out the git snapshot; load the full run.
+        let Ok(Some(run)) = db.get_run(&summary.id) else {
+            continue;
+        };
+        if let Err(error) = report_run(&client, &settings, agent, &run).await {
+            tracing::warn!("Failed to report run {} to GitHub: {}", run.id, error);
+            metrics::record_delivery_failure("github");
+        }
+    }
+    Ok(())
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(error) => {
+                tracing::warn!("GitHub reporting disabled: {}", error);
+                return;
+            }
+        };
+        let mut run_cursor = Utc::now().to_rfc3339();
+        loop {
+            std::thread::sleep(TICK_INTERVAL);
+            if let Err(error) = runtime.block_on(report_finalized(&db, &mut run_cursor)) {
+                tracing::warn!("GitHub reporting pass failed: {}", error);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remotes_and_summarizes_runs() {
+        let expected = Some(RepoRef {
+            owner: "sidmohan0".to_string(),
+            name: "kanbun".to_string(),
+        });
+        for remote in [
+            "git@github.com:sidmohan0/kanbun.git",
+            "https://github.com/sidmohan0/kanbun",
+            "https://github.com/sidmohan0/kanbun.git\n",
+            "ssh://git@github.com/sidmohan0/kanbun.git",
+        ] {
+            assert_eq!(parse_remote(remote, "github.com"), expected, "{}", remote);
+        }
+        assert_eq!(parse_remote("git@gitlab.com:a/b.git", "github.com"), None);
+        assert_eq!(
+            parse_remote("https://github.com/sidmohan0", "github.com"),
+            None
+        );
+        assert_eq!(remote_host("https://api.github.com"), "github.com");
+        assert_eq!(
+            remote_host("https://ghe.example.com/api/v3"),
+            "ghe.example.com"
+        );
+
+        let agent = Agent::new("Docs", "project", AgentKind::Terminal, "docs");
+        let mut run = Run::new(
+            &agent.id,
+            RunStatus::Completed,
+            Some("Rewrote intro".into()),
+        );
+        run.ended_at = Some(run.started_at + chrono::Duration::seconds(95));
+        for path in ["README.md", "docs/intro.md", "README.md"] {
+            run.file_changes.push(FileChange {
+                path: path.to_string(),
+                change_type: FileChangeType::Modified,
+                timestamp: run.started_at,
+                count: 1,
+                old_path: None,
+                lines_added: None,
+                lines_removed: None,
+            });
+        }
+        assert_eq!(
+            status_description(&agent, &run),
+            "Docs: completed in 1m 35s, 2 file(s) changed"
+        );
+        let body = comment_body(&agent, &run, "abc123");
+        assert!(body.contains("> Rewrote intro"));
+        assert!(body.contains("- Files changed: 2\n  - `README.md`\n  - `docs/intro.md`\n"));
+        assert!(body.contains(&format!("kanbun://run/{}", run.id)));
+        assert_eq!(status_state(&RunStatus::InProgress), None);
+
+        let mut settings = GithubSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+        settings.token = Some("ghp_token".to_string());
+        assert!(settings.validate().is_ok());
+        assert!(settings.covers("anything"));
+        settings.project_ids = vec!["site".to_string()];
+        assert!(!settings.covers("other"));
+    }
+}
@@ -0,0 +1,533 @@
+use crate::api_tokens;
+use crate::background;
+use crate::commands;
+use crate::connectors::inbox::{self, InboxConnector, IntakeTask, INBOX_CONNECTOR_ID};
+use crate::connectors::ConnectorConfig;
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::local_http::{self, header, Request, RequestError, Response};
+use crate::models::{Agent, ApiTokenScope, Message, MessageKind, Project};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::time::MissedTickBehavior;
+
+// ── Inbox intake ────────────────────────────────────────────────────────────
+// A local HTTP endpoint (POST /intake) that Zapier, Make or a form backend can
+// call with simple JSON tasks, plus polling of the Inbox connector's relay
+// URL. Tasks become Inbox connector items; a task naming an agent becomes an
+// instruction held for approval, since whoever holds a token can post one.
+// Quick capture (Shortcuts, Raycast, kanbun://capture) goes through the same
+// path.
+//
+// Every request needs a read-write API token, and requests a browser sends
+// (anything with an `Origin` header) are refused, so a web page can't post
+// tasks to the local port.
+
+pub const INTAKE_SETTINGS_KEY: &str = "inbox_intake";
+const RELAY_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_BODY_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntakeSettings {
+    pub enabled: bool,
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1:9465".to_string()
+}
+
+impl Default for IntakeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_bind_address(),
+        }
+    }
+}
+
+impl IntakeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.bind_address
+            .parse::<SocketAddr>()
+            .map(|_| ())
+            .map_err(|_| format!("bind address must be host:port, got {}", self.bind_address))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntakeReceipt {
+    pub item_id: String,
+    pub title: String,
+    pub duplicate: bool, // already in the Inbox; not re-sent to its agent
+    pub message_id: Option<String>, // the instruction held for the named agent
+}
+
+/// Make sure the Inbox shows up among the connectors.
+fn ensure_inbox_configured(db: &Database) -> Result<(), String> {
+    if db
+        .get_connector_config(INBOX_CONNECTOR_ID)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        db.save_connector_config(&ConnectorConfig {
+            connector_type: INBOX_CONNECTOR_ID.to_string(),
+            auth_token: None,
+            settings: HashMap::new(),
+            enabled: true,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Store tasks as Inbox items and hold an instruction for each agent they
+/// name until it's approved. Every task is validated before anything is
+/// stored.
+pub fn ingest(
+    db: &Arc<Database>,
+    tasks: Vec<IntakeTask>,
+    received_via: &str,
+) -> Result<Vec<IntakeReceipt>, String> {
+    let now = Utc::now();
+    let items = tasks
+        .into_iter()
+        .map(|task| task.into_item(received_via, now))
+        .collect::<Result<Vec<_>, _>>()?;
+    ensure_inbox_configured(db)?;
+    let agent_ids: Vec<String> = db
+        .list_agents()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|agent| agent.id)
+        .collect();
+
+    let mut receipts = Vec::new();
+    for item in items {
+        let duplicate = db
+            .get_connector_item(INBOX_CONNECTOR_ID, &item.id)
+            .map_err(|e| e.to_string())?
+            .is_some();
+        if duplicate {
+            receipts.push(IntakeReceipt {
+                item_id: item.id,
+                title: item.title,
+                duplicate,
+                message_id: None,
+            });
+            continue;
+        }
+        db.upsert_connector_items(INBOX_CONNECTOR_ID, std::slice::from_ref(&item))
+            .map_err(|e| e.to_string())?;
+        let message_id = match item.metadata.get("agent_id") {
+            Some(agent_id) if !agent_ids.contains(agent_id) => {
+                tracing::warn!("Inbox item {} names unknown agent {}", item.id, agent_id);
+                None
+            }
+            Some(agent_id) => {
+                let msg =
+                    commands::connector_item_instruction(agent_id, INBOX_CONNECTOR_ID, &item, None);
+                match commands::hold_message(db, msg) {
+                    Ok(msg) => Some(msg.id),
+                    Err(error) => {
+                        tracing::warn!(
+                            "Inbox item {} not sent to {}: {}",
+                            item.id,
+                            agent_id,
+                            error
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        receipts.push(IntakeReceipt {
+            item_id: item.id,
+            title: item.title,
+            duplicate,
+            message_id,
+        });
+    }
+    Ok(receipts)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureKind {
+    InboxItem,
+    Instruction,
+}
+
+/// What a capture created, so a Shortcut can hand the id to its next step.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureResult {
+    pub kind: CaptureKind,
+    pub id: String,
+    pub agent_id: Option<String>,
+    pub project_id: Option<String>,
+    pub awaiting_approval: bool,
+}
+
+fn nonempty(key: Option<&str>) -> Option<&str> {
+    key.map(str::trim).filter(|key| !key.is_empty())
+}
+
+fn matches_key(id: &str, name: &str, key: &str) -> bool {
+    id == key || name.eq_ignore_ascii_case(key)
+}
+
+fn resolve_project(db: &Database, key: &str) -> Result<Project, CommandError> {
+    db.list_projects()?
+        .into_iter()
+        .find(|project| matches_key(&project.id, &project.name, key))
+        .ok_or_else(|| CommandError::not_found(format!("Project not found: {}", key)))
+}
+
+fn resolve_agent(
+    db: &Database,
+    key: &str,
+    project_id: Option<&str>,
+) -> Result<Agent, CommandError> {
+    db.list_agents()?
+        .into_iter()
+        .filter(|agent| project_id.is_none_or(|id| agent.project_id == id))
+        .find(|agent| matches_key(&agent.id, &agent.name, key))
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", key)))
+}
+
+/// Capture free text as an instruction to `agent`, or as an Inbox item when
+/// no agent is named. `hold_for_approval` keeps the instruction queued until
+/// approved, for captures arriving from links.
+pub fn quick_capture(
+    db: &Arc<Database>,
+    text: &str,
+    project: Option<&str>,
+    agent: Option<&str>,
+    hold_for_approval: bool,
+) -> Result<CaptureResult, CommandError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(CommandError::invalid_input("capture text is required"));
+    }
+    let project = nonempty(project)
+        .map(|key| resolve_project(db, key))
+        .transpose()?;
+    let project_id = project.map(|project| project.id);
+
+    if let Some(key) = nonempty(agent) {
+        let agent = resolve_agent(db, key, project_id.as_deref())?;
+        let mut msg = Message::to_agent(&agent.id, MessageKind::Instruction, text);
+        msg.metadata = Some(serde_json::json!({ "source": "quick_capture" }));
+        let msg = if hold_for_approval {
+            commands::hold_message(db, msg)?
+        } else {
+            commands::enqueue_message(db, msg)?
+        };
+        return Ok(CaptureResult {
+            kind: CaptureKind::Instruction,
+            awaiting_approval: msg.is_awaiting_approval(),
+            id: msg.id,
+            agent_id: Some(agent.id),
+            project_id: Some(agent.project_id),
+        });
+    }
+
+    let mut task = IntakeTask::from_text(text);
+    task.project_id = project_id.clone();
+    let receipt = ingest(db, vec![task], "capture")?
+        .pop()
+        .ok_or("capture produced no item")?;
+    Ok(CaptureResult {
+        kind: CaptureKind::InboxItem,
+        id: receipt.item_id,
+        agent_id: None,
+        project_id,
+        awaiting_approval: false,
+    })
+}
+
+/// Status line and JSON body for one request.
+pub fn handle_request(
+    db: &Arc<Database>,
+    head: &str,
+    body: &[u8],
+) -> (&'static str, serde_json::Value) {
+    let mut parts = head.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    if path.map(|path| path.split('?').next().unwrap_or(path)) != Some("/intake") {
+        return ("404 Not Found", serde_json::json!({ "error": "not found" }));
+    }
+    if method != Some("POST") {
+        return (
+            "405 Method Not Allowed",
+            serde_json::json!({ "error": "use POST" }),
+        );
+    }
+    if header(head, "origin").is_some() {
+        return (
+            "403 Forbidden",
+            serde_json::json!({ "error": "browser requests are not accepted" }),
+        );
+    }
+    if let Err(error) = api_tokens::authenticate(db, head, ApiTokenScope::ReadWrite) {
+        return (
+            error.status(),
+            serde_json::json!({ "error": error.message() }),
+        );
+    }
+    let result = serde_json::from_slice(body)
+        .map_err(|e| format!("body must be JSON: {}", e))
+        .and_then(inbox::parse_tasks)
+        .and_then(|tasks| ingest(db, tasks, "intake"));
+    match result {
+        Ok(receipts) => ("201 Created", serde_json::json!({ "items": receipts })),
+        Err(error) => ("400 Bad Request", serde_json::json!({ "error": error })),
+    }
+}
+
+/// Answer one parsed request (or one that couldn't be read).
+fn handle(db: &Arc<Database>, request: Result<Request, RequestError>) -> Response {
+    let (status, body) = match request {
+        Ok(request) => handle_request(db, &request.head, &request.body),
+        Err(RequestError::TooLarge) => (
+            "413 Payload Too Large",
+            serde_json::json!({ "error": "body too large" }),
+        ),
+        Err(RequestError::Malformed) => (
+            "400 Bad Request",
+            serde_json::json!({ "error": "malformed request" }),
+        ),
+    };
+    Response::json(status, body)
+}
+
+/// Pull pending tasks from the Inbox relay, if one is configured.
+pub async fn poll_relay(db: &Arc<Database>) -> Result<Vec<IntakeReceipt>, String> {
+    let Some(config) = db
+        .get_connector_config(INBOX_CONNECTOR_ID)
+        .map_err(|e| e.to_string())?
+        .filter(|config| config.enabled && config.settings.contains_key("relay_url"))
+    else {
+        return Ok(vec![]);
+    };
+    let connector =
+        InboxConnector::new(config.settings.get("relay_url").cloned(), config.auth_token);
+    let tasks = connector.fetch_relay().await.map_err(|e| e.to_string())?;
+    if tasks.is_empty() {
+        return Ok(vec![]);
+    }
+    ingest(db, tasks, "relay")
+}
+
+/// Follow the intake settings (start, move or stop the listener whenever
+/// settings are written) and poll the relay.
+pub fn spawn(db: Arc<Database>) {
+    let mut writes = db.subscribe_writes();
+    background::spawn("Inbox intake", async move {
+        let mut serving: Option<(String, tokio::task::JoinHandle<()>)> = None;
+        let mut relay = tokio::time::interval(RELAY_POLL_INTERVAL);
+        relay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            let settings: IntakeSettings = db
+                .get_setting(INTAKE_SETTINGS_KEY)
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            let wanted = settings.enabled.then_some(settings.bind_address);
+            if serving.as_ref().map(|(address, _)| address) != wanted.as_ref() {
+                if let Some((_, task)) = serving.take() {
+                    task.abort();
+                }
+                if let Some(address) = wanted {
+                    match TcpListener::bind(&address).await {
+                        Ok(listener) => {
+                            tracing::info!("Accepting inbox tasks on http://{}/intake", address);
+                            let db = db.clone();
+                            let task = tokio::spawn(local_http::serve(
+                                listener,
+                                "Intake",
+                                MAX_BODY_BYTES,
+                                move |request| handle(&db, request),
+                            ));
+                            serving = Some((address, task));
+                        }
+                        Err(error) => {
+                            tracing::warn!("Failed to bind intake endpoint {}: {}", address, error)
+                        }
+                    }
+                }
+            }
+
+            let woken_by_write = tokio::select! {
+                open = background::wait_for_write(&mut writes, &["settings"]) => Some(open),
+                _ = relay.tick() => None,
+            };
+            match woken_by_write {
+                Some(false) => return,
+                Some(true) => background::settle(&mut writes).await,
+                None => match poll_relay(&db).await {
+                    Ok(receipts) if !receipts.is_empty() => tracing::info!(
+                        "Received {} task(s) from the inbox relay",
+                        receipts.iter().filter(|receipt| !receipt.duplicate).count()
+                    ),
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!("Inbox relay poll failed: {}", error),
+                },
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::models::*;
+
+    #[test]
+    fn quick_capture_creates_items_or_instructions() {
+        let db = Arc::new(Database::new(":memory:").expect("db should open"));
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Api, "docs");
+        db.create_agent(&agent).expect("agent should save");
+
+        let note = quick_capture(
+            &db,
+            "  Renew the cert\nExpires Friday  ",
+            Some("site"),
+            None,
+            false,
+        )
+        .expect("capture should create an item");
+        assert_eq!(note.kind, CaptureKind::InboxItem);
+        assert_eq!(note.project_id.as_deref(), Some(project.id.as_str()));
+        let item = db
+            .get_connector_item(INBOX_CONNECTOR_ID, &note.id)
+            .unwrap()
+            .expect("item should be cached");
+        assert_eq!(item.title, "Renew the cert");
+        assert_eq!(
+            item.content.as_deref(),
+            Some("Renew the cert\nExpires Friday")
+        );
+        assert_eq!(item.metadata.get("received_via").unwrap(), "capture");
+        assert_eq!(item.metadata.get("project_id"), Some(&project.id));
+
+        let sent = quick_capture(&db, "Update the README", None, Some("DOCS"), false)
+            .expect("capture should instruct the agent");
+        assert_eq!(sent.kind, CaptureKind::Instruction);
+        assert!(!sent.awaiting_approval);
+        let pending = db.get_pending_messages(&agent.id).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, sent.id);
+
+        let held = quick_capture(&db, "Delete old branches", None, Some(&agent.id), true)
+            .expect("capture should be held");
+        assert!(held.awaiting_approval);
+        assert_eq!(db.get_pending_messages(&agent.id).unwrap().len(), 1);
+
+        let code = |result: Result<CaptureResult, CommandError>| result.unwrap_err().code;
+        assert_eq!(
+            code(quick_capture(&db, "  ", None, None, false)),
+            ErrorCode::InvalidInput
+        );
+        assert_eq!(
+            code(quick_capture(&db, "Hi", None, Some("Nobody"), false)),
+            ErrorCode::NotFound
+        );
+        assert_eq!(
+            code(quick_capture(&db, "Hi", Some("Elsewhere"), None, false)),
+            ErrorCode::NotFound
+        );
+    }
+
+    #[test]
+    fn intake_creates_inbox_items_and_holds_instructions() {
+        let db = Arc::new(Database::new(":memory:").expect("db should open"));
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Api, "docs");
+        db.create_agent(&agent).expect("agent should save");
+
+        let body = serde_json::json!({
+            "tasks": [
+                { "id": "form-1", "subject": "Fix typo", "body": "On the pricing page", "tags": "web, urgent" },
+                { "title": "Write changelog", "agent": agent.id },
+            ]
+        })
+        .to_string();
+
+        // With no token configured nothing gets in.
+        let (status, _) = handle_request(&db, "POST /intake HTTP/1.1\r\n\r\n", body.as_bytes());
+        assert_eq!(status, "401 Unauthorized");
+
+        let writer = api_tokens::create(&db, "Zapier", ApiTokenScope::ReadWrite).unwrap();
+        let reader = api_tokens::create(&db, "Grafana", ApiTokenScope::ReadOnly).unwrap();
+        let head = format!(
+            "POST /intake HTTP/1.1\r\nAuthorization: Bearer {}\r\n\r\n",
+            writer.secret
+        );
+        let (status, response) = handle_request(&db, &head, body.as_bytes());
+        assert_eq!(status, "201 Created");
+        assert_eq!(response["items"][0]["item_id"], "form-1");
+        assert!(response["items"][0]["message_id"].is_null());
+
+        let items = db.get_connector_items(INBOX_CONNECTOR_ID).unwrap();
+        assert_eq!(items.len(), 2);
+        let typo = items.iter().find(|item| item.id == "form-1").unwrap();
+        assert_eq!(typo.tags, vec!["web", "urgent"]);
+        assert_eq!(typo.content.as_deref(), Some("On the pricing page"));
+        assert!(db
+            .get_connector_config(INBOX_CONNECTOR_ID)
+            .unwrap()
+            .is_some());
+        // The named agent's instruction waits for approval.
+        let message_id = response["items"][1]["message_id"].as_str().unwrap();
+        let held = db.get_message(message_id).unwrap().unwrap();
+        assert!(held.is_awaiting_approval());
+        assert!(held.content.starts_with("Write changelog"));
+        assert!(db.get_pending_messages(&agent.id).unwrap().is_empty());
+
+        // Resending the same form entry doesn't duplicate it.
+        let (_, response) = handle_request(&db, &head, br#"{"id": "form-1", "title": "Fix typo"}"#);
+        assert_eq!(response["items"][0]["duplicate"], true);
+        assert_eq!(db.get_connector_items(INBOX_CONNECTOR_ID).unwrap().len(), 2);
+
+        let (status, _) = handle_request(&db, &head, br#"{"title": "  "}"#);
+        assert_eq!(status, "400 Bad Request");
+        let (status, _) = handle_request(&db, "GET /intake HTTP/1.1\r\n\r\n", b"");
+        assert_eq!(status, "405 Method Not Allowed");
+
+        let task = br#"{"title": "From a page"}"#;
+        let from_browser = format!(
+            "POST /intake HTTP/1.1\r\nOrigin: https://example.com\r\nAuthorization: Bearer {}\r\n\r\n",
+            writer.secret
+        );
+        let (status, _) = handle_request(&db, &from_browser, task);
+        assert_eq!(status, "403 Forbidden");
+        let read_only = head.replace(&writer.secret, &reader.secret);
+        let (status, _) = handle_request(&db, &read_only, task);
+        assert_eq!(status, "403 Forbidden");
+        assert_eq!(db.get_connector_items(INBOX_CONNECTOR_ID).unwrap().len(), 2);
+
+        let open = IntakeSettings {
+            enabled: true,
+            bind_address: "0.0.0.0:9465".to_string(),
+        };
+        assert!(open.validate().is_ok());
+        assert!(IntakeSettings {
+            bind_address: "localhost".to_string(),
+            ..IntakeSettings::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -1,14 +1,54 @@
+pub mod activity;
+pub mod agenda;
+pub mod agent_bundle;
+pub mod agent_logs;
 pub mod agents;
+pub mod analytics;
+pub mod api_tokens;
+pub mod background;
+pub mod backup;
+pub mod board;
+pub mod clock;
 pub mod commands;
 pub mod connectors;
+pub mod context_docs;
+pub mod daily_stats;
+pub mod dashboard_cache;
 pub mod db;
+pub mod deep_link;
+pub mod diagnostics;
+pub mod digest;
+pub mod errors;
+pub mod export;
+pub mod git;
+pub mod git_monitor;
+pub mod github;
+pub mod intake;
+pub mod local_http;
+pub mod mcp;
+pub mod metrics;
 pub mod models;
+pub mod notifications;
+pub mod pipelines;
+pub mod project_bundle;
+pub mod redaction;
+pub mod routing;
+pub mod run_archive;
 pub mod seed;
+pub mod snapshot_crypto;
+pub mod summarize;
+pub mod timezone;
+pub mod traces;
+pub mod tray;
+pub mod watchdog;
 pub mod watchers;
+pub mod webhooks;
+pub mod workdir;
 
 use db::Database;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::Manager;
@@ -60,7 +100,7 @@ fn copy_legacy_db_files(from_dir: &Path, to_dir: &Path) -> Result<bool, String>
                 error
             )
         })?;
-        log::info!(
+        tracing::info!(
             "Migrated legacy database artifact {} -> {}",
             source.display(),
             destination.display()
@@ -96,14 +136,14 @@ fn migrate_legacy_database(app_data: &Path) {
             Ok(true) => return,
             Ok(false) => continue,
             Err(error) => {
-                log::warn!("Legacy database migration failed: {}", error);
+                tracing::warn!("Legacy database migration failed: {}", error);
                 return;
             }
         }
     }
 }
 
-fn collect_watch_paths(agent: &models::Agent) -> Vec<String> {
+pub(crate) fn collect_watch_paths(agent: &models::Agent) -> Vec<String> {
     let mut paths = Vec::new();
     if let Some(working_directory) = &agent.working_directory {
         if !working_directory.trim().is_empty() {
@@ -118,131 +158,322 @@ fn collect_watch_paths(agent: &models::Agent) -> Vec<String> {
     paths
 }
 
-fn change_label(change_type: &models::FileChangeType) -> &'static str {
-    match change_type {
-        models::FileChangeType::Created => "created",
-        models::FileChangeType::Modified => "modified",
-        models::FileChangeType::Deleted => "deleted",
-        models::FileChangeType::Renamed => "renamed",
+fn record_watcher_event(
+    db: &Database,
+    rollup: &mut watchers::ChangeRollup,
+    event: watchers::AgentFileEvent,
+) {
+    match db.file_change_is_agent_activity(&event.agent_id) {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::debug!(
+                "Ignoring change to {} for agent {} with no active run",
+                event.change.path,
+                event.agent_id
+            );
+            return;
+        }
+        Err(error) => {
+            tracing::warn!(
+                "Failed to check run state for agent {}: {}",
+                event.agent_id,
+                error
+            );
+            return;
+        }
+    }
+
+    let mut change = event.change;
+    watchers::annotate_line_stats(&mut change);
+    if let Err(error) = db.record_file_change(&event.agent_id, change.clone()) {
+        tracing::warn!(
+            "Failed to record file change for agent {}: {}",
+            event.agent_id,
+            error
+        );
+        return;
+    }
+
+    let _ = db.update_agent_status(&event.agent_id, &models::AgentStatus::Running);
+    rollup.push(&event.agent_id, change, Instant::now());
+}
+
+fn flush_watcher_rollup(db: &Database, agent_id: &str, changes: &[models::FileChange]) {
+    let Some(message) = watchers::rollup_message(agent_id, changes) else {
+        return;
+    };
+    if let Err(error) = db.insert_message(&message) {
+        tracing::warn!(
+            "Failed to insert watcher status message for agent {}: {}",
+            agent_id,
+            error
+        );
+    }
+}
+
+/// Watch state owned by the watcher thread: the watcher itself plus the
+/// (agent_id, canonical root) pairs currently registered with it.
+struct WatchRegistry {
+    watcher: watchers::FileSystemWatcher,
+    watched_pairs: HashSet<(String, String)>,
+}
+
+impl WatchRegistry {
+    fn sync_agent(&mut self, agent: &models::Agent) {
+        self.watcher.set_agent_globs(
+            &agent.id,
+            &agent.config.ignore_globs,
+            &agent.config.include_globs,
+        );
+        self.watcher
+            .set_agent_scope(&agent.id, &agent.config.allowed_paths);
+        let scope = watchers::PathScope::for_agent(agent);
+
+        let mut wanted_pairs = HashSet::<(String, String)>::new();
+        // Paused agents keep their paths but release every watch.
+        let paths = if agent.config.watching_paused {
+            vec![]
+        } else {
+            collect_watch_paths(agent)
+        };
+        for raw_path in paths {
+            let expanded_path = shellexpand::tilde(raw_path.trim()).to_string();
+            if expanded_path.is_empty() {
+                continue;
+            }
+            if !scope.contains(Path::new(&expanded_path)) {
+                tracing::warn!(
+                    "Not watching {} for agent {}: outside its allowed paths",
+                    expanded_path,
+                    agent.id
+                );
+                continue;
+            }
+
+            let canonical_path = std::fs::canonicalize(&expanded_path)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or(expanded_path.clone());
+            let watch_key = (agent.id.clone(), canonical_path);
+            wanted_pairs.insert(watch_key.clone());
+            if self.watched_pairs.contains(&watch_key) {
+                continue;
+            }
+
+            let force_poll = agent.config.poll_paths.iter().any(|poll_path| {
+                let poll_path = shellexpand::tilde(poll_path.trim()).to_string();
+                !poll_path.is_empty() && Path::new(&expanded_path).starts_with(&poll_path)
+            });
+            match self
+                .watcher
+                .watch_path(&expanded_path, &agent.id, force_poll)
+            {
+                Ok(true) => {
+                    self.watched_pairs.insert(watch_key);
+                }
+                Ok(false) => {}
+                Err(error) => {
+                    tracing::warn!(
+                        "Failed to watch {} for agent {}: {}",
+                        expanded_path,
+                        agent.id,
+                        error
+                    );
+                }
+            }
+        }
+
+        // Roots the agent no longer claims stop receiving its events.
+        let stale_pairs: Vec<(String, String)> = self
+            .watched_pairs
+            .iter()
+            .filter(|pair| pair.0 == agent.id && !wanted_pairs.contains(*pair))
+            .cloned()
+            .collect();
+        for (agent_id, canonical_path) in stale_pairs {
+            self.watcher.release_path(&canonical_path, &agent_id);
+            self.watched_pairs.remove(&(agent_id, canonical_path));
+        }
     }
+
+    fn remove_agent(&mut self, agent_id: &str) {
+        self.watcher.set_agent_globs(agent_id, &[], &[]);
+        self.watcher.set_agent_scope(agent_id, &[]);
+        let stale_pairs: Vec<(String, String)> = self
+            .watched_pairs
+            .iter()
+            .filter(|pair| pair.0 == agent_id)
+            .cloned()
+            .collect();
+        for (agent_id, canonical_path) in stale_pairs {
+            self.watcher.release_path(&canonical_path, &agent_id);
+            self.watched_pairs.remove(&(agent_id, canonical_path));
+        }
+    }
+
+    fn resync(&mut self, db: &Database) {
+        let agents = match db.list_agents() {
+            Ok(agents) => agents,
+            Err(error) => {
+                tracing::warn!("Failed to list agents for watcher sync: {}", error);
+                return;
+            }
+        };
+        let agent_ids: HashSet<String> = agents.iter().map(|agent| agent.id.clone()).collect();
+        let removed: HashSet<String> = self
+            .watched_pairs
+            .iter()
+            .filter(|(agent_id, _)| !agent_ids.contains(agent_id))
+            .map(|(agent_id, _)| agent_id.clone())
+            .collect();
+        for agent_id in removed {
+            self.remove_agent(&agent_id);
+        }
+        for agent in &agents {
+            self.sync_agent(agent);
+        }
+        self.watcher.retain_agents(&agent_ids);
+    }
+}
+
+fn apply_watcher_settings(
+    watcher: &mut watchers::FileSystemWatcher,
+    debouncer: &mut watchers::ChangeDebouncer,
+    rollup: &mut watchers::ChangeRollup,
+    settings: &watchers::WatcherSettings,
+) {
+    debouncer.set_window(settings.debounce_window());
+    rollup.set_window(settings.rollup_window());
+    watcher.set_poll_settings(settings.poll_interval(), settings.auto_poll_network_mounts);
 }
 
-fn spawn_filesystem_watcher(db: Arc<Database>) {
+fn spawn_filesystem_watcher(
+    db: Arc<Database>,
+    stats: watchers::WatcherStats,
+    commands: Receiver<watchers::WatcherCommand>,
+) {
     std::thread::spawn(move || {
-        let mut watcher = match watchers::FileSystemWatcher::new() {
+        let watcher = match watchers::FileSystemWatcher::with_stats(stats.clone()) {
             Ok(watcher) => watcher,
             Err(error) => {
-                log::warn!("Failed to initialize file watcher: {}", error);
+                tracing::warn!("Failed to initialize file watcher: {}", error);
                 return;
             }
         };
+        let mut registry = WatchRegistry {
+            watcher,
+            watched_pairs: HashSet::new(),
+        };
 
-        let mut watched_pairs = HashSet::<String>::new();
-        let mut last_sync = Instant::now() - Duration::from_secs(10);
+        let settings = db
+            .get_setting::<watchers::WatcherSettings>(watchers::WATCHER_SETTINGS_KEY)
+            .unwrap_or_else(|error| {
+                tracing::warn!("Failed to load watcher settings: {}", error);
+                None
+            })
+            .unwrap_or_default();
+        let mut debouncer = watchers::ChangeDebouncer::new(settings.debounce_window());
+        let mut rollup = watchers::ChangeRollup::new(settings.rollup_window());
+        apply_watcher_settings(
+            &mut registry.watcher,
+            &mut debouncer,
+            &mut rollup,
+            &settings,
+        );
+        registry.resync(&db);
 
         loop {
-            if last_sync.elapsed() >= Duration::from_secs(5) {
-                match db.list_agents() {
-                    Ok(agents) => {
-                        for agent in agents {
-                            for raw_path in collect_watch_paths(&agent) {
-                                let expanded_path = shellexpand::tilde(raw_path.trim()).to_string();
-                                if expanded_path.is_empty() {
-                                    continue;
-                                }
-
-                                let canonical_path = std::fs::canonicalize(&expanded_path)
-                                    .map(|path| path.to_string_lossy().to_string())
-                                    .unwrap_or(expanded_path.clone());
-                                let watch_key = format!("{}::{}", agent.id, canonical_path);
-                                if watched_pairs.contains(&watch_key) {
-                                    continue;
-                                }
-
-                                match watcher.watch_path(&expanded_path, &agent.id) {
-                                    Ok(true) => {
-                                        watched_pairs.insert(watch_key);
-                                    }
-                                    Ok(false) => {}
-                                    Err(error) => {
-                                        log::warn!(
-                                            "Failed to watch {} for agent {}: {}",
-                                            expanded_path,
-                                            agent.id,
-                                            error
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(error) => {
-                        log::warn!("Failed to list agents for watcher sync: {}", error);
+            for command in commands.try_iter() {
+                match command {
+                    watchers::WatcherCommand::AgentChanged(agent) => registry.sync_agent(&agent),
+                    watchers::WatcherCommand::AgentRemoved(agent_id) => {
+                        registry.remove_agent(&agent_id)
                     }
+                    watchers::WatcherCommand::SettingsChanged(settings) => apply_watcher_settings(
+                        &mut registry.watcher,
+                        &mut debouncer,
+                        &mut rollup,
+                        &settings,
+                    ),
+                    watchers::WatcherCommand::Resync => registry.resync(&db),
                 }
-                last_sync = Instant::now();
             }
 
             loop {
-                match watcher.receiver.try_recv() {
+                match registry.watcher.receiver.try_recv() {
                     Ok(event) => {
-                        let change = event.change.clone();
-                        if let Err(error) = db.record_file_change(&event.agent_id, change.clone()) {
-                            log::warn!(
-                                "Failed to record file change for agent {}: {}",
-                                event.agent_id,
-                                error
-                            );
-                            continue;
-                        }
-
-                        let _ =
-                            db.update_agent_status(&event.agent_id, &models::AgentStatus::Running);
-
-                        let filename = Path::new(&change.path)
-                            .file_name()
-                            .and_then(|name| name.to_str())
-                            .unwrap_or(change.path.as_str());
-                        let change_kind = change_label(&change.change_type);
-                        let content = format!("File {}: {}", change_kind, filename);
-
-                        let mut message = models::Message::from_agent(
-                            &event.agent_id,
-                            models::MessageKind::StatusUpdate,
-                            &content,
-                        );
-                        message.metadata = Some(serde_json::json!({
-                            "path": change.path,
-                            "change_type": change_kind,
-                        }));
-                        if let Err(error) = db.insert_message(&message) {
-                            log::warn!(
-                                "Failed to insert watcher status message for agent {}: {}",
-                                event.agent_id,
-                                error
-                            );
-                        }
+                        stats.record_dequeued();
+                        debouncer.push(event, Instant::now());
                     }
                     Err(TryRecvError::Empty) => break,
                     Err(TryRecvError::Disconnected) => {
-                        log::warn!("File watcher channel disconnected; stopping watcher loop");
+                        tracing::warn!("File watcher channel disconnected; stopping watcher loop");
                         return;
                     }
                 }
             }
 
+            for event in debouncer.drain_ready(Instant::now()) {
+                record_watcher_event(&db, &mut rollup, event);
+            }
+            stats.set_debouncing(debouncer.pending_len());
+            for (agent_id, changes) in rollup.drain_ready(Instant::now()) {
+                flush_watcher_rollup(&db, &agent_id, &changes);
+            }
+
             std::thread::sleep(Duration::from_millis(300));
         }
     });
 }
 
+fn register_deep_links(app: &tauri::App, db: Arc<Database>) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Linux and Windows dev builds aren't installed, so the scheme has to be
+    // registered at runtime; macOS takes it from the bundle.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    if let Err(error) = app.deep_link().register_all() {
+        tracing::warn!("Failed to register kanbun:// links: {}", error);
+    }
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            deep_link::handle(app.handle(), &db, url.as_str());
+        }
+    }
+    let handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            deep_link::handle(&handle, &db, url.as_str());
+        }
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    env_logger::init();
+    traces::install();
+
+    if std::env::args().any(|arg| arg == mcp::MCP_FLAG) {
+        if let Err(error) = mcp::run() {
+            tracing::error!("MCP server stopped: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     tauri::Builder::default()
+        // Must come first: a second launch (e.g. from a kanbun:// link) hands
+        // its URL to this instance and exits.
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // Initialize database in app data directory
             let app_data = app
@@ -251,48 +482,225 @@ pub fn run() {
                 .expect("failed to get app data dir");
             std::fs::create_dir_all(&app_data).expect("failed to create app data dir");
             migrate_legacy_database(&app_data);
+            agent_logs::install(app_data.join("logs").join("agents"));
             let db_path = app_data.join("kanbun.db");
             let db = Arc::new(
                 Database::new(db_path.to_str().unwrap()).expect("failed to initialize database"),
             );
 
-            // Optional sample seed for demos/dev. Disabled by default.
-            if env_flag("KANBUN_SEED_SAMPLE_DATA")
-                && db.list_projects().unwrap_or_default().is_empty()
-            {
-                log::info!("Seeding initial data...");
-                seed::seed_initial_data(db.as_ref());
-            }
             seed::ensure_default_adapter_configs(db.as_ref());
-            spawn_filesystem_watcher(db.clone());
+            match watchdog::recover_orphaned_runs(db.as_ref(), |agent_id| {
+                agents::has_live_session(db.as_ref(), agent_id)
+            }) {
+                Ok(recovered) if !recovered.is_empty() => {
+                    tracing::info!("Marked {} interrupted run(s) for review", recovered.len())
+                }
+                Ok(_) => {}
+                Err(error) => tracing::warn!("Failed to recover interrupted runs: {}", error),
+            }
+            let watcher_stats = watchers::WatcherStats::default();
+            let (watcher_handle, watcher_commands) = watchers::WatcherHandle::channel();
+            spawn_filesystem_watcher(db.clone(), watcher_stats.clone(), watcher_commands);
+            app.manage(watcher_stats);
+            app.manage(watcher_handle);
+            app.manage(dashboard_cache::DashboardCache::new(&db));
+            commands::spawn_adapter_supervisor(db.clone(), app.handle().clone());
+            git_monitor::spawn(db.clone());
+            watchdog::spawn(db.clone());
+            summarize::spawn(db.clone());
+            daily_stats::spawn(db.clone());
+            digest::spawn(db.clone());
+            agenda::spawn(db.clone());
+            pipelines::spawn(db.clone());
+            board::spawn(db.clone());
+            context_docs::spawn(db.clone());
+            github::spawn(db.clone());
+            backup::spawn(db.clone());
+            run_archive::spawn(db.clone());
+            // Woken by database writes, on the shared background runtime.
+            notifications::spawn(db.clone(), app.handle().clone());
+            webhooks::spawn(db.clone());
+            intake::spawn(db.clone());
+            metrics::spawn(db.clone());
+            traces::spawn_exporter(db.clone());
+            if let Err(error) = tray::spawn(app.handle(), db.clone()) {
+                tracing::warn!("Failed to create tray icon: {}", error);
+            }
+            register_deep_links(app, db.clone());
 
             app.manage(db);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_dashboard,
+            commands::list_dashboard_views,
+            commands::save_dashboard_view,
+            commands::delete_dashboard_view,
+            commands::dismiss_attention,
+            commands::snooze_attention,
             commands::get_agent_detail,
+            commands::get_run_detail,
+            commands::get_run_outputs,
             commands::create_project,
             commands::list_project_context_docs,
             commands::save_project_context_doc,
             commands::delete_project_context_doc,
+            commands::import_context_docs,
+            commands::list_context_doc_templates,
+            commands::save_context_doc_template,
+            commands::delete_context_doc_template,
+            commands::create_doc_from_template,
+            commands::get_agent_context_docs,
+            commands::set_agent_context_docs,
+            commands::list_doc_revisions,
+            commands::restore_doc_revision,
+            commands::diff_doc_revisions,
             commands::create_agent,
+            commands::update_agent,
+            commands::validate_working_directory,
+            commands::route_instruction,
             commands::update_agent_status,
+            commands::set_watching,
+            commands::clone_agent,
+            commands::export_agents_yaml,
+            commands::import_agents_yaml,
+            commands::export_project,
+            commands::import_project,
+            commands::pin_agent,
+            commands::list_agent_groups,
+            commands::create_agent_group,
+            commands::rename_agent_group,
+            commands::delete_agent_group,
+            commands::add_agent_to_group,
+            commands::remove_agent_from_group,
+            commands::send_group_instruction,
+            commands::set_group_paused,
+            commands::get_board,
+            commands::save_task,
+            commands::move_task,
+            commands::delete_task,
+            commands::assign_task,
+            commands::resolve_task_conflict,
+            commands::get_board_settings,
+            commands::set_board_settings,
+            commands::list_pipelines,
+            commands::save_pipeline,
+            commands::delete_pipeline,
+            commands::get_pipeline_status,
+            commands::list_notification_rules,
+            commands::save_notification_rule,
+            commands::delete_notification_rule,
+            commands::get_notification_center,
+            commands::mark_notifications_read,
+            commands::get_chat_notification_settings,
+            commands::set_chat_notification_settings,
+            commands::send_test_chat_notification,
+            commands::get_push_settings,
+            commands::set_push_settings,
+            commands::send_test_push,
+            commands::get_metrics_settings,
+            commands::set_metrics_settings,
+            commands::get_recent_traces,
+            commands::get_agent_logs,
+            commands::get_tracing_settings,
+            commands::set_tracing_settings,
+            commands::get_timezone_settings,
+            commands::set_timezone_settings,
+            commands::get_github_settings,
+            commands::set_github_settings,
+            commands::report_run_to_github,
+            commands::get_intake_settings,
+            commands::set_intake_settings,
+            commands::quick_capture,
+            commands::get_deep_link_settings,
+            commands::set_deep_link_settings,
+            commands::list_api_tokens,
+            commands::create_api_token,
+            commands::revoke_api_token,
+            commands::list_webhook_subscriptions,
+            commands::save_webhook_subscription,
+            commands::delete_webhook_subscription,
+            commands::send_test_webhook,
+            commands::list_agent_templates,
+            commands::save_agent_template,
+            commands::delete_agent_template,
+            commands::create_agent_from_template,
+            commands::reorder_pinned_agents,
             commands::send_message,
+            commands::reply_to_blocked,
+            commands::list_paused_agents,
+            commands::pause_all_agents,
+            commands::resume_all_agents,
+            commands::list_pending_approvals,
+            commands::approve_message,
+            commands::reject_message,
             commands::get_conversation,
+            commands::export_conversation,
+            commands::export_run_report,
             commands::receive_message,
             commands::poll_pending_messages,
+            commands::get_agent_queue,
+            commands::review_run,
+            commands::set_run_labels,
+            commands::list_run_comments,
+            commands::add_run_comment,
+            commands::edit_run_comment,
+            commands::delete_run_comment,
+            commands::list_runs_by_label,
+            commands::get_run_analytics,
+            commands::get_stats_timeseries,
+            commands::get_run_archive_settings,
+            commands::set_run_archive_settings,
+            commands::archive_runs,
+            commands::list_archived_runs,
+            commands::restore_run,
+            commands::get_activity_feed,
+            commands::get_agenda,
+            commands::export_agenda_ics,
+            commands::get_calendar_feed_settings,
+            commands::set_calendar_feed_settings,
+            commands::get_digest_settings,
+            commands::set_digest_settings,
+            commands::generate_weekly_digest,
+            commands::get_adapter_config_for_edit,
             commands::set_adapter_config,
+            commands::validate_adapter_config,
+            commands::get_demo_workspace,
+            commands::create_demo_workspace,
+            commands::delete_demo_workspace,
+            commands::record_simulation_transcript,
             commands::get_adapter_health,
+            commands::get_all_adapter_health,
+            commands::get_diagnostics,
             commands::restart_adapter,
+            commands::handoff_agent,
             commands::export_database_snapshot,
             commands::import_database_snapshot,
+            commands::get_cloud_backup_settings,
+            commands::set_cloud_backup_settings,
+            commands::run_cloud_backup,
+            commands::list_cloud_backups,
+            commands::restore_cloud_backup,
+            commands::get_redaction_settings,
+            commands::set_redaction_settings,
+            commands::get_watchdog_settings,
+            commands::set_watchdog_settings,
+            commands::get_watcher_settings,
+            commands::get_watcher_status,
+            commands::set_watcher_settings,
+            commands::get_summarizer_settings,
+            commands::set_summarizer_settings,
+            commands::summarize_run,
             commands::list_connectors,
             commands::save_connector,
             commands::get_connector_configs,
             commands::sync_connector,
             commands::get_connector_items,
+            commands::get_connector_item_detail,
             commands::push_connector_item,
+            commands::send_connector_item_to_agent,
+            commands::list_runs_for_connector_item,
+            commands::create_agents_from_connector_items,
             commands::delete_connector_item,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,197 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+// ── Local HTTP endpoints ────────────────────────────────────────────────────
+// The intake and metrics listeners speak just enough HTTP/1.1 for form
+// backends and scrapers: one request per connection, headers up to
+// MAX_HEADER_BYTES, a body only as long as Content-Length says, and every
+// response closes the connection. A client gets READ_TIMEOUT to send its
+// request, and at most MAX_CONNECTIONS are served at once; past that the
+// listener stops accepting until one finishes.
+
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_CONNECTIONS: usize = 32;
+
+/// The request line and headers as text, and the body.
+pub struct Request {
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn method(&self) -> Option<&str> {
+        self.head.split_whitespace().next()
+    }
+
+    /// The path without its query string.
+    pub fn path(&self) -> Option<&str> {
+        let target = self.head.split_whitespace().nth(1)?;
+        Some(target.split('?').next().unwrap_or(target))
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.head, name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError {
+    Malformed, // connection closed or headers too long before the blank line
+    TooLarge,  // Content-Length over the endpoint's limit
+}
+
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn text(status: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: body.into(),
+        }
+    }
+
+    pub fn json(status: &'static str, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+pub fn header<'a>(head: &'a str, wanted: &str) -> Option<&'a str> {
+    head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case(wanted)
+            .then(|| value.trim())
+    })
+}
+
+/// Whether a `host:port` bind address only accepts connections from this
+/// machine. Addresses that don't parse count as not loopback.
+pub fn is_loopback(bind_address: &str) -> bool {
+    bind_address
+        .parse::<SocketAddr>()
+        .is_ok_and(|address| address.ip().is_loopback())
+}
+
+async fn read_request(
+    stream: &mut TcpStream,
+    max_body: usize,
+) -> std::io::Result<Result<Request, RequestError>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let header_end = loop {
+        if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_HEADER_BYTES {
+            return Ok(Err(RequestError::Malformed));
+        }
+        request.extend_from_slice(&buffer[..read]);
+    };
+    let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+    let length = header(&head, "content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > max_body {
+        return Ok(Err(RequestError::TooLarge));
+    }
+    while request.len() < header_end + length {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let end = request.len().min(header_end + length);
+    Ok(Ok(Request {
+        head,
+        body: request[header_end..end].to_vec(),
+    }))
+}
+
+async fn respond<H>(mut stream: TcpStream, max_body: usize, handle: &H) -> std::io::Result<()>
+where
+    H: Fn(Result<Request, RequestError>) -> Response,
+{
+    let request = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream, max_body))
+        .await
+        .map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::TimedOut, "request not sent in time")
+        })??;
+    let response = handle(request);
+    let text = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    );
+    stream.write_all(text.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Accept connections until the task is aborted, answering each with
+/// `handle`. `name` labels failures in the log.
+pub async fn serve<H>(listener: TcpListener, name: &'static str, max_body: usize, handle: H)
+where
+    H: Fn(Result<Request, RequestError>) -> Response + Send + Sync + 'static,
+{
+    let handle = Arc::new(handle);
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let Ok(permit) = connections.clone().acquire_owned().await else {
+            return;
+        };
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = respond(stream, max_body, handle.as_ref()).await {
+                        tracing::debug!("{} request failed: {}", name, error);
+                    }
+                    drop(permit);
+                });
+            }
+            Err(error) => tracing::warn!("{} listener accept failed: {}", name, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_headers_and_bind_addresses() {
+        let request = Request {
+            head: "POST /intake?via=form HTTP/1.1\r\ncontent-length: 2\r\nOrigin: x\r\n\r\n"
+                .to_string(),
+            body: b"{}".to_vec(),
+        };
+        assert_eq!(request.method(), Some("POST"));
+        assert_eq!(request.path(), Some("/intake"));
+        assert_eq!(request.header("Content-Length"), Some("2"));
+        assert_eq!(request.header("origin"), Some("x"));
+        assert_eq!(request.header("authorization"), None);
+
+        assert!(is_loopback("127.0.0.1:9464"));
+        assert!(is_loopback("[::1]:9464"));
+        assert!(!is_loopback("0.0.0.0:9464"));
+        assert!(!is_loopback("192.168.1.20:9464"));
+        assert!(!is_loopback("localhost:9464"));
+    }
+}
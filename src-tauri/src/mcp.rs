@@ -0,0 +1,450 @@
+use crate::agents;
+use crate::commands;
+use crate::context_docs;
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+// ── MCP server ──────────────────────────────────────────────────────────────
+// `kanbun --mcp` speaks the Model Context Protocol over stdio (newline
+// delimited JSON-RPC 2.0) against the app's database, so MCP clients can list
+// agents, send them instructions, and — as an Api agent — poll for work and
+// report back. Instructions are only queued here; the running app's adapter
+// supervisor delivers them.
+
+pub const MCP_FLAG: &str = "--mcp";
+const APP_IDENTIFIER: &str = "com.kanbun.desktop";
+const DEFAULT_PROTOCOL_VERSION: &str = "2024-11-05";
+const REPORTABLE_KINDS: &[&str] = &[
+    "status_update",
+    "output",
+    "heartbeat",
+    "blocked",
+    "error",
+    "completed",
+];
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// `KANBUN_DB_PATH`, else the database the desktop app keeps in its app data
+/// directory.
+pub fn database_path() -> Result<PathBuf, String> {
+    if let Ok(path) = std::env::var("KANBUN_DB_PATH") {
+        if !path.trim().is_empty() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER).join("kanbun.db"))
+        .ok_or_else(|| "could not determine the app data directory".to_string())
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "list_agents",
+            "description": "List Kanbun agents with their project and current status.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project_id": { "type": "string", "description": "Only agents in this project" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["idle", "running", "blocked", "errored", "completed"]
+                    }
+                }
+            }
+        },
+        {
+            "name": "send_instruction",
+            "description": "Queue an instruction for an agent. Manual agents hold it for approval.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "agent_id": { "type": "string" },
+                    "content": { "type": "string" }
+                },
+                "required": ["agent_id", "content"]
+            }
+        },
+        {
+            "name": "poll_instructions",
+            "description": "Fetch and mark delivered the instructions waiting for an agent.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "agent_id": { "type": "string" } },
+                "required": ["agent_id"]
+            }
+        },
+        {
+            "name": "report_status",
+            "description": "Report progress from an agent. blocked, error and completed finish the current run.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "agent_id": { "type": "string" },
+                    "kind": { "type": "string", "enum": REPORTABLE_KINDS },
+                    "content": { "type": "string" },
+                    "run_id": { "type": "string", "description": "Instruction message id the report belongs to" }
+                },
+                "required": ["agent_id", "kind", "content"]
+            }
+        },
+        {
+            "name": "get_project_context",
+            "description": "Project details and context documents, by project or by one of its agents. For an agent, only the documents picked for it, in order, if it has any.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project_id": { "type": "string" },
+                    "agent_id": { "type": "string" }
+                }
+            }
+        }
+    ])
+}
+
+fn string_arg<'a>(args: &'a Value, name: &str) -> Option<&'a str> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn required_arg<'a>(args: &'a Value, name: &str) -> Result<&'a str, CommandError> {
+    string_arg(args, name)
+        .ok_or_else(|| CommandError::invalid_input(format!("missing argument: {}", name)))
+}
+
+fn find_agent(db: &Database, agent_id: &str) -> Result<Agent, CommandError> {
+    db.get_agent(agent_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Agent not found: {}", agent_id)))
+}
+
+fn list_agents(db: &Database, args: &Value) -> Result<Value, CommandError> {
+    let status: Option<AgentStatus> = string_arg(args, "status")
+        .map(|status| serde_json::from_value(json!(status)))
+        .transpose()
+        .map_err(|_| CommandError::invalid_input("unknown status"))?;
+    let projects = db.list_projects().map_err(|e| e.to_string())?;
+    let agents: Vec<Value> = db
+        .list_agents()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|agent| string_arg(args, "project_id").is_none_or(|id| agent.project_id == id))
+        .filter(|agent| status.as_ref().is_none_or(|status| agent.status == *status))
+        .map(|agent| {
+            let project_name = projects
+                .iter()
+                .find(|project| project.id == agent.project_id)
+                .map(|project| project.name.as_str());
+            json!({
+                "id": agent.id,
+                "name": agent.name,
+                "project_id": agent.project_id,
+                "project_name": project_name,
+                "kind": agent.kind,
+                "function_tag": agent.function_tag,
+                "status": agent.status,
+                "last_active_at": agent.last_active_at,
+            })
+        })
+        .collect();
+    Ok(json!(agents))
+}
+
+fn send_instruction(db: &Database, args: &Value) -> Result<Value, CommandError> {
+    let agent = find_agent(db, required_arg(args, "agent_id")?)?;
+    let mut msg = Message::to_agent(
+        &agent.id,
+        MessageKind::Instruction,
+        required_arg(args, "content")?,
+    );
+    msg.metadata = Some(json!({ "source": "mcp" }));
+    let msg = commands::queue_message(db, msg)?;
+    Ok(json!({
+        "message_id": msg.id,
+        "awaiting_approval": msg.is_awaiting_approval(),
+    }))
+}
+
+fn poll_instructions(db: &Database, args: &Value) -> Result<Value, CommandError> {
+    let agent = find_agent(db, required_arg(args, "agent_id")?)?;
+    agents::expire_stale_messages(db, &agent.id);
+    let messages = db
+        .get_pending_messages(&agent.id)
+        .map_err(|e| e.to_string())?;
+    for msg in &messages {
+        let _ = db.mark_delivered(&msg.id);
+    }
+    Ok(json!(messages
+        .iter()
+        .map(|msg| json!({
+            "message_id": msg.id,
+            "kind": msg.kind,
+            "content": msg.content,
+            "created_at": msg.created_at,
+        }))
+        .collect::<Vec<_>>()))
+}
+
+fn report_status(db: &Database, args: &Value) -> Result<Value, CommandError> {
+    let agent = find_agent(db, required_arg(args, "agent_id")?)?;
+    let kind = required_arg(args, "kind")?;
+    if !REPORTABLE_KINDS.contains(&kind) {
+        return Err(CommandError::invalid_input(format!(
+            "kind must be one of {}",
+            REPORTABLE_KINDS.join(", ")
+        )));
+    }
+    let kind: MessageKind = serde_json::from_value(json!(kind))
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+    let content = args.get("content").and_then(Value::as_str).unwrap_or("");
+    let mut msg = Message::from_agent(&agent.id, kind, content);
+    let mut metadata = json!({ "source": "mcp" });
+    if let Some(run_id) = string_arg(args, "run_id") {
+        metadata["run_id"] = json!(run_id);
+    }
+    msg.metadata = Some(metadata);
+    let msg = commands::record_agent_message(db, msg)?;
+    let status = find_agent(db, &agent.id)?.status;
+    Ok(json!({ "message_id": msg.id, "agent_status": status }))
+}
+
+fn get_project_context(db: &Database, args: &Value) -> Result<Value, CommandError> {
+    let (project_id, agent) = match (string_arg(args, "project_id"), string_arg(args, "agent_id")) {
+        (Some(project_id), _) => (project_id.to_string(), None),
+        (None, Some(agent_id)) => {
+            let agent = find_agent(db, agent_id)?;
+            (agent.project_id.clone(), Some(agent))
+        }
+        (None, None) => return Err(CommandError::invalid_input("pass project_id or agent_id")),
+    };
+    let project = db
+        .list_projects()?
+        .into_iter()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| CommandError::not_found(format!("Project not found: {}", project_id)))?;
+    let documents = match &agent {
+        Some(agent) => context_docs::agent_context(db, agent).map_err(|e| e.to_string())?,
+        None => db
+            .list_project_context_docs(&project.id)
+            .map_err(|e| e.to_string())?,
+    };
+    Ok(json!({
+        "project": {
+            "id": project.id,
+            "name": project.name,
+            "repo_paths": project.repo_paths,
+        },
+        "documents": documents
+            .iter()
+            .map(|doc| json!({
+                "title": doc.title,
+                "content": doc.content,
+                "updated_at": doc.updated_at,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+fn call_tool(db: &Database, name: &str, args: &Value) -> Option<Result<Value, CommandError>> {
+    Some(match name {
+        "list_agents" => list_agents(db, args),
+        "send_instruction" => send_instruction(db, args),
+        "poll_instructions" => poll_instructions(db, args),
+        "report_status" => report_status(db, args),
+        "get_project_context" => get_project_context(db, args),
+        _ => return None,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Handle one JSON-RPC message; None for notifications, which get no reply.
+pub fn handle_message(db: &Database, request: &Value) -> Option<Value> {
+    let id = request.get("id").cloned()?;
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(DEFAULT_PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "kanbun", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tool_definitions() }),
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+            let args = params
+                .get("arguments")
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            match call_tool(db, name, &args) {
+                Some(Ok(value)) => json!({
+                    "content": [{
+                        "type": "text",
+                        "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+                    }],
+                    "isError": false,
+                }),
+                Some(Err(error)) => json!({
+                    "content": [{ "type": "text", "text": error.message }],
+                    "isError": true,
+                }),
+                None => {
+                    return Some(error_response(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("Unknown tool: {}", name),
+                    ))
+                }
+            }
+        }
+        _ => {
+            return Some(error_response(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Method not found: {}", method),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+/// Serve requests from stdin until it closes. Logs go to stderr.
+pub fn serve_stdio(db: &Database) -> std::io::Result<()> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => handle_message(db, &request),
+            Err(error) => Some(error_response(Value::Null, PARSE_ERROR, &error.to_string())),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Entry point for `kanbun --mcp`.
+pub fn run() -> Result<(), String> {
+    let path = database_path()?;
+    if !path.exists() {
+        return Err(format!(
+            "No Kanbun database at {}; open the app once or set KANBUN_DB_PATH",
+            path.display()
+        ));
+    }
+    let db = Database::new(&path.to_string_lossy()).map_err(|e| e.to_string())?;
+    tracing::info!("Serving MCP over stdio for {}", path.display());
+    serve_stdio(&db).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(db: &Database, name: &str, arguments: Value) -> Value {
+        let response = handle_message(
+            db,
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+            }),
+        )
+        .expect("requests get a response");
+        assert_eq!(response["result"]["isError"], false, "{}", response);
+        let text = response["result"]["content"][0]["text"]
+            .as_str()
+            .expect("text content");
+        serde_json::from_str(text).expect("tool output is JSON")
+    }
+
+    #[test]
+    fn api_agent_round_trip_over_tools() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Writer", &project.id, AgentKind::Api, "content");
+        db.create_agent(&agent).expect("agent should save");
+
+        assert!(handle_message(
+            &db,
+            &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })
+        )
+        .is_none());
+        let tools = handle_message(
+            &db,
+            &json!({ "jsonrpc": "2.0", "id": 0, "method": "tools/list" }),
+        )
+        .expect("response");
+        assert_eq!(tools["result"]["tools"].as_array().map(Vec::len), Some(5));
+
+        let agents = call(&db, "list_agents", json!({ "project_id": project.id }));
+        assert_eq!(agents[0]["project_name"], "Site");
+
+        let sent = call(
+            &db,
+            "send_instruction",
+            json!({ "agent_id": agent.id, "content": "Draft the launch post" }),
+        );
+        let polled = call(&db, "poll_instructions", json!({ "agent_id": agent.id }));
+        assert_eq!(polled[0]["message_id"], sent["message_id"]);
+        assert_eq!(polled[0]["content"], "Draft the launch post");
+        assert_eq!(
+            call(&db, "poll_instructions", json!({ "agent_id": agent.id })),
+            json!([])
+        );
+
+        let reported = call(
+            &db,
+            "report_status",
+            json!({
+                "agent_id": agent.id,
+                "kind": "completed",
+                "content": "Draft is in docs/launch.md",
+                "run_id": sent["message_id"],
+            }),
+        );
+        assert_eq!(reported["agent_status"], "completed");
+
+        let rejected = handle_message(
+            &db,
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {
+                    "name": "report_status",
+                    "arguments": { "agent_id": agent.id, "kind": "instruction", "content": "" },
+                },
+            }),
+        )
+        .expect("response");
+        assert_eq!(rejected["result"]["isError"], true);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::api_tokens;
+use crate::background;
+use crate::commands;
+use crate::db::Database;
+use crate::local_http::{self, Request, RequestError, Response};
+use crate::models::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+// ── Metrics ─────────────────────────────────────────────────────────────────
+// An optional Prometheus text endpoint (GET /metrics). Gauges are read from
+// the database on each scrape; counters and last-sync durations are kept in
+// process and reset when the app restarts. Off loopback, scrapes must send
+// an API token.
+
+pub const METRICS_SETTINGS_KEY: &str = "metrics";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String, // anything but loopback needs require_token
+    #[serde(default)]
+    pub require_token: bool, // scrapes must send an API token (read-only is enough)
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1:9464".to_string()
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_bind_address(),
+            require_token: false,
+        }
+    }
+}
+
+impl MetricsSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.bind_address
+            .parse::<SocketAddr>()
+            .map_err(|_| format!("bind address must be host:port, got {}", self.bind_address))?;
+        if !self.require_token && !local_http::is_loopback(&self.bind_address) {
+            return Err(format!(
+                "serving metrics on {} needs require_token; without it, bind to 127.0.0.1",
+                self.bind_address
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    counters: BTreeMap<(&'static str, String), f64>, // (metric, rendered labels)
+    gauges: BTreeMap<(&'static str, String), f64>,
+}
+
+static RECORDED: OnceLock<Mutex<Recorded>> = OnceLock::new();
+
+fn recorded() -> &'static Mutex<Recorded> {
+    RECORDED.get_or_init(Default::default)
+}
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    if pairs.is_empty() {
+        return String::new();
+    }
+    let rendered: Vec<String> = pairs
+        .iter()
+        .map(|(name, value)| {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, escaped)
+        })
+        .collect();
+    format!("{{{}}}", rendered.join(","))
+}
+
+pub fn increment(metric: &'static str, label_pairs: &[(&str, &str)]) {
+    if let Ok(mut recorded) = recorded().lock() {
+        *recorded
+            .counters
+            .entry((metric, labels(label_pairs)))
+            .or_default() += 1.0;
+    }
+}
+
+pub fn set_gauge(metric: &'static str, label_pairs: &[(&str, &str)], value: f64) {
+    if let Ok(mut recorded) = recorded().lock() {
+        recorded.gauges.insert((metric, labels(label_pairs)), value);
+    }
+}
+
+/// A delivery to an external channel (webhook, Slack, push, ...) failed.
+pub fn record_delivery_failure(channel: &str) {
+    increment("kanbun_delivery_failures_total", &[("channel", channel)]);
+}
+
+pub fn record_connector_sync(connector: &str, duration: Duration, ok: bool) {
+    increment(
+        "kanbun_connector_syncs_total",
+        &[
+            ("connector", connector),
+            ("result", if ok { "ok" } else { "error" }),
+        ],
+    );
+    set_gauge(
+        "kanbun_connector_sync_duration_seconds",
+        &[("connector", connector)],
+        duration.as_secs_f64(),
+    );
+}
+
+fn write_family(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (label_set, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, label_set, value);
+    }
+}
+
+fn recorded_samples(
+    series: &BTreeMap<(&'static str, String), f64>,
+    metric: &str,
+) -> Vec<(String, f64)> {
+    series
+        .iter()
+        .filter(|((name, _), _)| *name == metric)
+        .map(|((_, label_set), value)| (label_set.clone(), *value))
+        .collect()
+}
+
+/// The Prometheus text exposition for one scrape.
+pub fn render(db: &Database) -> Result<String, String> {
+    let agents = db.list_agents().map_err(|e| e.to_string())?;
+    let mut out = String::new();
+
+    let statuses = [
+        AgentStatus::Idle,
+        AgentStatus::Running,
+        AgentStatus::Blocked,
+        AgentStatus::Errored,
+        AgentStatus::Completed,
+    ];
+    let by_status: Vec<(String, f64)> = statuses
+        .iter()
+        .map(|status| {
+            let name = serde_json::to_value(status)
+                .ok()
+                .and_then(|value| value.as_str().map(str::to_string))
+                .unwrap_or_default();
+            let count = agents
+                .iter()
+                .filter(|agent| agent.status == *status)
+                .count();
+            (labels(&[("status", &name)]), count as f64)
+        })
+        .collect();
+    write_family(
+        &mut out,
+        "kanbun_agents",
+        "gauge",
+        "Agents by current status.",
+        &by_status,
+    );
+
+    let gauge = |value: f64| vec![(String::new(), value)];
+    write_family(
+        &mut out,
+        "kanbun_pending_messages",
+        "gauge",
+        "Instructions queued and not yet delivered to an adapter.",
+        &gauge(db.count_pending_messages().map_err(|e| e.to_string())? as f64),
+    );
+    write_family(
+        &mut out,
+        "kanbun_messages_awaiting_approval",
+        "gauge",
+        "Instructions held for operator approval.",
+        &gauge(
+            db.get_messages_awaiting_approval(None)
+                .map_err(|e| e.to_string())?
+                .len() as f64,
+        ),
+    );
+    write_family(
+        &mut out,
+        "kanbun_adapters_failing",
+        "gauge",
+        "Adapters whose last start attempt failed.",
+        &gauge(commands::failing_adapter_count() as f64),
+    );
+    write_family(
+        &mut out,
+        "kanbun_unread_notifications",
+        "gauge",
+        "Unread entries in the notification center.",
+        &gauge(db.count_unread_notifications().map_err(|e| e.to_string())? as f64),
+    );
+    write_family(
+        &mut out,
+        "kanbun_db_size_bytes",
+        "gauge",
+        "Size of the SQLite database file.",
+        &gauge(db.database_size_bytes().map_err(|e| e.to_string())? as f64),
+    );
+
+    let recorded = recorded()
+        .lock()
+        .map_err(|_| "metrics lock poisoned".to_string())?;
+    for (name, kind, help, series) in [
+        (
+            "kanbun_delivery_failures_total",
+            "counter",
+            "Failed deliveries by channel since the app started.",
+            &recorded.counters,
+        ),
+        (
+            "kanbun_adapter_start_failures_total",
+            "counter",
+            "Failed adapter starts since the app started.",
+            &recorded.counters,
+        ),
+        (
+            "kanbun_connector_syncs_total",
+            "counter",
+            "Connector syncs by result since the app started.",
+            &recorded.counters,
+        ),
+        (
+            "kanbun_connector_sync_duration_seconds",
+            "gauge",
+            "Duration of each connector's last sync.",
+            &recorded.gauges,
+        ),
+    ] {
+        write_family(&mut out, name, kind, help, &recorded_samples(series, name));
+    }
+    Ok(out)
+}
+
+fn handle(db: &Database, require_token: bool, request: Result<Request, RequestError>) -> Response {
+    let Ok(request) = request else {
+        return Response::text("400 Bad Request", "Malformed request\n");
+    };
+    if request.method() != Some("GET") || request.path() != Some("/metrics") {
+        return Response::text("404 Not Found", "Not found\n");
+    }
+    if require_token {
+        if let Err(error) = api_tokens::authenticate(db, &request.head, ApiTokenScope::ReadOnly) {
+            return Response::text(error.status(), format!("{}\n", error.message()));
+        }
+    }
+    match render(db) {
+        Ok(body) => Response {
+            status: "200 OK",
+            content_type: "text/plain; version=0.0.4",
+            body,
+        },
+        Err(error) => Response::text("500 Internal Server Error", error),
+    }
+}
+
+/// Follow the metrics settings: start, move or stop the listener whenever
+/// settings are written.
+pub fn spawn(db: Arc<Database>) {
+    let mut writes = db.subscribe_writes();
+    background::spawn("Metrics endpoint", async move {
+        let mut serving: Option<((String, bool), tokio::task::JoinHandle<()>)> = None;
+        let mut refused: Option<String> = None;
+        loop {
+            let settings: MetricsSettings = db
+                .get_setting(METRICS_SETTINGS_KEY)
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            // Settings saved before the loopback rule may not pass it.
+            let refusal = settings
+                .enabled
+                .then(|| settings.validate().err())
+                .flatten();
+            match &refusal {
+                Some(error) if refused.as_ref() != Some(error) => {
+                    tracing::warn!("Metrics endpoint not served: {}", error)
+                }
+                _ => {}
+            }
+            let wanted = (settings.enabled && refusal.is_none())
+                .then_some((settings.bind_address, settings.require_token));
+            refused = refusal;
+            if serving.as_ref().map(|(wanted, _)| wanted) != wanted.as_ref() {
+                if let Some((_, task)) = serving.take() {
+                    task.abort();
+                }
+                if let Some((address, require_token)) = wanted {
+                    match TcpListener::bind(&address).await {
+                        Ok(listener) => {
+                            tracing::info!("Serving metrics on http://{}/metrics", address);
+                            let db = db.clone();
+                            let task = tokio::spawn(local_http::serve(
+                                listener,
+                                "Metrics",
+                                0,
+                                move |request| handle(&db, require_token, request),
+                            ));
+                            serving = Some(((address, require_token), task));
+                        }
+                        Err(error) => {
+                            tracing::warn!("Failed to bind metrics endpoint {}: {}", address, error)
+                        }
+                    }
+                }
+            }
+            if !background::wait_for_write(&mut writes, &["settings"]).await {
+                return;
+            }
+            background::settle(&mut writes).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_gauges_and_recorded_counters() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).expect("agent should save");
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .expect("status should update");
+        db.insert_message(&Message::to_agent(
+            &agent.id,
+            MessageKind::Instruction,
+            "Go",
+        ))
+        .expect("message should save");
+        record_delivery_failure("webhook");
+        record_connector_sync("todoist", Duration::from_millis(1500), true);
+
+        let text = render(&db).expect("metrics should render");
+        assert!(text.contains("kanbun_agents{status=\"blocked\"} 1\n"));
+        assert!(text.contains("kanbun_agents{status=\"running\"} 0\n"));
+        assert!(text.contains("kanbun_pending_messages 1\n"));
+        assert!(text.contains("# TYPE kanbun_delivery_failures_total counter\n"));
+        assert!(text.contains("kanbun_delivery_failures_total{channel=\"webhook\"}"));
+        assert!(
+            text.contains("kanbun_connector_sync_duration_seconds{connector=\"todoist\"} 1.5\n")
+        );
+        assert!(text.contains("kanbun_db_size_bytes "));
+
+        assert!(MetricsSettings::default().validate().is_ok());
+        let invalid = MetricsSettings {
+            enabled: true,
+            bind_address: "localhost".to_string(),
+            require_token: false,
+        };
+        assert!(invalid.validate().is_err());
+        let mut exposed = MetricsSettings {
+            enabled: true,
+            bind_address: "0.0.0.0:9464".to_string(),
+            require_token: false,
+        };
+        assert!(exposed.validate().is_err());
+        exposed.require_token = true;
+        assert!(exposed.validate().is_ok());
+
+        let get = |head: &str| {
+            handle(
+                &db,
+                true,
+                Ok(Request {
+                    head: head.to_string(),
+                    body: vec![],
+                }),
+            )
+        };
+        assert_eq!(
+            get("GET /metrics HTTP/1.1\r\n\r\n").status,
+            "401 Unauthorized"
+        );
+        let reader = api_tokens::create(&db, "Grafana", ApiTokenScope::ReadOnly).unwrap();
+        let scrape = get(&format!(
+            "GET /metrics HTTP/1.1\r\nAuthorization: Bearer {}\r\n\r\n",
+            reader.secret
+        ));
+        assert_eq!(scrape.status, "200 OK");
+        assert!(scrape.body.contains("kanbun_pending_messages 1\n"));
+        assert_eq!(get("GET /other HTTP/1.1\r\n\r\n").status, "404 Not Found");
+    }
+}
@@ -1,5 +1,7 @@
+use crate::connectors::ItemStatus;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 // ── Projects ────────────────────────────────────────────────────────────────
@@ -33,6 +35,10 @@ pub struct ProjectContextDocument {
     pub content: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub source_path: Option<String>, // file the doc was imported from
+    #[serde(default)]
+    pub watch_source: bool, // re-import when the file changes
 }
 
 impl ProjectContextDocument {
@@ -45,10 +51,22 @@ impl ProjectContextDocument {
             content: content.to_string(),
             created_at: now,
             updated_at: now,
+            source_path: None,
+            watch_source: false,
         }
     }
 }
 
+/// A context doc as it was before a save replaced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextDocRevision {
+    pub id: String,
+    pub doc_id: String,
+    pub title: String,
+    pub content: String,
+    pub saved_at: DateTime<Utc>, // when this version was saved, i.e. the doc's updated_at then
+}
+
 // ── Agents ──────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -81,6 +99,12 @@ pub struct Agent {
     pub last_active_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub config: AgentConfig,
+    #[serde(default)]
+    pub pinned_position: Option<u32>, // set when pinned; lower sorts first
+    #[serde(default)]
+    pub source_item: Option<SourceItemRef>, // connector item the agent was created for
+    #[serde(default)]
+    pub template_id: Option<String>, // template the agent was created from
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +113,44 @@ pub struct AgentConfig {
     pub watch_paths: Vec<String>, // specific paths this agent works in
     pub schedule: Option<String>, // cron expression if scheduled
     pub notify_on: Vec<AgentStatus>, // when to alert
+    #[serde(default)]
+    pub capture_git_patch: bool, // store the full patch on finalized runs, not just the stat
+    #[serde(default = "default_max_concurrent_runs")]
+    pub max_concurrent_runs: u32, // instructions beyond this join the newest active run
+    #[serde(default)]
+    pub exclude_heartbeats_from_runs: bool, // keep heartbeats in the conversation only
+    #[serde(default)]
+    pub ignore_globs: Vec<String>, // watcher events matching these are dropped
+    #[serde(default)]
+    pub include_globs: Vec<String>, // if set, only matching watcher events are kept
+    #[serde(default)]
+    pub watching_paused: bool, // watch paths kept but no file events recorded
+    #[serde(default)]
+    pub poll_paths: Vec<String>, // watch paths (or parents) that use the polling watcher
+    #[serde(default)]
+    pub idle_timeout_minutes: Option<u32>, // stop the adapter after this long without activity
+    #[serde(default)]
+    pub allowed_paths: Vec<String>, // if set, the only directories the agent may run in or watch
+    #[serde(default)]
+    pub capabilities: Vec<String>, // languages, repos, skills; matched by route_instruction
+}
+
+fn default_max_concurrent_runs() -> u32 {
+    1
+}
+
+/// Partial edit of an agent; omitted fields are left unchanged. An empty
+/// working_directory clears it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AgentUpdate {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub function_tag: Option<String>,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    #[serde(default)]
+    pub config: Option<AgentConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,7 +179,20 @@ impl Agent {
                 watch_paths: vec![],
                 schedule: None,
                 notify_on: vec![AgentStatus::Errored, AgentStatus::Blocked],
+                capture_git_patch: false,
+                max_concurrent_runs: default_max_concurrent_runs(),
+                exclude_heartbeats_from_runs: false,
+                ignore_globs: vec![],
+                include_globs: vec![],
+                watching_paused: false,
+                poll_paths: vec![],
+                idle_timeout_minutes: None,
+                allowed_paths: vec![],
+                capabilities: vec![],
             },
+            pinned_position: None,
+            source_item: None,
+            template_id: None,
         }
     }
 }
@@ -134,6 +209,273 @@ pub struct Run {
     pub summary: Option<String>,
     pub outputs: Vec<RunOutput>,
     pub file_changes: Vec<FileChange>,
+    #[serde(default)]
+    pub token_in: u64,
+    #[serde(default)]
+    pub token_out: u64,
+    #[serde(default)]
+    pub cost: f64, // USD
+    #[serde(default)]
+    pub git_snapshot: Option<GitSnapshot>, // working tree state when the run finalized
+    #[serde(default)]
+    pub review: Option<RunReview>,
+    #[serde(default)]
+    pub labels: Vec<String>, // free-form tags, e.g. "release", "client-x"
+    #[serde(default)]
+    pub instruction_message_id: Option<String>, // the to_agent message that opened this run
+    #[serde(default)]
+    pub source_item: Option<SourceItemRef>, // connector item the instruction came from
+}
+
+/// Points at a cached connector item (a Todoist task, an Obsidian note, ...).
+/// Instructions carry it as `metadata.source_item`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct SourceItemRef {
+    pub connector_id: String,
+    pub item_id: String,
+}
+
+impl SourceItemRef {
+    pub fn from_metadata(metadata: Option<&serde_json::Value>) -> Option<Self> {
+        metadata
+            .and_then(|metadata| metadata.get("source_item"))
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    Approve,
+    RequestChanges,
+    Reject,
+}
+
+impl ReviewDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Approve => "approve",
+            Self::RequestChanges => "request_changes",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+/// Operator resolution of a NeedsReview run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReview {
+    pub decision: ReviewDecision,
+    pub notes: Option<String>,
+    pub reviewed_at: DateTime<Utc>,
+    pub follow_up_message_id: Option<String>, // set for request_changes
+}
+
+/// A review note left on a run, optionally anchored to one of its outputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComment {
+    pub id: String,
+    pub run_id: String,
+    pub output_index: Option<usize>, // index into Run.outputs; None = the run as a whole
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RunComment {
+    pub fn new(run_id: &str, output_index: Option<usize>, body: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            run_id: run_id.to_string(),
+            output_index,
+            body: body.to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Working-tree state of the agent's repository, captured when a run ends.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitSnapshot {
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    pub status: String,    // `git status --porcelain`
+    pub diff_stat: String, // `git diff --stat` against HEAD
+    pub patch: Option<String>,
+    #[serde(default)]
+    pub patch_truncated: bool,
+    pub captured_at: DateTime<Utc>,
+}
+
+impl Run {
+    pub fn new(agent_id: &str, status: RunStatus, summary: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            agent_id: agent_id.to_string(),
+            status,
+            started_at: Utc::now(),
+            ended_at: None,
+            summary,
+            outputs: vec![],
+            file_changes: vec![],
+            token_in: 0,
+            token_out: 0,
+            cost: 0.0,
+            git_snapshot: None,
+            review: None,
+            labels: vec![],
+            instruction_message_id: None,
+            source_item: None,
+        }
+    }
+
+    pub fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            token_in: self.token_in,
+            token_out: self.token_out,
+            cost: self.cost,
+        }
+    }
+
+    pub fn add_usage(&mut self, usage: &TokenUsage) {
+        self.token_in = self.token_in.saturating_add(usage.token_in);
+        self.token_out = self.token_out.saturating_add(usage.token_out);
+        self.cost += usage.cost;
+    }
+
+    /// Lines added and removed across the run's changed files. Stats are
+    /// relative to HEAD, so only the latest change per path counts.
+    pub fn line_churn(&self) -> (u64, u64) {
+        let mut latest: HashMap<&str, (u32, u32)> = HashMap::new();
+        for change in &self.file_changes {
+            match (change.lines_added, change.lines_removed) {
+                (Some(added), Some(removed)) => {
+                    latest.insert(change.path.as_str(), (added, removed));
+                }
+                _ => {
+                    latest.remove(change.path.as_str());
+                }
+            }
+        }
+        latest.values().fold((0, 0), |(added, removed), (a, r)| {
+            (added + u64::from(*a), removed + u64::from(*r))
+        })
+    }
+
+    /// Collapse streaks of near-duplicate consecutive heartbeats (pane captures
+    /// that barely changed) down to the first and last, with a marker output
+    /// recording how many were dropped in between.
+    pub fn compact_heartbeats(&mut self) {
+        let mut compacted: Vec<RunOutput> = Vec::with_capacity(self.outputs.len());
+        let mut streak: Vec<RunOutput> = Vec::new();
+
+        fn flush(streak: &mut Vec<RunOutput>, into: &mut Vec<RunOutput>) {
+            if streak.len() <= 2 {
+                into.append(streak);
+                return;
+            }
+            let last = streak.pop().expect("streak has more than two entries");
+            let first = streak.remove(0);
+            let omitted = streak.len();
+            let marker = RunOutput {
+                kind: "heartbeats_compacted".to_string(),
+                content: format!(
+                    "{} similar heartbeats omitted ({} – {})",
+                    omitted,
+                    streak[0].timestamp.format("%H:%M:%S"),
+                    streak[omitted - 1].timestamp.format("%H:%M:%S")
+                ),
+                timestamp: streak[0].timestamp,
+            };
+            streak.clear();
+            into.extend([first, marker, last]);
+        }
+
+        for output in std::mem::take(&mut self.outputs) {
+            if output.kind == "heartbeat" {
+                let similar = streak
+                    .last()
+                    .is_some_and(|previous| heartbeats_similar(&previous.content, &output.content));
+                if !similar {
+                    flush(&mut streak, &mut compacted);
+                }
+                streak.push(output);
+            } else {
+                flush(&mut streak, &mut compacted);
+                compacted.push(output);
+            }
+        }
+        flush(&mut streak, &mut compacted);
+        self.outputs = compacted;
+    }
+}
+
+/// Two heartbeats are similar when most of their non-blank lines overlap,
+/// which is what repeated captures of a mostly idle terminal look like.
+fn heartbeats_similar(a: &str, b: &str) -> bool {
+    let lines = |text: &str| -> std::collections::HashSet<String> {
+        text.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect()
+    };
+    let (a, b) = (lines(a), lines(b));
+    if a.is_empty() && b.is_empty() {
+        return true;
+    }
+    let shared = a.intersection(&b).count();
+    let total = a.union(&b).count();
+    shared * 5 >= total * 4
+}
+
+/// Token counts and spend reported by an agent. Inbound messages carry it as
+/// `metadata.usage`; it is accumulated onto the agent's current run.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub token_in: u64,
+    #[serde(default)]
+    pub token_out: u64,
+    #[serde(default)]
+    pub cost: f64, // USD
+}
+
+impl TokenUsage {
+    pub fn is_empty(&self) -> bool {
+        self.token_in == 0 && self.token_out == 0 && self.cost <= 0.0
+    }
+
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.token_in = self.token_in.saturating_add(other.token_in);
+        self.token_out = self.token_out.saturating_add(other.token_out);
+        self.cost += other.cost;
+    }
+
+    /// Read usage from a JSON payload. Accepts our own `token_in`/`token_out`/
+    /// `cost` keys as well as the Anthropic (`input_tokens`, `total_cost_usd`)
+    /// and OpenAI (`prompt_tokens`, `completion_tokens`) shapes, either at the
+    /// top level or under a `usage` object.
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let usage = value.get("usage").filter(|usage| usage.is_object());
+        let field = |keys: &[&str]| -> Option<&serde_json::Value> {
+            keys.iter()
+                .find_map(|key| usage.and_then(|usage| usage.get(*key)))
+                .or_else(|| keys.iter().find_map(|key| value.get(*key)))
+        };
+        let count = |keys: &[&str]| field(keys).and_then(serde_json::Value::as_u64);
+
+        let parsed = Self {
+            token_in: count(&["token_in", "input_tokens", "prompt_tokens"]).unwrap_or(0)
+                + count(&["cache_creation_input_tokens"]).unwrap_or(0)
+                + count(&["cache_read_input_tokens"]).unwrap_or(0),
+            token_out: count(&["token_out", "output_tokens", "completion_tokens"]).unwrap_or(0),
+            cost: field(&["cost", "cost_usd", "total_cost_usd"])
+                .and_then(serde_json::Value::as_f64)
+                .unwrap_or(0.0),
+        };
+        (!parsed.is_empty()).then_some(parsed)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -145,6 +487,17 @@ pub enum RunStatus {
     NeedsReview,
 }
 
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InProgress => "in_progress",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::NeedsReview => "needs_review",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunOutput {
     pub kind: String, // "message", "file", "pr", "email_draft", etc.
@@ -152,11 +505,49 @@ pub struct RunOutput {
     pub timestamp: DateTime<Utc>,
 }
 
+/// A run without its outputs, which can run to thousands of lines. Serialized
+/// as the run's own fields plus `output_count`; `run.outputs` is empty.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunHeader {
+    #[serde(flatten)]
+    pub run: Run,
+    pub output_count: usize,
+}
+
+/// A slice of a run's outputs, oldest first. `offset` is the index of the
+/// first output in the run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutputPage {
+    pub run_id: String,
+    pub offset: usize,
+    pub total: usize,
+    pub outputs: Vec<RunOutput>,
+}
+
+/// One run with the newest page of its outputs.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDetail {
+    pub run: RunHeader,
+    pub outputs: RunOutputPage,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileChange {
     pub path: String,
     pub change_type: FileChangeType,
     pub timestamp: DateTime<Utc>,
+    #[serde(default = "default_change_count")]
+    pub count: u32, // raw watcher events coalesced into this change
+    #[serde(default)]
+    pub old_path: Option<String>, // previous location, for renames
+    #[serde(default)]
+    pub lines_added: Option<u32>, // relative to HEAD, or whole file when untracked
+    #[serde(default)]
+    pub lines_removed: Option<u32>,
+}
+
+fn default_change_count() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -168,6 +559,152 @@ pub enum FileChangeType {
     Renamed,
 }
 
+impl FileChangeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Modified => "modified",
+            Self::Deleted => "deleted",
+            Self::Renamed => "renamed",
+        }
+    }
+}
+
+// ── Board ───────────────────────────────────────────────────────────────────
+// Task cards on a per-project kanban board. A card can be handed to an agent,
+// which sends it as an instruction and follows the run that picks it up.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskColumn {
+    Backlog,
+    Ready,
+    InProgress,
+    Review,
+    Done,
+}
+
+impl TaskColumn {
+    pub const ALL: [TaskColumn; 5] = [
+        TaskColumn::Backlog,
+        TaskColumn::Ready,
+        TaskColumn::InProgress,
+        TaskColumn::Review,
+        TaskColumn::Done,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Backlog => "backlog",
+            Self::Ready => "ready",
+            Self::InProgress => "in_progress",
+            Self::Review => "review",
+            Self::Done => "done",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|column| column.as_str() == value)
+    }
+
+    /// Where a card belongs while its run is in `status`. Failed runs leave
+    /// the card where it is, so it stays visible until someone moves it.
+    pub fn for_run_status(status: &RunStatus) -> Option<Self> {
+        match status {
+            RunStatus::InProgress => Some(Self::InProgress),
+            RunStatus::NeedsReview => Some(Self::Review),
+            RunStatus::Completed => Some(Self::Done),
+            RunStatus::Failed => None,
+        }
+    }
+
+    /// The status a linked connector item gets when its card lands here.
+    pub fn item_status(&self) -> ItemStatus {
+        match self {
+            Self::Backlog | Self::Ready => ItemStatus::Active,
+            Self::InProgress | Self::Review => ItemStatus::InProgress,
+            Self::Done => ItemStatus::Completed,
+        }
+    }
+
+    /// Where a card at `current` moves when its item changes to `status`
+    /// upstream. A card already in a column for that status stays put, so
+    /// Ready and Review survive a sync.
+    pub fn for_item_status(status: &ItemStatus, current: Self) -> Self {
+        if current.item_status() == *status {
+            return current;
+        }
+        match status {
+            ItemStatus::Active => Self::Backlog,
+            ItemStatus::InProgress => Self::InProgress,
+            ItemStatus::Completed | ItemStatus::Archived => Self::Done,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub column: TaskColumn,
+    pub position: u32, // order within the column, from the top
+    pub assigned_agent_id: Option<String>,
+    pub source_item: Option<SourceItemRef>, // connector item the card tracks
+    #[serde(default)]
+    pub source_status: Option<ItemStatus>, // item status as of the last agreement with upstream
+    #[serde(default)]
+    pub sync_conflict: Option<ItemStatus>, // upstream status that clashed with a local move
+    pub instruction_message_id: Option<String>, // set when assigned to an agent
+    pub run_id: Option<String>,             // the run that picked up the instruction
+    #[serde(default)]
+    pub run_status: Option<RunStatus>, // filled in when the board is loaded
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Task {
+    pub fn new(project_id: &str, title: &str, column: TaskColumn) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            project_id: project_id.to_string(),
+            title: title.to_string(),
+            description: None,
+            column,
+            position: 0,
+            assigned_agent_id: None,
+            source_item: None,
+            source_status: None,
+            sync_conflict: None,
+            instruction_message_id: None,
+            run_id: None,
+            run_status: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Create or edit a card; `id` is None for a new one. Column and position
+/// change through `move_task`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub project_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub column: Option<TaskColumn>, // for new cards; defaults to backlog
+    #[serde(default)]
+    pub source_item: Option<SourceItemRef>,
+}
+
 // ── Message Protocol ────────────────────────────────────────────────────────
 // This is the stable contract. Agents don't talk to Kanbun directly —
 // they speak this protocol through thin adapters. When agent interfaces change,
@@ -185,6 +722,8 @@ pub struct Message {
     pub created_at: DateTime<Utc>,
     pub delivered_at: Option<DateTime<Utc>>, // None = still in queue
     pub acknowledged_at: Option<DateTime<Utc>>, // adapter confirmed receipt
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>, // to_agent only: skip delivery after this
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -213,6 +752,24 @@ pub enum MessageKind {
     Heartbeat,    // alive ping (adapters send these periodically)
 }
 
+impl MessageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Instruction => "instruction",
+            Self::Pause => "pause",
+            Self::Resume => "resume",
+            Self::Cancel => "cancel",
+            Self::StatusRequest => "status_request",
+            Self::StatusUpdate => "status_update",
+            Self::Output => "output",
+            Self::Error => "error",
+            Self::Blocked => "blocked",
+            Self::Completed => "completed",
+            Self::Heartbeat => "heartbeat",
+        }
+    }
+}
+
 impl Message {
     /// Create a new outbound message (kanbun → agent)
     pub fn to_agent(agent_id: &str, kind: MessageKind, content: &str) -> Self {
@@ -227,6 +784,7 @@ impl Message {
             created_at: Utc::now(),
             delivered_at: None,
             acknowledged_at: None,
+            expires_at: None,
         }
     }
 
@@ -243,8 +801,29 @@ impl Message {
             created_at: Utc::now(),
             delivered_at: Some(Utc::now()),
             acknowledged_at: None,
+            expires_at: None,
         }
     }
+
+    /// The run this message belongs to: a `reply_to` pointing at the triggering
+    /// instruction, or an explicit `metadata.run_id`.
+    pub fn run_key(&self) -> Option<&str> {
+        self.reply_to.as_deref().or_else(|| {
+            self.metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("run_id"))
+                .and_then(serde_json::Value::as_str)
+        })
+    }
+
+    /// Held until an operator approves it (instructions to Manual agents).
+    pub fn is_awaiting_approval(&self) -> bool {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("awaiting_approval"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    }
 }
 
 // ── Adapter Registry ────────────────────────────────────────────────────────
@@ -269,6 +848,301 @@ pub enum AdapterType {
     HttpWebhook, // API agents that accept/send webhooks
     Process,     // Spawn and manage a child process
     Mock,        // For testing — echoes messages back
+    Simulation,  // Replays a recorded transcript
+}
+
+// ── Agent Groups ────────────────────────────────────────────────────────────
+// Named sets of agents across projects ("launch crew") operated on together.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentGroup {
+    pub id: String,
+    pub name: String,
+    pub agent_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AgentGroup {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            agent_ids: vec![],
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// ── Agent Templates ─────────────────────────────────────────────────────────
+// Reusable agent setups ("a Claude Code engineer") instantiated per project.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTemplate {
+    pub id: String,
+    pub name: String,
+    pub kind: AgentKind,
+    pub function_tag: String,
+    pub autonomy_level: AutonomyLevel,
+    pub adapter: Option<AdapterConfig>, // applied to each new agent
+    #[serde(default)]
+    pub instructions: Vec<String>, // queued to the agent, in order, once created
+    #[serde(default)]
+    pub ignore_globs: Vec<String>,
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create or replace a template; `id` is None for a new one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgentTemplateInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub kind: AgentKind,
+    pub function_tag: String,
+    #[serde(default)]
+    pub autonomy_level: Option<AutonomyLevel>,
+    #[serde(default)]
+    pub adapter: Option<AdapterConfig>,
+    #[serde(default)]
+    pub instructions: Vec<String>,
+    #[serde(default)]
+    pub ignore_globs: Vec<String>,
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+}
+
+impl AgentTemplate {
+    pub fn new(name: &str, kind: AgentKind, function_tag: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            kind,
+            function_tag: function_tag.to_string(),
+            autonomy_level: AutonomyLevel::Supervised,
+            adapter: None,
+            instructions: vec![],
+            ignore_globs: vec![],
+            include_globs: vec![],
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// A new agent in `project_id` configured from this template.
+    pub fn instantiate(&self, project_id: &str, name: Option<&str>) -> Agent {
+        let mut agent = Agent::new(
+            name.unwrap_or(&self.name),
+            project_id,
+            self.kind.clone(),
+            &self.function_tag,
+        );
+        agent.config.autonomy_level = self.autonomy_level.clone();
+        agent.config.ignore_globs = self.ignore_globs.clone();
+        agent.config.include_globs = self.include_globs.clone();
+        agent.template_id = Some(self.id.clone());
+        agent
+    }
+}
+
+// ── Notifications ───────────────────────────────────────────────────────────
+// Rules decide which agent events alert where; every alert that fires is kept
+// in the notification center until read.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    AgentBlocked,
+    AgentErrored,
+    AgentCompleted,
+    RunCompleted,
+    RunNeedsReview,
+}
+
+impl NotificationEvent {
+    pub fn from_status(status: &AgentStatus) -> Option<Self> {
+        match status {
+            AgentStatus::Blocked => Some(Self::AgentBlocked),
+            AgentStatus::Errored => Some(Self::AgentErrored),
+            AgentStatus::Completed => Some(Self::AgentCompleted),
+            AgentStatus::Idle | AgentStatus::Running => None,
+        }
+    }
+
+    /// The agent status `notify_on` matches this event by, if any.
+    pub fn status(&self) -> Option<AgentStatus> {
+        match self {
+            Self::AgentBlocked => Some(AgentStatus::Blocked),
+            Self::AgentErrored => Some(AgentStatus::Errored),
+            Self::AgentCompleted => Some(AgentStatus::Completed),
+            Self::RunCompleted | Self::RunNeedsReview => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Desktop, // native desktop alert
+    InApp,   // notification center only
+    Slack,   // incoming webhook from the chat settings
+    Discord, // incoming webhook from the chat settings
+    Push,    // ntfy or Pushover, from the push settings
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRule {
+    pub id: String,
+    pub event: NotificationEvent,
+    #[serde(default)]
+    pub project_ids: Vec<String>, // empty = every project
+    pub channel: NotificationChannel,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>, // local time; alerts still reach the center
+    #[serde(default)]
+    pub repeat_every_minutes: Option<u32>, // re-alert while the agent stays in the state
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A local-time window like 22:00–07:00; may wrap past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuietHours {
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM"
+}
+
+/// Create or replace a rule; `id` is None for a new one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationRuleInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub event: NotificationEvent,
+    #[serde(default)]
+    pub project_ids: Vec<String>,
+    pub channel: NotificationChannel,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    #[serde(default)]
+    pub repeat_every_minutes: Option<u32>,
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+}
+
+fn default_rule_enabled() -> bool {
+    true
+}
+
+/// An alert as kept in the notification center.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRecord {
+    pub id: String,
+    pub agent_id: String,
+    pub event: NotificationEvent,
+    pub title: String,
+    pub body: String,
+    pub link: String,
+    pub channels: Vec<NotificationChannel>, // empty when quiet hours held it back
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+// ── Outbound Webhooks ───────────────────────────────────────────────────────
+// Lifecycle events POSTed as signed JSON to external automations.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    AgentStatusChanged,
+    RunCompleted, // any finalized run: completed, needs review or failed
+    AttentionRaised,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub url: String,
+    #[serde(default)]
+    pub secret: Option<String>, // signs payloads with HMAC-SHA256 when set
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>, // empty = every event
+    #[serde(default)]
+    pub project_ids: Vec<String>, // empty = every project
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_error: Option<String>, // None after a successful delivery
+}
+
+// ── API Tokens ──────────────────────────────────────────────────────────────
+// Credentials for the local HTTP endpoints (metrics, intake), one per
+// integration. Only a hash is stored; the token is shown once, on creation.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    ReadOnly,  // scrape metrics and read state
+    ReadWrite, // also create items and send instructions
+}
+
+impl ApiTokenScope {
+    /// Whether a token with this scope may do something that needs `required`.
+    pub fn permits(self, required: ApiTokenScope) -> bool {
+        self == ApiTokenScope::ReadWrite || required == ApiTokenScope::ReadOnly
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scope: ApiTokenScope,
+    pub prefix: String, // first characters of the token, to tell them apart
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookSubscription {
+    pub fn wants(&self, event: WebhookEvent, project_id: &str) -> bool {
+        self.enabled
+            && (self.events.is_empty() || self.events.contains(&event))
+            && (self.project_ids.is_empty() || self.project_ids.iter().any(|id| id == project_id))
+    }
+}
+
+/// Create or replace a subscription; `id` is None for a new one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookSubscriptionInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub project_ids: Vec<String>,
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+}
+
+/// The JSON body every delivery carries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub id: String, // unique per delivery, for receivers that dedupe
+    pub event: WebhookEvent,
+    pub occurred_at: DateTime<Utc>,
+    pub agent_id: String,
+    pub project_id: String,
+    pub data: serde_json::Value,
 }
 
 // ── Conversation Thread ─────────────────────────────────────────────────────
@@ -286,14 +1160,42 @@ pub struct ConversationThread {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DashboardView {
     pub projects: Vec<ProjectWithAgents>,
+    pub pinned: Vec<AgentSummary>, // pinned agents in pin order, also listed under their project
+    pub groups: Vec<GroupRollup>,
     pub needs_attention: Vec<AttentionItem>,
     pub stats: DashboardStats,
+    pub labels: Vec<LabelRollup>,
+}
+
+/// Status counts for a group's members that pass the dashboard filter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupRollup {
+    pub group_id: String,
+    pub name: String,
+    pub agent_count: usize,
+    pub running: usize,
+    pub idle: usize,
+    pub blocked: usize,
+    pub errored: usize,
+    pub needs_attention: usize,
+    pub usage: TokenUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelRollup {
+    pub label: String,
+    pub run_count: usize,
+    pub in_progress: usize,
+    pub needs_review: usize,
+    pub cost: f64,
+    pub last_run_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectWithAgents {
     pub project: Project,
     pub agents: Vec<AgentSummary>,
+    pub usage: TokenUsage,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -301,6 +1203,9 @@ pub struct AgentSummary {
     pub agent: Agent,
     pub recent_run: Option<Run>,
     pub files_changed_today: usize,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub usage: TokenUsage,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -308,16 +1213,68 @@ pub struct AttentionItem {
     pub agent_id: String,
     pub agent_name: String,
     pub project_name: String,
-    pub reason: String, // "errored", "needs_review", "blocked"
+    pub reason: String, // "errored", "needs_review", "blocked", "stuck"
     pub timestamp: DateTime<Utc>,
 }
 
+/// Narrows the dashboard. Empty lists match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DashboardFilter {
+    #[serde(default)]
+    pub project_ids: Vec<String>,
+    #[serde(default)]
+    pub statuses: Vec<AgentStatus>,
+    #[serde(default)]
+    pub function_tags: Vec<String>,
+    #[serde(default)]
+    pub only_needs_attention: bool,
+}
+
+impl DashboardFilter {
+    /// Whether agents are filtered on anything beyond their project, in
+    /// which case projects left without agents are hidden too.
+    pub fn narrows_agents(&self) -> bool {
+        !self.statuses.is_empty() || !self.function_tags.is_empty() || self.only_needs_attention
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedDashboardView {
+    pub name: String,
+    pub filter: DashboardFilter,
+}
+
+/// Acknowledgement of an attention item, keyed by (agent_id, reason).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttentionState {
+    pub agent_id: String,
+    pub reason: String,
+    pub dismissed_at: Option<DateTime<Utc>>, // hides occurrences from before this
+    pub snoozed_until: Option<DateTime<Utc>>, // hides every occurrence until then
+}
+
+impl AttentionState {
+    /// Whether the item should stay hidden. A dismissal only covers the
+    /// occurrence that was dismissed; the same reason happening again later
+    /// shows up as usual.
+    pub fn suppresses(&self, item: &AttentionItem, now: DateTime<Utc>) -> bool {
+        self.snoozed_until.is_some_and(|until| now < until)
+            || self
+                .dismissed_at
+                .is_some_and(|dismissed_at| item.timestamp <= dismissed_at)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DashboardStats {
     pub total_agents: usize,
     pub running: usize,
     pub idle: usize,
     pub errored: usize,
     pub needs_attention: usize,
-    pub files_changed_today: usize,
+    pub files_changed_today: usize, // distinct files since local midnight
+    pub files_changed_by_project: HashMap<String, usize>, // project id -> files changed today
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub usage: TokenUsage,
 }
@@ -0,0 +1,259 @@
+use crate::db::Database;
+use crate::metrics;
+use crate::models::*;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+// ── Slack / Discord ─────────────────────────────────────────────────────────
+// Alerts routed to the Slack or Discord channel are posted to an incoming
+// webhook: the project's own URL when it has one, else the default.
+
+pub const CHAT_SETTINGS_KEY: &str = "chat_notifications";
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+const SLACK_TEMPLATE: &str = "*{{title}}* · {{project}}\n{{body}}";
+const DISCORD_TEMPLATE: &str = "**{{title}}** · {{project}}\n{{body}}";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatSettings {
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    #[serde(default)]
+    pub discord_webhook_url: Option<String>,
+    #[serde(default)]
+    pub project_routes: Vec<ChatRoute>,
+    /// Replaces the default message; supports {{title}}, {{body}},
+    /// {{agent}}, {{project}}, {{event}} and {{link}}.
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+/// Per-project webhooks; an unset URL falls back to the default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatRoute {
+    pub project_id: String,
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    #[serde(default)]
+    pub discord_webhook_url: Option<String>,
+}
+
+fn non_empty(url: &Option<String>) -> Option<&str> {
+    url.as_deref().map(str::trim).filter(|url| !url.is_empty())
+}
+
+impl ChatSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let routes = self
+            .project_routes
+            .iter()
+            .flat_map(|route| [&route.slack_webhook_url, &route.discord_webhook_url]);
+        for url in [&self.slack_webhook_url, &self.discord_webhook_url]
+            .into_iter()
+            .chain(routes)
+            .filter_map(non_empty)
+        {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(format!("webhook URL must be http(s): {}", url));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn webhook_url(&self, channel: NotificationChannel, project_id: &str) -> Option<&str> {
+        let route = self
+            .project_routes
+            .iter()
+            .find(|route| route.project_id == project_id);
+        match channel {
+            NotificationChannel::Slack => route
+                .and_then(|route| non_empty(&route.slack_webhook_url))
+                .or_else(|| non_empty(&self.slack_webhook_url)),
+            NotificationChannel::Discord => route
+                .and_then(|route| non_empty(&route.discord_webhook_url))
+                .or_else(|| non_empty(&self.discord_webhook_url)),
+            NotificationChannel::Desktop
+            | NotificationChannel::InApp
+            | NotificationChannel::Push => None,
+        }
+    }
+}
+
+pub fn render(
+    template: &str,
+    record: &NotificationRecord,
+    agent_name: &str,
+    project_name: &str,
+) -> String {
+    let event = serde_json::to_value(record.event)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    template
+        .replace("{{title}}", &record.title)
+        .replace("{{body}}", &record.body)
+        .replace("{{agent}}", agent_name)
+        .replace("{{project}}", project_name)
+        .replace("{{event}}", &event)
+        .replace("{{link}}", &record.link)
+        .trim_end()
+        .to_string()
+}
+
+fn payload(channel: NotificationChannel, text: &str) -> serde_json::Value {
+    match channel {
+        NotificationChannel::Discord => json!({ "content": text }),
+        _ => json!({ "text": text }),
+    }
+}
+
+pub fn build_client() -> Client {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+pub async fn post(
+    client: &Client,
+    channel: NotificationChannel,
+    url: &str,
+    text: &str,
+) -> Result<(), String> {
+    let response = client
+        .post(url)
+        .json(&payload(channel, text))
+        .send()
+        .await
+        .map_err(|error| format!("webhook request failed: {}", error))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("webhook returned HTTP {}", status));
+    }
+    Ok(())
+}
+
+/// Post every record routed to Slack or Discord. Failures are logged; the
+/// record stays in the notification center either way.
+pub async fn deliver(db: &Database, records: &[NotificationRecord]) {
+    let chat_records: Vec<&NotificationRecord> = records
+        .iter()
+        .filter(|record| {
+            record.channels.contains(&NotificationChannel::Slack)
+                || record.channels.contains(&NotificationChannel::Discord)
+        })
+        .collect();
+    if chat_records.is_empty() {
+        return;
+    }
+    let settings: ChatSettings = db
+        .get_setting(CHAT_SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let agents: HashMap<String, Agent> = db
+        .list_agents()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|agent| (agent.id.clone(), agent))
+        .collect();
+    let projects: HashMap<String, String> = db
+        .list_projects()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|project| (project.id, project.name))
+        .collect();
+    let client = build_client();
+
+    for record in chat_records {
+        let Some(agent) = agents.get(&record.agent_id) else {
+            continue;
+        };
+        let project_name = projects
+            .get(&agent.project_id)
+            .map(String::as_str)
+            .unwrap_or("");
+        for channel in [NotificationChannel::Slack, NotificationChannel::Discord] {
+            if !record.channels.contains(&channel) {
+                continue;
+            }
+            let Some(url) = settings.webhook_url(channel, &agent.project_id) else {
+                tracing::warn!("No {:?} webhook configured for {}", channel, project_name);
+                continue;
+            };
+            let template = settings.template.as_deref().unwrap_or(match channel {
+                NotificationChannel::Discord => DISCORD_TEMPLATE,
+                _ => SLACK_TEMPLATE,
+            });
+            let text = render(template, record, &agent.name, project_name);
+            if let Err(error) = post(&client, channel, url, &text).await {
+                tracing::warn!("Failed to post {:?} notification: {}", channel, error);
+                metrics::record_delivery_failure(if channel == NotificationChannel::Slack {
+                    "slack"
+                } else {
+                    "discord"
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn routes_by_project_and_renders_templates() {
+        let settings = ChatSettings {
+            slack_webhook_url: Some("https://hooks.slack.com/default".to_string()),
+            discord_webhook_url: None,
+            project_routes: vec![ChatRoute {
+                project_id: "site".to_string(),
+                slack_webhook_url: Some("https://hooks.slack.com/site".to_string()),
+                discord_webhook_url: None,
+            }],
+            template: None,
+        };
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.webhook_url(NotificationChannel::Slack, "site"),
+            Some("https://hooks.slack.com/site")
+        );
+        assert_eq!(
+            settings.webhook_url(NotificationChannel::Slack, "sdk"),
+            Some("https://hooks.slack.com/default")
+        );
+        assert_eq!(
+            settings.webhook_url(NotificationChannel::Discord, "site"),
+            None
+        );
+
+        let record = NotificationRecord {
+            id: "n1".to_string(),
+            agent_id: "a1".to_string(),
+            event: NotificationEvent::RunNeedsReview,
+            title: "Docs has a run to review".to_string(),
+            body: "Rewrote the intro".to_string(),
+            link: "kanbun://agent/a1".to_string(),
+            channels: vec![NotificationChannel::Slack],
+            created_at: Utc::now(),
+            read_at: None,
+        };
+        assert_eq!(
+            render(SLACK_TEMPLATE, &record, "Docs", "Site"),
+            "*Docs has a run to review* · Site\nRewrote the intro"
+        );
+        assert_eq!(
+            render("{{event}}: {{agent}} {{link}}", &record, "Docs", "Site"),
+            "run_needs_review: Docs kanbun://agent/a1"
+        );
+
+        let invalid = ChatSettings {
+            discord_webhook_url: Some("discord.com/api/webhooks/1".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,564 @@
+use crate::background;
+use crate::db::{Database, StatusChange};
+use crate::deep_link;
+use crate::models::*;
+use crate::timezone::UserTimezone;
+use chrono::{DateTime, NaiveTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+pub mod chat;
+pub mod push;
+
+// ── Notifications ───────────────────────────────────────────────────────────
+// Status transitions, agent messages and finalized runs are matched against
+// each agent's `notify_on` and the notification rules. Whatever matches is
+// recorded in the notification center and delivered to the channels the
+// rules name. Status transitions come from the database's change stream,
+// the same one outbound webhooks use, so a change that is undone quickly
+// still alerts, and at the time it happened.
+
+/// Writes that can raise an alert: messages, finalized runs, statuses.
+const WATCHED_TABLES: [&str; 3] = ["messages", "runs", "agents"];
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// A message and a status change for the same event this close together are
+/// one occurrence.
+const PAIR_WINDOW: chrono::Duration = chrono::Duration::seconds(10);
+const MAX_BODY_CHARS: usize = 200;
+
+/// The event a message reports, for kinds worth alerting on.
+pub fn event_for_message(kind: &MessageKind) -> Option<NotificationEvent> {
+    match kind {
+        MessageKind::Error => Some(NotificationEvent::AgentErrored),
+        MessageKind::Blocked => Some(NotificationEvent::AgentBlocked),
+        MessageKind::Completed => Some(NotificationEvent::AgentCompleted),
+        _ => None,
+    }
+}
+
+fn event_label(event: NotificationEvent) -> &'static str {
+    match event {
+        NotificationEvent::AgentBlocked => "is blocked",
+        NotificationEvent::AgentErrored => "hit an error",
+        NotificationEvent::AgentCompleted => "completed",
+        NotificationEvent::RunCompleted => "finished a run",
+        NotificationEvent::RunNeedsReview => "has a run to review",
+    }
+}
+
+fn event_state(event: NotificationEvent) -> &'static str {
+    match event {
+        NotificationEvent::AgentBlocked => "blocked",
+        NotificationEvent::AgentErrored => "errored",
+        NotificationEvent::AgentCompleted => "completed",
+        NotificationEvent::RunCompleted => "finished",
+        NotificationEvent::RunNeedsReview => "awaiting review",
+    }
+}
+
+/// The event a finalized run reports.
+pub fn event_for_run(status: &RunStatus) -> Option<NotificationEvent> {
+    match status {
+        RunStatus::Completed => Some(NotificationEvent::RunCompleted),
+        RunStatus::NeedsReview => Some(NotificationEvent::RunNeedsReview),
+        RunStatus::InProgress | RunStatus::Failed => None,
+    }
+}
+
+fn excerpt(content: &str) -> String {
+    let line = content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    let line = line.trim();
+    match line.char_indices().nth(MAX_BODY_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+fn format_elapsed(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+fn parse_clock(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// Whether `time` falls in the window; a window whose end is before its
+/// start wraps past midnight.
+pub fn in_quiet_hours(quiet: &QuietHours, time: NaiveTime) -> bool {
+    let (Some(start), Some(end)) = (parse_clock(&quiet.start), parse_clock(&quiet.end)) else {
+        return false;
+    };
+    if start <= end {
+        time >= start && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// Build a rule from input, keeping the original creation time on edits.
+pub fn apply_rule_input(
+    input: NotificationRuleInput,
+    existing: Option<&NotificationRule>,
+) -> Result<NotificationRule, String> {
+    if let Some(quiet) = &input.quiet_hours {
+        if parse_clock(&quiet.start).is_none() || parse_clock(&quiet.end).is_none() {
+            return Err("quiet hours must be HH:MM".to_string());
+        }
+    }
+    if input.repeat_every_minutes == Some(0) {
+        return Err("repeat_every_minutes must be at least 1".to_string());
+    }
+    Ok(NotificationRule {
+        id: existing
+            .map(|rule| rule.id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        event: input.event,
+        project_ids: input.project_ids,
+        channel: input.channel,
+        quiet_hours: input.quiet_hours,
+        repeat_every_minutes: input.repeat_every_minutes,
+        enabled: input.enabled,
+        created_at: existing.map_or_else(Utc::now, |rule| rule.created_at),
+    })
+}
+
+fn rule_matches(rule: &NotificationRule, event: NotificationEvent, agent: &Agent) -> bool {
+    rule.enabled
+        && rule.event == event
+        && (rule.project_ids.is_empty() || rule.project_ids.contains(&agent.project_id))
+}
+
+fn rule_quiet(rule: &NotificationRule, local_time: NaiveTime) -> bool {
+    rule.quiet_hours
+        .as_ref()
+        .is_some_and(|quiet| in_quiet_hours(quiet, local_time))
+}
+
+/// Tracks what has already alerted so each transition, message or reminder
+/// fires once.
+pub struct NotificationDispatcher {
+    cursor: String,     // created_at of the newest message already considered
+    run_cursor: String, // ended_at of the newest finalized run already considered
+    entered_at: HashMap<String, DateTime<Utc>>, // when each agent entered its current status
+    last_reminded: HashMap<(String, String), DateTime<Utc>>, // (rule id, agent id)
+    // The latest message alert and status alert per agent, so a message and
+    // the status change it causes alert once between them.
+    by_message: HashMap<String, (NotificationEvent, DateTime<Utc>)>,
+    by_status: HashMap<String, (NotificationEvent, DateTime<Utc>)>,
+}
+
+/// Whether an earlier alert for the agent covers this one: same event, and
+/// close enough in time to be the same occurrence. Used up when it does.
+fn take_pair(
+    alerts: &mut HashMap<String, (NotificationEvent, DateTime<Utc>)>,
+    agent_id: &str,
+    event: NotificationEvent,
+    at: DateTime<Utc>,
+) -> bool {
+    let paired = alerts
+        .get(agent_id)
+        .is_some_and(|(sent, sent_at)| *sent == event && (at - *sent_at).abs() <= PAIR_WINDOW);
+    if paired {
+        alerts.remove(agent_id);
+    }
+    paired
+}
+
+impl NotificationDispatcher {
+    /// Starts from the current state; nothing that already happened alerts.
+    pub fn new(db: &Database, now: DateTime<Utc>) -> Self {
+        let agents = db.list_agents().unwrap_or_default();
+        Self {
+            entered_at: agents.into_iter().map(|agent| (agent.id, now)).collect(),
+            cursor: now.to_rfc3339(),
+            run_cursor: now.to_rfc3339(),
+            last_reminded: HashMap::new(),
+            by_message: HashMap::new(),
+            by_status: HashMap::new(),
+        }
+    }
+
+    /// Record and return the alerts for `changes` (from
+    /// `Database::subscribe_status_changes`), for messages and runs since the
+    /// last poll, and for reminders due at `now`.
+    pub fn poll(
+        &mut self,
+        db: &Database,
+        changes: &[StatusChange],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<NotificationRecord>, String> {
+        let agents: HashMap<String, Agent> = db
+            .list_agents()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|agent| (agent.id.clone(), agent))
+            .collect();
+        let rules = db.list_notification_rules().map_err(|e| e.to_string())?;
+        let local_time = UserTimezone::load(db).time_of(now);
+        let messages = db
+            .get_agent_messages_since(&self.cursor)
+            .map_err(|e| e.to_string())?;
+        if let Some(last) = messages.last() {
+            self.cursor = last.created_at.to_rfc3339();
+        }
+        let runs = db
+            .get_runs_ended_since(&self.run_cursor)
+            .map_err(|e| e.to_string())?;
+        if let Some(ended_at) = runs.last().and_then(|run| run.ended_at) {
+            self.run_cursor = ended_at.to_rfc3339();
+        }
+
+        // (agent, event, body, when) for everything that happened since
+        // last time. Messages come first so they explain the status change
+        // they cause, with their text as the body.
+        let mut fresh: Vec<(&Agent, NotificationEvent, String, DateTime<Utc>)> = Vec::new();
+        for msg in &messages {
+            let (Some(event), Some(agent)) =
+                (event_for_message(&msg.kind), agents.get(&msg.agent_id))
+            else {
+                continue;
+            };
+            if take_pair(&mut self.by_status, &agent.id, event, msg.created_at) {
+                continue;
+            }
+            self.by_message
+                .insert(agent.id.clone(), (event, msg.created_at));
+            fresh.push((agent, event, excerpt(&msg.content), msg.created_at));
+        }
+        for run in &runs {
+            let (Some(event), Some(agent)) =
+                (event_for_run(&run.status), agents.get(&run.agent_id))
+            else {
+                continue;
+            };
+            fresh.push((
+                agent,
+                event,
+                excerpt(run.summary.as_deref().unwrap_or("")),
+                run.ended_at.unwrap_or(now),
+            ));
+        }
+        for change in changes {
+            let Some(agent) = agents.get(&change.agent_id) else {
+                continue;
+            };
+            self.entered_at.insert(agent.id.clone(), change.at);
+            self.last_reminded
+                .retain(|(_, agent_id), _| agent_id != &agent.id);
+            let Some(event) = NotificationEvent::from_status(&change.to) else {
+                continue;
+            };
+            if take_pair(&mut self.by_message, &agent.id, event, change.at) {
+                continue;
+            }
+            self.by_status.insert(agent.id.clone(), (event, change.at));
+            fresh.push((agent, event, String::new(), change.at));
+        }
+        for agent_id in agents.keys() {
+            self.entered_at.entry(agent_id.clone()).or_insert(now);
+        }
+        self.entered_at
+            .retain(|agent_id, _| agents.contains_key(agent_id));
+
+        let mut records = Vec::new();
+        for (agent, event, body, at) in fresh {
+            let matching: Vec<&NotificationRule> = rules
+                .iter()
+                .filter(|rule| rule_matches(rule, event, agent))
+                .collect();
+            let by_notify_on = event
+                .status()
+                .is_some_and(|status| agent.config.notify_on.contains(&status));
+            if !by_notify_on && matching.is_empty() {
+                continue;
+            }
+            let mut channels = Vec::new();
+            if by_notify_on {
+                channels.push(NotificationChannel::Desktop);
+            }
+            for rule in &matching {
+                if rule.repeat_every_minutes.is_some() {
+                    self.last_reminded
+                        .insert((rule.id.clone(), agent.id.clone()), now);
+                }
+                if !rule_quiet(rule, local_time) && !channels.contains(&rule.channel) {
+                    channels.push(rule.channel);
+                }
+            }
+            records.push(Self::record(
+                agent,
+                event,
+                format!("{} {}", agent.name, event_label(event)),
+                body,
+                channels,
+                at,
+            ));
+        }
+
+        // Reminders while an agent stays blocked (or errored, ...).
+        for rule in &rules {
+            let Some(repeat) = rule.repeat_every_minutes else {
+                continue;
+            };
+            for agent in agents.values() {
+                if NotificationEvent::from_status(&agent.status) != Some(rule.event)
+                    || !rule_matches(rule, rule.event, agent)
+                {
+                    continue;
+                }
+                let key = (rule.id.clone(), agent.id.clone());
+                let entered = self.entered_at.get(&agent.id).copied().unwrap_or(now);
+                let since = self.last_reminded.get(&key).copied().unwrap_or(entered);
+                if now - since < chrono::Duration::minutes(repeat as i64) {
+                    continue;
+                }
+                self.last_reminded.insert(key, now);
+                let channels = if rule_quiet(rule, local_time) {
+                    vec![]
+                } else {
+                    vec![rule.channel]
+                };
+                records.push(Self::record(
+                    agent,
+                    rule.event,
+                    format!("{} is still {}", agent.name, event_state(rule.event)),
+                    format!("For {}", format_elapsed((now - entered).num_minutes())),
+                    channels,
+                    now,
+                ));
+            }
+        }
+
+        for record in &records {
+            db.insert_notification(record).map_err(|e| e.to_string())?;
+        }
+        Ok(records)
+    }
+
+    fn record(
+        agent: &Agent,
+        event: NotificationEvent,
+        title: String,
+        body: String,
+        channels: Vec<NotificationChannel>,
+        at: DateTime<Utc>,
+    ) -> NotificationRecord {
+        NotificationRecord {
+            id: Uuid::new_v4().to_string(),
+            agent_id: agent.id.clone(),
+            event,
+            title,
+            body,
+            link: deep_link::agent_link(&agent.id),
+            channels,
+            created_at: at,
+            read_at: None,
+        }
+    }
+}
+
+fn show(app: &AppHandle, record: &NotificationRecord) {
+    let result = app
+        .notification()
+        .builder()
+        .title(record.title.clone())
+        .body(record.body.clone())
+        .group(record.agent_id.clone())
+        .extra("url", record.link.clone())
+        .show();
+    if let Err(error) = result {
+        tracing::warn!(
+            "Failed to show notification for {}: {}",
+            record.agent_id,
+            error
+        );
+    }
+}
+
+/// Wake on new agent messages, finalized runs and status changes, and once
+/// a minute for reminders.
+pub fn spawn(db: Arc<Database>, app: AppHandle) {
+    background::spawn("Notifications", async move {
+        let mut writes = db.subscribe_writes();
+        let mut changes = db.subscribe_status_changes();
+        let mut reminders = tokio::time::interval(REMINDER_CHECK_INTERVAL);
+        reminders.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut dispatcher = NotificationDispatcher::new(&db, db.now());
+        let mut push_limiter = push::PushRateLimiter::default();
+        loop {
+            let woken_by_write = tokio::select! {
+                open = background::wait_for_write(&mut writes, &WATCHED_TABLES) => Some(open),
+                _ = reminders.tick() => None,
+            };
+            match woken_by_write {
+                Some(false) => return,
+                Some(true) => background::settle(&mut writes).await,
+                None => {}
+            }
+            let (changes, lagged) = background::drain(&mut changes);
+            if lagged {
+                tracing::warn!("Notifications fell behind; some status changes were missed");
+            }
+            match dispatcher.poll(&db, &changes, db.now()) {
+                Ok(records) => {
+                    for record in &records {
+                        if record.channels.contains(&NotificationChannel::Desktop) {
+                            show(&app, record);
+                        }
+                    }
+                    chat::deliver(&db, &records).await;
+                    push::deliver(&db, &records, &mut push_limiter).await;
+                }
+                Err(error) => tracing::warn!("Notification pass failed: {}", error),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::drain;
+    use crate::clock::ManualClock;
+
+    fn setup(start: DateTime<Utc>) -> (Database, Agent) {
+        let db = Database::new(":memory:")
+            .unwrap()
+            .with_clock(Arc::new(ManualClock::new(start)));
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).unwrap();
+        let agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).unwrap();
+        (db, agent)
+    }
+
+    #[test]
+    fn alerts_once_per_matching_message_or_transition() {
+        let now = Utc::now();
+        let (db, agent) = setup(now);
+        let mut changes = db.subscribe_status_changes();
+        let mut dispatcher = NotificationDispatcher::new(&db, now);
+        let mut poll = |db: &Database| {
+            let (changes, _) = drain(&mut changes);
+            dispatcher.poll(db, &changes, now).unwrap()
+        };
+
+        // Completed isn't in the default notify_on and no rule asks for it.
+        db.insert_message(&Message::from_agent(
+            &agent.id,
+            MessageKind::Completed,
+            "Done",
+        ))
+        .unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Completed)
+            .unwrap();
+        assert!(poll(&db).is_empty());
+
+        db.insert_message(&Message::from_agent(
+            &agent.id,
+            MessageKind::Blocked,
+            "Need the staging password\nmore detail",
+        ))
+        .unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .unwrap();
+        let sent = poll(&db);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].title, "Docs is blocked");
+        assert_eq!(sent[0].body, "Need the staging password");
+        assert_eq!(sent[0].link, format!("kanbun://agent/{}", agent.id));
+        assert_eq!(sent[0].channels, vec![NotificationChannel::Desktop]);
+        assert!(poll(&db).is_empty());
+
+        // A transition with no message still alerts.
+        db.update_agent_status(&agent.id, &AgentStatus::Running)
+            .unwrap();
+        assert!(poll(&db).is_empty());
+        db.update_agent_status(&agent.id, &AgentStatus::Errored)
+            .unwrap();
+        let sent = poll(&db);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].event, NotificationEvent::AgentErrored);
+        assert_eq!(sent[0].created_at, now);
+
+        // Blocked and back to idle between passes still alerts, once.
+        db.update_agent_status(&agent.id, &AgentStatus::Idle)
+            .unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .unwrap();
+        db.update_agent_status(&agent.id, &AgentStatus::Idle)
+            .unwrap();
+        let blocked = poll(&db);
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].event, NotificationEvent::AgentBlocked);
+
+        assert_eq!(db.count_unread_notifications().unwrap(), 3);
+        db.mark_notifications_read(Some(std::slice::from_ref(&sent[0].id)))
+            .unwrap();
+        assert_eq!(db.list_notifications(true, 10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rules_add_channels_and_remind_while_blocked() {
+        let start = Utc::now();
+        let (db, mut agent) = setup(start);
+        let mut changes = db.subscribe_status_changes();
+        agent.config.notify_on = vec![];
+        db.update_agent(&agent).unwrap();
+        let rule = apply_rule_input(
+            NotificationRuleInput {
+                id: None,
+                event: NotificationEvent::AgentBlocked,
+                project_ids: vec![agent.project_id.clone()],
+                channel: NotificationChannel::InApp,
+                quiet_hours: None,
+                repeat_every_minutes: Some(60),
+                enabled: true,
+            },
+            None,
+        )
+        .unwrap();
+        db.save_notification_rule(&rule).unwrap();
+
+        let mut dispatcher = NotificationDispatcher::new(&db, start);
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .unwrap();
+        let (blocked, _) = drain(&mut changes);
+        let sent = dispatcher.poll(&db, &blocked, start).unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].channels, vec![NotificationChannel::InApp]);
+
+        let later = start + chrono::Duration::minutes(30);
+        assert!(dispatcher.poll(&db, &[], later).unwrap().is_empty());
+        let later = start + chrono::Duration::minutes(125);
+        let sent = dispatcher.poll(&db, &[], later).unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].title, "Docs is still blocked");
+        assert_eq!(sent[0].body, "For 2h 5m");
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let quiet = QuietHours {
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+        };
+        let at = |clock: &str| parse_clock(clock).unwrap();
+        assert!(in_quiet_hours(&quiet, at("23:30")));
+        assert!(in_quiet_hours(&quiet, at("06:59")));
+        assert!(!in_quiet_hours(&quiet, at("07:00")));
+        assert!(!in_quiet_hours(&quiet, at("12:00")));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::db::Database;
+use crate::metrics;
+use crate::models::*;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+
+// ── Mobile push ─────────────────────────────────────────────────────────────
+// Alerts routed to the Push channel go to ntfy or Pushover, rate limited per
+// agent and per hour so a flapping agent can't flood the phone.
+
+pub const PUSH_SETTINGS_KEY: &str = "push_notifications";
+const DEFAULT_NTFY_SERVER: &str = "https://ntfy.sh";
+const PUSHOVER_ENDPOINT: &str = "https://api.pushover.net/1/messages.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushProvider {
+    Ntfy,
+    Pushover,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushSettings {
+    pub enabled: bool,
+    pub provider: PushProvider,
+    #[serde(default)]
+    pub ntfy_server: Option<String>, // defaults to ntfy.sh
+    #[serde(default)]
+    pub ntfy_topic: Option<String>,
+    #[serde(default)]
+    pub ntfy_token: Option<String>, // for protected topics
+    #[serde(default)]
+    pub pushover_token: Option<String>, // application token
+    #[serde(default)]
+    pub pushover_user: Option<String>, // user or group key
+    #[serde(default = "default_min_interval_minutes")]
+    pub min_interval_minutes: u32, // per agent and event
+    #[serde(default = "default_max_per_hour")]
+    pub max_per_hour: u32,
+}
+
+fn default_min_interval_minutes() -> u32 {
+    5
+}
+
+fn default_max_per_hour() -> u32 {
+    20
+}
+
+impl Default for PushSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: PushProvider::Ntfy,
+            ntfy_server: None,
+            ntfy_topic: None,
+            ntfy_token: None,
+            pushover_token: None,
+            pushover_user: None,
+            min_interval_minutes: default_min_interval_minutes(),
+            max_per_hour: default_max_per_hour(),
+        }
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+impl PushSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_per_hour == 0 {
+            return Err("max_per_hour must be at least 1".to_string());
+        }
+        if !self.enabled {
+            return Ok(());
+        }
+        match self.provider {
+            PushProvider::Ntfy if non_empty(&self.ntfy_topic).is_none() => {
+                Err("an ntfy topic is required".to_string())
+            }
+            PushProvider::Pushover
+                if non_empty(&self.pushover_token).is_none()
+                    || non_empty(&self.pushover_user).is_none() =>
+            {
+                Err("a Pushover application token and user key are required".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Remembers recent pushes; lives as long as the dispatcher thread.
+#[derive(Debug, Default)]
+pub struct PushRateLimiter {
+    sent: VecDeque<DateTime<Utc>>,
+    last_by_agent: HashMap<(String, String), DateTime<Utc>>, // (agent id, event)
+}
+
+impl PushRateLimiter {
+    /// Whether a push for this record may go out now; counts it if so.
+    pub fn allow(
+        &mut self,
+        settings: &PushSettings,
+        record: &NotificationRecord,
+        now: DateTime<Utc>,
+    ) -> bool {
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now - *sent >= Duration::hours(1))
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= settings.max_per_hour as usize {
+            return false;
+        }
+        let key = (record.agent_id.clone(), format!("{:?}", record.event));
+        if self.last_by_agent.get(&key).is_some_and(|last| {
+            now - *last < Duration::minutes(settings.min_interval_minutes as i64)
+        }) {
+            return false;
+        }
+        self.sent.push_back(now);
+        self.last_by_agent.insert(key, now);
+        true
+    }
+}
+
+fn is_urgent(event: NotificationEvent) -> bool {
+    matches!(
+        event,
+        NotificationEvent::AgentBlocked | NotificationEvent::AgentErrored
+    )
+}
+
+pub async fn send(
+    client: &Client,
+    settings: &PushSettings,
+    title: &str,
+    message: &str,
+    urgent: bool,
+) -> Result<(), String> {
+    let request = match settings.provider {
+        PushProvider::Ntfy => {
+            let topic = non_empty(&settings.ntfy_topic).ok_or("an ntfy topic is required")?;
+            let server = non_empty(&settings.ntfy_server).unwrap_or(DEFAULT_NTFY_SERVER);
+            let mut request = client
+                .post(format!("{}/{}", server.trim_end_matches('/'), topic))
+                .header("Title", title)
+                .header("Priority", if urgent { "high" } else { "default" })
+                .body(message.to_string());
+            if let Some(token) = non_empty(&settings.ntfy_token) {
+                request = request.bearer_auth(token);
+            }
+            request
+        }
+        PushProvider::Pushover => client.post(PUSHOVER_ENDPOINT).json(&json!({
+            "token": non_empty(&settings.pushover_token).unwrap_or_default(),
+            "user": non_empty(&settings.pushover_user).unwrap_or_default(),
+            "title": title,
+            "message": if message.is_empty() { title } else { message },
+            "priority": if urgent { 1 } else { 0 },
+        })),
+    };
+    let response = request
+        .send()
+        .await
+        .map_err(|error| format!("push request failed: {}", error))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("push provider returned HTTP {}", status));
+    }
+    Ok(())
+}
+
+/// Push every record routed to the Push channel that the rate limiter lets
+/// through. Failures are logged; the notification center keeps the record.
+pub async fn deliver(db: &Database, records: &[NotificationRecord], limiter: &mut PushRateLimiter) {
+    if !records
+        .iter()
+        .any(|record| record.channels.contains(&NotificationChannel::Push))
+    {
+        return;
+    }
+    let settings: PushSettings = db
+        .get_setting(PUSH_SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if !settings.enabled {
+        return;
+    }
+    let client = super::chat::build_client();
+    for record in records
+        .iter()
+        .filter(|record| record.channels.contains(&NotificationChannel::Push))
+    {
+        if !limiter.allow(&settings, record, Utc::now()) {
+            tracing::debug!("Push for {} rate limited", record.agent_id);
+            continue;
+        }
+        if let Err(error) = send(
+            &client,
+            &settings,
+            &record.title,
+            &record.body,
+            is_urgent(record.event),
+        )
+        .await
+        {
+            tracing::warn!("Failed to send push notification: {}", error);
+            metrics::record_delivery_failure("push");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(agent_id: &str, event: NotificationEvent) -> NotificationRecord {
+        NotificationRecord {
+            id: "n".to_string(),
+            agent_id: agent_id.to_string(),
+            event,
+            title: "Docs is blocked".to_string(),
+            body: String::new(),
+            link: String::new(),
+            channels: vec![NotificationChannel::Push],
+            created_at: Utc::now(),
+            read_at: None,
+        }
+    }
+
+    #[test]
+    fn rate_limits_per_agent_and_per_hour() {
+        let settings = PushSettings {
+            max_per_hour: 3,
+            ..Default::default()
+        };
+        let mut limiter = PushRateLimiter::default();
+        let start = Utc::now();
+        let blocked = record("a1", NotificationEvent::AgentBlocked);
+
+        assert!(limiter.allow(&settings, &blocked, start));
+        assert!(!limiter.allow(&settings, &blocked, start + Duration::minutes(2)));
+        assert!(limiter.allow(
+            &settings,
+            &record("a1", NotificationEvent::AgentErrored),
+            start + Duration::minutes(2)
+        ));
+        assert!(limiter.allow(&settings, &blocked, start + Duration::minutes(6)));
+        // Hourly cap reached.
+        assert!(!limiter.allow(
+            &settings,
+            &record("a2", NotificationEvent::AgentBlocked),
+            start + Duration::minutes(7)
+        ));
+        assert!(limiter.allow(
+            &settings,
+            &record("a2", NotificationEvent::AgentBlocked),
+            start + Duration::minutes(61)
+        ));
+    }
+
+    #[test]
+    fn enabled_settings_need_provider_credentials() {
+        let mut settings = PushSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+        settings.ntfy_topic = Some("kanbun-alerts".to_string());
+        assert!(settings.validate().is_ok());
+        settings.provider = PushProvider::Pushover;
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,450 @@
+use crate::db::Database;
+use crate::models::*;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ── Pipelines ───────────────────────────────────────────────────────────────
+// Links between agents: when the upstream agent completes a run, the
+// downstream agent gets an instruction rendered from that run. Triggered
+// messages carry `metadata.pipeline_id`, `pipeline_link_id` and
+// `upstream_run_id`, which is how a run is only ever handed on once.
+
+pub const PIPELINES_KEY: &str = "pipelines";
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+const LOOKBACK_HOURS: i64 = 24;
+const MAX_OUTPUT_CHARS: usize = 4_000;
+const DEFAULT_TEMPLATE: &str = "{{agent}} finished a run:\n\n{{summary}}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineLink {
+    pub id: String,
+    pub from_agent_id: String,
+    pub to_agent_id: String,
+    // {{agent}}, {{summary}}, {{outputs}}, {{run_id}}; empty uses a default
+    #[serde(default)]
+    pub instruction_template: String,
+    pub created_at: DateTime<Utc>, // runs that ended earlier never trigger it
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub links: Vec<PipelineLink>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Create or replace a pipeline. Links without an id are new.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub links: Vec<PipelineLinkInput>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineLinkInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub from_agent_id: String,
+    pub to_agent_id: String,
+    #[serde(default)]
+    pub instruction_template: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineLinkStatus {
+    pub link_id: String,
+    pub from_agent_id: String,
+    pub to_agent_id: String,
+    pub trigger_count: usize,
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    pub last_upstream_run_id: Option<String>,
+    pub last_downstream_run_id: Option<String>,
+    pub last_downstream_status: Option<RunStatus>,
+}
+
+pub fn load(db: &Database) -> Result<Vec<Pipeline>, String> {
+    db.get_setting::<Vec<Pipeline>>(PIPELINES_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| e.to_string())
+}
+
+/// Merge `input` into the stored pipelines, keeping ids and creation times
+/// of links that already existed.
+pub fn apply_input(
+    pipelines: &mut Vec<Pipeline>,
+    input: PipelineInput,
+) -> Result<Pipeline, String> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err("Pipeline name cannot be empty".to_string());
+    }
+    let now = Utc::now();
+    let existing = input
+        .id
+        .as_ref()
+        .and_then(|id| pipelines.iter().find(|pipeline| &pipeline.id == id))
+        .cloned();
+    let links = input
+        .links
+        .into_iter()
+        .map(|link| {
+            let previous = link.id.as_ref().and_then(|id| {
+                existing
+                    .as_ref()
+                    .and_then(|pipeline| pipeline.links.iter().find(|old| &old.id == id))
+            });
+            PipelineLink {
+                id: link
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+                from_agent_id: link.from_agent_id,
+                to_agent_id: link.to_agent_id,
+                instruction_template: link.instruction_template,
+                created_at: previous.map(|old| old.created_at).unwrap_or(now),
+            }
+        })
+        .collect();
+    let pipeline = Pipeline {
+        id: input.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name: name.to_string(),
+        enabled: input.enabled,
+        links,
+        created_at: existing.map(|old| old.created_at).unwrap_or(now),
+    };
+
+    pipelines.retain(|stored| stored.id != pipeline.id);
+    pipelines.push(pipeline.clone());
+    validate(pipelines)?;
+    Ok(pipeline)
+}
+
+/// Reject self-links and cycles across all enabled pipelines, since a
+/// cycle would hand work around forever.
+pub fn validate(pipelines: &[Pipeline]) -> Result<(), String> {
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for link in pipelines
+        .iter()
+        .filter(|pipeline| pipeline.enabled)
+        .flat_map(|pipeline| pipeline.links.iter())
+    {
+        if link.from_agent_id == link.to_agent_id {
+            return Err("A pipeline link cannot point an agent at itself".to_string());
+        }
+        edges
+            .entry(link.from_agent_id.as_str())
+            .or_default()
+            .push(link.to_agent_id.as_str());
+    }
+
+    fn visit<'a>(
+        node: &'a str,
+        edges: &HashMap<&'a str, Vec<&'a str>>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if let Some(start) = path.iter().position(|seen| *seen == node) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(node);
+            return Some(cycle);
+        }
+        if done.contains(node) {
+            return None;
+        }
+        path.push(node);
+        for next in edges.get(node).into_iter().flatten() {
+            if let Some(cycle) = visit(next, edges, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(node);
+        None
+    }
+
+    let mut done = HashSet::new();
+    let mut starts: Vec<&str> = edges.keys().copied().collect();
+    starts.sort();
+    for start in starts {
+        if let Some(cycle) = visit(start, &edges, &mut vec![], &mut done) {
+            return Err(format!(
+                "Pipeline links form a cycle: {}",
+                cycle.join(" → ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn render_instruction(template: &str, agent_name: &str, run: &Run) -> String {
+    let template = if template.trim().is_empty() {
+        DEFAULT_TEMPLATE
+    } else {
+        template
+    };
+    let outputs = run
+        .outputs
+        .iter()
+        .filter(|output| output.kind != "heartbeat" && output.kind != "instruction")
+        .map(|output| output.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let skip = outputs.chars().count().saturating_sub(MAX_OUTPUT_CHARS);
+    let outputs: String = outputs.chars().skip(skip).collect(); // keep the tail
+    template
+        .replace("{{agent}}", agent_name)
+        .replace("{{summary}}", run.summary.as_deref().unwrap_or(""))
+        .replace("{{outputs}}", &outputs)
+        .replace("{{run_id}}", &run.id)
+}
+
+/// (link id, upstream run id) pairs that have already been handed on.
+fn triggered(messages: &[Message]) -> HashSet<(String, String)> {
+    messages
+        .iter()
+        .filter_map(|message| {
+            let metadata = message.metadata.as_ref()?;
+            Some((
+                metadata.get("pipeline_link_id")?.as_str()?.to_string(),
+                metadata.get("upstream_run_id")?.as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Send downstream instructions for completed upstream runs that haven't
+/// triggered their links yet.
+pub fn trigger_completed_runs(db: &Arc<Database>) -> Result<usize, String> {
+    let pipelines: Vec<Pipeline> = load(db)?
+        .into_iter()
+        .filter(|pipeline| pipeline.enabled && !pipeline.links.is_empty())
+        .collect();
+    if pipelines.is_empty() {
+        return Ok(0);
+    }
+
+    let since = (Utc::now() - Duration::hours(LOOKBACK_HOURS)).to_rfc3339();
+    let runs: Vec<Run> = db
+        .get_finished_runs_since(&since)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|run| run.status == RunStatus::Completed)
+        .collect();
+    if runs.is_empty() {
+        return Ok(0);
+    }
+    let agent_names: HashMap<String, String> = db
+        .list_agents()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|agent| (agent.id, agent.name))
+        .collect();
+
+    let mut sent = 0;
+    for pipeline in &pipelines {
+        let done = triggered(
+            &db.get_pipeline_messages(&pipeline.id)
+                .map_err(|e| e.to_string())?,
+        );
+        for link in &pipeline.links {
+            if !agent_names.contains_key(&link.to_agent_id) {
+                continue;
+            }
+            for run in runs.iter().filter(|run| {
+                run.agent_id == link.from_agent_id
+                    && run.ended_at.is_some_and(|ended| ended >= link.created_at)
+                    && !done.contains(&(link.id.clone(), run.id.clone()))
+            }) {
+                let upstream_name = agent_names
+                    .get(&run.agent_id)
+                    .map(String::as_str)
+                    .unwrap_or("Upstream agent");
+                let content = render_instruction(&link.instruction_template, upstream_name, run);
+                let mut msg =
+                    Message::to_agent(&link.to_agent_id, MessageKind::Instruction, &content);
+                msg.metadata = Some(serde_json::json!({
+                    "pipeline_id": pipeline.id,
+                    "pipeline_link_id": link.id,
+                    "upstream_run_id": run.id,
+                }));
+                crate::commands::enqueue_message(db, msg)?;
+                sent += 1;
+            }
+        }
+    }
+    Ok(sent)
+}
+
+/// Latest trigger per link, with the downstream run it started if any.
+pub fn status(db: &Database, pipeline: &Pipeline) -> Result<Vec<PipelineLinkStatus>, String> {
+    let messages = db
+        .get_pipeline_messages(&pipeline.id)
+        .map_err(|e| e.to_string())?;
+    pipeline
+        .links
+        .iter()
+        .map(|link| {
+            let mut triggers: Vec<&Message> = messages
+                .iter()
+                .filter(|message| {
+                    message
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.get("pipeline_link_id"))
+                        .and_then(serde_json::Value::as_str)
+                        == Some(link.id.as_str())
+                })
+                .collect();
+            triggers.sort_by_key(|message| message.created_at);
+            let last = triggers.last();
+            let downstream = match last {
+                Some(message) => db
+                    .get_run_for_instruction(&message.id)
+                    .map_err(|e| e.to_string())?,
+                None => None,
+            };
+            Ok(PipelineLinkStatus {
+                link_id: link.id.clone(),
+                from_agent_id: link.from_agent_id.clone(),
+                to_agent_id: link.to_agent_id.clone(),
+                trigger_count: triggers.len(),
+                last_triggered_at: last.map(|message| message.created_at),
+                last_upstream_run_id: last
+                    .and_then(|message| message.metadata.as_ref())
+                    .and_then(|metadata| metadata.get("upstream_run_id"))
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string),
+                last_downstream_run_id: downstream.as_ref().map(|run| run.id.clone()),
+                last_downstream_status: downstream.map(|run| run.status),
+            })
+        })
+        .collect()
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        match trigger_completed_runs(&db) {
+            Ok(0) => {}
+            Ok(sent) => tracing::info!("Pipelines sent {} downstream instruction(s)", sent),
+            Err(error) => tracing::warn!("Pipeline pass failed: {}", error),
+        }
+        std::thread::sleep(TICK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(from: &str, to: &str) -> PipelineLinkInput {
+        PipelineLinkInput {
+            id: None,
+            from_agent_id: from.to_string(),
+            to_agent_id: to.to_string(),
+            instruction_template: String::new(),
+        }
+    }
+
+    #[test]
+    fn rejects_cycles_across_pipelines() {
+        let mut pipelines = vec![];
+        apply_input(
+            &mut pipelines,
+            PipelineInput {
+                id: None,
+                name: "Research".to_string(),
+                enabled: true,
+                links: vec![link("research", "proposal")],
+            },
+        )
+        .expect("first pipeline is acyclic");
+        let error = apply_input(
+            &mut pipelines.clone(),
+            PipelineInput {
+                id: None,
+                name: "Loop back".to_string(),
+                enabled: true,
+                links: vec![
+                    link("proposal", "deliverable"),
+                    link("deliverable", "research"),
+                ],
+            },
+        )
+        .expect_err("cycle should be rejected");
+        assert!(error.contains("deliverable → research → proposal → deliverable"));
+
+        let disabled = apply_input(
+            &mut pipelines,
+            PipelineInput {
+                id: None,
+                name: "Loop back".to_string(),
+                enabled: false,
+                links: vec![link("proposal", "research")],
+            },
+        );
+        assert!(disabled.is_ok());
+    }
+
+    #[test]
+    fn completed_runs_trigger_downstream_once() {
+        let db = Arc::new(Database::new(":memory:").expect("in-memory db should initialize"));
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let research = Agent::new("Research", &project.id, AgentKind::Api, "research");
+        let writer = Agent::new("Writer", &project.id, AgentKind::Api, "docs");
+        db.create_agent(&research).expect("agent should insert");
+        db.create_agent(&writer).expect("agent should insert");
+
+        let mut pipelines = vec![];
+        let mut input_link = link(&research.id, &writer.id);
+        input_link.instruction_template = "Draft a proposal from: {{summary}}".to_string();
+        apply_input(
+            &mut pipelines,
+            PipelineInput {
+                id: None,
+                name: "Research to proposal".to_string(),
+                enabled: true,
+                links: vec![input_link],
+            },
+        )
+        .expect("pipeline should save");
+        db.set_setting(PIPELINES_KEY, &pipelines)
+            .expect("pipelines should store");
+
+        db.start_instruction_run(&research.id, None, "Survey the market")
+            .expect("run should start");
+        db.finalize_latest_run(
+            &research.id,
+            RunStatus::Completed,
+            Some("Three competitors".to_string()),
+        )
+        .expect("run should finalize");
+
+        assert_eq!(trigger_completed_runs(&db).expect("pass should run"), 1);
+        assert_eq!(trigger_completed_runs(&db).expect("pass should run"), 0);
+
+        let sent = db
+            .get_pipeline_messages(&pipelines[0].id)
+            .expect("messages should load");
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].agent_id, writer.id);
+        assert_eq!(sent[0].content, "Draft a proposal from: Three competitors");
+
+        let links = status(&db, &pipelines[0]).expect("status should load");
+        assert_eq!(links[0].trigger_count, 1);
+        assert_eq!(links[0].last_downstream_status, Some(RunStatus::InProgress));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::agent_bundle::{self, AgentBundle, PortableAgent, PortableTemplate};
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+// ── Project bundles ─────────────────────────────────────────────────────────
+// A whole project in one JSON file, for archiving a client setup or handing
+// it to a collaborator: the project, its agents with their adapter configs
+// (env dropped, as in agent bundles), context docs and which agents use them,
+// the templates its agents were created from, and optionally finished runs.
+// Messages, connector items and anything tied to this machine's sessions stay
+// behind. An import that fails part way is removed again.
+
+pub const PROJECT_BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectBundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub project: PortableProject,
+    #[serde(default)]
+    pub agents: Vec<PortableAgent>,
+    #[serde(default)]
+    pub templates: Vec<PortableTemplate>,
+    #[serde(default)]
+    pub context_docs: Vec<PortableContextDoc>,
+    #[serde(default)]
+    pub runs: Vec<PortableRun>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableProject {
+    pub name: String,
+    pub color: String,
+    #[serde(default)]
+    pub repo_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableContextDoc {
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub agents: Vec<String>, // names of the agents it's attached to
+}
+
+/// A finished run, tied to its agent by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableRun {
+    pub agent_name: String,
+    pub run: Run,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectExportResult {
+    pub path: String,
+    pub agent_count: usize,
+    pub template_count: usize,
+    pub context_doc_count: usize,
+    pub run_count: usize,
+    pub size_bytes: u64,
+    pub completed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectImport {
+    pub project: Project,
+    pub agents: Vec<Agent>,
+    pub templates: Vec<AgentTemplate>,
+    pub context_doc_count: usize,
+    pub run_count: usize,
+}
+
+pub fn build_bundle(
+    db: &Database,
+    project_id: &str,
+    include_runs: bool,
+) -> Result<ProjectBundle, CommandError> {
+    let project = db
+        .list_projects()?
+        .into_iter()
+        .find(|project| project.id == project_id)
+        .ok_or_else(|| CommandError::not_found(format!("Project not found: {}", project_id)))?;
+    let agents: Vec<Agent> = db
+        .list_agents()?
+        .into_iter()
+        .filter(|agent| agent.project_id == project_id)
+        .collect();
+    let agent_ids: Vec<String> = agents.iter().map(|agent| agent.id.clone()).collect();
+    let used: HashSet<&str> = agents
+        .iter()
+        .filter_map(|agent| agent.template_id.as_deref())
+        .collect();
+    let template_ids: Vec<String> = db
+        .list_agent_templates()?
+        .into_iter()
+        .filter(|template| used.contains(template.id.as_str()))
+        .map(|template| template.id)
+        .collect();
+    let AgentBundle {
+        agents: portable_agents,
+        templates,
+        ..
+    } = agent_bundle::build_bundle(db, &agent_ids, &template_ids, false)?;
+
+    let mut doc_agents: HashMap<String, Vec<String>> = HashMap::new();
+    for agent in &agents {
+        for doc in db.list_agent_context_docs(&agent.id)? {
+            doc_agents
+                .entry(doc.id)
+                .or_default()
+                .push(agent.name.clone());
+        }
+    }
+    let context_docs = db
+        .list_project_context_docs(project_id)?
+        .into_iter()
+        .map(|doc| PortableContextDoc {
+            agents: doc_agents.remove(&doc.id).unwrap_or_default(),
+            title: doc.title,
+            content: doc.content,
+        })
+        .collect();
+
+    let mut runs = Vec::new();
+    if include_runs {
+        for agent in &agents {
+            let mut agent_runs = db.get_runs_for_agent(&agent.id, i64::MAX as usize)?;
+            agent_runs.retain(|run| run.ended_at.is_some());
+            agent_runs.reverse(); // oldest first
+            runs.extend(agent_runs.into_iter().map(|run| PortableRun {
+                agent_name: agent.name.clone(),
+                run,
+            }));
+        }
+    }
+
+    Ok(ProjectBundle {
+        version: PROJECT_BUNDLE_VERSION,
+        exported_at: Utc::now(),
+        project: PortableProject {
+            name: project.name,
+            color: project.color,
+            repo_paths: project.repo_paths,
+        },
+        agents: portable_agents,
+        templates,
+        context_docs,
+        runs,
+    })
+}
+
+pub fn to_json(bundle: &ProjectBundle) -> Result<String, CommandError> {
+    serde_json::to_string_pretty(bundle)
+        .map_err(|error| CommandError::from(format!("failed to write project bundle: {}", error)))
+}
+
+pub fn from_json(content: &str) -> Result<ProjectBundle, CommandError> {
+    let bundle: ProjectBundle = serde_json::from_str(content).map_err(|error| {
+        CommandError::invalid_input(format!("invalid project bundle: {}", error))
+    })?;
+    if bundle.version > PROJECT_BUNDLE_VERSION {
+        return Err(CommandError::invalid_input(format!(
+            "project bundle version {} is newer than this Kanbun supports ({})",
+            bundle.version, PROJECT_BUNDLE_VERSION
+        )));
+    }
+    Ok(bundle)
+}
+
+/// Recreate the bundle as a new project, named `name` or the exported name.
+/// Everything gets fresh ids, so importing twice gives two projects. On an
+/// error nothing of the import is kept.
+pub fn import_bundle(
+    db: &Database,
+    bundle: &ProjectBundle,
+    name: Option<&str>,
+) -> Result<ProjectImport, CommandError> {
+    let name = name.unwrap_or(&bundle.project.name).trim();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input("Project name cannot be empty"));
+    }
+    let mut project = Project::new(name, &bundle.project.color);
+    project.repo_paths = bundle.project.repo_paths.clone();
+    let existing_templates: HashSet<String> = db
+        .list_agent_templates()?
+        .into_iter()
+        .map(|template| template.id)
+        .collect();
+    db.create_project(&project)?;
+
+    let project_id = project.id.clone();
+    import_into(db, bundle, project).inspect_err(|_| {
+        discard_import(db, bundle, &project_id, &existing_templates);
+    })
+}
+
+fn import_into(
+    db: &Database,
+    bundle: &ProjectBundle,
+    project: Project,
+) -> Result<ProjectImport, CommandError> {
+    let imported = agent_bundle::import_bundle(
+        db,
+        &AgentBundle {
+            version: agent_bundle::BUNDLE_VERSION,
+            exported_at: bundle.exported_at,
+            agents: bundle.agents.clone(),
+            templates: bundle.templates.clone(),
+        },
+        Some(&project.id),
+    )?;
+    let agent_ids: HashMap<&str, &str> = imported
+        .agents
+        .iter()
+        .rev() // the first agent with a name wins
+        .map(|agent| (agent.name.as_str(), agent.id.as_str()))
+        .collect();
+
+    let mut agent_docs: HashMap<&str, Vec<String>> = HashMap::new();
+    for portable in &bundle.context_docs {
+        let doc = ProjectContextDocument::new(&project.id, &portable.title, &portable.content);
+        db.save_project_context_doc(&doc)?;
+        for agent_name in &portable.agents {
+            if let Some(agent_id) = agent_ids.get(agent_name.trim()) {
+                agent_docs.entry(agent_id).or_default().push(doc.id.clone());
+            }
+        }
+    }
+    for (agent_id, doc_ids) in &agent_docs {
+        db.set_agent_context_docs(agent_id, doc_ids)?;
+    }
+
+    let mut run_count = 0;
+    for portable in &bundle.runs {
+        let Some(agent_id) = agent_ids.get(portable.agent_name.trim()) else {
+            continue;
+        };
+        let run = Run {
+            id: Uuid::new_v4().to_string(),
+            agent_id: agent_id.to_string(),
+            instruction_message_id: None,
+            source_item: None,
+            ..portable.run.clone()
+        };
+        db.create_run(&run)?;
+        run_count += 1;
+    }
+
+    Ok(ProjectImport {
+        project,
+        agents: imported.agents,
+        templates: imported.templates,
+        context_doc_count: bundle.context_docs.len(),
+        run_count,
+    })
+}
+
+/// Undo a failed import: the project goes with its agents, docs and runs,
+/// and templates the import added are deleted. Templates it replaced by name
+/// keep the bundle's version.
+fn discard_import(
+    db: &Database,
+    bundle: &ProjectBundle,
+    project_id: &str,
+    existing_templates: &HashSet<String>,
+) {
+    if let Err(error) = db.delete_project(project_id) {
+        tracing::warn!(
+            "Failed to remove partly imported project {}: {}",
+            project_id,
+            error
+        );
+    }
+    let names: HashSet<&str> = bundle
+        .templates
+        .iter()
+        .map(|template| template.name.as_str())
+        .collect();
+    let added = match db.list_agent_templates() {
+        Ok(templates) => templates.into_iter().filter(|template| {
+            names.contains(template.name.as_str()) && !existing_templates.contains(&template.id)
+        }),
+        Err(error) => {
+            tracing::warn!("Failed to list templates after a failed import: {}", error);
+            return;
+        }
+    };
+    for template in added {
+        if let Err(error) = db.delete_agent_template(&template.id) {
+            tracing::warn!(
+                "Failed to remove imported template {}: {}",
+                template.name,
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_round_trips_with_docs_and_history() {
+        let source = Database::new(":memory:").unwrap();
+        let project = Project::new("Client X", "#0ea5e9");
+        source.create_project(&project).unwrap();
+        let builder = AgentTemplate::new("Builder", AgentKind::Terminal, "engineering");
+        source.save_agent_template(&builder).unwrap();
+        let agent = builder.instantiate(&project.id, Some("Site"));
+        source.create_agent(&agent).unwrap();
+        source
+            .set_adapter_config(
+                &agent.id,
+                &AdapterConfig {
+                    adapter_type: AdapterType::ClaudeCode,
+                    session_name: Some("kanbun-site".to_string()),
+                    endpoint: None,
+                    command: Some("/tmp/site".to_string()),
+                    env: Some(serde_json::json!({ "API_KEY": "secret" })),
+                },
+            )
+            .unwrap();
+        let doc = ProjectContextDocument::new(&project.id, "Brief", "Ship the landing page");
+        source.save_project_context_doc(&doc).unwrap();
+        source
+            .set_agent_context_docs(&agent.id, std::slice::from_ref(&doc.id))
+            .unwrap();
+        source
+            .save_agent_template(&AgentTemplate::new(
+                "Reviewer",
+                AgentKind::Terminal,
+                "review",
+            ))
+            .unwrap();
+        source
+            .start_instruction_run(&agent.id, None, "Draft the hero copy")
+            .unwrap();
+        source
+            .finalize_latest_run(&agent.id, RunStatus::Completed, Some("Drafted".into()))
+            .unwrap();
+        source
+            .start_instruction_run(&agent.id, None, "still going")
+            .unwrap();
+
+        let without_runs = build_bundle(&source, &project.id, false).unwrap();
+        assert!(without_runs.runs.is_empty());
+        let json = to_json(&build_bundle(&source, &project.id, true).unwrap()).unwrap();
+        assert!(!json.contains("secret"));
+        assert!(!json.contains("still going"));
+        assert!(!json.contains("Reviewer")); // no agent uses it
+
+        let target = Database::new(":memory:").unwrap();
+        let imported = import_bundle(&target, &from_json(&json).unwrap(), None).unwrap();
+        assert_eq!(imported.project.name, "Client X");
+        assert_eq!(imported.agents.len(), 1);
+        assert_eq!(imported.templates.len(), 1);
+        assert_eq!(imported.run_count, 1);
+
+        let copy = &imported.agents[0];
+        assert_eq!(copy.project_id, imported.project.id);
+        assert_eq!(copy.template_id.as_ref(), Some(&imported.templates[0].id));
+        let adapter = target.get_adapter_config(&copy.id).unwrap().unwrap();
+        assert!(adapter.env.is_none());
+        let docs = target.list_agent_context_docs(&copy.id).unwrap();
+        assert_eq!(docs[0].content, "Ship the landing page");
+        let runs = target.get_runs_for_agent(&copy.id, 10).unwrap();
+        assert_eq!(runs[0].summary.as_deref(), Some("Drafted"));
+
+        let renamed = import_bundle(&target, &from_json(&json).unwrap(), Some("Client Y")).unwrap();
+        assert_ne!(renamed.project.id, imported.project.id);
+        assert_eq!(renamed.project.name, "Client Y");
+        assert_eq!(target.list_agent_templates().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn failed_import_leaves_nothing_behind() {
+        let db = Database::new(":memory:").unwrap();
+        let mut bundle = from_json(
+            r##"{"version": 1, "exported_at": "2026-01-01T00:00:00Z",
+                "project": {"name": "Client X", "color": "#0ea5e9"},
+                "templates": [{"name": "Builder", "kind": "terminal",
+                               "function_tag": "engineering", "autonomy_level": "supervised"}]}"##,
+        )
+        .unwrap();
+        let mut agent = Agent::new("Site", "", AgentKind::Terminal, "engineering");
+        agent.working_directory = Some("/nonexistent/kanbun/site".to_string());
+        bundle.agents.push(PortableAgent {
+            name: agent.name,
+            project_name: String::new(),
+            kind: agent.kind,
+            function_tag: agent.function_tag,
+            working_directory: agent.working_directory,
+            config: agent.config,
+            adapter: None,
+            template: Some("Builder".to_string()),
+        });
+
+        assert!(import_bundle(&db, &bundle, None).is_err());
+        assert!(db.list_projects().unwrap().is_empty());
+        assert!(db.list_agent_templates().unwrap().is_empty());
+    }
+}
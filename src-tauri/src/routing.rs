@@ -0,0 +1,147 @@
+use crate::models::{Agent, Message};
+use serde::Serialize;
+
+// ── Instruction routing ─────────────────────────────────────────────────────
+// `route_instruction` fires work at a project without naming an agent. Each
+// idle agent scores a point per capability tag (or its function tag) that
+// the instruction mentions, less a little for every run it started in the
+// last LOAD_WINDOW_HOURS, so equally suited agents take turns.
+
+pub const MAX_CAPABILITY_CHARS: usize = 48;
+pub const LOAD_WINDOW_HOURS: i64 = 24;
+const MATCH_WEIGHT: i64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteCandidate {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub matched: Vec<String>, // tags the instruction mentions
+    pub recent_runs: usize,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutedInstruction {
+    pub message: Message,
+    pub candidates: Vec<RouteCandidate>, // best first; the first got the message
+}
+
+/// Trim, lowercase, and dedupe tags so "Rust" and "rust " match together.
+pub fn normalize_capabilities(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_CAPABILITY_CHARS {
+            return Err(format!(
+                "Capability '{}' is longer than {} characters",
+                tag, MAX_CAPABILITY_CHARS
+            ));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Lowercase words; `+` and `#` count as letters so "c++" and "c#" survive.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '+' || c == '#'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether the tag's words appear, in order and adjacent, in `text_words`.
+/// A "kind:" prefix ("repo:kanbun", "lang:go") is ignored.
+fn mentions(text_words: &[String], tag: &str) -> bool {
+    let tag = tag.split_once(':').map_or(tag, |(_, value)| value);
+    let tag_words = words(tag);
+    !tag_words.is_empty()
+        && text_words
+            .windows(tag_words.len())
+            .any(|window| window == tag_words.as_slice())
+}
+
+pub fn score(agent: &Agent, recent_runs: usize, text: &str) -> RouteCandidate {
+    let text_words = words(text);
+    let mut matched: Vec<String> = agent
+        .config
+        .capabilities
+        .iter()
+        .filter(|tag| mentions(&text_words, tag))
+        .cloned()
+        .collect();
+    let function_tag = agent.function_tag.trim().to_lowercase();
+    if !function_tag.is_empty()
+        && !matched.contains(&function_tag)
+        && mentions(&text_words, &function_tag)
+    {
+        matched.push(function_tag);
+    }
+    RouteCandidate {
+        agent_id: agent.id.clone(),
+        agent_name: agent.name.clone(),
+        score: matched.len() as i64 * MATCH_WEIGHT - recent_runs as i64,
+        matched,
+        recent_runs,
+    }
+}
+
+/// Candidates best first. Ties go to the agent idle longest.
+pub fn rank(agents: &[(Agent, usize)], text: &str) -> Vec<RouteCandidate> {
+    let mut scored: Vec<(&Agent, RouteCandidate)> = agents
+        .iter()
+        .map(|(agent, recent_runs)| (agent, score(agent, *recent_runs, text)))
+        .collect();
+    scored.sort_by(|(a, a_score), (b, b_score)| {
+        b_score
+            .score
+            .cmp(&a_score.score)
+            .then(a.last_active_at.cmp(&b.last_active_at))
+            .then(a.name.cmp(&b.name))
+    });
+    scored.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentKind;
+
+    fn agent(name: &str, function_tag: &str, capabilities: &[&str]) -> Agent {
+        let mut agent = Agent::new(name, "p1", AgentKind::Terminal, function_tag);
+        agent.config.capabilities = capabilities.iter().map(|tag| tag.to_string()).collect();
+        agent
+    }
+
+    #[test]
+    fn rank_prefers_matching_tags_then_lighter_load() {
+        let rust = agent("Rust", "engineering", &["rust", "repo:kanbun"]);
+        let web = agent("Web", "landing_page", &["next.js", "css"]);
+        let spare = agent("Spare", "engineering", &["rust", "repo:kanbun"]);
+
+        let ranked = rank(
+            &[(web.clone(), 0), (rust.clone(), 3), (spare, 0)],
+            "Fix the Rust panic in kanbun's db layer.",
+        );
+        assert_eq!(ranked[0].agent_name, "Spare");
+        assert_eq!(ranked[0].matched, vec!["rust", "repo:kanbun"]);
+        assert_eq!(ranked[1].agent_name, "Rust");
+        assert_eq!(ranked[1].score, 17);
+
+        let ranked = rank(&[(rust, 0), (web, 5)], "Tweak the Next.js landing page CSS");
+        assert_eq!(ranked[0].agent_name, "Web");
+        assert_eq!(ranked[0].matched, vec!["next.js", "css", "landing_page"]);
+
+        assert_eq!(
+            normalize_capabilities(vec![" Rust ".into(), "rust".into(), "".into()]).unwrap(),
+            vec!["rust"]
+        );
+        assert!(normalize_capabilities(vec!["x".repeat(MAX_CAPABILITY_CHARS + 1)]).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::db::Database;
+use crate::models::RunStatus;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ── Run archive ─────────────────────────────────────────────────────────────
+// Runs and their JSON blobs dominate the database after a few months. Runs
+// that finished more than `after_days` ago move to `runs_archive`: the
+// headline columns (status, times, summary, cost, labels) stay queryable,
+// while outputs, file changes, git snapshot, review and comments are kept
+// gzipped in one payload until `restore_run` puts the run back. Archived
+// runs drop out of feeds, analytics and search; days already rolled into
+// daily stats keep their numbers.
+
+pub const RUN_ARCHIVE_SETTINGS_KEY: &str = "run_archive";
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+pub(crate) const ARCHIVE_BATCH: usize = 200;
+
+/// Scheduled archival. Off by default; `archive_runs` works either way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunArchiveSettings {
+    pub enabled: bool,
+    #[serde(default = "default_after_days")]
+    pub after_days: u32, // runs that ended longer ago than this are archived
+}
+
+fn default_after_days() -> u32 {
+    90
+}
+
+impl Default for RunArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_days: default_after_days(),
+        }
+    }
+}
+
+impl RunArchiveSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.after_days == 0 {
+            return Err("Runs must be at least a day old to archive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Headline fields of an archived run, without its payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedRun {
+    pub id: String,
+    pub agent_id: String,
+    pub status: RunStatus,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub summary: Option<String>,
+    pub token_in: u64,
+    pub token_out: u64,
+    pub cost: f64,
+    pub labels: Vec<String>,
+    pub output_count: usize,
+    pub archived_at: DateTime<Utc>,
+    pub payload_bytes: usize, // compressed size
+}
+
+/// Archive every finished run that ended more than `after_days` ago, a
+/// batch per transaction so the app isn't locked out for long.
+pub fn archive_older_than(db: &Database, after_days: u32) -> rusqlite::Result<usize> {
+    let cutoff = (db.now() - chrono::Duration::days(after_days as i64)).to_rfc3339();
+    let mut archived = 0;
+    loop {
+        let batch = db.archive_runs_ended_before(&cutoff, ARCHIVE_BATCH)?;
+        archived += batch;
+        if batch < ARCHIVE_BATCH {
+            return Ok(archived);
+        }
+    }
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        let settings = db
+            .get_setting::<RunArchiveSettings>(RUN_ARCHIVE_SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if settings.enabled && settings.validate().is_ok() {
+            match archive_older_than(&db, settings.after_days) {
+                Ok(0) => {}
+                Ok(archived) => tracing::info!("Archived {} run(s)", archived),
+                Err(error) => tracing::warn!("Failed to archive runs: {}", error),
+            }
+        }
+        std::thread::sleep(ARCHIVE_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::models::*;
+
+    #[test]
+    fn archived_runs_keep_headlines_and_restore_whole() {
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-01-01T09:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+        let db = Database::new(":memory:")
+            .expect("in-memory db should initialize")
+            .with_clock(clock.clone());
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Worker", &project.id, AgentKind::Terminal, "ops");
+        db.create_agent(&agent).expect("agent should insert");
+
+        let old = db
+            .start_instruction_run(&agent.id, None, "Rotate the logs")
+            .unwrap();
+        db.append_run_output(&agent.id, "output", &"rotated ".repeat(500))
+            .unwrap();
+        db.finalize_latest_run(&agent.id, RunStatus::Completed, Some("Done".into()))
+            .unwrap();
+        db.save_run_comment(&RunComment::new(&old.id, Some(1), "Looks right"))
+            .unwrap();
+
+        clock.advance(Duration::from_secs(40 * 24 * 60 * 60));
+        let recent = db.start_instruction_run(&agent.id, None, "Again").unwrap();
+        db.finalize_latest_run(&agent.id, RunStatus::Completed, None)
+            .unwrap();
+
+        assert_eq!(archive_older_than(&db, 30).unwrap(), 1);
+        assert!(db.get_run(&old.id).unwrap().is_none());
+        assert!(db.get_run(&recent.id).unwrap().is_some());
+        let archived = db.list_archived_runs(Some(&agent.id), None, 10).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].summary.as_deref(), Some("Done"));
+        assert_eq!(archived[0].output_count, 2);
+        assert!(archived[0].payload_bytes < 500);
+
+        let restored = db
+            .restore_archived_run(&old.id)
+            .unwrap()
+            .expect("run should restore");
+        assert_eq!(restored.outputs.len(), 2);
+        assert_eq!(restored.ended_at, archived[0].ended_at);
+        assert_eq!(db.list_run_comments(&old.id).unwrap().len(), 1);
+        assert!(db.list_archived_runs(None, None, 10).unwrap().is_empty());
+        assert!(db.restore_archived_run(&old.id).unwrap().is_none());
+    }
+}
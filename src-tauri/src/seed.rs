@@ -1,128 +1,114 @@
 use crate::db::Database;
 use crate::models::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-/// Pre-populate with Sid's agent portfolio for immediate usefulness
-pub fn seed_initial_data(db: &Database) {
-    // ── Projects ────────────────────────────────────────────────────────
-
-    let threadfork = Project::new("ThreadFork", "#6366f1"); // indigo
-    let datafog = Project::new("DataFog", "#10b981"); // emerald
-    let personal = Project::new("Personal", "#f59e0b"); // amber
-    let consulting = Project::new("Consulting", "#ec4899"); // pink
-
-    let _ = db.create_project(&threadfork);
-    let _ = db.create_project(&datafog);
-    let _ = db.create_project(&personal);
-    let _ = db.create_project(&consulting);
-
-    // ── ThreadFork agents ───────────────────────────────────────────────
-
-    let agents = vec![
-        Agent::new(
-            "TF Landing Page",
-            &threadfork.id,
-            AgentKind::Terminal,
-            "landing_page",
-        ),
-        Agent::new(
-            "TF Marketing & Outbound",
-            &threadfork.id,
-            AgentKind::Script,
-            "marketing",
-        ),
-        Agent::new(
-            "TF Core App (Tauri)",
-            &threadfork.id,
-            AgentKind::Terminal,
-            "engineering",
-        ),
-        Agent::new(
-            "TF Speech Pipeline",
-            &threadfork.id,
-            AgentKind::Terminal,
-            "engineering",
-        ),
-        Agent::new(
-            "TF MCP Integrations",
-            &threadfork.id,
-            AgentKind::Terminal,
-            "engineering",
-        ),
-        // ── DataFog agents ──────────────────────────────────────────────
-        Agent::new("DF Python SDK", &datafog.id, AgentKind::Terminal, "sdk"),
-        Agent::new(
-            "DF Docs & Content",
-            &datafog.id,
-            AgentKind::Script,
-            "marketing",
-        ),
-        Agent::new(
-            "DF Landing Page",
-            &datafog.id,
-            AgentKind::Terminal,
-            "landing_page",
-        ),
-        // ── Personal agents ─────────────────────────────────────────────
-        Agent::new(
-            "Golf Improvement Tracker",
-            &personal.id,
-            AgentKind::Script,
-            "personal",
-        ),
-        Agent::new(
-            "Morning Routine Ops",
-            &personal.id,
-            AgentKind::Script,
-            "personal",
-        ),
-        Agent::new(
-            "Research & Learning",
-            &personal.id,
-            AgentKind::Api,
-            "research",
-        ),
-        // ── Consulting agents ───────────────────────────────────────────
-        Agent::new(
-            "AI Consulting Outbound",
-            &consulting.id,
-            AgentKind::Script,
-            "marketing",
-        ),
-        Agent::new(
-            "Proposal Generator",
-            &consulting.id,
-            AgentKind::Api,
-            "sales",
-        ),
-        Agent::new(
-            "Client Research",
-            &consulting.id,
-            AgentKind::Api,
-            "research",
-        ),
-        Agent::new(
-            "Deliverable Builder",
-            &consulting.id,
-            AgentKind::Terminal,
-            "engineering",
-        ),
-    ];
-
-    for agent in &agents {
-        let _ = db.create_agent(agent);
-        let _ = db.set_adapter_config(
-            &agent.id,
-            &AdapterConfig {
-                adapter_type: AdapterType::Mock,
-                session_name: None,
-                endpoint: None,
-                command: None,
-                env: None,
-            },
-        );
+/// Settings key recording which rows belong to the demo workspace.
+pub const DEMO_WORKSPACE_KEY: &str = "demo_workspace";
+
+/// What `create_demo_workspace` added, so it can be taken out again
+/// without touching anything the user created alongside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemoWorkspace {
+    pub project_ids: Vec<String>,
+    pub agent_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Name, kind and function tag of one sample agent.
+type DemoAgent = (&'static str, AgentKind, &'static str);
+
+const DEMO_PROJECTS: [(&str, &str, &[DemoAgent]); 3] = [
+    (
+        "Sample Website",
+        "#6366f1", // indigo
+        &[
+            ("Landing Page", AgentKind::Terminal, "landing_page"),
+            ("Blog & Newsletter", AgentKind::Script, "marketing"),
+            ("SEO Audit", AgentKind::Api, "research"),
+        ],
+    ),
+    (
+        "Sample Mobile App",
+        "#10b981", // emerald
+        &[
+            ("iOS Client", AgentKind::Terminal, "engineering"),
+            ("API Backend", AgentKind::Terminal, "engineering"),
+            ("Release Notes", AgentKind::Script, "docs"),
+        ],
+    ),
+    (
+        "Sample Research",
+        "#f59e0b", // amber
+        &[
+            ("Competitor Scan", AgentKind::Api, "research"),
+            ("Weekly Digest", AgentKind::Script, "research"),
+        ],
+    ),
+];
+
+pub fn get_demo_workspace(db: &Database) -> Result<Option<DemoWorkspace>, String> {
+    db.get_setting(DEMO_WORKSPACE_KEY)
+        .map_err(|e| e.to_string())
+}
+
+/// Add a few sample projects with agents on Mock adapters, for trying the
+/// app without connecting real agents.
+pub fn create_demo_workspace(db: &Database) -> Result<DemoWorkspace, String> {
+    if get_demo_workspace(db)?.is_some() {
+        return Err("A demo workspace already exists".to_string());
     }
 
-    log::info!("Seeded {} projects and {} agents", 4, agents.len());
+    let mut workspace = DemoWorkspace {
+        project_ids: vec![],
+        agent_ids: vec![],
+        created_at: Utc::now(),
+    };
+    for (name, color, agents) in DEMO_PROJECTS {
+        let project = Project::new(name, color);
+        db.create_project(&project).map_err(|e| e.to_string())?;
+        workspace.project_ids.push(project.id.clone());
+        for (name, kind, function_tag) in agents {
+            let agent = Agent::new(name, &project.id, kind.clone(), function_tag);
+            db.create_agent(&agent).map_err(|e| e.to_string())?;
+            db.set_adapter_config(&agent.id, &mock_adapter_config())
+                .map_err(|e| e.to_string())?;
+            workspace.agent_ids.push(agent.id);
+        }
+    }
+    db.set_setting(DEMO_WORKSPACE_KEY, &workspace)
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Created demo workspace with {} projects and {} agents",
+        workspace.project_ids.len(),
+        workspace.agent_ids.len()
+    );
+    Ok(workspace)
+}
+
+/// Remove the demo projects and everything under them. Returns what was
+/// removed, or None when there was no demo workspace.
+pub fn delete_demo_workspace(db: &Database) -> Result<Option<DemoWorkspace>, String> {
+    let Some(workspace) = get_demo_workspace(db)? else {
+        return Ok(None);
+    };
+    for project_id in &workspace.project_ids {
+        db.delete_project(project_id).map_err(|e| e.to_string())?;
+    }
+    db.delete_setting(DEMO_WORKSPACE_KEY)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(workspace))
+}
+
+fn mock_adapter_config() -> AdapterConfig {
+    AdapterConfig {
+        adapter_type: AdapterType::Mock,
+        session_name: None,
+        endpoint: None,
+        command: None,
+        env: None,
+    }
 }
 
 /// Ensure every existing agent has at least a default adapter config.
@@ -132,7 +118,7 @@ pub fn ensure_default_adapter_configs(db: &Database) {
     let agents = match db.list_agents() {
         Ok(agents) => agents,
         Err(error) => {
-            log::warn!(
+            tracing::warn!(
                 "Failed to list agents for adapter config backfill: {}",
                 error
             );
@@ -144,20 +130,59 @@ pub fn ensure_default_adapter_configs(db: &Database) {
         match db.get_adapter_config(&agent.id) {
             Ok(Some(_)) => {}
             Ok(None) => {
-                let _ = db.set_adapter_config(
-                    &agent.id,
-                    &AdapterConfig {
-                        adapter_type: AdapterType::Mock,
-                        session_name: None,
-                        endpoint: None,
-                        command: None,
-                        env: None,
-                    },
-                );
+                let _ = db.set_adapter_config(&agent.id, &mock_adapter_config());
             }
             Err(error) => {
-                log::warn!("Failed checking adapter config for {}: {}", agent.id, error);
+                tracing::warn!("Failed checking adapter config for {}: {}", agent.id, error);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demo_workspace_is_removed_without_touching_user_data() {
+        let db = Database::new(":memory:").expect("db should open");
+        let mine = Project::new("Mine", "#ec4899");
+        db.create_project(&mine).unwrap();
+        let my_agent = Agent::new("Mine", &mine.id, AgentKind::Api, "docs");
+        db.create_agent(&my_agent).unwrap();
+
+        let workspace = create_demo_workspace(&db).expect("demo should be created");
+        assert!(create_demo_workspace(&db).is_err());
+        assert_eq!(db.list_projects().unwrap().len(), 4);
+        let demo_agent = &workspace.agent_ids[0];
+        assert_eq!(
+            db.get_adapter_config(demo_agent)
+                .unwrap()
+                .map(|config| config.adapter_type),
+            Some(AdapterType::Mock)
+        );
+        let run = Run::new(demo_agent, RunStatus::Completed, None);
+        db.create_run(&run).unwrap();
+        db.save_run_comment(&RunComment::new(&run.id, None, "nice"))
+            .unwrap();
+        db.insert_message(&Message::to_agent(
+            demo_agent,
+            MessageKind::Instruction,
+            "go",
+        ))
+        .unwrap();
+
+        let removed = delete_demo_workspace(&db).expect("demo should be removed");
+        assert_eq!(
+            removed.map(|removed| removed.agent_ids),
+            Some(workspace.agent_ids)
+        );
+        let projects = db.list_projects().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, mine.id);
+        assert_eq!(db.list_agents().unwrap().len(), 1);
+        assert!(db.get_run(&run.id).unwrap().is_none());
+        assert!(get_demo_workspace(&db).unwrap().is_none());
+        assert!(delete_demo_workspace(&db).unwrap().is_none());
+    }
+}
@@ -0,0 +1,118 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use std::path::Path;
+
+// ── Encrypted snapshots ─────────────────────────────────────────────────────
+// Snapshots hold connector tokens and adapter env in the clear, so exports
+// can be sealed with a passphrase: AES-256-GCM under an Argon2id key.
+//
+// Layout: MAGIC | salt (16) | nonce (12) | ciphertext + tag
+
+const MAGIC: &[u8; 8] = b"KBNSNAP1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+pub const MIN_PASSPHRASE_CHARS: usize = 8;
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, String> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|error| format!("failed to derive snapshot key: {}", error))?;
+    Ok(key)
+}
+
+pub fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!(
+            "passphrase must be at least {} characters",
+            MIN_PASSPHRASE_CHARS
+        ));
+    }
+    Ok(())
+}
+
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    validate_passphrase(passphrase)?;
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "failed to encrypt snapshot".to_string())?;
+
+    let mut sealed = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub fn decrypt(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    if !is_sealed(sealed) || sealed.len() < HEADER_LEN {
+        return Err("not an encrypted Kanbun snapshot".to_string());
+    }
+    let salt = &sealed[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = Nonce::from_slice(&sealed[MAGIC.len() + SALT_LEN..HEADER_LEN]);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt)?);
+    cipher
+        .decrypt(nonce, &sealed[HEADER_LEN..])
+        .map_err(|_| "wrong passphrase or corrupted snapshot".to_string())
+}
+
+fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Whether the file at `path` is an encrypted snapshot (by its header).
+pub fn is_encrypted_file(path: &Path) -> bool {
+    use std::io::Read;
+    let mut header = [0u8; 8];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| is_sealed(&header))
+}
+
+pub fn encrypt_file(source: &Path, destination: &Path, passphrase: &str) -> Result<(), String> {
+    let plaintext = std::fs::read(source)
+        .map_err(|error| format!("failed to read {}: {}", source.display(), error))?;
+    std::fs::write(destination, encrypt(&plaintext, passphrase)?)
+        .map_err(|error| format!("failed to write {}: {}", destination.display(), error))
+}
+
+pub fn decrypt_file(source: &Path, destination: &Path, passphrase: &str) -> Result<(), String> {
+    let sealed = std::fs::read(source)
+        .map_err(|error| format!("failed to read {}: {}", source.display(), error))?;
+    std::fs::write(destination, decrypt(&sealed, passphrase)?)
+        .map_err(|error| format!("failed to write {}: {}", destination.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_snapshots_open_only_with_their_passphrase() {
+        let plaintext = b"SQLite format 3\0 todoist token abc123";
+        let sealed = encrypt(plaintext, "correct horse").expect("encrypt should succeed");
+        assert!(is_sealed(&sealed));
+        assert!(!sealed
+            .windows(b"abc123".len())
+            .any(|window| window == b"abc123"));
+        assert_eq!(decrypt(&sealed, "correct horse").unwrap(), plaintext);
+        assert!(decrypt(&sealed, "wrong horse").is_err());
+
+        // Fresh salt and nonce every time.
+        assert_ne!(encrypt(plaintext, "correct horse").unwrap(), sealed);
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tampered, "correct horse").is_err());
+        assert!(decrypt(plaintext, "correct horse").is_err());
+        assert!(encrypt(plaintext, "short").is_err());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::db::Database;
+use crate::errors::CommandError;
+use crate::models::*;
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub const SUMMARIZER_SETTINGS_KEY: &str = "run_summarizer";
+const REQUEST_TIMEOUT_SECONDS: u64 = 60;
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+const MAX_SUMMARY_CHARS: usize = 280;
+const LOOKBACK_HOURS: i64 = 24;
+const BATCH_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SummarizerProvider {
+    #[default]
+    Ollama,
+    OpenAi, // any OpenAI-compatible chat completions API
+    Anthropic,
+}
+
+impl SummarizerProvider {
+    fn default_endpoint(&self) -> &'static str {
+        match self {
+            SummarizerProvider::Ollama => "http://localhost:11434",
+            SummarizerProvider::OpenAi => "https://api.openai.com",
+            SummarizerProvider::Anthropic => "https://api.anthropic.com",
+        }
+    }
+}
+
+/// Optional LLM pass that rewrites placeholder run summaries once a run
+/// finishes. Off by default; nothing leaves the machine until enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizerSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub provider: SummarizerProvider,
+    #[serde(default)]
+    pub endpoint: Option<String>, // defaults per provider
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_max_input_chars")]
+    pub max_input_chars: usize, // tail of the run outputs sent to the model
+}
+
+fn default_max_input_chars() -> usize {
+    12_000
+}
+
+impl Default for SummarizerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: SummarizerProvider::Ollama,
+            endpoint: None,
+            model: "llama3.2".to_string(),
+            api_key: None,
+            max_input_chars: default_max_input_chars(),
+        }
+    }
+}
+
+impl SummarizerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model.trim().is_empty() {
+            return Err("model is required".to_string());
+        }
+        if self.max_input_chars < 500 {
+            return Err("max_input_chars must be at least 500".to_string());
+        }
+        let has_key = self
+            .api_key
+            .as_deref()
+            .is_some_and(|key| !key.trim().is_empty());
+        if self.provider != SummarizerProvider::Ollama && !has_key {
+            return Err("an API key is required for this provider".to_string());
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self) -> String {
+        self.endpoint
+            .as_deref()
+            .map(str::trim)
+            .filter(|endpoint| !endpoint.is_empty())
+            .unwrap_or(self.provider.default_endpoint())
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
+
+/// True for summaries that are just the instruction preview written when the
+/// run started, as opposed to something the agent or an operator wrote.
+pub fn is_placeholder_summary(summary: Option<&str>) -> bool {
+    match summary.map(str::trim) {
+        None | Some("") | Some("Running instruction") => true,
+        Some(summary) => summary.starts_with("Running: "),
+    }
+}
+
+/// Prompt describing the run, or None when it produced nothing worth
+/// summarizing. Only the tail of the outputs is kept within the budget.
+pub fn build_prompt(run: &Run, max_input_chars: usize) -> Option<String> {
+    let lines: Vec<String> = run
+        .outputs
+        .iter()
+        .filter(|output| output.kind != "heartbeat" && output.kind != "watchdog")
+        .filter(|output| !output.content.trim().is_empty())
+        .map(|output| format!("[{}] {}", output.kind, output.content.trim()))
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut transcript = lines.join("\n");
+    let total = transcript.chars().count();
+    if total > max_input_chars {
+        transcript = transcript.chars().skip(total - max_input_chars).collect();
+    }
+
+    let status = serde_json::to_value(&run.status)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let mut prompt = String::from(
+        "Summarize what this coding agent run accomplished in one or two plain sentences \
+         (under 280 characters). Mention the outcome and anything left unresolved. \
+         Reply with the summary only.\n\n",
+    );
+    if let Some(instruction) = run.summary.as_deref() {
+        prompt.push_str(&format!("Task: {}\n", instruction.trim()));
+    }
+    prompt.push_str(&format!("Final status: {}\n", status));
+    if !run.file_changes.is_empty() {
+        prompt.push_str(&format!("Files changed: {}\n", run.file_changes.len()));
+    }
+    prompt.push_str("\nOutputs:\n");
+    prompt.push_str(&transcript);
+    Some(prompt)
+}
+
+/// Trim model chatter down to a single short paragraph.
+pub fn clean_summary(raw: &str) -> Option<String> {
+    let paragraph = raw
+        .trim()
+        .split("\n\n")
+        .next()
+        .unwrap_or("")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let paragraph = paragraph
+        .trim_start_matches("Summary:")
+        .trim()
+        .trim_matches('"')
+        .trim();
+    if paragraph.is_empty() {
+        return None;
+    }
+    let mut summary: String = paragraph.chars().take(MAX_SUMMARY_CHARS).collect();
+    if paragraph.chars().count() > MAX_SUMMARY_CHARS {
+        summary.push_str("...");
+    }
+    Some(summary)
+}
+
+fn build_client() -> Client {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+async fn request_completion(
+    client: &Client,
+    settings: &SummarizerSettings,
+    prompt: &str,
+) -> Result<String, String> {
+    let endpoint = settings.endpoint();
+    let api_key = settings.api_key.as_deref().unwrap_or("").trim();
+    let request = match settings.provider {
+        SummarizerProvider::Ollama => client
+            .post(format!("{}/api/generate", endpoint))
+            .json(&json!({ "model": settings.model, "prompt": prompt, "stream": false })),
+        SummarizerProvider::OpenAi => client
+            .post(format!("{}/v1/chat/completions", endpoint))
+            .bearer_auth(api_key)
+            .json(&json!({
+                "model": settings.model,
+                "messages": [{ "role": "user", "content": prompt }],
+                "max_tokens": 200,
+            })),
+        SummarizerProvider::Anthropic => client
+            .post(format!("{}/v1/messages", endpoint))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&json!({
+                "model": settings.model,
+                "max_tokens": 200,
+                "messages": [{ "role": "user", "content": prompt }],
+            })),
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|error| format!("summarizer request failed: {}", error))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("summarizer returned HTTP {}", status));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|error| format!("summarizer returned invalid JSON: {}", error))?;
+
+    let text = match settings.provider {
+        SummarizerProvider::Ollama => body.get("response"),
+        SummarizerProvider::OpenAi => body.pointer("/choices/0/message/content"),
+        SummarizerProvider::Anthropic => body.pointer("/content/0/text"),
+    };
+    text.and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "summarizer response had no text".to_string())
+}
+
+/// Summarize one run and store the result. Returns the updated run, or None
+/// when the run has no outputs to work from.
+pub async fn summarize_run(
+    db: &Database,
+    settings: &SummarizerSettings,
+    run_id: &str,
+) -> Result<Option<Run>, CommandError> {
+    let mut run = db
+        .get_run(run_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Run not found: {}", run_id)))?;
+    let Some(prompt) = build_prompt(&run, settings.max_input_chars) else {
+        return Ok(None);
+    };
+
+    let raw = request_completion(&build_client(), settings, &prompt).await?;
+    let summary = clean_summary(&raw).ok_or_else(|| "summarizer returned no text".to_string())?;
+    db.set_run_summary(&run.id, &summary)?;
+    run.summary = db.get_run(&run.id)?.and_then(|stored| stored.summary);
+    Ok(Some(run))
+}
+
+/// Summarize recently finished runs that still carry a placeholder. Each run
+/// is tried once per process so a broken endpoint doesn't cause a retry loop.
+pub async fn summarize_pending(
+    db: &Database,
+    attempted: &mut HashSet<String>,
+) -> Result<usize, String> {
+    let settings = db
+        .get_setting::<SummarizerSettings>(SUMMARIZER_SETTINGS_KEY)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    if !settings.enabled {
+        return Ok(0);
+    }
+
+    let since = (Utc::now() - Duration::hours(LOOKBACK_HOURS)).to_rfc3339();
+    let runs = db
+        .get_finished_runs_since(&since)
+        .map_err(|e| e.to_string())?;
+    let pending: Vec<Run> = runs
+        .into_iter()
+        .filter(|run| is_placeholder_summary(run.summary.as_deref()))
+        .filter(|run| !attempted.contains(&run.id))
+        .take(BATCH_SIZE)
+        .collect();
+
+    let mut summarized = 0;
+    for run in pending {
+        attempted.insert(run.id.clone());
+        match summarize_run(db, &settings, &run.id).await {
+            Ok(Some(_)) => summarized += 1,
+            Ok(None) => {}
+            Err(error) => tracing::warn!("Failed to summarize run {}: {}", run.id, error),
+        }
+    }
+    Ok(summarized)
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(error) => {
+                tracing::warn!("Run summarizer disabled: {}", error);
+                return;
+            }
+        };
+        let mut attempted = HashSet::new();
+        loop {
+            if let Err(error) = runtime.block_on(summarize_pending(&db, &mut attempted)) {
+                tracing::warn!("Run summarizer pass failed: {}", error);
+            }
+            std::thread::sleep(TICK_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_skips_heartbeats_and_summary_is_trimmed() {
+        let mut run = Run::new(
+            "agent-1",
+            RunStatus::Completed,
+            Some("Running: Fix the login redirect".to_string()),
+        );
+        assert!(is_placeholder_summary(run.summary.as_deref()));
+        assert!(build_prompt(&run, 1_000).is_none());
+
+        for (kind, content) in [
+            ("heartbeat", "still alive"),
+            ("message", "Patched redirect in auth.rs"),
+        ] {
+            run.outputs.push(RunOutput {
+                kind: kind.to_string(),
+                content: content.to_string(),
+                timestamp: Utc::now(),
+            });
+        }
+        let prompt = build_prompt(&run, 1_000).expect("prompt should build");
+        assert!(prompt.contains("Patched redirect"));
+        assert!(!prompt.contains("still alive"));
+        assert!(prompt.contains("Final status: completed"));
+
+        let cleaned = clean_summary("Summary: \"Fixed the redirect loop.\"\n\nExtra notes")
+            .expect("summary should clean");
+        assert_eq!(cleaned, "Fixed the redirect loop.");
+        assert!(!is_placeholder_summary(Some(&cleaned)));
+        assert!(clean_summary("   ").is_none());
+    }
+}
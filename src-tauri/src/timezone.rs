@@ -0,0 +1,138 @@
+use crate::db::Database;
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+// ── Timezone ────────────────────────────────────────────────────────────────
+// The zone calendar days are counted in: "today" on the dashboard, all-day
+// due dates, daily stats, weekly digest weeks, agenda schedules and quiet
+// hours. Follows the system unless an IANA name is set, which helps when
+// the machine's clock runs in UTC but its user doesn't.
+
+pub const TIMEZONE_SETTINGS_KEY: &str = "timezone";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimezoneSettings {
+    #[serde(default)]
+    pub timezone: Option<String>, // IANA name, e.g. "America/New_York"; unset follows the system
+}
+
+impl TimezoneSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.zone().map(|_| ())
+    }
+
+    pub fn zone(&self) -> Result<UserTimezone, String> {
+        match self
+            .timezone
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            None => Ok(UserTimezone::System),
+            Some(name) => name
+                .parse::<Tz>()
+                .map(UserTimezone::Named)
+                .map_err(|_| format!("Unknown timezone: {}", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UserTimezone {
+    #[default]
+    System,
+    Named(Tz),
+}
+
+impl UserTimezone {
+    /// The configured zone; the system's when none is set or the stored
+    /// name no longer parses.
+    pub fn load(db: &Database) -> Self {
+        db.get_setting::<TimezoneSettings>(TIMEZONE_SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|settings| settings.zone().ok())
+            .unwrap_or_default()
+    }
+
+    /// The calendar day `at` falls on.
+    pub fn date_of(&self, at: DateTime<Utc>) -> NaiveDate {
+        self.local(at).date()
+    }
+
+    /// The wall-clock time at `at`.
+    pub fn time_of(&self, at: DateTime<Utc>) -> NaiveTime {
+        self.local(at).time()
+    }
+
+    pub fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Self::System => at.with_timezone(&Local).naive_local(),
+            Self::Named(tz) => at.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// The earliest instant with this wall-clock time, if it exists (a DST
+    /// gap skips some).
+    pub fn instant(&self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Self::System => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.with_timezone(&Utc)),
+            Self::Named(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.with_timezone(&Utc)),
+        }
+    }
+
+    /// When `day` begins: midnight, or the first hour after it where a DST
+    /// change skips midnight.
+    pub fn day_start(&self, day: NaiveDate) -> DateTime<Utc> {
+        (0..3)
+            .find_map(|hour| self.instant(day.and_hms_opt(hour, 0, 0)?))
+            .unwrap_or_else(|| day.and_time(NaiveTime::MIN).and_utc())
+    }
+
+    /// Bounds of a calendar day, [start, end).
+    pub fn day_bounds(&self, day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        let next = day.checked_add_days(Days::new(1)).unwrap_or(day);
+        (self.day_start(day), self.day_start(next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_zones_move_day_boundaries() {
+        let settings = TimezoneSettings {
+            timezone: Some("America/Los_Angeles".to_string()),
+        };
+        let zone = settings.zone().expect("zone should parse");
+        // 7pm Pacific on the 15th is already the 16th in UTC.
+        let evening = DateTime::parse_from_rfc3339("2026-10-16T02:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let day = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        assert_eq!(zone.date_of(evening), day);
+        let (start, end) = zone.day_bounds(day);
+        assert_eq!(start.to_rfc3339(), "2026-10-15T07:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2026-10-16T07:00:00+00:00");
+
+        // The day DST ends is 25 hours long.
+        let fall_back = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        let (start, end) = zone.day_bounds(fall_back);
+        assert_eq!(end - start, chrono::Duration::hours(25));
+
+        assert!(TimezoneSettings {
+            timezone: Some("Mars/Olympus_Mons".to_string())
+        }
+        .validate()
+        .is_err());
+        assert_eq!(TimezoneSettings::default().zone(), Ok(UserTimezone::System));
+    }
+}
@@ -0,0 +1,480 @@
+use crate::background;
+use crate::db::Database;
+use crate::metrics;
+use crate::models::{Message, Run};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::filter::{self, EnvFilter};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{fmt, Layer};
+
+// ── Tracing ─────────────────────────────────────────────────────────────────
+// The global subscriber is a tracing-subscriber registry with two layers: a
+// stderr formatter filtered by RUST_LOG, and a collector that keeps events in
+// a ring buffer along with the fields of the spans they happened in. `log`
+// records from dependencies are bridged in by tracing-log. Spans carry a
+// `correlation_id` — the instruction message id a run is keyed by, or a fresh
+// id for a connector sync — so one flow can be followed across the command,
+// adapter and watcher threads, and into the database writes that start,
+// finish and reopen its run. Debug events are only collected from the app
+// itself; dependencies are collected at info and up. With an OTLP endpoint
+// set, collected events are also exported as OTLP logs.
+
+pub const TRACING_SETTINGS_KEY: &str = "tracing";
+const BUFFER_CAPACITY: usize = 2000;
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5); // batching delay after new events
+const MAX_EXPORT_BATCH: usize = 500;
+const APP_TARGET: &str = env!("CARGO_CRATE_NAME"); // the library, kanbun_lib
+const EXPORT_TARGET: &str = "otlp_export"; // the exporter's own warnings, never collected
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceEvent {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: String, // "error", "warn", "info" or "debug"
+    pub target: String,
+    pub message: String,
+    pub correlation_id: Option<String>,
+    pub spans: Vec<String>,               // outermost first
+    pub fields: BTreeMap<String, String>, // event fields, then span fields
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingSettings {
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>, // base URL; logs go to {endpoint}/v1/logs
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    #[serde(default = "default_export_level")]
+    pub export_level: String, // least severe level exported
+}
+
+fn default_service_name() -> String {
+    "kanbun".to_string()
+}
+
+fn default_export_level() -> String {
+    "info".to_string()
+}
+
+impl Default for TracingSettings {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: default_service_name(),
+            export_level: default_export_level(),
+        }
+    }
+}
+
+impl TracingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        parse_level(&self.export_level)?;
+        match self.otlp_endpoint.as_deref().map(str::trim) {
+            Some(url) if !url.is_empty() && !url.starts_with("http") => {
+                Err(format!("OTLP endpoint must be http(s): {}", url))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn parse_level(level: &str) -> Result<Level, String> {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "error" => Ok(Level::ERROR),
+        "warn" => Ok(Level::WARN),
+        "info" => Ok(Level::INFO),
+        "debug" => Ok(Level::DEBUG),
+        other => Err(format!("unknown level: {}", other)),
+    }
+}
+
+/// Span for handling one message; its correlation id is the run it belongs
+/// to, else the message itself (an instruction starts a run keyed by its id).
+pub fn message_span(msg: &Message, stage: &'static str) -> tracing::Span {
+    tracing::info_span!(
+        "message",
+        stage,
+        correlation_id = msg.run_key().unwrap_or(&msg.id),
+        agent_id = msg.agent_id.as_str(),
+        message_id = msg.id.as_str(),
+        kind = msg.kind.as_str(),
+    )
+}
+
+/// Span for a database write to a run, correlated by the instruction that
+/// opened it like the messages that drive it, whichever thread writes.
+pub fn run_span(run: &Run) -> tracing::Span {
+    tracing::info_span!(
+        "db_write",
+        correlation_id = run.instruction_message_id.as_deref().unwrap_or(&run.id),
+        agent_id = run.agent_id.as_str(),
+        run_id = run.id.as_str(),
+    )
+}
+
+/// Span for an adapter's delivery thread, so everything logged on it lands
+/// in the agent's log file.
+pub fn adapter_span(agent_id: &str, adapter: &'static str) -> tracing::Span {
+    tracing::info_span!("adapter", agent_id, adapter)
+}
+
+#[derive(Default)]
+struct FieldCollector {
+    message: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl FieldCollector {
+    fn push(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = Some(value),
+            // Where a bridged `log` record came from; the target is kept.
+            name if name.starts_with("log.") => {}
+            name => self.fields.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl Visit for FieldCollector {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field, format!("{:?}", value));
+    }
+}
+
+/// A span's fields, kept in its registry extensions.
+struct SpanFields(Vec<(String, String)>);
+
+#[derive(Default)]
+struct Collected {
+    next_seq: AtomicU64,
+    events: Mutex<VecDeque<TraceEvent>>,
+    logged: Notify, // wakes the exporter
+}
+
+/// The collecting layer; clones share one buffer.
+#[derive(Clone, Default)]
+pub struct TraceCollector {
+    collected: Arc<Collected>,
+}
+
+static INSTALLED: OnceLock<TraceCollector> = OnceLock::new();
+
+/// Make the registry the global subscriber and bridge `log` records into it.
+/// Call once, first thing.
+pub fn install() {
+    let collector = INSTALLED.get_or_init(TraceCollector::default).clone();
+    let formatter = fmt::layer()
+        .with_writer(std::io::stderr) // stdout is the MCP transport
+        .with_filter(EnvFilter::from_default_env());
+    let subscriber = subscriber(collector).with(formatter);
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        tracing::warn!("A tracing subscriber was already installed");
+        return;
+    }
+    if let Err(error) = tracing_log::LogTracer::init() {
+        tracing::warn!("Could not bridge log records into tracing: {}", error);
+    }
+}
+
+/// A registry feeding `collector`, for `install` and tests.
+fn subscriber(collector: TraceCollector) -> impl Subscriber + for<'a> LookupSpan<'a> {
+    tracing_subscriber::registry().with(collector.with_filter(filter::filter_fn(collects)))
+}
+
+fn collects(metadata: &Metadata<'_>) -> bool {
+    let level = *metadata.level();
+    let target = metadata.target();
+    target != EXPORT_TARGET
+        && (level <= Level::INFO || (level == Level::DEBUG && is_app_target(target)))
+}
+
+fn level_name(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "error",
+        Level::WARN => "warn",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        _ => "trace",
+    }
+}
+
+impl TraceCollector {
+    /// Newest first; `min_level` keeps that level and anything more severe.
+    pub fn recent(
+        &self,
+        correlation_id: Option<&str>,
+        min_level: Level,
+        limit: usize,
+    ) -> Vec<TraceEvent> {
+        let Ok(events) = self.collected.events.lock() else {
+            return vec![];
+        };
+        events
+            .iter()
+            .rev()
+            .filter(|event| {
+                correlation_id.is_none_or(|id| event.correlation_id.as_deref() == Some(id))
+            })
+            .filter(|event| parse_level(&event.level).is_ok_and(|level| level <= min_level))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn since(&self, seq: u64, min_level: Level) -> Vec<TraceEvent> {
+        let Ok(events) = self.collected.events.lock() else {
+            return vec![];
+        };
+        events
+            .iter()
+            .filter(|event| event.seq > seq)
+            .filter(|event| parse_level(&event.level).is_ok_and(|level| level <= min_level))
+            .take(MAX_EXPORT_BATCH)
+            .cloned()
+            .collect()
+    }
+}
+
+fn is_app_target(target: &str) -> bool {
+    target
+        .strip_prefix(APP_TARGET)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+pub fn installed() -> Option<&'static TraceCollector> {
+    INSTALLED.get()
+}
+
+impl<S> Layer<S> for TraceCollector
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldCollector::default();
+        attributes.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldCollector::default();
+        values.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                fields.0.extend(visitor.fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldCollector::default();
+        event.record(&mut visitor);
+        let mut fields: BTreeMap<String, String> = visitor.fields.into_iter().collect();
+        let mut span_names = Vec::new();
+        // Innermost first, so the nearest span wins a field both define.
+        for span in ctx.event_scope(event).into_iter().flatten() {
+            span_names.push(span.name().to_string());
+            if let Some(SpanFields(span_fields)) = span.extensions().get::<SpanFields>() {
+                for (name, value) in span_fields {
+                    fields.entry(name.clone()).or_insert_with(|| value.clone());
+                }
+            }
+        }
+        span_names.reverse();
+        let metadata = event.metadata();
+        let trace_event = TraceEvent {
+            seq: self.collected.next_seq.fetch_add(1, Ordering::Relaxed) + 1,
+            timestamp: Utc::now(),
+            level: level_name(metadata.level()).to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message.unwrap_or_default(),
+            correlation_id: fields.get("correlation_id").cloned(),
+            spans: span_names,
+            fields,
+        };
+        if let Some(agent_id) = trace_event.fields.get("agent_id") {
+            crate::agent_logs::record(agent_id, &trace_event);
+        }
+        if let Ok(mut events) = self.collected.events.lock() {
+            if events.len() >= BUFFER_CAPACITY {
+                events.pop_front();
+            }
+            events.push_back(trace_event);
+        }
+        self.collected.logged.notify_one();
+    }
+}
+
+fn severity_number(level: &str) -> u8 {
+    match level {
+        "error" => 17,
+        "warn" => 13,
+        "info" => 9,
+        "debug" => 5,
+        _ => 1,
+    }
+}
+
+/// OTLP/HTTP JSON body for a batch of events, as log records.
+pub fn otlp_logs_payload(service_name: &str, events: &[TraceEvent]) -> serde_json::Value {
+    let attribute =
+        |key: &str, value: &str| json!({ "key": key, "value": { "stringValue": value } });
+    let records: Vec<serde_json::Value> = events
+        .iter()
+        .map(|event| {
+            let mut attributes = vec![attribute("target", &event.target)];
+            if !event.spans.is_empty() {
+                attributes.push(attribute("spans", &event.spans.join(" > ")));
+            }
+            attributes.extend(
+                event
+                    .fields
+                    .iter()
+                    .map(|(name, value)| attribute(name, value)),
+            );
+            json!({
+                "timeUnixNano": event
+                    .timestamp
+                    .timestamp_nanos_opt()
+                    .unwrap_or_default()
+                    .to_string(),
+                "severityNumber": severity_number(&event.level),
+                "severityText": event.level.to_uppercase(),
+                "body": { "stringValue": event.message },
+                "attributes": attributes,
+            })
+        })
+        .collect();
+    json!({
+        "resourceLogs": [{
+            "resource": { "attributes": [attribute("service.name", service_name)] },
+            "scopeLogs": [{ "scope": { "name": "kanbun" }, "logRecords": records }],
+        }]
+    })
+}
+
+/// Ship buffered events to the configured OTLP endpoint, in batches a few
+/// seconds after new events arrive. Only wakes for settings writes while no
+/// endpoint is set.
+pub fn spawn_exporter(db: Arc<Database>) {
+    let Some(collector) = installed().cloned() else {
+        return;
+    };
+    let mut writes = db.subscribe_writes();
+    background::spawn("OTLP export", async move {
+        let client = crate::notifications::chat::build_client();
+        let mut exported = collector.collected.next_seq.load(Ordering::Relaxed);
+        loop {
+            let settings: TracingSettings = db
+                .get_setting(TRACING_SETTINGS_KEY)
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            let Some(endpoint) = settings
+                .otlp_endpoint
+                .as_deref()
+                .map(str::trim)
+                .filter(|endpoint| !endpoint.is_empty())
+            else {
+                exported = collector.collected.next_seq.load(Ordering::Relaxed);
+                if !background::wait_for_write(&mut writes, &["settings"]).await {
+                    return;
+                }
+                background::settle(&mut writes).await;
+                continue;
+            };
+            let level = parse_level(&settings.export_level).unwrap_or(Level::INFO);
+            let batch = collector.since(exported, level);
+            if let Some(last) = batch.last().map(|event| event.seq) {
+                let url = format!("{}/v1/logs", endpoint.trim_end_matches('/'));
+                let body = otlp_logs_payload(&settings.service_name, &batch);
+                let sent = match client.post(&url).json(&body).send().await {
+                    Ok(response) => response.error_for_status().map(|_| ()),
+                    Err(error) => Err(error),
+                };
+                match sent {
+                    Ok(()) => exported = last,
+                    Err(error) => {
+                        // Not collected, so a failing exporter doesn't feed itself.
+                        tracing::warn!(target: EXPORT_TARGET, "OTLP export to {} failed: {}", url, error);
+                        metrics::record_delivery_failure("otlp");
+                    }
+                }
+            }
+
+            tokio::select! {
+                open = background::wait_for_write(&mut writes, &["settings"]) => {
+                    if !open {
+                        return;
+                    }
+                }
+                _ = collector.collected.logged.notified() => {}
+            }
+            tokio::time::sleep(EXPORT_INTERVAL).await;
+            background::drain(&mut writes);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentKind, MessageKind, Project, RunStatus};
+
+    #[test]
+    fn events_carry_the_correlation_id_of_their_spans() {
+        let collector = TraceCollector::default();
+        let db = Database::new(":memory:").unwrap();
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).unwrap();
+        let agent = crate::models::Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).unwrap();
+        let msg = Message::to_agent(&agent.id, MessageKind::Instruction, "Ship it");
+        tracing::subscriber::with_default(subscriber(collector.clone()), || {
+            let span = message_span(&msg, "deliver");
+            let _entered = span.enter();
+            tracing::warn!(attempt = 2, "Adapter busy");
+        });
+        // The run is finished from another thread, outside the message span.
+        let mut run = Run::new(&agent.id, RunStatus::InProgress, None);
+        run.instruction_message_id = Some(msg.id.clone());
+        db.create_run(&run).unwrap();
+        tracing::subscriber::with_default(subscriber(collector.clone()), || {
+            db.finalize_run_for(&agent.id, Some(&msg.id), RunStatus::Completed, None)
+                .unwrap();
+            tracing::info!("Unrelated");
+            tracing::debug!(target: "hyper", "Connection reused");
+        });
+
+        let events = collector.recent(Some(&msg.id), Level::DEBUG, 10);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].message, "Run finalized");
+        assert_eq!(events[0].spans, vec!["db_write"]);
+        assert_eq!(events[0].fields["run_id"], run.id);
+        assert_eq!(events[1].fields["attempt"], "2");
+        assert_eq!(events[1].fields["stage"], "deliver");
+        assert_eq!(collector.recent(None, Level::WARN, 10).len(), 1);
+        assert_eq!(collector.recent(None, Level::DEBUG, 10).len(), 3);
+
+        let payload = otlp_logs_payload("kanbun", &events[1..]);
+        let record = &payload["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["severityNumber"], 13);
+        assert_eq!(record["body"]["stringValue"], "Adapter busy");
+    }
+}
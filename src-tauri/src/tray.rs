@@ -0,0 +1,243 @@
+use crate::background;
+use crate::commands;
+use crate::db::Database;
+use crate::models::*;
+use std::sync::Arc;
+use tauri::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+// ── System tray ─────────────────────────────────────────────────────────────
+// A tray icon whose title and tooltip carry live running/blocked/errored
+// counts, with a menu to jump to agents and pause or resume everything.
+
+const TRAY_ID: &str = "kanbun";
+/// What the title, tooltip and menu are built from.
+const WATCHED_TABLES: [&str; 3] = ["agents", "notifications", "settings"];
+const MAX_MENU_AGENTS: usize = 10;
+const OPEN_AGENT_PREFIX: &str = "open_agent:";
+pub const OPEN_AGENT_EVENT: &str = "tray:open-agent";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrayCounts {
+    pub running: usize,
+    pub blocked: usize,
+    pub errored: usize,
+    pub unread: u32, // notification center
+}
+
+impl TrayCounts {
+    pub fn from_agents(agents: &[Agent], unread: u32) -> Self {
+        let count = |status: AgentStatus| agents.iter().filter(|a| a.status == status).count();
+        Self {
+            running: count(AgentStatus::Running),
+            blocked: count(AgentStatus::Blocked),
+            errored: count(AgentStatus::Errored),
+            unread,
+        }
+    }
+
+    /// Short text next to the icon (macOS), e.g. "▶3 ⏸1 ✕2"; empty when
+    /// nothing is running or needs attention.
+    pub fn title(&self) -> String {
+        [
+            ("▶", self.running),
+            ("⏸", self.blocked),
+            ("✕", self.errored),
+        ]
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(symbol, count)| format!("{}{}", symbol, count))
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+
+    pub fn tooltip(&self) -> String {
+        let mut tooltip = format!(
+            "Kanbun: {} running, {} blocked, {} errored",
+            self.running, self.blocked, self.errored
+        );
+        if self.unread > 0 {
+            tooltip.push_str(&format!(", {} unread", self.unread));
+        }
+        tooltip
+    }
+}
+
+/// Agents worth a menu entry: errored and blocked first, then running.
+pub fn menu_agents(agents: &[Agent]) -> Vec<&Agent> {
+    let rank = |agent: &Agent| match agent.status {
+        AgentStatus::Errored => Some(0),
+        AgentStatus::Blocked => Some(1),
+        AgentStatus::Running => Some(2),
+        _ => None,
+    };
+    let mut listed: Vec<&Agent> = agents.iter().filter(|a| rank(a).is_some()).collect();
+    listed.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.name.cmp(&b.name)));
+    listed.truncate(MAX_MENU_AGENTS);
+    listed
+}
+
+fn status_marker(status: &AgentStatus) -> &'static str {
+    match status {
+        AgentStatus::Errored => "✕",
+        AgentStatus::Blocked => "⏸",
+        AgentStatus::Running => "▶",
+        _ => "",
+    }
+}
+
+fn build_menu(app: &AppHandle, agents: &[Agent], any_paused: bool) -> tauri::Result<Menu<Wry>> {
+    let mut agent_items = Vec::new();
+    for agent in menu_agents(agents) {
+        agent_items.push(MenuItem::with_id(
+            app,
+            format!("{}{}", OPEN_AGENT_PREFIX, agent.id),
+            format!("{} {}", status_marker(&agent.status), agent.name),
+            true,
+            None::<&str>,
+        )?);
+    }
+    let agent_refs: Vec<&dyn IsMenuItem<Wry>> = agent_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let agents_menu = Submenu::with_items(app, "Open agent", !agent_refs.is_empty(), &agent_refs)?;
+
+    let show = MenuItem::with_id(app, "show", "Show Kanbun", true, None::<&str>)?;
+    let pause = if any_paused {
+        MenuItem::with_id(app, "resume_all", "Resume all agents", true, None::<&str>)?
+    } else {
+        MenuItem::with_id(app, "pause_all", "Pause all agents", true, None::<&str>)?
+    };
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit Kanbun", true, None::<&str>)?;
+    Menu::with_items(app, &[&show, &agents_menu, &pause, &separator, &quit])
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    if let Some(agent_id) = id.strip_prefix(OPEN_AGENT_PREFIX) {
+        show_main_window(app);
+        if let Err(error) = app.emit(OPEN_AGENT_EVENT, agent_id.to_string()) {
+            tracing::warn!("Failed to open agent {} from tray: {}", agent_id, error);
+        }
+        return;
+    }
+    match id {
+        "show" => show_main_window(app),
+        "pause_all" | "resume_all" => {
+            let db = app.state::<Arc<Database>>();
+            let result = if id == "pause_all" {
+                commands::pause_agents(db.inner(), None).map(|_| ())
+            } else {
+                commands::resume_agents(db.inner(), None).map(|_| ())
+            };
+            if let Err(error) = result {
+                tracing::warn!("Tray {} failed: {}", id, error);
+            }
+            refresh(app, db.inner());
+        }
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+fn refresh(app: &AppHandle, db: &Database) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let agents = db.list_agents().unwrap_or_default();
+    let counts = TrayCounts::from_agents(&agents, db.count_unread_notifications().unwrap_or(0));
+    let any_paused = !commands::load_paused_agents(db)
+        .unwrap_or_default()
+        .is_empty();
+    let _ = tray.set_title(Some(counts.title()));
+    let _ = tray.set_tooltip(Some(counts.tooltip()));
+    match build_menu(app, &agents, any_paused) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(error) => tracing::warn!("Failed to rebuild tray menu: {}", error),
+    }
+}
+
+/// Create the tray icon and keep it current: woken by writes to the tables
+/// it shows, and only rebuilt when the agents' statuses, the unread count or
+/// the paused state changed.
+pub fn spawn(app: &AppHandle, db: Arc<Database>) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Kanbun")
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    refresh(app, &db);
+
+    let app = app.clone();
+    let mut writes = db.subscribe_writes();
+    background::spawn("Tray refresh", async move {
+        let mut last = None;
+        while background::wait_for_write(&mut writes, &WATCHED_TABLES).await {
+            background::settle(&mut writes).await;
+            let agents = db.list_agents().unwrap_or_default();
+            let snapshot = (
+                agents
+                    .iter()
+                    .map(|agent| (agent.id.clone(), agent.name.clone(), agent.status.clone()))
+                    .collect::<Vec<_>>(),
+                db.count_unread_notifications().unwrap_or(0),
+                commands::load_paused_agents(&db).unwrap_or_default().len(),
+            );
+            if last.as_ref() != Some(&snapshot) {
+                refresh(&app, &db);
+                last = Some(snapshot);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(name: &str, status: AgentStatus) -> Agent {
+        let mut agent = Agent::new(name, "p1", AgentKind::Terminal, "docs");
+        agent.status = status;
+        agent
+    }
+
+    #[test]
+    fn counts_and_menu_order_follow_attention() {
+        let agents = vec![
+            agent("Site", AgentStatus::Running),
+            agent("Docs", AgentStatus::Blocked),
+            agent("Idle", AgentStatus::Idle),
+            agent("SDK", AgentStatus::Errored),
+            agent("Blog", AgentStatus::Running),
+        ];
+        let counts = TrayCounts::from_agents(&agents, 4);
+        assert_eq!(counts.title(), "▶2 ⏸1 ✕1");
+        assert_eq!(
+            counts.tooltip(),
+            "Kanbun: 2 running, 1 blocked, 1 errored, 4 unread"
+        );
+        assert_eq!(TrayCounts::default().title(), "");
+
+        let names: Vec<&str> = menu_agents(&agents)
+            .iter()
+            .map(|agent| agent.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["SDK", "Docs", "Blog", "Site"]);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::db::Database;
+use crate::models::*;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub const WATCHDOG_SETTINGS_KEY: &str = "run_watchdog";
+const WATCHDOG_OUTPUT_KIND: &str = "watchdog";
+const ORPHANED_RUN_NOTE: &str =
+    "Interrupted by restart; the adapter session ended before the run finished.";
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Stuck-run detection. A run is stale once it has produced nothing for
+/// `stale_after_minutes`; past `hard_timeout_minutes` it is finalized as
+/// NeedsReview so it stops looking busy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    pub stale_after_minutes: u32,
+    #[serde(default)]
+    pub send_status_request: bool,
+    #[serde(default)]
+    pub hard_timeout_minutes: Option<u32>,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stale_after_minutes: 30,
+            send_status_request: true,
+            hard_timeout_minutes: Some(180),
+        }
+    }
+}
+
+impl WatchdogSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stale_after_minutes == 0 {
+            return Err("stale_after_minutes must be at least 1".to_string());
+        }
+        if let Some(hard_timeout) = self.hard_timeout_minutes {
+            if hard_timeout <= self.stale_after_minutes {
+                return Err(
+                    "hard_timeout_minutes must be longer than stale_after_minutes".to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StuckRun {
+    pub run_id: String,
+    pub agent_id: String,
+    pub last_activity_at: DateTime<Utc>,
+    pub idle_minutes: i64,
+}
+
+/// Most recent sign of life on a run. Watchdog notes are ignored so that
+/// flagging a run doesn't reset its own timer.
+pub fn last_activity_at(run: &Run) -> DateTime<Utc> {
+    let outputs = run
+        .outputs
+        .iter()
+        .filter(|output| output.kind != WATCHDOG_OUTPUT_KIND)
+        .map(|output| output.timestamp);
+    let changes = run.file_changes.iter().map(|change| change.timestamp);
+    outputs
+        .chain(changes)
+        .fold(run.started_at, |latest, timestamp| latest.max(timestamp))
+}
+
+fn flagged_since(run: &Run, since: DateTime<Utc>) -> bool {
+    run.outputs
+        .iter()
+        .any(|output| output.kind == WATCHDOG_OUTPUT_KIND && output.timestamp >= since)
+}
+
+/// In-progress runs that have been quiet longer than the stale threshold.
+pub fn find_stuck_runs(
+    db: &Database,
+    settings: &WatchdogSettings,
+    now: DateTime<Utc>,
+) -> rusqlite::Result<Vec<StuckRun>> {
+    if !settings.enabled {
+        return Ok(vec![]);
+    }
+    let stale_after = Duration::minutes(settings.stale_after_minutes as i64);
+    Ok(db
+        .get_all_active_runs()?
+        .into_iter()
+        .filter_map(|run| {
+            let last_activity = last_activity_at(&run);
+            let idle = now - last_activity;
+            (idle >= stale_after).then(|| StuckRun {
+                run_id: run.id,
+                agent_id: run.agent_id,
+                last_activity_at: last_activity,
+                idle_minutes: idle.num_minutes(),
+            })
+        })
+        .collect())
+}
+
+/// One watchdog pass: nudge newly stale runs with a StatusRequest (once per
+/// quiet period) and finalize runs past the hard timeout as NeedsReview.
+pub fn check_runs(db: &Database, now: DateTime<Utc>) -> rusqlite::Result<Vec<StuckRun>> {
+    let settings = db
+        .get_setting::<WatchdogSettings>(WATCHDOG_SETTINGS_KEY)?
+        .unwrap_or_default();
+    let stuck = find_stuck_runs(db, &settings, now)?;
+
+    for entry in &stuck {
+        let timed_out = settings
+            .hard_timeout_minutes
+            .is_some_and(|limit| entry.idle_minutes >= limit as i64);
+
+        if timed_out {
+            let note = format!(
+                "No output for {} minutes; marked for review by the run watchdog.",
+                entry.idle_minutes
+            );
+            db.finalize_run_for(
+                &entry.agent_id,
+                Some(&entry.run_id),
+                RunStatus::NeedsReview,
+                Some(note.clone()),
+            )?;
+            let mut message =
+                Message::from_agent(&entry.agent_id, MessageKind::StatusUpdate, &note);
+            message.metadata = Some(serde_json::json!({
+                "source": "run_watchdog",
+                "run_id": entry.run_id,
+            }));
+            db.insert_message(&message)?;
+            db.update_agent_status(&entry.agent_id, &AgentStatus::Blocked)?;
+            continue;
+        }
+
+        let Some(run) = db.get_run(&entry.run_id)? else {
+            continue;
+        };
+        if flagged_since(&run, entry.last_activity_at) {
+            continue;
+        }
+        db.append_run_output_to(
+            &entry.agent_id,
+            Some(&entry.run_id),
+            WATCHDOG_OUTPUT_KIND,
+            &format!("No output for {} minutes", entry.idle_minutes),
+        )?;
+        if settings.send_status_request {
+            let mut request = Message::to_agent(
+                &entry.agent_id,
+                MessageKind::StatusRequest,
+                "Status check: no output received recently. What are you working on?",
+            );
+            request.metadata = Some(serde_json::json!({
+                "source": "run_watchdog",
+                "run_id": entry.run_id,
+            }));
+            db.insert_message(&request)?;
+        }
+    }
+
+    Ok(stuck)
+}
+
+/// Finalize runs left InProgress by a crash or force-quit, so the next
+/// instruction won't be appended to a stale run. Called once at startup.
+/// Runs whose agent still has a live session (`has_live_session`, e.g. a
+/// Claude Code tmux session that outlived the app) are still working and
+/// are left alone.
+pub fn recover_orphaned_runs(
+    db: &Database,
+    has_live_session: impl Fn(&str) -> bool,
+) -> rusqlite::Result<Vec<Run>> {
+    let orphaned: Vec<Run> = db
+        .get_all_active_runs()?
+        .into_iter()
+        .filter(|run| !has_live_session(&run.agent_id))
+        .collect();
+    if orphaned.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut recovered = Vec::new();
+    for run in &orphaned {
+        if let Some(run) = db.finalize_run_for(
+            &run.agent_id,
+            Some(&run.id),
+            RunStatus::NeedsReview,
+            Some(ORPHANED_RUN_NOTE.to_string()),
+        )? {
+            recovered.push(run);
+        }
+    }
+
+    for agent in db.list_agents()? {
+        if agent.status == AgentStatus::Running
+            && orphaned.iter().any(|run| run.agent_id == agent.id)
+        {
+            db.update_agent_status(&agent.id, &AgentStatus::Idle)?;
+        }
+    }
+    Ok(recovered)
+}
+
+pub fn spawn(db: Arc<Database>) {
+    std::thread::spawn(move || loop {
+        if let Err(error) = check_runs(&db, db.now()) {
+            tracing::warn!("Run watchdog pass failed: {}", error);
+        }
+        std::thread::sleep(TICK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_runs_are_flagged_once_then_timed_out() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Worker", &project.id, AgentKind::Terminal, "ops");
+        db.create_agent(&agent).expect("agent should insert");
+
+        let run = db
+            .start_instruction_run(&agent.id, None, "Migrate the database")
+            .expect("run should start");
+        let started = last_activity_at(&run);
+
+        let early = check_runs(&db, started + Duration::minutes(5)).expect("pass should run");
+        assert!(early.is_empty());
+
+        let stale = check_runs(&db, started + Duration::minutes(40)).expect("pass should run");
+        assert_eq!(stale.len(), 1);
+        check_runs(&db, started + Duration::minutes(45)).expect("pass should run");
+        let pending = db
+            .get_pending_messages(&agent.id)
+            .expect("pending should load");
+        assert_eq!(
+            pending
+                .iter()
+                .filter(|message| message.kind == MessageKind::StatusRequest)
+                .count(),
+            1
+        );
+
+        check_runs(&db, started + Duration::minutes(200)).expect("pass should run");
+        let run = db
+            .get_run(&run.id)
+            .expect("run should load")
+            .expect("run should exist");
+        assert_eq!(run.status, RunStatus::NeedsReview);
+    }
+
+    #[test]
+    fn orphaned_runs_are_finalized_for_review() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let agent = Agent::new("Worker", &project.id, AgentKind::Terminal, "ops");
+        db.create_agent(&agent).expect("agent should insert");
+        db.update_agent_status(&agent.id, &AgentStatus::Running)
+            .expect("status should update");
+
+        let stale = db
+            .start_instruction_run(&agent.id, None, "Refactor the parser")
+            .expect("run should start");
+        let recovered = recover_orphaned_runs(&db, |_| false).expect("recovery should run");
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].status, RunStatus::NeedsReview);
+        assert!(db
+            .get_all_active_runs()
+            .expect("runs should load")
+            .is_empty());
+        assert_eq!(
+            db.list_agents().expect("agents should load")[0].status,
+            AgentStatus::Idle
+        );
+
+        let next = db
+            .start_instruction_run(&agent.id, None, "Write the changelog")
+            .expect("run should start");
+        assert_ne!(next.id, stale.id);
+    }
+
+    #[test]
+    fn runs_with_a_live_session_survive_recovery() {
+        let db = Database::new(":memory:").expect("in-memory db should initialize");
+        let project = Project::new("Test Project", "#123456");
+        db.create_project(&project).expect("project should insert");
+        let live = Agent::new("Tmux", &project.id, AgentKind::Terminal, "ops");
+        let dead = Agent::new("Worker", &project.id, AgentKind::Terminal, "ops");
+        for agent in [&live, &dead] {
+            db.create_agent(agent).expect("agent should insert");
+            db.update_agent_status(&agent.id, &AgentStatus::Running)
+                .expect("status should update");
+        }
+        let working = db
+            .start_instruction_run(&live.id, None, "Refactor the parser")
+            .expect("run should start");
+        db.start_instruction_run(&dead.id, None, "Write the changelog")
+            .expect("run should start");
+
+        let recovered = recover_orphaned_runs(&db, |agent_id| agent_id == live.id)
+            .expect("recovery should run");
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].agent_id, dead.id);
+        let active = db.get_all_active_runs().expect("runs should load");
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, working.id);
+        let status = |id: &str| {
+            db.get_agent(id)
+                .expect("agent should load")
+                .expect("agent should exist")
+                .status
+        };
+        assert_eq!(status(&live.id), AgentStatus::Running);
+        assert_eq!(status(&dead.id), AgentStatus::Idle);
+    }
+}
@@ -0,0 +1,139 @@
+use super::AgentFileEvent;
+use crate::models::FileChangeType;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+struct PendingChange {
+    event: AgentFileEvent,
+    last_seen: Instant,
+}
+
+/// Holds watcher events per (agent, path) until the path has been quiet for
+/// the window, then releases a single change carrying the burst's count.
+pub struct ChangeDebouncer {
+    window: Duration,
+    pending: HashMap<(String, String), PendingChange>,
+}
+
+impl ChangeDebouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    pub fn push(&mut self, event: AgentFileEvent, now: Instant) {
+        let key = (event.agent_id.clone(), event.change.path.clone());
+        match self.pending.get_mut(&key) {
+            Some(pending) => {
+                let merged = &mut pending.event.change;
+                // A file created or moved here during the burst is still
+                // reported as such, however many times it was written afterwards.
+                if !matches!(
+                    (&merged.change_type, &event.change.change_type),
+                    (
+                        FileChangeType::Created | FileChangeType::Renamed,
+                        FileChangeType::Modified
+                    )
+                ) {
+                    merged.change_type = event.change.change_type;
+                    merged.old_path = event.change.old_path;
+                }
+                merged.count = merged.count.saturating_add(event.change.count);
+                merged.timestamp = event.change.timestamp;
+                pending.last_seen = now;
+            }
+            None => {
+                self.pending.insert(
+                    key,
+                    PendingChange {
+                        event,
+                        last_seen: now,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Changes whose path has been quiet for at least the window, oldest first.
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<AgentFileEvent> {
+        let ready: Vec<(String, String)> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| now.duration_since(pending.last_seen) >= self.window)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut events: Vec<AgentFileEvent> = ready
+            .into_iter()
+            .filter_map(|key| self.pending.remove(&key))
+            .map(|pending| pending.event)
+            .collect();
+        events.sort_by_key(|event| event.change.timestamp);
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FileChange;
+    use chrono::Utc;
+
+    fn event(path: &str, change_type: FileChangeType) -> AgentFileEvent {
+        AgentFileEvent {
+            agent_id: "agent-1".to_string(),
+            change: FileChange {
+                path: path.to_string(),
+                change_type,
+                timestamp: Utc::now(),
+                count: 1,
+                old_path: None,
+                lines_added: None,
+                lines_removed: None,
+            },
+        }
+    }
+
+    #[test]
+    fn bursts_coalesce_until_the_path_goes_quiet() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_millis(500));
+        let start = Instant::now();
+        debouncer.push(event("/repo/a.md", FileChangeType::Created), start);
+        debouncer.push(
+            event("/repo/a.md", FileChangeType::Modified),
+            start + Duration::from_millis(200),
+        );
+        debouncer.push(
+            event("/repo/a.md", FileChangeType::Modified),
+            start + Duration::from_millis(400),
+        );
+        debouncer.push(
+            event("/repo/b.md", FileChangeType::Modified),
+            start + Duration::from_millis(100),
+        );
+
+        let ready = debouncer.drain_ready(start + Duration::from_millis(700));
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].change.path, "/repo/b.md");
+
+        let ready = debouncer.drain_ready(start + Duration::from_millis(900));
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].change.count, 3);
+        assert!(matches!(
+            ready[0].change.change_type,
+            FileChangeType::Created
+        ));
+        assert!(debouncer
+            .drain_ready(start + Duration::from_secs(5))
+            .is_empty());
+    }
+}
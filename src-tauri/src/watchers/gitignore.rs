@@ -0,0 +1,103 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+use std::path::Path;
+
+/// Gitignore rules that apply under one watched root: the enclosing
+/// repository's info/exclude, every .gitignore from the repository root down
+/// to the watched directory, and any nested .gitignore files beneath it.
+#[derive(Default)]
+pub struct GitignoreRules {
+    /// Ordered shallowest first; deeper files take precedence.
+    matchers: Vec<Gitignore>,
+}
+
+impl GitignoreRules {
+    fn build(dir: &Path, file: &Path) -> Option<Gitignore> {
+        if !file.is_file() {
+            return None;
+        }
+        let mut builder = GitignoreBuilder::new(dir);
+        if let Some(error) = builder.add(file) {
+            tracing::warn!("Skipping invalid rules in {}: {}", file.display(), error);
+        }
+        builder.build().ok().filter(|matcher| !matcher.is_empty())
+    }
+
+    pub fn load(root: &Path) -> Self {
+        let mut matchers = Vec::new();
+        let repo_root = root.ancestors().find(|dir| dir.join(".git").exists());
+
+        let mut chain: Vec<&Path> = match repo_root {
+            Some(repo_root) => {
+                if let Some(exclude) = Self::build(repo_root, &repo_root.join(".git/info/exclude"))
+                {
+                    matchers.push(exclude);
+                }
+                root.ancestors()
+                    .take_while(|dir| dir.starts_with(repo_root))
+                    .collect()
+            }
+            None => vec![root],
+        };
+        chain.reverse();
+        for dir in chain {
+            matchers.extend(Self::build(dir, &dir.join(".gitignore")));
+        }
+
+        // Nested files. The walk itself honors .gitignore, so ignored trees
+        // like node_modules are never descended into.
+        let nested = WalkBuilder::new(root)
+            .hidden(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_name() == ".gitignore" && entry.depth() > 1);
+        for entry in nested {
+            if let Some(dir) = entry.path().parent() {
+                matchers.extend(Self::build(dir, entry.path()));
+            }
+        }
+        matchers.sort_by_key(|matcher| matcher.path().components().count());
+
+        Self { matchers }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for matcher in self.matchers.iter().rev() {
+            if !path.starts_with(matcher.path()) {
+                continue;
+            }
+            match matcher.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn honors_root_nested_and_negated_rules() {
+        let dir = std::env::temp_dir().join(format!("kanbun-ignore-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join(".git")).expect("repo dir should create");
+        std::fs::create_dir_all(dir.join("app/build")).expect("app dir should create");
+        std::fs::write(dir.join(".gitignore"), "*.log\n.venv/\n").expect("rules should write");
+        std::fs::write(dir.join("app/.gitignore"), "build/\n!keep.log\n")
+            .expect("rules should write");
+        let dir = std::fs::canonicalize(&dir).expect("dir should canonicalize");
+
+        let rules = GitignoreRules::load(&dir.join("app"));
+        assert!(rules.is_ignored(&dir.join("app/debug.log"), false));
+        assert!(!rules.is_ignored(&dir.join("app/keep.log"), false));
+        assert!(rules.is_ignored(&dir.join("app/build/out.js"), false));
+        assert!(rules.is_ignored(&dir.join("app/.venv/lib/site.py"), false));
+        assert!(!rules.is_ignored(&dir.join("app/src/main.rs"), false));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,90 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+fn build_set(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            continue;
+        }
+        let glob =
+            Glob::new(pattern).map_err(|error| format!("invalid glob '{}': {}", pattern, error))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|error| error.to_string())
+}
+
+/// Reject patterns that don't compile so a typo surfaces when the agent is
+/// saved rather than silently matching nothing.
+pub fn validate_globs(patterns: &[String]) -> Result<(), String> {
+    build_set(patterns).map(|_| ())
+}
+
+/// An agent's ignore/include globs. Patterns match the path relative to the
+/// watched root (so `*.png` or `drafts/**`) as well as the absolute path.
+#[derive(Debug, Clone)]
+pub struct AgentGlobs {
+    ignore_patterns: Vec<String>,
+    include_patterns: Vec<String>,
+    ignore: GlobSet,
+    include: GlobSet,
+}
+
+impl AgentGlobs {
+    pub fn new(ignore_globs: &[String], include_globs: &[String]) -> Self {
+        let compile = |patterns: &[String]| {
+            build_set(patterns).unwrap_or_else(|error| {
+                tracing::warn!("Ignoring watcher globs: {}", error);
+                GlobSet::empty()
+            })
+        };
+        Self {
+            ignore_patterns: ignore_globs.to_vec(),
+            include_patterns: include_globs.to_vec(),
+            ignore: compile(ignore_globs),
+            include: compile(include_globs),
+        }
+    }
+
+    pub fn same_patterns(&self, ignore_globs: &[String], include_globs: &[String]) -> bool {
+        self.ignore_patterns == ignore_globs && self.include_patterns == include_globs
+    }
+
+    fn matches(set: &GlobSet, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        set.is_match(relative) || set.is_match(path)
+    }
+
+    /// Whether an event at `path` under the watched `root` should reach the
+    /// agent. An empty include list admits everything not ignored.
+    pub fn allows(&self, root: &Path, path: &Path) -> bool {
+        if Self::matches(&self.ignore, root, path) {
+            return false;
+        }
+        self.include.is_empty() || Self::matches(&self.include, root, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignore_wins_and_include_narrows() {
+        let root = Path::new("/home/me/content");
+        let globs = AgentGlobs::new(
+            &["*.png".to_string(), "exports/**".to_string()],
+            &["**/*.md".to_string(), "*.txt".to_string()],
+        );
+        assert!(globs.allows(root, &root.join("posts/launch.md")));
+        assert!(globs.allows(root, &root.join("notes.txt")));
+        assert!(!globs.allows(root, &root.join("posts/hero.png")));
+        assert!(!globs.allows(root, &root.join("exports/launch.md")));
+        assert!(!globs.allows(root, &root.join("posts/data.json")));
+
+        let open = AgentGlobs::new(&[], &[]);
+        assert!(open.allows(root, &root.join("anything.bin")));
+        assert!(validate_globs(&["src/[".to_string()]).is_err());
+    }
+}
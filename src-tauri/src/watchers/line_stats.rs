@@ -0,0 +1,89 @@
+use crate::git::git;
+use crate::models::{FileChange, FileChangeType};
+use std::path::Path;
+
+/// New files larger than this are not read to count their lines.
+const MAX_COUNTED_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Parse one `git diff --numstat` line. Binary files report "-" and yield None.
+fn parse_numstat(output: &str) -> Option<(u32, u32)> {
+    let mut fields = output.lines().next()?.split('\t');
+    let added = fields.next()?.parse().ok()?;
+    let removed = fields.next()?.parse().ok()?;
+    Some((added, removed))
+}
+
+/// Lines in a file that git does not track yet, or None for binary and
+/// oversized files.
+fn count_new_lines(path: &Path) -> Option<(u32, u32)> {
+    if std::fs::metadata(path).ok()?.len() > MAX_COUNTED_FILE_BYTES {
+        return None;
+    }
+    let content = std::fs::read(path).ok()?;
+    if content.contains(&0) {
+        return None;
+    }
+    let lines = content.iter().filter(|byte| **byte == b'\n').count()
+        + usize::from(content.last().is_some_and(|byte| *byte != b'\n'));
+    Some((u32::try_from(lines).unwrap_or(u32::MAX), 0))
+}
+
+/// Lines added/removed relative to HEAD for the changed file. Files outside
+/// a repository (or untracked ones) count every line of a new file as added.
+pub fn line_stats(change: &FileChange) -> Option<(u32, u32)> {
+    let path = Path::new(&change.path);
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_str()?;
+    if let Some(output) = git(dir, &["diff", "--numstat", "HEAD", "--", name]) {
+        if !output.trim().is_empty() {
+            return parse_numstat(&output);
+        }
+        let tracked = git(dir, &["ls-files", "--error-unmatch", "--", name]).is_some();
+        if tracked {
+            return Some((0, 0));
+        }
+    }
+    match change.change_type {
+        FileChangeType::Deleted => None,
+        _ => count_new_lines(path),
+    }
+}
+
+/// Fill in `lines_added`/`lines_removed` when they can be computed.
+pub fn annotate_line_stats(change: &mut FileChange) {
+    if let Some((added, removed)) = line_stats(change) {
+        change.lines_added = Some(added);
+        change.lines_removed = Some(removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn counts_lines_for_numstat_and_new_files() {
+        assert_eq!(parse_numstat("12\t3\tsrc/main.rs\n"), Some((12, 3)));
+        assert_eq!(parse_numstat("-\t-\tlogo.png\n"), None);
+
+        let dir = std::env::temp_dir().join(format!("kanbun-lines-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("temp dir should create");
+        let path = dir.join("notes.md");
+        std::fs::write(&path, "one\ntwo\nthree").expect("file should write");
+        let mut change = FileChange {
+            path: path.to_string_lossy().to_string(),
+            change_type: FileChangeType::Created,
+            timestamp: Utc::now(),
+            count: 1,
+            old_path: None,
+            lines_added: None,
+            lines_removed: None,
+        };
+        annotate_line_stats(&mut change);
+        assert_eq!(change.lines_added, Some(3));
+        assert_eq!(change.lines_removed, Some(0));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,15 +1,125 @@
+mod debounce;
+mod gitignore;
+mod globs;
+mod line_stats;
+mod mounts;
+mod registration;
+mod rollup;
+mod scope;
+mod status;
+
 use crate::models::{FileChange, FileChangeType};
 use chrono::Utc;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+pub use debounce::ChangeDebouncer;
+use gitignore::GitignoreRules;
+pub use globs::validate_globs;
+use globs::AgentGlobs;
+pub use line_stats::annotate_line_stats;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+pub use registration::{WatcherCommand, WatcherHandle};
+pub use rollup::{rollup_message, ChangeRollup};
+pub use scope::{validate_paths, validate_scope, PathScope};
+use serde::{Deserialize, Serialize};
+pub use status::{WatchedRootStatus, WatcherStats, WatcherStatus};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
 
+pub const WATCHER_SETTINGS_KEY: &str = "file_watcher";
+const MAX_DEBOUNCE_MS: u64 = 60_000;
+const MAX_ROLLUP_MS: u64 = 300_000;
+const MIN_POLL_INTERVAL_MS: u64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherSettings {
+    pub debounce_ms: u64, // quiet period per path before a burst is recorded
+    #[serde(default = "default_rollup_ms")]
+    pub rollup_ms: u64, // changes within this window share one status message
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64, // scan interval for roots on the polling fallback
+    #[serde(default = "default_true")]
+    pub auto_poll_network_mounts: bool, // poll NFS/SMB/etc. roots without being told
+}
+
+fn default_rollup_ms() -> u64 {
+    5_000
+}
+
+fn default_poll_interval_ms() -> u64 {
+    5_000
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 1_000,
+            rollup_ms: default_rollup_ms(),
+            poll_interval_ms: default_poll_interval_ms(),
+            auto_poll_network_mounts: true,
+        }
+    }
+}
+
+impl WatcherSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.debounce_ms > MAX_DEBOUNCE_MS {
+            return Err(format!("debounce_ms must be at most {}", MAX_DEBOUNCE_MS));
+        }
+        if self.rollup_ms > MAX_ROLLUP_MS {
+            return Err(format!("rollup_ms must be at most {}", MAX_ROLLUP_MS));
+        }
+        if self.poll_interval_ms < MIN_POLL_INTERVAL_MS {
+            return Err(format!(
+                "poll_interval_ms must be at least {}",
+                MIN_POLL_INTERVAL_MS
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    pub fn debounce_window(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+
+    pub fn rollup_window(&self) -> Duration {
+        Duration::from_millis(self.rollup_ms)
+    }
+}
+
+type EventHandler = Arc<dyn Fn(Result<Event, notify::Error>) + Send + Sync>;
+
 pub struct FileSystemWatcher {
     _watcher: RecommendedWatcher,
+    /// Polling fallback for roots where native notifications are unreliable,
+    /// created on first use
+    poll_watcher: Option<PollWatcher>,
+    poll_interval: Duration,
+    auto_poll_network_mounts: bool,
+    /// Watched directories served by the polling fallback
+    polled_roots: HashSet<String>,
+    /// Shared by both backends so events are filtered and routed identically
+    handler: EventHandler,
     /// Maps watched directory -> agent_ids
     path_agent_map: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    /// Maps watched directory -> .gitignore rules in effect beneath it
+    ignore_rules: Arc<Mutex<HashMap<String, GitignoreRules>>>,
+    /// Maps agent_id -> the agent's configured ignore/include globs
+    agent_globs: Arc<Mutex<HashMap<String, AgentGlobs>>>,
+    /// Maps agent_id -> allowed-paths scope, for agents that have one
+    agent_scopes: Arc<Mutex<HashMap<String, PathScope>>>,
+    /// Per-root counters for diagnostics
+    stats: WatcherStats,
     /// Channel receiver for file change events
     pub receiver: mpsc::UnboundedReceiver<AgentFileEvent>,
 }
@@ -39,79 +149,259 @@ impl FileSystemWatcher {
         path.to_path_buf()
     }
 
+    /// Flatten a notify event into (path, old_path, change) triples. A rename
+    /// within the watched tree arrives as one `Name(Both)` event carrying
+    /// [from, to]; the separate From/To halves that inotify also reports are
+    /// dropped so the move isn't counted twice.
+    fn event_changes(event: &Event) -> Vec<(&Path, Option<&Path>, FileChangeType)> {
+        let change_type = match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                return vec![(
+                    event.paths[1].as_path(),
+                    Some(event.paths[0].as_path()),
+                    FileChangeType::Renamed,
+                )];
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From | RenameMode::To)) => {
+                return vec![];
+            }
+            EventKind::Create(_) => FileChangeType::Created,
+            EventKind::Modify(_) => FileChangeType::Modified,
+            EventKind::Remove(_) => FileChangeType::Deleted,
+            _ => return vec![],
+        };
+        event
+            .paths
+            .iter()
+            .map(|path| (path.as_path(), None, change_type.clone()))
+            .collect()
+    }
+
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_stats(WatcherStats::default())
+    }
+
+    /// Build a watcher that reports into `stats`, so callers outside the
+    /// watcher thread can inspect it.
+    pub fn with_stats(stats: WatcherStats) -> Result<Self, Box<dyn std::error::Error>> {
         let (tx, rx) = mpsc::unbounded_channel();
-        let path_agent_map: Arc<Mutex<HashMap<String, Vec<String>>>> =
-            Arc::new(Mutex::new(HashMap::new()));
+        let path_agent_map = stats.path_agent_map.clone();
         let map_clone = path_agent_map.clone();
+        let stats_clone = stats.clone();
+        let ignore_rules: Arc<Mutex<HashMap<String, GitignoreRules>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let rules_clone = ignore_rules.clone();
+        let agent_globs: Arc<Mutex<HashMap<String, AgentGlobs>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let globs_clone = agent_globs.clone();
+        let agent_scopes: Arc<Mutex<HashMap<String, PathScope>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let scopes_clone = agent_scopes.clone();
 
-        let watcher = RecommendedWatcher::new(
-            move |result: Result<Event, notify::Error>| {
-                if let Ok(event) = result {
-                    let change_type = match event.kind {
-                        EventKind::Create(_) => Some(FileChangeType::Created),
-                        EventKind::Modify(_) => Some(FileChangeType::Modified),
-                        EventKind::Remove(_) => Some(FileChangeType::Deleted),
-                        _ => None,
-                    };
-
-                    if let Some(change_type) = change_type {
-                        for path in &event.paths {
-                            let normalized_path = Self::normalize_event_path(path);
-
-                            // Skip hidden files, node_modules, build output, and OS artifacts.
-                            let path_str = normalized_path.to_string_lossy();
-                            if path_str.contains("/.git/")
-                                || path_str.contains("/node_modules/")
-                                || path_str.contains("/target/")
-                                || path_str.contains("/.next/")
-                                || path_str.contains("/.DS_Store")
-                            {
-                                continue;
-                            }
+        let handler: EventHandler = Arc::new(move |result: Result<Event, notify::Error>| {
+            if let Ok(event) = result {
+                for (path, old_path, change_type) in Self::event_changes(&event) {
+                    let normalized_path = Self::normalize_event_path(path);
+                    let is_dir = normalized_path.is_dir();
+                    // Resolved once, before taking the locks, and only when
+                    // some agent has a scope to check it against.
+                    let scoped_path = (!scopes_clone.lock().unwrap().is_empty())
+                        .then(|| scope::resolve(&normalized_path));
 
-                            // Find all agents that own this path (supports overlapping paths).
-                            let map = map_clone.lock().unwrap();
-                            let mut matching_agents = HashSet::<String>::new();
-                            for (watched_path, agent_ids) in map.iter() {
-                                if normalized_path.starts_with(Path::new(watched_path)) {
-                                    for agent_id in agent_ids {
-                                        matching_agents.insert(agent_id.clone());
-                                    }
-                                }
-                            }
+                    // Skip hidden files, node_modules, build output, and OS artifacts.
+                    let path_str = normalized_path.to_string_lossy();
+                    let builtin_skip = path_str.contains("/.git/")
+                        || path_str.contains("/node_modules/")
+                        || path_str.contains("/target/")
+                        || path_str.contains("/.next/")
+                        || path_str.contains("/.DS_Store");
 
-                            for agent_id in matching_agents {
-                                let _ = tx.send(AgentFileEvent {
-                                    agent_id,
-                                    change: FileChange {
-                                        path: path_str.to_string(),
-                                        change_type: change_type.clone(),
-                                        timestamp: Utc::now(),
-                                    },
-                                });
+                    // Find all agents that own this path (supports overlapping paths),
+                    // skipping roots whose .gitignore excludes it.
+                    let map = map_clone.lock().unwrap();
+                    let mut rules = rules_clone.lock().unwrap();
+                    let globs = globs_clone.lock().unwrap();
+                    let scopes = scopes_clone.lock().unwrap();
+                    let is_gitignore = normalized_path
+                        .file_name()
+                        .is_some_and(|name| name == ".gitignore");
+                    let mut matching_agents = HashSet::<String>::new();
+                    let mut under_any_root = false;
+                    for (watched_path, agent_ids) in map.iter() {
+                        if !normalized_path.starts_with(Path::new(watched_path)) {
+                            continue;
+                        }
+                        under_any_root = true;
+                        if is_gitignore {
+                            rules.insert(
+                                watched_path.clone(),
+                                GitignoreRules::load(Path::new(watched_path)),
+                            );
+                        }
+                        if builtin_skip
+                            || rules
+                                .get(watched_path)
+                                .is_some_and(|rules| rules.is_ignored(&normalized_path, is_dir))
+                        {
+                            stats_clone.record_ignored(watched_path);
+                            continue;
+                        }
+                        let mut delivered = false;
+                        for agent_id in agent_ids {
+                            // Overlapping roots can reach past an agent's
+                            // scope; never credit it with those changes.
+                            let allowed = globs.get(agent_id).is_none_or(|globs| {
+                                globs.allows(Path::new(watched_path), &normalized_path)
+                            }) && scopes.get(agent_id).is_none_or(|scope| {
+                                scoped_path
+                                    .as_deref()
+                                    .is_some_and(|path| scope.contains_resolved(path))
+                            });
+                            if allowed {
+                                delivered = true;
+                                matching_agents.insert(agent_id.clone());
                             }
                         }
+                        if delivered {
+                            stats_clone.record_delivered(watched_path);
+                        } else {
+                            stats_clone.record_ignored(watched_path);
+                        }
+                    }
+                    drop(scopes);
+                    drop(globs);
+                    drop(rules);
+                    drop(map);
+                    if !under_any_root {
+                        stats_clone.record_dropped();
+                    }
+
+                    for agent_id in matching_agents {
+                        stats_clone.record_queued();
+                        let _ = tx.send(AgentFileEvent {
+                            agent_id,
+                            change: FileChange {
+                                path: path_str.to_string(),
+                                change_type: change_type.clone(),
+                                timestamp: Utc::now(),
+                                count: 1,
+                                old_path: old_path.map(|old_path| {
+                                    Self::normalize_event_path(old_path)
+                                        .to_string_lossy()
+                                        .to_string()
+                                }),
+                                lines_added: None,
+                                lines_removed: None,
+                            },
+                        });
                     }
-                } else if let Err(error) = result {
-                    log::warn!("Watcher error: {}", error);
                 }
-            },
+            } else if let Err(error) = result {
+                tracing::warn!("Watcher error: {}", error);
+                stats_clone.record_error(&error.to_string());
+            }
+        });
+        let native_handler = handler.clone();
+        let watcher = RecommendedWatcher::new(
+            move |result: Result<Event, notify::Error>| native_handler(result),
             Config::default(),
         )?;
 
+        stats.mark_initialized();
+        let defaults = WatcherSettings::default();
         Ok(Self {
             _watcher: watcher,
+            poll_watcher: None,
+            poll_interval: defaults.poll_interval(),
+            auto_poll_network_mounts: defaults.auto_poll_network_mounts,
+            polled_roots: HashSet::new(),
+            handler,
             path_agent_map,
+            ignore_rules,
+            agent_globs,
+            agent_scopes,
+            stats,
             receiver: rx,
         })
     }
 
-    /// Register a directory to watch, associated with an agent
+    fn create_poll_watcher(&self) -> notify::Result<PollWatcher> {
+        let handler = self.handler.clone();
+        PollWatcher::new(
+            move |result: Result<Event, notify::Error>| handler(result),
+            Config::default().with_poll_interval(self.poll_interval),
+        )
+    }
+
+    /// Apply polling settings. A changed interval rebuilds the polling
+    /// backend and re-registers the roots it was serving.
+    pub fn set_poll_settings(&mut self, interval: Duration, auto_poll_network_mounts: bool) {
+        self.auto_poll_network_mounts = auto_poll_network_mounts;
+        if interval == self.poll_interval {
+            return;
+        }
+        self.poll_interval = interval;
+        if self.poll_watcher.is_none() {
+            return;
+        }
+        self.poll_watcher = match self.create_poll_watcher() {
+            Ok(mut poll_watcher) => {
+                for root in &self.polled_roots {
+                    if let Err(error) =
+                        poll_watcher.watch(Path::new(root), RecursiveMode::Recursive)
+                    {
+                        tracing::warn!("Failed to re-poll {}: {}", root, error);
+                    }
+                }
+                Some(poll_watcher)
+            }
+            Err(error) => {
+                tracing::warn!("Failed to rebuild polling watcher: {}", error);
+                None
+            }
+        };
+    }
+
+    fn start_watch(
+        &mut self,
+        canonical_path: &Path,
+        force_poll: bool,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let use_poll = force_poll
+            || (self.auto_poll_network_mounts && mounts::is_network_filesystem(canonical_path));
+        if !use_poll {
+            self._watcher
+                .watch(canonical_path, RecursiveMode::Recursive)?;
+            return Ok(false);
+        }
+        if self.poll_watcher.is_none() {
+            self.poll_watcher = Some(self.create_poll_watcher()?);
+        }
+        if let Some(poll_watcher) = self.poll_watcher.as_mut() {
+            poll_watcher.watch(canonical_path, RecursiveMode::Recursive)?;
+        }
+        Ok(true)
+    }
+
+    fn stop_watch(&mut self, canonical_key: &str) -> notify::Result<()> {
+        if self.polled_roots.remove(canonical_key) {
+            match self.poll_watcher.as_mut() {
+                Some(poll_watcher) => poll_watcher.unwatch(Path::new(canonical_key)),
+                None => Ok(()),
+            }
+        } else {
+            self._watcher.unwatch(Path::new(canonical_key))
+        }
+    }
+
+    /// Register a directory to watch, associated with an agent. `force_poll`
+    /// selects the polling fallback; otherwise it is used only for roots on
+    /// network filesystems (when auto-detection is on).
     pub fn watch_path(
         &mut self,
         path: &str,
         agent_id: &str,
+        force_poll: bool,
     ) -> Result<bool, Box<dyn std::error::Error>> {
         let input_path = PathBuf::from(path);
         if input_path.exists() {
@@ -122,7 +412,7 @@ impl FileSystemWatcher {
             if let Some(agent_ids) = map.get_mut(&canonical_key) {
                 if !agent_ids.iter().any(|existing| existing == agent_id) {
                     agent_ids.push(agent_id.to_string());
-                    log::info!(
+                    tracing::info!(
                         "Registered additional agent {} for watched path {}",
                         agent_id,
                         canonical_path.display()
@@ -132,32 +422,185 @@ impl FileSystemWatcher {
             }
 
             drop(map);
-            self._watcher
-                .watch(&canonical_path, RecursiveMode::Recursive)?;
+            let polled = self.start_watch(&canonical_path, force_poll)?;
+            if polled {
+                self.polled_roots.insert(canonical_key.clone());
+            }
+            self.stats
+                .set_backend(&canonical_key, if polled { "poll" } else { "native" });
+            self.ignore_rules
+                .lock()
+                .unwrap()
+                .insert(canonical_key.clone(), GitignoreRules::load(&canonical_path));
             self.path_agent_map
                 .lock()
                 .unwrap()
                 .insert(canonical_key, vec![agent_id.to_string()]);
-            log::info!(
-                "Watching {} for agent {}",
+            tracing::info!(
+                "Watching {} for agent {}{}",
                 canonical_path.display(),
-                agent_id
+                agent_id,
+                if polled { " (polling)" } else { "" }
             );
             Ok(true)
         } else {
-            log::warn!("Path does not exist, skipping watch: {}", path);
+            tracing::warn!("Path does not exist, skipping watch: {}", path);
             Ok(false)
         }
     }
 
+    /// Apply an agent's ignore/include globs to future events. Cheap to call
+    /// repeatedly; the globs are only recompiled when the patterns change.
+    pub fn set_agent_globs(
+        &self,
+        agent_id: &str,
+        ignore_globs: &[String],
+        include_globs: &[String],
+    ) {
+        let mut globs = self.agent_globs.lock().unwrap();
+        if ignore_globs.is_empty() && include_globs.is_empty() {
+            globs.remove(agent_id);
+            return;
+        }
+        if globs
+            .get(agent_id)
+            .is_some_and(|existing| existing.same_patterns(ignore_globs, include_globs))
+        {
+            return;
+        }
+        globs.insert(
+            agent_id.to_string(),
+            AgentGlobs::new(ignore_globs, include_globs),
+        );
+    }
+
+    /// Restrict which events an agent is credited with to its allowed paths.
+    pub fn set_agent_scope(&self, agent_id: &str, allowed_paths: &[String]) {
+        let mut scopes = self.agent_scopes.lock().unwrap();
+        if allowed_paths.iter().all(|path| path.trim().is_empty()) {
+            scopes.remove(agent_id);
+            return;
+        }
+        if scopes
+            .get(agent_id)
+            .is_some_and(|existing| existing.same_paths(allowed_paths))
+        {
+            return;
+        }
+        scopes.insert(agent_id.to_string(), PathScope::new(allowed_paths));
+    }
+
+    /// Detach an agent from a watched root. The OS watch itself is dropped
+    /// once no agent needs the root any more.
+    pub fn release_path(&mut self, path: &str, agent_id: &str) {
+        let canonical_key = Self::normalize_existing_path(Path::new(path))
+            .to_string_lossy()
+            .to_string();
+        let mut map = self.path_agent_map.lock().unwrap();
+        let Some(agent_ids) = map.get_mut(&canonical_key) else {
+            return;
+        };
+        agent_ids.retain(|existing| existing != agent_id);
+        if !agent_ids.is_empty() {
+            tracing::info!(
+                "Released agent {} from watched path {}",
+                agent_id,
+                canonical_key
+            );
+            return;
+        }
+        map.remove(&canonical_key);
+        drop(map);
+        self.ignore_rules.lock().unwrap().remove(&canonical_key);
+        self.stats.forget_root(&canonical_key);
+
+        // If the directory was deleted the OS has already dropped the watch.
+        if let Err(error) = self.stop_watch(&canonical_key) {
+            tracing::debug!("Unwatch of {} failed: {}", canonical_key, error);
+        }
+        tracing::info!("Stopped watching {}", canonical_key);
+    }
+
+    /// Drop per-agent filters for agents that no longer exist.
+    pub fn retain_agents(&self, agent_ids: &HashSet<String>) {
+        self.agent_globs
+            .lock()
+            .unwrap()
+            .retain(|agent_id, _| agent_ids.contains(agent_id));
+        self.agent_scopes
+            .lock()
+            .unwrap()
+            .retain(|agent_id, _| agent_ids.contains(agent_id));
+    }
+
     /// Unwatch a path
     pub fn unwatch_path(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
         let canonical_path = Self::normalize_existing_path(Path::new(path));
-        self._watcher.unwatch(&canonical_path)?;
+        self.stop_watch(canonical_path.to_string_lossy().as_ref())?;
         self.path_agent_map
             .lock()
             .unwrap()
             .remove(canonical_path.to_string_lossy().as_ref());
+        self.ignore_rules
+            .lock()
+            .unwrap()
+            .remove(canonical_path.to_string_lossy().as_ref());
+        self.stats
+            .forget_root(canonical_path.to_string_lossy().as_ref());
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_pairs_become_one_renamed_change() {
+        let both = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(PathBuf::from("/repo/old.md"))
+            .add_path(PathBuf::from("/repo/new.md"));
+        let changes = FileSystemWatcher::event_changes(&both);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, Path::new("/repo/new.md"));
+        assert_eq!(changes[0].1, Some(Path::new("/repo/old.md")));
+        assert!(matches!(changes[0].2, FileChangeType::Renamed));
+
+        let half = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::From)))
+            .add_path(PathBuf::from("/repo/old.md"));
+        assert!(FileSystemWatcher::event_changes(&half).is_empty());
+    }
+
+    #[test]
+    fn released_roots_are_unwatched_once_unclaimed() {
+        let dir = std::env::temp_dir().join(format!("kanbun-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("temp dir should create");
+        let path = dir.to_string_lossy().to_string();
+        let Ok(mut watcher) = FileSystemWatcher::new() else {
+            // no filesystem notification backend in this environment
+            return;
+        };
+        assert!(watcher
+            .watch_path(&path, "agent-a", false)
+            .expect("watch should register"));
+        assert!(watcher
+            .watch_path(&path, "agent-b", false)
+            .expect("watch should register"));
+
+        watcher.release_path(&path, "agent-a");
+        let key = FileSystemWatcher::normalize_existing_path(&dir)
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(
+            watcher.path_agent_map.lock().unwrap().get(&key),
+            Some(&vec!["agent-b".to_string()])
+        );
+
+        watcher.release_path(&path, "agent-b");
+        assert!(watcher.path_agent_map.lock().unwrap().is_empty());
+        assert!(watcher.ignore_rules.lock().unwrap().is_empty());
+        assert!(watcher.stats.snapshot().roots.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
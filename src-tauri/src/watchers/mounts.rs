@@ -0,0 +1,107 @@
+use std::path::Path;
+
+/// Filesystems whose change notifications are unreliable or missing, so
+/// watches on them fall back to polling.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "9p",
+    "virtiofs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "fuse.grpcfuse",
+    "fakeowner",
+];
+
+/// Pick the longest mount point containing `path` from (mount point, type) pairs.
+fn longest_match<'a>(
+    mounts: impl Iterator<Item = (String, &'a str)>,
+    path: &Path,
+) -> Option<String> {
+    mounts
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, fs_type)| fs_type.to_string())
+}
+
+/// Parse /proc/self/mounts ("device mount_point type options ...").
+fn type_from_proc_mounts(mounts: &str, path: &Path) -> Option<String> {
+    let entries = mounts.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let _device = fields.next()?;
+        let mount_point = fields.next()?.replace("\\040", " ");
+        let fs_type = fields.next()?;
+        Some((mount_point, fs_type))
+    });
+    longest_match(entries, path)
+}
+
+/// Parse BSD/macOS `mount` output ("device on /mount/point (type, options)").
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn type_from_mount_output(output: &str, path: &Path) -> Option<String> {
+    let entries = output.lines().filter_map(|line| {
+        let (_, rest) = line.split_once(" on ")?;
+        let (mount_point, details) = rest.rsplit_once(" (")?;
+        let fs_type = details.split([',', ')']).next()?.trim();
+        Some((mount_point.to_string(), fs_type))
+    });
+    longest_match(entries, path)
+}
+
+fn filesystem_type(path: &Path) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+        type_from_proc_mounts(&mounts, path)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let output = std::process::Command::new("mount").output().ok()?;
+        type_from_mount_output(&String::from_utf8_lossy(&output.stdout), path)
+    }
+}
+
+pub fn is_network_filesystem(path: &Path) -> bool {
+    filesystem_type(path).is_some_and(|fs_type| NETWORK_FS_TYPES.contains(&fs_type.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_the_innermost_mount() {
+        let proc_mounts = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+server:/export /mnt/shared nfs4 rw,vers=4.2 0 0
+//nas/media /mnt/My\\040Media cifs rw 0 0
+";
+        assert_eq!(
+            type_from_proc_mounts(proc_mounts, Path::new("/mnt/shared/repo/src")).as_deref(),
+            Some("nfs4")
+        );
+        assert_eq!(
+            type_from_proc_mounts(proc_mounts, Path::new("/mnt/My Media/clips")).as_deref(),
+            Some("cifs")
+        );
+        assert_eq!(
+            type_from_proc_mounts(proc_mounts, Path::new("/home/me/repo")).as_deref(),
+            Some("ext4")
+        );
+
+        let mount_output = "\
+/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)
+//me@nas/code on /Volumes/code (smbfs, nodev, nosuid, mounted by me)
+";
+        assert_eq!(
+            type_from_mount_output(mount_output, Path::new("/Volumes/code/app")).as_deref(),
+            Some("smbfs")
+        );
+    }
+}
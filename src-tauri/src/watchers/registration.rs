@@ -0,0 +1,50 @@
+use super::WatcherSettings;
+use crate::models::Agent;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// A change the watcher thread has to apply to its registrations.
+#[derive(Debug)]
+pub enum WatcherCommand {
+    AgentChanged(Box<Agent>),
+    AgentRemoved(String),
+    SettingsChanged(WatcherSettings),
+    /// Reload every agent, e.g. after a database snapshot replaced them all
+    Resync,
+}
+
+/// Sending side of the watcher's registration channel. Commands that create,
+/// update or delete agents push their changes here instead of the watcher
+/// re-reading the agent list on a timer.
+#[derive(Clone)]
+pub struct WatcherHandle {
+    tx: Sender<WatcherCommand>,
+}
+
+impl WatcherHandle {
+    pub fn channel() -> (Self, Receiver<WatcherCommand>) {
+        let (tx, rx) = mpsc::channel();
+        (Self { tx }, rx)
+    }
+
+    fn send(&self, command: WatcherCommand) {
+        if self.tx.send(command).is_err() {
+            tracing::warn!("File watcher is not running; registration change dropped");
+        }
+    }
+
+    pub fn agent_changed(&self, agent: &Agent) {
+        self.send(WatcherCommand::AgentChanged(Box::new(agent.clone())));
+    }
+
+    pub fn agent_removed(&self, agent_id: &str) {
+        self.send(WatcherCommand::AgentRemoved(agent_id.to_string()));
+    }
+
+    pub fn settings_changed(&self, settings: &WatcherSettings) {
+        self.send(WatcherCommand::SettingsChanged(settings.clone()));
+    }
+
+    pub fn resync(&self) {
+        self.send(WatcherCommand::Resync);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::models::{FileChange, FileChangeType, Message, MessageKind};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+struct PendingRollup {
+    changes: Vec<FileChange>,
+    first_seen: Instant,
+}
+
+/// Collects recorded file changes per agent so the conversation gets one
+/// status message per window instead of one per file.
+pub struct ChangeRollup {
+    window: Duration,
+    pending: HashMap<String, PendingRollup>,
+}
+
+impl ChangeRollup {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    pub fn push(&mut self, agent_id: &str, change: FileChange, now: Instant) {
+        self.pending
+            .entry(agent_id.to_string())
+            .or_insert_with(|| PendingRollup {
+                changes: Vec::new(),
+                first_seen: now,
+            })
+            .changes
+            .push(change);
+    }
+
+    /// Agents whose window has elapsed since their first pending change.
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<(String, Vec<FileChange>)> {
+        let ready: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| now.duration_since(pending.first_seen) >= self.window)
+            .map(|(agent_id, _)| agent_id.clone())
+            .collect();
+        ready
+            .into_iter()
+            .filter_map(|agent_id| {
+                self.pending
+                    .remove(&agent_id)
+                    .map(|pending| (agent_id, pending.changes))
+            })
+            .collect()
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+fn describe_single(change: &FileChange) -> String {
+    let filename = file_name(&change.path);
+    match (&change.change_type, &change.old_path) {
+        (FileChangeType::Renamed, Some(old_path)) => {
+            format!("File renamed: {} -> {}", file_name(old_path), filename)
+        }
+        _ if change.count > 1 => format!(
+            "File {}: {} ({} events)",
+            change.change_type.as_str(),
+            filename,
+            change.count
+        ),
+        _ => format!("File {}: {}", change.change_type.as_str(), filename),
+    }
+}
+
+fn common_dir(changes: &[FileChange]) -> Option<PathBuf> {
+    let mut dirs = changes
+        .iter()
+        .filter_map(|change| Path::new(&change.path).parent());
+    let mut common = dirs.next()?.to_path_buf();
+    for dir in dirs {
+        while !dir.starts_with(&common) {
+            if !common.pop() {
+                return None;
+            }
+        }
+    }
+    Some(common)
+}
+
+/// "12 files modified in src/, 2 created", largest group first.
+pub fn rollup_summary(changes: &[FileChange]) -> String {
+    let mut groups: Vec<(&'static str, usize)> = Vec::new();
+    for change in changes {
+        let kind = change.change_type.as_str();
+        match groups.iter_mut().find(|(existing, _)| *existing == kind) {
+            Some((_, count)) => *count += 1,
+            None => groups.push((kind, 1)),
+        }
+    }
+    groups.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    let location = common_dir(changes)
+        .and_then(|dir| {
+            dir.file_name()
+                .map(|name| format!(" in {}/", name.to_string_lossy()))
+        })
+        .unwrap_or_default();
+    groups
+        .iter()
+        .enumerate()
+        .map(|(index, (kind, count))| {
+            if index == 0 {
+                let noun = if *count == 1 { "file" } else { "files" };
+                format!("{} {} {}{}", count, noun, kind, location)
+            } else {
+                format!("{} {}", count, kind)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The StatusUpdate for a window of changes. A lone change keeps the
+/// per-file wording; several are rolled up with the file list in metadata.
+pub fn rollup_message(agent_id: &str, changes: &[FileChange]) -> Option<Message> {
+    let content = match changes {
+        [] => return None,
+        [change] => describe_single(change),
+        _ => rollup_summary(changes),
+    };
+    let files: Vec<serde_json::Value> = changes
+        .iter()
+        .map(|change| {
+            serde_json::json!({
+                "path": change.path,
+                "old_path": change.old_path,
+                "change_type": change.change_type.as_str(),
+                "count": change.count,
+            })
+        })
+        .collect();
+
+    let mut message = Message::from_agent(agent_id, MessageKind::StatusUpdate, &content);
+    message.metadata = Some(match changes {
+        [change] => serde_json::json!({
+            "path": change.path,
+            "old_path": change.old_path,
+            "change_type": change.change_type.as_str(),
+            "count": change.count,
+            "files": files,
+        }),
+        _ => serde_json::json!({
+            "file_count": changes.len(),
+            "files": files,
+        }),
+    });
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn change(path: &str, change_type: FileChangeType) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            change_type,
+            timestamp: Utc::now(),
+            count: 1,
+            old_path: None,
+            lines_added: None,
+            lines_removed: None,
+        }
+    }
+
+    #[test]
+    fn rolls_up_changes_per_agent_window() {
+        let mut rollup = ChangeRollup::new(Duration::from_secs(5));
+        let start = Instant::now();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            rollup.push(
+                "agent-1",
+                change(&format!("/repo/src/{}", name), FileChangeType::Modified),
+                start,
+            );
+        }
+        rollup.push(
+            "agent-1",
+            change("/repo/src/db/new.rs", FileChangeType::Created),
+            start + Duration::from_secs(1),
+        );
+        assert!(rollup
+            .drain_ready(start + Duration::from_secs(2))
+            .is_empty());
+
+        let ready = rollup.drain_ready(start + Duration::from_secs(5));
+        assert_eq!(ready.len(), 1);
+        let message = rollup_message("agent-1", &ready[0].1).expect("message should build");
+        assert_eq!(message.content, "3 files modified in src/, 1 created");
+        let metadata = message.metadata.expect("metadata should be set");
+        assert_eq!(metadata["files"].as_array().map(Vec::len), Some(4));
+
+        let single = rollup_message(
+            "agent-1",
+            &[change("/repo/README.md", FileChangeType::Modified)],
+        )
+        .expect("message should build");
+        assert_eq!(single.content, "File modified: README.md");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::models::{Agent, AgentConfig};
+use std::path::{Component, Path, PathBuf};
+
+/// Expand `~`, resolve `..` lexically, and canonicalize the longest existing
+/// prefix so symlinks can't be used to step outside a scope.
+fn normalize(path: &str) -> PathBuf {
+    let expanded = PathBuf::from(shellexpand::tilde(path.trim()).to_string());
+    let mut lexical = PathBuf::new();
+    for component in expanded.components() {
+        match component {
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            Component::CurDir => {}
+            component => lexical.push(component),
+        }
+    }
+    let mut existing = lexical.as_path();
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            return lexical;
+        };
+        rest.push(name.to_os_string());
+        existing = parent;
+    }
+    let mut normalized = std::fs::canonicalize(existing).unwrap_or_else(|_| existing.to_path_buf());
+    normalized.extend(rest.iter().rev());
+    normalized
+}
+
+/// `path` as scopes compare it: `~` expanded, `..` resolved, symlinks
+/// followed as far as the path exists.
+pub fn resolve(path: &Path) -> PathBuf {
+    normalize(&path.to_string_lossy())
+}
+
+/// An agent's allowed-paths scope. Empty means unrestricted; otherwise the
+/// agent may only run in, watch, or be credited with changes under one of
+/// the roots.
+#[derive(Debug, Clone, Default)]
+pub struct PathScope {
+    allowed_paths: Vec<String>,
+    roots: Vec<PathBuf>,
+}
+
+impl PathScope {
+    pub fn new(allowed_paths: &[String]) -> Self {
+        let allowed_paths: Vec<String> = allowed_paths
+            .iter()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+            .collect();
+        Self {
+            roots: allowed_paths.iter().map(|path| normalize(path)).collect(),
+            allowed_paths,
+        }
+    }
+
+    pub fn for_agent(agent: &Agent) -> Self {
+        Self::new(&agent.config.allowed_paths)
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    pub fn same_paths(&self, allowed_paths: &[String]) -> bool {
+        self.allowed_paths == Self::new(allowed_paths).allowed_paths
+    }
+
+    /// The first allowed root, where a scoped agent without a working
+    /// directory runs.
+    pub fn default_root(&self) -> Option<&Path> {
+        self.roots.first().map(PathBuf::as_path)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.is_unrestricted() || self.contains_resolved(&resolve(path))
+    }
+
+    /// `contains` for a path already passed through `resolve`, so a caller
+    /// checking one path against many scopes touches the filesystem once.
+    pub fn contains_resolved(&self, path: &Path) -> bool {
+        self.is_unrestricted() || self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    /// Error naming the first of `paths` that falls outside the scope.
+    pub fn check<'a>(&self, paths: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        for path in paths.into_iter().filter(|path| !path.trim().is_empty()) {
+            if !self.contains(Path::new(path.trim())) {
+                return Err(format!(
+                    "{} is outside the agent's allowed paths ({})",
+                    path.trim(),
+                    self.allowed_paths.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reject an agent whose working directory or watch paths fall outside its
+/// own allowed paths, and allowed paths that aren't absolute.
+pub fn validate_scope(agent: &Agent) -> Result<(), String> {
+    validate_paths(agent.working_directory.as_deref(), &agent.config)
+}
+
+/// `validate_scope` for an agent that hasn't been built yet, e.g. one in a
+/// bundle being checked before anything is imported.
+pub fn validate_paths(working_directory: Option<&str>, config: &AgentConfig) -> Result<(), String> {
+    for path in &config.allowed_paths {
+        let expanded = shellexpand::tilde(path.trim()).to_string();
+        if !expanded.is_empty() && !Path::new(&expanded).is_absolute() {
+            return Err(format!("allowed path must be absolute: {}", path));
+        }
+    }
+    let scope = PathScope::new(&config.allowed_paths);
+    scope.check(working_directory)?;
+    scope.check(config.watch_paths.iter().map(String::as_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentKind;
+
+    #[test]
+    fn scope_admits_only_paths_under_its_roots() {
+        let base = std::env::temp_dir().join(format!("kanbun-scope-{}", uuid::Uuid::new_v4()));
+        let client = base.join("client");
+        let personal = base.join("personal");
+        std::fs::create_dir_all(&client).expect("temp dir should create");
+        std::fs::create_dir_all(&personal).expect("temp dir should create");
+
+        let scope = PathScope::new(&[client.to_string_lossy().to_string()]);
+        assert!(scope.contains(&client));
+        assert!(scope.contains(&client.join("src/not-yet-created.rs")));
+        assert!(!scope.contains(&personal));
+        assert!(!scope.contains(&client.join("../personal/notes.md")));
+        assert!(!scope.contains(&base.join("client-archive")));
+        assert!(PathScope::new(&[]).contains(&personal));
+
+        #[cfg(unix)]
+        {
+            let link = client.join("shortcut");
+            std::os::unix::fs::symlink(&personal, &link).expect("symlink should create");
+            assert!(!scope.contains(&link.join("notes.md")));
+        }
+
+        let mut agent = Agent::new("Consultant", "p1", AgentKind::Terminal, "engineering");
+        agent.config.allowed_paths = vec![client.to_string_lossy().to_string()];
+        agent.working_directory = Some(client.join("app").to_string_lossy().to_string());
+        assert!(validate_scope(&agent).is_ok());
+        agent.config.watch_paths = vec![personal.to_string_lossy().to_string()];
+        assert!(validate_scope(&agent).is_err());
+        agent.config.watch_paths.clear();
+        agent.config.allowed_paths = vec!["relative/dir".to_string()];
+        assert!(validate_scope(&agent).is_err());
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
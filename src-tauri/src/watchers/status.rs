@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Default)]
+struct RootCounters {
+    backend: Option<&'static str>,
+    events: u64,
+    ignored: u64,
+    last_event_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    initialized: bool,
+    roots: HashMap<String, RootCounters>,
+    dropped_events: u64,
+    errors: u64,
+    last_error: Option<String>,
+    queued_events: u64,
+    debouncing_events: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedRootStatus {
+    pub path: String,
+    pub agent_ids: Vec<String>,
+    pub backend: String, // "native" or "poll"
+    pub events: u64,     // delivered to at least one agent
+    pub ignored: u64,    // filtered by built-in skips, .gitignore, or agent globs
+    pub last_event_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatcherStatus {
+    pub initialized: bool,
+    pub roots: Vec<WatchedRootStatus>,
+    pub dropped_events: u64, // events under no watched root
+    pub errors: u64,
+    pub last_error: Option<String>,
+    pub queued_events: u64, // sent by the watcher, not yet picked up by its loop
+    pub debouncing_events: usize, // picked up, waiting for their path to go quiet
+}
+
+/// Shared view of what the file watcher is doing, cloned into the watcher
+/// callback and read by `get_watcher_status`.
+#[derive(Clone, Default)]
+pub struct WatcherStats {
+    pub(super) path_agent_map: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    counters: Arc<Mutex<Counters>>,
+}
+
+impl WatcherStats {
+    pub(super) fn mark_initialized(&self) {
+        self.counters.lock().unwrap().initialized = true;
+    }
+
+    pub(super) fn set_backend(&self, root: &str, backend: &'static str) {
+        let mut counters = self.counters.lock().unwrap();
+        counters.roots.entry(root.to_string()).or_default().backend = Some(backend);
+    }
+
+    pub(super) fn record_delivered(&self, root: &str) {
+        let mut counters = self.counters.lock().unwrap();
+        let entry = counters.roots.entry(root.to_string()).or_default();
+        entry.events += 1;
+        entry.last_event_at = Some(Utc::now());
+    }
+
+    pub(super) fn record_ignored(&self, root: &str) {
+        let mut counters = self.counters.lock().unwrap();
+        counters.roots.entry(root.to_string()).or_default().ignored += 1;
+    }
+
+    pub(super) fn record_queued(&self) {
+        self.counters.lock().unwrap().queued_events += 1;
+    }
+
+    /// Called by the loop that drains the watcher channel, per event taken.
+    pub fn record_dequeued(&self) {
+        let mut counters = self.counters.lock().unwrap();
+        counters.queued_events = counters.queued_events.saturating_sub(1);
+    }
+
+    pub fn set_debouncing(&self, events: usize) {
+        self.counters.lock().unwrap().debouncing_events = events;
+    }
+
+    pub(super) fn record_dropped(&self) {
+        self.counters.lock().unwrap().dropped_events += 1;
+    }
+
+    pub(super) fn record_error(&self, error: &str) {
+        let mut counters = self.counters.lock().unwrap();
+        counters.errors += 1;
+        counters.last_error = Some(error.to_string());
+    }
+
+    pub(super) fn forget_root(&self, root: &str) {
+        self.counters.lock().unwrap().roots.remove(root);
+    }
+
+    pub fn snapshot(&self) -> WatcherStatus {
+        let map = self.path_agent_map.lock().unwrap().clone();
+        let counters = self.counters.lock().unwrap();
+        let mut roots: Vec<WatchedRootStatus> = map
+            .into_iter()
+            .map(|(path, agent_ids)| {
+                let root = counters.roots.get(&path).cloned().unwrap_or_default();
+                WatchedRootStatus {
+                    path,
+                    agent_ids,
+                    backend: root.backend.unwrap_or("native").to_string(),
+                    events: root.events,
+                    ignored: root.ignored,
+                    last_event_at: root.last_event_at,
+                }
+            })
+            .collect();
+        roots.sort_by(|a, b| a.path.cmp(&b.path));
+        WatcherStatus {
+            initialized: counters.initialized,
+            roots,
+            dropped_events: counters.dropped_events,
+            errors: counters.errors,
+            last_error: counters.last_error.clone(),
+            queued_events: counters.queued_events,
+            debouncing_events: counters.debouncing_events,
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use crate::background;
+use crate::commands;
+use crate::db::{Database, StatusChange};
+use crate::metrics;
+use crate::models::*;
+use crate::notifications::chat;
+use crate::watchdog::{self, WatchdogSettings, WATCHDOG_SETTINGS_KEY};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+// ── Outbound webhooks ───────────────────────────────────────────────────────
+// Agent status changes, finalized runs and new attention items are POSTed to
+// every subscription that wants them, stamped with when they happened. Status
+// changes come from the database's change stream, so one that is undone
+// before anyone looks is still sent. With a secret set, the body is signed
+// like GitHub's: `X-Kanbun-Signature: sha256=<hex HMAC-SHA256 of the body>`.
+
+/// Writes that can raise an event: statuses, finalized runs, dismissals.
+const WATCHED_TABLES: [&str; 3] = ["agents", "runs", "attention_state"];
+const ATTENTION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const SIGNATURE_HEADER: &str = "X-Kanbun-Signature";
+const EVENT_HEADER: &str = "X-Kanbun-Event";
+const DELIVERY_HEADER: &str = "X-Kanbun-Delivery";
+
+pub fn apply_subscription_input(
+    input: WebhookSubscriptionInput,
+    existing: Option<&WebhookSubscription>,
+) -> Result<WebhookSubscription, String> {
+    let url = input.url.trim();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(format!("webhook URL must be http(s): {}", url));
+    }
+    Ok(WebhookSubscription {
+        id: existing
+            .map(|subscription| subscription.id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        url: url.to_string(),
+        secret: input.secret.filter(|secret| !secret.trim().is_empty()),
+        events: input.events,
+        project_ids: input.project_ids,
+        enabled: input.enabled,
+        created_at: existing.map_or_else(Utc::now, |subscription| subscription.created_at),
+        last_delivery_at: existing.and_then(|subscription| subscription.last_delivery_at),
+        last_error: existing.and_then(|subscription| subscription.last_error.clone()),
+    })
+}
+
+fn event_name(event: WebhookEvent) -> String {
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// HMAC-SHA256 (RFC 2104).
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(hmac_sha256(secret.as_bytes(), body))
+    )
+}
+
+fn payload(
+    event: WebhookEvent,
+    agent: &Agent,
+    data: serde_json::Value,
+    occurred_at: DateTime<Utc>,
+) -> WebhookPayload {
+    WebhookPayload {
+        id: Uuid::new_v4().to_string(),
+        event,
+        occurred_at,
+        agent_id: agent.id.clone(),
+        project_id: agent.project_id.clone(),
+        data,
+    }
+}
+
+/// Attention items as the dashboard shows them, dismissals and snoozes
+/// applied.
+fn visible_attention(
+    db: &Database,
+    agents: &HashMap<String, Agent>,
+    now: DateTime<Utc>,
+) -> Result<Vec<AttentionItem>, String> {
+    let projects: HashMap<String, String> = db
+        .list_projects()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|project| (project.id, project.name))
+        .collect();
+    let latest_runs = db.get_latest_runs_by_agent().map_err(|e| e.to_string())?;
+    let watchdog_settings = db
+        .get_setting::<WatchdogSettings>(WATCHDOG_SETTINGS_KEY)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| e.to_string())?;
+    let stuck_runs =
+        watchdog::find_stuck_runs(db, &watchdog_settings, now).map_err(|e| e.to_string())?;
+    let states = db.list_attention_states().map_err(|e| e.to_string())?;
+    Ok(agents
+        .values()
+        .flat_map(|agent| {
+            commands::attention_items(
+                agent,
+                projects
+                    .get(&agent.project_id)
+                    .map(String::as_str)
+                    .unwrap_or(""),
+                latest_runs.get(&agent.id),
+                &stuck_runs,
+            )
+        })
+        .filter(|item| commands::attention_visible(&states, item, now))
+        .collect())
+}
+
+/// Tracks what has already been sent so each event goes out once.
+pub struct WebhookTracker {
+    run_cursor: String, // ended_at of the newest finalized run already sent
+    raised: HashSet<(String, String, DateTime<Utc>)>, // (agent id, reason, timestamp)
+}
+
+impl WebhookTracker {
+    /// Starts from the current state; nothing that already happened is sent.
+    pub fn new(db: &Database, now: DateTime<Utc>) -> Self {
+        let agents: HashMap<String, Agent> = db
+            .list_agents()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|agent| (agent.id.clone(), agent))
+            .collect();
+        let raised = visible_attention(db, &agents, now)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| (item.agent_id, item.reason, item.timestamp))
+            .collect();
+        Self {
+            run_cursor: now.to_rfc3339(),
+            raised,
+        }
+    }
+
+    /// Payloads for `changes` (from `Database::subscribe_status_changes`)
+    /// and for runs ended and attention raised since the last poll, each
+    /// stamped with when it happened.
+    pub fn poll(
+        &mut self,
+        db: &Database,
+        changes: &[StatusChange],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WebhookPayload>, String> {
+        let agents: HashMap<String, Agent> = db
+            .list_agents()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|agent| (agent.id.clone(), agent))
+            .collect();
+        let mut payloads = Vec::new();
+
+        for change in changes {
+            let Some(agent) = agents.get(&change.agent_id) else {
+                continue;
+            };
+            payloads.push(payload(
+                WebhookEvent::AgentStatusChanged,
+                agent,
+                json!({
+                    "agent_name": agent.name,
+                    "from": change.from,
+                    "to": change.to,
+                }),
+                change.at,
+            ));
+        }
+
+        let runs = db
+            .get_runs_ended_since(&self.run_cursor)
+            .map_err(|e| e.to_string())?;
+        if let Some(ended_at) = runs.last().and_then(|run| run.ended_at) {
+            self.run_cursor = ended_at.to_rfc3339();
+        }
+        for run in &runs {
+            let Some(agent) = agents.get(&run.agent_id) else {
+                continue;
+            };
+            payloads.push(payload(
+                WebhookEvent::RunCompleted,
+                agent,
+                json!({
+                    "agent_name": agent.name,
+                    "run_id": run.id,
+                    "status": run.status,
+                    "summary": run.summary,
+                    "started_at": run.started_at,
+                    "ended_at": run.ended_at,
+                }),
+                run.ended_at.unwrap_or(now),
+            ));
+        }
+
+        let attention = visible_attention(db, &agents, now)?;
+        let mut raised = HashSet::new();
+        for item in attention {
+            let key = (item.agent_id.clone(), item.reason.clone(), item.timestamp);
+            if !self.raised.contains(&key) {
+                if let Some(agent) = agents.get(&item.agent_id) {
+                    payloads.push(payload(
+                        WebhookEvent::AttentionRaised,
+                        agent,
+                        json!({
+                            "agent_name": item.agent_name,
+                            "project_name": item.project_name,
+                            "reason": item.reason,
+                            "since": item.timestamp,
+                        }),
+                        item.timestamp,
+                    ));
+                }
+            }
+            raised.insert(key);
+        }
+        self.raised = raised;
+
+        Ok(payloads)
+    }
+}
+
+pub async fn send(
+    client: &Client,
+    subscription: &WebhookSubscription,
+    payload: &WebhookPayload,
+) -> Result<(), String> {
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let mut request = client
+        .post(&subscription.url)
+        .header("Content-Type", "application/json")
+        .header(EVENT_HEADER, event_name(payload.event))
+        .header(DELIVERY_HEADER, &payload.id);
+    if let Some(secret) = &subscription.secret {
+        request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+    }
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .map_err(|error| format!("webhook request failed: {}", error))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("webhook returned HTTP {}", status));
+    }
+    Ok(())
+}
+
+/// POST each payload to the subscriptions that want it and record how the
+/// latest delivery went on each.
+pub async fn deliver(db: &Database, payloads: &[WebhookPayload]) {
+    if payloads.is_empty() {
+        return;
+    }
+    let subscriptions = match db.list_webhook_subscriptions() {
+        Ok(subscriptions) => subscriptions,
+        Err(error) => {
+            tracing::warn!("Failed to load webhook subscriptions: {}", error);
+            return;
+        }
+    };
+    let client = chat::build_client();
+    for subscription in &subscriptions {
+        for payload in payloads
+            .iter()
+            .filter(|payload| subscription.wants(payload.event, &payload.project_id))
+        {
+            let result = send(&client, subscription, payload).await;
+            if let Err(error) = &result {
+                tracing::warn!("Webhook delivery to {} failed: {}", subscription.url, error);
+                metrics::record_delivery_failure("webhook");
+            }
+            if let Err(error) =
+                db.record_webhook_delivery(&subscription.id, Utc::now(), result.err().as_deref())
+            {
+                tracing::warn!("Failed to record webhook delivery: {}", error);
+            }
+        }
+    }
+}
+
+/// Wake on writes that can raise an event, and once a minute for attention
+/// that comes with time (stuck runs, snoozes running out). Nothing is
+/// computed while no subscription is enabled; enabling one starts from the
+/// state at that point.
+pub fn spawn(db: Arc<Database>) {
+    background::spawn("Outbound webhooks", async move {
+        let mut writes = db.subscribe_writes();
+        let mut changes = db.subscribe_status_changes();
+        let mut check = tokio::time::interval(ATTENTION_CHECK_INTERVAL);
+        check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut tracker: Option<WebhookTracker> = None;
+        loop {
+            let woken_by_write = tokio::select! {
+                open = background::wait_for_write(&mut writes, &WATCHED_TABLES) => Some(open),
+                _ = check.tick() => None,
+            };
+            match woken_by_write {
+                Some(false) => return,
+                Some(true) => background::settle(&mut writes).await,
+                None => {}
+            }
+            let (changes, lagged) = background::drain(&mut changes);
+            if lagged {
+                tracing::warn!("Webhooks fell behind; some status changes were not sent");
+            }
+            let subscribed = db
+                .list_webhook_subscriptions()
+                .map(|subscriptions| subscriptions.iter().any(|s| s.enabled))
+                .unwrap_or(false);
+            if !subscribed {
+                tracker = None;
+                continue;
+            }
+            let now = db.now();
+            let Some(tracker) = tracker.as_mut() else {
+                tracker = Some(WebhookTracker::new(&db, now));
+                continue;
+            };
+            match tracker.poll(&db, &changes, now) {
+                Ok(payloads) => deliver(&db, &payloads).await,
+                Err(error) => tracing::warn!("Webhook pass failed: {}", error),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_bodies_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Keys longer than a block are hashed first (test case 6).
+        assert_eq!(
+            hex::encode(hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        assert!(signature("s3cret", b"{}").starts_with("sha256="));
+    }
+
+    #[test]
+    fn sends_status_changes_and_new_attention_once() {
+        let db = Database::new(":memory:").expect("db should open");
+        let project = Project::new("Site", "#6366f1");
+        db.create_project(&project).expect("project should save");
+        let agent = Agent::new("Docs", &project.id, AgentKind::Terminal, "docs");
+        db.create_agent(&agent).expect("agent should save");
+        let mut changes = db.subscribe_status_changes();
+
+        let now = Utc::now();
+        let mut tracker = WebhookTracker::new(&db, now);
+        assert!(tracker.poll(&db, &[], now).expect("poll").is_empty());
+
+        // Blocked and back before the tracker looks: both changes go out.
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .expect("status should update");
+        db.update_agent_status(&agent.id, &AgentStatus::Idle)
+            .expect("status should update");
+        let (steps, _) = background::drain(&mut changes);
+        let payloads = tracker.poll(&db, &steps, now).expect("poll");
+        let targets: Vec<&serde_json::Value> =
+            payloads.iter().map(|payload| &payload.data["to"]).collect();
+        assert_eq!(targets, ["blocked", "idle"]);
+        assert_eq!(payloads[0].occurred_at, steps[0].at);
+
+        db.update_agent_status(&agent.id, &AgentStatus::Blocked)
+            .expect("status should update");
+        let (steps, _) = background::drain(&mut changes);
+        let payloads = tracker.poll(&db, &steps, now).expect("poll");
+        let events: Vec<WebhookEvent> = payloads.iter().map(|payload| payload.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                WebhookEvent::AgentStatusChanged,
+                WebhookEvent::AttentionRaised
+            ]
+        );
+        assert_eq!(payloads[1].data["reason"], "blocked");
+        assert!(tracker.poll(&db, &[], now).expect("poll").is_empty());
+
+        let subscription = apply_subscription_input(
+            WebhookSubscriptionInput {
+                id: None,
+                url: "https://example.com/hook".to_string(),
+                secret: Some(" ".to_string()),
+                events: vec![WebhookEvent::RunCompleted],
+                project_ids: vec![],
+                enabled: true,
+            },
+            None,
+        )
+        .expect("input should be valid");
+        assert_eq!(subscription.secret, None);
+        assert!(subscription.wants(WebhookEvent::RunCompleted, &project.id));
+        assert!(!subscription.wants(WebhookEvent::AgentStatusChanged, &project.id));
+    }
+}
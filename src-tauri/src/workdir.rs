@@ -0,0 +1,168 @@
+use crate::errors::CommandError;
+use crate::git::git;
+use serde::Serialize;
+use std::path::Path;
+
+// ── Working directories ─────────────────────────────────────────────────────
+// An agent's working directory used to be taken on trust and only failed when
+// the adapter tried to spawn there. `inspect` expands `~`, checks the path is
+// a directory the app can read and write, and reports the git repository it
+// sits in, so the picker can show the branch and every path that creates or
+// edits an agent (create/update, templates, bundle imports) can refuse a
+// broken path up front.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkingDirectory {
+    pub path: String, // with `~` expanded
+    pub created: bool,
+    pub writable: bool, // a probe file could be created there
+    pub git: Option<GitRepoInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitRepoInfo {
+    pub root: String,
+    pub branch: Option<String>, // None on a detached HEAD
+    pub remote: Option<String>, // origin's URL, else the first remote's
+}
+
+/// Check `path`, creating it (and missing parents) first when `create` is
+/// set and it doesn't exist yet.
+pub fn inspect(path: &str, create: bool) -> Result<WorkingDirectory, CommandError> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(CommandError::invalid_input("Working directory is empty"));
+    }
+    let expanded = shellexpand::tilde(path).to_string();
+    let dir = Path::new(&expanded);
+    if !dir.is_absolute() {
+        return Err(CommandError::invalid_input(format!(
+            "Working directory must be an absolute path: {}",
+            path
+        )));
+    }
+
+    let mut created = false;
+    if !dir.exists() {
+        if !create {
+            return Err(CommandError::not_found(format!(
+                "Working directory not found: {}",
+                expanded
+            )));
+        }
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CommandError::from(format!("Could not create {}: {}", expanded, e)))?;
+        created = true;
+    }
+    if !dir.is_dir() {
+        return Err(CommandError::invalid_input(format!(
+            "Working directory is not a directory: {}",
+            expanded
+        )));
+    }
+    std::fs::read_dir(dir).map_err(|e| {
+        CommandError::invalid_input(format!("Working directory is not readable: {}", e))
+    })?;
+    let writable = can_create_files(dir);
+
+    let git = repo_info(dir);
+    Ok(WorkingDirectory {
+        path: expanded,
+        created,
+        writable,
+        git,
+    })
+}
+
+/// What an agent may be given: nothing, or a directory that exists and can
+/// be written to.
+pub fn validate(path: Option<&str>) -> Result<(), CommandError> {
+    let Some(path) = path.filter(|path| !path.trim().is_empty()) else {
+        return Ok(());
+    };
+    let checked = inspect(path, false)?;
+    if !checked.writable {
+        return Err(CommandError::invalid_input(format!(
+            "Working directory is read-only: {}",
+            checked.path
+        )));
+    }
+    Ok(())
+}
+
+/// Permission bits don't account for ACLs, read-only mounts or Windows
+/// attributes, so actually create (and remove) a file.
+fn can_create_files(dir: &Path) -> bool {
+    let probe = dir.join(format!(".kanbun-probe-{}", uuid::Uuid::new_v4()));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn repo_info(dir: &Path) -> Option<GitRepoInfo> {
+    let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+    // symbolic-ref also names the branch of a repo with no commits yet.
+    let branch = git(dir, &["symbolic-ref", "--short", "-q", "HEAD"])
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    let remote = git(dir, &["remote", "get-url", "origin"])
+        .or_else(|| {
+            let first = git(dir, &["remote"])?.lines().next()?.trim().to_string();
+            git(dir, &["remote", "get-url", &first])
+        })
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    Some(GitRepoInfo {
+        root: root.trim().to_string(),
+        branch,
+        remote,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inspects_creates_and_reports_git_repos() {
+        let base = std::env::temp_dir().join(format!("kanbun-workdir-{}", uuid::Uuid::new_v4()));
+        let missing = base.join("nested/repo");
+        let missing_path = missing.to_string_lossy().to_string();
+
+        let error = inspect(&missing_path, false).unwrap_err();
+        assert_eq!(error.code, crate::errors::ErrorCode::NotFound);
+        assert!(validate(Some(&missing_path)).is_err());
+        assert!(validate(Some("  ")).is_ok());
+        assert!(inspect("relative/dir", true).is_err());
+
+        let checked = inspect(&missing_path, true).unwrap();
+        assert!(checked.created && checked.writable);
+        assert!(validate(Some(&missing_path)).is_ok());
+        assert_eq!(std::fs::read_dir(&missing).unwrap().count(), 0); // probe removed
+
+        if git(&missing, &["init", "-q", "-b", "main"]).is_some() {
+            git(
+                &missing,
+                &["remote", "add", "upstream", "git@github.com:acme/site.git"],
+            );
+            let checked = inspect(&missing_path, false).unwrap();
+            assert!(!checked.created);
+            let repo = checked.git.expect("repo should be detected");
+            assert_eq!(repo.branch.as_deref(), Some("main"));
+            assert_eq!(repo.remote.as_deref(), Some("git@github.com:acme/site.git"));
+        }
+
+        let file = base.join("notes.txt");
+        std::fs::write(&file, "x").unwrap();
+        assert!(inspect(&file.to_string_lossy(), false).is_err());
+        std::fs::remove_dir_all(&base).ok();
+    }
+}
@@ -10,10 +10,14 @@ import { open, save } from "@tauri-apps/plugin-dialog";
 import type {
   AdapterConfig,
   AdapterHealth,
+  ConfigValidation,
   AdapterType,
   AgentDetail,
   AgentKind,
   DatabaseSnapshotResult,
+  DemoWorkspace,
+  TimezoneSettings,
+  RecordedTranscript,
   ConnectorConfig,
   ConnectorInfo,
   ConnectorItem,
@@ -24,6 +28,8 @@ import type {
 } from "@/types";
 import {
   createAgent,
+  createDemoWorkspace,
+  deleteDemoWorkspace,
   deleteProjectContextDoc,
   createProject,
   deleteConnectorItem,
@@ -32,12 +38,18 @@ import {
   getAgentDetail,
   getConnectorConfigs,
   getConnectorItems,
+  getDemoWorkspace,
+  getTimezoneSettings,
+  setTimezoneSettings,
+  recordSimulationTranscript,
   getConversation,
   getDashboard,
+  isCommandError,
   isTauriRuntime,
   listConnectors,
   listProjectContextDocs,
   importDatabaseSnapshot,
+  onAdapterHealth,
   saveConnector,
   saveProjectContextDoc,
   restartAdapter,
@@ -139,6 +151,7 @@ type AgentDraft = {
   processCommand: string;
   webhookEndpoint: string;
   webhookAuthHeader: string;
+  simulationTranscript: string;
   preset: AgentPresetId;
   cliPermissions: string;
 };
@@ -173,6 +186,7 @@ const DEFAULT_AGENT_DRAFT: AgentDraft = {
   processCommand: "",
   webhookEndpoint: DEFAULT_WEBHOOK_ENDPOINT,
   webhookAuthHeader: "",
+  simulationTranscript: "",
   preset: "mock_demo",
   cliPermissions: "",
 };
@@ -226,6 +240,7 @@ function isTextualInputTarget(target: EventTarget | null): boolean {
 
 function toErrorMessage(error: unknown): string {
   if (error instanceof Error) return error.message;
+  if (isCommandError(error)) return error.message;
   if (typeof error === "string") return error;
   return "Unknown error";
 }
@@ -313,6 +328,11 @@ export default function Dashboard() {
   const [backupBusy, setBackupBusy] = useState<"idle" | "exporting" | "importing">("idle");
   const [backupMessage, setBackupMessage] = useState<string | null>(null);
   const [backupError, setBackupError] = useState<string | null>(null);
+  const [demoWorkspace, setDemoWorkspace] = useState<DemoWorkspace | null>(null);
+  const [demoBusy, setDemoBusy] = useState<"idle" | "creating" | "deleting">("idle");
+  const [demoError, setDemoError] = useState<string | null>(null);
+  const [timezoneDraft, setTimezoneDraft] = useState("");
+  const [timezoneMessage, setTimezoneMessage] = useState<string | null>(null);
   const [projectDraft, setProjectDraft] = useState<ProjectDraft>(DEFAULT_PROJECT_DRAFT);
   const [projectBusy, setProjectBusy] = useState(false);
   const [projectMessage, setProjectMessage] = useState<string | null>(null);
@@ -594,20 +614,16 @@ export default function Dashboard() {
     let cancelled = false;
     const syncConversation = async () => {
       try {
-        const [, detail, health] = await Promise.all([
+        // Adapter health arrives as `adapter:health` events instead of being probed here.
+        const [, detail] = await Promise.all([
           refreshConversation(selectedAgentId),
           getAgentDetail(selectedAgentId),
-          getAdapterHealth(selectedAgentId),
         ]);
         if (cancelled) return;
         setAgentDetailsById((prev) => ({
           ...prev,
           [selectedAgentId]: detail,
         }));
-        setAdapterHealthByAgent((prev) => ({
-          ...prev,
-          [selectedAgentId]: health,
-        }));
       } catch (error) {
         if (!cancelled) console.error("Failed to refresh conversation:", error);
       }
@@ -630,11 +646,37 @@ export default function Dashboard() {
     void refreshAdapterHealth(selectedAgentId);
   }, [isTauri, selectedAgentId, refreshAgentDetail, refreshAdapterHealth]);
 
+  useEffect(() => {
+    if (!isTauri) return;
+    let unlisten: (() => void) | null = null;
+    let disposed = false;
+    void onAdapterHealth(({ agent_id, health }) => {
+      setAdapterHealthByAgent((prev) => ({ ...prev, [agent_id]: health }));
+    }).then((stop) => {
+      if (disposed) stop();
+      else unlisten = stop;
+    });
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, [isTauri]);
+
   useEffect(() => {
     if (!isTauri) return;
     void refreshConnectors();
   }, [isTauri, refreshConnectors]);
 
+  useEffect(() => {
+    if (!isTauri || activeView !== "settings") return;
+    void getDemoWorkspace()
+      .then(setDemoWorkspace)
+      .catch(() => setDemoWorkspace(null));
+    void getTimezoneSettings()
+      .then((settings: TimezoneSettings) => setTimezoneDraft(settings.timezone ?? ""))
+      .catch(() => setTimezoneDraft(""));
+  }, [isTauri, activeView]);
+
   useEffect(() => {
     if (!isTauri || activeView !== "connectors") return;
     void refreshConnectorItems(selectedConnectorId);
@@ -829,6 +871,14 @@ export default function Dashboard() {
           command: draft.processCommand.trim(),
           env: buildAdapterEnv(cliPermissions),
         } as const)
+      : draft.adapterType === "simulation"
+      ? ({
+          adapter_type: "simulation" as const,
+          session_name: null,
+          endpoint: draft.simulationTranscript.trim(),
+          command: null,
+          env: null,
+        } as const)
       : ({
           adapter_type: "mock" as const,
           session_name: null,
@@ -1176,6 +1226,10 @@ export default function Dashboard() {
       setAgentError("Webhook endpoint is required.");
       return;
     }
+    if (agentDraft.adapterType === "simulation" && !agentDraft.simulationTranscript.trim()) {
+      setAgentError("Transcript path is required for simulation adapter.");
+      return;
+    }
 
     setAgentBusy(true);
     try {
@@ -1376,6 +1430,47 @@ export default function Dashboard() {
     }
   }, [contextProjectId, isTauri, refreshConnectors, refreshDashboard, refreshProjectContextDocs]);
 
+  const handleSaveTimezone = useCallback(async () => {
+    setTimezoneMessage(null);
+    try {
+      const saved = await setTimezoneSettings({ timezone: timezoneDraft.trim() || null });
+      setTimezoneMessage(saved.timezone ? `Days now follow ${saved.timezone}.` : "Days now follow the system timezone.");
+      await refreshDashboard();
+    } catch (error) {
+      setTimezoneMessage(toErrorMessage(error));
+    }
+  }, [refreshDashboard, timezoneDraft]);
+
+  const handleCreateDemoWorkspace = useCallback(async () => {
+    setDemoBusy("creating");
+    setDemoError(null);
+    try {
+      setDemoWorkspace(await createDemoWorkspace());
+      await refreshDashboard();
+    } catch (error) {
+      setDemoError(`Demo setup failed: ${toErrorMessage(error)}`);
+    } finally {
+      setDemoBusy("idle");
+    }
+  }, [refreshDashboard]);
+
+  const handleDeleteDemoWorkspace = useCallback(async () => {
+    if (demoWorkspace && selectedAgentId && demoWorkspace.agent_ids.includes(selectedAgentId)) {
+      setSelectedAgentId(null);
+    }
+    setDemoBusy("deleting");
+    setDemoError(null);
+    try {
+      await deleteDemoWorkspace();
+      setDemoWorkspace(null);
+      await refreshDashboard();
+    } catch (error) {
+      setDemoError(`Demo removal failed: ${toErrorMessage(error)}`);
+    } finally {
+      setDemoBusy("idle");
+    }
+  }, [demoWorkspace, refreshDashboard, selectedAgentId]);
+
   const handleConnectorDraftChange = useCallback(
     (connectorType: string, patch: Partial<ConnectorDraft>) => {
       setConnectorDrafts((prev) => ({
@@ -1496,19 +1591,37 @@ export default function Dashboard() {
     [isTauri, refreshAgentDetail, refreshConversation, refreshDashboard]
   );
 
+  const handleRecordTranscript = useCallback(
+    async (agentId: string, agentName: string): Promise<RecordedTranscript | null> => {
+      if (!isTauri) {
+        throw new Error("Transcript recording is available only in desktop runtime.");
+      }
+
+      const slug = agentName.toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-|-$/g, "") || "agent";
+      const selected = await save({
+        defaultPath: `${slug}-transcript.json`,
+        filters: [{ name: "Transcript", extensions: ["json"] }],
+      });
+      if (!selected) return null;
+      return recordSimulationTranscript(agentId, selected);
+    },
+    [isTauri]
+  );
+
   const handleSaveAdapterConfig = useCallback(
-    async (agentId: string, config: AdapterConfig) => {
+    async (agentId: string, config: AdapterConfig): Promise<ConfigValidation> => {
       if (!isTauri) {
         throw new Error("Adapter config save is available only in desktop runtime.");
       }
 
-      await setAdapterConfig(agentId, config);
+      const validation = await setAdapterConfig(agentId, config);
       await Promise.all([
         refreshAgentDetail(agentId),
         refreshAdapterHealth(agentId),
         refreshConversation(agentId),
         refreshDashboard(),
       ]);
+      return validation;
     },
     [isTauri, refreshAgentDetail, refreshAdapterHealth, refreshConversation, refreshDashboard]
   );
@@ -2170,6 +2283,7 @@ export default function Dashboard() {
                             <option value="claude_code">claude_code</option>
                             <option value="process">process</option>
                             <option value="http_webhook">http_webhook</option>
+                            <option value="simulation">simulation</option>
                           </select>
                         </label>
                         {agentDraft.adapterType === "claude_code" && (
@@ -2247,6 +2361,31 @@ export default function Dashboard() {
                             />
                           </label>
                         )}
+                        {agentDraft.adapterType === "simulation" && (
+                          <label
+                            className="mn"
+                            style={{ fontSize: 10, color: "var(--main)", display: "grid", gap: 6 }}
+                          >
+                            Transcript path (required)
+                            <input
+                              type="text"
+                              value={agentDraft.simulationTranscript}
+                              onChange={(event) =>
+                                handleAgentDraftChange({ simulationTranscript: event.currentTarget.value })
+                              }
+                              placeholder="~/kanbun/transcripts/session.json"
+                              style={{
+                                border: "1px solid var(--border)",
+                                background: "var(--bg-card)",
+                                color: "var(--main)",
+                                padding: "6px 8px",
+                                fontFamily: "var(--font-mono)",
+                                fontSize: 12,
+                              }}
+                              disabled={!isTauri || agentBusy}
+                            />
+                          </label>
+                        )}
                         {agentDraft.adapterType === "http_webhook" && (
                           <>
                             <label className="mn" style={{ fontSize: 10, color: "var(--main)", display: "grid", gap: 6 }}>
@@ -2364,6 +2503,93 @@ export default function Dashboard() {
                   )}
                 </div>
 
+                <div
+                  style={{
+                    border: "1px solid var(--border)",
+                    background: "var(--bg-card)",
+                    padding: 12,
+                  }}
+                >
+                  <p className="mn" style={{ fontSize: 10, color: "var(--dim)", marginBottom: 6 }}>
+                    TIMEZONE
+                  </p>
+                  <p style={{ fontSize: 13, fontWeight: 700, color: "var(--hi)", marginBottom: 8 }}>
+                    Where &quot;today&quot; starts
+                  </p>
+                  <p className="mn" style={{ fontSize: 10, color: "var(--main)", marginBottom: 10 }}>
+                    Due dates, files changed today, daily stats, weekly digests, schedules and quiet hours use this
+                    IANA timezone. Leave empty to follow the system.
+                  </p>
+                  <div className="flex items-center gap-2">
+                    <input
+                      type="text"
+                      value={timezoneDraft}
+                      placeholder={Intl.DateTimeFormat().resolvedOptions().timeZone}
+                      onChange={(event) => setTimezoneDraft(event.currentTarget.value)}
+                      style={{
+                        border: "1px solid var(--border)",
+                        background: "var(--bg-card)",
+                        color: "var(--main)",
+                        padding: "6px 8px",
+                        fontFamily: "var(--font-mono)",
+                        fontSize: 12,
+                      }}
+                    />
+                    <button className="btn-cortex" onClick={() => void handleSaveTimezone()} disabled={!isTauri}>
+                      Save
+                    </button>
+                  </div>
+                  {timezoneMessage && (
+                    <p className="mn" style={{ fontSize: 10, color: "var(--dim)", marginTop: 8 }}>
+                      {timezoneMessage}
+                    </p>
+                  )}
+                </div>
+
+                <div
+                  style={{
+                    border: "1px solid var(--border)",
+                    background: "var(--bg-card)",
+                    padding: 12,
+                  }}
+                >
+                  <p className="mn" style={{ fontSize: 10, color: "var(--dim)", marginBottom: 6 }}>
+                    DEMO_WORKSPACE
+                  </p>
+                  <p style={{ fontSize: 13, fontWeight: 700, color: "var(--hi)", marginBottom: 8 }}>
+                    Sample projects on mock agents
+                  </p>
+                  <p className="mn" style={{ fontSize: 10, color: "var(--main)", marginBottom: 10 }}>
+                    {demoWorkspace
+                      ? `${demoWorkspace.project_ids.length} sample projects with ${demoWorkspace.agent_ids.length} mock agents are loaded. Removing them leaves your own projects untouched.`
+                      : "Adds a few sample projects whose agents run on mock adapters, to try the app without real agents."}
+                  </p>
+                  <div className="flex items-center gap-2">
+                    {demoWorkspace ? (
+                      <button
+                        className="btn-cortex"
+                        onClick={() => void handleDeleteDemoWorkspace()}
+                        disabled={!isTauri || demoBusy !== "idle"}
+                      >
+                        {demoBusy === "deleting" ? "Removing..." : "Remove demo"}
+                      </button>
+                    ) : (
+                      <button
+                        className="btn-cortex"
+                        onClick={() => void handleCreateDemoWorkspace()}
+                        disabled={!isTauri || demoBusy !== "idle"}
+                      >
+                        {demoBusy === "creating" ? "Creating..." : "Create demo"}
+                      </button>
+                    )}
+                  </div>
+                  {demoError && (
+                    <p className="mn" style={{ fontSize: 10, color: "var(--err)", marginTop: 8 }}>
+                      {demoError}
+                    </p>
+                  )}
+                </div>
+
                 <div
                   style={{
                     border: "1px solid var(--border)",
@@ -2684,6 +2910,7 @@ export default function Dashboard() {
             onRefreshAdapterHealth={() => void refreshAdapterHealth(selectedAgent.agent.id)}
             onRestartAdapter={() => void handleRestartAdapter(selectedAgent.agent.id)}
             onSaveAdapterConfig={handleSaveAdapterConfig}
+            onRecordTranscript={() => handleRecordTranscript(selectedAgent.agent.id, selectedAgent.agent.name)}
           />
         )}
       </div>
@@ -2,14 +2,26 @@ import { useEffect, useState } from "react";
 import type {
   AdapterConfig,
   AdapterHealth,
+  AgentLogTail,
+  ConfigValidation,
+  RecordedTranscript,
   AgentSummary,
   Run,
+  RunHeader,
+  RunOutput,
   FileChange,
   Message,
   MessageKind,
 } from "@/types";
 import { StatusBadge } from "./StatusBadge";
 import { ConversationThread } from "./ConversationThread";
+import {
+  getAgentLogs,
+  getRunDetail,
+  getRunOutputs,
+  isCommandError,
+  isTauriRuntime,
+} from "@/lib/tauri";
 
 const autonomyLabels: Record<string, string> = {
   manual: "Manual — requires approval for everything",
@@ -37,6 +49,7 @@ function formatDate(dateStr: string): string {
 
 function toErrorMessage(error: unknown): string {
   if (error instanceof Error) return error.message;
+  if (isCommandError(error)) return error.message;
   if (typeof error === "string") return error;
   return "Unknown error";
 }
@@ -92,7 +105,7 @@ function parseEnvDraft(raw: string): Record<string, string> | null {
   return Object.keys(normalized).length > 0 ? normalized : null;
 }
 
-type TabId = "chat" | "overview";
+type TabId = "chat" | "overview" | "logs";
 
 export function AgentDetailPanel({
   summary,
@@ -112,6 +125,7 @@ export function AgentDetailPanel({
   onRefreshAdapterHealth,
   onRestartAdapter,
   onSaveAdapterConfig,
+  onRecordTranscript,
 }: {
   summary: AgentSummary;
   runs: Run[];
@@ -129,7 +143,8 @@ export function AgentDetailPanel({
   onLoadOlderConversation: (agentId: string) => void;
   onRefreshAdapterHealth: () => void;
   onRestartAdapter: () => void;
-  onSaveAdapterConfig: (agentId: string, config: AdapterConfig) => Promise<void>;
+  onSaveAdapterConfig: (agentId: string, config: AdapterConfig) => Promise<ConfigValidation>;
+  onRecordTranscript: () => Promise<RecordedTranscript | null>;
 }) {
   const { agent, recent_run } = summary;
   const [activeTab, setActiveTab] = useState<TabId>("chat");
@@ -143,9 +158,17 @@ export function AgentDetailPanel({
     getProcessRestartPolicy(adapterConfig?.env ?? null)
   );
   const [adapterConfigSaving, setAdapterConfigSaving] = useState(false);
+  const [transcriptBusy, setTranscriptBusy] = useState(false);
+  const [transcriptMessage, setTranscriptMessage] = useState<string | null>(null);
   const [adapterConfigMessage, setAdapterConfigMessage] = useState<string | null>(null);
   const [adapterConfigError, setAdapterConfigError] = useState<string | null>(null);
   const latestRun = runs[0] ?? recent_run;
+  const [latestOutputs, setLatestOutputs] = useState<RunOutput[]>([]);
+  const [latestOutputsOffset, setLatestOutputsOffset] = useState(0);
+  const [agentLog, setAgentLog] = useState<AgentLogTail | null>(null);
+  const [agentLogError, setAgentLogError] = useState<string | null>(null);
+  const latestRunId = latestRun?.id ?? null;
+  const latestRunOutputCount = latestRun ? outputCount(latestRun) : 0;
   const completedRuns = runs.filter((run) => run.status === "completed").length;
   const terminalRuns = runs.filter((run) => run.status !== "in_progress").length;
   const successRate = terminalRuns > 0 ? `${((completedRuns / terminalRuns) * 100).toFixed(1)}%` : "—";
@@ -157,6 +180,56 @@ export function AgentDetailPanel({
     adapterConfig?.endpoint ?? ""
   }|${adapterConfig?.command ?? ""}|${JSON.stringify(adapterConfig?.env ?? null)}`;
 
+  // Run headers come without outputs; load the newest page for the latest run.
+  useEffect(() => {
+    if (!latestRun) {
+      setLatestOutputs([]);
+      setLatestOutputsOffset(0);
+      return;
+    }
+    if (!isTauriRuntime()) {
+      setLatestOutputs(latestRun.outputs);
+      setLatestOutputsOffset(0);
+      return;
+    }
+    let cancelled = false;
+    getRunDetail(latestRun.id)
+      .then((detail) => {
+        if (cancelled) return;
+        setLatestOutputs(detail.outputs.outputs);
+        setLatestOutputsOffset(detail.outputs.offset);
+      })
+      .catch(() => {
+        if (!cancelled) setLatestOutputs([]);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [latestRunId, latestRunOutputCount]);
+
+  const loadAgentLog = async () => {
+    if (!isTauriRuntime()) return;
+    try {
+      setAgentLog(await getAgentLogs(agent.id, 500));
+      setAgentLogError(null);
+    } catch (error) {
+      setAgentLogError(toErrorMessage(error));
+    }
+  };
+
+  useEffect(() => {
+    if (activeTab === "logs") void loadAgentLog();
+  }, [activeTab, agent.id]);
+
+  const loadOlderOutputs = async () => {
+    if (!latestRunId || latestOutputsOffset === 0) return;
+    const limit = 100;
+    const offset = Math.max(0, latestOutputsOffset - limit);
+    const page = await getRunOutputs(latestRunId, offset, latestOutputsOffset - offset);
+    setLatestOutputs((current) => [...page.outputs, ...current]);
+    setLatestOutputsOffset(page.offset);
+  };
+
   useEffect(() => {
     setAdapterCommandDraft(adapterConfig?.command ?? "");
     setAdapterEnvDraft(formatEnvDraft(adapterConfig?.env ?? null));
@@ -168,6 +241,21 @@ export function AgentDetailPanel({
     setAdapterConfigSaving(false);
   }, [adapterSignature]);
 
+  const handleRecordTranscript = async () => {
+    setTranscriptBusy(true);
+    setTranscriptMessage(null);
+    try {
+      const recorded = await onRecordTranscript();
+      if (recorded) {
+        setTranscriptMessage(`Recorded ${recorded.turns} turns (${recorded.steps} steps) to ${recorded.path}.`);
+      }
+    } catch (error) {
+      setTranscriptMessage(`Recording failed: ${toErrorMessage(error)}`);
+    } finally {
+      setTranscriptBusy(false);
+    }
+  };
+
   const handleSaveAdapterConfig = async () => {
     if (!adapterConfig) {
       setAdapterConfigError("No adapter configuration available for this workstream.");
@@ -182,6 +270,10 @@ export function AgentDetailPanel({
       setAdapterConfigError("Webhook endpoint is required.");
       return;
     }
+    if (adapterConfig.adapter_type === "simulation" && !adapterEndpointDraft.trim()) {
+      setAdapterConfigError("Transcript path is required.");
+      return;
+    }
 
     let parsedEnv: Record<string, string> | null = null;
     try {
@@ -208,11 +300,13 @@ export function AgentDetailPanel({
       nextEnv = Object.keys(nextWithAuth).length > 0 ? nextWithAuth : null;
     }
 
+    const usesEndpointDraft =
+      adapterConfig.adapter_type === "http_webhook" || adapterConfig.adapter_type === "simulation";
     const nextConfig: AdapterConfig = {
       ...adapterConfig,
-      endpoint: adapterConfig.adapter_type === "http_webhook" ? adapterEndpointDraft.trim() : adapterConfig.endpoint,
+      endpoint: usesEndpointDraft ? adapterEndpointDraft.trim() : adapterConfig.endpoint,
       command:
-        adapterConfig.adapter_type === "http_webhook"
+        usesEndpointDraft
           ? null
           : adapterCommandDraft.trim()
           ? adapterCommandDraft.trim()
@@ -224,8 +318,13 @@ export function AgentDetailPanel({
     setAdapterConfigMessage(null);
     setAdapterConfigError(null);
     try {
-      await onSaveAdapterConfig(agent.id, nextConfig);
-      setAdapterConfigMessage("Adapter configuration saved.");
+      const validation = await onSaveAdapterConfig(agent.id, nextConfig);
+      const warnings = validation.issues.map((issue) => `${issue.field}: ${issue.message}`);
+      setAdapterConfigMessage(
+        warnings.length > 0
+          ? `Adapter configuration saved with warnings: ${warnings.join("; ")}`
+          : "Adapter configuration saved."
+      );
     } catch (error) {
       setAdapterConfigError(`Save failed: ${toErrorMessage(error)}`);
     } finally {
@@ -315,6 +414,12 @@ export function AgentDetailPanel({
         >
           Overview
         </button>
+        <button
+          className={`tab-cortex ${activeTab === "logs" ? "active" : ""}`}
+          onClick={() => setActiveTab("logs")}
+        >
+          Logs
+        </button>
       </div>
 
       {/* Tab content */}
@@ -328,6 +433,26 @@ export function AgentDetailPanel({
             onLoadOlder={() => onLoadOlderConversation(agent.id)}
             onSendMessage={(kind, content) => onSendMessage(agent.id, kind, content)}
           />
+        ) : activeTab === "logs" ? (
+          <div className="flex flex-col h-full" style={{ padding: "12px 14px" }}>
+            <div className="flex items-center justify-between" style={{ marginBottom: 8 }}>
+              <span className="mn truncate" style={{ fontSize: 10, color: "var(--dim)" }}>
+                {agentLog?.path ?? "—"}
+              </span>
+              <button className="btn-cortex" onClick={() => void loadAgentLog()}>
+                Refresh
+              </button>
+            </div>
+            {agentLogError && (
+              <div style={{ fontSize: 11, color: "var(--err)", marginBottom: 8 }}>{agentLogError}</div>
+            )}
+            <pre
+              className="mn flex-1 overflow-auto"
+              style={{ fontSize: 10, lineHeight: 1.5, color: "var(--main)", whiteSpace: "pre-wrap", margin: 0 }}
+            >
+              {agentLog && agentLog.lines.length > 0 ? agentLog.lines.join("\n") : "No log lines yet."}
+            </pre>
+          </div>
         ) : (
           <div className="overflow-y-auto h-full" style={{ padding: "12px 14px" }}>
             {/* Config */}
@@ -405,6 +530,29 @@ export function AgentDetailPanel({
                         />
                       </label>
                     </>
+                  ) : adapterConfig.adapter_type === "simulation" ? (
+                    <label className="mn" style={{ fontSize: 10, color: "var(--main)", display: "grid", gap: 6 }}>
+                      Transcript path
+                      <input
+                        type="text"
+                        value={adapterEndpointDraft}
+                        onChange={(event) => {
+                          setAdapterEndpointDraft(event.currentTarget.value);
+                          setAdapterConfigMessage(null);
+                          setAdapterConfigError(null);
+                        }}
+                        placeholder="~/kanbun/transcripts/session.json"
+                        style={{
+                          border: "1px solid var(--border)",
+                          background: "var(--bg-card)",
+                          color: "var(--main)",
+                          padding: "6px 8px",
+                          fontFamily: "var(--font-mono)",
+                          fontSize: 11,
+                        }}
+                        disabled={adapterConfigSaving}
+                      />
+                    </label>
                   ) : (
                     <label className="mn" style={{ fontSize: 10, color: "var(--main)", display: "grid", gap: 6 }}>
                       {adapterConfig.adapter_type === "process" ? "Process command" : "Command"}
@@ -585,7 +733,17 @@ export function AgentDetailPanel({
                   onClick={onRestartAdapter}
                   disabled={adapterRestartBusy}
                 />
+                <ActionBtn
+                  label={transcriptBusy ? "Recording..." : "Record Transcript"}
+                  onClick={() => void handleRecordTranscript()}
+                  disabled={transcriptBusy}
+                />
               </div>
+              {transcriptMessage && (
+                <p className="mn" style={{ fontSize: 9, color: "var(--dim)", marginTop: 6, whiteSpace: "pre-wrap" }}>
+                  {transcriptMessage}
+                </p>
+              )}
             </div>
 
             {/* Runs */}
@@ -611,11 +769,20 @@ export function AgentDetailPanel({
             )}
 
             {/* Outputs */}
-            {latestRun && latestRun.outputs.length > 0 && (
+            {latestOutputs.length > 0 && (
               <>
                 <SectionTitle>Outputs</SectionTitle>
                 <div style={{ marginBottom: 16 }}>
-                  {latestRun.outputs.map((output, i) => (
+                  {latestOutputsOffset > 0 && (
+                    <button
+                      onClick={() => void loadOlderOutputs()}
+                      className="btn-cortex"
+                      style={{ fontSize: 9, padding: "4px 9px", marginBottom: 4 }}
+                    >
+                      Load older outputs ({latestOutputsOffset})
+                    </button>
+                  )}
+                  {latestOutputs.map((output, i) => (
                     <div
                       key={i}
                       style={{
@@ -706,6 +873,11 @@ function ConfigRow({ label, value, mono }: { label: string; value: string; mono?
   );
 }
 
+/** Output count for a run header, or for a full run from the dashboard. */
+function outputCount(run: Run): number {
+  return "output_count" in run ? (run as RunHeader).output_count : run.outputs.length;
+}
+
 const runStripe: Record<string, string> = {
   in_progress: "var(--accent)",
   completed: "var(--done)",
@@ -731,7 +903,7 @@ function RunCard({ run }: { run: Run }) {
       {run.summary && <p className="sv" style={{ fontSize: 10, color: "var(--main)" }}>{run.summary}</p>}
       <div className="flex items-center gap-3 mn" style={{ marginTop: 3, fontSize: 9, color: "var(--dim)" }}>
         {run.file_changes.length > 0 && <span>{run.file_changes.length} files</span>}
-        {run.outputs.length > 0 && <span>{run.outputs.length} outputs</span>}
+        {outputCount(run) > 0 && <span>{outputCount(run)} outputs</span>}
       </div>
     </div>
   );
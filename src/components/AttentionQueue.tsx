@@ -21,6 +21,12 @@ const reasonStyle: Record<string, { stripe: string; badge: string; badgeColor: s
     badgeColor: "var(--warn)",
     label: "BLOCKED",
   },
+  adapter_down: {
+    stripe: "var(--err)",
+    badge: "var(--err-soft)",
+    badgeColor: "var(--err)",
+    label: "DOWN",
+  },
 };
 
 export function AttentionQueue({
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
   DashboardView,
   AgentDetail,
@@ -12,11 +13,30 @@ import type {
   ConversationThread,
   AdapterConfig,
   AdapterHealth,
+  ConfigValidation,
+  AdapterHealthEvent,
   DatabaseSnapshotResult,
+  DemoWorkspace,
+  TimezoneSettings,
+  RecordedTranscript,
+  AgentLogTail,
+  AgentQueue,
+  ProjectExportResult,
+  ProjectImport,
+  BlockedReply,
+  WorkingDirectory,
+  CommandError,
+  RoutedInstruction,
   ConnectorInfo,
   ConnectorConfig,
   ConnectorItem,
+  ConnectorItemDetail,
   SyncResult,
+  RunDetail,
+  RunOutputPage,
+  Run,
+  ArchivedRun,
+  RunArchiveSettings,
 } from "@/types";
 
 declare global {
@@ -29,6 +49,15 @@ export function isTauriRuntime(): boolean {
   return typeof window !== "undefined" && typeof window.__TAURI_INTERNALS__ !== "undefined";
 }
 
+export function isCommandError(error: unknown): error is CommandError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as CommandError).code === "string" &&
+    typeof (error as CommandError).message === "string"
+  );
+}
+
 export async function getDashboard(): Promise<DashboardView> {
   return invoke("get_dashboard");
 }
@@ -37,6 +66,35 @@ export async function getAgentDetail(agentId: string): Promise<AgentDetail> {
   return invoke("get_agent_detail", { agentId });
 }
 
+export async function getRunDetail(runId: string, outputLimit?: number): Promise<RunDetail> {
+  return invoke("get_run_detail", { runId, outputLimit });
+}
+
+export async function getRunOutputs(runId: string, offset: number, limit?: number): Promise<RunOutputPage> {
+  return invoke("get_run_outputs", { runId, offset, limit });
+}
+
+export async function getRunArchiveSettings(): Promise<RunArchiveSettings> {
+  return invoke("get_run_archive_settings");
+}
+
+export async function setRunArchiveSettings(settings: RunArchiveSettings): Promise<RunArchiveSettings> {
+  return invoke("set_run_archive_settings", { settings });
+}
+
+/** Archive finished runs older than `olderThanDays` (default: the saved setting). */
+export async function archiveRuns(olderThanDays?: number): Promise<number> {
+  return invoke("archive_runs", { olderThanDays });
+}
+
+export async function listArchivedRuns(agentId?: string, before?: string, limit?: number): Promise<ArchivedRun[]> {
+  return invoke("list_archived_runs", { agentId, before, limit });
+}
+
+export async function restoreRun(runId: string): Promise<Run> {
+  return invoke("restore_run", { runId });
+}
+
 export async function createProject(
   name: string,
   color: string
@@ -71,11 +129,18 @@ export async function createAgent(params: {
   return invoke("create_agent", params);
 }
 
+/** Check a working directory before saving it; `create` makes it if missing. */
+export async function validateWorkingDirectory(path: string, create?: boolean): Promise<WorkingDirectory> {
+  return invoke("validate_working_directory", { path, create });
+}
+
+/** Resolves false when the transition was illegal and ignored; `force` is an operator override. */
 export async function updateAgentStatus(
   agentId: string,
-  status: AgentStatus
-): Promise<void> {
-  return invoke("update_agent_status", { agentId, status });
+  status: AgentStatus,
+  force?: boolean
+): Promise<boolean> {
+  return invoke("update_agent_status", { agentId, status, force: force ?? null });
 }
 
 // ── Message Bus ─────────────────────────────────────────────────────────────
@@ -89,6 +154,21 @@ export async function sendMessage(
   return invoke("send_message", { agentId, kind, content, replyTo });
 }
 
+/** Answer a Blocked message; the agent's stopped run continues instead of a new one starting. */
+export async function replyToBlocked(agentId: string, messageId: string, content: string): Promise<BlockedReply> {
+  return invoke("reply_to_blocked", { agentId, messageId, content });
+}
+
+/** Pending messages for an agent, in delivery order, with delivery estimates. */
+export async function getAgentQueue(agentId: string): Promise<AgentQueue> {
+  return invoke("get_agent_queue", { agentId });
+}
+
+/** Send an instruction to the project's best-suited idle agent. */
+export async function routeInstruction(projectId: string, text: string): Promise<RoutedInstruction> {
+  return invoke("route_instruction", { projectId, text });
+}
+
 export async function getConversation(
   agentId: string,
   limit?: number,
@@ -107,17 +187,44 @@ export async function receiveMessage(
   return invoke("receive_message", { agentId, kind, content, metadata, replyTo });
 }
 
+/** Saves and restarts the adapter. Rejects if the config has errors. */
 export async function setAdapterConfig(
   agentId: string,
   config: AdapterConfig
-): Promise<void> {
+): Promise<ConfigValidation> {
   return invoke("set_adapter_config", { agentId, config });
 }
 
+export async function validateAdapterConfig(
+  config: AdapterConfig,
+  probe = false
+): Promise<ConfigValidation> {
+  return invoke("validate_adapter_config", { config, probe });
+}
+
+/** Saves the agent's session as a transcript a simulation adapter can replay. */
+export async function recordSimulationTranscript(
+  agentId: string,
+  path: string,
+  since?: string
+): Promise<RecordedTranscript> {
+  return invoke("record_simulation_transcript", { agentId, path, since: since ?? null });
+}
+
+/** The end of the agent's log file: adapter events and bus traffic. */
+export async function getAgentLogs(agentId: string, tailLines?: number): Promise<AgentLogTail> {
+  return invoke("get_agent_logs", { agentId, tailLines: tailLines ?? null });
+}
+
 export async function getAdapterHealth(agentId: string): Promise<AdapterHealth | null> {
   return invoke("get_adapter_health", { agentId });
 }
 
+/** Called whenever the adapter supervisor sees an adapter's health change. */
+export async function onAdapterHealth(handler: (event: AdapterHealthEvent) => void): Promise<UnlistenFn> {
+  return listen<AdapterHealthEvent>("adapter:health", (event) => handler(event.payload));
+}
+
 export async function restartAdapter(agentId: string): Promise<AdapterHealth | null> {
   return invoke("restart_adapter", { agentId });
 }
@@ -130,6 +237,45 @@ export async function importDatabaseSnapshot(sourcePath: string): Promise<Databa
   return invoke("import_database_snapshot", { sourcePath });
 }
 
+/** Write a project (agents, adapter configs without env, docs, templates, optionally runs) to one file. */
+export async function exportProject(
+  projectId: string,
+  destinationPath: string,
+  includeRuns?: boolean
+): Promise<ProjectExportResult> {
+  return invoke("export_project", { projectId, destinationPath, includeRuns });
+}
+
+/** Recreate an exported project as a new project, optionally under another name. */
+export async function importProject(sourcePath: string, name?: string): Promise<ProjectImport> {
+  return invoke("import_project", { sourcePath, name });
+}
+
+// ── Timezone ────────────────────────────────────────────────────────────────
+
+export async function getTimezoneSettings(): Promise<TimezoneSettings> {
+  return invoke("get_timezone_settings");
+}
+
+export async function setTimezoneSettings(settings: TimezoneSettings): Promise<TimezoneSettings> {
+  return invoke("set_timezone_settings", { settings });
+}
+
+// ── Demo Workspace ──────────────────────────────────────────────────────────
+
+export async function getDemoWorkspace(): Promise<DemoWorkspace | null> {
+  return invoke("get_demo_workspace");
+}
+
+export async function createDemoWorkspace(): Promise<DemoWorkspace> {
+  return invoke("create_demo_workspace");
+}
+
+/** Resolves false when there was no demo workspace to remove. */
+export async function deleteDemoWorkspace(): Promise<boolean> {
+  return invoke("delete_demo_workspace");
+}
+
 // ── Connectors ──────────────────────────────────────────────────────────────
 
 export async function listConnectors(): Promise<ConnectorInfo[]> {
@@ -152,6 +298,10 @@ export async function getConnectorItems(connectorType: string): Promise<Connecto
   return invoke("get_connector_items", { connectorType });
 }
 
+export async function getConnectorItemDetail(connectorId: string, itemId: string): Promise<ConnectorItemDetail> {
+  return invoke("get_connector_item_detail", { connectorId, itemId });
+}
+
 export async function pushConnectorItem(
   connectorType: string,
   item: ConnectorItem
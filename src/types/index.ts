@@ -29,6 +29,7 @@ export interface AgentConfig {
   watch_paths: string[];
   schedule: string | null;
   notify_on: AgentStatus[];
+  capabilities?: string[]; // languages, repos, skills; matched by routeInstruction
 }
 
 export interface Agent {
@@ -73,6 +74,45 @@ export interface Run {
   file_changes: FileChange[];
 }
 
+/** A run without its outputs (`outputs` is empty); fetch them with `getRunDetail`. */
+export interface RunHeader extends Run {
+  output_count: number;
+}
+
+export interface RunOutputPage {
+  run_id: string;
+  offset: number;
+  total: number;
+  outputs: RunOutput[];
+}
+
+export interface RunDetail {
+  run: RunHeader;
+  outputs: RunOutputPage;
+}
+
+/** Headline fields of a run moved to the archive; `restoreRun` brings it back. */
+export interface ArchivedRun {
+  id: string;
+  agent_id: string;
+  status: RunStatus;
+  started_at: string;
+  ended_at: string | null;
+  summary: string | null;
+  token_in: number;
+  token_out: number;
+  cost: number;
+  labels: string[];
+  output_count: number;
+  archived_at: string;
+  payload_bytes: number;
+}
+
+export interface RunArchiveSettings {
+  enabled: boolean;
+  after_days: number;
+}
+
 // ── Dashboard DTOs ──────────────────────────────────────────────────────────
 
 export interface DashboardView {
@@ -111,7 +151,7 @@ export interface DashboardStats {
 
 export interface AgentDetail {
   agent: Agent;
-  runs: Run[];
+  runs: RunHeader[];
   messages: Message[];
   adapter_config: AdapterConfig | null;
 }
@@ -160,7 +200,8 @@ export type AdapterType =
   | "tmux"
   | "http_webhook"
   | "process"
-  | "mock";
+  | "mock"
+  | "simulation";
 
 export interface AdapterConfig {
   adapter_type: AdapterType;
@@ -170,6 +211,16 @@ export interface AdapterConfig {
   env: Record<string, string> | null;
 }
 
+export interface ConfigIssue {
+  level: "error" | "warning";
+  field: string;
+  message: string;
+}
+
+export interface ConfigValidation {
+  issues: ConfigIssue[];
+}
+
 export interface AdapterHealth {
   connected: boolean;
   session_active: boolean;
@@ -181,12 +232,126 @@ export interface AdapterHealth {
   suppress_auto_restart: boolean | null;
 }
 
+/** Payload of the `adapter:health` event, from the supervisor's cached state. */
+export interface AdapterHealthEvent {
+  agent_id: string;
+  health: AdapterHealth;
+}
+
 export interface DatabaseSnapshotResult {
   path: string;
   size_bytes: number;
   completed_at: string;
 }
 
+export interface ProjectExportResult {
+  path: string;
+  agent_count: number;
+  template_count: number;
+  context_doc_count: number;
+  run_count: number;
+  size_bytes: number;
+  completed_at: string;
+}
+
+export interface ProjectImport {
+  project: Project;
+  agents: Agent[];
+  templates: unknown[];
+  context_doc_count: number;
+  run_count: number;
+}
+
+export interface RecordedTranscript {
+  path: string;
+  turns: number;
+  steps: number;
+}
+
+export type ErrorCode =
+  | "not_found"
+  | "invalid_input"
+  | "database_busy"
+  | "database"
+  | "adapter_not_connected"
+  | "adapter_delivery_failed"
+  | "adapter_spawn_failed"
+  | "adapter"
+  | "connector_auth_failed"
+  | "connector_rate_limited"
+  | "connector_network"
+  | "connector"
+  | "failed";
+
+// What a rejected command invoke throws.
+export interface CommandError {
+  code: ErrorCode;
+  message: string;
+  retryable: boolean;
+}
+
+export interface GitRepoInfo {
+  root: string;
+  branch: string | null; // null on a detached HEAD
+  remote: string | null;
+}
+
+export interface WorkingDirectory {
+  path: string; // with ~ expanded
+  created: boolean;
+  writable: boolean;
+  git: GitRepoInfo | null;
+}
+
+/** The answer sent to a Blocked message and the run it put back in progress. */
+export interface BlockedReply {
+  message: Message;
+  run: Run;
+}
+
+export interface QueuedMessage {
+  position: number; // 1 is delivered first
+  message: Message;
+  age_seconds: number;
+  estimated_delivery_at: string | null; // null until the adapter is up
+}
+
+export interface AgentQueue {
+  agent_id: string;
+  adapter_running: boolean;
+  delivery_interval_ms: number;
+  messages: QueuedMessage[];
+  held_for_approval: number;
+}
+
+export interface RouteCandidate {
+  agent_id: string;
+  agent_name: string;
+  matched: string[];
+  recent_runs: number;
+  score: number;
+}
+
+export interface RoutedInstruction {
+  message: Message;
+  candidates: RouteCandidate[]; // best first; the first got the message
+}
+
+export interface AgentLogTail {
+  path: string;
+  lines: string[]; // oldest first
+}
+
+export interface TimezoneSettings {
+  timezone: string | null; // IANA name; null follows the system
+}
+
+export interface DemoWorkspace {
+  project_ids: string[];
+  agent_ids: string[];
+  created_at: string;
+}
+
 // ── Connectors ──────────────────────────────────────────────────────────────
 
 export type ConnectorType = "todoist" | "notion" | "obsidian" | "linear" | "github_issues";
@@ -213,6 +378,14 @@ export interface ConnectorItem {
   due_at: string | null;
 }
 
+/** A cached item next to the upstream JSON it was mapped from (null for inbox and Obsidian items). */
+export interface ConnectorItemDetail {
+  connector_id: string;
+  item: ConnectorItem;
+  raw: unknown | null;
+  synced_at: string | null;
+}
+
 export interface ConnectorInfo {
   id: string;
   name: string;